@@ -0,0 +1,313 @@
+//! Native ARP probing over a raw socket (AF_PACKET, Linux only).
+//!
+//! Sends an ARP who-has for every host of the /24 and collects the replies,
+//! so devices with every TCP port firewalled still show up. Needs root or
+//! CAP_NET_RAW; `check_capability` tells the caller up front so `arp_scan`
+//! can fall back to arp-scan / the ARP cache / the TCP sweep instead.
+
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use crate::network_scan::ArpHost;
+
+const ETH_P_ARP: u16 = 0x0806;
+const ARP_REQUEST: u16 = 1;
+const ARP_REPLY: u16 = 2;
+pub const ARP_FRAME_LEN: usize = 42;
+/// Bit of CAP_NET_RAW in the capability masks of /proc/self/status
+const CAP_NET_RAW_BIT: u32 = 13;
+
+/// Ethernet broadcast frame carrying an ARP who-has `target` from `src`.
+pub fn build_arp_request(src_mac: [u8; 6], src_ip: Ipv4Addr, target: Ipv4Addr) -> [u8; ARP_FRAME_LEN] {
+    let mut f = [0u8; ARP_FRAME_LEN];
+    // Ethernet: dst broadcast, src, ethertype
+    f[0..6].copy_from_slice(&[0xff; 6]);
+    f[6..12].copy_from_slice(&src_mac);
+    f[12..14].copy_from_slice(&ETH_P_ARP.to_be_bytes());
+    // ARP: Ethernet / IPv4, 6-byte MAC, 4-byte IP
+    f[14..16].copy_from_slice(&1u16.to_be_bytes());
+    f[16..18].copy_from_slice(&0x0800u16.to_be_bytes());
+    f[18] = 6;
+    f[19] = 4;
+    f[20..22].copy_from_slice(&ARP_REQUEST.to_be_bytes());
+    f[22..28].copy_from_slice(&src_mac);
+    f[28..32].copy_from_slice(&src_ip.octets());
+    // Target MAC unknown (zeros)
+    f[38..42].copy_from_slice(&target.octets());
+    f
+}
+
+/// Sender (ip, mac) of an ARP reply frame; `None` for anything else.
+pub fn parse_arp_reply(frame: &[u8]) -> Option<(Ipv4Addr, [u8; 6])> {
+    if frame.len() < ARP_FRAME_LEN
+        || frame[12..14] != ETH_P_ARP.to_be_bytes()
+        || frame[14..16] != 1u16.to_be_bytes()
+        || frame[16..18] != 0x0800u16.to_be_bytes()
+        || frame[18] != 6
+        || frame[19] != 4
+        || frame[20..22] != ARP_REPLY.to_be_bytes()
+    {
+        return None;
+    }
+    let mut mac = [0u8; 6];
+    mac.copy_from_slice(&frame[22..28]);
+    let ip = Ipv4Addr::new(frame[28], frame[29], frame[30], frame[31]);
+    Some((ip, mac))
+}
+
+pub fn format_mac(mac: &[u8; 6]) -> String {
+    mac.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(":")
+}
+
+fn parse_mac(s: &str) -> Option<[u8; 6]> {
+    let parts: Vec<u8> = s.trim().split(':').filter_map(|p| u8::from_str_radix(p, 16).ok()).collect();
+    parts.try_into().ok()
+}
+
+/// CAP_NET_RAW in the effective set of a /proc/<pid>/status dump.
+pub fn cap_net_raw_from_status(status: &str) -> bool {
+    status
+        .lines()
+        .find_map(|l| l.strip_prefix("CapEff:"))
+        .and_then(|hex| u64::from_str_radix(hex.trim(), 16).ok())
+        .is_some_and(|caps| caps & (1 << CAP_NET_RAW_BIT) != 0)
+}
+
+/// Ok when this process may open AF_PACKET sockets.
+#[cfg(target_os = "linux")]
+pub fn check_capability() -> Result<(), String> {
+    // SAFETY: geteuid has no preconditions and cannot fail
+    if unsafe { libc::geteuid() } == 0 {
+        return Ok(());
+    }
+    let status = std::fs::read_to_string("/proc/self/status").unwrap_or_default();
+    if cap_net_raw_from_status(&status) {
+        Ok(())
+    } else {
+        Err("Natywny skan ARP wymaga roota lub CAP_NET_RAW (sudo setcap cap_net_raw+ep <broxeen>)".to_string())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn check_capability() -> Result<(), String> {
+    Err("Natywny skan ARP jest dostępny tylko na Linuksie".to_string())
+}
+
+/// Local interface (name, address) whose /24 is `subnet` ("192.168.1"), or
+/// the primary interface for "auto".
+pub fn find_interface(subnet: &str) -> Option<(String, Ipv4Addr)> {
+    let primary = local_ip_address::local_ip().ok();
+    local_ip_address::list_afinet_netifas()
+        .ok()?
+        .into_iter()
+        .filter_map(|(name, ip)| match ip {
+            std::net::IpAddr::V4(v4) if !v4.is_loopback() => Some((name, v4)),
+            _ => None,
+        })
+        .find(|(_, ip)| {
+            let o = ip.octets();
+            if subnet == "auto" {
+                primary == Some(std::net::IpAddr::V4(*ip))
+            } else {
+                format!("{}.{}.{}", o[0], o[1], o[2]) == subnet
+            }
+        })
+}
+
+/// Merge `extra` (e.g. ARP-cache entries) into `primary`: new IPs are added,
+/// known ones only get their missing mac / hostname / vendor filled in.
+pub fn merge_hosts(mut primary: Vec<ArpHost>, extra: Vec<ArpHost>) -> Vec<ArpHost> {
+    let mut index: HashMap<String, usize> = primary.iter().enumerate().map(|(i, h)| (h.ip.clone(), i)).collect();
+    for host in extra {
+        match index.get(&host.ip) {
+            Some(&i) => {
+                let known = &mut primary[i];
+                if known.mac.is_empty() || known.mac == "unknown" {
+                    known.mac = host.mac;
+                }
+                known.hostname = known.hostname.take().or(host.hostname);
+                known.vendor = known.vendor.take().or(host.vendor);
+                known.response_time = known.response_time.or(host.response_time);
+            }
+            None => {
+                index.insert(host.ip.clone(), primary.len());
+                primary.push(host);
+            }
+        }
+    }
+    primary.sort_by_key(|h| h.ip.parse::<Ipv4Addr>().map(u32::from).unwrap_or(u32::MAX));
+    primary
+}
+
+/// Probe every host of `iface`'s /24 and wait up to `timeout` for replies.
+#[cfg(target_os = "linux")]
+pub fn probe_subnet(iface: &str, src_ip: Ipv4Addr, timeout: Duration) -> Result<Vec<ArpHost>, String> {
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::time::Instant;
+
+    check_capability()?;
+    let src_mac = std::fs::read_to_string(format!("/sys/class/net/{}/address", iface))
+        .ok()
+        .and_then(|s| parse_mac(&s))
+        .ok_or_else(|| format!("Nie można odczytać adresu MAC interfejsu {}", iface))?;
+    let c_iface = std::ffi::CString::new(iface).map_err(|e| e.to_string())?;
+    // SAFETY: c_iface is a valid NUL-terminated string
+    let ifindex = unsafe { libc::if_nametoindex(c_iface.as_ptr()) };
+    if ifindex == 0 {
+        return Err(format!("Nieznany interfejs {}", iface));
+    }
+
+    // SAFETY: plain socket(2) call; the fd is owned (and closed) by OwnedFd
+    let fd = unsafe { libc::socket(libc::AF_PACKET, libc::SOCK_RAW, ETH_P_ARP.to_be() as i32) };
+    if fd < 0 {
+        return Err(format!("socket(AF_PACKET): {}", std::io::Error::last_os_error()));
+    }
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    // SAFETY: sockaddr_ll is plain old data; all-zero is a valid value
+    let mut addr: libc::sockaddr_ll = unsafe { std::mem::zeroed() };
+    addr.sll_family = libc::AF_PACKET as u16;
+    addr.sll_protocol = ETH_P_ARP.to_be();
+    addr.sll_ifindex = ifindex as i32;
+    addr.sll_halen = 6;
+    addr.sll_addr[..6].copy_from_slice(&[0xff; 6]);
+    let addr_ptr = &addr as *const libc::sockaddr_ll as *const libc::sockaddr;
+    let addr_len = std::mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t;
+
+    // SAFETY: addr outlives the call and addr_len matches its size
+    if unsafe { libc::bind(socket.as_raw_fd(), addr_ptr, addr_len) } < 0 {
+        return Err(format!("bind({}): {}", iface, std::io::Error::last_os_error()));
+    }
+    let tv = libc::timeval { tv_sec: 0, tv_usec: 50_000 };
+    // SAFETY: tv is a valid timeval for SO_RCVTIMEO
+    unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_RCVTIMEO,
+            &tv as *const libc::timeval as *const libc::c_void,
+            std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+        );
+    }
+
+    let o = src_ip.octets();
+    let mut sent: HashMap<Ipv4Addr, Instant> = HashMap::new();
+    for host in 1..=254u8 {
+        let target = Ipv4Addr::new(o[0], o[1], o[2], host);
+        if target == src_ip {
+            continue;
+        }
+        let frame = build_arp_request(src_mac, src_ip, target);
+        // SAFETY: frame and addr are valid for the duration of the call
+        let n = unsafe {
+            libc::sendto(
+                socket.as_raw_fd(),
+                frame.as_ptr() as *const libc::c_void,
+                frame.len(),
+                0,
+                addr_ptr,
+                addr_len,
+            )
+        };
+        if n >= 0 {
+            sent.insert(target, Instant::now());
+        }
+        // Spread the burst a little so small switches don't drop requests
+        std::thread::sleep(Duration::from_micros(500));
+    }
+
+    let mut replies: HashMap<Ipv4Addr, ArpHost> = HashMap::new();
+    let deadline = Instant::now() + timeout;
+    let mut buf = [0u8; 1514];
+    while Instant::now() < deadline {
+        // SAFETY: buf is writable for buf.len() bytes
+        let n = unsafe { libc::recv(socket.as_raw_fd(), buf.as_mut_ptr() as *mut libc::c_void, buf.len(), 0) };
+        if n <= 0 {
+            continue; // timeout tick (EAGAIN) or interrupted
+        }
+        let Some((ip, mac)) = parse_arp_reply(&buf[..n as usize]) else { continue };
+        let Some(sent_at) = sent.get(&ip) else { continue };
+        replies.entry(ip).or_insert_with(|| ArpHost {
+            ip: ip.to_string(),
+            mac: format_mac(&mac),
+            vendor: None,
+            hostname: None,
+            response_time: Some(sent_at.elapsed().as_millis() as u64),
+        });
+    }
+    Ok(merge_hosts(replies.into_values().collect(), Vec::new()))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn probe_subnet(_iface: &str, _src_ip: Ipv4Addr, _timeout: Duration) -> Result<Vec<ArpHost>, String> {
+    check_capability().map(|_| Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: [u8; 6] = [0x02, 0x42, 0xac, 0x11, 0x00, 0x02];
+
+    #[test]
+    fn test_build_arp_request() {
+        let f = build_arp_request(MAC, Ipv4Addr::new(192, 168, 1, 10), Ipv4Addr::new(192, 168, 1, 77));
+        assert_eq!(&f[0..6], &[0xff; 6]);
+        assert_eq!(&f[6..12], &MAC);
+        assert_eq!(&f[12..14], &[0x08, 0x06]);
+        assert_eq!(&f[14..22], &[0x00, 0x01, 0x08, 0x00, 6, 4, 0x00, 0x01]);
+        assert_eq!(&f[22..28], &MAC);
+        assert_eq!(&f[28..32], &[192, 168, 1, 10]);
+        assert_eq!(&f[32..38], &[0; 6]);
+        assert_eq!(&f[38..42], &[192, 168, 1, 77]);
+        // Our own request is not a reply
+        assert_eq!(parse_arp_reply(&f), None);
+    }
+
+    #[test]
+    fn test_parse_mocked_reply() {
+        let replier = [0xb8, 0x27, 0xeb, 0x01, 0x02, 0x03];
+        let mut reply = build_arp_request(replier, Ipv4Addr::new(192, 168, 1, 77), Ipv4Addr::new(192, 168, 1, 10));
+        reply[0..6].copy_from_slice(&MAC);
+        reply[20..22].copy_from_slice(&2u16.to_be_bytes());
+        reply[32..38].copy_from_slice(&MAC);
+        // Ethernet padding up to the 60-byte minimum frame is ignored
+        let mut padded = reply.to_vec();
+        padded.resize(60, 0);
+
+        let (ip, mac) = parse_arp_reply(&padded).unwrap();
+        assert_eq!(ip, Ipv4Addr::new(192, 168, 1, 77));
+        assert_eq!(format_mac(&mac), "b8:27:eb:01:02:03");
+        assert_eq!(parse_arp_reply(&padded[..30]), None);
+        let mut ipv6 = padded.clone();
+        ipv6[12..14].copy_from_slice(&[0x86, 0xdd]);
+        assert_eq!(parse_arp_reply(&ipv6), None);
+    }
+
+    #[test]
+    fn test_capability_and_merge() {
+        assert!(cap_net_raw_from_status("Name:\tbroxeen\nCapEff:\t0000000000002000\n"));
+        assert!(!cap_net_raw_from_status("CapEff:\t0000000000000000\n"));
+        assert!(!cap_net_raw_from_status("Name:\tbroxeen\n"));
+
+        let host = |ip: &str, mac: &str, hostname: Option<&str>, rt: Option<u64>| ArpHost {
+            ip: ip.to_string(),
+            mac: mac.to_string(),
+            vendor: None,
+            hostname: hostname.map(str::to_string),
+            response_time: rt,
+        };
+        let merged = merge_hosts(
+            vec![host("192.168.1.20", "aa:aa:aa:aa:aa:aa", None, Some(3))],
+            vec![
+                host("192.168.1.20", "aa:aa:aa:aa:aa:aa", Some("nas"), None),
+                host("192.168.1.3", "bb:bb:bb:bb:bb:bb", Some("printer"), None),
+            ],
+        );
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].ip, "192.168.1.3");
+        assert_eq!(merged[1].hostname.as_deref(), Some("nas"));
+        assert_eq!(merged[1].response_time, Some(3));
+    }
+}
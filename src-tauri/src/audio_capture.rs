@@ -1,18 +1,29 @@
 //! audio_capture.rs — Microphone recording via cpal (ALSA on Linux).
 //! Bypasses WebKitGTK's broken getUserMedia by capturing audio natively.
+//!
+//! The device comes from `mic_device_id` (a cpal device name, matched exactly
+//! or as a case-insensitive part of the name), the capture rate from
+//! `mic_sample_rate` and the recorded channel from `mic_channel`. Whatever
+//! the device delivers is mixed or picked down to mono and resampled to
+//! 16 kHz, the rate Whisper is trained on, before WAV encoding.
 
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{SampleRate, StreamConfig};
+use cpal::{SampleFormat, SampleRate, StreamConfig, SupportedStreamConfigRange};
 use hound::{WavSpec, WavWriter};
 use std::io::Cursor;
 use std::sync::{Arc, Mutex};
 
+/// Rate of the WAVs handed to STT
+pub const STT_SAMPLE_RATE: u32 = 16_000;
+
 /// Recording state shared between Tauri commands and the audio thread.
 pub struct RecordingState {
     pub(crate) samples: Vec<i16>,
     pub(crate) is_recording: bool,
     pub(crate) sample_rate: u32,
     pub(crate) channels: u16,
+    /// Device and format of the current / last recording
+    pub(crate) capture: Option<CaptureInfo>,
 }
 
 impl RecordingState {
@@ -22,10 +33,26 @@ impl RecordingState {
             is_recording: false,
             sample_rate: 16000,
             channels: 1,
+            capture: None,
         }
     }
 }
 
+/// What `start_recording` actually opened.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CaptureInfo {
+    /// `mic_device_id` as configured
+    pub requested_device: String,
+    pub device: String,
+    /// Rate the device delivers (before resampling to 16 kHz)
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// 1-based channel recorded; None = all channels mixed
+    pub input_channel: Option<u16>,
+    /// Set when the configured device, rate or channel could not be used
+    pub warning: Option<String>,
+}
+
 /// Global recording state, managed via Tauri's state system.
 pub type SharedRecordingState = Arc<Mutex<RecordingState>>;
 
@@ -43,22 +70,82 @@ pub fn list_input_devices() -> Result<Vec<String>, String> {
     Ok(names)
 }
 
-/// Start recording from the default microphone.
-/// Audio is captured as 16kHz mono PCM (ideal for STT).
-/// Returns immediately; samples accumulate in `state`.
-pub fn start_recording(state: &SharedRecordingState) -> Result<cpal::Stream, String> {
-    let host = cpal::default_host();
+fn is_default_device(device_id: &str) -> bool {
+    let id = device_id.trim();
+    id.is_empty() || id.eq_ignore_ascii_case("default")
+}
+
+/// Index of the device `device_id` names: an exact name, else the first name
+/// containing it case-insensitively ("USB" → "hw:CARD=USB,DEV=0").
+fn match_device(names: &[String], device_id: &str) -> Option<usize> {
+    let id = device_id.trim();
+    names.iter().position(|n| n == id).or_else(|| {
+        let id = id.to_lowercase();
+        names.iter().position(|n| n.to_lowercase().contains(&id))
+    })
+}
+
+/// The `mic_device_id` input, or the default input plus a warning when it is missing.
+pub(crate) fn select_input_device(host: &cpal::Host, device_id: &str) -> Result<(cpal::Device, Option<String>), String> {
+    let mut warning = None;
+    if !is_default_device(device_id) {
+        let mut devices: Vec<cpal::Device> = host
+            .input_devices()
+            .map(|d| d.collect())
+            .unwrap_or_default();
+        let names: Vec<String> = devices.iter().map(|d| d.name().unwrap_or_default()).collect();
+        match match_device(&names, device_id) {
+            Some(i) => return Ok((devices.swap_remove(i), None)),
+            None => {
+                let msg = format!("Mikrofon '{}' nie znaleziony — używam domyślnego wejścia", device_id);
+                crate::backend_warn(&msg);
+                warning = Some(msg);
+            }
+        }
+    }
     let device = host
         .default_input_device()
         .ok_or("No input device (microphone) found")?;
+    Ok((device, warning))
+}
+
+/// Start recording from the `mic_device_id` microphone (default input when
+/// it is missing) at `mic_sample_rate`, on channel `mic_channel`.
+/// Returns immediately; samples accumulate in `state` and levels are pushed
+/// as `broxeen:audio_level` events.
+pub fn start_recording(
+    state: &SharedRecordingState,
+    app_handle: tauri::AppHandle,
+) -> Result<(cpal::Stream, CaptureInfo), String> {
+    let settings = crate::settings::load_settings();
+    let host = cpal::default_host();
+    let (device, mut warning) = select_input_device(&host, &settings.mic_device_id)?;
 
     let device_name = device.name().unwrap_or_else(|_| "unknown".into());
     println!("[audio] Using input device: {device_name}");
 
-    // Prefer 16kHz mono for STT, fallback to device default
-    let config = preferred_input_config(&device)?;
+    let wanted_channel = settings.mic_channel;
+    let config = preferred_input_config(&device, settings.mic_sample_rate, wanted_channel)?;
     let sample_rate = config.sample_rate.0;
-    let channels = config.channels as u16;
+    let channels = config.channels;
+    let input_channel = match wanted_channel {
+        0 => None,
+        ch if ch <= channels => Some(ch),
+        ch => {
+            let msg = format!("Kanał {} niedostępny ({} ma {} kan.) — miksuję wszystkie", ch, device_name, channels);
+            crate::backend_warn(&msg);
+            warning = Some(warning.map_or(msg.clone(), |w| format!("{w}; {msg}")));
+            None
+        }
+    };
+    let capture = CaptureInfo {
+        requested_device: settings.mic_device_id.clone(),
+        device: device_name,
+        sample_rate,
+        channels,
+        input_channel,
+        warning,
+    };
 
     {
         let mut s = state.lock().unwrap();
@@ -66,10 +153,14 @@ pub fn start_recording(state: &SharedRecordingState) -> Result<cpal::Stream, Str
         s.is_recording = true;
         s.sample_rate = sample_rate;
         s.channels = channels;
+        s.capture = Some(capture.clone());
     }
 
     let state_clone = Arc::clone(state);
     let err_fn = |err| eprintln!("[audio] Recording error: {err}");
+    // Voice activity uses the wake word threshold so both meters agree
+    let voice_rms = crate::wake_word::WakeWordConfig::from_settings(&settings).rms_threshold();
+    let mut meter = crate::audio_level::LevelMeter::new("recording", sample_rate, voice_rms);
 
     let stream = device
         .build_input_stream(
@@ -79,13 +170,13 @@ pub fn start_recording(state: &SharedRecordingState) -> Result<cpal::Stream, Str
                 if !s.is_recording {
                     return;
                 }
-                // Convert f32 → i16, downsample to mono if needed
-                for chunk in data.chunks(channels as usize) {
-                    // Average channels to mono
-                    let mono: f32 = chunk.iter().sum::<f32>() / channels as f32;
-                    let sample = (mono * i16::MAX as f32).clamp(i16::MIN as f32, i16::MAX as f32) as i16;
-                    s.samples.push(sample);
-                }
+                // Pick the selected channel or average all to mono, then f32 → i16
+                let mono = to_mono(data, channels, input_channel);
+                meter.push_and_emit(&app_handle, &mono);
+                s.samples.extend(
+                    mono.iter()
+                        .map(|m| (m * i16::MAX as f32).clamp(i16::MIN as f32, i16::MAX as f32) as i16),
+                );
             },
             err_fn,
             None,
@@ -93,14 +184,41 @@ pub fn start_recording(state: &SharedRecordingState) -> Result<cpal::Stream, Str
         .map_err(|e| format!("Cannot build input stream: {e}"))?;
 
     stream.play().map_err(|e| format!("Cannot start recording: {e}"))?;
-    println!("[audio] Recording started ({sample_rate}Hz, {channels}ch)");
+    println!("[audio] Recording started ({sample_rate}Hz, {channels}ch, channel {input_channel:?})");
+
+    Ok((stream, capture))
+}
+
+/// Interleaved frames → mono: channel `input_channel` (1-based) or the average.
+fn to_mono(data: &[f32], channels: u16, input_channel: Option<u16>) -> Vec<f32> {
+    let channels = channels.max(1) as usize;
+    match input_channel {
+        Some(ch) => data.chunks(channels).filter_map(|frame| frame.get(ch as usize - 1).copied()).collect(),
+        None => data.chunks(channels).map(|frame| frame.iter().sum::<f32>() / frame.len() as f32).collect(),
+    }
+}
 
-    Ok(stream)
+/// Stop recording and encode collected samples to WAVs (16-bit PCM, mono)
+/// of `segment_secs` that overlap by `overlap_secs`, so long dictations can be
+/// transcribed piecewise. Returns base64 WAVs (ready for STT API); a short
+/// recording is a single segment.
+pub fn stop_and_encode_wav_segments(
+    state: &SharedRecordingState,
+    segment_secs: f32,
+    overlap_secs: f32,
+) -> Result<(Vec<String>, u32), String> {
+    let (samples, rate) = stop_and_take_samples(state)?;
+    let segment_len = (segment_secs * rate as f32) as usize;
+    let overlap_len = (overlap_secs * rate as f32) as usize;
+    let segments = segment_ranges(samples.len(), segment_len, overlap_len)
+        .into_iter()
+        .map(|range| encode_wav_base64(&samples[range], rate))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((segments, rate))
 }
 
-/// Stop recording and encode collected samples to WAV (16-bit PCM, mono).
-/// Returns the WAV bytes as base64 string (ready for STT API).
-pub fn stop_and_encode_wav(state: &SharedRecordingState) -> Result<(String, u32), String> {
+/// Take the recorded samples, resampled to 16 kHz.
+fn stop_and_take_samples(state: &SharedRecordingState) -> Result<(Vec<i16>, u32), String> {
     let (samples, sample_rate, _channels) = {
         let mut s = state.lock().unwrap();
         s.is_recording = false;
@@ -115,18 +233,40 @@ pub fn stop_and_encode_wav(state: &SharedRecordingState) -> Result<(String, u32)
     let duration_secs = samples.len() as f32 / sample_rate as f32;
     println!("[audio] Recorded {:.1}s ({} samples)", duration_secs, samples.len());
 
-    // Downsample to 16kHz if needed (simple decimation for now)
-    let target_rate = 16000u32;
+    let target_rate = STT_SAMPLE_RATE;
     let final_samples = if sample_rate != target_rate {
         resample_linear(&samples, sample_rate, target_rate)
     } else {
         samples
     };
+    Ok((final_samples, target_rate))
+}
+
+/// Sample ranges of `segment_len` that overlap by `overlap_len`. A short
+/// tail (under twice the overlap) is merged into the previous segment.
+fn segment_ranges(total: usize, segment_len: usize, overlap_len: usize) -> Vec<std::ops::Range<usize>> {
+    if segment_len == 0 || overlap_len >= segment_len || total <= segment_len {
+        return std::iter::once(0..total).collect();
+    }
+    let step = segment_len - overlap_len;
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    loop {
+        let end = start + segment_len;
+        if end + 2 * overlap_len >= total {
+            ranges.push(start..total);
+            return ranges;
+        }
+        ranges.push(start..end);
+        start += step;
+    }
+}
 
+fn encode_wav_base64(samples: &[i16], sample_rate: u32) -> Result<String, String> {
     // Encode to WAV in memory
     let spec = WavSpec {
         channels: 1, // always mono after our processing
-        sample_rate: target_rate,
+        sample_rate,
         bits_per_sample: 16,
         sample_format: hound::SampleFormat::Int,
     };
@@ -135,21 +275,27 @@ pub fn stop_and_encode_wav(state: &SharedRecordingState) -> Result<(String, u32)
     {
         let mut writer = WavWriter::new(&mut cursor, spec)
             .map_err(|e| format!("WAV writer error: {e}"))?;
-        for sample in &final_samples {
+        for sample in samples {
             writer.write_sample(*sample).map_err(|e| format!("WAV write error: {e}"))?;
         }
         writer.finalize().map_err(|e| format!("WAV finalize error: {e}"))?;
     }
 
-    let wav_bytes = cursor.into_inner();
-    let base64 = base64_encode(&wav_bytes);
-
-    Ok((base64, target_rate))
+    Ok(base64_encode(&cursor.into_inner()))
 }
 
-/// Simple linear resampling (good enough for speech).
+/// Linear resampling (good enough for speech). When downsampling, a moving
+/// average over one output period first suppresses what would alias into
+/// the speech band.
 fn resample_linear(samples: &[i16], from_rate: u32, to_rate: u32) -> Vec<i16> {
     let ratio = from_rate as f64 / to_rate as f64;
+    let filtered;
+    let samples = if ratio > 1.0 && !samples.is_empty() {
+        filtered = moving_average(samples, ratio.round() as usize);
+        &filtered[..]
+    } else {
+        samples
+    };
     let out_len = (samples.len() as f64 / ratio) as usize;
     let mut out = Vec::with_capacity(out_len);
 
@@ -167,21 +313,62 @@ fn resample_linear(samples: &[i16], from_rate: u32, to_rate: u32) -> Vec<i16> {
     out
 }
 
-/// Prefer 16kHz mono config, fall back to device default.
-fn preferred_input_config(device: &cpal::Device) -> Result<StreamConfig, String> {
-    // Try supported configs for something close to 16kHz
-    if let Ok(configs) = device.supported_input_configs() {
-        for cfg in configs {
-            if cfg.channels() <= 2 {
-                let rate = SampleRate(16000);
-                if cfg.min_sample_rate() <= rate && rate <= cfg.max_sample_rate() {
-                    return Ok(cfg.with_sample_rate(rate).into());
-                }
-            }
+/// Centred moving average of `width` samples (no-op below 2).
+fn moving_average(samples: &[i16], width: usize) -> Vec<i16> {
+    if width < 2 {
+        return samples.to_vec();
+    }
+    let half = width / 2;
+    let mut prefix = Vec::with_capacity(samples.len() + 1);
+    prefix.push(0i64);
+    for &s in samples {
+        prefix.push(prefix.last().copied().unwrap_or(0) + s as i64);
+    }
+    (0..samples.len())
+        .map(|i| {
+            let (lo, hi) = (i.saturating_sub(half), (i + width - half).min(samples.len()));
+            ((prefix[hi] - prefix[lo]) / (hi - lo) as i64) as i16
+        })
+        .collect()
+}
+
+/// An f32 config (what the capture callback takes) at `rate` with at least
+/// `channel` channels, the fewest channels first. `None` for rate 0, which
+/// leaves the choice to the device.
+fn pick_input_config(configs: &[SupportedStreamConfigRange], rate: u32, channel: u16) -> Option<StreamConfig> {
+    if rate == 0 {
+        return None;
+    }
+    let rate = SampleRate(rate);
+    configs
+        .iter()
+        .filter(|c| c.sample_format() == SampleFormat::F32)
+        .filter(|c| c.channels() >= channel.max(1) && c.min_sample_rate() <= rate && rate <= c.max_sample_rate())
+        .min_by_key(|c| c.channels())
+        .map(|c| c.with_sample_rate(rate).into())
+}
+
+/// `rate` with enough channels for `channel` if the device supports it, else
+/// the device default (resampled to 16 kHz after recording).
+fn preferred_input_config(device: &cpal::Device, rate: u32, channel: u16) -> Result<StreamConfig, String> {
+    let configs: Vec<SupportedStreamConfigRange> = device
+        .supported_input_configs()
+        .map(|c| c.collect())
+        .unwrap_or_default();
+    if let Some(config) = pick_input_config(&configs, rate, channel) {
+        return Ok(config);
+    }
+    // Any rate, as long as the channel exists
+    if channel > 0 {
+        if let Some(c) = configs
+            .iter()
+            .filter(|c| c.sample_format() == SampleFormat::F32 && c.channels() >= channel)
+            .min_by_key(|c| c.channels())
+        {
+            return Ok((*c).with_max_sample_rate().into());
         }
     }
 
-    // Fallback: use default config
     device
         .default_input_config()
         .map(|c| c.into())
@@ -202,24 +389,10 @@ pub fn get_mic_level(state: &SharedRecordingState) -> f32 {
         return 0.0;
     }
 
-    // Use last ~50ms of samples for responsive level
-    let window_samples = (s.sample_rate as f32 * 0.05) as usize;
-    let start_idx = if s.samples.len() > window_samples {
-        s.samples.len() - window_samples
-    } else {
-        0
-    };
-    
-    let window = &s.samples[start_idx..];
-    if window.is_empty() {
-        return 0.0;
-    }
-
-    let rms: f32 = (window.iter().map(|&s| (s as f32).powi(2)).sum::<f32>() / window.len() as f32).sqrt();
-    let normalized_rms = rms / i16::MAX as f32;
-    
-    // Scale for better visual response (multiply by 4, clamp to 1.0)
-    (normalized_rms * 4.0).clamp(0.0, 1.0)
+    // Same window and scaling as `broxeen:audio_level`
+    let window = crate::audio_level::window_len(s.sample_rate);
+    let start_idx = s.samples.len().saturating_sub(window);
+    crate::audio_level::measure(s.samples[start_idx..].iter().map(|&v| v as f32 / i16::MAX as f32)).display()
 }
 
 /// Check if the last N seconds of audio are silence.
@@ -241,3 +414,73 @@ pub fn is_silence(state: &SharedRecordingState, threshold_seconds: f32, rms_thre
 
     normalized_rms < rms_threshold
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_ranges_overlap() {
+        let rate = 16_000;
+        // 95 s at 30 s / 1 s overlap: 0–30, 29–59, 58–88, 87–95
+        let ranges = segment_ranges(95 * rate, 30 * rate, rate);
+        let secs: Vec<(usize, usize)> = ranges.iter().map(|r| (r.start / rate, r.end / rate)).collect();
+        assert_eq!(secs, vec![(0, 30), (29, 59), (58, 88), (87, 95)]);
+        // A tail shorter than two overlaps joins the last segment
+        assert_eq!(segment_ranges(61 * rate, 30 * rate, rate).last(), Some(&(29 * rate..61 * rate)));
+        assert_eq!(segment_ranges(20 * rate, 30 * rate, rate), vec![0..20 * rate]);
+        assert_eq!(segment_ranges(100, 0, 0), vec![0..100]);
+    }
+
+    #[test]
+    fn test_device_matching_and_channel_pick() {
+        let names: Vec<String> = ["default", "sysdefault:CARD=PCH", "hw:CARD=USB,DEV=0"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(match_device(&names, "sysdefault:CARD=PCH"), Some(1));
+        assert_eq!(match_device(&names, "usb"), Some(2));
+        assert_eq!(match_device(&names, "Focusrite"), None);
+        assert!(is_default_device(" Default "));
+
+        let stereo = [0.1, 0.9, 0.3, 0.7];
+        assert_eq!(to_mono(&stereo, 2, Some(2)), vec![0.9, 0.7]);
+        assert_eq!(to_mono(&stereo, 2, None), vec![0.5, 0.5]);
+    }
+
+    #[test]
+    fn test_pick_input_config() {
+        use cpal::SupportedBufferSize::Unknown;
+        let range = |ch, min, max, fmt| SupportedStreamConfigRange::new(ch, SampleRate(min), SampleRate(max), Unknown, fmt);
+        let configs = [
+            range(4, 8_000, 96_000, SampleFormat::F32),
+            range(2, 8_000, 48_000, SampleFormat::I16),
+            range(2, 44_100, 48_000, SampleFormat::F32),
+        ];
+        let pick = |rate, ch| pick_input_config(&configs, rate, ch).map(|c| (c.channels, c.sample_rate.0));
+        assert_eq!(pick(48_000, 0), Some((2, 48_000)));
+        assert_eq!(pick(16_000, 0), Some((4, 16_000))); // only f32 range reaching 16 kHz
+        assert_eq!(pick(16_000, 3), Some((4, 16_000)));
+        assert_eq!(pick(16_000, 5), None);
+        assert_eq!(pick(0, 1), None);
+        // The callback takes f32: an i16-only device gets the default config
+        assert_eq!(pick_input_config(&configs[1..2], 16_000, 0), None);
+    }
+
+    #[test]
+    fn test_downsampling_keeps_speech_and_damps_aliases() {
+        let tone = |freq: f64, rate: u32| -> Vec<i16> {
+            (0..rate as usize / 10)
+                .map(|i| ((i as f64 * freq * std::f64::consts::TAU / rate as f64).sin() * 10_000.0) as i16)
+                .collect()
+        };
+        let peak = |s: &[i16]| s[50..s.len() - 50].iter().map(|v| v.unsigned_abs()).max().unwrap_or(0);
+
+        let speech = resample_linear(&tone(500.0, 48_000), 48_000, 16_000);
+        assert_eq!(speech.len(), 1_600);
+        assert!(peak(&speech) > 9_000, "{}", peak(&speech));
+        // 12 kHz can't exist at 16 kHz: without filtering it would fold to 4 kHz at full level
+        let alias = resample_linear(&tone(12_000.0, 48_000), 48_000, 16_000);
+        assert!(peak(&alias) < 4_000, "{}", peak(&alias));
+    }
+}
@@ -16,6 +16,8 @@ use crate::audio_capture::{self, SharedRecordingState};
 use crate::settings::load_settings;
 use crate::stt;
 use crate::tts_backend;
+use crate::tts_text;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 
 /// Active recording stream, stored in Tauri state.
@@ -35,15 +37,46 @@ pub struct ActiveWakeWordStream(pub Arc<Mutex<Option<cpal::Stream>>>);
 unsafe impl Send for ActiveWakeWordStream {}
 unsafe impl Sync for ActiveWakeWordStream {}
 
+/// Context needed to bring wake word listening back after a manual recording.
+#[derive(Default)]
+pub struct WakeWordResumeState {
+    /// Wake word was active and got paused by `stt_start`
+    paused_for_recording: bool,
+    /// User called `wake_word_stop` while paused — do not resume
+    stopped_by_user: bool,
+    app_handle: Option<tauri::AppHandle>,
+    last_outcome: Option<String>,
+    last_outcome_at: Option<u64>,
+}
+
+pub struct WakeWordResume(pub Arc<Mutex<WakeWordResumeState>>);
+
+#[derive(serde::Serialize)]
+pub struct WakeWordResumeStatus {
+    pub paused_for_recording: bool,
+    pub listening: bool,
+    /// "resumed" | "skipped_user_stopped" | "failed: …"
+    pub last_auto_resume: Option<String>,
+    pub last_auto_resume_at: Option<u64>,
+}
+
 /// Check if wake word detection is currently active
 fn is_wake_word_active(active_wake_word: &ActiveWakeWordStream) -> bool {
     active_wake_word.0.lock().unwrap().is_some()
 }
 
 /// Pause wake word detection temporarily (drop and recreate later)
-fn pause_wake_word(active_wake_word: &ActiveWakeWordStream) -> Result<(), String> {
+fn pause_wake_word(
+    active_wake_word: &ActiveWakeWordStream,
+    resume: &WakeWordResume,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
     if let Some(stream) = active_wake_word.0.lock().unwrap().take() {
         drop(stream); // This stops the wake word detection
+        let mut r = resume.0.lock().unwrap();
+        r.paused_for_recording = true;
+        r.stopped_by_user = false;
+        r.app_handle = Some(app_handle);
         crate::backend_info("⏸️ Wake word detection paused for manual recording");
         Ok(())
     } else {
@@ -52,13 +85,57 @@ fn pause_wake_word(active_wake_word: &ActiveWakeWordStream) -> Result<(), String
     }
 }
 
-/// Resume wake word detection (needs to be recreated)
-fn resume_wake_word(_active_wake_word: &ActiveWakeWordStream) -> Result<(), String> {
-    // Note: This would need to recreate the wake word stream
-    // For now, we'll just log that it needs to be restarted
-    crate::backend_info("▶️ Wake word detection needs to be restarted manually");
-    crate::backend_warn("⚠️ Automatic wake word resume not implemented - please restart wake word detection");
-    Ok(())
+/// Resume wake word detection if it was paused by `stt_start`.
+/// Returns true when the stream was recreated.
+fn resume_wake_word(
+    active_wake_word: &ActiveWakeWordStream,
+    wake_word_state: &SharedWakeWordState,
+    resume: &WakeWordResume,
+) -> bool {
+    let (app_handle, stopped_by_user) = {
+        let mut r = resume.0.lock().unwrap();
+        if !r.paused_for_recording {
+            return false;
+        }
+        r.paused_for_recording = false;
+        (r.app_handle.take(), r.stopped_by_user)
+    };
+
+    let outcome = if stopped_by_user {
+        crate::backend_info("Wake word was stopped during recording - not resuming");
+        "skipped_user_stopped".to_string()
+    } else if is_wake_word_active(active_wake_word) {
+        // Restarted manually while recording — nothing to do
+        "resumed".to_string()
+    } else if let Some(app_handle) = app_handle {
+        // Keep a phrase/sensitivity override from wake_word_start across the pause
+        let config = wake_word_state.lock().unwrap().config.clone();
+        match wake_word::start_wake_word_listening(wake_word_state, app_handle, config) {
+            Ok(stream) => {
+                *active_wake_word.0.lock().unwrap() = Some(stream);
+                crate::backend_info("▶️ Wake word detection resumed after manual recording");
+                "resumed".to_string()
+            }
+            Err(e) => {
+                crate::backend_warn(format!("⚠️ Failed to resume wake word: {}", e));
+                format!("failed: {}", e)
+            }
+        }
+    } else {
+        "failed: missing app handle".to_string()
+    };
+
+    let resumed = outcome == "resumed";
+    let mut r = resume.0.lock().unwrap();
+    r.stopped_by_user = false;
+    r.last_outcome = Some(outcome);
+    r.last_outcome_at = Some(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64,
+    );
+    resumed
 }
 
 // ── STT Commands ─────────────────────────────────────
@@ -70,9 +147,12 @@ pub fn stt_start(
     recording_state: tauri::State<SharedRecordingState>,
     active_stream: tauri::State<ActiveStream>,
     active_wake_word: tauri::State<ActiveWakeWordStream>,
+    wake_word_resume: tauri::State<WakeWordResume>,
+    app_handle: tauri::AppHandle,
 ) -> Result<String, String> {
     let mode = mode.unwrap_or_else(|| "manual".to_string());
     crate::backend_info(format!("Command stt_start invoked with mode: {}", mode));
+    let level_app = app_handle.clone();
 
     // Check if already recording
     {
@@ -91,7 +171,7 @@ pub fn stt_start(
         "manual" => {
             if wake_word_active {
                 crate::backend_info("🎯 Manual mode - automatically pausing wake word detection");
-                pause_wake_word(&active_wake_word)?;
+                pause_wake_word(&active_wake_word, &wake_word_resume, app_handle)?;
             } else {
                 crate::backend_info("🎯 Manual recording started");
             }
@@ -102,21 +182,34 @@ pub fn stt_start(
     }
 
     crate::backend_info("🎙️ Starting native audio capture...");
-    let stream = audio_capture::start_recording(&recording_state)?;
+    let (stream, capture) = audio_capture::start_recording(&recording_state, level_app)?;
 
     // Store stream handle so it stays alive
     *active_stream.0.lock().unwrap() = Some(stream);
-    crate::backend_info("✅ Native microphone recording started successfully");
+    crate::backend_info(format!(
+        "✅ Native microphone recording started successfully ({}, {} Hz, {} ch)",
+        capture.device, capture.sample_rate, capture.channels
+    ));
 
-    Ok(format!("Recording started in {} mode", mode))
+    // A missing mic_device_id falls back to the default input; say so
+    match capture.warning {
+        Some(warning) => Ok(format!("Recording started in {} mode — warning: {}", mode, warning)),
+        None => Ok(format!("Recording started in {} mode", mode)),
+    }
 }
 
 /// Stop recording, transcribe via cloud STT, return text.
+/// Long recordings are transcribed in segments; `broxeen:stt_partial`
+/// carries the text accumulated so far after each one.
+#[allow(clippy::too_many_arguments)]
 #[tauri::command]
 pub async fn stt_stop(
+    app_handle: tauri::AppHandle,
     recording_state: tauri::State<'_, SharedRecordingState>,
     active_stream: tauri::State<'_, ActiveStream>,
     active_wake_word: tauri::State<'_, ActiveWakeWordStream>,
+    wake_word_state: tauri::State<'_, SharedWakeWordState>,
+    wake_word_resume: tauri::State<'_, WakeWordResume>,
     mode: Option<String>,  // Nowy parametr: "manual", "wake_word_trigger", etc.
     language: Option<String>,
     api_key: Option<String>,
@@ -140,9 +233,50 @@ pub async fn stt_stop(
     crate::backend_info("Waiting 100ms for buffer flush...");
     tokio::time::sleep(std::time::Duration::from_millis(100)).await;
 
+    let result = transcribe_recording(&app_handle, &recording_state, language, api_key, model).await;
+
+    // Automatycznie wznow wake word po manual recording (also when STT failed)
+    resume_wake_word(&active_wake_word, &wake_word_state, &wake_word_resume);
+
+    let transcript = result?;
+    crate::backend_info("🎯 Manual: transkrypcja gotowa");
+    Ok(transcript)
+}
+
+/// Segment length for chunked transcription of long recordings.
+const STT_SEGMENT_SECS: f32 = 30.0;
+/// Overlap between segments so words at the cut are not lost.
+const STT_SEGMENT_OVERLAP_SECS: f32 = 1.0;
+/// Retries of a failed segment before it is replaced by a marker.
+const STT_SEGMENT_RETRIES: u32 = 2;
+
+#[derive(Clone, serde::Serialize)]
+struct SttPartial {
+    /// 1-based index of the segment just transcribed
+    segment: usize,
+    segments: usize,
+    /// Stitched transcript up to and including this segment
+    text: String,
+}
+
+/// Encode the finished recording and send it to the STT provider,
+/// segment by segment.
+async fn transcribe_recording(
+    app_handle: &tauri::AppHandle,
+    recording_state: &SharedRecordingState,
+    language: Option<String>,
+    api_key: Option<String>,
+    model: Option<String>,
+) -> Result<String, String> {
+    use tauri::Emitter;
+
     // Encode recorded audio to WAV base64
     crate::backend_info("Encoding recorded audio to WAV...");
-    let (wav_base64, sample_rate) = audio_capture::stop_and_encode_wav(&recording_state)?;
+    let (segments, sample_rate) = audio_capture::stop_and_encode_wav_segments(
+        recording_state,
+        STT_SEGMENT_SECS,
+        STT_SEGMENT_OVERLAP_SECS,
+    )?;
     let lang = language
         .as_deref()
         .map(str::trim)
@@ -150,35 +284,71 @@ pub async fn stt_stop(
         .unwrap_or("pl");
 
     crate::backend_info(format!(
-        "✓ Audio encoded: sample_rate={}, payload_kb={}, sending to STT provider (lang={})",
+        "✓ Audio encoded: sample_rate={}, segments={}, payload_kb={}, sending to STT provider (lang={})",
         sample_rate,
-        wav_base64.len() / 1024,
+        segments.len(),
+        segments.iter().map(String::len).sum::<usize>() / 1024,
         lang
     ));
 
-    // Send to OpenRouter Whisper for transcription
-    let transcript = stt::transcribe_wav_base64(
-        &wav_base64,
-        lang,
-        api_key.as_deref(),
-        model.as_deref(),
-    )
-    .await?;
+    // Cloud (OpenRouter) or local Whisper, per settings.stt_engine
+    let engine = load_settings().stt_engine;
+    let total = segments.len();
+    let mut transcript = String::new();
+    let mut failed = 0;
+    let mut last_error = String::new();
+
+    for (index, wav_base64) in segments.iter().enumerate() {
+        let mut attempt = 0;
+        let text = loop {
+            match stt::transcribe_with_engine(&engine, wav_base64, lang, api_key.as_deref(), model.as_deref()).await {
+                Ok(text) => break Some(text),
+                // A single segment keeps the old behaviour: the error goes to the caller
+                Err(e) if total == 1 => return Err(e),
+                Err(e) if attempt < STT_SEGMENT_RETRIES => {
+                    attempt += 1;
+                    crate::backend_warn(format!(
+                        "STT segment {}/{} failed (attempt {}): {} — retrying",
+                        index + 1, total, attempt, e
+                    ));
+                    tokio::time::sleep(std::time::Duration::from_millis(500 * attempt as u64)).await;
+                }
+                Err(e) => {
+                    crate::backend_warn(format!("STT segment {}/{} skipped: {}", index + 1, total, e));
+                    last_error = e;
+                    break None;
+                }
+            }
+        };
 
-    crate::backend_info(format!(
-        "✓ STT transcript ready: \"{}\" (len={})",
-        transcript.chars().take(50).collect::<String>(),
-        transcript.len()
-    ));
+        match text {
+            Some(text) => transcript = stt::stitch_transcripts(&transcript, &text),
+            None => {
+                failed += 1;
+                transcript = stt::stitch_transcripts(&transcript, stt::UNINTELLIGIBLE_MARKER);
+            }
+        }
 
-    // Automatycznie wznow wake word po manual recording
-    if is_wake_word_active(&active_wake_word) {
-        if let Err(e) = resume_wake_word(&active_wake_word) {
-            crate::backend_warn(format!("⚠️ Failed to resume wake word: {}", e));
+        if total > 1 {
+            let _ = app_handle.emit("broxeen:stt_partial", SttPartial {
+                segment: index + 1,
+                segments: total,
+                text: transcript.clone(),
+            });
         }
     }
 
-    crate::backend_info("🎯 Manual: transkrypcja gotowa");
+    if failed == total {
+        return Err(last_error);
+    }
+
+    crate::backend_info(format!(
+        "✓ STT transcript ready: \"{}\" (len={}, failed_segments={})",
+        transcript.chars().take(50).collect::<String>(),
+        transcript.len(),
+        failed
+    ));
+
     Ok(transcript)
 }
 
@@ -198,6 +368,7 @@ pub fn stt_status(
         } else {
             0.0
         },
+        capture: s.capture.clone(),
     })
 }
 
@@ -225,37 +396,96 @@ pub struct SttStatus {
     pub is_recording: bool,
     pub samples_count: usize,
     pub duration_seconds: f32,
+    /// Device, native rate and channel of the current / last recording
+    pub capture: Option<audio_capture::CaptureInfo>,
 }
 
 // ── TTS Commands ─────────────────────────────────────
 
+/// Texts longer than this are spoken sentence-by-sentence by default.
+const TTS_STREAMING_THRESHOLD: usize = 200;
+/// Upper bound for a single synthesized chunk (characters).
+const TTS_MAX_CHUNK_CHARS: usize = 300;
+
+/// Bumped on every speak/stop so background chunk synthesis of a superseded
+/// utterance stops appending to the sink.
+static TTS_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, serde::Serialize)]
+struct TtsProgress {
+    generation: u64,
+    chunk: usize,
+    total: usize,
+    text: String,
+}
+
 /// Speak text through the system audio output (Piper or espeak-ng).
 /// Non-blocking — audio plays in background.
+///
+/// With `streaming` (default for texts over ~200 chars) the text is split into
+/// sentences: the first one is synthesized and played right away while the rest
+/// are synthesized in the background and appended to the same sink. Each chunk
+/// emits `broxeen:tts_progress` when it starts playing.
+///
+/// A repeated `idempotency_key` (webhook / rule retries) is not spoken again.
+/// Text goes through `tts_text::prepare` first unless `exact` is set.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn backend_tts_speak(
+    app_handle: tauri::AppHandle,
     active_tts: tauri::State<'_, ActiveTts>,
     text: String,
     rate: Option<f32>,
     volume: Option<f32>,
     lang: Option<String>,
+    streaming: Option<bool>,
+    idempotency_key: Option<String>,
+    exact: Option<bool>,
+) -> Result<(), String> {
+    crate::idempotency::run_once("tts_speak", idempotency_key.as_deref(), || {
+        speak_text(app_handle, &active_tts, text, rate, volume, lang, streaming, exact.unwrap_or(false))
+    })
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn speak_text(
+    app_handle: tauri::AppHandle,
+    active_tts: &ActiveTts,
+    text: String,
+    rate: Option<f32>,
+    volume: Option<f32>,
+    lang: Option<String>,
+    streaming: Option<bool>,
+    exact: bool,
 ) -> Result<(), String> {
     let rate = rate.unwrap_or(1.0);
     let volume = volume.unwrap_or(1.0);
     let lang = lang.unwrap_or_else(|| "pl-PL".into());
+    let streaming = streaming.unwrap_or(text.chars().count() > TTS_STREAMING_THRESHOLD);
 
     crate::backend_info(format!(
-        "Command backend_tts_speak invoked (text_len={}, lang={}, rate={}, volume={})",
+        "Command backend_tts_speak invoked (text_len={}, lang={}, rate={}, volume={}, streaming={}, exact={})",
         text.len(),
         lang,
         rate,
-        volume
+        volume,
+        streaming,
+        exact
     ));
 
-    // Load current settings to get preferred TTS engine
+    // Load current settings each call so engine / speaker changes apply immediately
     let settings = load_settings();
     let engine = settings.tts_engine.clone();
+    let speaker = settings.speaker_device_id.clone();
+    let text = if exact {
+        text
+    } else {
+        tts_text::prepare(&text, &lang, &settings.tts_dictionary)
+    };
 
     // Stop current playback immediately before synthesis begins
+    let generation = TTS_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
     {
         *active_tts.0.lock().unwrap() = None;
     }
@@ -264,24 +494,132 @@ pub async fn backend_tts_speak(
         return Ok(());
     }
 
+    let chunks = if streaming {
+        tts_backend::split_sentences(&text, TTS_MAX_CHUNK_CHARS)
+    } else {
+        vec![text]
+    };
+    let total = chunks.len();
+    let mut chunks = chunks.into_iter().enumerate();
+    let Some((_, first)) = chunks.next() else {
+        return Ok(());
+    };
+
+    let first_text = first.clone();
+    let (first_lang, first_engine) = (lang.clone(), engine.clone());
     let wav = tokio::task::spawn_blocking(move || {
-        tts_backend::synthesize_to_wav_with_engine(&text, rate, &lang, &engine)
+        tts_backend::synthesize_to_wav_with_engine(&first_text, rate, &first_lang, &first_engine)
     })
     .await
     .map_err(|e| format!("Task join error: {}", e))??;
 
+    if TTS_GENERATION.load(Ordering::SeqCst) != generation {
+        return Ok(()); // superseded while synthesizing
+    }
+
     // Start playback
-    let (stream, sink) = tts_backend::play_wav_stoppable(&wav, volume)?;
+    let (stream, sink) = tts_backend::open_sink(&speaker, volume)?;
+    append_progress_marker(&sink, &app_handle, generation, 0, total, &first);
+    tts_backend::append_wav(&sink, &wav)?;
 
     // Store in state so we can stop/pause it later
     *active_tts.0.lock().unwrap() = Some((stream, sink));
+    watch_output_device(ActiveTts(Arc::clone(&active_tts.0)), generation, speaker);
+
+    if total == 1 {
+        return Ok(());
+    }
+
+    // Synthesize the remaining sentences while the first ones play
+    let tts = ActiveTts(Arc::clone(&active_tts.0));
+    tauri::async_runtime::spawn(async move {
+        let tts = tts; // capture the Send wrapper, not its inner Arc
+        for (index, chunk) in chunks {
+            if TTS_GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            let (chunk_text, chunk_lang, chunk_engine) = (chunk.clone(), lang.clone(), engine.clone());
+            let wav = match tokio::task::spawn_blocking(move || {
+                tts_backend::synthesize_to_wav_with_engine(&chunk_text, rate, &chunk_lang, &chunk_engine)
+            })
+            .await
+            {
+                Ok(Ok(wav)) => wav,
+                Ok(Err(e)) => {
+                    crate::backend_warn(format!("TTS chunk {}/{} synthesis failed: {}", index + 1, total, e));
+                    continue;
+                }
+                Err(e) => {
+                    crate::backend_warn(format!("TTS chunk task join error: {}", e));
+                    return;
+                }
+            };
+
+            let guard = tts.0.lock().unwrap();
+            if TTS_GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            let Some((_, sink)) = guard.as_ref() else {
+                return;
+            };
+            append_progress_marker(sink, &app_handle, generation, index, total, &chunk);
+            if let Err(e) = tts_backend::append_wav(sink, &wav) {
+                crate::backend_warn(format!("TTS chunk {}/{} playback failed: {}", index + 1, total, e));
+            }
+        }
+    });
 
     Ok(())
 }
 
+/// Stop playback cleanly if the selected speaker disappears mid-utterance
+/// (USB / Bluetooth unplugged) instead of leaving a stalled sink behind.
+fn watch_output_device(tts: ActiveTts, generation: u64, device_id: String) {
+    if device_id.trim().is_empty() || device_id.eq_ignore_ascii_case("default") {
+        return;
+    }
+    std::thread::spawn(move || {
+        let tts = tts; // capture the Send wrapper, not its inner Arc
+        loop {
+            std::thread::sleep(std::time::Duration::from_secs(1));
+            if TTS_GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            let finished = tts.0.lock().unwrap().as_ref().is_none_or(|(_, sink)| sink.empty());
+            if finished {
+                return;
+            }
+            if !tts_backend::output_device_available(&device_id) {
+                crate::backend_warn(format!("Speaker '{}' disappeared during playback — stopping TTS", device_id));
+                TTS_GENERATION.fetch_add(1, Ordering::SeqCst);
+                *tts.0.lock().unwrap() = None;
+                return;
+            }
+        }
+    });
+}
+
+/// Queue a zero-length source that emits `broxeen:tts_progress` when the sink reaches it.
+fn append_progress_marker(
+    sink: &rodio::Sink,
+    app_handle: &tauri::AppHandle,
+    generation: u64,
+    chunk: usize,
+    total: usize,
+    text: &str,
+) {
+    use tauri::Emitter;
+    let app = app_handle.clone();
+    let progress = TtsProgress { generation, chunk, total, text: text.to_string() };
+    sink.append(rodio::source::EmptyCallback::<i16>::new(Box::new(move || {
+        let _ = app.emit("broxeen:tts_progress", progress.clone());
+    })));
+}
+
 #[tauri::command]
 pub fn backend_tts_stop(active_tts: tauri::State<ActiveTts>) {
     crate::backend_info("Command backend_tts_stop invoked");
+    TTS_GENERATION.fetch_add(1, Ordering::SeqCst);
     *active_tts.0.lock().unwrap() = None;
 }
 
@@ -302,12 +640,13 @@ pub fn backend_tts_resume(active_tts: tauri::State<ActiveTts>) {
 }
 
 /// Synthesize text to WAV and return as base64.
-/// For frontend playback via <audio> element.
+/// For frontend playback via <audio> element. `exact` skips text preprocessing.
 #[tauri::command]
 pub fn backend_tts_speak_base64(
     text: String,
     rate: Option<f32>,
     lang: Option<String>,
+    exact: Option<bool>,
 ) -> Result<String, String> {
     let rate = rate.unwrap_or(1.0);
     let lang = lang.unwrap_or_else(|| "pl-PL".into());
@@ -326,6 +665,11 @@ pub fn backend_tts_speak_base64(
         settings.tts_engine
     ));
 
+    let text = if exact.unwrap_or(false) {
+        text
+    } else {
+        tts_text::prepare(&text, &lang, &settings.tts_dictionary)
+    };
     tts_backend::speak_to_base64_with_engine(&text, rate, &lang, &settings.tts_engine)
 }
 
@@ -355,18 +699,23 @@ pub struct TtsInfo {
 
 // ── Audio Device Commands ────────────────────────────
 
-/// List available audio input devices.
+/// List available audio input and output devices.
 #[tauri::command]
 pub fn backend_audio_devices() -> Result<AudioDevices, String> {
     crate::backend_info("Command backend_audio_devices invoked");
 
     let inputs = audio_capture::list_input_devices()?;
-    Ok(AudioDevices { inputs })
+    let outputs = tts_backend::list_output_devices().unwrap_or_else(|e| {
+        crate::backend_warn(e);
+        Vec::new()
+    });
+    Ok(AudioDevices { inputs, outputs })
 }
 
 #[derive(serde::Serialize)]
 pub struct AudioDevices {
     pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
 }
 
 // ── Piper Install Commands ───────────────────────────
@@ -394,8 +743,13 @@ pub fn wake_word_start(
     wake_word_state: tauri::State<SharedWakeWordState>,
     active_wake_word_stream: tauri::State<ActiveWakeWordStream>,
     app_handle: tauri::AppHandle,
+    phrase: Option<String>,
+    sensitivity: Option<f32>,
 ) -> Result<String, String> {
-    crate::backend_info("Command wake_word_start invoked");
+    crate::backend_info(format!(
+        "Command wake_word_start invoked (phrase_override={:?}, sensitivity_override={:?})",
+        phrase, sensitivity
+    ));
 
     // Check if already listening
     {
@@ -406,7 +760,10 @@ pub fn wake_word_start(
         }
     }
 
-    let stream = wake_word::start_wake_word_listening(&wake_word_state, app_handle)?;
+    let has_overrides = phrase.is_some() || sensitivity.is_some();
+    let config = wake_word::WakeWordConfig::from_settings(&load_settings())
+        .with_overrides(phrase, sensitivity);
+    let stream = wake_word::start_wake_word_listening(&wake_word_state, app_handle, config)?;
 
     // Store stream to keep it alive
     {
@@ -414,16 +771,50 @@ pub fn wake_word_start(
         *active = Some(stream);
     }
 
+    // Microphone changes need a fresh input stream
+    crate::settings::clear_restart_required("wake_word");
+    crate::settings::watch_for_restart("wake_word", &["mic_enabled", "mic_device_id"]);
+
+    // Phrase / sensitivity apply live, unless overridden for testing
+    if has_overrides {
+        crate::settings::unwatch_settings("wake_word_config");
+    } else {
+        apply_wake_word_settings_live(wake_word_state.inner().clone());
+    }
+
     Ok("Wake word listening started".into())
 }
 
+/// Replaced on every start and removed by `wake_word_stop`.
+fn apply_wake_word_settings_live(state: SharedWakeWordState) {
+    crate::settings::watch_settings("wake_word_config", move |change| {
+        if change.changed_keys.iter().any(|k| k.starts_with("wake_word_")) {
+            let mut s = state.lock().unwrap();
+            s.set_config(wake_word::WakeWordConfig::from_settings(&change.settings));
+            crate::backend_info(format!(
+                "Wake word settings applied live (phrase=\"{}\", sensitivity={:.2})",
+                s.config.phrase, s.config.sensitivity
+            ));
+        }
+    });
+}
+
 /// Stop wake word listening
 #[tauri::command]
 pub fn wake_word_stop(
     wake_word_state: tauri::State<SharedWakeWordState>,
     active_wake_word_stream: tauri::State<ActiveWakeWordStream>,
+    wake_word_resume: tauri::State<WakeWordResume>,
 ) -> Result<String, String> {
     crate::backend_info("Command wake_word_stop invoked");
+
+    // Stopped while paused for a manual recording — cancel the pending auto-resume
+    {
+        let mut r = wake_word_resume.0.lock().unwrap();
+        if r.paused_for_recording {
+            r.stopped_by_user = true;
+        }
+    }
     
     // Drop the stream to stop listening
     {
@@ -432,6 +823,9 @@ pub fn wake_word_stop(
     }
     
     wake_word::stop_wake_word_listening(&wake_word_state);
+    crate::settings::unwatch_settings("wake_word");
+    crate::settings::unwatch_settings("wake_word_config");
+    crate::settings::clear_restart_required("wake_word");
     Ok("Wake word listening stopped".into())
 }
 
@@ -443,3 +837,65 @@ pub fn wake_word_check_triggered(
     Ok(wake_word::check_wake_word_triggered(&wake_word_state))
 }
 
+#[derive(serde::Serialize)]
+pub struct WakeWordTestResult {
+    pub phrase: String,
+    pub sensitivity: f32,
+    pub triggered: bool,
+    /// Best match score over the recording (0.0–1.0)
+    pub score: f32,
+    pub trigger_threshold: f32,
+    /// Loudest 100 ms-stepped window RMS vs the voice-activity gate
+    pub peak_rms: f32,
+    pub rms_threshold: f32,
+    pub duration_secs: f32,
+}
+
+/// Record 3 seconds and report whether the phrase would have triggered,
+/// so users can calibrate `wake_word_sensitivity`.
+#[tauri::command]
+pub async fn wake_word_test(
+    phrase: Option<String>,
+    sensitivity: Option<f32>,
+) -> Result<WakeWordTestResult, String> {
+    crate::backend_info("Command wake_word_test invoked (recording 3s)");
+    let config = wake_word::WakeWordConfig::from_settings(&load_settings())
+        .with_overrides(phrase, sensitivity);
+
+    let (samples, sample_rate) = tokio::task::spawn_blocking(|| wake_word::record_mono_blocking(3.0))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))??;
+
+    let (score, peak_rms) = wake_word::score_recording(&samples, sample_rate, &config);
+    let result = WakeWordTestResult {
+        phrase: config.phrase.clone(),
+        sensitivity: config.sensitivity,
+        triggered: score > config.trigger_threshold(),
+        score,
+        trigger_threshold: config.trigger_threshold(),
+        peak_rms,
+        rms_threshold: config.rms_threshold(),
+        duration_secs: samples.len() as f32 / sample_rate.max(1) as f32,
+    };
+    crate::backend_info(format!(
+        "wake_word_test: triggered={} score={:.2} peak_rms={:.4}",
+        result.triggered, result.score, result.peak_rms
+    ));
+    Ok(result)
+}
+
+/// Report whether wake word is paused for a recording and how the last auto-resume went
+#[tauri::command]
+pub fn wake_word_resume_status(
+    active_wake_word_stream: tauri::State<ActiveWakeWordStream>,
+    wake_word_resume: tauri::State<WakeWordResume>,
+) -> WakeWordResumeStatus {
+    let r = wake_word_resume.0.lock().unwrap();
+    WakeWordResumeStatus {
+        paused_for_recording: r.paused_for_recording,
+        listening: is_wake_word_active(&active_wake_word_stream),
+        last_auto_resume: r.last_outcome.clone(),
+        last_auto_resume_at: r.last_outcome_at,
+    }
+}
+
@@ -0,0 +1,183 @@
+//! audio_level.rs — Microphone level meter shared by wake word listening and
+//! manual recording.
+//!
+//! Both input callbacks feed a [`LevelMeter`], which measures RMS and peak
+//! over ~100 ms windows and pushes them to the UI as `broxeen:audio_level`
+//! events instead of the UI polling over IPC. A callback delivering several
+//! windows at once yields one event (the latest), and nothing is emitted
+//! while the window is unfocused unless `audio_level_when_unfocused` is set.
+//! The polling commands (`wake_word_get_level`, `stt_get_mic_level`) measure
+//! the same window with [`measure`], so every meter agrees.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter};
+
+pub const EVENT: &str = "broxeen:audio_level";
+/// Length of one measured window
+pub const WINDOW_SECS: f32 = 0.1;
+/// Voice activity stays on for this many windows after the last loud one
+const VOICE_HANGOVER_WINDOWS: u32 = 3;
+
+static WINDOW_FOCUSED: AtomicBool = AtomicBool::new(true);
+
+/// Called from the main window's focus events.
+pub fn set_window_focused(focused: bool) {
+    WINDOW_FOCUSED.store(focused, Ordering::Relaxed);
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Level {
+    pub rms: f32,
+    pub peak: f32,
+}
+
+impl Level {
+    /// 0.0–1.0 for a meter bar: RMS scaled ×4 so speech fills most of it
+    pub fn display(&self) -> f32 {
+        (self.rms * 4.0).clamp(0.0, 1.0)
+    }
+}
+
+/// RMS and peak of mono samples in -1.0..1.0.
+pub fn measure(samples: impl IntoIterator<Item = f32>) -> Level {
+    let (mut sum_sq, mut peak, mut n) = (0.0f64, 0.0f32, 0usize);
+    for s in samples {
+        sum_sq += (s as f64) * (s as f64);
+        peak = peak.max(s.abs());
+        n += 1;
+    }
+    if n == 0 {
+        return Level::default();
+    }
+    Level { rms: (sum_sq / n as f64).sqrt() as f32, peak }
+}
+
+/// Number of samples in one window at `sample_rate`.
+pub fn window_len(sample_rate: u32) -> usize {
+    ((sample_rate as f32 * WINDOW_SECS) as usize).max(1)
+}
+
+/// Payload of `broxeen:audio_level`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioLevelEvent {
+    /// "wake_word" or "recording"
+    pub source: &'static str,
+    pub rms: f32,
+    pub peak: f32,
+    /// `rms` scaled for a meter bar, as returned by `stt_get_mic_level`
+    pub level: f32,
+    /// Voice in this window or the last few
+    pub voice_active: bool,
+}
+
+/// Accumulates samples into windows; owned by one input stream callback.
+pub struct LevelMeter {
+    source: &'static str,
+    window_len: usize,
+    voice_rms: f32,
+    emit_when_unfocused: bool,
+    sum_sq: f64,
+    peak: f32,
+    count: usize,
+    quiet_windows: u32,
+}
+
+impl LevelMeter {
+    /// `voice_rms`: RMS above which a window counts as voice
+    pub fn new(source: &'static str, sample_rate: u32, voice_rms: f32) -> Self {
+        Self {
+            source,
+            window_len: window_len(sample_rate),
+            voice_rms,
+            emit_when_unfocused: crate::settings::load_settings().audio_level_when_unfocused,
+            sum_sq: 0.0,
+            peak: 0.0,
+            count: 0,
+            quiet_windows: VOICE_HANGOVER_WINDOWS + 1,
+        }
+    }
+
+    /// Add mono samples; returns the latest window completed by them, if any.
+    pub fn push(&mut self, samples: &[f32]) -> Option<AudioLevelEvent> {
+        let mut latest = None;
+        for &s in samples {
+            self.sum_sq += (s as f64) * (s as f64);
+            self.peak = self.peak.max(s.abs());
+            self.count += 1;
+            if self.count >= self.window_len {
+                latest = Some(self.finish_window());
+            }
+        }
+        latest
+    }
+
+    fn finish_window(&mut self) -> AudioLevelEvent {
+        let level = Level { rms: (self.sum_sq / self.count as f64).sqrt() as f32, peak: self.peak };
+        (self.sum_sq, self.peak, self.count) = (0.0, 0.0, 0);
+        if level.rms > self.voice_rms {
+            self.quiet_windows = 0;
+        } else {
+            self.quiet_windows = self.quiet_windows.saturating_add(1);
+        }
+        AudioLevelEvent {
+            source: self.source,
+            rms: level.rms,
+            peak: level.peak,
+            level: level.display(),
+            voice_active: self.quiet_windows <= VOICE_HANGOVER_WINDOWS,
+        }
+    }
+
+    /// Push samples and emit the resulting event, if any and if allowed.
+    pub fn push_and_emit(&mut self, app: &AppHandle, samples: &[f32]) {
+        if let Some(event) = self.push(samples) {
+            if self.emit_when_unfocused || WINDOW_FOCUSED.load(Ordering::Relaxed) {
+                let _ = app.emit(EVENT, event);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meter() -> LevelMeter {
+        LevelMeter {
+            source: "recording",
+            window_len: window_len(16_000),
+            voice_rms: 0.015,
+            emit_when_unfocused: false,
+            sum_sq: 0.0,
+            peak: 0.0,
+            count: 0,
+            quiet_windows: VOICE_HANGOVER_WINDOWS + 1,
+        }
+    }
+
+    #[test]
+    fn test_meter_windows_and_voice_hangover() {
+        let mut meter = meter();
+        let silence = vec![0.0f32; 1_600];
+        let tone: Vec<f32> = (0..1_600).map(|i| if i % 2 == 0 { 0.5 } else { -0.5 }).collect();
+
+        assert!(meter.push(&silence[..800]).is_none());
+        let event = meter.push(&silence[..800]).unwrap();
+        assert_eq!((event.rms, event.voice_active), (0.0, false));
+
+        // Several windows in one callback coalesce into the latest
+        let burst = [silence.clone(), tone.clone()].concat();
+        let event = meter.push(&burst).unwrap();
+        assert!((event.rms - 0.5).abs() < 1e-6 && event.peak == 0.5);
+        assert_eq!(event.level, 1.0);
+        assert!(event.voice_active);
+
+        // Voice stays on for the hangover, then drops
+        let quiet: Vec<bool> = (0..4).map(|_| meter.push(&silence).unwrap().voice_active).collect();
+        assert_eq!(quiet, vec![true, true, true, false]);
+
+        assert_eq!(measure(tone.iter().copied()), Level { rms: 0.5, peak: 0.5 });
+        assert_eq!(measure(std::iter::empty()), Level::default());
+    }
+}
@@ -0,0 +1,218 @@
+//! Low-bandwidth mode for metered connections (phone hotspot, LTE).
+//!
+//! `low_bandwidth_mode` in settings: `"on"`, `"off"` or `"auto"` (default).
+//! In auto mode the mode activates when the interface carrying the default
+//! route is flagged metered — currently detected via NetworkManager on Linux;
+//! other platforms report "unknown" and stay off unless forced on.
+//!
+//! While active:
+//! - RTSP preview workers run at a lower fps with stronger JPEG compression,
+//! - `rtsp_capture_frame` downsizes frames to `PREVIEW_MAX_WIDTH`,
+//! - `browse` skips screenshot inlining,
+//! - dashboard snapshots should use text summaries (`omit_snapshot_images`).
+//!
+//! Bytes that would have been sent at full quality vs. bytes actually sent are
+//! counted so `low_bandwidth_status` can show what was saved.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::logging::backend_info;
+
+/// RTSP worker frame rate while active (ffmpeg `fps=` filter value).
+pub const PREVIEW_FPS: &str = "1/3";
+/// ffmpeg mjpeg quality while active (2 = best … 31 = worst; normal mode uses 5).
+pub const PREVIEW_JPEG_Q: &str = "15";
+/// Max width of frames returned to the UI while active.
+pub const PREVIEW_MAX_WIDTH: u32 = 640;
+
+const METERED_CACHE_TTL: Duration = Duration::from_secs(30);
+/// `is_active` is polled per frame — avoid re-reading settings.json each time.
+const ACTIVE_CACHE_TTL: Duration = Duration::from_secs(5);
+
+lazy_static::lazy_static! {
+    static ref METERED_CACHE: Mutex<Option<(Instant, Option<bool>)>> = Mutex::new(None);
+    static ref ACTIVE_CACHE: Mutex<Option<(Instant, bool)>> = Mutex::new(None);
+}
+
+static BYTES_FULL: AtomicU64 = AtomicU64::new(0);
+static BYTES_SENT: AtomicU64 = AtomicU64::new(0);
+static SCREENSHOTS_SKIPPED: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    On,
+    Off,
+    Auto,
+}
+
+impl Mode {
+    pub fn parse(s: &str) -> Self {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "on" | "true" | "1" => Mode::On,
+            "off" | "false" | "0" => Mode::Off,
+            _ => Mode::Auto,
+        }
+    }
+}
+
+// ── Metered detection ────────────────────────────────
+
+/// Interface carrying the default route (Linux `ip route`).
+#[cfg(target_os = "linux")]
+fn default_route_interface() -> Option<String> {
+    let out = std::process::Command::new("ip").args(["route", "show", "default"]).output().ok()?;
+    let text = String::from_utf8_lossy(&out.stdout);
+    let mut parts = text.split_whitespace();
+    while let Some(word) = parts.next() {
+        if word == "dev" {
+            return parts.next().map(str::to_string);
+        }
+    }
+    None
+}
+
+/// `Some(true)` when NetworkManager flags the default interface as metered,
+/// `None` when this cannot be determined.
+#[cfg(target_os = "linux")]
+fn detect_metered() -> Option<bool> {
+    let iface = default_route_interface()?;
+    let out = std::process::Command::new("nmcli")
+        .args(["-g", "GENERAL.METERED", "device", "show", &iface])
+        .output()
+        .ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    parse_nm_metered(&String::from_utf8_lossy(&out.stdout))
+}
+
+/// No portable metered flag outside NetworkManager yet.
+#[cfg(not(target_os = "linux"))]
+fn detect_metered() -> Option<bool> {
+    None
+}
+
+/// NetworkManager values: "yes", "yes (guessed)", "no", "no (guessed)", "unknown".
+fn parse_nm_metered(value: &str) -> Option<bool> {
+    let v = value.trim().to_ascii_lowercase();
+    if v.starts_with("yes") {
+        Some(true)
+    } else if v.starts_with("no") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+fn metered_cached() -> Option<bool> {
+    let mut cache = METERED_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((at, value)) = *cache {
+        if at.elapsed() < METERED_CACHE_TTL {
+            return value;
+        }
+    }
+    let value = detect_metered();
+    *cache = Some((Instant::now(), value));
+    value
+}
+
+// ── Public API ───────────────────────────────────────
+
+pub fn mode() -> Mode {
+    Mode::parse(&crate::settings::load_settings().low_bandwidth_mode)
+}
+
+/// Whether low-bandwidth behaviour should be applied right now.
+pub fn is_active() -> bool {
+    let mut cache = ACTIVE_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((at, active)) = *cache {
+        if at.elapsed() < ACTIVE_CACHE_TTL {
+            return active;
+        }
+    }
+    let active = match mode() {
+        Mode::On => true,
+        Mode::Off => false,
+        Mode::Auto => metered_cached().unwrap_or(false),
+    };
+    if cache.map(|(_, prev)| prev) != Some(active) {
+        backend_info(format!("Low-bandwidth mode {}", if active { "activated" } else { "inactive" }));
+    }
+    *cache = Some((Instant::now(), active));
+    active
+}
+
+/// Count a payload: `full` = size at normal quality, `sent` = size actually sent.
+pub fn record_transfer(full: usize, sent: usize) {
+    BYTES_FULL.fetch_add(full as u64, Ordering::Relaxed);
+    BYTES_SENT.fetch_add(sent as u64, Ordering::Relaxed);
+}
+
+pub fn record_screenshot_skipped() {
+    SCREENSHOTS_SKIPPED.fetch_add(1, Ordering::Relaxed);
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LowBandwidthStatus {
+    pub mode: Mode,
+    pub active: bool,
+    /// `None` when the OS does not expose a metered flag
+    pub metered: Option<bool>,
+    pub omit_snapshot_images: bool,
+    pub preview_max_width: u32,
+    pub bytes_full_quality: u64,
+    pub bytes_sent: u64,
+    pub bytes_saved: u64,
+    pub saved_percent: f64,
+    pub screenshots_skipped: u64,
+}
+
+pub fn status() -> LowBandwidthStatus {
+    let active = is_active();
+    let full = BYTES_FULL.load(Ordering::Relaxed);
+    let sent = BYTES_SENT.load(Ordering::Relaxed);
+    let saved = full.saturating_sub(sent);
+    LowBandwidthStatus {
+        mode: mode(),
+        active,
+        metered: metered_cached(),
+        omit_snapshot_images: active,
+        preview_max_width: PREVIEW_MAX_WIDTH,
+        bytes_full_quality: full,
+        bytes_sent: sent,
+        bytes_saved: saved,
+        saved_percent: if full > 0 { saved as f64 * 100.0 / full as f64 } else { 0.0 },
+        screenshots_skipped: SCREENSHOTS_SKIPPED.load(Ordering::Relaxed),
+    }
+}
+
+// ── Tauri commands ───────────────────────────────────
+
+#[tauri::command]
+pub fn low_bandwidth_status() -> LowBandwidthStatus {
+    status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_parse() {
+        assert_eq!(Mode::parse("on"), Mode::On);
+        assert_eq!(Mode::parse(" OFF "), Mode::Off);
+        assert_eq!(Mode::parse(""), Mode::Auto);
+        assert_eq!(Mode::parse("auto"), Mode::Auto);
+    }
+
+    #[test]
+    fn test_parse_nm_metered() {
+        assert_eq!(parse_nm_metered("yes (guessed)\n"), Some(true));
+        assert_eq!(parse_nm_metered("no"), Some(false));
+        assert_eq!(parse_nm_metered("unknown"), None);
+    }
+}
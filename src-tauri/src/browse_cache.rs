@@ -0,0 +1,298 @@
+//! On-disk HTTP cache for `browse`.
+//!
+//! One JSON file per URL under `<data_local_dir>/broxeen/browse_cache/`,
+//! holding the final body, the validators (ETag / Last-Modified) and the
+//! extracted `BrowseResult`. Within `browse_cache_ttl_secs` the stored
+//! result is served without touching the network; past it `browse` sends a
+//! conditional GET and reuses the entry on `304 Not Modified`. Cached pages
+//! are still subject to robots.txt (`http_policy::check_robots`).
+//!
+//! The directory is capped at `browse_cache_max_mb`; the least recently
+//! used entries (file mtime, bumped on every hit) are evicted first.
+//!
+//! Entries hold the whole extracted content; `browse` returns it in chunks
+//! and `browse_content_page` reads the following ones from here.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+use crate::logging::{backend_info, backend_warn};
+use crate::BrowseResult;
+
+static TMP_SEQ: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub url: String,
+    /// Unix seconds of the last full fetch or successful revalidation
+    pub fetched_at: i64,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body: String,
+    pub result: BrowseResult,
+}
+
+pub enum Lookup {
+    /// Within the TTL — serve as is
+    Fresh(BrowseResult),
+    /// Past the TTL — revalidate with the entry's validators
+    Stale(CacheEntry),
+    Miss,
+}
+
+fn cache_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("broxeen")
+        .join("browse_cache")
+}
+
+fn entry_path(dir: &Path, url: &str) -> PathBuf {
+    let key: String = hmac_sha256::Hash::hash(url.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    dir.join(format!("{}.json", key))
+}
+
+fn now_secs() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+/// Mark an entry as recently used for LRU eviction.
+fn touch(path: &Path) {
+    let _ = fs::File::options()
+        .append(true)
+        .open(path)
+        .and_then(|f| f.set_modified(SystemTime::now()));
+}
+
+/// `resolve_type` of a result served from the cache, e.g. "exact-cached".
+pub fn mark_cached(mut result: BrowseResult) -> BrowseResult {
+    if !result.resolve_type.ends_with("-cached") {
+        result.resolve_type.push_str("-cached");
+    }
+    result
+}
+
+fn read_entry(dir: &Path, url: &str) -> Option<CacheEntry> {
+    let path = entry_path(dir, url);
+    let entry = fs::read(&path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<CacheEntry>(&bytes).ok())
+        .filter(|e| e.url == url)?;
+    touch(&path);
+    Some(entry)
+}
+
+pub fn lookup_in(dir: &Path, url: &str, ttl: Duration, now: i64) -> Lookup {
+    let Some(entry) = read_entry(dir, url) else {
+        return Lookup::Miss;
+    };
+    if now - entry.fetched_at < ttl.as_secs() as i64 {
+        Lookup::Fresh(mark_cached(entry.result))
+    } else {
+        Lookup::Stale(entry)
+    }
+}
+
+/// Write `entry`, then evict least recently used entries above `max_bytes`.
+pub fn store_in(dir: &Path, entry: &CacheEntry, max_bytes: u64) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+    let json = serde_json::to_vec(entry).map_err(|e| e.to_string())?;
+    let path = entry_path(dir, &entry.url);
+    // Unique per write: two concurrent stores of one URL must not share a temp file
+    let tmp = path.with_extension(format!(
+        "{}-{}.tmp",
+        std::process::id(),
+        TMP_SEQ.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::write(&tmp, json)
+        .and_then(|_| fs::rename(&tmp, &path))
+        .map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+    let evicted = evict(dir, max_bytes, &path);
+    if evicted > 0 {
+        backend_info(format!("browse cache: evicted {} entries", evicted));
+    }
+    Ok(())
+}
+
+/// Remove oldest-used entries until the directory fits in `max_bytes`.
+/// `keep` (the entry just written) is never evicted.
+fn evict(dir: &Path, max_bytes: u64, keep: &Path) -> usize {
+    let Ok(read) = fs::read_dir(dir) else { return 0 };
+    let mut files: Vec<(PathBuf, u64, SystemTime)> = read
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|x| x == "json"))
+        .filter_map(|e| {
+            let meta = e.metadata().ok()?;
+            Some((e.path(), meta.len(), meta.modified().unwrap_or(SystemTime::UNIX_EPOCH)))
+        })
+        .collect();
+    let mut total: u64 = files.iter().map(|f| f.1).sum();
+    files.sort_by_key(|f| f.2);
+
+    let mut evicted = 0;
+    for (path, size, _) in files {
+        if total <= max_bytes {
+            break;
+        }
+        if path == keep {
+            continue;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total = total.saturating_sub(size);
+            evicted += 1;
+        }
+    }
+    evicted
+}
+
+pub fn clear_in(dir: &Path) -> usize {
+    let Ok(read) = fs::read_dir(dir) else { return 0 };
+    read.flatten()
+        .filter(|e| e.path().extension().is_some_and(|x| x == "json" || x == "tmp"))
+        .filter(|e| fs::remove_file(e.path()).is_ok())
+        .count()
+}
+
+// ── Settings-backed wrappers used by `browse` ─────────────────
+
+pub fn lookup(url: &str) -> Lookup {
+    let ttl = Duration::from_secs(crate::settings::load_settings().browse_cache_ttl_secs);
+    lookup_in(&cache_dir(), url, ttl, now_secs())
+}
+
+/// Stored result for `url` whatever its age — `browse_content_page` serves
+/// further chunks of the content `browse` returned from it.
+pub fn cached_result(url: &str) -> Option<BrowseResult> {
+    read_entry(&cache_dir(), url).map(|entry| mark_cached(entry.result))
+}
+
+pub fn store(url: &str, body: &str, etag: Option<String>, last_modified: Option<String>, result: &BrowseResult) {
+    let max_bytes = crate::settings::load_settings().browse_cache_max_mb * 1_048_576;
+    let entry = CacheEntry {
+        url: url.to_string(),
+        fetched_at: now_secs(),
+        etag,
+        last_modified,
+        body: body.to_string(),
+        result: result.clone(),
+    };
+    if let Err(e) = store_in(&cache_dir(), &entry, max_bytes) {
+        backend_warn(format!("browse cache: {}", e));
+    }
+}
+
+/// 304 Not Modified: restart the TTL and return the stored result.
+pub fn revalidated(mut entry: CacheEntry) -> BrowseResult {
+    entry.fetched_at = now_secs();
+    let max_bytes = crate::settings::load_settings().browse_cache_max_mb * 1_048_576;
+    if let Err(e) = store_in(&cache_dir(), &entry, max_bytes) {
+        backend_warn(format!("browse cache: {}", e));
+    }
+    mark_cached(entry.result)
+}
+
+#[tauri::command]
+pub fn browse_cache_clear() -> Result<usize, String> {
+    let removed = clear_in(&cache_dir());
+    backend_info(format!("Command browse_cache_clear invoked: removed {} entries", removed));
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(url: &str, fetched_at: i64, body_len: usize) -> CacheEntry {
+        CacheEntry {
+            url: url.to_string(),
+            fetched_at,
+            etag: Some("\"abc\"".to_string()),
+            last_modified: None,
+            body: "x".repeat(body_len),
+            result: BrowseResult {
+                url: url.to_string(),
+                title: "Title".to_string(),
+                content: "Content".to_string(),
+                resolve_type: "exact".to_string(),
+                suggestions: vec![],
+                screenshot_base64: None,
+                rss_url: None,
+                contact_url: None,
+                phone_url: None,
+                sitemap_url: None,
+                blog_url: None,
+                linkedin_url: None,
+                facebook_url: None,
+                twitter_url: None,
+                github_url: None,
+                youtube_url: None,
+                instagram_url: None,
+                removed_chars: 0,
+                search_results: Vec::new(),
+                detected_language: None,
+                original_content_length: 0,
+                content_chunk_chars: 0,
+                content_total_chars: 0,
+                content_offset: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_fresh_stale_and_lru_eviction() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let ttl = Duration::from_secs(600);
+        let now = 1_700_000_000;
+
+        assert!(matches!(lookup_in(dir.path(), "https://a.pl/", ttl, now), Lookup::Miss));
+        store_in(dir.path(), &entry("https://a.pl/", now, 10), u64::MAX).unwrap();
+        match lookup_in(dir.path(), "https://a.pl/", ttl, now + 60) {
+            Lookup::Fresh(r) => assert_eq!(r.resolve_type, "exact-cached"),
+            _ => panic!("expected fresh hit"),
+        }
+        match lookup_in(dir.path(), "https://a.pl/", ttl, now + 601) {
+            Lookup::Stale(e) => assert_eq!(e.etag.as_deref(), Some("\"abc\"")),
+            _ => panic!("expected stale entry"),
+        }
+
+        // b is used after c, so c is the least recently used when d arrives
+        let size = fs::metadata(entry_path(dir.path(), "https://a.pl/")).unwrap().len();
+        store_in(dir.path(), &entry("https://b.pl/", now, 10), u64::MAX).unwrap();
+        store_in(dir.path(), &entry("https://c.pl/", now, 10), u64::MAX).unwrap();
+        let age = |url: &str, secs: u64| {
+            let f = fs::File::options().append(true).open(entry_path(dir.path(), url)).unwrap();
+            f.set_modified(SystemTime::now() - Duration::from_secs(secs)).unwrap();
+        };
+        age("https://a.pl/", 300);
+        age("https://c.pl/", 200);
+        age("https://b.pl/", 100);
+        store_in(dir.path(), &entry("https://d.pl/", now, 10), size * 2 + 10).unwrap();
+        assert!(matches!(lookup_in(dir.path(), "https://a.pl/", ttl, now), Lookup::Miss));
+        assert!(matches!(lookup_in(dir.path(), "https://c.pl/", ttl, now), Lookup::Miss));
+        assert!(matches!(lookup_in(dir.path(), "https://b.pl/", ttl, now), Lookup::Fresh(_)));
+        assert!(matches!(lookup_in(dir.path(), "https://d.pl/", ttl, now), Lookup::Fresh(_)));
+
+        assert_eq!(clear_in(dir.path()), 2);
+        assert!(matches!(lookup_in(dir.path(), "https://b.pl/", ttl, now), Lookup::Miss));
+    }
+
+    #[test]
+    fn test_concurrent_stores_of_one_url() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let dir = dir.path();
+                scope.spawn(move || store_in(dir, &entry("https://a.pl/", i, 10), u64::MAX).unwrap());
+            }
+        });
+        let ttl = Duration::from_secs(u64::MAX / 2);
+        assert!(matches!(lookup_in(dir.path(), "https://a.pl/", ttl, 0), Lookup::Fresh(_)));
+        assert_eq!(clear_in(dir.path()), 1);
+    }
+}
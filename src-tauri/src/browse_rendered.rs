@@ -311,11 +311,12 @@ async fn describe_image_with_vision(
     use base64::Engine;
     let img_base64 = base64::engine::general_purpose::STANDARD.encode(img_bytes);
 
+    let model = env::var("BROWSE_LLM_MODEL").unwrap_or_else(|_| {
+        env::var("VITE_BROWSE_LLM_MODEL")
+            .unwrap_or_else(|_| "google/gemini-2.0-flash-001".to_string())
+    });
     let payload = serde_json::json!({
-        "model": env::var("BROWSE_LLM_MODEL").unwrap_or_else(|_| {
-            env::var("VITE_BROWSE_LLM_MODEL")
-                .unwrap_or_else(|_| "google/gemini-2.0-flash-001".to_string())
-        }),
+        "model": model,
         "messages": [
             {
                 "role": "user",
@@ -370,6 +371,8 @@ async fn describe_image_with_vision(
         .await
         .map_err(|e| format!("Vision LLM JSON parse error: {e}"))?;
 
+    crate::llm_usage::record("browse_vision", &model, &data);
+
     let text = data["choices"][0]["message"]["content"]
         .as_str()
         .unwrap_or("")
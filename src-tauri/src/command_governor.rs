@@ -0,0 +1,206 @@
+//! command_governor.rs — Concurrency limits and time budgets for heavy commands.
+//!
+//! Commands that spawn threads, processes or hundreds of sockets (page
+//! fetches, network scans, camera discovery, RTSP frame grabs) run through
+//! the managed [`CommandGovernor`]. Each command class has a fixed number of
+//! slots: a call finding them all taken fails at once with a "busy, try
+//! again" error instead of queueing, and a call exceeding its class budget is
+//! dropped with "operation timed out after Ns". Dropping only stops the async
+//! part, so the task also gets a [`CancellationToken`], cancelled on timeout,
+//! for the threads and processes it started.
+//!
+//! Defaults can be overridden per class in settings (`command_limits`,
+//! e.g. `{ "browse": { "max_concurrent": 4, "timeout_secs": 90 } }`). Saved
+//! changes apply to the next call; calls already running keep their slot.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
+
+use crate::error::{BroxeenError, ErrorCode};
+use crate::logging::backend_warn;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CommandClass {
+    /// `browse`
+    Browse,
+    /// `scan_network`, `arp_scan`
+    Scan,
+    /// `discover_onvif_cameras`
+    Discovery,
+    /// `rtsp_capture_frame` (ffmpeg)
+    Snapshot,
+}
+
+impl CommandClass {
+    pub const ALL: [CommandClass; 4] =
+        [CommandClass::Browse, CommandClass::Scan, CommandClass::Discovery, CommandClass::Snapshot];
+
+    /// Key in `command_limits`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommandClass::Browse => "browse",
+            CommandClass::Scan => "scan",
+            CommandClass::Discovery => "discovery",
+            CommandClass::Snapshot => "snapshot",
+        }
+    }
+
+    fn default_limit(&self) -> (usize, Duration) {
+        match self {
+            CommandClass::Browse => (2, Duration::from_secs(120)),
+            CommandClass::Scan => (1, Duration::from_secs(300)),
+            CommandClass::Discovery => (1, Duration::from_secs(120)),
+            CommandClass::Snapshot => (4, Duration::from_secs(30)),
+        }
+    }
+}
+
+/// Override of one class's defaults (`command_limits` in settings).
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CommandLimit {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+struct ClassSlots {
+    semaphore: Arc<Semaphore>,
+    max_concurrent: usize,
+    timeout: Duration,
+}
+
+pub struct CommandGovernor {
+    classes: RwLock<HashMap<CommandClass, Arc<ClassSlots>>>,
+}
+
+impl CommandGovernor {
+    pub fn new(overrides: &BTreeMap<String, CommandLimit>) -> Self {
+        Self { classes: RwLock::new(Self::build(overrides)) }
+    }
+
+    pub fn from_settings(settings: &crate::settings::AudioSettings) -> Self {
+        Self::new(&settings.command_limits)
+    }
+
+    /// Replace the limits (settings saved). Running calls hold permits of the
+    /// old slots, so a lowered limit is reached once they finish.
+    pub fn apply(&self, overrides: &BTreeMap<String, CommandLimit>) {
+        let classes = Self::build(overrides);
+        *self.classes.write().unwrap_or_else(|e| e.into_inner()) = classes;
+    }
+
+    fn build(overrides: &BTreeMap<String, CommandLimit>) -> HashMap<CommandClass, Arc<ClassSlots>> {
+        for key in overrides.keys() {
+            if !CommandClass::ALL.iter().any(|c| c.as_str() == key) {
+                backend_warn(format!("command_limits: unknown command class '{}' ignored", key));
+            }
+        }
+        CommandClass::ALL
+            .into_iter()
+            .map(|class| {
+                let (default_max, default_timeout) = class.default_limit();
+                let limit = overrides.get(class.as_str()).cloned().unwrap_or_default();
+                let max_concurrent = limit.max_concurrent.unwrap_or(default_max).max(1);
+                let timeout = limit.timeout_secs.filter(|s| *s > 0).map_or(default_timeout, Duration::from_secs);
+                let slots = ClassSlots { semaphore: Arc::new(Semaphore::new(max_concurrent)), max_concurrent, timeout };
+                (class, Arc::new(slots))
+            })
+            .collect()
+    }
+
+    /// Run `task(cancel)` in a free `class` slot within the class time budget.
+    /// `command` names the command in the error messages; a full class fails
+    /// with [`ErrorCode::Busy`], an overrun with [`ErrorCode::Timeout`] — the
+    /// future is dropped and `cancel` cancelled.
+    pub async fn run<T, F, Fut>(&self, class: CommandClass, command: &str, task: F) -> Result<T, BroxeenError>
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: Future<Output = Result<T, BroxeenError>>,
+    {
+        let slots = Arc::clone(&self.classes.read().unwrap_or_else(|e| e.into_inner())[&class]);
+        let _permit = slots.semaphore.try_acquire().map_err(|_| {
+            let running = format!("{} {} call(s) already running", slots.max_concurrent, class.as_str());
+            backend_warn(format!("{}: rejected, {}", command, running));
+            BroxeenError::new(ErrorCode::Busy, format!("{}: busy, try again ({})", command, running))
+        })?;
+        let cancel = CancellationToken::new();
+        match tokio::time::timeout(slots.timeout, task(cancel.clone())).await {
+            Ok(result) => result,
+            Err(_) => {
+                cancel.cancel();
+                backend_warn(format!("{}: aborted after {}s", command, slots.timeout.as_secs()));
+                Err(BroxeenError::new(
+                    ErrorCode::Timeout,
+                    format!("{}: operation timed out after {}s", command, slots.timeout.as_secs()),
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_busy_slots_fail_fast_and_slow_calls_time_out() {
+        let overrides = BTreeMap::from([
+            ("scan".to_string(), CommandLimit { max_concurrent: Some(1), timeout_secs: Some(1) }),
+            ("bogus".to_string(), CommandLimit::default()),
+        ]);
+        let governor = Arc::new(CommandGovernor::new(&overrides));
+        assert_eq!(governor.classes.read().unwrap()[&CommandClass::Browse].max_concurrent, 2);
+
+        let (started_tx, started_rx) = tokio::sync::oneshot::channel();
+        let (release_tx, release_rx) = tokio::sync::oneshot::channel::<()>();
+        let busy = Arc::clone(&governor);
+        let first = tokio::spawn(async move {
+            busy.run(CommandClass::Scan, "scan_network", |_| async move {
+                let _ = started_tx.send(());
+                let _ = release_rx.await;
+                Ok(1)
+            })
+            .await
+        });
+        started_rx.await.unwrap();
+
+        let err = governor.run(CommandClass::Scan, "arp_scan", |_| async { Ok(2) }).await.unwrap_err();
+        assert_eq!(err.code, ErrorCode::Busy);
+        assert!(err.message.starts_with("arp_scan: busy, try again"), "{}", err);
+        // Other classes are unaffected
+        assert_eq!(governor.run(CommandClass::Snapshot, "rtsp_capture_frame", |_| async { Ok(3) }).await, Ok(3));
+
+        release_tx.send(()).unwrap();
+        assert_eq!(first.await.unwrap(), Ok(1));
+
+        // Blocking work handed off by the task sees the cancellation
+        let (worker_tx, worker_rx) = std::sync::mpsc::channel();
+        let slow = governor.run(CommandClass::Scan, "scan_network", |cancel| async move {
+            std::thread::spawn(move || {
+                while !cancel.is_cancelled() {
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                let _ = worker_tx.send(());
+            });
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        });
+        assert_eq!(
+            slow.await,
+            Err(BroxeenError::new(ErrorCode::Timeout, "scan_network: operation timed out after 1s"))
+        );
+        assert!(worker_rx.recv_timeout(Duration::from_secs(2)).is_ok(), "worker not cancelled");
+        // The timed-out call released its slot
+        assert_eq!(governor.run(CommandClass::Scan, "scan_network", |_| async { Ok(4) }).await, Ok(4));
+
+        governor.apply(&BTreeMap::from([("browse".to_string(), CommandLimit { max_concurrent: Some(5), timeout_secs: None })]));
+        assert_eq!(governor.classes.read().unwrap()[&CommandClass::Browse].max_concurrent, 5);
+        assert_eq!(governor.classes.read().unwrap()[&CommandClass::Scan].max_concurrent, 1);
+    }
+}
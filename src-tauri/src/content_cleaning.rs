@@ -1,12 +1,48 @@
 /// Content cleaning utilities.
 ///
-/// Handles cookie banner stripping, whitespace normalization,
-/// and content truncation.
+/// Handles cookie banner stripping, boilerplate and link-list removal,
+/// whitespace normalization, and content truncation / chunking at sentence
+/// boundaries.
 
+use crate::content_extraction::TextBlock;
 use crate::logging::{backend_warn};
 
 pub const MIN_READABLE_CONTENT_LENGTH: usize = 120;
+/// Default of the `browse_content_max_chars` setting
 pub const MAX_BACKEND_CONTENT_CHARS: usize = 20_000;
+/// Smaller chunks would cut every page into dozens of calls
+const MIN_CONTENT_CHUNK_CHARS: usize = 500;
+
+/// Built-in boilerplate phrases (lowercase); settings may add more.
+pub const DEFAULT_BOILERPLATE_PATTERNS: &[&str] = &[
+    "czytaj również",
+    "czytaj także",
+    "czytaj też",
+    "zobacz także",
+    "zobacz również",
+    "zapisz się do newslettera",
+    "zapisz się na newsletter",
+    "udostępnij",
+    "reklama",
+    "subscribe to our newsletter",
+    "sign up for our newsletter",
+    "share this article",
+    "read more:",
+    "advertisement",
+];
+/// Blocks with more link text than this are navigation / related-article lists
+pub const LINK_DENSITY_THRESHOLD: f32 = 0.6;
+/// Shorter blocks are too ambiguous to cut out of the flattened text
+const MIN_LINK_BLOCK_CHARS: usize = 20;
+/// Sentences longer than this are kept even if they mention a pattern
+const MAX_BOILERPLATE_SENTENCE_CHARS: usize = 160;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CleanedContent {
+    pub text: String,
+    /// Characters removed by all cleaning steps
+    pub removed_chars: usize,
+}
 
 pub fn strip_cookie_banner_text(text: &str) -> String {
     let raw = text.trim();
@@ -104,6 +140,155 @@ pub fn strip_cookie_banner_text(text: &str) -> String {
     }
 }
 
+/// Built-in patterns plus `content_boilerplate_patterns` from settings.
+pub fn boilerplate_patterns() -> Vec<String> {
+    boilerplate_patterns_from(&crate::settings::load_settings())
+}
+
+/// [`boilerplate_patterns`] for settings the caller already loaded.
+pub fn boilerplate_patterns_from(settings: &crate::settings::AudioSettings) -> Vec<String> {
+    let mut patterns: Vec<String> = DEFAULT_BOILERPLATE_PATTERNS.iter().map(|p| p.to_string()).collect();
+    for extra in &settings.content_boilerplate_patterns {
+        let extra = extra.trim().to_lowercase();
+        if !extra.is_empty() && !patterns.contains(&extra) {
+            patterns.push(extra);
+        }
+    }
+    patterns
+}
+
+/// Remove link-dense blocks ("Czytaj także" lists, tag clouds, share bars)
+/// from extracted text. Blocks come from the same HTML the text was taken from.
+pub fn strip_link_dense_blocks(text: &str, blocks: &[TextBlock]) -> String {
+    // Blocks follow document order, so each is searched for after the
+    // previous match (a dense block is cut where it sits, not at an earlier
+    // copy of the same words in the article). A block nested in the previous
+    // one (`<p>` in `<li>`) is found inside it instead.
+    let mut cuts: Vec<std::ops::Range<usize>> = Vec::new();
+    let (mut prev_start, mut cursor) = (0, 0);
+    for block in blocks {
+        if block.text.is_empty() {
+            continue;
+        }
+        let found = text[cursor..]
+            .find(&block.text)
+            .map(|rel| cursor + rel)
+            .or_else(|| text[prev_start..].find(&block.text).map(|rel| prev_start + rel));
+        let Some(start) = found else { continue };
+        let end = start + block.text.len();
+        prev_start = start;
+        cursor = cursor.max(end);
+        let dense =
+            block.text.chars().count() >= MIN_LINK_BLOCK_CHARS && block.link_density() > LINK_DENSITY_THRESHOLD;
+        if dense && cuts.last().is_none_or(|cut| cut.end <= start) {
+            cuts.push(start..end);
+        }
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for cut in cuts {
+        out.push_str(&text[last..cut.start]);
+        out.push(' ');
+        last = cut.end;
+    }
+    out.push_str(&text[last..]);
+    tidy_lines(&out)
+}
+
+/// Split a line into sentences, keeping the terminating punctuation.
+fn split_sentences(line: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = line.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        let boundary = matches!(c, '.' | '!' | '?' | '…' | '|')
+            && chars.peek().is_none_or(|(_, next)| next.is_whitespace());
+        if boundary {
+            let end = idx + c.len_utf8();
+            sentences.push(&line[start..end]);
+            start = end;
+        }
+    }
+    if start < line.len() {
+        sentences.push(&line[start..]);
+    }
+    sentences
+}
+
+/// Drop short sentences containing a boilerplate phrase ("Czytaj także",
+/// "Zapisz się do newslettera", "Reklama", …). `patterns` are lowercase.
+pub fn strip_boilerplate(text: &str, patterns: &[String]) -> String {
+    let cleaned: Vec<String> = text
+        .split('\n')
+        .map(|line| {
+            split_sentences(line)
+                .into_iter()
+                .filter(|sentence| {
+                    let trimmed = sentence.trim();
+                    if trimmed.chars().count() > MAX_BOILERPLATE_SENTENCE_CHARS {
+                        return true;
+                    }
+                    let lower = trimmed.to_lowercase();
+                    !patterns.iter().any(|p| contains_phrase(&lower, p))
+                })
+                .collect::<String>()
+        })
+        .collect();
+    tidy_lines(&cleaned.join("\n"))
+}
+
+/// `phrase` occurs in `text` as whole words: "reklama" matches "Reklama."
+/// but not "Reklamacje". A phrase ending in punctuation ("read more:") may
+/// be followed by anything.
+fn contains_phrase(text: &str, phrase: &str) -> bool {
+    if phrase.is_empty() {
+        return false;
+    }
+    let word_end = phrase.chars().next_back().is_some_and(char::is_alphanumeric);
+    text.match_indices(phrase).any(|(idx, _)| {
+        let before = text[..idx].chars().next_back();
+        let after = text[idx + phrase.len()..].chars().next();
+        let starts_word = !before.is_some_and(char::is_alphanumeric);
+        let ends_word = !word_end || !after.is_some_and(char::is_alphanumeric);
+        starts_word && ends_word
+    })
+}
+
+/// Collapse whitespace within lines and runs of empty lines, keeping
+/// paragraph breaks.
+fn tidy_lines(text: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    for line in text.split('\n') {
+        let line = normalize_whitespace(line);
+        if line.is_empty() && out.last().is_none_or(|l| l.is_empty()) {
+            continue;
+        }
+        out.push(line);
+    }
+    while out.last().is_some_and(|l| l.is_empty()) {
+        out.pop();
+    }
+    out.join("\n")
+}
+
+/// Full cleaning pass: cookie banner, link-dense blocks, boilerplate
+/// sentences. Falls back to the input if cleaning would leave too little.
+pub fn clean_content(text: &str, blocks: &[TextBlock], patterns: &[String]) -> CleanedContent {
+    let original_chars = text.chars().count();
+    let cleaned = strip_cookie_banner_text(text);
+    let cleaned = strip_link_dense_blocks(&cleaned, blocks);
+    let cleaned = strip_boilerplate(&cleaned, patterns);
+
+    if cleaned.chars().count() < MIN_READABLE_CONTENT_LENGTH && original_chars >= MIN_READABLE_CONTENT_LENGTH {
+        return CleanedContent { text: text.trim().to_string(), removed_chars: 0 };
+    }
+    CleanedContent {
+        removed_chars: original_chars.saturating_sub(cleaned.chars().count()),
+        text: cleaned,
+    }
+}
+
 pub fn truncate_to_chars(text: &str, max_chars: usize) -> String {
     let mut iter = text.chars();
     let truncated: String = iter.by_ref().take(max_chars).collect();
@@ -116,6 +301,68 @@ pub fn truncate_to_chars(text: &str, max_chars: usize) -> String {
     truncated
 }
 
+/// Chunk size for `browse` content: the `browse_content_max_chars` setting.
+pub fn content_max_chars() -> usize {
+    crate::settings::load_settings().browse_content_max_chars.max(MIN_CONTENT_CHUNK_CHARS)
+}
+
+/// Cut `text` to at most `max_chars` after the last complete sentence (or
+/// line); without one in the second half of the window, after the last word,
+/// and only then mid-word. The whitespace following the cut stays in the
+/// returned part, so consecutive chunks concatenate back to `text`.
+pub fn truncate_at_sentence(text: &str, max_chars: usize) -> String {
+    let Some(cut) = sentence_cut(text, max_chars) else {
+        return text.to_string();
+    };
+    backend_warn(format!(
+        "Extracted content exceeded {} chars and was cut at char {}",
+        max_chars,
+        text[..cut].chars().count()
+    ));
+    text[..cut].to_string()
+}
+
+/// Byte index [`truncate_at_sentence`] cuts at, `None` when `text` fits.
+fn sentence_cut(text: &str, max_chars: usize) -> Option<usize> {
+    let (window_end, _) = text.char_indices().nth(max_chars)?;
+    let mut sentence_end = None;
+    let mut word_end = None;
+    for (idx, c) in text[..window_end].char_indices() {
+        let end = idx + c.len_utf8();
+        if c.is_whitespace() {
+            word_end = Some(end);
+            if c == '\n' {
+                sentence_end = Some(end);
+            }
+        } else if matches!(c, '.' | '!' | '?' | '…') {
+            if let Some(space) = text[end..].chars().next().filter(|n| n.is_whitespace()) {
+                if end + space.len_utf8() <= window_end {
+                    sentence_end = Some(end + space.len_utf8());
+                }
+            }
+        }
+    }
+    let half = window_end / 2;
+    Some(
+        sentence_end
+            .filter(|&end| end > half)
+            .or(word_end.filter(|&end| end > half))
+            .unwrap_or(window_end),
+    )
+}
+
+/// The chunk of `text` starting `offset` chars in, cut like
+/// [`truncate_at_sentence`]; empty past the end. Only the first chunk warns
+/// about the cut — the later ones are the reader paging on.
+pub fn content_chunk(text: &str, offset: usize, max_chars: usize) -> String {
+    if offset == 0 {
+        return truncate_at_sentence(text, max_chars);
+    }
+    let start = text.char_indices().nth(offset).map_or(text.len(), |(idx, _)| idx);
+    let rest = &text[start..];
+    rest[..sentence_cut(rest, max_chars).unwrap_or(rest.len())].to_string()
+}
+
 pub fn normalize_whitespace(text: &str) -> String {
     text.split_whitespace().collect::<Vec<_>>().join(" ")
 }
@@ -145,3 +392,120 @@ pub fn is_bot_protection_text(text: &str) -> bool {
     
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content_extraction::{extract_text_blocks, extract_with_scraper};
+
+    fn patterns() -> Vec<String> {
+        DEFAULT_BOILERPLATE_PATTERNS.iter().map(|p| p.to_string()).collect()
+    }
+
+    fn clean_fixture(html: &str) -> CleanedContent {
+        let (_, content) = extract_with_scraper(html, "https://example.com");
+        let blocks = extract_text_blocks(&scraper::Html::parse_document(html));
+        clean_content(&content, &blocks, &patterns())
+    }
+
+    #[test]
+    fn test_polish_news_fixture() {
+        let cleaned = clean_fixture(include_str!("../tests/fixtures/news_pl.html"));
+        let text = cleaned.text.to_lowercase();
+
+        assert!(text.contains("rada miasta przyjęła budżet"));
+        assert!(text.contains("inwestycje w komunikację"));
+        assert!(!text.contains("czytaj także"));
+        assert!(!text.contains("newslettera"));
+        assert!(!text.contains("udostępnij"));
+        assert!(!text.contains("korki na obwodnicy"), "related-article links left: {}", cleaned.text);
+        assert!(cleaned.removed_chars > 100);
+    }
+
+    #[test]
+    fn test_english_blog_fixture() {
+        let cleaned = clean_fixture(include_str!("../tests/fixtures/blog_en.html"));
+        let text = cleaned.text.to_lowercase();
+
+        assert!(text.contains("sourdough starter"));
+        assert!(text.contains("advertising budgets"), "long sentence mentioning a pattern must stay");
+        assert!(!text.contains("subscribe to our newsletter"));
+        assert!(!text.contains("popular posts"));
+        assert!(cleaned.removed_chars > 50);
+    }
+
+    #[test]
+    fn test_split_sentences_keeps_decimals() {
+        assert_eq!(
+            split_sentences("Kurs wynosi 4.25 zł. Reklama. Koniec"),
+            vec!["Kurs wynosi 4.25 zł.", " Reklama.", " Koniec"]
+        );
+    }
+
+    #[test]
+    fn test_truncate_at_sentence_boundaries() {
+        let text = "Rada miasta przyjęła budżet. Więcej pieniędzy trafi na tramwaje! Remonty szkół ruszą latem.";
+        assert_eq!(truncate_at_sentence(text, 200), text);
+        assert_eq!(truncate_at_sentence(text, 70), "Rada miasta przyjęła budżet. Więcej pieniędzy trafi na tramwaje! ");
+        assert_eq!(truncate_at_sentence(text, 50), "Rada miasta przyjęła budżet. ");
+        // No sentence end in the second half of the window: cut after a word
+        assert_eq!(truncate_at_sentence("Tak. Rada miasta przyjęła nowy budżet", 30), "Tak. Rada miasta przyjęła ");
+        assert_eq!(truncate_at_sentence("Kurs 4.25 zł dziś", 9), "Kurs ");
+        assert_eq!(truncate_at_sentence("Bezspacji", 4), "Bezs");
+    }
+
+    #[test]
+    fn test_content_chunks_concatenate_to_text() {
+        let text = "Zdanie pierwsze ma kilka słów. Zdanie drugie też.\nNowa linia bez kropki\nKoniec tekstu.";
+        let mut offset = 0;
+        let mut chunks = Vec::new();
+        while offset < text.chars().count() {
+            let chunk = content_chunk(text, offset, 40);
+            assert!(chunk.chars().count() <= 40);
+            offset += chunk.chars().count();
+            chunks.push(chunk);
+        }
+        assert_eq!(chunks[0], "Zdanie pierwsze ma kilka słów. ");
+        assert_eq!(chunks.concat(), text);
+        assert_eq!(content_chunk(text, 10_000, 40), "");
+    }
+
+    #[test]
+    fn test_boilerplate_matches_whole_words() {
+        let text = "Reklamacje przyjmujemy do piątku. Reklama. Udostępnianie danych wymaga zgody. Read more: link";
+        assert_eq!(
+            strip_boilerplate(text, &patterns()),
+            "Reklamacje przyjmujemy do piątku. Udostępnianie danych wymaga zgody."
+        );
+    }
+
+    #[test]
+    fn test_link_dense_block_cut_at_its_position() {
+        let related = "Korki na obwodnicy Nowe rondo";
+        let text = format!("{} to temat dnia. Treść artykułu.\n{}", related, related);
+        let blocks = vec![
+            TextBlock { text: format!("{} to temat dnia. Treść artykułu.", related), link_chars: 0 },
+            TextBlock { text: related.to_string(), link_chars: related.chars().count() },
+        ];
+        assert_eq!(
+            strip_link_dense_blocks(&text, &blocks),
+            format!("{} to temat dnia. Treść artykułu.", related)
+        );
+
+        // A dense <p> inside a plain <li> is still found
+        let item = format!("Zobacz: {}", related);
+        let blocks = vec![
+            TextBlock { text: item.clone(), link_chars: 0 },
+            TextBlock { text: related.to_string(), link_chars: related.chars().count() },
+        ];
+        assert_eq!(strip_link_dense_blocks(&format!("Wstęp.\n{}", item), &blocks), "Wstęp.\nZobacz:");
+    }
+
+    #[test]
+    fn test_clean_content_short_boilerplate_only() {
+        let text = "Reklama. Udostępnij.";
+        let cleaned = clean_content(text, &[], &patterns());
+        assert_eq!(cleaned.text, "");
+        assert_eq!(cleaned.removed_chars, text.chars().count());
+    }
+}
@@ -3,93 +3,209 @@
 /// Handles structured extraction from HTML using CSS selectors,
 /// DuckDuckGo search result parsing, and scraper-based fallbacks.
 
+use serde::{Deserialize, Serialize};
+
 use crate::content_cleaning::{normalize_whitespace, MIN_READABLE_CONTENT_LENGTH, MAX_BACKEND_CONTENT_CHARS, truncate_to_chars};
-use crate::logging::backend_info;
+use crate::logging::{backend_info, backend_warn};
+
+/// One organic search result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchResultItem {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+}
 
-/// Extract search results from DuckDuckGo HTML-only page.
-/// Returns formatted search results as text, or None if not a DuckDuckGo page.
-pub fn extract_search_results(html: &str, url: &str) -> Option<String> {
-    // Only handle DuckDuckGo HTML search pages
-    if !url.contains("html.duckduckgo.com") && !url.contains("duckduckgo.com/?q=") {
-        return None;
-    }
+/// Structured results plus the text shown to the assistant.
+#[derive(Debug, Clone)]
+pub struct SearchExtraction {
+    pub engine: &'static str,
+    pub items: Vec<SearchResultItem>,
+    pub text: String,
+}
 
-    let document = scraper::Html::parse_document(html);
+/// Selectors for one search engine's results page. Add an entry to
+/// `SEARCH_ENGINES` to support another engine.
+struct SearchEngine {
+    name: &'static str,
+    /// Host suffix (`google.` matches every country domain)
+    host: &'static str,
+    /// Required path prefix; empty = any
+    path: &'static str,
+    /// Result containers, tried in order until one yields results
+    result: &'static [&'static str],
+    title: &'static str,
+    link: &'static str,
+    snippet: &'static str,
+}
 
-    // DuckDuckGo HTML results are in <div class="result"> or <div class="links_main">
-    let result_selectors = [
-        ".result",
-        ".links_main",
-        ".result__body",
-    ];
+const MAX_SEARCH_RESULTS: usize = 10;
+
+const SEARCH_ENGINES: &[SearchEngine] = &[
+    SearchEngine {
+        name: "duckduckgo",
+        host: "duckduckgo.com",
+        path: "",
+        result: &[".result", ".links_main", ".result__body"],
+        title: "a.result__a, .result__title a, a",
+        link: "a.result__a, .result__title a, a",
+        snippet: ".result__snippet, .snippet",
+    },
+    SearchEngine {
+        name: "google",
+        host: "google.",
+        path: "/search",
+        result: &["div.g", "div.MjjYud", "div.Gx5Zad"],
+        title: "h3",
+        link: "a[href]",
+        snippet: ".VwiC3b, .IsZvec, div[data-sncf], .BNeawe.s3v9rd, .st",
+    },
+    SearchEngine {
+        name: "bing",
+        host: "bing.com",
+        path: "/search",
+        result: &["li.b_algo"],
+        title: "h2",
+        link: "h2 a[href], a[href]",
+        snippet: ".b_caption p, .b_lineclamp2, .b_algoSlug, p",
+    },
+];
+
+fn engine_for(url: &url::Url) -> Option<&'static SearchEngine> {
+    let host = url.host_str()?.to_ascii_lowercase();
+    SEARCH_ENGINES.iter().find(|engine| {
+        let host_ok = if engine.host.ends_with('.') {
+            host.starts_with(engine.host) || host.contains(&format!(".{}", engine.host))
+        } else {
+            host == engine.host || host.ends_with(&format!(".{}", engine.host))
+        };
+        host_ok && url.path().starts_with(engine.path)
+    })
+}
 
-    let mut results: Vec<String> = Vec::new();
+/// Unwrap engine redirect links (`/l/?uddg=`, `/url?q=`, Bing `/ck/a?u=a1…`)
+/// and resolve relative hrefs against the results page.
+fn result_target(href: &str, page: &url::Url) -> Option<String> {
+    let resolved = page.join(href.trim()).ok()?;
+    let param = |name: &str| {
+        resolved
+            .query_pairs()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.into_owned())
+    };
+    let target = match (resolved.host_str().unwrap_or_default(), resolved.path()) {
+        (host, "/l/") if host.ends_with("duckduckgo.com") => param("uddg"),
+        (host, "/url") if host.contains("google.") => param("q").or_else(|| param("url")),
+        (host, "/ck/a") if host.ends_with("bing.com") => param("u").and_then(|u| {
+            use base64::Engine;
+            let encoded = u.strip_prefix("a1")?;
+            let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+                .decode(encoded.trim_end_matches('='))
+                .ok()?;
+            String::from_utf8(bytes).ok()
+        }),
+        _ => None,
+    };
+    let target = target.unwrap_or_else(|| resolved.to_string());
+    target.starts_with("http").then_some(target)
+}
 
-    for sel_str in &result_selectors {
-        if let Ok(selector) = scraper::Selector::parse(sel_str) {
-            let elements: Vec<_> = document.select(&selector).collect();
-            if elements.is_empty() {
+fn extract_items(document: &scraper::Html, engine: &SearchEngine, page: &url::Url) -> Vec<SearchResultItem> {
+    let (Ok(title_sel), Ok(link_sel), Ok(snippet_sel)) = (
+        scraper::Selector::parse(engine.title),
+        scraper::Selector::parse(engine.link),
+        scraper::Selector::parse(engine.snippet),
+    ) else {
+        return Vec::new();
+    };
+    let text_of = |el: scraper::ElementRef| normalize_whitespace(&el.text().collect::<Vec<_>>().join(" "));
+
+    for result_sel in engine.result.iter().filter_map(|s| scraper::Selector::parse(s).ok()) {
+        let mut items: Vec<SearchResultItem> = Vec::new();
+        for element in document.select(&result_sel) {
+            let title = element.select(&title_sel).next().map(text_of).unwrap_or_default();
+            let url = element
+                .select(&link_sel)
+                .filter_map(|a| a.value().attr("href"))
+                .find_map(|href| result_target(href, page))
+                .unwrap_or_default();
+            let snippet = element.select(&snippet_sel).next().map(text_of).unwrap_or_default();
+
+            if title.is_empty() && snippet.is_empty() {
                 continue;
             }
-
-            for element in elements.iter().take(10) {
-                // Extract title from <a class="result__a"> or first <a>
-                let title = scraper::Selector::parse("a.result__a, .result__title a, a")
-                    .ok()
-                    .and_then(|sel| element.select(&sel).next())
-                    .map(|a| a.text().collect::<Vec<_>>().join(" ").trim().to_string())
-                    .unwrap_or_default();
-
-                // Extract snippet from <a class="result__snippet"> or <div class="result__snippet">
-                let snippet = scraper::Selector::parse(".result__snippet, .snippet")
-                    .ok()
-                    .and_then(|sel| element.select(&sel).next())
-                    .map(|s| s.text().collect::<Vec<_>>().join(" ").trim().to_string())
-                    .unwrap_or_default();
-
-                // Extract URL from <a class="result__url"> or href
-                let result_url = scraper::Selector::parse("a.result__url, .result__extras__url a")
-                    .ok()
-                    .and_then(|sel| element.select(&sel).next())
-                    .map(|a| a.text().collect::<Vec<_>>().join(" ").trim().to_string())
-                    .unwrap_or_default();
-
-                if title.is_empty() && snippet.is_empty() {
-                    continue;
-                }
-
-                let mut entry = String::new();
-                if !title.is_empty() {
-                    entry.push_str(&format!("• {}", title));
-                }
-                if !result_url.is_empty() {
-                    entry.push_str(&format!(" ({})", result_url));
-                }
-                if !snippet.is_empty() {
-                    entry.push_str(&format!("\n  {}", snippet));
-                }
-                results.push(entry);
+            // Nested containers (DuckDuckGo .result > .links_main) repeat results
+            if items.iter().any(|i| i.title == title && i.url == url) {
+                continue;
             }
-
-            if !results.is_empty() {
+            items.push(SearchResultItem { title, url, snippet });
+            if items.len() >= MAX_SEARCH_RESULTS {
                 break;
             }
         }
+        if !items.is_empty() {
+            return items;
+        }
     }
+    Vec::new()
+}
 
-    if results.is_empty() {
-        // Fallback: try to get any text from the body that looks like results
+fn format_search_results(items: &[SearchResultItem]) -> String {
+    let entries: Vec<String> = items
+        .iter()
+        .map(|item| {
+            let mut entry = String::new();
+            if !item.title.is_empty() {
+                entry.push_str(&format!("• {}", item.title));
+            }
+            if !item.url.is_empty() {
+                entry.push_str(&format!(" ({})", item.url));
+            }
+            if !item.snippet.is_empty() {
+                entry.push_str(&format!("\n  {}", item.snippet));
+            }
+            entry
+        })
+        .collect();
+    format!("Wyniki wyszukiwania:\n\n{}", entries.join("\n\n"))
+}
+
+/// Extract results from a known search engine's results page (see
+/// `SEARCH_ENGINES`). Returns None for other pages or when nothing matched.
+pub fn extract_search_results(html: &str, url: &str) -> Option<SearchExtraction> {
+    let page = url::Url::parse(url).ok()?;
+    let engine = engine_for(&page)?;
+
+    let document = scraper::Html::parse_document(html);
+    let items = extract_items(&document, engine, &page);
+    if items.is_empty() {
         return None;
     }
 
     backend_info(format!(
-        "Extracted {} search results from DuckDuckGo HTML page",
-        results.len()
+        "Extracted {} search results from {} results page",
+        items.len(),
+        engine.name
     ));
 
-    Some(format!("Wyniki wyszukiwania:\n\n{}", results.join("\n\n")))
+    Some(SearchExtraction {
+        engine: engine.name,
+        text: format_search_results(&items),
+        items,
+    })
 }
 
+/// Main content containers, most specific first
+const CONTENT_SELECTORS: &[&str] = &[
+    "article",
+    "main",
+    "[role=\"main\"]",
+    ".content",
+    "#content",
+    ".article-body",
+    ".post-content",
+];
+
 /// Extract meaningful content from an HTML document using priority selectors.
 pub fn extract_content(document: &scraper::Html) -> String {
     // Junk elements to subtract from any matched container
@@ -101,17 +217,7 @@ pub fn extract_content(document: &scraper::Html) -> String {
     ).unwrap();
 
     // Try to find article content first
-    let selectors = [
-        "article",
-        "main",
-        "[role=\"main\"]",
-        ".content",
-        "#content",
-        ".article-body",
-        ".post-content",
-    ];
-
-    for sel_str in &selectors {
+    for sel_str in CONTENT_SELECTORS {
         if let Ok(selector) = scraper::Selector::parse(sel_str) {
             if let Some(element) = document.select(&selector).next() {
                 // Collect full text, then subtract junk element text
@@ -177,24 +283,225 @@ pub fn extract_with_scraper(html: &str, url: &str) -> (String, String) {
         .filter(|t| !t.is_empty())
         .unwrap_or_else(|| url.to_string());
 
-    let content = extract_content(&document);
+    let content = splice_tables(&extract_content(&document), &extract_tables(&document));
 
     (title, content)
 }
 
-/// Build a search result BrowseResult if the URL is a DuckDuckGo search.
+/// Tier 1 of `browse`: readability on the requested URL, falling back to
+/// the scraper heuristics when it fails or returns too little text.
+/// Returns raw (uncleaned) title and content.
+pub fn extract_tier1(html: &str, url: &str, final_url: &str) -> (String, String) {
+    let parsed_url = match url::Url::parse(url) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            backend_warn(format!(
+                "Failed to parse requested URL {} for readability context: {}. Using final URL {}.",
+                url, err, final_url
+            ));
+            url::Url::parse(final_url)
+                .unwrap_or_else(|_| url::Url::parse("https://example.com").unwrap())
+        }
+    };
+    let mut cursor = std::io::Cursor::new(html.as_bytes());
+
+    match readability::extractor::extract(&mut cursor, &parsed_url) {
+        Ok(product) => {
+            let readable_title = normalize_whitespace(&product.title);
+            let readable_content = normalize_whitespace(&product.text);
+
+            if readable_content.len() >= MIN_READABLE_CONTENT_LENGTH {
+                backend_info("Tier 1: Readability extraction successful");
+                let tables = extract_tables(&scraper::Html::parse_document(html));
+                (
+                    if readable_title.is_empty() {
+                        url.to_string()
+                    } else {
+                        readable_title
+                    },
+                    splice_tables(&readable_content, &tables),
+                )
+            } else {
+                backend_warn(format!(
+                    "Readability returned short content ({} chars). Falling back to scraper.",
+                    readable_content.len()
+                ));
+                extract_with_scraper(html, final_url)
+            }
+        }
+        Err(e) => {
+            backend_warn(format!(
+                "Readability extraction failed: {}. Falling back to scraper.",
+                e
+            ));
+            extract_with_scraper(html, final_url)
+        }
+    }
+}
+
+// ── Tables ───────────────────────────────────────────
+
+/// Body rows kept per table
+const MAX_TABLE_ROWS: usize = 30;
+const MAX_TABLE_COLS: usize = 8;
+/// Tables with a larger share of empty cells are layout, not data
+const MAX_EMPTY_CELL_RATIO: f32 = 0.5;
+
+/// A data table as GitHub-style markdown, with the flattened text it
+/// replaces in extracted content.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkdownTable {
+    pub flat_text: String,
+    pub markdown: String,
+}
+
+/// Data tables in the main content area, in document order. Skipped: tables
+/// containing tables, tables nested in a cell of another data grid, and
+/// layout tables (a single row or column, or mostly empty cells). A table
+/// inside a single-cell layout wrapper still counts.
+pub fn extract_tables(document: &scraper::Html) -> Vec<MarkdownTable> {
+    let table_sel = scraper::Selector::parse("table").unwrap();
+
+    let root = CONTENT_SELECTORS
+        .iter()
+        .filter_map(|sel| scraper::Selector::parse(sel).ok())
+        .find_map(|sel| document.select(&sel).find(|el| el.select(&table_sel).next().is_some()))
+        .unwrap_or_else(|| document.root_element());
+
+    root.select(&table_sel)
+        .filter(|table| table.select(&table_sel).next().is_none())
+        .filter(|table| {
+            let mut ancestors = table.ancestors().filter_map(scraper::ElementRef::wrap);
+            let in_chrome = ancestors
+                .clone()
+                .any(|a| matches!(a.value().name(), "nav" | "footer" | "header" | "aside"));
+            let in_grid = ancestors
+                .find(|a| a.value().name() == "table")
+                .is_some_and(|outer| {
+                    let rows = table_rows(outer);
+                    rows.len() > 1 && rows.iter().any(|r| r.len() > 1)
+                });
+            !in_chrome && !in_grid
+        })
+        .filter_map(|table| {
+            let markdown = table_to_markdown(&table_rows(table))?;
+            Some(MarkdownTable {
+                flat_text: normalize_whitespace(&table.text().collect::<Vec<_>>().join(" ")),
+                markdown,
+            })
+        })
+        .collect()
+}
+
+/// Cell texts of a table's own rows (not those of nested tables);
+/// `colspan` cells are followed by empty ones.
+fn table_rows(table: scraper::ElementRef) -> Vec<Vec<String>> {
+    fn children(el: scraper::ElementRef<'_>) -> Vec<scraper::ElementRef<'_>> {
+        el.children().filter_map(scraper::ElementRef::wrap).collect()
+    }
+    let rows = children(table).into_iter().flat_map(|child| match child.value().name() {
+        "thead" | "tbody" | "tfoot" => children(child),
+        _ => vec![child],
+    });
+    rows.filter(|row| row.value().name() == "tr")
+        .map(|row| {
+            let mut cells = Vec::new();
+            for cell in children(row).into_iter().filter(|c| matches!(c.value().name(), "th" | "td")) {
+                let span = cell.value().attr("colspan").and_then(|v| v.parse().ok()).unwrap_or(1usize);
+                cells.push(normalize_whitespace(&cell.text().collect::<Vec<_>>().join(" ")));
+                cells.extend(std::iter::repeat_n(String::new(), span.clamp(1, MAX_TABLE_COLS) - 1));
+            }
+            cells
+        })
+        .filter(|cells| !cells.is_empty())
+        .collect()
+}
+
+/// Markdown for `rows` (the first is the header), or `None` for layout tables.
+fn table_to_markdown(rows: &[Vec<String>]) -> Option<String> {
+    let cols = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if rows.len() < 2 || cols < 2 {
+        return None;
+    }
+    let total = rows.len() * cols;
+    let empty = rows.iter().map(|r| r.iter().filter(|c| c.is_empty()).count() + cols - r.len()).sum::<usize>();
+    if empty as f32 / total as f32 > MAX_EMPTY_CELL_RATIO {
+        return None;
+    }
+
+    let shown_cols = cols.min(MAX_TABLE_COLS);
+    let line = |row: &[String]| {
+        let cells: Vec<String> = (0..shown_cols)
+            .map(|i| row.get(i).map(|c| c.replace('|', "\\|")).unwrap_or_default())
+            .collect();
+        format!("| {} |", cells.join(" | "))
+    };
+
+    let body = &rows[1..];
+    let mut out = vec![line(&rows[0]), format!("|{}", " --- |".repeat(shown_cols))];
+    out.extend(body.iter().take(MAX_TABLE_ROWS).map(|row| line(row)));
+
+    let mut cut = Vec::new();
+    if body.len() > MAX_TABLE_ROWS {
+        cut.push(format!("{} z {} wierszy", MAX_TABLE_ROWS, body.len()));
+    }
+    if cols > MAX_TABLE_COLS {
+        cut.push(format!("{} z {} kolumn", MAX_TABLE_COLS, cols));
+    }
+    if !cut.is_empty() {
+        out.push(format!("\n(Tabela skrócona: pokazano {})", cut.join(", ")));
+    }
+    Some(out.join("\n"))
+}
+
+/// Byte range of `needle` in `haystack` (from `from`), ignoring whitespace,
+/// since extractors join cell text differently.
+fn find_ignoring_whitespace(haystack: &str, needle: &str, from: usize) -> Option<std::ops::Range<usize>> {
+    let needle: Vec<char> = needle.chars().filter(|c| !c.is_whitespace()).collect();
+    let first = *needle.first()?;
+    let hay: Vec<(usize, char)> = haystack[from..]
+        .char_indices()
+        .filter(|(_, c)| !c.is_whitespace())
+        .map(|(i, c)| (i + from, c))
+        .collect();
+    let last_start = hay.len().checked_sub(needle.len())?;
+    (0..=last_start)
+        .find(|&i| hay[i].1 == first && hay[i..i + needle.len()].iter().map(|(_, c)| *c).eq(needle.iter().copied()))
+        .map(|i| {
+            let (end, c) = hay[i + needle.len() - 1];
+            hay[i].0..end + c.len_utf8()
+        })
+}
+
+/// Replace each table's flattened text in `text` with its markdown, as a
+/// separate paragraph. Tables the extractor dropped are left out.
+pub fn splice_tables(text: &str, tables: &[MarkdownTable]) -> String {
+    let mut out = text.to_string();
+    let mut from = 0;
+    for table in tables {
+        if let Some(range) = find_ignoring_whitespace(&out, &table.flat_text, from) {
+            let before = out[..range.start].trim_end();
+            let spliced = format!("{}\n\n{}\n\n", before, table.markdown);
+            from = spliced.len();
+            out = spliced + out[range.end..].trim_start();
+        }
+    }
+    out.trim().to_string()
+}
+
+/// Build a search result BrowseResult if the URL is a known search engine page.
 #[allow(dead_code)]
 pub fn try_extract_search(html: &str, url: &str, _final_url: &str) -> Option<(String, String)> {
-    let search_content = extract_search_results(html, url)?;
+    let search = extract_search_results(html, url)?;
 
-    backend_info("Detected DuckDuckGo search results page, extracting results directly");
+    backend_info(format!("Detected {} search results page, extracting results directly", search.engine));
     let search_title = format!("Wyniki wyszukiwania: {}",
         url::Url::parse(url)
             .ok()
             .and_then(|u| u.query_pairs().find(|(k, _)| k == "q").map(|(_, v)| v.to_string()))
             .unwrap_or_else(|| url.to_string())
     );
-    let final_content = truncate_to_chars(&search_content, MAX_BACKEND_CONTENT_CHARS);
+    let final_content = truncate_to_chars(&search.text, MAX_BACKEND_CONTENT_CHARS);
 
     Some((search_title, final_content))
 }
@@ -214,107 +521,200 @@ pub struct ActionLinks {
     pub instagram_url: Option<String>,
 }
 
-/// Extract quick action links (RSS, Contact, Phone, Social Media, etc.) from parsed HTML
-pub fn extract_action_links(document: &scraper::Html) -> ActionLinks {
-    let mut links = ActionLinks::default();
+/// Query parameters that only carry tracking state
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "igshid", "mc_cid", "mc_eid", "_ga"];
 
-    // RSS
-    if let Ok(sel) = scraper::Selector::parse("link[type='application/rss+xml'], link[type='application/atom+xml']") {
-        if let Some(el) = document.select(&sel).next() {
-            links.rss_url = el.value().attr("href").map(|s| s.to_string());
-        }
-    }
-    if links.rss_url.is_none() {
-        if let Ok(sel) = scraper::Selector::parse("a[href*='rss'], a[href*='feed']") {
-            if let Some(el) = document.select(&sel).next() {
-                links.rss_url = el.value().attr("href").map(|s| s.to_string());
-            }
-        }
-    }
-
-    // Sitemap
-    if let Ok(sel) = scraper::Selector::parse("link[type='application/xml'][rel='sitemap'], a[href*='sitemap']") {
-        if let Some(el) = document.select(&sel).next() {
-            links.sitemap_url = el.value().attr("href").map(|s| s.to_string());
-        }
+/// Resolve an href against the page URL and drop tracking parameters.
+/// `mailto:` / `tel:` links are returned as-is; fragments and
+/// `javascript:` links are ignored.
+fn resolve_href(href: &str, base: Option<&url::Url>) -> Option<String> {
+    let href = href.trim();
+    let lower = href.to_ascii_lowercase();
+    if href.is_empty() || href.starts_with('#') || lower.starts_with("javascript:") {
+        return None;
     }
-    if links.sitemap_url.is_none() {
-        if let Ok(sel) = scraper::Selector::parse("a[href*='sitemap.xml'], a[href*='mapa-strony']") {
-            if let Some(el) = document.select(&sel).next() {
-                links.sitemap_url = el.value().attr("href").map(|s| s.to_string());
-            }
-        }
+    if lower.starts_with("mailto:") || lower.starts_with("tel:") {
+        return Some(href.to_string());
     }
 
-    // Blog
-    if let Ok(sel) = scraper::Selector::parse("a[href*='blog'], a[href*='wpis'], a[href*='artykul']") {
-        if let Some(el) = document.select(&sel).next() {
-            links.blog_url = el.value().attr("href").map(|s| s.to_string());
-        }
-    }
-
-    // Contact
-    if let Ok(sel) = scraper::Selector::parse("a[href^='mailto:'], a[href*='kontakt'], a[href*='contact']") {
-        for el in document.select(&sel) {
-            let href = el.value().attr("href").unwrap_or_default();
-            if href.starts_with("mailto:") || href.contains("kontakt") || href.contains("contact") {
-                // Ensure it's not a generic relative link that just happens to have contact in text unless it's a real link
-                links.contact_url = Some(href.to_string());
-                break;
-            }
-        }
+    let mut resolved = match base {
+        Some(base) => base.join(href).ok()?,
+        None => match url::Url::parse(href) {
+            Ok(url) => url,
+            // No base to resolve against — better a relative link than none
+            Err(_) => return Some(href.to_string()),
+        },
+    };
+    let kept: Vec<(String, String)> = resolved
+        .query_pairs()
+        .filter(|(k, _)| !k.starts_with("utm_") && !TRACKING_PARAMS.contains(&k.as_ref()))
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    if kept.is_empty() {
+        resolved.set_query(None);
+    } else {
+        resolved.query_pairs_mut().clear().extend_pairs(kept);
     }
+    resolved.set_fragment(None);
+    Some(resolved.to_string())
+}
 
-    // Phone
-    if let Ok(sel) = scraper::Selector::parse("a[href^='tel:']") {
-        if let Some(el) = document.select(&sel).next() {
-            links.phone_url = el.value().attr("href").map(|s| s.to_string());
+/// Canonical form of a social profile URL: lowercase host (done by the URL
+/// parser), no trailing slash.
+fn canonicalize_profile(url: String) -> String {
+    match url::Url::parse(&url) {
+        Ok(mut parsed) if parsed.path().len() > 1 && parsed.path().ends_with('/') => {
+            let path = parsed.path().trim_end_matches('/').to_string();
+            parsed.set_path(&path);
+            parsed.to_string()
         }
+        _ => url,
     }
+}
 
-    // Social Media - LinkedIn
-    if let Ok(sel) = scraper::Selector::parse("a[href*='linkedin.com'], a[href*='linkedin']") {
-        if let Some(el) = document.select(&sel).next() {
-            links.linkedin_url = el.value().attr("href").map(|s| s.to_string());
-        }
+/// 0 for site-wide chrome (`<head>` links, header, footer, nav), 1 for links
+/// inside page content.
+fn location_rank(el: &scraper::ElementRef) -> u8 {
+    if el.value().name() == "link" {
+        return 0;
     }
+    let in_chrome = el.ancestors().filter_map(scraper::ElementRef::wrap).any(|a| {
+        matches!(a.value().name(), "header" | "footer" | "nav")
+            || matches!(a.value().attr("role"), Some("banner" | "contentinfo" | "navigation"))
+    });
+    if in_chrome { 0 } else { 1 }
+}
 
-    // Social Media - Facebook
-    if let Ok(sel) = scraper::Selector::parse("a[href*='facebook.com'], a[href*='facebook']") {
-        if let Some(el) = document.select(&sel).next() {
-            links.facebook_url = el.value().attr("href").map(|s| s.to_string());
+/// Pick the best link for one action. Selectors are tried in priority order;
+/// within a selector candidates are deduplicated after resolution and ranked
+/// by location (header/footer first), then by how often the page links them,
+/// then by document order.
+fn pick_link(
+    document: &scraper::Html,
+    selectors: &[&str],
+    base: Option<&url::Url>,
+    accept: impl Fn(&str) -> bool,
+    canonicalize: bool,
+) -> Option<String> {
+    for sel_str in selectors {
+        let Ok(sel) = scraper::Selector::parse(sel_str) else { continue };
+        // (url, best rank, count, first position)
+        let mut candidates: Vec<(String, u8, usize, usize)> = Vec::new();
+        for (pos, el) in document.select(&sel).enumerate() {
+            let Some(href) = el.value().attr("href") else { continue };
+            if !accept(href) {
+                continue;
+            }
+            let Some(mut resolved) = resolve_href(href, base) else { continue };
+            if canonicalize {
+                resolved = canonicalize_profile(resolved);
+            }
+            let rank = location_rank(&el);
+            match candidates.iter_mut().find(|c| c.0 == resolved) {
+                Some(existing) => {
+                    existing.1 = existing.1.min(rank);
+                    existing.2 += 1;
+                }
+                None => candidates.push((resolved, rank, 1, pos)),
+            }
         }
-    }
-
-    // Social Media - Twitter/X
-    if let Ok(sel) = scraper::Selector::parse("a[href*='twitter.com'], a[href*='x.com'], a[href*='twitter']") {
-        if let Some(el) = document.select(&sel).next() {
-            links.twitter_url = el.value().attr("href").map(|s| s.to_string());
+        if let Some(best) = candidates
+            .into_iter()
+            .min_by_key(|(_, rank, count, pos)| (*rank, std::cmp::Reverse(*count), *pos))
+        {
+            return Some(best.0);
         }
     }
+    None
+}
 
-    // Social Media - GitHub
-    if let Ok(sel) = scraper::Selector::parse("a[href*='github.com'], a[href*='github']") {
-        if let Some(el) = document.select(&sel).next() {
-            links.github_url = el.value().attr("href").map(|s| s.to_string());
-        }
+/// Extract quick action links (RSS, Contact, Phone, Social Media, etc.) from
+/// parsed HTML. Links are resolved against `base_url` (the page URL).
+pub fn extract_action_links(document: &scraper::Html, base_url: &str) -> ActionLinks {
+    let base = url::Url::parse(base_url).ok();
+    let base = base.as_ref();
+    let any = |_: &str| true;
+    let social = |selector: &str| pick_link(document, &[selector], base, any, true);
+
+    ActionLinks {
+        rss_url: pick_link(
+            document,
+            &[
+                "link[type='application/rss+xml'], link[type='application/atom+xml']",
+                "a[href*='rss'], a[href*='feed']",
+            ],
+            base,
+            any,
+            false,
+        ),
+        sitemap_url: pick_link(
+            document,
+            &[
+                "link[type='application/xml'][rel='sitemap'], a[href*='sitemap']",
+                "a[href*='sitemap.xml'], a[href*='mapa-strony']",
+            ],
+            base,
+            any,
+            false,
+        ),
+        blog_url: pick_link(document, &["a[href*='blog'], a[href*='wpis'], a[href*='artykul']"], base, any, false),
+        contact_url: pick_link(
+            document,
+            &["a[href^='mailto:'], a[href*='kontakt'], a[href*='contact']"],
+            base,
+            |href| href.starts_with("mailto:") || href.contains("kontakt") || href.contains("contact"),
+            false,
+        ),
+        phone_url: pick_link(document, &["a[href^='tel:']"], base, any, false),
+        linkedin_url: social("a[href*='linkedin.com'], a[href*='linkedin']"),
+        facebook_url: social("a[href*='facebook.com'], a[href*='facebook']"),
+        twitter_url: social("a[href*='twitter.com'], a[href*='x.com'], a[href*='twitter']"),
+        github_url: social("a[href*='github.com'], a[href*='github']"),
+        youtube_url: social("a[href*='youtube.com'], a[href*='youtube']"),
+        instagram_url: social("a[href*='instagram.com'], a[href*='instagram']"),
     }
+}
 
-    // Social Media - YouTube
-    if let Ok(sel) = scraper::Selector::parse("a[href*='youtube.com'], a[href*='youtube']") {
-        if let Some(el) = document.select(&sel).next() {
-            links.youtube_url = el.value().attr("href").map(|s| s.to_string());
-        }
-    }
+/// A paragraph-level block of page text and how much of it is link text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextBlock {
+    /// Whitespace-normalized text, as it appears in extracted content
+    pub text: String,
+    pub link_chars: usize,
+}
 
-    // Social Media - Instagram
-    if let Ok(sel) = scraper::Selector::parse("a[href*='instagram.com'], a[href*='instagram']") {
-        if let Some(el) = document.select(&sel).next() {
-            links.instagram_url = el.value().attr("href").map(|s| s.to_string());
+impl TextBlock {
+    /// Share of the block's characters that sit inside `<a>` elements.
+    pub fn link_density(&self) -> f32 {
+        let total = self.text.chars().count();
+        if total == 0 {
+            0.0
+        } else {
+            self.link_chars as f32 / total as f32
         }
     }
+}
 
-    links
+/// Collect leaf text blocks (paragraphs, list items, cells and divs without
+/// nested blocks) with their link text length, for link-density cleaning.
+pub fn extract_text_blocks(document: &scraper::Html) -> Vec<TextBlock> {
+    let block_sel = scraper::Selector::parse("p, li, dd, dt, td, figcaption, div").unwrap();
+    let nested_sel = scraper::Selector::parse("p, div, li, ul, ol, table, section, article").unwrap();
+    let link_sel = scraper::Selector::parse("a").unwrap();
+    let text_of = |el: scraper::ElementRef| normalize_whitespace(&el.text().collect::<Vec<_>>().join(" "));
+
+    document
+        .select(&block_sel)
+        .filter(|el| el.value().name() != "div" || el.select(&nested_sel).next().is_none())
+        .filter_map(|el| {
+            let text = text_of(el);
+            if text.is_empty() {
+                return None;
+            }
+            let link_chars = el.select(&link_sel).map(|a| text_of(a).chars().count()).sum();
+            Some(TextBlock { text, link_chars })
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -358,22 +758,22 @@ mod tests {
         "#;
 
         let document = Html::parse_fragment(html);
-        let links = extract_action_links(&document);
+        let links = extract_action_links(&document, "https://example.com/o-nas/");
 
         // Test RSS links
-        assert_eq!(links.rss_url, Some("/feed.xml".to_string()));
+        assert_eq!(links.rss_url, Some("https://example.com/feed.xml".to_string()));
 
         // Test contact links - first matching link will be selected
-        assert_eq!(links.contact_url, Some("/kontakt".to_string()));
+        assert_eq!(links.contact_url, Some("https://example.com/kontakt".to_string()));
 
         // Test phone links
         assert_eq!(links.phone_url, Some("tel:+123456789".to_string()));
 
         // Test sitemap links
-        assert_eq!(links.sitemap_url, Some("/sitemap.xml".to_string()));
+        assert_eq!(links.sitemap_url, Some("https://example.com/sitemap.xml".to_string()));
 
         // Test blog links
-        assert_eq!(links.blog_url, Some("/blog".to_string()));
+        assert_eq!(links.blog_url, Some("https://example.com/blog".to_string()));
 
         // Test social media links
         assert_eq!(links.linkedin_url, Some("https://linkedin.com/company/example".to_string()));
@@ -400,7 +800,7 @@ mod tests {
         "#;
 
         let document = Html::parse_fragment(html);
-        let links = extract_action_links(&document);
+        let links = extract_action_links(&document, "https://example.com/o-nas/");
 
         // All links should be None
         assert_eq!(links.rss_url, None);
@@ -438,13 +838,13 @@ mod tests {
         "#;
 
         let document = Html::parse_fragment(html);
-        let links = extract_action_links(&document);
+        let links = extract_action_links(&document, "https://example.com/o-nas/");
 
         // Test fallback patterns
-        assert_eq!(links.rss_url, Some("/rss-feed".to_string()));
-        assert_eq!(links.sitemap_url, Some("/sitemap".to_string()));
-        assert_eq!(links.blog_url, Some("/blog-posts".to_string()));
-        assert_eq!(links.contact_url, Some("/contact-us".to_string()));
+        assert_eq!(links.rss_url, Some("https://example.com/rss-feed".to_string()));
+        assert_eq!(links.sitemap_url, Some("https://example.com/sitemap".to_string()));
+        assert_eq!(links.blog_url, Some("https://example.com/blog-posts".to_string()));
+        assert_eq!(links.contact_url, Some("https://example.com/contact-us".to_string()));
     }
 
     #[test]
@@ -468,12 +868,12 @@ mod tests {
         "#;
 
         let document = Html::parse_fragment(html);
-        let links = extract_action_links(&document);
+        let links = extract_action_links(&document, "https://example.com/o-nas/");
 
         // Test Polish patterns
-        assert_eq!(links.blog_url, Some("/blog".to_string()));
-        assert_eq!(links.contact_url, Some("/kontakt".to_string()));
-        assert_eq!(links.sitemap_url, Some("/mapa-strony".to_string()));
+        assert_eq!(links.blog_url, Some("https://example.com/blog".to_string()));
+        assert_eq!(links.contact_url, Some("https://example.com/kontakt".to_string()));
+        assert_eq!(links.sitemap_url, Some("https://example.com/mapa-strony".to_string()));
     }
 
     #[test]
@@ -496,11 +896,159 @@ mod tests {
         "#;
 
         let document = Html::parse_fragment(html);
-        let links = extract_action_links(&document);
+        let links = extract_action_links(&document, "https://example.com/o-nas/");
 
         // Link tags should take priority over fallback patterns
-        assert_eq!(links.rss_url, Some("/priority-feed.xml".to_string()));
-        assert_eq!(links.sitemap_url, Some("/priority-sitemap.xml".to_string()));
+        assert_eq!(links.rss_url, Some("https://example.com/priority-feed.xml".to_string()));
+        assert_eq!(links.sitemap_url, Some("https://example.com/priority-sitemap.xml".to_string()));
     }
-}
 
+    #[test]
+    fn test_action_links_relative_and_protocol_relative() {
+        let html = r#"
+        <html>
+        <head>
+            <link rel="alternate" type="application/rss+xml" href="feed.xml">
+        </head>
+        <body>
+            <a href="//cdn.example.net/sitemap.xml">Sitemap</a>
+            <a href="mailto:biuro@example.com?subject=Zapytanie">Napisz do nas</a>
+            <a href="../kontakt">Kontakt</a>
+        </body>
+        </html>
+        "#;
+
+        let document = Html::parse_document(html);
+        let links = extract_action_links(&document, "https://example.com/o-nas/zespol");
+
+        assert_eq!(links.rss_url.as_deref(), Some("https://example.com/o-nas/feed.xml"));
+        assert_eq!(links.sitemap_url.as_deref(), Some("https://cdn.example.net/sitemap.xml"));
+        // mailto: is kept verbatim, query included
+        assert_eq!(links.contact_url.as_deref(), Some("mailto:biuro@example.com?subject=Zapytanie"));
+    }
+
+    #[test]
+    fn test_action_links_strip_tracking_and_prefer_footer() {
+        let html = r#"
+        <html>
+        <body>
+            <article>
+                <p>Polecamy <a href="https://facebook.com/inny-profil">inny profil</a>.</p>
+            </article>
+            <footer>
+                <a href="https://WWW.facebook.com/Broxeen/?utm_source=site&fbclid=abc">FB</a>
+                <a href="https://www.facebook.com/Broxeen?utm_medium=footer">FB</a>
+                <a href="https://github.com/wronai/broxeen?tab=readme&utm_campaign=x">GitHub</a>
+            </footer>
+        </body>
+        </html>
+        "#;
+
+        let document = Html::parse_document(html);
+        let links = extract_action_links(&document, "https://example.com/");
+
+        assert_eq!(links.facebook_url.as_deref(), Some("https://www.facebook.com/Broxeen"));
+        assert_eq!(links.github_url.as_deref(), Some("https://github.com/wronai/broxeen?tab=readme"));
+    }
+
+    #[test]
+    fn test_search_results_duckduckgo() {
+        let html = include_str!("../tests/fixtures/search_duckduckgo.html");
+        let search = extract_search_results(html, "https://html.duckduckgo.com/html/?q=rust").unwrap();
+
+        assert_eq!(search.engine, "duckduckgo");
+        assert_eq!(search.items.len(), 2);
+        assert_eq!(search.items[0].title, "Rust Programming Language");
+        assert_eq!(search.items[0].url, "https://www.rust-lang.org/pl");
+        assert!(search.items[0].snippet.starts_with("Język, który"));
+        assert_eq!(search.items[1].url, "https://doc.rust-lang.org/book/");
+        assert!(search.text.starts_with("Wyniki wyszukiwania:\n\n• Rust Programming Language (https://www.rust-lang.org/pl)"));
+    }
+
+    #[test]
+    fn test_search_results_google() {
+        let html = include_str!("../tests/fixtures/search_google.html");
+        let search = extract_search_results(html, "https://www.google.pl/search?q=krak%C3%B3w").unwrap();
+
+        assert_eq!(search.engine, "google");
+        assert_eq!(
+            search.items,
+            vec![
+                SearchResultItem {
+                    title: "Kraków – Wikipedia, wolna encyklopedia".into(),
+                    url: "https://pl.wikipedia.org/wiki/Krak%C3%B3w".into(),
+                    snippet: "Kraków – miasto na prawach powiatu w południowej Polsce, położone nad Wisłą.".into(),
+                },
+                SearchResultItem {
+                    title: "Magiczny Kraków".into(),
+                    url: "https://www.krakow.pl/".into(),
+                    snippet: "Oficjalny serwis miasta Krakowa.".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_search_results_bing() {
+        let html = include_str!("../tests/fixtures/search_bing.html");
+        let search = extract_search_results(html, "https://www.bing.com/search?q=pogoda+krak%C3%B3w").unwrap();
+
+        assert_eq!(search.engine, "bing");
+        assert_eq!(search.items.len(), 2);
+        assert_eq!(search.items[0].url, "https://www.pogoda.pl/krakow");
+        assert_eq!(search.items[0].snippet, "Aktualna pogoda w Krakowie: temperatura, opady i wiatr.");
+        assert_eq!(search.items[1].title, "Meteo ICM");
+    }
+
+    #[test]
+    fn test_search_results_ignores_other_pages() {
+        let html = include_str!("../tests/fixtures/search_google.html");
+        assert!(extract_search_results(html, "https://www.google.pl/maps?q=krakow").is_none());
+        assert!(extract_search_results(html, "https://example.com/search?q=x").is_none());
+    }
+
+    #[test]
+    fn test_timetable_becomes_markdown_in_place() {
+        let html = include_str!("../tests/fixtures/table_timetable.html");
+        let (_, content) = extract_with_scraper(html, "https://example.com/rozklad");
+
+        let table = "| Pociąg | Odjazd | Przyjazd | Peron |\n\
+                     | --- | --- | --- | --- |\n\
+                     | SKA1 10101 | 05:12 | 05:35 | 2 |";
+        let at = content.find(table).unwrap_or_else(|| panic!("no table in: {}", content));
+        assert!(content.contains("| SKA1 10107 \\| opóźniony | 06:42 | 07:05 | 3 |"));
+        assert!(content.find("kursują codziennie").unwrap() < at);
+        assert!(content.find("Bilety można kupić").unwrap() > at);
+        // Nothing left of the flattened cells, and the nav table is not a data table
+        assert!(!content.contains("SKA1 10101 05:12"));
+        assert_eq!(extract_tables(&Html::parse_document(html)).len(), 1);
+
+        // Readability joins cell text differently; whitespace doesn't matter
+        let tables = extract_tables(&Html::parse_document(html));
+        let spliced = splice_tables("Przed. Pociąg Odjazd Przyjazd Peron SKA1 10101 05:12 05:35 2 SKA1 10103 05:42 06:05 2 \
+             SKA1 10105 06:12 06:35 3 SKA1 10107 | opóźniony 06:42 07:05 3 Po.", &tables);
+        assert!(spliced.starts_with("Przed.\n\n| Pociąg |") && spliced.ends_with("| 3 |\n\nPo."), "{}", spliced);
+    }
+
+    #[test]
+    fn test_pricing_table_truncated_and_layout_tables_skipped() {
+        let html = include_str!("../tests/fixtures/table_pricing.html");
+        let tables = extract_tables(&Html::parse_document(html));
+        // Kept despite the layout wrapper; the nested grid, spacer and single-column tables are not
+        assert_eq!(tables.len(), 1);
+
+        let lines: Vec<&str> = tables[0].markdown.lines().collect();
+        assert_eq!(lines[0], "| Pakiet | Dane | Cena | Roaming | Umowa | Karty SIM | Promocja | Kol. 8 |");
+        assert_eq!(lines[2], "| Pakiet 1 | 10 GB | 20,99 zł | tak | 12 mies. | 1 | — | x |");
+        assert_eq!(lines[31], "| Pakiet 30 | 300 GB | 49,99 zł | nie | 12 mies. | 1 | — | x |");
+        assert_eq!(lines.last(), Some(&"(Tabela skrócona: pokazano 30 z 35 wierszy, 8 z 9 kolumn)"));
+
+        let (_, content) = extract_with_scraper(html, "https://example.com/cennik");
+        assert!(content.contains("| Pakiet 30 |"));
+        assert!(!content.contains("Pakiet 31"));
+        assert!(content.contains("Roaming Strefa Cena UE 0 zł"));
+
+        assert!(table_to_markdown(&[vec!["a".into(), "".into()], vec!["".into(), "".into()]]).is_none());
+        assert!(table_to_markdown(&[vec!["a".into()], vec!["b".into()]]).is_none());
+    }
+}
@@ -0,0 +1,219 @@
+//! content_language.rs — Language of extracted page content, and optional
+//! translation for `browse`.
+//!
+//! Detection uses whatlang on the start of the content and reports an
+//! ISO 639-1 code ("en", "de") where one exists. Translation sends the content
+//! through the configured LLM (`llm::complete`) in paragraph-aligned chunks
+//! small enough for any model's context, so a 20k-character page becomes a
+//! few sequential requests instead of one oversized one.
+
+use crate::content_cleaning::{content_max_chars, truncate_at_sentence};
+use crate::logging::{backend_info, backend_warn};
+use crate::BrowseResult;
+
+/// Characters fed to the detector; more doesn't change the answer
+const DETECT_SAMPLE_CHARS: usize = 4_000;
+/// Shorter text is too ambiguous to detect
+const MIN_DETECT_CHARS: usize = 40;
+/// Characters per translation request (~2k tokens in, about as many out)
+const TRANSLATE_CHUNK_CHARS: usize = 6_000;
+const TRANSLATE_MAX_TOKENS: u32 = 4_096;
+
+/// ISO 639-1 ↔ whatlang (ISO 639-3) for the languages that have both.
+const ISO_639_1: &[(&str, whatlang::Lang)] = {
+    use whatlang::Lang::*;
+    &[
+        ("en", Eng), ("pl", Pol), ("de", Deu), ("fr", Fra), ("es", Spa), ("it", Ita),
+        ("pt", Por), ("nl", Nld), ("ru", Rus), ("uk", Ukr), ("be", Bel), ("cs", Ces),
+        ("sk", Slk), ("sl", Slv), ("hr", Hrv), ("sr", Srp), ("mk", Mkd), ("bg", Bul),
+        ("ro", Ron), ("hu", Hun), ("lt", Lit), ("lv", Lav), ("et", Est), ("fi", Fin),
+        ("sv", Swe), ("da", Dan), ("nb", Nob), ("el", Ell), ("tr", Tur), ("ca", Cat),
+        ("la", Lat), ("eo", Epo), ("zh", Cmn), ("ja", Jpn), ("ko", Kor), ("ar", Ara),
+        ("he", Heb), ("hi", Hin), ("vi", Vie), ("th", Tha), ("id", Ind), ("fa", Pes),
+    ]
+};
+
+fn code_of(lang: whatlang::Lang) -> String {
+    ISO_639_1
+        .iter()
+        .find(|(_, l)| *l == lang)
+        .map(|(code, _)| code.to_string())
+        .unwrap_or_else(|| lang.code().to_string())
+}
+
+/// "PL", "pl-PL", "pol" → "pl"; unknown codes are lowercased as given.
+pub fn normalize_code(code: &str) -> String {
+    let primary = code.trim().split(['-', '_']).next().unwrap_or("").to_lowercase();
+    match whatlang::Lang::from_code(primary.as_str()) {
+        Some(lang) => code_of(lang),
+        None => primary,
+    }
+}
+
+/// English name of a language code, for the translation prompt.
+fn language_name(code: &str) -> String {
+    ISO_639_1
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, lang)| lang.eng_name().to_string())
+        .or_else(|| whatlang::Lang::from_code(code).map(|l| l.eng_name().to_string()))
+        .unwrap_or_else(|| code.to_string())
+}
+
+/// Language of `text`, or None when it is too short or the guess unreliable.
+pub fn detect_language(text: &str) -> Option<String> {
+    let sample: String = text.chars().take(DETECT_SAMPLE_CHARS).collect();
+    if sample.trim().chars().count() < MIN_DETECT_CHARS {
+        return None;
+    }
+    let info = whatlang::detect(&sample)?;
+    info.is_reliable().then(|| code_of(info.lang()))
+}
+
+/// Split `text` into chunks of at most `max_chars`, at paragraph breaks where
+/// possible, then at line and sentence ends; a single overlong sentence is
+/// cut at a character boundary.
+fn translation_chunks(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_len = 0;
+    for piece in pieces(text, max_chars) {
+        let len = piece.chars().count();
+        if current_len + len > max_chars && !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+            current_len = 0;
+        }
+        current.push_str(piece);
+        current_len += len;
+    }
+    if !current.trim().is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Paragraphs (with their trailing breaks) no longer than `max_chars`.
+fn pieces(text: &str, max_chars: usize) -> Vec<&str> {
+    let mut out = Vec::new();
+    for paragraph in text.split_inclusive("\n\n") {
+        if paragraph.chars().count() <= max_chars {
+            out.push(paragraph);
+            continue;
+        }
+        for sentence in paragraph.split_inclusive(['\n', '.', '!', '?']) {
+            let mut rest = sentence;
+            while rest.chars().count() > max_chars {
+                let cut = rest.char_indices().nth(max_chars).map(|(i, _)| i).unwrap_or(rest.len());
+                out.push(&rest[..cut]);
+                rest = &rest[cut..];
+            }
+            if !rest.is_empty() {
+                out.push(rest);
+            }
+        }
+    }
+    out
+}
+
+/// Translate `text` from `from` into `to` with the configured LLM.
+pub async fn translate(text: &str, from: &str, to: &str) -> Result<String, String> {
+    let system = format!(
+        "Translate the user's text from {} into {}. Keep the paragraph breaks, lists, \
+         markdown tables, numbers, names and URLs. Output only the translation, \
+         without comments.",
+        language_name(from),
+        language_name(to)
+    );
+    let chunks = translation_chunks(text, TRANSLATE_CHUNK_CHARS);
+    let mut out = String::with_capacity(text.len());
+    for (i, chunk) in chunks.iter().enumerate() {
+        let messages = serde_json::json!([
+            { "role": "system", "content": system },
+            { "role": "user", "content": chunk },
+        ]);
+        let response = crate::llm::complete("browse_translate", messages, TRANSLATE_MAX_TOKENS, 0.2)
+            .await
+            .map_err(|e| format!("Tłumaczenie fragmentu {}/{} nie powiodło się: {}", i + 1, chunks.len(), e))?;
+        out.push_str(response.text.trim());
+        // Chunks end at paragraph breaks; keep them between translations
+        if i + 1 < chunks.len() {
+            out.push_str(if chunk.ends_with("\n\n") { "\n\n" } else { "\n" });
+        }
+    }
+    Ok(out)
+}
+
+/// Fill in `detected_language` / `original_content_length` (results cached
+/// by older builds lack them) and, when `translate_to` differs from the
+/// content's language, replace the content with its translation. A failed
+/// translation leaves the original content.
+pub async fn apply(result: &mut BrowseResult, translate_to: Option<&str>) {
+    if result.detected_language.is_none() {
+        result.detected_language = detect_language(&result.content);
+    }
+    if result.original_content_length == 0 {
+        result.original_content_length = result.content.chars().count();
+    }
+
+    let Some(target) = translate_to.map(normalize_code).filter(|t| !t.is_empty()) else { return };
+    let Some(source) = result.detected_language.clone() else {
+        backend_info(format!("browse: language of {} unknown — not translating", result.url));
+        return;
+    };
+    if source == target {
+        return;
+    }
+
+    backend_info(format!(
+        "browse: translating {} ({} chars) {} → {}",
+        result.url, result.content_chunk_chars, source, target
+    ));
+    match translate(&result.content, &source, &target).await {
+        Ok(translated) => {
+            result.content = truncate_at_sentence(&translated, content_max_chars());
+            result.resolve_type.push_str("+translated");
+        }
+        Err(e) => backend_warn(format!("browse: {} — returning {} content", e, source)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_page_languages() {
+        let en = "The city council approved the new budget on Tuesday, allocating more money \
+                  to public transport and the renovation of several schools in the district.";
+        let de = "Der Stadtrat hat am Dienstag den neuen Haushalt beschlossen und mehr Geld für \
+                  den öffentlichen Nahverkehr und die Sanierung mehrerer Schulen bereitgestellt.";
+        let pl = "Rada miasta przyjęła we wtorek nowy budżet, przeznaczając więcej pieniędzy na \
+                  komunikację miejską oraz remont kilku szkół w dzielnicy.";
+        assert_eq!(detect_language(en).as_deref(), Some("en"));
+        assert_eq!(detect_language(de).as_deref(), Some("de"));
+        assert_eq!(detect_language(pl).as_deref(), Some("pl"));
+        assert_eq!(detect_language("OK"), None);
+
+        assert_eq!(normalize_code("PL"), "pl");
+        assert_eq!(normalize_code("pl-PL"), "pl");
+        assert_eq!(normalize_code("deu"), "de");
+        assert_eq!(normalize_code("xx"), "xx");
+        assert_eq!(language_name("pl"), "Polish");
+    }
+
+    #[test]
+    fn test_translation_chunks_follow_paragraphs() {
+        let para = |c: char| format!("{}\n\n", c.to_string().repeat(30));
+        let text: String = ['a', 'b', 'c', 'd'].into_iter().map(para).collect();
+        let chunks = translation_chunks(&text, 70);
+        assert_eq!(chunks, vec![para('a') + &para('b'), para('c') + &para('d')]);
+        assert_eq!(chunks.concat(), text);
+
+        // An overlong paragraph splits at sentences, an overlong sentence anywhere
+        let long = format!("{}. {}", "ż".repeat(50), "x".repeat(130));
+        let chunks = translation_chunks(&long, 60);
+        assert!(chunks.iter().all(|c| c.chars().count() <= 60), "{:?}", chunks);
+        assert_eq!(chunks.concat(), long);
+        assert_eq!(chunks[0], format!("{}.", "ż".repeat(50)));
+    }
+}
@@ -0,0 +1,346 @@
+//! device_catalog.rs — Fingerprints naming the devices `scan_network` finds.
+//!
+//! A [`Fingerprint`] matches a set of open ports and, optionally, a regex on
+//! the HTTP banner (`Server` header and `<title>`) of one of them. Built-in
+//! entries cover printers (IPP, raw 9100), Synology NAS, Home Assistant,
+//! ESPHome, Chromecast and Samsung TVs. Entries from
+//! `<data_local_dir>/broxeen/device_fingerprints.json` (a JSON array of the
+//! same shape) are checked first, so they can add types or outrank built-in
+//! ones. The match with the highest confidence wins.
+//!
+//! Banners cost one HTTP GET, made only when a banner entry could beat the
+//! best port-only match, and at most once per port. A port that does not
+//! answer plain HTTP (DSM on 5001) is asked again over TLS, without checking
+//! the certificate — only the banner is read.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::logging::{backend_info, backend_warn};
+
+const CATALOG_FILE: &str = "device_fingerprints.json";
+/// Response bytes read for a banner; `<title>` is near the top
+const BANNER_MAX_BYTES: usize = 16 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub device_type: String,
+    /// All of these must be open
+    #[serde(default)]
+    pub ports_all: Vec<u16>,
+    /// At least one of these must be open (ignored when empty)
+    #[serde(default)]
+    pub ports_any: Vec<u16>,
+    /// Case-insensitive regex on the HTTP banner
+    #[serde(default)]
+    pub banner: Option<String>,
+    /// Port whose banner is read; defaults to the first open one listed above
+    #[serde(default)]
+    pub probe_port: Option<u16>,
+    /// 0.0–1.0
+    pub confidence: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Classification {
+    pub device_type: String,
+    pub confidence: f32,
+}
+
+pub struct Catalog {
+    entries: Vec<(Fingerprint, Option<Regex>)>,
+}
+
+fn entry(device_type: &str, ports_all: &[u16], ports_any: &[u16], banner: Option<&str>, confidence: f32) -> Fingerprint {
+    Fingerprint {
+        device_type: device_type.to_string(),
+        ports_all: ports_all.to_vec(),
+        ports_any: ports_any.to_vec(),
+        banner: banner.map(str::to_string),
+        probe_port: None,
+        confidence,
+    }
+}
+
+fn builtin_entries() -> Vec<Fingerprint> {
+    vec![
+        entry("printer", &[631, 9100], &[], None, 0.95),
+        entry("printer", &[], &[631], None, 0.8),
+        entry("printer", &[], &[9100], None, 0.7),
+        entry("nas", &[], &[5000, 5001], Some("synology|diskstation"), 0.95),
+        entry("home-assistant", &[8123], &[], Some("home assistant"), 0.98),
+        entry("home-assistant", &[8123], &[], None, 0.8),
+        entry("esphome", &[6053], &[], None, 0.9),
+        entry("chromecast", &[8008, 8009], &[], None, 0.9),
+        entry("smart-tv", &[8001, 8002], &[], None, 0.6),
+    ]
+}
+
+fn catalog_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("broxeen")
+        .join(CATALOG_FILE)
+}
+
+impl Catalog {
+    /// `entries` in priority order; ones with an invalid regex are skipped.
+    pub fn new(entries: Vec<Fingerprint>) -> Self {
+        let entries = entries
+            .into_iter()
+            .filter_map(|fp| {
+                let regex = match &fp.banner {
+                    None => None,
+                    Some(pattern) => match Regex::new(&format!("(?i){}", pattern)) {
+                        Ok(regex) => Some(regex),
+                        Err(e) => {
+                            backend_warn(format!("device catalog: bad banner regex for {}: {}", fp.device_type, e));
+                            return None;
+                        }
+                    },
+                };
+                Some((fp, regex))
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// User entries from the data dir, then the built-in ones.
+    pub fn load() -> Self {
+        let path = catalog_path();
+        let mut entries = match std::fs::read_to_string(&path) {
+            Ok(text) => match serde_json::from_str::<Vec<Fingerprint>>(&text) {
+                Ok(user) => {
+                    backend_info(format!("device catalog: {} entries from {}", user.len(), path.display()));
+                    user
+                }
+                Err(e) => {
+                    backend_warn(format!("device catalog: ignoring {}: {}", path.display(), e));
+                    Vec::new()
+                }
+            },
+            Err(_) => Vec::new(),
+        };
+        entries.extend(builtin_entries());
+        Self::new(entries)
+    }
+
+    /// Ports `scan_network` has to probe for every entry to be able to match.
+    pub fn ports(&self) -> Vec<u16> {
+        let mut ports: Vec<u16> = self
+            .entries
+            .iter()
+            .flat_map(|(fp, _)| fp.ports_all.iter().chain(&fp.ports_any).chain(&fp.probe_port))
+            .copied()
+            .collect();
+        ports.sort_unstable();
+        ports.dedup();
+        ports
+    }
+
+    /// Best match for a host with `open` ports. `banner` fetches the HTTP
+    /// banner of a port and is called only when that could change the result.
+    pub fn classify(&self, open: &[u16], mut banner: impl FnMut(u16) -> Option<String>) -> Option<Classification> {
+        let port_match = |fp: &Fingerprint| {
+            fp.ports_all.iter().all(|p| open.contains(p))
+                && (fp.ports_any.is_empty() || fp.ports_any.iter().any(|p| open.contains(p)))
+                && !(fp.ports_all.is_empty() && fp.ports_any.is_empty())
+        };
+        let mut best: Option<&Fingerprint> = None;
+        let better = |best: Option<&Fingerprint>, fp: &Fingerprint| best.is_none_or(|b| fp.confidence > b.confidence);
+
+        for (fp, _) in self.entries.iter().filter(|(fp, regex)| regex.is_none() && port_match(fp)) {
+            if better(best, fp) {
+                best = Some(fp);
+            }
+        }
+
+        let mut banners: HashMap<u16, Option<String>> = HashMap::new();
+        for (fp, regex) in &self.entries {
+            let Some(regex) = regex else { continue };
+            if !better(best, fp) || !port_match(fp) {
+                continue;
+            }
+            let Some(port) = fp
+                .probe_port
+                .into_iter()
+                .chain(fp.ports_all.iter().chain(&fp.ports_any).copied())
+                .find(|p| open.contains(p))
+            else {
+                continue;
+            };
+            let text = banners.entry(port).or_insert_with(|| banner(port));
+            if text.as_deref().is_some_and(|t| regex.is_match(t)) {
+                best = Some(fp);
+            }
+        }
+
+        best.map(|fp| Classification { device_type: fp.device_type.clone(), confidence: fp.confidence })
+    }
+}
+
+/// `Server` header and `<title>` of an HTTP response, one per line.
+pub fn banner_from_response(response: &str) -> String {
+    let mut parts = Vec::new();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap_or((response, ""));
+    if let Some(server) = head
+        .lines()
+        .find_map(|l| l.split_once(':').filter(|(k, _)| k.trim().eq_ignore_ascii_case("server")))
+    {
+        parts.push(server.1.trim().to_string());
+    }
+    let lower = body.to_ascii_lowercase();
+    if let Some(start) = lower.find("<title") {
+        let start = lower[start..].find('>').map(|i| start + i + 1);
+        let end = start.and_then(|s| lower[s..].find("</title>").map(|i| s + i));
+        if let (Some(start), Some(end)) = (start, end) {
+            parts.push(body[start..end].trim().to_string());
+        }
+    }
+    parts.join("\n")
+}
+
+/// Reply of a TLS port to plain HTTP: a TLS alert, or nginx's "400 The plain
+/// HTTP request was sent to HTTPS port".
+fn wants_tls(response: &str) -> bool {
+    !response.starts_with("HTTP/")
+        || (response.split_whitespace().nth(1) == Some("400") && response.to_ascii_lowercase().contains("https"))
+}
+
+fn connect(ip: &str, port: u16, timeout: Duration) -> Option<TcpStream> {
+    let addr: SocketAddr = format!("{}:{}", ip, port).parse().ok()?;
+    let stream = TcpStream::connect_timeout(&addr, timeout).ok()?;
+    stream.set_read_timeout(Some(timeout)).ok()?;
+    stream.set_write_timeout(Some(timeout)).ok()?;
+    Some(stream)
+}
+
+/// `GET /` over `stream`; `None` when nothing came back.
+fn get_root(mut stream: impl Read + Write, ip: &str) -> Option<String> {
+    let request = format!("GET / HTTP/1.0\r\nHost: {}\r\nUser-Agent: broxeen-scan\r\n\r\n", ip);
+    stream.write_all(request.as_bytes()).ok()?;
+
+    let mut buf = Vec::new();
+    let _ = stream.take(BANNER_MAX_BYTES as u64).read_to_end(&mut buf);
+    if buf.is_empty() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&buf).into_owned())
+}
+
+/// `GET /` to `ip:port`, over TLS when the port does not speak plain HTTP.
+/// Blocking — called from the scan tasks.
+pub fn http_banner(ip: &str, port: u16, timeout: Duration) -> Option<String> {
+    match get_root(connect(ip, port, timeout)?, ip) {
+        Some(response) if !wants_tls(&response) => return Some(banner_from_response(&response)),
+        _ => {}
+    }
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .danger_accept_invalid_hostnames(true)
+        .build()
+        .ok()?;
+    let stream = connector.connect(ip, connect(ip, port, timeout)?).ok()?;
+    get_root(stream, ip).map(|response| banner_from_response(&response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_fingerprints() {
+        let catalog = Catalog::new(builtin_entries());
+        let no_banner = |_: u16| -> Option<String> { panic!("no banner needed") };
+        let classify = |ports: &[u16]| catalog.classify(ports, |_| None).map(|c| (c.device_type, c.confidence));
+
+        assert_eq!(classify(&[80, 631]), Some(("printer".into(), 0.8)));
+        assert_eq!(classify(&[80, 631, 9100]), Some(("printer".into(), 0.95)));
+        assert_eq!(classify(&[6053]), Some(("esphome".into(), 0.9)));
+        assert_eq!(classify(&[8008, 8009, 8443]), Some(("chromecast".into(), 0.9)));
+        assert_eq!(classify(&[8009]), None);
+        assert_eq!(classify(&[80, 443]), None);
+        // Port 5000 alone is too common (UPnP, dev servers) without the banner
+        assert_eq!(classify(&[5000]), None);
+        assert!(catalog.classify(&[631, 9100], no_banner).is_some());
+
+        let nas = catalog.classify(&[80, 5000, 5001], |port| {
+            assert_eq!(port, 5000);
+            Some("nginx\nSynology DiskStation - ds920".into())
+        });
+        assert_eq!(nas.map(|c| c.device_type), Some("nas".into()));
+
+        let mut probes = 0;
+        let ha = catalog.classify(&[8123], |_| {
+            probes += 1;
+            Some("Home Assistant".into())
+        });
+        assert_eq!(ha, Some(Classification { device_type: "home-assistant".into(), confidence: 0.98 }));
+        assert_eq!(probes, 1);
+        assert!(catalog.ports().contains(&6053) && catalog.ports().contains(&8123));
+    }
+
+    #[test]
+    fn test_user_entries_extend_and_outrank_builtin() {
+        let user: Vec<Fingerprint> = serde_json::from_str(
+            r#"[
+                {"device_type": "printer-brother", "ports_any": [631], "banner": "brother", "confidence": 0.9},
+                {"device_type": "shelly", "ports_all": [80], "banner": "shelly", "probe_port": 80, "confidence": 0.85},
+                {"device_type": "broken", "ports_any": [1], "banner": "(", "confidence": 1.0}
+            ]"#,
+        )
+        .unwrap();
+        let mut entries = user;
+        entries.extend(builtin_entries());
+        let catalog = Catalog::new(entries);
+
+        let brother = catalog.classify(&[631], |_| Some("Brother HL-L2350DW".into())).unwrap();
+        assert_eq!(brother.device_type, "printer-brother");
+        let other = catalog.classify(&[631], |_| Some("CUPS/2.4".into())).unwrap();
+        assert_eq!(other.device_type, "printer");
+        let shelly = catalog.classify(&[80], |_| Some("Shelly Plus 1PM".into())).unwrap();
+        assert_eq!(shelly.device_type, "shelly");
+        assert!(!catalog.ports().contains(&1));
+    }
+
+    #[test]
+    fn test_http_banner_retries_over_tls() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut plain, _) = listener.accept().unwrap();
+            let _ = plain.read(&mut [0u8; 1024]);
+            plain
+                .write_all(b"HTTP/1.1 400 Bad Request\r\n\r\nThe plain HTTP request was sent to HTTPS port")
+                .unwrap();
+            drop(plain);
+            let (mut tls, _) = listener.accept().unwrap();
+            let mut first = [0u8; 1];
+            tls.read_exact(&mut first).unwrap();
+            first[0]
+        });
+        assert_eq!(http_banner("127.0.0.1", port, Duration::from_secs(2)), None);
+        // 0x16: a TLS handshake record — the ClientHello
+        assert_eq!(server.join().unwrap(), 0x16);
+    }
+
+    #[test]
+    fn test_banner_from_response() {
+        let response = "HTTP/1.1 200 OK\r\nServer: CUPS/2.4 IPP/2.1\r\nContent-Type: text/html\r\n\r\n\
+                        <html><head><TITLE>Home - CUPS 2.4.2</TITLE></head></html>";
+        assert_eq!(banner_from_response(response), "CUPS/2.4 IPP/2.1\nHome - CUPS 2.4.2");
+        assert_eq!(banner_from_response("HTTP/1.0 404 Not Found\r\n\r\n"), "");
+
+        assert!(!wants_tls(response));
+        assert!(wants_tls("\u{15}\u{3}\u{1}\u{0}\u{2}\u{2}\n"));
+        assert!(wants_tls(
+            "HTTP/1.1 400 Bad Request\r\nServer: nginx\r\n\r\n<center>The plain HTTP request was sent to HTTPS port</center>"
+        ));
+        assert!(!wants_tls("HTTP/1.1 400 Bad Request\r\n\r\n"));
+    }
+}
@@ -1,12 +1,19 @@
 /**
  * Disk information commands for Tauri backend.
- * Provides: get_disk_info, get_disk_partitions, get_disk_usage
+ * Provides: get_disk_info, get_disk_partitions, get_disk_usage,
+ * disk_usage_tree, disk_usage_cancel, get_disk_smart, disk_mount, disk_unmount
  */
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 
-use crate::logging::backend_info;
+use crate::error::{BroxeenError, ErrorCode};
+use crate::logging::{backend_info, backend_warn};
 
 // ─── Disk Partition ──────────────────────────────────────────
 
@@ -19,6 +26,11 @@ pub struct DiskPartition {
     pub used_bytes: u64,
     pub available_bytes: u64,
     pub use_percent: f32,
+    /// USB stick, SD card, external drive…
+    #[serde(default)]
+    pub is_removable: bool,
+    #[serde(default)]
+    pub label: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -36,7 +48,8 @@ pub async fn get_disk_info() -> Result<DiskInfo, String> {
     backend_info("Command get_disk_info invoked");
 
     let hostname = get_hostname();
-    let partitions = parse_df_output()?;
+    let mut partitions = parse_df_output()?;
+    annotate_partitions(&mut partitions, &list_block_devices());
 
     let total_bytes: u64 = partitions.iter().map(|p| p.total_bytes).sum();
     let used_bytes: u64 = partitions.iter().map(|p| p.used_bytes).sum();
@@ -87,12 +100,21 @@ pub async fn get_disk_usage(path: Option<String>) -> Result<DiskPartition, Strin
         return Err("Unexpected df output format".to_string());
     }
 
-    parse_df_line(lines[1])
+    let mut partition = parse_df_line(lines[1])?;
+    annotate_partitions(std::slice::from_mut(&mut partition), &list_block_devices());
+    Ok(partition)
 }
 
 fn parse_df_output() -> Result<Vec<DiskPartition>, String> {
     let output = Command::new("df")
-        .args(["-B1", "--output=source,target,fstype,size,used,avail,pcent", "-x", "tmpfs", "-x", "devtmpfs", "-x", "squashfs", "-x", "overlay"])
+        .args([
+            "-B1",
+            "--output=source,target,fstype,size,used,avail,pcent",
+            "-x", "tmpfs",
+            "-x", "devtmpfs",
+            "-x", "squashfs",
+            "-x", "overlay",
+        ])
         .output()
         .map_err(|e| format!("Failed to run df: {}", e))?;
 
@@ -124,6 +146,8 @@ fn parse_df_output() -> Result<Vec<DiskPartition>, String> {
                     used_bytes,
                     available_bytes,
                     use_percent,
+                    is_removable: false,
+                    label: None,
                 });
             }
         }
@@ -172,9 +196,798 @@ fn parse_df_line(line: &str) -> Result<DiskPartition, String> {
         used_bytes,
         available_bytes,
         use_percent,
+        is_removable: false,
+        label: None,
     })
 }
 
+// ─── Removable Media ─────────────────────────────────────────
+
+/// A disk or partition, mounted or not.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BlockDevice {
+    pub device: String,
+    pub is_removable: bool,
+    pub label: Option<String>,
+    pub fs_type: Option<String>,
+    pub mount_point: Option<String>,
+    pub size_bytes: u64,
+}
+
+/// `/proc/mounts` escapes space, tab, newline and backslash as octal (`\040`).
+fn unescape_octal(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('\\') {
+        out.push_str(&rest[..i]);
+        let code = rest.get(i + 1..i + 4).and_then(|d| u8::from_str_radix(d, 8).ok());
+        match code {
+            Some(c) => {
+                out.push(c as char);
+                rest = &rest[i + 4..];
+            }
+            None => {
+                out.push('\\');
+                rest = &rest[i + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// udev escapes label bytes as `\x20`; the bytes may form UTF-8 together.
+fn unescape_hex(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = if bytes[i] == b'\\' && bytes.get(i + 1) == Some(&b'x') {
+            s.get(i + 2..i + 4).and_then(|h| u8::from_str_radix(h, 16).ok())
+        } else {
+            None
+        };
+        match escaped {
+            Some(b) => {
+                out.push(b);
+                i += 4;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// Device → first mount point, from `/proc/mounts`.
+fn parse_proc_mounts(text: &str) -> HashMap<String, String> {
+    let mut mounts = HashMap::new();
+    for line in text.lines() {
+        let mut fields = line.split_whitespace();
+        if let (Some(device), Some(target)) = (fields.next(), fields.next()) {
+            if device.starts_with("/dev/") {
+                mounts.entry(unescape_octal(device)).or_insert_with(|| unescape_octal(target));
+            }
+        }
+    }
+    mounts
+}
+
+/// `E:KEY=value` properties of a udev database entry (`/run/udev/data/b8:17`).
+fn parse_udev_properties(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter_map(|line| line.strip_prefix("E:")?.split_once('='))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+/// Where desktops auto-mount removable media: `/media/<user>/<label>`,
+/// `/run/media/<user>/<label>`, `/Volumes/<label>` (macOS).
+fn removable_mount_label(mount_point: &str) -> Option<String> {
+    let parts: Vec<&str> = mount_point.trim_start_matches('/').split('/').collect();
+    let label = match parts.as_slice() {
+        ["Volumes", label] | ["media", _, label] | ["run", "media", _, label] => label,
+        _ => return None,
+    };
+    Some(label.to_string())
+}
+
+/// Fill in `is_removable`, `label` and a missing `fs_type` from `devices`;
+/// partitions without a matching device go by their mount point.
+fn annotate_partitions(partitions: &mut [DiskPartition], devices: &[BlockDevice]) {
+    for p in partitions {
+        match devices.iter().find(|d| d.device == p.device) {
+            Some(d) => {
+                p.is_removable = d.is_removable;
+                p.label = d.label.clone();
+                if p.fs_type == "unknown" {
+                    if let Some(fs_type) = &d.fs_type {
+                        p.fs_type = fs_type.clone();
+                    }
+                }
+            }
+            None => {
+                p.label = removable_mount_label(&p.mount_point);
+                p.is_removable = p.label.is_some();
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_trimmed(path: impl AsRef<Path>) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// The disk's `removable` flag (set for card readers and most sticks) or a
+/// USB bus anywhere up the device path (USB hard drives report removable=0).
+#[cfg(target_os = "linux")]
+fn sysfs_removable(sys: &Path, udev: &HashMap<String, String>) -> bool {
+    let Ok(real) = fs::canonicalize(sys) else { return false };
+    let disk = if real.join("partition").exists() { real.parent().unwrap_or(&real) } else { &real };
+    read_trimmed(disk.join("removable")).as_deref() == Some("1")
+        || real.to_string_lossy().contains("/usb")
+        || udev.get("ID_BUS").map(String::as_str) == Some("usb")
+}
+
+/// Disks and partitions from `/sys/class/block`, with filesystem type and
+/// label from the udev database and mount points from `/proc/mounts`.
+/// Loop, RAM and empty (no medium) devices are left out.
+#[cfg(target_os = "linux")]
+pub fn list_block_devices() -> Vec<BlockDevice> {
+    let mounts = fs::read_to_string("/proc/mounts").map(|t| parse_proc_mounts(&t)).unwrap_or_default();
+    let Ok(entries) = fs::read_dir("/sys/class/block") else { return Vec::new() };
+
+    let mut devices: Vec<BlockDevice> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if ["loop", "ram", "zram", "nbd"].iter().any(|p| name.starts_with(p)) {
+                return None;
+            }
+            let sys = entry.path();
+            let sectors: u64 = read_trimmed(sys.join("size"))?.parse().ok()?;
+            if sectors == 0 {
+                return None;
+            }
+            let udev = read_trimmed(sys.join("dev"))
+                .and_then(|dev| fs::read_to_string(format!("/run/udev/data/b{}", dev)).ok())
+                .map(|t| parse_udev_properties(&t))
+                .unwrap_or_default();
+            let label = udev
+                .get("ID_FS_LABEL_ENC")
+                .map(|l| unescape_hex(l))
+                .or_else(|| udev.get("ID_FS_LABEL").cloned())
+                .filter(|l| !l.is_empty());
+            let device = format!("/dev/{}", name);
+            Some(BlockDevice {
+                is_removable: sysfs_removable(&sys, &udev),
+                label,
+                fs_type: udev.get("ID_FS_TYPE").cloned().filter(|t| !t.is_empty()),
+                mount_point: mounts.get(&device).cloned(),
+                size_bytes: sectors * 512,
+                device,
+            })
+        })
+        .collect();
+    devices.sort_by(|a, b| a.device.cmp(&b.device));
+    devices
+}
+
+/// Without sysfs only mounted volumes are visible: `df`, with removable media
+/// recognised by where they are mounted.
+#[cfg(not(target_os = "linux"))]
+pub fn list_block_devices() -> Vec<BlockDevice> {
+    let mut partitions = parse_df_output().unwrap_or_default();
+    annotate_partitions(&mut partitions, &[]);
+    partitions
+        .into_iter()
+        .map(|p| BlockDevice {
+            device: p.device,
+            is_removable: p.is_removable,
+            label: p.label,
+            fs_type: Some(p.fs_type).filter(|t| t != "unknown"),
+            mount_point: Some(p.mount_point),
+            size_bytes: p.total_bytes,
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct DiskMountResult {
+    pub device: String,
+    /// Where the device is mounted now; None after an unmount
+    pub mount_point: Option<String>,
+    pub message: String,
+}
+
+fn normalize_device(device: &str) -> Result<String, BroxeenError> {
+    let device = device.trim();
+    if device.is_empty() {
+        return Err(BroxeenError::invalid_input("Nie podano urządzenia"));
+    }
+    Ok(if device.starts_with("/dev/") { device.to_string() } else { format!("/dev/{}", device) })
+}
+
+/// "Mounted /dev/sdb1 at /media/jan/KINGSTON." → mount point. Also matches
+/// udisks' AlreadyMounted error ("… is already mounted at `/media/jan/X'.").
+fn parse_mounted_at(text: &str) -> Option<String> {
+    let (_, rest) = text.rsplit_once(" at ")?;
+    let rest = rest.trim().trim_end_matches('.');
+    let path = rest.trim_start_matches('`').trim_end_matches('\'');
+    path.starts_with('/').then(|| path.to_string())
+}
+
+fn udisks_error(stderr: &str) -> BroxeenError {
+    let message = stderr.trim();
+    let has = |needles: &[&str]| needles.iter().any(|n| message.contains(n));
+    let code = if has(&["NotAuthorized", "Not authorized", "Permission denied"]) {
+        ErrorCode::PermissionDenied
+    } else if has(&["DeviceBusy", "target is busy"]) {
+        ErrorCode::Busy
+    } else if has(&["Error looking up object", "No such file"]) {
+        ErrorCode::NotFound
+    } else if has(&["not a mountable", "NotSupported", "no filesystem"]) {
+        ErrorCode::InvalidInput
+    } else {
+        ErrorCode::Internal
+    };
+    BroxeenError::new(code, format!("udisksctl: {}", message))
+}
+
+/// Run `udisksctl <args> --no-user-interaction`; Err carries stdout+stderr
+/// of a failed run so callers can recognise "already (un)mounted".
+fn udisksctl(args: &[&str]) -> Result<Result<String, String>, BroxeenError> {
+    if !cfg!(target_os = "linux") {
+        return Err(BroxeenError::unsupported("Montowanie dysków jest dostępne tylko na Linuksie (udisksctl)"));
+    }
+    let output = Command::new("udisksctl")
+        .args(args)
+        .arg("--no-user-interaction")
+        .output()
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                BroxeenError::tool_missing("udisksctl nie jest zainstalowany — zainstaluj pakiet udisks2")
+            } else {
+                BroxeenError::from(e).context("udisksctl")
+            }
+        })?;
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.status.success() {
+        Ok(Ok(stdout))
+    } else {
+        Ok(Err(format!("{}{}", stdout, String::from_utf8_lossy(&output.stderr)).trim().to_string()))
+    }
+}
+
+/// Mount `device` (`/dev/sdb1` or `sdb1`) through udisks, as the desktop
+/// does for a plugged-in stick: under `/media/<user>/<label>`, no root needed.
+#[tauri::command]
+pub async fn disk_mount(app: tauri::AppHandle, device: String) -> Result<DiskMountResult, BroxeenError> {
+    backend_info(format!("Command disk_mount invoked for device: {}", device));
+    let device = normalize_device(&device)?;
+    let dev = device.clone();
+    let run = tokio::task::spawn_blocking(move || udisksctl(&["mount", "-b", &dev]))
+        .await
+        .map_err(|e| format!("disk_mount task failed: {}", e))??;
+
+    let (mount_point, message) = match run {
+        Ok(stdout) => {
+            let mount_point = parse_mounted_at(&stdout);
+            let message = format!("Zamontowano {} w {}", device, mount_point.as_deref().unwrap_or("?"));
+            (mount_point, message)
+        }
+        Err(output) if output.contains("AlreadyMounted") => {
+            let mount_point = parse_mounted_at(&output);
+            let message = format!("{} był już zamontowany w {}", device, mount_point.as_deref().unwrap_or("?"));
+            (mount_point, message)
+        }
+        Err(output) => {
+            backend_warn(format!("disk_mount {} failed: {}", device, output));
+            return Err(udisks_error(&output));
+        }
+    };
+    backend_info(format!("disk_mount: {}", message));
+    crate::disk_watch::refresh(&app);
+    Ok(DiskMountResult { device, mount_point, message })
+}
+
+/// Unmount `device` through udisks. A device that was not mounted is not an error.
+#[tauri::command]
+pub async fn disk_unmount(app: tauri::AppHandle, device: String) -> Result<DiskMountResult, BroxeenError> {
+    backend_info(format!("Command disk_unmount invoked for device: {}", device));
+    let device = normalize_device(&device)?;
+    let dev = device.clone();
+    let run = tokio::task::spawn_blocking(move || udisksctl(&["unmount", "-b", &dev]))
+        .await
+        .map_err(|e| format!("disk_unmount task failed: {}", e))??;
+
+    let message = match run {
+        Ok(_) => format!("Odmontowano {} — można bezpiecznie odłączyć", device),
+        Err(output) if output.contains("NotMounted") => format!("{} nie był zamontowany", device),
+        Err(output) => {
+            backend_warn(format!("disk_unmount {} failed: {}", device, output));
+            return Err(udisks_error(&output));
+        }
+    };
+    backend_info(format!("disk_unmount: {}", message));
+    crate::disk_watch::refresh(&app);
+    Ok(DiskMountResult { device, mount_point: None, message })
+}
+
+// ─── Directory Usage Tree ────────────────────────────────────
+
+/// Bumped by every `disk_usage_tree` call and by `disk_usage_cancel`; a walk
+/// stops as soon as the counter no longer matches the value it started with.
+static DU_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+const DU_DEFAULT_TIMEOUT_SECS: u64 = 60;
+const DU_MAX_CHILDREN: usize = 50;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DiskUsageNode {
+    pub path: String,
+    pub name: String,
+    /// Apparent size of all files below this directory (whole subtree)
+    pub size_bytes: u64,
+    pub file_count: u64,
+    /// Largest subdirectories; listed for directories at depth 0 (the
+    /// root) through `max_depth` inclusive, empty below that
+    pub children: Vec<DiskUsageNode>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiskUsageTree {
+    pub root: DiskUsageNode,
+    pub duration_ms: u64,
+    /// Walk stopped early (timeout or cancelled) — sizes are lower bounds
+    pub incomplete: bool,
+    /// Directories that could not be read (permissions)
+    pub unreadable_dirs: u64,
+}
+
+struct DuWalk {
+    generation: u64,
+    deadline: Instant,
+    max_depth: usize,
+    min_size_bytes: u64,
+    root_dev: Option<u64>,
+    stopped: bool,
+    unreadable_dirs: u64,
+}
+
+impl DuWalk {
+    fn should_stop(&mut self) -> bool {
+        if !self.stopped
+            && (Instant::now() >= self.deadline || DU_GENERATION.load(Ordering::SeqCst) != self.generation)
+        {
+            self.stopped = true;
+        }
+        self.stopped
+    }
+
+    /// Aggregate `dir` recursively. Symlinks are never followed and
+    /// directories on another device (mount points) are skipped.
+    fn walk(&mut self, dir: &Path, depth: usize) -> DiskUsageNode {
+        let mut node = DiskUsageNode {
+            path: dir.to_string_lossy().to_string(),
+            name: dir
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| dir.to_string_lossy().to_string()),
+            size_bytes: 0,
+            file_count: 0,
+            children: Vec::new(),
+        };
+
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => {
+                self.unreadable_dirs += 1;
+                return node;
+            }
+        };
+
+        for entry in entries.flatten() {
+            if self.should_stop() {
+                break;
+            }
+            let Ok(meta) = entry.path().symlink_metadata() else { continue };
+            let file_type = meta.file_type();
+            if file_type.is_symlink() {
+                continue;
+            }
+            if file_type.is_dir() {
+                if self.root_dev.is_some() && device_id(&meta) != self.root_dev {
+                    continue;
+                }
+                let child = self.walk(&entry.path(), depth + 1);
+                node.size_bytes += child.size_bytes;
+                node.file_count += child.file_count;
+                // Inclusive: a directory at depth `max_depth` still lists its
+                // subdirectories, so `max_depth` 0 shows the root's children
+                if depth <= self.max_depth && child.size_bytes >= self.min_size_bytes {
+                    node.children.push(child);
+                }
+            } else {
+                node.size_bytes += meta.len();
+                node.file_count += 1;
+            }
+        }
+
+        node.children.sort_by_key(|c| std::cmp::Reverse(c.size_bytes));
+        node.children.truncate(DU_MAX_CHILDREN);
+        node
+    }
+}
+
+#[cfg(unix)]
+fn device_id(meta: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(meta.dev())
+}
+
+#[cfg(not(unix))]
+fn device_id(_meta: &fs::Metadata) -> Option<u64> {
+    None
+}
+
+fn build_usage_tree(
+    root: &Path,
+    max_depth: usize,
+    min_size_bytes: u64,
+    timeout: Duration,
+    generation: u64,
+) -> Result<DiskUsageTree, String> {
+    let started = Instant::now();
+    let root_meta = root
+        .symlink_metadata()
+        .map_err(|e| format!("Nie można odczytać {}: {}", root.display(), e))?;
+    if !root_meta.is_dir() {
+        return Err(format!("To nie jest katalog: {}", root.display()));
+    }
+
+    let mut walk = DuWalk {
+        generation,
+        deadline: started + timeout,
+        max_depth,
+        min_size_bytes,
+        root_dev: device_id(&root_meta),
+        stopped: false,
+        unreadable_dirs: 0,
+    };
+    let root_node = walk.walk(root, 0);
+
+    Ok(DiskUsageTree {
+        root: root_node,
+        duration_ms: started.elapsed().as_millis() as u64,
+        incomplete: walk.stopped,
+        unreadable_dirs: walk.unreadable_dirs,
+    })
+}
+
+/// du-style breakdown of `path`: sizes are aggregated over the whole subtree,
+/// the returned tree lists the subdirectories of every directory down to
+/// `max_depth` inclusive (default 3, the root is depth 0) that are at least
+/// `min_size_mb` (default 1) big, largest first. Starting a new walk or
+/// calling `disk_usage_cancel` stops the previous one; `timeout_secs`
+/// (default 60) bounds it otherwise.
+#[tauri::command]
+pub async fn disk_usage_tree(
+    path: String,
+    max_depth: Option<usize>,
+    min_size_mb: Option<u64>,
+    timeout_secs: Option<u64>,
+) -> Result<DiskUsageTree, String> {
+    backend_info(format!("Command disk_usage_tree invoked for path: {}", path));
+
+    let root = PathBuf::from(&path);
+    let max_depth = max_depth.unwrap_or(3);
+    let min_size_bytes = min_size_mb.unwrap_or(1).saturating_mul(1_048_576);
+    let timeout = Duration::from_secs(timeout_secs.unwrap_or(DU_DEFAULT_TIMEOUT_SECS).max(1));
+    let generation = DU_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    let tree = tokio::task::spawn_blocking(move || {
+        build_usage_tree(&root, max_depth, min_size_bytes, timeout, generation)
+    })
+    .await
+    .map_err(|e| format!("disk_usage_tree task failed: {}", e))??;
+
+    backend_info(format!(
+        "disk_usage_tree: {} → {}MB in {} files, {}ms{}",
+        path,
+        tree.root.size_bytes / 1_048_576,
+        tree.root.file_count,
+        tree.duration_ms,
+        if tree.incomplete { " (incomplete)" } else { "" }
+    ));
+    Ok(tree)
+}
+
+/// Stop the running `disk_usage_tree` walk; it returns a partial tree.
+#[tauri::command]
+pub fn disk_usage_cancel() {
+    backend_info("Command disk_usage_cancel invoked");
+    DU_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+// ─── SMART Health ────────────────────────────────────────────
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SmartStatus {
+    Ok,
+    Warning,
+    Failing,
+    /// Loop devices, network mounts, USB bridges without pass-through…
+    Unsupported,
+    /// SMART is there but nothing useful could be read (e.g. no permission)
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DiskSmart {
+    pub device: String,
+    pub status: SmartStatus,
+    /// "smartctl", "sysfs" or "none"
+    pub source: String,
+    pub model: Option<String>,
+    /// Overall self-assessment: PASSED / OK → true, FAILED → false
+    pub health_passed: Option<bool>,
+    pub temperature_c: Option<i64>,
+    pub power_on_hours: Option<u64>,
+    pub reallocated_sectors: Option<u64>,
+    pub pending_sectors: Option<u64>,
+    /// Why the status is not `ok`, in plain words
+    pub reasons: Vec<String>,
+}
+
+const SMART_TEMP_WARNING_C: i64 = 60;
+
+impl DiskSmart {
+    fn empty(device: &str, source: &str) -> Self {
+        DiskSmart {
+            device: device.to_string(),
+            status: SmartStatus::Unknown,
+            source: source.to_string(),
+            model: None,
+            health_passed: None,
+            temperature_c: None,
+            power_on_hours: None,
+            reallocated_sectors: None,
+            pending_sectors: None,
+            reasons: Vec::new(),
+        }
+    }
+
+    fn unsupported(device: &str, reason: impl Into<String>) -> Self {
+        let mut smart = DiskSmart::empty(device, "none");
+        smart.status = SmartStatus::Unsupported;
+        smart.reasons.push(reason.into());
+        smart
+    }
+
+    /// ok / warning / failing from whatever was read; keeps earlier reasons.
+    fn classify(&mut self) {
+        let mut status = SmartStatus::Ok;
+        if self.health_passed == Some(false) {
+            status = SmartStatus::Failing;
+            self.reasons.push("SMART self-assessment FAILED".to_string());
+        }
+        for (count, what) in [
+            (self.reallocated_sectors, "reallocated sectors"),
+            (self.pending_sectors, "pending sectors"),
+        ] {
+            if let Some(n) = count.filter(|n| *n > 0) {
+                self.reasons.push(format!("{} {}", n, what));
+            }
+        }
+        if let Some(t) = self.temperature_c.filter(|t| *t >= SMART_TEMP_WARNING_C) {
+            self.reasons.push(format!("temperature {}°C", t));
+        }
+        // Any reason (including parser notes such as an NVMe critical warning) warns
+        if status == SmartStatus::Ok && !self.reasons.is_empty() {
+            status = SmartStatus::Warning;
+        }
+        let has_data = self.health_passed.is_some()
+            || self.temperature_c.is_some()
+            || self.power_on_hours.is_some()
+            || self.reallocated_sectors.is_some();
+        self.status = if has_data { status } else { SmartStatus::Unknown };
+    }
+}
+
+/// Digits at the start of `s`, ignoring thousands separators
+/// ("1,234" / "1.234").
+fn leading_number(s: &str) -> Option<u64> {
+    let digits: String = s
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || matches!(*c, ',' | '.'))
+        .filter(char::is_ascii_digit)
+        .collect();
+    digits.parse().ok()
+}
+
+/// Partition → whole disk (`/dev/sda1` → `/dev/sda`, `/dev/nvme0n1p2` → `/dev/nvme0n1`).
+fn base_disk(device: &str) -> String {
+    let name = device.trim_start_matches("/dev/");
+    let trimmed = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    let partitioned = (name.starts_with("nvme") || name.starts_with("mmcblk")) && trimmed.ends_with('p');
+    let base = if partitioned {
+        &trimmed[..trimmed.len() - 1]
+    } else if ["sd", "hd", "vd", "xvd"].iter().any(|p| name.starts_with(p)) {
+        trimmed
+    } else {
+        name
+    };
+    format!("/dev/{}", base)
+}
+
+/// Devices that never have SMART: reported as unsupported without probing.
+fn smart_unsupported_reason(device: &str) -> Option<String> {
+    if !device.starts_with("/dev/") {
+        return Some(format!("{} is not a local block device (network or virtual mount)", device));
+    }
+    let name = device.trim_start_matches("/dev/");
+    ["loop", "ram", "zram", "nbd", "dm-", "md", "sr", "mapper/"]
+        .iter()
+        .find(|prefix| name.starts_with(*prefix))
+        .map(|prefix| format!("{} devices do not report SMART", prefix.trim_end_matches(['-', '/'])))
+}
+
+/// Parse `smartctl -i -H -A` text output. Handles the ATA attribute table,
+/// the NVMe health log and SCSI/SAS key-value output of smartctl 5.x–7.x.
+pub fn parse_smartctl_output(device: &str, output: &str) -> DiskSmart {
+    let lower = output.to_lowercase();
+    if lower.contains("smart support is: unavailable")
+        || lower.contains("device does not support smart")
+        || lower.contains("unknown usb bridge")
+        || lower.contains("unable to detect device type")
+    {
+        return DiskSmart::unsupported(device, "device does not expose SMART data");
+    }
+
+    let mut smart = DiskSmart::empty(device, "smartctl");
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if let Some((key, value)) = trimmed.split_once(':') {
+            let value = value.trim();
+            match key.trim().to_lowercase().as_str() {
+                "device model" | "model number" | "product" if smart.model.is_none() => {
+                    smart.model = Some(value.to_string());
+                }
+                "smart overall-health self-assessment test result" | "smart health status" => {
+                    let v = value.to_uppercase();
+                    smart.health_passed = Some(v.starts_with("PASSED") || v.starts_with("OK"));
+                }
+                "temperature" | "current drive temperature" => {
+                    smart.temperature_c = leading_number(value).map(|t| t as i64);
+                }
+                "power on hours" | "accumulated power on time, hours" => {
+                    smart.power_on_hours = leading_number(value);
+                }
+                "elements in grown defect list" => smart.reallocated_sectors = leading_number(value),
+                "critical warning" => {
+                    let flags = u64::from_str_radix(value.trim_start_matches("0x"), 16).unwrap_or(0);
+                    if flags != 0 {
+                        smart.reasons.push(format!("NVMe critical warning {}", value));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // ATA attribute row: ID NAME FLAG VALUE WORST THRESH TYPE UPDATED WHEN_FAILED RAW...
+        let cols: Vec<&str> = trimmed.split_whitespace().collect();
+        if cols.len() < 10 || !cols[2].starts_with("0x") {
+            continue;
+        }
+        let Ok(id) = cols[0].parse::<u32>() else { continue };
+        let raw = leading_number(cols[9]);
+        match id {
+            5 => smart.reallocated_sectors = raw,
+            9 => smart.power_on_hours = raw,
+            194 => smart.temperature_c = raw.map(|v| v as i64),
+            190 if smart.temperature_c.is_none() => smart.temperature_c = raw.map(|v| v as i64),
+            197 => smart.pending_sectors = raw,
+            _ => {}
+        }
+        if cols[8] != "-" {
+            smart.reasons.push(format!("{} failed ({})", cols[1], cols[8]));
+        }
+    }
+
+    smart.classify();
+    smart
+}
+
+/// Linux fallback without smartctl: drive temperature from hwmon (drivetemp, nvme).
+#[cfg(target_os = "linux")]
+fn read_sysfs_smart(device: &str) -> DiskSmart {
+    let name = device.trim_start_matches("/dev/");
+    let block = PathBuf::from("/sys/block").join(name);
+    if !block.exists() {
+        return DiskSmart::unsupported(device, format!("{} not found in /sys/block", name));
+    }
+    let mut smart = DiskSmart::empty(device, "sysfs");
+    smart.model = fs::read_to_string(block.join("device/model")).ok().map(|m| m.trim().to_string());
+
+    let hwmon_dirs = [block.join("device/hwmon"), block.join("device/device/hwmon")];
+    for dir in hwmon_dirs.iter().filter_map(|d| fs::read_dir(d).ok()) {
+        for entry in dir.flatten() {
+            if let Some(millis) = fs::read_to_string(entry.path().join("temp1_input"))
+                .ok()
+                .and_then(|t| t.trim().parse::<i64>().ok())
+            {
+                smart.temperature_c = Some(millis / 1000);
+            }
+        }
+    }
+    smart.classify();
+    if smart.status == SmartStatus::Unknown {
+        smart.reasons.push("smartctl not installed and no hwmon sensor; install smartmontools".to_string());
+    }
+    smart
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_sysfs_smart(device: &str) -> DiskSmart {
+    let mut smart = DiskSmart::empty(device, "none");
+    smart.reasons.push("smartctl not installed; install smartmontools".to_string());
+    smart
+}
+
+fn read_smart(device: &str) -> DiskSmart {
+    let disk = base_disk(device);
+    if let Some(reason) = smart_unsupported_reason(&disk) {
+        return DiskSmart::unsupported(device, reason);
+    }
+    // smartctl's exit status is a bit mask that is non-zero even for readable
+    // drives with logged errors, so the output is parsed regardless
+    match Command::new("smartctl").args(["-i", "-H", "-A", &disk]).output() {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let mut smart = parse_smartctl_output(&disk, &stdout);
+            if smart.status == SmartStatus::Unknown && stdout.to_lowercase().contains("permission denied") {
+                smart.reasons.push("smartctl needs root (permission denied)".to_string());
+            }
+            smart
+        }
+        Err(_) => read_sysfs_smart(&disk),
+    }
+}
+
+/// SMART health of `device` (disk or partition, e.g. `/dev/sda1`): overall
+/// self-assessment, temperature, power-on hours and reallocated / pending
+/// sectors, classified as ok / warning / failing. Devices without SMART come
+/// back as `unsupported` instead of an error.
+#[tauri::command]
+pub async fn get_disk_smart(device: String) -> Result<DiskSmart, String> {
+    backend_info(format!("Command get_disk_smart invoked for device: {}", device));
+    let device = device.trim().to_string();
+    if device.is_empty() {
+        return Err("Nie podano urządzenia".to_string());
+    }
+
+    let smart = tokio::task::spawn_blocking(move || read_smart(&device))
+        .await
+        .map_err(|e| format!("get_disk_smart task failed: {}", e))?;
+
+    backend_info(format!(
+        "get_disk_smart: {} → {:?} via {} (temp={:?}, realloc={:?}, pending={:?})",
+        smart.device,
+        smart.status,
+        smart.source,
+        smart.temperature_c,
+        smart.reallocated_sectors,
+        smart.pending_sectors
+    ));
+    Ok(smart)
+}
+
 fn get_hostname() -> String {
     Command::new("hostname")
         .output()
@@ -198,6 +1011,206 @@ mod tests {
         assert_eq!(p.use_percent, 47.0);
     }
 
+    #[test]
+    fn test_usage_tree_nested_dirs() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("media/photos")).unwrap();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("media/video.bin"), vec![0u8; 3000]).unwrap();
+        fs::write(root.join("media/photos/a.jpg"), vec![0u8; 2000]).unwrap();
+        fs::write(root.join("media/photos/b.jpg"), vec![0u8; 2000]).unwrap();
+        fs::write(root.join("docs/notes.txt"), vec![0u8; 100]).unwrap();
+        fs::write(root.join("top.txt"), vec![0u8; 10]).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(root.join("media"), root.join("docs/link")).unwrap();
+
+        let generation = DU_GENERATION.load(Ordering::SeqCst);
+        let tree = build_usage_tree(root, 2, 0, Duration::from_secs(10), generation).unwrap();
+        assert!(!tree.incomplete);
+        assert_eq!(tree.root.size_bytes, 7110);
+        assert_eq!(tree.root.file_count, 5);
+
+        let names: Vec<&str> = tree.root.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["media", "docs"]);
+        let media = &tree.root.children[0];
+        assert_eq!((media.size_bytes, media.file_count), (7000, 3));
+        assert_eq!(media.children[0].name, "photos");
+        assert_eq!(tree.root.children[1].size_bytes, 100);
+
+        // Depth 1 still lists media's subdirectories (inclusive limit)
+        let tree = build_usage_tree(root, 1, 0, Duration::from_secs(10), generation).unwrap();
+        assert_eq!(tree.root.children[0].children[0].name, "photos");
+        assert!(tree.root.children[0].children[0].children.is_empty());
+
+        // Depth limit prunes the tree but keeps totals; min size drops small dirs
+        let tree = build_usage_tree(root, 0, 1000, Duration::from_secs(10), generation).unwrap();
+        assert_eq!(tree.root.size_bytes, 7110);
+        assert_eq!(tree.root.children.len(), 1);
+        assert!(tree.root.children[0].children.is_empty());
+    }
+
+    #[test]
+    fn test_usage_tree_stops_on_stale_generation() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "x").unwrap();
+        let stale = DU_GENERATION.load(Ordering::SeqCst).wrapping_sub(1);
+        let tree = build_usage_tree(dir.path(), 3, 0, Duration::from_secs(10), stale).unwrap();
+        assert!(tree.incomplete);
+        assert_eq!(tree.root.file_count, 0);
+    }
+
+    const SMARTCTL_ATA: &str = "smartctl 6.6 2016-05-31 r4324 [x86_64-linux-4.19.0] (local build)
+=== START OF INFORMATION SECTION ===
+Device Model:     WDC WD40EFRX-68N32N0
+Serial Number:    WD-WCC7K1234567
+SMART support is: Available - device has SMART capability.
+SMART support is: Enabled
+
+=== START OF READ SMART DATA SECTION ===
+SMART overall-health self-assessment test result: PASSED
+
+ID# ATTRIBUTE_NAME          FLAG     VALUE WORST THRESH TYPE      UPDATED  WHEN_FAILED RAW_VALUE
+  1 Raw_Read_Error_Rate     0x002f   200   200   051    Pre-fail  Always       -       0
+  5 Reallocated_Sector_Ct   0x0033   199   199   140    Pre-fail  Always       -       8
+  9 Power_On_Hours          0x0032   062   062   000    Old_age   Always       -       28011
+194 Temperature_Celsius     0x0022   118   104   000    Old_age   Always       -       34 (Min/Max 18/46)
+197 Current_Pending_Sector  0x0032   200   200   000    Old_age   Always       -       0
+";
+
+    const SMARTCTL_NVME: &str = "smartctl 7.2 2020-12-30 r5155 [x86_64-linux-6.1.0] (local build)
+=== START OF INFORMATION SECTION ===
+Model Number:                       Samsung SSD 970 EVO Plus 1TB
+
+=== START OF SMART DATA SECTION ===
+SMART overall-health self-assessment test result: PASSED
+
+SMART/Health Information (NVMe Log 0x02)
+Critical Warning:                   0x00
+Temperature:                        41 Celsius
+Available Spare:                    100%
+Power On Hours:                     1,234
+Media and Data Integrity Errors:    0
+";
+
+    const SMARTCTL_SCSI_FAILING: &str = "smartctl 5.41 2011-06-09 r3365 [x86_64-linux-2.6.32] (local build)
+Vendor:               SEAGATE
+Product:              ST3300657SS
+SMART Health Status: FAILURE PREDICTION THRESHOLD EXCEEDED [asc=5d, ascq=10]
+Current Drive Temperature:     63 C
+Elements in grown defect list: 112
+";
+
+    #[test]
+    fn test_parse_smartctl_fixtures() {
+        let ata = parse_smartctl_output("/dev/sda", SMARTCTL_ATA);
+        assert_eq!(ata.model.as_deref(), Some("WDC WD40EFRX-68N32N0"));
+        assert_eq!(ata.health_passed, Some(true));
+        assert_eq!(ata.power_on_hours, Some(28011));
+        assert_eq!(ata.temperature_c, Some(34));
+        assert_eq!((ata.reallocated_sectors, ata.pending_sectors), (Some(8), Some(0)));
+        assert_eq!(ata.status, SmartStatus::Warning);
+        assert_eq!(ata.reasons, vec!["8 reallocated sectors".to_string()]);
+
+        let nvme = parse_smartctl_output("/dev/nvme0n1", SMARTCTL_NVME);
+        assert_eq!(nvme.model.as_deref(), Some("Samsung SSD 970 EVO Plus 1TB"));
+        assert_eq!((nvme.temperature_c, nvme.power_on_hours), (Some(41), Some(1234)));
+        assert_eq!(nvme.status, SmartStatus::Ok);
+        assert!(nvme.reasons.is_empty());
+
+        let scsi = parse_smartctl_output("/dev/sdb", SMARTCTL_SCSI_FAILING);
+        assert_eq!(scsi.model.as_deref(), Some("ST3300657SS"));
+        assert_eq!(scsi.health_passed, Some(false));
+        assert_eq!(scsi.reallocated_sectors, Some(112));
+        assert_eq!(scsi.status, SmartStatus::Failing);
+        assert_eq!(scsi.reasons.len(), 3);
+
+        let usb = parse_smartctl_output(
+            "/dev/sdc",
+            "/dev/sdc: Unknown USB bridge [0x152d:0x0578 (0x209)]\nPlease specify device type with the -d option.",
+        );
+        assert_eq!(usb.status, SmartStatus::Unsupported);
+        let denied = parse_smartctl_output("/dev/sdd", "Smartctl open device: /dev/sdd failed: Permission denied");
+        assert_eq!(denied.status, SmartStatus::Unknown);
+    }
+
+    #[test]
+    fn test_smart_device_mapping() {
+        assert_eq!(base_disk("/dev/sda1"), "/dev/sda");
+        assert_eq!(base_disk("/dev/nvme0n1p2"), "/dev/nvme0n1");
+        assert_eq!(base_disk("/dev/nvme0n1"), "/dev/nvme0n1");
+        assert_eq!(base_disk("/dev/mmcblk0p1"), "/dev/mmcblk0");
+        assert!(smart_unsupported_reason("/dev/loop3").is_some());
+        assert!(smart_unsupported_reason("nas:/export/media").is_some());
+        assert!(smart_unsupported_reason("/dev/sda").is_none());
+        assert_eq!(read_smart("/dev/loop0").status, SmartStatus::Unsupported);
+    }
+
+    #[test]
+    fn test_removable_media_metadata() {
+        let mounts = parse_proc_mounts(
+            "/dev/sda2 / ext4 rw,relatime 0 0\n\
+             proc /proc proc rw 0 0\n\
+             /dev/sdb1 /media/jan/MY\\040STICK vfat rw,nosuid 0 0\n\
+             /dev/sda2 /var/lib/docker ext4 rw 0 0\n",
+        );
+        assert_eq!(mounts.len(), 2);
+        assert_eq!(mounts["/dev/sda2"], "/");
+        assert_eq!(mounts["/dev/sdb1"], "/media/jan/MY STICK");
+
+        let udev = parse_udev_properties("S:disk/by-label/Zdjecia\nE:ID_FS_TYPE=vfat\nE:ID_FS_LABEL_ENC=Zdj\\xc4\\x99cia\\x202024\nE:ID_BUS=usb\n");
+        assert_eq!(udev["ID_FS_TYPE"], "vfat");
+        assert_eq!(unescape_hex(&udev["ID_FS_LABEL_ENC"]), "Zdjęcia 2024");
+        assert_eq!(unescape_hex("a\\xZZb"), "a\\xZZb");
+
+        assert_eq!(removable_mount_label("/run/media/jan/BACKUP").as_deref(), Some("BACKUP"));
+        assert_eq!(removable_mount_label("/Volumes/Untitled").as_deref(), Some("Untitled"));
+        assert_eq!(removable_mount_label("/media/data"), None);
+
+        let mut partitions = vec![
+            parse_df_line("/dev/sdb1 16000000000 1000 15999999000 1% /media/jan/STICK").unwrap(),
+            parse_df_line("/dev/disk4s1 32000000000 1000 31999999000 1% /Volumes/CAMERA").unwrap(),
+            parse_df_line("/dev/sda2 500000000000 1000 499999999000 1% /").unwrap(),
+        ];
+        let stick = BlockDevice {
+            device: "/dev/sdb1".into(),
+            is_removable: true,
+            label: Some("MY STICK".into()),
+            fs_type: Some("vfat".into()),
+            mount_point: Some("/media/jan/STICK".into()),
+            size_bytes: 16_000_000_000,
+        };
+        annotate_partitions(&mut partitions, &[stick]);
+        assert_eq!((partitions[0].is_removable, partitions[0].fs_type.as_str()), (true, "vfat"));
+        assert_eq!(partitions[0].label.as_deref(), Some("MY STICK"));
+        assert_eq!((partitions[1].is_removable, partitions[1].label.as_deref()), (true, Some("CAMERA")));
+        assert!(!partitions[2].is_removable && partitions[2].label.is_none());
+    }
+
+    #[test]
+    fn test_udisksctl_output_and_errors() {
+        assert_eq!(
+            parse_mounted_at("Mounted /dev/sdb1 at /media/jan/KINGSTON.").as_deref(),
+            Some("/media/jan/KINGSTON")
+        );
+        assert_eq!(parse_mounted_at("Mounted /dev/sdb1 at /run/media/jan/a b").as_deref(), Some("/run/media/jan/a b"));
+        let already = "Error mounting /dev/sdb1: GDBus.Error:org.freedesktop.UDisks2.Error.AlreadyMounted: \
+                       Device /dev/sdb1 is already mounted at `/media/jan/KINGSTON'.";
+        assert_eq!(parse_mounted_at(already).as_deref(), Some("/media/jan/KINGSTON"));
+
+        let code = |stderr: &str| udisks_error(stderr).code;
+        assert_eq!(
+            code("Error mounting /dev/sdb1: GDBus.Error:org.freedesktop.UDisks2.Error.NotAuthorizedCanObtain: Not authorized to perform operation"),
+            ErrorCode::PermissionDenied
+        );
+        assert_eq!(code("Error unmounting /dev/sdb1: GDBus.Error:org.freedesktop.UDisks2.Error.DeviceBusy: target is busy"), ErrorCode::Busy);
+        assert_eq!(code("Error looking up object for device /dev/sdz1"), ErrorCode::NotFound);
+        assert!(udisks_error("boom\n").message.starts_with("udisksctl: boom"));
+
+        assert_eq!(normalize_device(" sdb1 ").unwrap(), "/dev/sdb1");
+        assert_eq!(normalize_device("").unwrap_err().code, ErrorCode::InvalidInput);
+    }
+
     #[test]
     fn test_get_hostname() {
         let hostname = get_hostname();
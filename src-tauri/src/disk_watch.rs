@@ -0,0 +1,184 @@
+//! disk_watch.rs — Background watcher for block devices and mounts.
+//!
+//! Keeps a snapshot of `disk_info::list_block_devices` and emits
+//! `broxeen:disk_changed` when a device appears or disappears (USB stick
+//! plugged in or pulled out) or gets mounted / unmounted. On Linux changes are
+//! pushed by `udevadm monitor`; a periodic re-check covers other platforms, a
+//! missing `udevadm`, and mounts made outside udisks (which send no uevent).
+
+use serde::Serialize;
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::sync::RwLock;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::disk_info::{list_block_devices, BlockDevice};
+use crate::logging::{backend_info, backend_warn};
+
+const DISK_CHANGED_EVENT: &str = "broxeen:disk_changed";
+/// Re-check interval without push notifications
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Re-check when `udevadm monitor` is running; catches plain `mount` calls
+const MONITOR_RECHECK_INTERVAL: Duration = Duration::from_secs(15);
+/// A plugged-in stick sends a burst (disk, partitions, udev rules); the
+/// automounter needs a moment more
+const DEBOUNCE: Duration = Duration::from_millis(1500);
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DiskChangedEvent {
+    /// New devices, with their mount point if already mounted
+    pub added: Vec<BlockDevice>,
+    pub removed: Vec<BlockDevice>,
+    /// Known devices that got mounted (or moved to another mount point)
+    pub mounted: Vec<BlockDevice>,
+    /// Known devices that got unmounted; `mount_point` is the old one
+    pub unmounted: Vec<BlockDevice>,
+    pub timestamp: i64,
+}
+
+lazy_static::lazy_static! {
+    static ref SNAPSHOT: RwLock<Vec<BlockDevice>> = RwLock::new(Vec::new());
+}
+
+/// None when no device came, went, or changed its mount point.
+fn diff_devices(old: &[BlockDevice], new: &[BlockDevice]) -> Option<DiskChangedEvent> {
+    let find = |list: &[BlockDevice], device: &str| list.iter().find(|d| d.device == device).cloned();
+    let added: Vec<BlockDevice> = new.iter().filter(|d| find(old, &d.device).is_none()).cloned().collect();
+    let removed: Vec<BlockDevice> = old.iter().filter(|d| find(new, &d.device).is_none()).cloned().collect();
+    let mut mounted = Vec::new();
+    let mut unmounted = Vec::new();
+    for d in new {
+        let Some(before) = find(old, &d.device) else { continue };
+        match (&before.mount_point, &d.mount_point) {
+            (old_mp, Some(new_mp)) if old_mp.as_ref() != Some(new_mp) => mounted.push(d.clone()),
+            (Some(_), None) => unmounted.push(before),
+            _ => {}
+        }
+    }
+    if added.is_empty() && removed.is_empty() && mounted.is_empty() && unmounted.is_empty() {
+        return None;
+    }
+    Some(DiskChangedEvent { added, removed, mounted, unmounted, timestamp: chrono::Utc::now().timestamp_millis() })
+}
+
+/// Spawn `udevadm monitor` for block devices and forward a tick per line.
+/// Returns false when udev monitoring is unavailable.
+fn spawn_udev_monitor(tx: mpsc::Sender<()>) -> bool {
+    if !cfg!(target_os = "linux") {
+        return false;
+    }
+    let child = Command::new("udevadm")
+        .args(["monitor", "--udev", "--subsystem-match=block"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    let Ok(mut child) = child else { return false };
+    let Some(stdout) = child.stdout.take() else { return false };
+
+    let pid = child.id();
+    std::thread::spawn(move || {
+        let _pid = crate::shutdown::track_child(pid, "udevadm", "disk_watch");
+        for line in BufReader::new(stdout).lines() {
+            if line.is_err() || tx.send(()).is_err() {
+                break;
+            }
+        }
+        let _ = child.kill();
+        let _ = child.wait();
+        backend_warn("disk_watch: udevadm monitor exited, falling back to polling");
+    });
+    true
+}
+
+/// Re-read the devices and emit `broxeen:disk_changed` if anything changed.
+/// Also called right after `disk_mount` / `disk_unmount`.
+pub fn refresh(app: &AppHandle) {
+    let devices = list_block_devices();
+    let event = {
+        let Ok(mut snapshot) = SNAPSHOT.write() else { return };
+        let event = diff_devices(&snapshot, &devices);
+        *snapshot = devices;
+        event
+    };
+    let Some(event) = event else { return };
+    let names = |list: &[BlockDevice]| list.iter().map(|d| d.device.as_str()).collect::<Vec<_>>().join(",");
+    backend_info(format!(
+        "disk_watch: +[{}] -[{}] mounted [{}] unmounted [{}]",
+        names(&event.added),
+        names(&event.removed),
+        names(&event.mounted),
+        names(&event.unmounted)
+    ));
+    if let Err(e) = app.emit(DISK_CHANGED_EVENT, event) {
+        backend_warn(format!("disk_watch: emit failed: {}", e));
+    }
+}
+
+/// Take the initial snapshot and start watching (called once at launch).
+pub fn start(app: AppHandle) {
+    let (tx, rx) = mpsc::channel::<()>();
+    let udev = spawn_udev_monitor(tx);
+    if let Ok(mut snapshot) = SNAPSHOT.write() {
+        *snapshot = list_block_devices();
+    }
+    backend_info(format!("disk_watch started ({})", if udev { "udev" } else { "polling" }));
+
+    std::thread::spawn(move || {
+        let mut interval = if udev { MONITOR_RECHECK_INTERVAL } else { POLL_INTERVAL };
+        loop {
+            match rx.recv_timeout(interval) {
+                Ok(()) => {
+                    std::thread::sleep(DEBOUNCE);
+                    while rx.try_recv().is_ok() {}
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    interval = POLL_INTERVAL;
+                    std::thread::sleep(interval);
+                }
+            }
+            refresh(&app);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(name: &str, mount_point: Option<&str>) -> BlockDevice {
+        BlockDevice {
+            device: format!("/dev/{}", name),
+            is_removable: name.starts_with("sdb"),
+            label: None,
+            fs_type: Some("vfat".into()),
+            mount_point: mount_point.map(str::to_string),
+            size_bytes: 16_000_000_000,
+        }
+    }
+
+    #[test]
+    fn test_usb_stick_plug_mount_unmount_pull() {
+        let system = vec![device("sda", None), device("sda1", Some("/"))];
+        let plugged = [system.clone(), vec![device("sdb", None), device("sdb1", None)]].concat();
+        let event = diff_devices(&system, &plugged).unwrap();
+        let added: Vec<&str> = event.added.iter().map(|d| d.device.as_str()).collect();
+        assert_eq!(added, vec!["/dev/sdb", "/dev/sdb1"]);
+        assert!(event.removed.is_empty() && event.mounted.is_empty());
+
+        let mut automounted = plugged.clone();
+        automounted[3].mount_point = Some("/media/jan/KINGSTON".into());
+        let event = diff_devices(&plugged, &automounted).unwrap();
+        assert_eq!(event.mounted, vec![automounted[3].clone()]);
+        assert!(event.added.is_empty());
+
+        let event = diff_devices(&automounted, &plugged).unwrap();
+        assert_eq!(event.unmounted[0].mount_point.as_deref(), Some("/media/jan/KINGSTON"));
+
+        let event = diff_devices(&plugged, &system).unwrap();
+        assert_eq!(event.removed.len(), 2);
+        assert!(diff_devices(&system, &system.clone()).is_none());
+    }
+}
@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+use std::collections::HashMap;
+use std::process::{Child, Command};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DockerContainer {
@@ -97,10 +100,15 @@ pub async fn docker_list_containers(all: bool) -> Result<Vec<DockerContainer>, S
         return Err("Docker ps command failed".to_string());
     }
 
-    let containers_str = String::from_utf8_lossy(&output.stdout);
+    parse_containers(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse `docker ps --format '{{json .}}'` output (one object per line);
+/// shared with the SSH variant in `remote_machine`.
+pub(crate) fn parse_containers(stdout: &str) -> Result<Vec<DockerContainer>, String> {
     let mut containers = Vec::new();
 
-    for line in containers_str.lines() {
+    for line in stdout.lines() {
         if line.trim().is_empty() {
             continue;
         }
@@ -227,9 +235,15 @@ pub async fn docker_list_networks() -> Result<Vec<DockerNetwork>, String> {
 }
 
 #[tauri::command]
-pub async fn docker_start_container(container_id: String) -> Result<String, String> {
+pub async fn docker_start_container(container_id: String, initiator: Option<String>) -> Result<String, String> {
+    let params = serde_json::json!({ "container_id": container_id });
+    let result = docker_start(&container_id);
+    crate::audit::audited("docker_start_container", initiator.as_deref(), params, result)
+}
+
+fn docker_start(container_id: &str) -> Result<String, String> {
     let output = Command::new("docker")
-        .args(&["start", &container_id])
+        .args(["start", container_id])
         .output()
         .map_err(|e| format!("Failed to start container: {}", e))?;
 
@@ -241,9 +255,15 @@ pub async fn docker_start_container(container_id: String) -> Result<String, Stri
 }
 
 #[tauri::command]
-pub async fn docker_stop_container(container_id: String) -> Result<String, String> {
+pub async fn docker_stop_container(container_id: String, initiator: Option<String>) -> Result<String, String> {
+    let params = serde_json::json!({ "container_id": container_id });
+    let result = docker_stop(&container_id);
+    crate::audit::audited("docker_stop_container", initiator.as_deref(), params, result)
+}
+
+fn docker_stop(container_id: &str) -> Result<String, String> {
     let output = Command::new("docker")
-        .args(&["stop", &container_id])
+        .args(["stop", container_id])
         .output()
         .map_err(|e| format!("Failed to stop container: {}", e))?;
 
@@ -255,9 +275,15 @@ pub async fn docker_stop_container(container_id: String) -> Result<String, Strin
 }
 
 #[tauri::command]
-pub async fn docker_restart_container(container_id: String) -> Result<String, String> {
+pub async fn docker_restart_container(container_id: String, initiator: Option<String>) -> Result<String, String> {
+    let params = serde_json::json!({ "container_id": container_id });
+    let result = docker_restart(&container_id);
+    crate::audit::audited("docker_restart_container", initiator.as_deref(), params, result)
+}
+
+fn docker_restart(container_id: &str) -> Result<String, String> {
     let output = Command::new("docker")
-        .args(&["restart", &container_id])
+        .args(["restart", container_id])
         .output()
         .map_err(|e| format!("Failed to restart container: {}", e))?;
 
@@ -269,12 +295,22 @@ pub async fn docker_restart_container(container_id: String) -> Result<String, St
 }
 
 #[tauri::command]
-pub async fn docker_remove_container(container_id: String, force: bool) -> Result<String, String> {
+pub async fn docker_remove_container(
+    container_id: String,
+    force: bool,
+    initiator: Option<String>,
+) -> Result<String, String> {
+    let params = serde_json::json!({ "container_id": container_id, "force": force });
+    let result = docker_remove(&container_id, force);
+    crate::audit::audited("docker_remove_container", initiator.as_deref(), params, result)
+}
+
+fn docker_remove(container_id: &str, force: bool) -> Result<String, String> {
     let mut args = vec!["rm"];
     if force {
         args.push("-f");
     }
-    args.push(&container_id);
+    args.push(container_id);
 
     let output = Command::new("docker")
         .args(&args)
@@ -288,23 +324,691 @@ pub async fn docker_remove_container(container_id: String, force: bool) -> Resul
     Ok(format!("Container {} removed", container_id))
 }
 
+// ── Compose stacks ───────────────────────────────────
+
+const COMPOSE_PROJECT: &str = "com.docker.compose.project";
+const COMPOSE_SERVICE: &str = "com.docker.compose.service";
+const COMPOSE_DEPENDS_ON: &str = "com.docker.compose.depends_on";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DockerStackService {
+    pub name: String,
+    /// Container names (several with `scale`)
+    pub containers: Vec<String>,
+    pub running: u32,
+    /// Services this one depends on, from the compose labels
+    pub depends_on: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DockerStack {
+    pub project: String,
+    /// Services in start order (dependencies first)
+    pub services: Vec<DockerStackService>,
+    /// "running", "partial" or "stopped"
+    pub state: String,
+    pub running: u32,
+    pub total: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DockerStackOutcome {
+    pub service: String,
+    pub container: String,
+    pub action: String,
+    pub ok: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DockerStackReport {
+    pub project: String,
+    pub outcomes: Vec<DockerStackOutcome>,
+    pub failed: u32,
+}
+
+/// One compose-managed container from `docker ps`.
+#[derive(Debug, Clone)]
+struct ComposeContainer {
+    name: String,
+    project: String,
+    service: String,
+    running: bool,
+    depends_on: Vec<String>,
+}
+
+/// Split the `Labels` column of `docker ps` ("k=v,k=v"). Values may contain
+/// commas (compose's depends_on), so a part without `=` continues the previous value.
+fn parse_labels(labels: &str) -> HashMap<String, String> {
+    let mut out: HashMap<String, String> = HashMap::new();
+    let mut last: Option<String> = None;
+    for part in labels.split(',') {
+        match part.split_once('=') {
+            Some((key, value)) => {
+                out.insert(key.to_string(), value.to_string());
+                last = Some(key.to_string());
+            }
+            None => {
+                if let Some(value) = last.as_ref().and_then(|key| out.get_mut(key)) {
+                    value.push(',');
+                    value.push_str(part);
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Service names from a depends_on label: "db:service_started:false,redis:service_healthy:true".
+fn parse_depends_on(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .filter_map(|dep| dep.split(':').next())
+        .map(str::trim)
+        .filter(|dep| !dep.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn parse_compose_container(json: &serde_json::Value) -> Option<ComposeContainer> {
+    let labels = parse_labels(json["Labels"].as_str().unwrap_or(""));
+    Some(ComposeContainer {
+        name: json["Names"].as_str().unwrap_or("").to_string(),
+        project: labels.get(COMPOSE_PROJECT)?.clone(),
+        service: labels.get(COMPOSE_SERVICE).cloned().unwrap_or_default(),
+        running: json["State"].as_str() == Some("running"),
+        depends_on: labels.get(COMPOSE_DEPENDS_ON).map(|v| parse_depends_on(v)).unwrap_or_default(),
+    })
+}
+
+fn list_compose_containers() -> Result<Vec<ComposeContainer>, String> {
+    let output = Command::new("docker")
+        .args(["ps", "-a", "--filter", &format!("label={}", COMPOSE_PROJECT), "--format", "{{json .}}"])
+        .output()
+        .map_err(|e| format!("Failed to list containers: {}", e))?;
+
+    if !output.status.success() {
+        return Err("Docker ps command failed".to_string());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter_map(|json| parse_compose_container(&json))
+        .collect())
+}
+
+/// Group containers into stacks, services ordered dependencies-first.
+/// Cycles or dependencies on services without containers fall back to name order.
+fn group_stacks(containers: Vec<ComposeContainer>) -> Vec<DockerStack> {
+    let mut projects: std::collections::BTreeMap<String, std::collections::BTreeMap<String, DockerStackService>> =
+        Default::default();
+    for c in containers {
+        let service = projects
+            .entry(c.project)
+            .or_default()
+            .entry(c.service.clone())
+            .or_insert_with(|| DockerStackService {
+                name: c.service,
+                containers: Vec::new(),
+                running: 0,
+                depends_on: Vec::new(),
+            });
+        service.containers.push(c.name);
+        service.running += c.running as u32;
+        for dep in c.depends_on {
+            if !service.depends_on.contains(&dep) {
+                service.depends_on.push(dep);
+            }
+        }
+    }
+
+    projects
+        .into_iter()
+        .map(|(project, mut pending)| {
+            let mut services = Vec::with_capacity(pending.len());
+            while !pending.is_empty() {
+                let ready = pending
+                    .iter()
+                    .find(|(_, svc)| svc.depends_on.iter().all(|dep| !pending.contains_key(dep) || dep == &svc.name))
+                    .map(|(name, _)| name.clone())
+                    .unwrap_or_else(|| pending.keys().next().cloned().unwrap_or_default());
+                services.extend(pending.remove(&ready));
+            }
+            let total: u32 = services.iter().map(|s| s.containers.len() as u32).sum();
+            let running: u32 = services.iter().map(|s| s.running).sum();
+            let state = match running {
+                0 => "stopped",
+                n if n == total => "running",
+                _ => "partial",
+            };
+            DockerStack { project, services, state: state.to_string(), running, total }
+        })
+        .collect()
+}
+
+fn find_stack(project: &str) -> Result<DockerStack, String> {
+    group_stacks(list_compose_containers()?)
+        .into_iter()
+        .find(|stack| stack.project == project)
+        .ok_or_else(|| format!("Nie znaleziono stosu compose '{}'", project))
+}
+
+/// Run `op` on every container of `services`; failures are recorded, not fatal.
+fn run_on_stack<'a>(
+    services: impl Iterator<Item = &'a DockerStackService>,
+    action: &str,
+    op: fn(&str) -> Result<String, String>,
+    outcomes: &mut Vec<DockerStackOutcome>,
+) {
+    for service in services {
+        for container in &service.containers {
+            let result = op(container);
+            outcomes.push(DockerStackOutcome {
+                service: service.name.clone(),
+                container: container.clone(),
+                action: action.to_string(),
+                ok: result.is_ok(),
+                message: result.unwrap_or_else(|e| e.trim().to_string()),
+            });
+        }
+    }
+}
+
+fn stack_report(project: String, outcomes: Vec<DockerStackOutcome>) -> DockerStackReport {
+    let failed = outcomes.iter().filter(|o| !o.ok).count() as u32;
+    DockerStackReport { project, outcomes, failed }
+}
+
 #[tauri::command]
-pub async fn docker_get_logs(container_id: String, lines: Option<u32>) -> Result<String, String> {
-    let output = if let Some(n) = lines {
-        Command::new("docker")
-            .args(&["logs", "--tail", &n.to_string(), &container_id])
-            .output()
-            .map_err(|e| format!("Failed to get container logs: {}", e))?
-    } else {
-        Command::new("docker")
-            .args(&["logs", &container_id])
-            .output()
-            .map_err(|e| format!("Failed to get container logs: {}", e))?
-    };
+pub async fn docker_list_stacks() -> Result<Vec<DockerStack>, String> {
+    Ok(group_stacks(list_compose_containers()?))
+}
+
+/// Stop all containers of a compose project, dependents first.
+#[tauri::command]
+pub async fn docker_stack_stop(project: String, initiator: Option<String>) -> Result<DockerStackReport, String> {
+    let params = serde_json::json!({ "project": project });
+    let result = find_stack(&project).map(|stack| {
+        let mut outcomes = Vec::new();
+        run_on_stack(stack.services.iter().rev(), "stop", docker_stop, &mut outcomes);
+        stack_report(project, outcomes)
+    });
+    crate::audit::audited("docker_stack_stop", initiator.as_deref(), params, result)
+}
+
+/// Restart a compose project: stop dependents first, then start in dependency order.
+#[tauri::command]
+pub async fn docker_stack_restart(project: String, initiator: Option<String>) -> Result<DockerStackReport, String> {
+    let params = serde_json::json!({ "project": project });
+    let result = find_stack(&project).map(|stack| {
+        let mut outcomes = Vec::new();
+        run_on_stack(stack.services.iter().rev(), "stop", docker_stop, &mut outcomes);
+        run_on_stack(stack.services.iter(), "start", docker_start, &mut outcomes);
+        stack_report(project, outcomes)
+    });
+    crate::audit::audited("docker_stack_restart", initiator.as_deref(), params, result)
+}
+
+/// Validate a `--since` value: RFC 3339, a unix timestamp, or a relative
+/// duration like "10m" / "1h30m".
+fn normalize_since(since: &str) -> Result<String, String> {
+    let since = since.trim();
+    if chrono::DateTime::parse_from_rfc3339(since).is_ok() || since.parse::<f64>().is_ok() {
+        return Ok(since.to_string());
+    }
+    let mut digits = 0;
+    let mut units = 0;
+    for c in since.chars() {
+        match c {
+            '0'..='9' => digits += 1,
+            'h' | 'm' | 's' if digits > 0 => {
+                units += 1;
+                digits = 0;
+            }
+            _ => return Err(format!("Invalid since value '{}' (use RFC3339 or e.g. 10m, 1h30m)", since)),
+        }
+    }
+    if units == 0 || digits > 0 {
+        return Err(format!("Invalid since value '{}' (use RFC3339 or e.g. 10m, 1h30m)", since));
+    }
+    Ok(since.to_string())
+}
+
+/// `tail` (alias: legacy `lines`) limits to the last N lines; `since` accepts
+/// RFC 3339 or a relative duration ("10m").
+#[tauri::command]
+pub async fn docker_get_logs(
+    container_id: String,
+    lines: Option<u32>,
+    tail: Option<u32>,
+    since: Option<String>,
+) -> Result<String, String> {
+    let mut args = vec!["logs".to_string()];
+    if let Some(n) = tail.or(lines) {
+        args.push("--tail".into());
+        args.push(n.to_string());
+    }
+    if let Some(since) = since.as_deref().filter(|s| !s.trim().is_empty()) {
+        args.push("--since".into());
+        args.push(normalize_since(since)?);
+    }
+    args.push(container_id);
+
+    let output = Command::new("docker")
+        .args(&args)
+        .output()
+        .map_err(|e| format!("Failed to get container logs: {}", e))?;
 
     if !output.status.success() {
         return Err(format!("Failed to get container logs: {}", String::from_utf8_lossy(&output.stderr)));
     }
 
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    // `docker logs` replays the container's stderr on our stderr
+    let mut logs = String::from_utf8_lossy(&output.stdout).to_string();
+    logs.push_str(&String::from_utf8_lossy(&output.stderr));
+    Ok(logs)
+}
+
+// ── Log follow ───────────────────────────────────────
+
+lazy_static::lazy_static! {
+    /// One `docker logs -f` per container — a second follow is a no-op.
+    static ref LOG_FOLLOWERS: Mutex<HashMap<String, Arc<Mutex<Child>>>> = Mutex::new(HashMap::new());
+}
+
+/// Payload of `broxeen:docker_log`. `stream` is "stdout", "stderr", or
+/// "end" once following stops (container exited or `docker_logs_stop`).
+#[derive(Debug, Clone, Serialize)]
+pub struct DockerLogEvent {
+    pub container_id: String,
+    pub stream: &'static str,
+    pub line: String,
+}
+
+fn spawn_log_reader(
+    app: AppHandle,
+    container_id: String,
+    stream: &'static str,
+    source: impl std::io::Read + Send + 'static,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let reader = std::io::BufReader::new(source);
+        for line in std::io::BufRead::lines(reader).map_while(Result::ok) {
+            let _ = app.emit("broxeen:docker_log", DockerLogEvent {
+                container_id: container_id.clone(),
+                stream,
+                line,
+            });
+        }
+    })
+}
+
+/// Stream new log lines as `broxeen:docker_log` events until the container
+/// exits or `docker_logs_stop` is called. Returns false if already following.
+#[tauri::command]
+pub async fn docker_logs_follow(app: AppHandle, container_id: String) -> Result<bool, String> {
+    let mut followers = LOG_FOLLOWERS.lock().unwrap_or_else(|e| e.into_inner());
+    if followers.contains_key(&container_id) {
+        return Ok(false);
+    }
+
+    let mut child = Command::new("docker")
+        .args(["logs", "--follow", "--tail", "0", &container_id])
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to follow container logs: {}", e))?;
+
+    let readers = [
+        child.stdout.take().map(|out| spawn_log_reader(app.clone(), container_id.clone(), "stdout", out)),
+        child.stderr.take().map(|err| spawn_log_reader(app.clone(), container_id.clone(), "stderr", err)),
+    ];
+    let child = Arc::new(Mutex::new(child));
+    followers.insert(container_id.clone(), Arc::clone(&child));
+    drop(followers);
+
+    std::thread::spawn(move || {
+        loop {
+            let status = child.lock().unwrap_or_else(|e| e.into_inner()).try_wait();
+            match status {
+                Ok(None) => std::thread::sleep(std::time::Duration::from_millis(200)),
+                Ok(Some(_)) | Err(_) => break,
+            }
+        }
+        for reader in readers.into_iter().flatten() {
+            let _ = reader.join();
+        }
+        LOG_FOLLOWERS.lock().unwrap_or_else(|e| e.into_inner()).remove(&container_id);
+        let _ = app.emit("broxeen:docker_log", DockerLogEvent {
+            container_id,
+            stream: "end",
+            line: String::new(),
+        });
+    });
+
+    Ok(true)
+}
+
+/// Stop following a container's logs. Returns false if it wasn't followed.
+#[tauri::command]
+pub async fn docker_logs_stop(container_id: String) -> Result<bool, String> {
+    let child = LOG_FOLLOWERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&container_id)
+        .cloned();
+    match child {
+        Some(child) => {
+            child
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .kill()
+                .map_err(|e| format!("Failed to stop log follow: {}", e))?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+// ── Stats & exec ─────────────────────────────────────
+
+/// Captured exec output is cut off beyond this many bytes per stream.
+const EXEC_OUTPUT_CAP: usize = 1024 * 1024;
+const EXEC_DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DockerContainerStats {
+    pub id: String,
+    pub name: String,
+    pub cpu_percent: f64,
+    pub memory_usage_bytes: u64,
+    pub memory_limit_bytes: u64,
+    pub memory_percent: f64,
+    pub net_rx_bytes: u64,
+    pub net_tx_bytes: u64,
+    pub block_read_bytes: u64,
+    pub block_write_bytes: u64,
+    pub pids: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DockerExecResult {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: i32,
+    /// stdout or stderr exceeded the capture cap and was cut off
+    pub truncated: bool,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+}
+
+/// Fail with a specific error unless the container exists and is running.
+fn ensure_running(container_id: &str) -> Result<(), String> {
+    let output = Command::new("docker")
+        .args(["inspect", "--format", "{{.State.Running}}", container_id])
+        .output()
+        .map_err(|e| format!("Docker command failed: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!("No such container: {}", container_id));
+    }
+    if String::from_utf8_lossy(&output.stdout).trim() != "true" {
+        return Err(format!("Container {} is not running", container_id));
+    }
+    Ok(())
+}
+
+/// "12.3MiB", "1.2kB", "0B" → bytes (docker mixes SI and binary units).
+fn parse_size(value: &str) -> u64 {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().unwrap_or(0.0);
+    let multiplier = match unit.trim() {
+        "kB" | "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        "TiB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => 1.0,
+    };
+    (number * multiplier) as u64
+}
+
+/// "1.2kB / 648B" → (1200, 648)
+fn parse_size_pair(value: &str) -> (u64, u64) {
+    let mut parts = value.split('/');
+    let first = parts.next().map(parse_size).unwrap_or(0);
+    let second = parts.next().map(parse_size).unwrap_or(0);
+    (first, second)
+}
+
+fn parse_percent(value: &str) -> f64 {
+    value.trim().trim_end_matches('%').parse().unwrap_or(0.0)
+}
+
+fn parse_stats(json: &serde_json::Value) -> DockerContainerStats {
+    let field = |key: &str| json[key].as_str().unwrap_or("");
+    let (memory_usage_bytes, memory_limit_bytes) = parse_size_pair(field("MemUsage"));
+    let (net_rx_bytes, net_tx_bytes) = parse_size_pair(field("NetIO"));
+    let (block_read_bytes, block_write_bytes) = parse_size_pair(field("BlockIO"));
+    DockerContainerStats {
+        id: field("ID").to_string(),
+        name: field("Name").to_string(),
+        cpu_percent: parse_percent(field("CPUPerc")),
+        memory_usage_bytes,
+        memory_limit_bytes,
+        memory_percent: parse_percent(field("MemPerc")),
+        net_rx_bytes,
+        net_tx_bytes,
+        block_read_bytes,
+        block_write_bytes,
+        pids: field("PIDs").trim().parse().unwrap_or(0),
+    }
+}
+
+/// Read `source` to the end, keeping at most `cap` bytes. Returns the kept
+/// bytes and whether anything was dropped.
+fn read_capped(mut source: impl std::io::Read, cap: usize) -> (Vec<u8>, bool) {
+    let mut kept = Vec::new();
+    let mut truncated = false;
+    let mut buf = [0u8; 8192];
+    loop {
+        match source.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                let room = cap.saturating_sub(kept.len());
+                if n > room {
+                    truncated = true;
+                }
+                kept.extend_from_slice(&buf[..n.min(room)]);
+            }
+        }
+    }
+    (kept, truncated)
+}
+
+/// One-shot resource usage of a running container (`docker stats --no-stream`).
+#[tauri::command]
+pub async fn docker_container_stats(container_id: String) -> Result<DockerContainerStats, String> {
+    ensure_running(&container_id)?;
+
+    let output = Command::new("docker")
+        .args(["stats", "--no-stream", "--format", "{{json .}}", &container_id])
+        .output()
+        .map_err(|e| format!("Failed to get container stats: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!("Failed to get container stats: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.lines().find(|l| !l.trim().is_empty()).ok_or("Docker stats returned no data")?;
+    let json: serde_json::Value = serde_json::from_str(line)
+        .map_err(|e| format!("Failed to parse container stats: {}", e))?;
+
+    Ok(parse_stats(&json))
+}
+
+/// Run `cmd` inside a running container. Output beyond 1 MB per stream is
+/// dropped (`truncated`); on timeout the docker client is killed (`timed_out`).
+#[tauri::command]
+pub async fn docker_exec(
+    container_id: String,
+    cmd: Vec<String>,
+    timeout_secs: Option<u64>,
+    initiator: Option<String>,
+) -> Result<DockerExecResult, String> {
+    let params = serde_json::json!({ "container_id": container_id, "cmd": cmd });
+    let result = tokio::task::spawn_blocking(move || {
+        exec_blocking(&container_id, &cmd, timeout_secs.unwrap_or(EXEC_DEFAULT_TIMEOUT_SECS))
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r);
+
+    let outcome = match &result {
+        Ok(r) if r.exit_code == 0 && !r.timed_out => Ok(()),
+        Ok(r) if r.timed_out => Err("timeout".to_string()),
+        Ok(r) => Err(format!("exit code {}", r.exit_code)),
+        Err(e) => Err(e.clone()),
+    };
+    crate::audit::record("docker_exec", initiator.as_deref(), params, outcome);
+    result
+}
+
+fn exec_blocking(container_id: &str, cmd: &[String], timeout_secs: u64) -> Result<DockerExecResult, String> {
+    if cmd.is_empty() {
+        return Err("Empty command".to_string());
+    }
+    ensure_running(container_id)?;
+
+    let t0 = std::time::Instant::now();
+    let mut child = Command::new("docker")
+        .arg("exec")
+        .arg(container_id)
+        .args(cmd)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to exec in container: {}", e))?;
+
+    let stdout = child.stdout.take().map(|out| std::thread::spawn(move || read_capped(out, EXEC_OUTPUT_CAP)));
+    let stderr = child.stderr.take().map(|err| std::thread::spawn(move || read_capped(err, EXEC_OUTPUT_CAP)));
+
+    let deadline = std::time::Duration::from_secs(timeout_secs.max(1));
+    let mut timed_out = false;
+    let status = loop {
+        match child.try_wait().map_err(|e| e.to_string())? {
+            Some(status) => break status,
+            None if t0.elapsed() >= deadline => {
+                timed_out = true;
+                let _ = child.kill();
+                break child.wait().map_err(|e| e.to_string())?;
+            }
+            None => std::thread::sleep(std::time::Duration::from_millis(50)),
+        }
+    };
+
+    let collect = |handle: Option<std::thread::JoinHandle<(Vec<u8>, bool)>>| {
+        handle.and_then(|h| h.join().ok()).unwrap_or_default()
+    };
+    let (out, out_truncated) = collect(stdout);
+    let (err, err_truncated) = collect(stderr);
+
+    Ok(DockerExecResult {
+        stdout: String::from_utf8_lossy(&out).to_string(),
+        stderr: String::from_utf8_lossy(&err).to_string(),
+        exit_code: status.code().unwrap_or(-1),
+        truncated: out_truncated || err_truncated,
+        timed_out,
+        duration_ms: t0.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stats_units() {
+        let json = serde_json::json!({
+            "ID": "abc", "Name": "web", "CPUPerc": "12.50%", "MemPerc": "0.62%",
+            "MemUsage": "12.5MiB / 1.944GiB", "NetIO": "1.2kB / 648B",
+            "BlockIO": "4.1MB / 0B", "PIDs": "7"
+        });
+        let stats = parse_stats(&json);
+        assert_eq!(stats.cpu_percent, 12.5);
+        assert_eq!(stats.memory_usage_bytes, 13_107_200);
+        assert_eq!((stats.net_rx_bytes, stats.net_tx_bytes), (1200, 648));
+        assert_eq!(stats.block_read_bytes, 4_100_000);
+        assert_eq!(stats.pids, 7);
+    }
+
+    #[test]
+    fn test_normalize_since() {
+        assert!(normalize_since("10m").is_ok());
+        assert!(normalize_since("1h30m").is_ok());
+        assert!(normalize_since("2024-05-01T10:00:00Z").is_ok());
+        assert!(normalize_since("1714557600").is_ok());
+        assert!(normalize_since("10").is_ok());
+        assert!(normalize_since("yesterday").is_err());
+        assert!(normalize_since("m10").is_err());
+    }
+
+    #[test]
+    fn test_read_capped_flags_truncation() {
+        let data = vec![b'x'; 10_000];
+        let (kept, truncated) = read_capped(data.as_slice(), 4096);
+        assert_eq!(kept.len(), 4096);
+        assert!(truncated);
+
+        let (kept, truncated) = read_capped(&b"short"[..], 4096);
+        assert_eq!(kept, b"short");
+        assert!(!truncated);
+    }
+
+    #[test]
+    fn test_group_stacks_orders_by_depends_on() {
+        let row = |name: &str, service: &str, state: &str, depends: &str| {
+            let mut labels = format!("{}=frigate,{}={}", COMPOSE_PROJECT, COMPOSE_SERVICE, service);
+            if !depends.is_empty() {
+                labels.push_str(&format!(",{}={}", COMPOSE_DEPENDS_ON, depends));
+            }
+            labels.push_str(",com.docker.compose.version=2.24.0");
+            parse_compose_container(&serde_json::json!({ "Names": name, "State": state, "Labels": labels })).unwrap()
+        };
+        let containers = vec![
+            row("frigate-web-1", "web", "running", "api:service_started:false"),
+            row("frigate-api-1", "api", "running", "db:service_healthy:true,mqtt:service_started:false"),
+            row("frigate-db-1", "db", "exited", ""),
+            row("frigate-mqtt-1", "mqtt", "running", ""),
+        ];
+        assert_eq!(containers[1].depends_on, vec!["db", "mqtt"]);
+        assert!(parse_compose_container(&serde_json::json!({ "Names": "plain", "Labels": "a=b" })).is_none());
+
+        let stacks = group_stacks(containers);
+        assert_eq!(stacks.len(), 1);
+        let order: Vec<_> = stacks[0].services.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(order, vec!["db", "mqtt", "api", "web"]);
+        assert_eq!((stacks[0].running, stacks[0].total), (3, 4));
+        assert_eq!(stacks[0].state, "partial");
+
+        // A dependency cycle still yields every service once
+        let stacks = group_stacks(vec![
+            row("x-a-1", "a", "running", "b:service_started:false"),
+            row("x-b-1", "b", "running", "a:service_started:false"),
+        ]);
+        assert_eq!(stacks[0].services.len(), 2);
+        assert_eq!(stacks[0].state, "running");
+    }
 }
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use std::process::Command;
 use std::path::Path;
 
+use crate::error::{BroxeenError, ErrorCode};
 use crate::logging::{backend_info, backend_warn, backend_error};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -18,6 +19,7 @@ pub struct EmailConfig {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct EmailMessage {
+    /// IMAP UID in INBOX
     pub id: String,
     pub from: String,
     pub to: Vec<String>,
@@ -37,94 +39,147 @@ pub struct InboxSummary {
     pub poll_time: String,
 }
 
-/// Send email using system `sendmail`, `msmtp`, or Python fallback
+/// Image embedded in the HTML body, referenced as `<img src="cid:{cid}">`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InlineImage {
+    pub cid: String,
+    pub path: String,
+}
+
+/// Send email using system `sendmail`, `msmtp`, or Python fallback.
+/// With `html_body` the message is multipart/alternative (the plain part is
+/// `body`, or text derived from the HTML when `body` is empty); `inline_images`
+/// are attached as multipart/related parts addressable by Content-ID.
+/// A repeated `idempotency_key` returns the first result without re-sending.
+/// Every actual send attempt is written to the audit log.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn email_send(
     to: Vec<String>,
     subject: String,
     body: String,
+    html_body: Option<String>,
+    inline_images: Option<Vec<InlineImage>>,
     attachments: Option<Vec<String>>,
     config: Option<EmailConfig>,
-) -> Result<String, String> {
+    idempotency_key: Option<String>,
+    initiator: Option<String>,
+) -> Result<String, BroxeenError> {
+    crate::idempotency::run_once("email_send", idempotency_key.as_deref(), || async move {
+        let audit_params = serde_json::json!({
+            "to": to,
+            "subject": subject,
+            "body_chars": body.chars().count(),
+            "html": html_body.is_some(),
+            "inline_images": inline_images.as_ref().map_or(0, |i| i.len()),
+            "attachments": attachments.as_ref().map_or(0, |a| a.len()),
+            "smtp_host": config.as_ref().map(|c| c.smtp_host.clone()),
+        });
+        let result = send_email(to, subject, body, html_body, inline_images, attachments, config).await;
+        crate::audit::audited("email_send", initiator.as_deref(), audit_params, result)
+    })
+    .await
+}
+
+fn not_configured() -> BroxeenError {
+    BroxeenError::not_configured("Email nie jest skonfigurowany. Użyj komendy 'konfiguruj email' w czacie.")
+}
+
+/// `python3` (which talks SMTP / IMAP) could not be started.
+fn python_spawn_error(context: &str, e: std::io::Error) -> BroxeenError {
+    if e.kind() == std::io::ErrorKind::NotFound {
+        BroxeenError::tool_missing(format!("{}: python3 nie jest zainstalowany", context))
+    } else {
+        BroxeenError::from(e).context(context)
+    }
+}
+
+/// Code of a failed SMTP / IMAP script, from the Python exception it printed.
+fn mail_error_code(stderr: &str) -> ErrorCode {
+    const AUTH: &[&str] = &["SMTPAuthenticationError", "AUTHENTICATIONFAILED", "authentication failed", "Invalid credentials"];
+    const TIMEOUT: &[&str] = &["timed out", "TimeoutError"];
+    const UNREACHABLE: &[&str] = &[
+        "ConnectionRefusedError", "Connection refused", "gaierror", "Name or service not known",
+        "No route to host", "Network is unreachable", "SMTPServerDisconnected",
+    ];
+    let has = |needles: &[&str]| needles.iter().any(|n| stderr.contains(n));
+    if has(AUTH) {
+        ErrorCode::PermissionDenied
+    } else if has(TIMEOUT) {
+        ErrorCode::Timeout
+    } else if has(UNREACHABLE) {
+        ErrorCode::Unreachable
+    } else if stderr.contains("Brak wiadomości") {
+        ErrorCode::NotFound
+    } else {
+        ErrorCode::Internal
+    }
+}
+
+async fn send_email(
+    to: Vec<String>,
+    subject: String,
+    body: String,
+    html_body: Option<String>,
+    inline_images: Option<Vec<InlineImage>>,
+    attachments: Option<Vec<String>>,
+    config: Option<EmailConfig>,
+) -> Result<String, BroxeenError> {
     backend_info(format!(
-        "Command email_send invoked: to={:?}, subject='{}', attachments={:?}",
-        to, subject, attachments.as_ref().map(|a| a.len())
+        "Command email_send invoked: to={:?}, subject='{}', html={}, inline_images={:?}, attachments={:?}",
+        to,
+        subject,
+        html_body.is_some(),
+        inline_images.as_ref().map(|i| i.len()),
+        attachments.as_ref().map(|a| a.len())
     ));
 
     let cfg = config.unwrap_or_else(|| load_email_config_from_env());
 
     if cfg.smtp_host.is_empty() || cfg.smtp_user.is_empty() {
-        return Err("Email nie jest skonfigurowany. Użyj komendy 'konfiguruj email' w czacie.".to_string());
+        return Err(not_configured());
     }
 
     let recipients = to.join(", ");
     let attachment_paths = attachments.unwrap_or_default();
 
-    // Build email content
-    let boundary = format!("broxeen-boundary-{}", chrono::Utc::now().timestamp_millis());
-    let mut email_content = String::new();
-
-    email_content.push_str(&format!("From: {}\r\n", cfg.from_address));
-    email_content.push_str(&format!("To: {}\r\n", recipients));
-    email_content.push_str(&format!("Subject: {}\r\n", subject));
-    email_content.push_str("MIME-Version: 1.0\r\n");
-
-    if attachment_paths.is_empty() {
-        email_content.push_str("Content-Type: text/plain; charset=utf-8\r\n");
-        email_content.push_str("Content-Transfer-Encoding: 8bit\r\n\r\n");
-        email_content.push_str(&body);
-    } else {
-        email_content.push_str(&format!(
-            "Content-Type: multipart/mixed; boundary=\"{}\"\r\n\r\n",
-            boundary
-        ));
-
-        // Body part
-        email_content.push_str(&format!("--{}\r\n", boundary));
-        email_content.push_str("Content-Type: text/plain; charset=utf-8\r\n");
-        email_content.push_str("Content-Transfer-Encoding: 8bit\r\n\r\n");
-        email_content.push_str(&body);
-        email_content.push_str("\r\n");
-
-        // Attachment parts
-        for attach_path in &attachment_paths {
-            let path = Path::new(attach_path);
-            if !path.exists() {
-                backend_warn(format!("Attachment not found: {}", attach_path));
-                continue;
-            }
-
-            let filename = path
-                .file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_else(|| "attachment".to_string());
-
-            let file_bytes = std::fs::read(path)
-                .map_err(|e| format!("Nie można odczytać załącznika {}: {}", attach_path, e))?;
-            use base64::Engine as _;
-            let b64 = base64::engine::general_purpose::STANDARD.encode(&file_bytes);
-
-            email_content.push_str(&format!("--{}\r\n", boundary));
-            email_content.push_str(&format!(
-                "Content-Type: application/octet-stream; name=\"{}\"\r\n",
-                filename
-            ));
-            email_content.push_str("Content-Transfer-Encoding: base64\r\n");
-            email_content.push_str(&format!(
-                "Content-Disposition: attachment; filename=\"{}\"\r\n\r\n",
-                filename
-            ));
-
-            // Split base64 into 76-char lines
-            for chunk in b64.as_bytes().chunks(76) {
-                email_content.push_str(&String::from_utf8_lossy(chunk));
-                email_content.push_str("\r\n");
-            }
+    let mut attachment_parts = Vec::new();
+    for attach_path in &attachment_paths {
+        let path = Path::new(attach_path);
+        if !path.exists() {
+            backend_warn(format!("Attachment not found: {}", attach_path));
+            continue;
         }
-
-        email_content.push_str(&format!("--{}--\r\n", boundary));
+        attachment_parts.push(load_file_part(path, None)?);
+    }
+    let inline_images = inline_images.unwrap_or_default();
+    if html_body.is_none() && !inline_images.is_empty() {
+        return Err(BroxeenError::invalid_input("Obrazki inline wymagają treści HTML (html_body)"));
+    }
+    let mut inline_parts = Vec::new();
+    for image in inline_images {
+        let cid = image.cid.trim().trim_start_matches("cid:").trim_matches(['<', '>']).to_string();
+        if cid.is_empty() || cid.contains(char::is_whitespace) {
+            return Err(BroxeenError::invalid_input(format!("Nieprawidłowy Content-ID obrazka: '{}'", image.cid)));
+        }
+        inline_parts.push(load_file_part(Path::new(&image.path), Some(cid))?);
     }
 
+    let headers = MessageHeaders {
+        from: &cfg.from_address,
+        to: &recipients,
+        subject: &subject,
+    };
+    let boundary_seed = chrono::Utc::now().timestamp_millis().to_string();
+    let email_content = build_mime(
+        &headers,
+        &body,
+        html_body.as_deref(),
+        &inline_parts,
+        &attachment_parts,
+        &boundary_seed,
+    );
+
     // Try sending via Python (most reliable cross-platform approach)
     let python_script = format!(
         r#"
@@ -163,59 +218,47 @@ print("OK")
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
         .spawn()
-        .map_err(|e| format!("Nie można uruchomić Python do wysyłki email: {}", e))?;
+        .map_err(|e| python_spawn_error("Nie można uruchomić Python do wysyłki email", e))?;
 
     if let Some(ref mut stdin) = child.stdin {
         use std::io::Write;
         stdin
             .write_all(email_content.as_bytes())
-            .map_err(|e| format!("Nie można przesłać treści email: {}", e))?;
+            .map_err(|e| BroxeenError::from(e).context("Nie można przesłać treści email"))?;
     }
 
     let output = child
         .wait_with_output()
-        .map_err(|e| format!("Błąd podczas wysyłki email: {}", e))?;
+        .map_err(|e| BroxeenError::from(e).context("Błąd podczas wysyłki email"))?;
 
     if output.status.success() {
         let msg = format!(
             "Email wysłany do {} (temat: '{}', załączników: {})",
             recipients,
             subject,
-            attachment_paths.len()
+            attachment_parts.len()
         );
         backend_info(&msg);
         Ok(msg)
     } else {
         let stderr = String::from_utf8_lossy(&output.stderr).to_string();
         backend_error(format!("Email send failed: {}", stderr));
-        Err(format!("Nie udało się wysłać email: {}", stderr))
+        Err(BroxeenError::new(mail_error_code(&stderr), format!("Nie udało się wysłać email: {}", stderr)))
     }
 }
 
-/// Poll inbox via Python IMAP
-#[tauri::command]
-pub async fn email_poll_inbox(
-    max_messages: Option<usize>,
-    config: Option<EmailConfig>,
-) -> Result<InboxSummary, String> {
-    backend_info("Command email_poll_inbox invoked");
-
-    let cfg = config.unwrap_or_else(|| load_email_config_from_env());
-
-    if cfg.imap_host.is_empty() || cfg.smtp_user.is_empty() {
-        return Err("Email nie jest skonfigurowany. Użyj komendy 'konfiguruj email' w czacie.".to_string());
-    }
-
-    let max = max_messages.unwrap_or(10);
-
-    let python_script = format!(
-        r#"
+/// Shared head of the IMAP scripts: connection settings, credentials and the
+/// script's own parameters arrive as JSON on stdin, never in the script text.
+const IMAP_PRELUDE: &str = r#"
 import imaplib
 import email
 from email.header import decode_header
+import base64
 import json
 import sys
 
+params = json.load(sys.stdin)
+
 def decode_str(s):
     if s is None:
         return ""
@@ -228,31 +271,40 @@ def decode_str(s):
             parts.append(str(part))
     return ' '.join(parts)
 
-try:
-    if {use_tls}:
-        mail = imaplib.IMAP4_SSL("{imap_host}", {imap_port})
+def connect():
+    if params["use_tls"]:
+        mail = imaplib.IMAP4_SSL(params["host"], params["port"])
     else:
-        mail = imaplib.IMAP4("{imap_host}", {imap_port})
+        mail = imaplib.IMAP4(params["host"], params["port"])
+    mail.login(params["user"], params["password"])
+    return mail
 
-    mail.login("{user}", "{password}")
+"#;
+
+/// Lists the newest `params["max"]` messages of INBOX (headers and the
+/// start of the body only).
+const POLL_SCRIPT: &str = r#"
+try:
+    mail = connect()
     mail.select("INBOX")
 
-    # Get total and unseen counts
-    status, data = mail.search(None, "ALL")
+    # Get total and unseen counts; ids are UIDs, stable across sessions
+    status, data = mail.uid("SEARCH", None, "ALL")
     all_ids = data[0].split() if data[0] else []
     total = len(all_ids)
 
-    status, data = mail.search(None, "UNSEEN")
+    status, data = mail.uid("SEARCH", None, "UNSEEN")
     unseen_ids = data[0].split() if data[0] else []
     unread = len(unseen_ids)
 
     # Fetch recent messages
-    fetch_ids = all_ids[-{max}:] if len(all_ids) > {max} else all_ids
+    max_messages = params["max"]
+    fetch_ids = all_ids[-max_messages:] if len(all_ids) > max_messages else all_ids
     fetch_ids.reverse()
 
     messages = []
     for msg_id in fetch_ids:
-        status, msg_data = mail.fetch(msg_id, "(FLAGS BODY.PEEK[HEADER] BODY.PEEK[TEXT])")
+        status, msg_data = mail.uid("FETCH", msg_id, "(FLAGS BODY.PEEK[HEADER] BODY.PEEK[TEXT])")
         if status != "OK":
             continue
 
@@ -277,7 +329,7 @@ try:
         is_read = "\\\\Seen" in flags_str
         has_attach = "attachment" in str(raw_header).lower()
 
-        messages.append({{
+        messages.append({
             "id": msg_id.decode(),
             "from": from_addr,
             "to": [t.strip() for t in to_addr.split(",")],
@@ -286,38 +338,116 @@ try:
             "date": date,
             "has_attachments": has_attach,
             "is_read": is_read,
-        }})
+        })
 
     mail.close()
     mail.logout()
 
-    print(json.dumps({{
+    print(json.dumps({
         "total_messages": total,
         "unread_count": unread,
         "recent_messages": messages,
-    }}))
+    }))
 except Exception as e:
-    print(json.dumps({{"error": str(e)}}), file=sys.stderr)
+    print(json.dumps({"error": str(e)}, ensure_ascii=False), file=sys.stderr)
     sys.exit(1)
-"#,
-        imap_host = cfg.imap_host,
-        imap_port = cfg.imap_port,
-        use_tls = if cfg.use_tls { "True" } else { "False" },
-        user = cfg.smtp_user.replace('\"', "\\\""),
-        password = cfg.smtp_password.replace('\"', "\\\""),
-        max = max,
-    );
+"#;
 
-    let output = Command::new("python3")
+/// Lists the attachments of message `params["uid"]` and returns
+/// `params["download_index"]` base64-encoded when within `params["max_bytes"]`.
+const ATTACHMENTS_SCRIPT: &str = r#"
+try:
+    mail = connect()
+    mail.select("INBOX", readonly=True)
+
+    uid = params["uid"]
+    status, msg_data = mail.uid("FETCH", uid, "(BODY.PEEK[])")
+    if status != "OK" or not msg_data or not isinstance(msg_data[0], tuple):
+        raise Exception("Brak wiadomości " + uid)
+    msg = email.message_from_bytes(msg_data[0][1])
+
+    attachments = []
+    data = None
+    for part in msg.walk():
+        if part.is_multipart():
+            continue
+        filename = part.get_filename()
+        if not filename and part.get_content_disposition() != "attachment":
+            continue
+        payload = part.get_payload(decode=True) or b""
+        index = len(attachments)
+        attachments.append({
+            "index": index,
+            "filename": decode_str(filename) if filename else "",
+            "content_type": part.get_content_type(),
+            "size": len(payload),
+        })
+        if index == params["download_index"] and len(payload) <= params["max_bytes"]:
+            data = base64.b64encode(payload).decode()
+
+    mail.logout()
+    print(json.dumps({"attachments": attachments, "data": data}))
+except Exception as e:
+    print(json.dumps({"error": str(e)}, ensure_ascii=False), file=sys.stderr)
+    sys.exit(1)
+"#;
+
+/// Run `IMAP_PRELUDE` + `script` with `python3 -c`, passing the IMAP
+/// settings of `cfg` and `params` as JSON on stdin.
+fn run_imap_script(
+    cfg: &EmailConfig,
+    script: &str,
+    mut params: serde_json::Value,
+) -> Result<std::process::Output, BroxeenError> {
+    use std::io::Write;
+
+    params["host"] = cfg.imap_host.clone().into();
+    params["port"] = cfg.imap_port.into();
+    params["use_tls"] = cfg.use_tls.into();
+    params["user"] = cfg.smtp_user.clone().into();
+    params["password"] = cfg.smtp_password.clone().into();
+
+    let mut child = Command::new("python3")
         .arg("-c")
-        .arg(&python_script)
-        .output()
-        .map_err(|e| format!("Nie można uruchomić Python do odczytu email: {}", e))?;
+        .arg(format!("{}{}", IMAP_PRELUDE, script))
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| python_spawn_error("Nie można uruchomić Python do odczytu email", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Dropping stdin closes it, so json.load returns
+        stdin
+            .write_all(params.to_string().as_bytes())
+            .map_err(|e| BroxeenError::from(e).context("Nie można przekazać parametrów IMAP"))?;
+    }
+    child
+        .wait_with_output()
+        .map_err(|e| BroxeenError::from(e).context("Błąd podczas odczytu email"))
+}
+
+/// Poll inbox via Python IMAP
+#[tauri::command]
+pub async fn email_poll_inbox(
+    max_messages: Option<usize>,
+    config: Option<EmailConfig>,
+) -> Result<InboxSummary, BroxeenError> {
+    backend_info("Command email_poll_inbox invoked");
+
+    let cfg = config.unwrap_or_else(|| load_email_config_from_env());
+
+    if cfg.imap_host.is_empty() || cfg.smtp_user.is_empty() {
+        return Err(not_configured());
+    }
+
+    let max = max_messages.unwrap_or(10);
+
+    let output = run_imap_script(&cfg, POLL_SCRIPT, serde_json::json!({ "max": max }))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr).to_string();
         backend_error(format!("Email poll failed: {}", stderr));
-        return Err(format!("Nie udało się odczytać skrzynki: {}", stderr));
+        return Err(BroxeenError::new(mail_error_code(&stderr), format!("Nie udało się odczytać skrzynki: {}", stderr)));
     }
 
     let stdout = String::from_utf8_lossy(&output.stdout).to_string();
@@ -380,9 +510,226 @@ except Exception as e:
     })
 }
 
+// ── Attachments ──────────────────────────────────────────────────
+
+/// One attachment of a message, as listed by `email_get_attachments`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct EmailAttachment {
+    /// Position among the message's attachments (`attachment_index`)
+    pub index: usize,
+    pub filename: String,
+    pub content_type: String,
+    /// Decoded size in bytes
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedAttachment {
+    pub path: String,
+    pub filename: String,
+    pub content_type: String,
+    pub size: u64,
+}
+
+#[derive(Deserialize)]
+struct AttachmentFetch {
+    attachments: Vec<EmailAttachment>,
+    /// Base64 of the requested attachment, when within the size limit
+    data: Option<String>,
+}
+
+/// Fetch the full MIME structure of one message (polling only reads headers
+/// and the start of the body) and list its attachments. With `download =
+/// Some((index, max_bytes))` the decoded attachment is returned as well,
+/// unless it is larger than `max_bytes`.
+fn fetch_attachments(
+    cfg: &EmailConfig,
+    message_id: &str,
+    download: Option<(usize, u64)>,
+) -> Result<AttachmentFetch, BroxeenError> {
+    // IMAP UIDs only
+    if message_id.is_empty() || !message_id.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(BroxeenError::invalid_input(format!("Nieprawidłowy identyfikator wiadomości: {}", message_id)));
+    }
+    let (download_index, max_bytes) = match download {
+        Some((index, max)) => (index as i64, max),
+        None => (-1, 0),
+    };
+
+    let output = run_imap_script(
+        cfg,
+        ATTACHMENTS_SCRIPT,
+        serde_json::json!({ "uid": message_id, "download_index": download_index, "max_bytes": max_bytes }),
+    )?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        backend_error(format!("Email attachment fetch failed: {}", stderr));
+        return Err(BroxeenError::new(mail_error_code(&stderr), format!("Nie udało się pobrać wiadomości: {}", stderr)));
+    }
+
+    serde_json::from_slice(&output.stdout).map_err(|e| BroxeenError::from(format!("Błąd parsowania odpowiedzi: {}", e)))
+}
+
+fn imap_config(config: Option<EmailConfig>) -> Result<EmailConfig, BroxeenError> {
+    let cfg = config.unwrap_or_else(load_email_config_from_env);
+    if cfg.imap_host.is_empty() || cfg.smtp_user.is_empty() {
+        return Err(not_configured());
+    }
+    Ok(cfg)
+}
+
+/// List attachment names, sizes and content types of one message
+/// (`id` from `email_poll_inbox`).
+#[tauri::command]
+pub async fn email_get_attachments(
+    message_id: String,
+    config: Option<EmailConfig>,
+) -> Result<Vec<EmailAttachment>, BroxeenError> {
+    backend_info(format!("Command email_get_attachments invoked: message {}", message_id));
+    let cfg = imap_config(config)?;
+    let fetched = tokio::task::spawn_blocking(move || fetch_attachments(&cfg, &message_id, None))
+        .await
+        .map_err(|e| e.to_string())??;
+    Ok(fetched.attachments)
+}
+
+/// Save one attachment of a message into `save_dir` (default: Downloads) and
+/// return its path. Files above `email_attachment_max_mb` are refused.
+#[tauri::command]
+pub async fn email_download_attachment(
+    message_id: String,
+    attachment_index: usize,
+    save_dir: Option<String>,
+    config: Option<EmailConfig>,
+) -> Result<SavedAttachment, BroxeenError> {
+    backend_info(format!(
+        "Command email_download_attachment invoked: message {} attachment {}",
+        message_id, attachment_index
+    ));
+    let cfg = imap_config(config)?;
+    let dir = match save_dir.filter(|d| !d.trim().is_empty()) {
+        Some(d) => std::path::PathBuf::from(d),
+        None => dirs::download_dir()
+            .or_else(dirs::home_dir)
+            .ok_or("Nie można ustalić katalogu Pobrane — podaj save_dir")?,
+    };
+    let max_bytes = crate::settings::load_settings().email_attachment_max_mb * 1024 * 1024;
+
+    tokio::task::spawn_blocking(move || {
+        let fetched = fetch_attachments(&cfg, &message_id, Some((attachment_index, max_bytes)))?;
+        let attachment = fetched
+            .attachments
+            .iter()
+            .find(|a| a.index == attachment_index)
+            .ok_or_else(|| BroxeenError::not_found(format!(
+                "Wiadomość {} nie ma załącznika nr {} (załączników: {})",
+                message_id, attachment_index, fetched.attachments.len()
+            )))?;
+        check_attachment_size(attachment, max_bytes).map_err(BroxeenError::invalid_input)?;
+        let bytes = {
+            use base64::Engine as _;
+            let data = fetched.data.as_deref().ok_or("Serwer nie zwrócił treści załącznika")?;
+            base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .map_err(|e| format!("Błąd dekodowania załącznika: {}", e))?
+        };
+        let path = save_attachment(&dir, attachment, &bytes, max_bytes)?;
+        backend_info(format!("Saved attachment {} ({} B)", path.display(), bytes.len()));
+        Ok(SavedAttachment {
+            path: path.to_string_lossy().to_string(),
+            filename: attachment.filename.clone(),
+            content_type: attachment.content_type.clone(),
+            size: bytes.len() as u64,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn check_attachment_size(attachment: &EmailAttachment, max_bytes: u64) -> Result<(), String> {
+    if attachment.size > max_bytes {
+        return Err(format!(
+            "Załącznik \"{}\" ma {:.1} MB — limit to {} MB (email_attachment_max_mb)",
+            attachment.filename,
+            attachment.size as f64 / (1024.0 * 1024.0),
+            max_bytes / (1024 * 1024)
+        ));
+    }
+    Ok(())
+}
+
+/// Attachment name reduced to a single safe path component: directories,
+/// control and reserved characters, leading dots and trailing dots / spaces
+/// are dropped, and Windows device names (`CON`, `nul.txt`, `COM1`…) get a
+/// `_` prefix.
+fn sanitize_attachment_filename(name: &str, index: usize) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or("");
+    let cleaned: String = base
+        .chars()
+        .map(|c| if c.is_control() || matches!(c, ':' | '*' | '?' | '"' | '<' | '>' | '|') { '_' } else { c })
+        .collect();
+    let cleaned = cleaned.trim().trim_start_matches('.').trim_end_matches(['.', ' ']).trim();
+    if cleaned.is_empty() {
+        return format!("zalacznik-{}", index + 1);
+    }
+    let cleaned: String = cleaned.chars().take(200).collect();
+    if is_windows_device_name(&cleaned) {
+        format!("_{}", cleaned)
+    } else {
+        cleaned
+    }
+}
+
+/// `CON`, `PRN`, `AUX`, `NUL`, `COM1`–`COM9`, `LPT1`–`LPT9`, with any
+/// extension and in any case — Windows opens the device instead of a file.
+fn is_windows_device_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or("").trim_end().to_ascii_uppercase();
+    match stem.as_str() {
+        "CON" | "PRN" | "AUX" | "NUL" => true,
+        _ => {
+            let digits = stem.strip_prefix("COM").or_else(|| stem.strip_prefix("LPT"));
+            matches!(digits, Some(d) if d.len() == 1 && matches!(d.as_bytes()[0], b'1'..=b'9'))
+        }
+    }
+}
+
+/// Write `bytes` as a new file in `dir` — never overwriting: an existing
+/// name gets a " (n)" suffix.
+fn save_attachment(
+    dir: &Path,
+    attachment: &EmailAttachment,
+    bytes: &[u8],
+    max_bytes: u64,
+) -> Result<std::path::PathBuf, String> {
+    use std::io::Write;
+
+    // The listed size is the server's; check what was actually decoded
+    check_attachment_size(&EmailAttachment { size: bytes.len() as u64, ..attachment.clone() }, max_bytes)?;
+    std::fs::create_dir_all(dir).map_err(|e| format!("Nie można utworzyć {}: {}", dir.display(), e))?;
+    let name = sanitize_attachment_filename(&attachment.filename, attachment.index);
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem.to_string(), format!(".{}", ext)),
+        _ => (name.clone(), String::new()),
+    };
+    for n in 0..1000 {
+        let file_name = if n == 0 { name.clone() } else { format!("{} ({}){}", stem, n, ext) };
+        let path = dir.join(file_name);
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(bytes).map_err(|e| format!("Błąd zapisu {}: {}", path.display(), e))?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("Błąd zapisu {}: {}", path.display(), e)),
+        }
+    }
+    Err(format!("Zbyt wiele plików {} w {}", name, dir.display()))
+}
+
 /// Test email configuration
 #[tauri::command]
-pub async fn email_test_config(config: EmailConfig) -> Result<String, String> {
+pub async fn email_test_config(config: EmailConfig) -> Result<String, BroxeenError> {
     backend_info(format!(
         "Command email_test_config invoked: smtp={}:{}, imap={}:{}",
         config.smtp_host, config.smtp_port, config.imap_host, config.imap_port
@@ -434,7 +781,7 @@ print(json.dumps(results))
         .arg("-c")
         .arg(&python_script)
         .output()
-        .map_err(|e| format!("Nie można uruchomić Python: {}", e))?;
+        .map_err(|e| python_spawn_error("Nie można uruchomić Python", e))?;
 
     let stdout = String::from_utf8_lossy(&output.stdout).to_string();
     let parsed: serde_json::Value =
@@ -462,7 +809,8 @@ print(json.dumps(results))
         Ok(result)
     } else {
         backend_warn(format!("Email config test partial failure: smtp={} imap={}", smtp_ok, imap_ok));
-        Err(result)
+        let details = serde_json::json!({ "smtp": smtp_ok, "imap": imap_ok, "smtp_error": smtp_err, "imap_error": imap_err });
+        Err(BroxeenError::new(mail_error_code(&format!("{}\n{}", smtp_err, imap_err)), result).with_details(details))
     }
 }
 
@@ -486,3 +834,321 @@ fn load_email_config_from_env() -> EmailConfig {
             .unwrap_or(true),
     }
 }
+
+// ── MIME building ────────────────────────────────────────────────
+
+pub struct MessageHeaders<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    pub subject: &'a str,
+}
+
+/// A file part: attachment, or inline image when `content_id` is set.
+pub struct FilePart {
+    pub filename: String,
+    pub content_type: &'static str,
+    pub content_id: Option<String>,
+    pub bytes: Vec<u8>,
+}
+
+enum MimeNode<'a> {
+    Text { subtype: &'static str, text: &'a str, base64: bool },
+    File(&'a FilePart),
+    Multi { subtype: &'static str, children: Vec<MimeNode<'a>> },
+}
+
+fn load_file_part(path: &Path, content_id: Option<String>) -> Result<FilePart, String> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| format!("Nie można odczytać załącznika {}: {}", path.display(), e))?;
+    let filename = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "attachment".to_string());
+    Ok(FilePart {
+        content_type: content_type_for(&filename),
+        filename,
+        content_id,
+        bytes,
+    })
+}
+
+/// MIME type from the file extension; unknown types stay application/octet-stream.
+pub fn content_type_for(filename: &str) -> &'static str {
+    let ext = Path::new(filename)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "txt" | "log" => "text/plain",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "ogg" => "audio/ogg",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Plain-text fallback for an HTML body: tags dropped, block ends become
+/// line breaks, common entities decoded.
+pub fn html_to_text(html: &str) -> String {
+    let breaks = regex_lite::Regex::new(r"(?i)<br\s*/?>|</(p|div|h[1-6]|li|tr)>").expect("valid regex");
+    let hidden = regex_lite::Regex::new(r"(?is)<(style|script)[^>]*>.*?</(style|script)>").expect("valid regex");
+    let tags = regex_lite::Regex::new(r"<[^>]*>").expect("valid regex");
+    let text = hidden.replace_all(html, "");
+    let text = breaks.replace_all(&text, "\n");
+    let text = tags.replace_all(&text, "");
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join("\r\n")
+}
+
+fn push_base64(out: &mut String, bytes: &[u8]) {
+    use base64::Engine as _;
+    let b64 = base64::engine::general_purpose::STANDARD.encode(bytes);
+    // Split base64 into 76-char lines
+    for chunk in b64.as_bytes().chunks(76) {
+        out.push_str(&String::from_utf8_lossy(chunk));
+        out.push_str("\r\n");
+    }
+}
+
+fn render_node(node: &MimeNode, seed: &str, counter: &mut usize, out: &mut String) {
+    match node {
+        MimeNode::Text { subtype, text, base64 } => {
+            out.push_str(&format!("Content-Type: text/{}; charset=utf-8\r\n", subtype));
+            if *base64 {
+                out.push_str("Content-Transfer-Encoding: base64\r\n\r\n");
+                push_base64(out, text.as_bytes());
+            } else {
+                out.push_str("Content-Transfer-Encoding: 8bit\r\n\r\n");
+                out.push_str(text);
+                out.push_str("\r\n");
+            }
+        }
+        MimeNode::File(part) => {
+            out.push_str(&format!("Content-Type: {}; name=\"{}\"\r\n", part.content_type, part.filename));
+            out.push_str("Content-Transfer-Encoding: base64\r\n");
+            match &part.content_id {
+                Some(cid) => {
+                    out.push_str(&format!("Content-ID: <{}>\r\n", cid));
+                    out.push_str(&format!("Content-Disposition: inline; filename=\"{}\"\r\n\r\n", part.filename));
+                }
+                None => {
+                    out.push_str(&format!("Content-Disposition: attachment; filename=\"{}\"\r\n\r\n", part.filename));
+                }
+            }
+            push_base64(out, &part.bytes);
+        }
+        MimeNode::Multi { subtype, children } => {
+            *counter += 1;
+            let boundary = format!("broxeen-{}-{}", seed, counter);
+            out.push_str(&format!("Content-Type: multipart/{}; boundary=\"{}\"\r\n\r\n", subtype, boundary));
+            for child in children {
+                out.push_str(&format!("--{}\r\n", boundary));
+                render_node(child, seed, counter, out);
+            }
+            out.push_str(&format!("--{}--\r\n", boundary));
+        }
+    }
+}
+
+/// Full RFC 5322 message:
+/// mixed( related( alternative(plain, html), inline images… ), attachments… ),
+/// with every layer that has a single child collapsed away.
+pub fn build_mime(
+    headers: &MessageHeaders,
+    body: &str,
+    html_body: Option<&str>,
+    inline_images: &[FilePart],
+    attachments: &[FilePart],
+    boundary_seed: &str,
+) -> String {
+    let derived_text;
+    let plain = match html_body {
+        Some(html) if body.trim().is_empty() => {
+            derived_text = html_to_text(html);
+            derived_text.as_str()
+        }
+        _ => body,
+    };
+
+    let mut content = MimeNode::Text { subtype: "plain", text: plain, base64: false };
+    if let Some(html) = html_body {
+        content = MimeNode::Multi {
+            subtype: "alternative",
+            children: vec![content, MimeNode::Text { subtype: "html", text: html, base64: true }],
+        };
+        // Inline images only make sense next to HTML that references them
+        if !inline_images.is_empty() {
+            let mut children = vec![content];
+            children.extend(inline_images.iter().map(MimeNode::File));
+            content = MimeNode::Multi { subtype: "related", children };
+        }
+    }
+    if !attachments.is_empty() {
+        let mut children = vec![content];
+        children.extend(attachments.iter().map(MimeNode::File));
+        content = MimeNode::Multi { subtype: "mixed", children };
+    }
+
+    let mut out = String::new();
+    out.push_str(&format!("From: {}\r\n", headers.from));
+    out.push_str(&format!("To: {}\r\n", headers.to));
+    out.push_str(&format!("Subject: {}\r\n", headers.subject));
+    out.push_str("MIME-Version: 1.0\r\n");
+    render_node(&content, boundary_seed, &mut 0, &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn part(filename: &str, cid: Option<&str>, bytes: &[u8]) -> FilePart {
+        FilePart {
+            filename: filename.to_string(),
+            content_type: content_type_for(filename),
+            content_id: cid.map(str::to_string),
+            bytes: bytes.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_mime_structure_html_inline_image_and_attachment() {
+        let headers = MessageHeaders { from: "cam@broxeen.local", to: "me@example.com", subject: "Raport" };
+        let html = "<p>Osoba przy &amp; drzwiach</p><img src=\"cid:det1\">";
+        let inline = [part("det1.jpg", Some("det1"), b"JPEG")];
+        let attachments = [part("raport.pdf", None, b"%PDF")];
+        let mime = build_mime(&headers, "", Some(html), &inline, &attachments, "t");
+
+        let expected = "From: cam@broxeen.local\r\n\
+To: me@example.com\r\n\
+Subject: Raport\r\n\
+MIME-Version: 1.0\r\n\
+Content-Type: multipart/mixed; boundary=\"broxeen-t-1\"\r\n\r\n\
+--broxeen-t-1\r\n\
+Content-Type: multipart/related; boundary=\"broxeen-t-2\"\r\n\r\n\
+--broxeen-t-2\r\n\
+Content-Type: multipart/alternative; boundary=\"broxeen-t-3\"\r\n\r\n\
+--broxeen-t-3\r\n\
+Content-Type: text/plain; charset=utf-8\r\n\
+Content-Transfer-Encoding: 8bit\r\n\r\n\
+Osoba przy & drzwiach\r\n\
+--broxeen-t-3\r\n\
+Content-Type: text/html; charset=utf-8\r\n\
+Content-Transfer-Encoding: base64\r\n\r\n\
+PHA+T3NvYmEgcHJ6eSAmYW1wOyBkcnp3aWFjaDwvcD48aW1nIHNyYz0iY2lkOmRldDEiPg==\r\n\
+--broxeen-t-3--\r\n\
+--broxeen-t-2\r\n\
+Content-Type: image/jpeg; name=\"det1.jpg\"\r\n\
+Content-Transfer-Encoding: base64\r\n\
+Content-ID: <det1>\r\n\
+Content-Disposition: inline; filename=\"det1.jpg\"\r\n\r\n\
+SlBFRw==\r\n\
+--broxeen-t-2--\r\n\
+--broxeen-t-1\r\n\
+Content-Type: application/pdf; name=\"raport.pdf\"\r\n\
+Content-Transfer-Encoding: base64\r\n\
+Content-Disposition: attachment; filename=\"raport.pdf\"\r\n\r\n\
+JVBERg==\r\n\
+--broxeen-t-1--\r\n";
+        assert_eq!(mime, expected);
+    }
+
+    #[test]
+    fn test_plain_message_and_content_types() {
+        let headers = MessageHeaders { from: "a@b", to: "c@d", subject: "Hi" };
+        let mime = build_mime(&headers, "Treść", None, &[], &[], "t");
+        assert!(mime.ends_with("Content-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\nTreść\r\n"));
+        assert!(!mime.contains("multipart"));
+
+        assert_eq!(content_type_for("klatka.PNG"), "image/png");
+        assert_eq!(content_type_for("dane.csv"), "text/csv");
+        assert_eq!(content_type_for("bez_rozszerzenia"), "application/octet-stream");
+        assert_eq!(html_to_text("<h1>Tytuł</h1><style>p{}</style>a<br/>b&nbsp;c"), "Tytuł\r\na\r\nb c");
+    }
+
+    fn attachment(index: usize, filename: &str, size: u64) -> EmailAttachment {
+        EmailAttachment { index, filename: filename.into(), content_type: "application/pdf".into(), size }
+    }
+
+    #[test]
+    fn test_attachment_filenames_cannot_escape_save_dir() {
+        assert_eq!(sanitize_attachment_filename("faktura 03/2024.pdf", 0), "2024.pdf");
+        assert_eq!(sanitize_attachment_filename("../../.bashrc", 0), "bashrc");
+        assert_eq!(sanitize_attachment_filename("..\\..\\Windows\\win.ini", 0), "win.ini");
+        assert_eq!(sanitize_attachment_filename("a:b*c?\u{7}.txt", 0), "a_b_c__.txt");
+        assert_eq!(sanitize_attachment_filename("..", 2), "zalacznik-3");
+        assert_eq!(sanitize_attachment_filename("", 0), "zalacznik-1");
+        assert_eq!(sanitize_attachment_filename("CON", 0), "_CON");
+        assert_eq!(sanitize_attachment_filename("nul.txt", 0), "_nul.txt");
+        assert_eq!(sanitize_attachment_filename("Com1.tar.gz", 0), "_Com1.tar.gz");
+        assert_eq!(sanitize_attachment_filename("lpt9 .pdf", 0), "_lpt9 .pdf");
+        assert_eq!(sanitize_attachment_filename("raport. . ", 0), "raport");
+        assert_eq!(sanitize_attachment_filename("COM10.txt", 0), "COM10.txt");
+        assert_eq!(sanitize_attachment_filename("console.log", 0), "console.log");
+
+        let dir = tempfile::tempdir().unwrap();
+        let evil = attachment(0, "../../etc/passwd", 4);
+        let first = save_attachment(dir.path(), &evil, b"root", 1024).unwrap();
+        let second = save_attachment(dir.path(), &evil, b"toor", 1024).unwrap();
+        assert_eq!(first, dir.path().join("passwd"));
+        assert_eq!(second, dir.path().join("passwd (1)"));
+        assert_eq!(std::fs::read(&first).unwrap(), b"root");
+
+        let pdf = attachment(1, "raport.pdf", 4);
+        save_attachment(dir.path(), &pdf, b"%PDF", 1024).unwrap();
+        assert_eq!(save_attachment(dir.path(), &pdf, b"%PDF", 1024).unwrap(), dir.path().join("raport (1).pdf"));
+    }
+
+    #[test]
+    fn test_attachment_size_limit() {
+        let max = 25 * 1024 * 1024;
+        assert!(check_attachment_size(&attachment(0, "maly.pdf", max), max).is_ok());
+        let err = check_attachment_size(&attachment(0, "duzy.zip", max + 5 * 1024 * 1024), max).unwrap_err();
+        assert!(err.contains("30.0 MB") && err.contains("limit to 25 MB"), "{}", err);
+
+        // The decoded bytes count, whatever size the server listed
+        let dir = tempfile::tempdir().unwrap();
+        assert!(save_attachment(dir.path(), &attachment(0, "a.bin", 1), &[0u8; 16], 8).is_err());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_mail_failures_are_coded() {
+        let auth = "smtplib.SMTPAuthenticationError: (535, b'5.7.8 Username and Password not accepted')";
+        assert_eq!(mail_error_code(auth), ErrorCode::PermissionDenied);
+        assert_eq!(mail_error_code("ConnectionRefusedError: [Errno 111] Connection refused"), ErrorCode::Unreachable);
+        assert_eq!(mail_error_code("socket.gaierror: [Errno -2] Name or service not known"), ErrorCode::Unreachable);
+        assert_eq!(mail_error_code("TimeoutError: timed out"), ErrorCode::Timeout);
+        assert_eq!(mail_error_code(r#"{"error": "Brak wiadomości 42"}"#), ErrorCode::NotFound);
+        assert_eq!(mail_error_code("KeyError: 'x'"), ErrorCode::Internal);
+
+        let missing = python_spawn_error("Nie można uruchomić Python", std::io::ErrorKind::NotFound.into());
+        assert_eq!(missing.code, ErrorCode::ExternalToolMissing);
+        assert_eq!(missing.message, "Nie można uruchomić Python: python3 nie jest zainstalowany");
+    }
+}
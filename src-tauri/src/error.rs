@@ -0,0 +1,231 @@
+//! error.rs — Error type of Tauri commands.
+//!
+//! Commands used to fail with a bare `String`, leaving the frontend to guess
+//! what went wrong from Polish or English fragments of it. A
+//! [`BroxeenError`] serializes as `{ code, message, details? }`: `code` is
+//! stable and meant for branching, `message` is the same human-readable text
+//! as before and meant for display.
+//!
+//! Internal helpers may keep returning `Result<_, String>`; `?` turns their
+//! errors into [`ErrorCode::Internal`]. Sites that know better say so with the
+//! constructors ([`BroxeenError::not_found`] …), and `io::Error`,
+//! `reqwest::Error` and `rusqlite::Error` are coded by what failed.
+
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// File, directory, record, camera or pipeline does not exist
+    NotFound,
+    PermissionDenied,
+    Timeout,
+    /// Host or service could not be reached
+    Unreachable,
+    /// Credentials or settings the command needs are missing
+    NotConfigured,
+    /// A required program (ffmpeg, python3, nmap …) is not installed
+    ExternalToolMissing,
+    /// Arguments the command cannot work with
+    InvalidInput,
+    /// All slots of a limited command are taken; retry later
+    Busy,
+    /// Not available in this build (e.g. without `--features vision`)
+    Unsupported,
+    Internal,
+}
+
+impl ErrorCode {
+    /// Code of an unsuccessful HTTP status.
+    pub fn from_http_status(status: u16) -> Self {
+        match status {
+            404 | 410 => ErrorCode::NotFound,
+            401 | 403 | 407 => ErrorCode::PermissionDenied,
+            408 | 504 => ErrorCode::Timeout,
+            429 => ErrorCode::Busy,
+            502 | 503 => ErrorCode::Unreachable,
+            _ => ErrorCode::Internal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BroxeenError {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+impl BroxeenError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self { code, message: message.into(), details: None }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn not_configured(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotConfigured, message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput, message)
+    }
+
+    pub fn tool_missing(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::ExternalToolMissing, message)
+    }
+
+    pub fn unsupported(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Unsupported, message)
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// Prefix the message ("Nie można odczytać pliku: …"), keeping the code.
+    pub fn context(mut self, context: impl fmt::Display) -> Self {
+        self.message = format!("{}: {}", context, self.message);
+        self
+    }
+}
+
+impl fmt::Display for BroxeenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for BroxeenError {}
+
+impl From<String> for BroxeenError {
+    fn from(message: String) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+}
+
+impl From<&str> for BroxeenError {
+    fn from(message: &str) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+}
+
+/// For helpers that still report errors as text.
+impl From<BroxeenError> for String {
+    fn from(e: BroxeenError) -> Self {
+        e.message
+    }
+}
+
+impl From<std::io::Error> for BroxeenError {
+    fn from(e: std::io::Error) -> Self {
+        use std::io::ErrorKind::*;
+        let code = match e.kind() {
+            NotFound => ErrorCode::NotFound,
+            PermissionDenied | ReadOnlyFilesystem => ErrorCode::PermissionDenied,
+            TimedOut => ErrorCode::Timeout,
+            ConnectionRefused | ConnectionReset | ConnectionAborted | NotConnected | AddrNotAvailable
+            | HostUnreachable | NetworkUnreachable | NetworkDown => ErrorCode::Unreachable,
+            InvalidInput | InvalidData | InvalidFilename => ErrorCode::InvalidInput,
+            ResourceBusy => ErrorCode::Busy,
+            _ => ErrorCode::Internal,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<reqwest::Error> for BroxeenError {
+    fn from(e: reqwest::Error) -> Self {
+        let status = e.status().map(|s| s.as_u16());
+        let code = if e.is_timeout() {
+            ErrorCode::Timeout
+        } else if e.is_connect() {
+            ErrorCode::Unreachable
+        } else if e.is_builder() {
+            ErrorCode::InvalidInput
+        } else {
+            status.map_or(ErrorCode::Internal, ErrorCode::from_http_status)
+        };
+        let mut details = serde_json::Map::new();
+        if let Some(url) = e.url() {
+            details.insert("url".into(), url.as_str().into());
+        }
+        if let Some(status) = status {
+            details.insert("status".into(), status.into());
+        }
+        let err = Self::new(code, e.to_string());
+        if details.is_empty() {
+            err
+        } else {
+            err.with_details(details.into())
+        }
+    }
+}
+
+impl From<rusqlite::Error> for BroxeenError {
+    fn from(e: rusqlite::Error) -> Self {
+        use rusqlite::ErrorCode as Sqlite;
+        let code = match &e {
+            rusqlite::Error::QueryReturnedNoRows => ErrorCode::NotFound,
+            rusqlite::Error::SqliteFailure(failure, _) => match failure.code {
+                Sqlite::CannotOpen => ErrorCode::NotFound,
+                Sqlite::PermissionDenied | Sqlite::ReadOnly | Sqlite::AuthorizationForStatementDenied => {
+                    ErrorCode::PermissionDenied
+                }
+                Sqlite::DatabaseBusy | Sqlite::DatabaseLocked => ErrorCode::Busy,
+                _ => ErrorCode::Internal,
+            },
+            rusqlite::Error::InvalidParameterName(_)
+            | rusqlite::Error::InvalidColumnName(_)
+            | rusqlite::Error::InvalidColumnIndex(_)
+            | rusqlite::Error::InvalidQuery
+            | rusqlite::Error::MultipleStatement
+            | rusqlite::Error::SqlInputError { .. } => ErrorCode::InvalidInput,
+            _ => ErrorCode::Internal,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializes_code_message_and_details() {
+        let err = BroxeenError::not_found("Plik nie istnieje: /tmp/x").with_details(serde_json::json!({ "path": "/tmp/x" }));
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            serde_json::json!({ "code": "not_found", "message": "Plik nie istnieje: /tmp/x", "details": { "path": "/tmp/x" } })
+        );
+        let plain = serde_json::to_value(BroxeenError::from("boom".to_string())).unwrap();
+        assert_eq!(plain, serde_json::json!({ "code": "internal", "message": "boom" }));
+    }
+
+    #[test]
+    fn test_maps_io_and_sqlite_errors() {
+        let missing = std::fs::File::open("/nonexistent/broxeen").unwrap_err();
+        let err = BroxeenError::from(missing).context("Nie można odczytać pliku");
+        assert_eq!(err.code, ErrorCode::NotFound);
+        assert!(err.message.starts_with("Nie można odczytać pliku: "), "{}", err);
+
+        let refused = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+        assert_eq!(BroxeenError::from(refused).code, ErrorCode::Unreachable);
+        let timeout = std::io::Error::new(std::io::ErrorKind::TimedOut, "ffmpeg timed out");
+        assert_eq!(BroxeenError::from(timeout), BroxeenError::new(ErrorCode::Timeout, "ffmpeg timed out"));
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let none = conn.query_row("SELECT 1 WHERE 0", [], |r| r.get::<_, i64>(0)).unwrap_err();
+        assert_eq!(BroxeenError::from(none).code, ErrorCode::NotFound);
+        let bad_sql = conn.prepare("SELEC 1").unwrap_err();
+        assert_ne!(BroxeenError::from(bad_sql).code, ErrorCode::NotFound);
+        let flags = rusqlite::OpenFlags::SQLITE_OPEN_READ_WRITE;
+        let unopenable = rusqlite::Connection::open_with_flags("/nonexistent/broxeen.db", flags).unwrap_err();
+        assert_eq!(BroxeenError::from(unopenable).code, ErrorCode::NotFound);
+    }
+}
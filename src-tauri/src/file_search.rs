@@ -4,7 +4,9 @@ use std::fs;
 use std::time::SystemTime;
 use rust_search::SearchBuilder;
 
-use crate::logging::backend_info;
+use crate::error::BroxeenError;
+use crate::image_meta::{self, ImageMeta};
+use crate::logging::{backend_info, backend_warn};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FileSearchResult {
@@ -17,6 +19,24 @@ pub struct FileSearchResult {
     pub is_dir: bool,
     pub preview: Option<String>,
     pub mime_type: String,
+    /// Matching lines when searching file contents
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matches: Vec<ContentMatch>,
+    /// Name relevance 0–1000 (see `name_score`); 0 when no name query
+    #[serde(default)]
+    pub score: u32,
+    /// Base64 JPEG (≤128 px) when `include_thumbnails` is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail_base64: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_meta: Option<ImageMeta>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ContentMatch {
+    /// 1-based
+    pub line: usize,
+    pub text: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,6 +57,9 @@ pub struct FileContentResponse {
     pub size_bytes: u64,
     pub mime_type: String,
     pub truncated: bool,
+    /// Byte offset of the returned window (after clamping / tail seek)
+    #[serde(default)]
+    pub offset: u64,
 }
 
 fn guess_mime_type(ext: &str) -> &'static str {
@@ -187,22 +210,6 @@ fn search_with_rust_search(
         let mime = guess_mime_type(&ext);
         let file_type = classify_file_type(&ext);
         
-        // Generate preview for text files
-        let preview = if is_text_file(&ext) && metadata.len() < 1_000_000 {
-            fs::read_to_string(&path)
-                .ok()
-                .map(|content| {
-                    let trimmed: String = content.chars().take(500).collect();
-                    if content.len() > 500 {
-                        format!("{}...", trimmed)
-                    } else {
-                        trimmed
-                    }
-                })
-        } else {
-            None
-        };
-        
         results.push(FileSearchResult {
             path: path_str,
             name,
@@ -211,8 +218,12 @@ fn search_with_rust_search(
             modified,
             file_type: file_type.to_string(),
             is_dir: false,
-            preview,
+            preview: None,
             mime_type: mime.to_string(),
+            matches: Vec::new(),
+            score: 0,
+            thumbnail_base64: None,
+            image_meta: None,
         });
         
         if results.len() >= max_results {
@@ -223,14 +234,293 @@ fn search_with_rust_search(
     results
 }
 
+// ── Content search ───────────────────────────────────
+
+/// Files larger than this are not grepped.
+const CONTENT_MAX_FILE_BYTES: u64 = 1_000_000;
+/// Wall-clock budget for a whole content search.
+const CONTENT_SEARCH_BUDGET: std::time::Duration = std::time::Duration::from_secs(5);
+const MAX_MATCHES_PER_FILE: usize = 5;
+const MATCH_SNIPPET_CHARS: usize = 200;
+/// Directories never worth grepping
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "__pycache__"];
+
+fn is_greppable(ext: &str) -> bool {
+    is_text_file(ext) || guess_mime_type(ext).starts_with("text/")
+}
+
+/// Null byte in the first 8 KB → treat as binary.
+fn looks_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(8192).any(|&b| b == 0)
+}
+
+/// Case-insensitive line matches of `needle` (already lowercased).
+fn grep_lines(content: &str, needle: &str) -> Vec<ContentMatch> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.to_lowercase().contains(needle))
+        .take(MAX_MATCHES_PER_FILE)
+        .map(|(i, line)| {
+            let trimmed = line.trim();
+            let mut text: String = trimmed.chars().take(MATCH_SNIPPET_CHARS).collect();
+            if trimmed.chars().count() > MATCH_SNIPPET_CHARS {
+                text.push_str("...");
+            }
+            ContentMatch { line: i + 1, text }
+        })
+        .collect()
+}
+
+/// Depth-limited walk of regular files under `base_path`, skipping hidden and
+/// build directories. `visit` returns false to stop. Returns true when the
+/// walk was cut short (by `visit` or the `deadline`).
+fn walk_files(
+    base_path: &Path,
+    max_depth: usize,
+    deadline: std::time::Instant,
+    mut visit: impl FnMut(&Path, &str, &fs::Metadata) -> bool,
+) -> bool {
+    let mut stack = vec![(base_path.to_path_buf(), 0usize)];
+    while let Some((dir, depth)) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            if std::time::Instant::now() >= deadline {
+                backend_info("file_search time budget exhausted");
+                return true;
+            }
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Ok(metadata) = entry.metadata() else { continue };
+
+            if metadata.is_dir() {
+                if depth + 1 < max_depth && !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_str()) {
+                    stack.push((path, depth + 1));
+                }
+                continue;
+            }
+            if !visit(&path, &name, &metadata) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// First 500 characters of a text file; filled in only for the returned
+/// results so ranking large candidate sets stays cheap.
+fn text_preview(result: &FileSearchResult) -> Option<String> {
+    if !is_text_file(&result.extension) || result.size_bytes >= 1_000_000 {
+        return None;
+    }
+    fs::read_to_string(&result.path).ok().map(|content| {
+        let trimmed: String = content.chars().take(500).collect();
+        if content.len() > 500 {
+            format!("{}...", trimmed)
+        } else {
+            trimmed
+        }
+    })
+}
+
+fn extension_of(path: &Path) -> String {
+    path.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+fn matches_extensions(ext: &str, extensions: &[String]) -> bool {
+    extensions.is_empty() || extensions.iter().any(|e| e.eq_ignore_ascii_case(ext))
+}
+
+fn result_for(path: &Path, name: &str, metadata: &fs::Metadata) -> FileSearchResult {
+    let ext = extension_of(path);
+    FileSearchResult {
+        path: path.to_string_lossy().to_string(),
+        name: name.to_string(),
+        size_bytes: metadata.len(),
+        modified: metadata.modified().ok().and_then(format_time),
+        file_type: classify_file_type(&ext).to_string(),
+        is_dir: false,
+        preview: None,
+        mime_type: guess_mime_type(&ext).to_string(),
+        extension: ext,
+        matches: Vec::new(),
+        score: 0,
+        thumbnail_base64: None,
+        image_meta: None,
+    }
+}
+
+/// Most thumbnails generated per response
+const MAX_THUMBNAILS: usize = 20;
+
+/// Attach thumbnails + image metadata to raster images among `results`.
+/// Oversized or corrupt files are logged and skipped, never failing the search.
+fn attach_image_previews(results: &mut [FileSearchResult]) {
+    let mut generated = 0;
+    for r in results.iter_mut() {
+        if generated >= MAX_THUMBNAILS {
+            break;
+        }
+        if r.is_dir || !image_meta::is_raster_image(&r.extension) || r.size_bytes > image_meta::MAX_THUMBNAIL_SOURCE_BYTES {
+            continue;
+        }
+        let path = Path::new(&r.path);
+        match image_meta::thumbnail_base64(path) {
+            Ok(thumb) => {
+                r.thumbnail_base64 = Some(thumb);
+                r.image_meta = image_meta::read_image_meta(path).ok();
+                generated += 1;
+            }
+            Err(e) => backend_warn(format!("file_search: thumbnail skipped: {}", e)),
+        }
+    }
+}
+
+/// Walk `base_path` (up to `max_depth`) and grep text-like files for
+/// `content_query`. `name_query` / `extensions` further restrict the files.
+/// Returns the results and whether the search stopped early (limit or budget).
+fn search_in_files(
+    base_path: &Path,
+    name_query: &str,
+    content_query: &str,
+    extensions: &[String],
+    max_results: usize,
+    max_depth: usize,
+) -> (Vec<FileSearchResult>, bool) {
+    let deadline = std::time::Instant::now() + CONTENT_SEARCH_BUDGET;
+    let needle = content_query.to_lowercase();
+    let name_needle = name_query.to_lowercase();
+    let mut results = Vec::new();
+
+    let truncated = walk_files(base_path, max_depth, deadline, |path, name, metadata| {
+        let ext = extension_of(path);
+        if !matches_extensions(&ext, extensions)
+            || (!name_needle.is_empty() && !name.to_lowercase().contains(&name_needle))
+            || !is_greppable(&ext)
+            || metadata.len() >= CONTENT_MAX_FILE_BYTES
+        {
+            return true;
+        }
+        let Ok(bytes) = fs::read(path) else { return true };
+        if looks_binary(&bytes) {
+            return true;
+        }
+        let matches = grep_lines(&String::from_utf8_lossy(&bytes), &needle);
+        if !matches.is_empty() {
+            results.push(FileSearchResult { matches, ..result_for(path, name, metadata) });
+        }
+        results.len() < max_results
+    });
+    (results, truncated)
+}
+
+// ── Ranking ──────────────────────────────────────────
+
+const SCORE_EXACT: u32 = 1000;
+const SCORE_PREFIX: u32 = 800;
+const SCORE_SUBSTRING: u32 = 600;
+const SCORE_FUZZY_MAX: u32 = 400;
+/// Candidates scored before ranking in non-fuzzy mode
+const RANK_CANDIDATES: usize = 2000;
+
+/// Subsequence match score in 1..=SCORE_FUZZY_MAX, rewarding consecutive
+/// characters and matches at word starts; None when not a subsequence.
+fn fuzzy_score(name: &str, query: &str) -> Option<u32> {
+    let name: Vec<char> = name.chars().collect();
+    let mut bonus = 0u32;
+    let mut prev_match: Option<usize> = None;
+    let mut pos = 0;
+    for q in query.chars() {
+        let idx = (pos..name.len()).find(|&i| name[i] == q)?;
+        if prev_match == Some(idx.wrapping_sub(1)) {
+            bonus += 3;
+        }
+        if idx == 0 || !name[idx - 1].is_alphanumeric() {
+            bonus += 2;
+        }
+        prev_match = Some(idx);
+        pos = idx + 1;
+    }
+    let qlen = query.chars().count().max(1) as u32;
+    let density = qlen * 100 / name.len().max(1) as u32;
+    Some((density + bonus * 100 / qlen).clamp(1, SCORE_FUZZY_MAX))
+}
+
+/// Relevance of a file name for `query`: exact (name or stem) > prefix >
+/// substring > fuzzy. Within a tier shorter names rank higher. 0 = no match.
+fn name_score(name: &str, query: &str, fuzzy: bool) -> u32 {
+    let name = name.to_lowercase();
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return 0;
+    }
+    let stem = name.rsplit_once('.').map_or(name.as_str(), |(stem, _)| stem);
+    // Up to 199 within a tier, by how much of the name the query covers
+    let coverage = (query.chars().count() * 199 / name.chars().count().max(1)) as u32;
+
+    if name == query || stem == query {
+        SCORE_EXACT
+    } else if name.starts_with(&query) {
+        SCORE_PREFIX + coverage
+    } else if name.contains(&query) {
+        SCORE_SUBSTRING + coverage
+    } else if fuzzy {
+        fuzzy_score(&name, &query).unwrap_or(0)
+    } else {
+        0
+    }
+}
+
+/// Highest score first, newest first on ties.
+fn rank_results(results: &mut [FileSearchResult]) {
+    results.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| b.modified.cmp(&a.modified)));
+}
+
+/// Fuzzy name search: walk everything within the budget and keep scored hits.
+fn search_fuzzy(
+    base_path: &Path,
+    query: &str,
+    extensions: &[String],
+    max_depth: usize,
+) -> (Vec<FileSearchResult>, bool) {
+    let deadline = std::time::Instant::now() + CONTENT_SEARCH_BUDGET;
+    let mut results = Vec::new();
+    let cut_short = walk_files(base_path, max_depth, deadline, |path, name, metadata| {
+        if matches_extensions(&extension_of(path), extensions) {
+            let score = name_score(name, query, true);
+            if score > 0 {
+                results.push(FileSearchResult { score, ..result_for(path, name, metadata) });
+            }
+        }
+        true
+    });
+    (results, cut_short)
+}
+
+/// Search by file name, or — with `search_content` — inside text files.
+/// In content mode `content_query` (default: `query`) is grepped and a
+/// separate `content_query` lets `query` still filter by name.
+///
+/// Name results are ranked by `score` (exact > prefix > substring, newest
+/// first on ties) before `max_results` is applied. Only when no name contains
+/// the query does `fuzzy` (default true) fall back to subsequence matches
+/// such as "rprt" → "raport.txt".
+///
+/// `include_thumbnails` adds a small JPEG thumbnail and `image_meta`
+/// (dimensions, EXIF date, GPS) to up to 20 image results under 25 MB.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn file_search(
     query: String,
     search_path: Option<String>,
     extensions: Option<Vec<String>>,
     max_results: Option<usize>,
     max_depth: Option<usize>,
-) -> Result<FileSearchResponse, String> {
+    search_content: Option<bool>,
+    content_query: Option<String>,
+    fuzzy: Option<bool>,
+    include_thumbnails: Option<bool>,
+) -> Result<FileSearchResponse, BroxeenError> {
     let start = std::time::Instant::now();
     backend_info(format!(
         "Command file_search invoked: query='{}', path={:?}, extensions={:?}",
@@ -244,21 +534,66 @@ pub async fn file_search(
         });
 
     if !base_path.exists() {
-        return Err(format!("Ścieżka nie istnieje: {}", base_path.display()));
+        return Err(BroxeenError::not_found(format!("Ścieżka nie istnieje: {}", base_path.display())));
     }
 
     let max = max_results.unwrap_or(50);
     let depth = max_depth.unwrap_or(8);
     let exts = extensions.unwrap_or_default();
 
-    // Use rust_search for faster searching
-    let mut results = search_with_rust_search(&base_path, &query, &exts, max, depth);
+    let content_query = content_query.filter(|q| !q.trim().is_empty());
+    let content_mode = search_content.unwrap_or(false) || content_query.is_some();
+    let (mut results, truncated) = if content_mode {
+        let (name_query, needle) = match &content_query {
+            Some(cq) => (query.as_str(), cq.as_str()),
+            None => ("", query.as_str()),
+        };
+        if needle.trim().is_empty() {
+            return Err(BroxeenError::invalid_input("Podaj tekst do wyszukania w treści plików."));
+        }
+        let (mut results, truncated) = search_in_files(&base_path, name_query, needle, &exts, max, depth);
+        results.sort_by(|a, b| b.modified.cmp(&a.modified));
+        (results, truncated)
+    } else if query.trim().is_empty() {
+        // Listing by extension: nothing to rank, newest first
+        let mut results = search_with_rust_search(&base_path, &query, &exts, max, depth);
+        results.sort_by(|a, b| b.modified.cmp(&a.modified));
+        let truncated = results.len() >= max;
+        (results, truncated)
+    } else {
+        // Use rust_search for faster searching; over-fetch so ranking sees more than `max`
+        let mut results = search_with_rust_search(&base_path, &query, &exts, RANK_CANDIDATES, depth);
+        for r in results.iter_mut() {
+            r.score = name_score(&r.name, &query, false);
+        }
+        let cut_short = results.len() >= RANK_CANDIDATES;
+        let (mut results, cut_short) = if results.is_empty() && fuzzy.unwrap_or(true) {
+            search_fuzzy(&base_path, &query, &exts, depth)
+        } else {
+            (results, cut_short)
+        };
+        rank_results(&mut results);
+        let truncated = cut_short || results.len() > max;
+        results.truncate(max);
+        (results, truncated)
+    };
+
+    if !content_mode {
+        for r in results.iter_mut() {
+            r.preview = text_preview(r);
+        }
+    }
 
-    // Sort by modification date (newest first)
-    results.sort_by(|a, b| b.modified.cmp(&a.modified));
+    if include_thumbnails.unwrap_or(false) {
+        results = tokio::task::spawn_blocking(move || {
+            attach_image_previews(&mut results);
+            results
+        })
+        .await
+        .map_err(|e| format!("Thumbnail task failed: {}", e))?;
+    }
 
     let total = results.len();
-    let truncated = total >= max;
 
     backend_info(format!(
         "file_search completed: {} results in {}ms (truncated={})",
@@ -277,21 +612,134 @@ pub async fn file_search(
     })
 }
 
+// ── Windowed reads ───────────────────────────────────
+
+/// Default window for `encoding = "hex"` when `max_chars` is not given
+const HEXDUMP_DEFAULT_BYTES: usize = 4096;
+const HEXDUMP_MAX_BYTES: usize = 1024 * 1024;
+/// Backwards read step for `tail_lines`
+const TAIL_CHUNK: u64 = 64 * 1024;
+
+fn read_window(file: &mut fs::File, offset: u64, len: usize) -> std::io::Result<Vec<u8>> {
+    use std::io::{Read, Seek, SeekFrom};
+    file.seek(SeekFrom::Start(offset))?;
+    let mut buf = Vec::with_capacity(len);
+    file.take(len as u64).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// Lossy UTF-8 decode of a window cut out of a larger file: drops a partial
+/// character at the start (mid-file offset) and at the end (window cut short).
+fn decode_window(bytes: &[u8], mid_start: bool, mid_end: bool) -> String {
+    let mut start = 0;
+    if mid_start {
+        while start < bytes.len().min(3) && (bytes[start] & 0xC0) == 0x80 {
+            start += 1;
+        }
+    }
+    let mut slice = &bytes[start..];
+    if mid_end {
+        if let Err(e) = std::str::from_utf8(slice) {
+            if e.error_len().is_none() {
+                slice = &slice[..e.valid_up_to()];
+            }
+        }
+    }
+    String::from_utf8_lossy(slice).into_owned()
+}
+
+/// Classic `hexdump -C` layout: offset, 16 hex bytes, ASCII column.
+fn format_hexdump(bytes: &[u8], base_offset: u64) -> String {
+    let mut out = String::with_capacity(bytes.len() * 4 + 16);
+    for (i, row) in bytes.chunks(16).enumerate() {
+        out.push_str(&format!("{:08x}  ", base_offset + (i * 16) as u64));
+        for col in 0..16 {
+            match row.get(col) {
+                Some(b) => out.push_str(&format!("{:02x} ", b)),
+                None => out.push_str("   "),
+            }
+            if col == 7 {
+                out.push(' ');
+            }
+        }
+        out.push_str(" |");
+        out.extend(row.iter().map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }));
+        out.push_str("|\n");
+    }
+    out
+}
+
+/// Byte offset where the last `lines` lines start, found by scanning
+/// backwards in `TAIL_CHUNK` steps. Stops early once `max_bytes` are covered;
+/// the flag is false then (fewer lines than requested).
+fn tail_start(file: &mut fs::File, size: u64, lines: u32, max_bytes: u64) -> std::io::Result<(u64, bool)> {
+    let mut pos = size;
+    let mut newlines = 0u32;
+    let mut skip_trailing = true;
+    while pos > 0 {
+        let step = TAIL_CHUNK.min(pos);
+        pos -= step;
+        let chunk = read_window(file, pos, step as usize)?;
+        for (i, &b) in chunk.iter().enumerate().rev() {
+            if skip_trailing {
+                skip_trailing = false;
+                if b == b'\n' {
+                    continue;
+                }
+            }
+            if b == b'\n' {
+                newlines += 1;
+                if newlines >= lines {
+                    return Ok((pos + i as u64 + 1, true));
+                }
+            }
+        }
+        if size - pos > max_bytes {
+            return Ok((size - max_bytes, false));
+        }
+    }
+    Ok((0, true))
+}
+
+/// Read a text file, a binary header or the end of a log.
+///
+/// - `offset` starts the window anywhere in the file (clamped to its size);
+///   `max_chars` is the window length (in bytes for hex).
+/// - `encoding = "hex"` returns a hexdump instead of rejecting binary data.
+/// - `tail_lines` returns the last N lines by seeking from the end.
 #[tauri::command]
 pub async fn file_read_content(
     path: String,
     max_chars: Option<usize>,
-) -> Result<FileContentResponse, String> {
-    backend_info(format!("Command file_read_content invoked: path='{}'", path));
+    offset: Option<u64>,
+    encoding: Option<String>,
+    tail_lines: Option<u32>,
+) -> Result<FileContentResponse, BroxeenError> {
+    backend_info(format!(
+        "Command file_read_content invoked: path='{}', offset={:?}, encoding={:?}, tail_lines={:?}",
+        path, offset, encoding, tail_lines
+    ));
 
     let file_path = Path::new(&path);
     if !file_path.exists() {
-        return Err(format!("Plik nie istnieje: {}", path));
+        return Err(BroxeenError::not_found(format!("Plik nie istnieje: {}", path)));
     }
 
-    let metadata = fs::metadata(file_path).map_err(|e| format!("Nie można odczytać metadanych: {}", e))?;
+    let metadata = fs::metadata(file_path).map_err(|e| BroxeenError::from(e).context("Nie można odczytać metadanych"))?;
     if metadata.is_dir() {
-        return Err("Podana ścieżka jest katalogiem, nie plikiem.".to_string());
+        return Err(BroxeenError::invalid_input("Podana ścieżka jest katalogiem, nie plikiem."));
+    }
+
+    let hex = match encoding.as_deref().map(|e| e.trim().to_lowercase()) {
+        None => false,
+        Some(e) if e.is_empty() || e == "text" || e == "utf-8" || e == "utf8" => false,
+        Some(e) if e == "hex" => true,
+        Some(other) => {
+            return Err(BroxeenError::invalid_input(format!("Nieobsługiwane kodowanie: {} (dostępne: text, hex)", other)))
+        }
+    };
+    if hex && tail_lines.is_some() {
+        return Err(BroxeenError::invalid_input("tail_lines nie obsługuje kodowania hex — użyj offset."));
     }
 
     let ext = file_path
@@ -305,39 +753,86 @@ pub async fn file_read_content(
         .unwrap_or_else(|| path.clone());
 
     let mime = guess_mime_type(&ext);
-    let max = max_chars.unwrap_or(10_000);
+    let size = metadata.len();
+    let start = offset.unwrap_or(0).min(size);
+    let read_err = |e: std::io::Error| BroxeenError::from(e).context("Nie można odczytać pliku");
+    let open = || fs::File::open(file_path).map_err(read_err);
 
-    // For binary files, return base64
-    let (content, truncated) = if is_text_file(&ext) {
-        let full = fs::read_to_string(file_path)
-            .map_err(|e| format!("Nie można odczytać pliku: {}", e))?;
-        let trunc = full.len() > max;
+    let (content, truncated, start) = if hex {
+        let len = max_chars.unwrap_or(HEXDUMP_DEFAULT_BYTES).min(HEXDUMP_MAX_BYTES);
+        let bytes = read_window(&mut open()?, start, len).map_err(read_err)?;
+        let truncated = start + (bytes.len() as u64) < size;
+        (format_hexdump(&bytes, start), truncated, start)
+    } else if let Some(lines) = tail_lines {
+        let max = max_chars.unwrap_or(10_000);
+        // Up to 4 bytes per char, so this always covers `max` characters
+        let max_bytes = (max as u64).saturating_mul(4);
+        let mut file = open()?;
+        let (tail_at, complete) = tail_start(&mut file, size, lines.max(1), max_bytes).map_err(read_err)?;
+        let bytes = read_window(&mut file, tail_at, (size - tail_at) as usize).map_err(read_err)?;
+        let text = decode_window(&bytes, tail_at > 0, false);
+        let count = text.chars().count();
+        // Too long for `max_chars`: keep the end, it is the interesting part
+        let text = if count > max { text.chars().skip(count - max).collect() } else { text };
+        (text, !complete || count > max, tail_at)
+    } else if is_text_file(&ext) || offset.is_some() {
+        let max = max_chars.unwrap_or(10_000);
+        let window = max.saturating_mul(4);
+        let bytes = read_window(&mut open()?, start, window).map_err(read_err)?;
+        let cut = start + (bytes.len() as u64) < size;
+        let full = decode_window(&bytes, start > 0, cut);
+        let trunc = cut || full.chars().count() > max;
         let text: String = full.chars().take(max).collect();
-        (text, trunc)
-    } else if mime.starts_with("image/") && metadata.len() < 10_000_000 {
+        (text, trunc, start)
+    } else if mime.starts_with("image/") && size < 10_000_000 {
         // Return base64 for images
         use base64::Engine as _;
-        let bytes = fs::read(file_path)
-            .map_err(|e| format!("Nie można odczytać pliku: {}", e))?;
+        let bytes = fs::read(file_path).map_err(read_err)?;
         let b64 = base64::engine::general_purpose::STANDARD.encode(&bytes);
-        (format!("data:{};base64,{}", mime, b64), false)
+        (format!("data:{};base64,{}", mime, b64), false, 0)
     } else {
-        (format!("[Plik binarny: {} — {} bajtów]", mime, metadata.len()), false)
+        (format!("[Plik binarny: {} — {} bajtów]", mime, size), false, 0)
     };
 
     Ok(FileContentResponse {
         path,
         name,
         content,
-        size_bytes: metadata.len(),
+        size_bytes: size,
         mime_type: mime.to_string(),
         truncated,
+        offset: start,
     })
 }
 
+/// Delete a regular file; each call is recorded in the audit log.
+/// Directories are refused — one wrong path must not take a tree with it.
+#[tauri::command]
+pub fn file_delete(path: String, initiator: Option<String>) -> Result<(), BroxeenError> {
+    backend_info(format!("Command file_delete invoked: path='{}'", path));
+    let audit_params = serde_json::json!({ "path": path });
+    let result = delete_file(Path::new(&path));
+    crate::audit::audited("file_delete", initiator.as_deref(), audit_params, result)
+}
+
+fn delete_file(path: &Path) -> Result<(), BroxeenError> {
+    let metadata = fs::symlink_metadata(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            BroxeenError::not_found(format!("Plik nie istnieje: {}", path.display()))
+        } else {
+            BroxeenError::from(e).context("Nie można odczytać metadanych")
+        }
+    })?;
+    if metadata.is_dir() {
+        return Err(BroxeenError::invalid_input("Podana ścieżka jest katalogiem — usuwane są tylko pliki."));
+    }
+    fs::remove_file(path).map_err(|e| BroxeenError::from(e).context("Nie można usunąć pliku"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::ErrorCode;
     use std::fs;
     use std::path::PathBuf;
     use tempfile::TempDir;
@@ -368,6 +863,10 @@ mod tests {
             None,
             Some(10),
             Some(5),
+            None,
+            None,
+            None,
+            None,
         ).await.unwrap();
 
         assert_eq!(result.total_found, 1);
@@ -393,6 +892,10 @@ mod tests {
             Some(vec!["rs".to_string()]),
             Some(10),
             Some(5),
+            None,
+            None,
+            None,
+            None,
         ).await.unwrap();
 
         assert_eq!(result.total_found, 2);
@@ -415,6 +918,10 @@ mod tests {
             None,
             Some(10),
             Some(5),
+            None,
+            None,
+            None,
+            None,
         ).await.unwrap();
 
         assert_eq!(result.total_found, 3);
@@ -434,6 +941,10 @@ mod tests {
             None,
             Some(5),
             Some(5),
+            None,
+            None,
+            None,
+            None,
         ).await.unwrap();
 
         assert_eq!(result.total_found, 5);
@@ -448,10 +959,130 @@ mod tests {
             None,
             Some(10),
             Some(5),
+            None,
+            None,
+            None,
+            None,
         ).await;
 
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("nie istnieje"));
+        let err = result.unwrap_err();
+        assert_eq!(err.code, ErrorCode::NotFound);
+        assert!(err.message.contains("nie istnieje"));
+    }
+
+    #[tokio::test]
+    async fn test_file_search_content_polish() {
+        let temp_dir = TempDir::new().unwrap();
+
+        create_test_files(&temp_dir, &[
+            ("notatki/styczeń.md", "# Notatki\nZapłacić FAKTURA 2024/01 do końca miesiąca\nżółć"),
+            ("inne.txt", "nic ciekawego"),
+            ("obraz.bin", "faktura 2024\0binary"),
+        ]);
+
+        let result = file_search(
+            "faktura 2024".to_string(),
+            Some(temp_dir.path().to_str().unwrap().to_string()),
+            None,
+            Some(10),
+            Some(5),
+            Some(true),
+            None,
+            None,
+            None,
+        ).await.unwrap();
+
+        assert_eq!(result.total_found, 1);
+        let hit = &result.results[0];
+        assert_eq!(hit.name, "styczeń.md");
+        assert_eq!(hit.matches, vec![ContentMatch {
+            line: 2,
+            text: "Zapłacić FAKTURA 2024/01 do końca miesiąca".to_string(),
+        }]);
+        assert!(!result.truncated);
+
+        // Polish case folding
+        let result = file_search(
+            "".to_string(),
+            Some(temp_dir.path().to_str().unwrap().to_string()),
+            None,
+            Some(10),
+            Some(5),
+            Some(true),
+            Some("ŻÓŁĆ".to_string()),
+            None,
+            None,
+        ).await.unwrap();
+        assert_eq!(result.results[0].matches[0].line, 3);
+    }
+
+    #[tokio::test]
+    async fn test_file_search_content_skips_large_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let big = format!("needle\n{}", "x".repeat(CONTENT_MAX_FILE_BYTES as usize));
+        create_test_files(&temp_dir, &[("big.log", big.as_str()), ("small.log", "a needle here")]);
+
+        let result = file_search(
+            "needle".to_string(),
+            Some(temp_dir.path().to_str().unwrap().to_string()),
+            None,
+            Some(10),
+            Some(5),
+            Some(true),
+            None,
+            None,
+            None,
+        ).await.unwrap();
+
+        assert_eq!(result.total_found, 1);
+        assert_eq!(result.results[0].name, "small.log");
+    }
+
+    #[tokio::test]
+    async fn test_file_search_ranking() {
+        let temp_dir = TempDir::new().unwrap();
+
+        create_test_files(&temp_dir, &[
+            ("archiwum/stary_raport_2019.txt", "old"),
+            ("raport-final.txt", "final"),
+            ("docs/raport.txt", "draft"),
+            ("rpt_notes.txt", "unrelated"),
+            ("rap-ort.txt", "fuzzy only"),
+        ]);
+        let path = Some(temp_dir.path().to_str().unwrap().to_string());
+
+        let result = file_search("raport".to_string(), path.clone(), None, Some(10), Some(5), None, None, None, None)
+            .await
+            .unwrap();
+        let names: Vec<&str> = result.results.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["raport.txt", "raport-final.txt", "stary_raport_2019.txt"]);
+        assert_eq!(result.results[0].score, SCORE_EXACT);
+        assert!(result.results[1].score > result.results[2].score);
+
+        // Fuzzy (only when nothing contains the query) picks up abbreviations;
+        // limit applies after ranking
+        let result = file_search("rprt".to_string(), path.clone(), None, Some(1), Some(5), None, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(result.results[0].name, "raport.txt");
+        assert!(result.truncated);
+
+        let result = file_search("rprt".to_string(), path, None, Some(10), Some(5), None, None, Some(false), None)
+            .await
+            .unwrap();
+        assert_eq!(result.total_found, 0);
+    }
+
+    #[test]
+    fn test_name_score_tiers() {
+        assert_eq!(name_score("Raport.TXT", "raport", true), SCORE_EXACT);
+        assert!(name_score("raport-final.txt", "raport", true) >= SCORE_PREFIX);
+        let substring = name_score("stary_raport_2019.txt", "raport", true);
+        assert!((SCORE_SUBSTRING..SCORE_PREFIX).contains(&substring));
+        assert!(name_score("rpt_notes.txt", "raport", true) == 0);
+        assert!((1..=SCORE_FUZZY_MAX).contains(&name_score("raport.txt", "rprt", true)));
+        assert_eq!(name_score("raport.txt", "rprt", false), 0);
     }
 
     #[tokio::test]
@@ -463,6 +1094,9 @@ mod tests {
         let result = file_read_content(
             test_file.to_str().unwrap().to_string(),
             Some(100),
+            None,
+            None,
+            None,
         ).await.unwrap();
 
         assert_eq!(result.name, "test.txt");
@@ -493,6 +1127,9 @@ mod tests {
         let result = file_read_content(
             test_file.to_str().unwrap().to_string(),
             Some(100),
+            None,
+            None,
+            None,
         ).await.unwrap();
 
         assert_eq!(result.name, "test.png");
@@ -510,6 +1147,9 @@ mod tests {
         let result = file_read_content(
             test_file.to_str().unwrap().to_string(),
             Some(100),
+            None,
+            None,
+            None,
         ).await.unwrap();
 
         assert_eq!(result.name, "test.bin");
@@ -523,10 +1163,14 @@ mod tests {
         let result = file_read_content(
             "/nonexistent/file.txt".to_string(),
             Some(100),
+            None,
+            None,
+            None,
         ).await;
 
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("nie istnieje"));
+        let err = result.unwrap_err();
+        assert_eq!(err.code, ErrorCode::NotFound);
+        assert!(err.message.contains("nie istnieje"));
     }
 
     #[tokio::test]
@@ -536,10 +1180,77 @@ mod tests {
         let result = file_read_content(
             temp_dir.path().to_str().unwrap().to_string(),
             Some(100),
+            None,
+            None,
+            None,
         ).await;
 
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("katalogiem"));
+        let err = result.unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidInput);
+        assert!(err.message.contains("katalogiem"));
+    }
+
+    #[tokio::test]
+    async fn test_file_read_content_tail_of_large_log() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = temp_dir.path().join("app.log");
+        let mut content = String::with_capacity(10_500_000);
+        let mut n = 0;
+        while content.len() < 10 * 1024 * 1024 {
+            content.push_str(&format!("2024-07-14 12:00:00 INFO line {:07}\n", n));
+            n += 1;
+        }
+        fs::write(&log, &content).unwrap();
+
+        let result = file_read_content(log.to_str().unwrap().to_string(), None, None, None, Some(3))
+            .await
+            .unwrap();
+        let lines: Vec<&str> = result.content.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[2].ends_with(&format!("line {:07}", n - 1)));
+        assert!(lines[0].ends_with(&format!("line {:07}", n - 3)));
+        assert!(!result.truncated);
+        assert_eq!(result.offset + result.content.len() as u64, result.size_bytes);
+
+        // Offset window in the middle, clamped past the end
+        let mid = file_read_content(log.to_str().unwrap().to_string(), Some(20), Some(5_000_000), None, None)
+            .await
+            .unwrap();
+        assert_eq!(mid.offset, 5_000_000);
+        assert_eq!(mid.content.chars().count(), 20);
+        assert!(mid.truncated);
+        let end = file_read_content(log.to_str().unwrap().to_string(), Some(20), Some(u64::MAX), None, None)
+            .await
+            .unwrap();
+        assert_eq!(end.offset, end.size_bytes);
+        assert!(end.content.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_file_read_content_hexdump() {
+        let temp_dir = TempDir::new().unwrap();
+        let bin = temp_dir.path().join("firmware.bin");
+        let mut bytes = b"\x7fELF".to_vec();
+        bytes.extend(0u8..40);
+        fs::write(&bin, &bytes).unwrap();
+
+        let result = file_read_content(bin.to_str().unwrap().to_string(), None, None, Some("hex".into()), None)
+            .await
+            .unwrap();
+        let lines: Vec<&str> = result.content.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "00000000  7f 45 4c 46 00 01 02 03  04 05 06 07 08 09 0a 0b  |.ELF............|");
+        assert!(lines[2].starts_with("00000020  "));
+        assert!(!result.truncated);
+
+        let window = file_read_content(bin.to_str().unwrap().to_string(), Some(4), Some(1), Some("HEX".into()), None)
+            .await
+            .unwrap();
+        assert_eq!(window.content.trim_end(), format!("00000001  45 4c 46 00{}|ELF.|", " ".repeat(39)));
+        assert!(window.truncated);
+
+        let bad = file_read_content(bin.to_str().unwrap().to_string(), None, None, Some("ebcdic".into()), None).await;
+        assert_eq!(bad.unwrap_err().code, ErrorCode::InvalidInput);
     }
 
     #[test]
@@ -579,4 +1290,33 @@ mod tests {
         let formatted = format_time(time).unwrap();
         assert_eq!(formatted, "2022-01-01 00:00:00");
     }
+
+    #[test]
+    fn test_file_delete_is_audited() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_files(&temp_dir, &[("old.log", "x")]);
+        let file = temp_dir.path().join("old.log").to_string_lossy().to_string();
+        let dir = temp_dir.path().to_string_lossy().to_string();
+
+        let (results, rows) = crate::audit::capture(|| {
+            [
+                file_delete(file.clone(), Some("voice".into())),
+                file_delete(file.clone(), None),
+                file_delete(dir.clone(), None),
+            ]
+        });
+        assert!(results[0].is_ok());
+        assert!(!Path::new(&file).exists());
+        assert_eq!(results[1].as_ref().unwrap_err().code, ErrorCode::NotFound);
+        assert_eq!(results[2].as_ref().unwrap_err().code, ErrorCode::InvalidInput);
+        assert!(temp_dir.path().exists());
+
+        let outcomes: Vec<(&str, &str, &str)> =
+            rows.iter().map(|r| (r.command.as_str(), r.outcome.as_str(), r.initiator.as_str())).collect();
+        assert_eq!(
+            outcomes,
+            [("file_delete", "ok", "voice"), ("file_delete", "error", "ui"), ("file_delete", "error", "ui")]
+        );
+        assert_eq!(rows[0].params["path"], file.as_str());
+    }
 }
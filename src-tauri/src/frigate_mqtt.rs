@@ -1,6 +1,9 @@
+use crate::http_client::{self, RetryPolicy};
 use crate::logging::{backend_error, backend_info, backend_warn};
 use rumqttc::{AsyncClient, Event, Incoming, MqttOptions, QoS};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tauri::AppHandle;
@@ -10,6 +13,12 @@ use tokio::task::JoinHandle;
 
 const FRIGATE_EVENT_NAME: &str = "broxeen:frigate_event";
 
+/// Events kept in memory regardless of the retention window
+const MAX_BUFFERED_EVENTS: usize = 2000;
+const DEFAULT_RETENTION_MINUTES: u64 = 24 * 60;
+const RECONNECT_BACKOFF_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FrigateMqttEvent {
     pub topic: String,
@@ -25,6 +34,47 @@ pub struct FrigateMqttConfig {
     pub password: Option<String>,
     pub topic: String,
     pub client_id: Option<String>,
+    /// Frigate HTTP API, e.g. `http://frigate.local:5000` (snapshots)
+    #[serde(default)]
+    pub http_base_url: Option<String>,
+    /// How long buffered events are kept (default 24 h)
+    #[serde(default)]
+    pub retention_minutes: Option<u64>,
+}
+
+/// One Frigate detection, folded from its new/update/end messages.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FrigateEventRecord {
+    pub id: String,
+    /// Last message type: "new", "update" or "end"
+    pub event_type: String,
+    pub camera: String,
+    pub label: String,
+    pub score: f64,
+    /// Unix seconds (as sent by Frigate)
+    pub start_time: f64,
+    pub end_time: Option<f64>,
+    pub has_snapshot: bool,
+    pub has_clip: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct FrigateMqttStatus {
+    pub running: bool,
+    pub connected: bool,
+    pub reconnect_attempts: u32,
+    pub last_error: Option<String>,
+    pub last_connected_at: Option<i64>,
+    pub buffered_events: usize,
+    pub http_base_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FrigateSnapshot {
+    pub event_id: String,
+    pub mime_type: String,
+    pub size_bytes: usize,
+    pub base64: String,
 }
 
 struct FrigateMqttRuntime {
@@ -32,8 +82,62 @@ struct FrigateMqttRuntime {
     join_handle: JoinHandle<()>,
 }
 
+#[derive(Default)]
+struct EventBuffer {
+    events: VecDeque<FrigateEventRecord>,
+    retention_secs: f64,
+}
+
+impl EventBuffer {
+    /// Insert or update by id, then drop events outside the retention window.
+    fn upsert(&mut self, record: FrigateEventRecord, now_secs: f64) {
+        match self.events.iter_mut().find(|e| e.id == record.id) {
+            Some(existing) => *existing = record,
+            None => self.events.push_back(record),
+        }
+        self.prune(now_secs);
+    }
+
+    fn prune(&mut self, now_secs: f64) {
+        let cutoff = now_secs - self.retention_secs;
+        self.events.retain(|e| e.end_time.unwrap_or(e.start_time) >= cutoff);
+        while self.events.len() > MAX_BUFFERED_EVENTS {
+            self.events.pop_front();
+        }
+    }
+
+    /// Newest first, optionally filtered by camera / label (case-insensitive).
+    fn recent(&self, camera: Option<&str>, label: Option<&str>, limit: usize) -> Vec<FrigateEventRecord> {
+        let mut matching: Vec<FrigateEventRecord> = self
+            .events
+            .iter()
+            .filter(|e| camera.is_none_or(|c| e.camera.eq_ignore_ascii_case(c)))
+            .filter(|e| label.is_none_or(|l| e.label.eq_ignore_ascii_case(l)))
+            .cloned()
+            .collect();
+        matching.sort_by(|a, b| b.start_time.total_cmp(&a.start_time));
+        matching.truncate(limit);
+        matching
+    }
+}
+
 lazy_static::lazy_static! {
     static ref RUNTIME: Arc<Mutex<Option<FrigateMqttRuntime>>> = Arc::new(Mutex::new(None));
+    static ref EVENTS: Mutex<EventBuffer> = Mutex::new(EventBuffer {
+        events: VecDeque::new(),
+        retention_secs: (DEFAULT_RETENTION_MINUTES * 60) as f64,
+    });
+    static ref STATUS: Mutex<FrigateMqttStatus> = Mutex::new(FrigateMqttStatus::default());
+}
+
+fn update_status(f: impl FnOnce(&mut FrigateMqttStatus)) {
+    if let Ok(mut status) = STATUS.lock() {
+        f(&mut status);
+    }
+}
+
+fn now_secs() -> f64 {
+    chrono::Utc::now().timestamp_millis() as f64 / 1000.0
 }
 
 #[tauri::command]
@@ -44,12 +148,35 @@ pub async fn frigate_mqtt_start(app: AppHandle, config: FrigateMqttConfig) -> Re
             return Ok("already_running".to_string());
         }
 
+        if let Ok(mut buffer) = EVENTS.lock() {
+            let minutes = config.retention_minutes.unwrap_or(DEFAULT_RETENTION_MINUTES).max(1);
+            buffer.retention_secs = (minutes * 60) as f64;
+            buffer.prune(now_secs());
+        }
+        let base_url = config
+            .http_base_url
+            .as_ref()
+            .map(|u| u.trim().trim_end_matches('/').to_string())
+            .filter(|u| !u.is_empty());
+        update_status(|s| {
+            *s = FrigateMqttStatus {
+                running: true,
+                http_base_url: base_url,
+                ..Default::default()
+            }
+        });
+
         let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
 
         let join_handle = tokio::spawn(async move {
             if let Err(err) = run_loop(app, config, shutdown_rx).await {
                 backend_error(format!("frigate_mqtt loop exited with error: {}", err));
+                update_status(|s| s.last_error = Some(err));
             }
+            update_status(|s| {
+                s.running = false;
+                s.connected = false;
+            });
         });
 
         *guard = Some(FrigateMqttRuntime {
@@ -79,6 +206,114 @@ pub async fn frigate_mqtt_stop() -> Result<String, String> {
     }
 }
 
+#[tauri::command]
+pub fn frigate_mqtt_status() -> Result<FrigateMqttStatus, String> {
+    let mut status = STATUS
+        .lock()
+        .map_err(|_| "frigate_mqtt status lock poisoned".to_string())?
+        .clone();
+    status.buffered_events = EVENTS.lock().map(|b| b.events.len()).unwrap_or(0);
+    Ok(status)
+}
+
+/// Buffered Frigate events, newest first (default limit 50).
+#[tauri::command]
+pub fn frigate_recent_events(
+    camera: Option<String>,
+    label: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<FrigateEventRecord>, String> {
+    let mut buffer = EVENTS.lock().map_err(|_| "frigate_mqtt events lock poisoned".to_string())?;
+    buffer.prune(now_secs());
+    Ok(buffer.recent(camera.as_deref(), label.as_deref(), limit.unwrap_or(50)))
+}
+
+/// Fetch the event snapshot JPEG from the Frigate HTTP API as base64.
+#[tauri::command]
+pub async fn frigate_get_snapshot(event_id: String) -> Result<FrigateSnapshot, String> {
+    let base_url = STATUS
+        .lock()
+        .map_err(|_| "frigate_mqtt status lock poisoned".to_string())?
+        .http_base_url
+        .clone()
+        .ok_or("Frigate HTTP URL nie jest skonfigurowany (http_base_url)")?;
+    let url = snapshot_url(&base_url, &event_id)?;
+    backend_info(format!("frigate_get_snapshot: {}", url));
+
+    let client = http_client::client(Duration::from_secs(15))?;
+    let (resp, meta) = http_client::send_with_retry(client.get(&url), &RetryPolicy::default().max_retries(2)).await?;
+    let status = resp.status();
+    if status == reqwest::StatusCode::NOT_FOUND {
+        return Err(format!("Frigate nie ma snapshotu dla zdarzenia {}", event_id));
+    }
+    if !status.is_success() {
+        return Err(format!("Frigate HTTP {}{}", status, meta.describe()));
+    }
+    let mime_type = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("image/jpeg")
+        .to_string();
+    let bytes = resp
+        .bytes()
+        .await
+        .map_err(|e| format!("Frigate snapshot read failed: {}", e))?;
+
+    use base64::Engine;
+    Ok(FrigateSnapshot {
+        event_id,
+        mime_type,
+        size_bytes: bytes.len(),
+        base64: base64::engine::general_purpose::STANDARD.encode(&bytes),
+    })
+}
+
+/// Frigate ids look like `1700000000.123456-abc123`; anything else is
+/// rejected so it cannot escape the API path.
+fn snapshot_url(base_url: &str, event_id: &str) -> Result<String, String> {
+    let valid = !event_id.is_empty()
+        && event_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_');
+    if !valid {
+        return Err(format!("Nieprawidłowe ID zdarzenia Frigate: {}", event_id));
+    }
+    Ok(format!(
+        "{}/api/events/{}/snapshot.jpg",
+        base_url.trim_end_matches('/'),
+        event_id
+    ))
+}
+
+/// Parse a `frigate/events` payload (`{"type", "before", "after"}`).
+fn parse_event(payload: &str) -> Option<FrigateEventRecord> {
+    let value: Value = serde_json::from_str(payload).ok()?;
+    let event_type = value.get("type")?.as_str()?.to_string();
+    let after = value.get("after").or_else(|| value.get("before"))?;
+
+    let score = after
+        .get("top_score")
+        .and_then(Value::as_f64)
+        .or_else(|| after.get("score").and_then(Value::as_f64))
+        .unwrap_or(0.0);
+
+    Some(FrigateEventRecord {
+        id: after.get("id")?.as_str()?.to_string(),
+        event_type,
+        camera: after.get("camera").and_then(Value::as_str).unwrap_or_default().to_string(),
+        label: after.get("label").and_then(Value::as_str).unwrap_or_default().to_string(),
+        score,
+        start_time: after.get("start_time").and_then(Value::as_f64).unwrap_or_else(now_secs),
+        end_time: after.get("end_time").and_then(Value::as_f64),
+        has_snapshot: after.get("has_snapshot").and_then(Value::as_bool).unwrap_or(false),
+        has_clip: after.get("has_clip").and_then(Value::as_bool).unwrap_or(false),
+    })
+}
+
+/// Connect, subscribe and forward messages until shutdown. Broker errors do
+/// not end the loop: polling resumes after an exponential backoff, and the
+/// subscription is renewed on every ConnAck.
 async fn run_loop(app: AppHandle, config: FrigateMqttConfig, mut shutdown_rx: oneshot::Receiver<()>) -> Result<(), String> {
     let client_id = config
         .client_id
@@ -99,12 +334,7 @@ async fn run_loop(app: AppHandle, config: FrigateMqttConfig, mut shutdown_rx: on
         config.host, config.port, config.topic
     ));
 
-    client
-        .subscribe(config.topic.clone(), QoS::AtMostOnce)
-        .await
-        .map_err(|e| format!("MQTT subscribe failed: {}", e))?;
-
-    backend_info("frigate_mqtt subscribed");
+    let mut backoff = RECONNECT_BACKOFF_MIN;
 
     loop {
         tokio::select! {
@@ -118,6 +348,14 @@ async fn run_loop(app: AppHandle, config: FrigateMqttConfig, mut shutdown_rx: on
                         let topic = p.topic.clone();
                         let payload = String::from_utf8_lossy(&p.payload).to_string();
 
+                        if topic.ends_with("/events") {
+                            if let Some(record) = parse_event(&payload) {
+                                if let Ok(mut buffer) = EVENTS.lock() {
+                                    buffer.upsert(record, now_secs());
+                                }
+                            }
+                        }
+
                         let msg = FrigateMqttEvent {
                             topic,
                             payload,
@@ -130,16 +368,44 @@ async fn run_loop(app: AppHandle, config: FrigateMqttConfig, mut shutdown_rx: on
                     }
                     Ok(Event::Incoming(Incoming::ConnAck(_))) => {
                         backend_info("frigate_mqtt connected".to_string());
+                        backoff = RECONNECT_BACKOFF_MIN;
+                        update_status(|s| {
+                            s.connected = true;
+                            s.last_error = None;
+                            s.last_connected_at = Some(chrono::Utc::now().timestamp_millis());
+                        });
+                        client
+                            .subscribe(config.topic.clone(), QoS::AtMostOnce)
+                            .await
+                            .map_err(|e| format!("MQTT subscribe failed: {}", e))?;
+                        backend_info("frigate_mqtt subscribed");
                     }
                     Ok(Event::Incoming(Incoming::Disconnect)) => {
                         backend_warn("frigate_mqtt disconnected".to_string());
+                        update_status(|s| s.connected = false);
                     }
                     Ok(_) => {
                         // ignore other events
                     }
                     Err(e) => {
-                        backend_error(format!("frigate_mqtt poll error: {}", e));
-                        tokio::time::sleep(Duration::from_secs(2)).await;
+                        backend_error(format!(
+                            "frigate_mqtt poll error: {} (reconnecting in {}s)",
+                            e,
+                            backoff.as_secs()
+                        ));
+                        update_status(|s| {
+                            s.connected = false;
+                            s.reconnect_attempts += 1;
+                            s.last_error = Some(e.to_string());
+                        });
+                        tokio::select! {
+                            _ = &mut shutdown_rx => {
+                                backend_info("frigate_mqtt shutdown requested");
+                                break;
+                            }
+                            _ = tokio::time::sleep(backoff) => {}
+                        }
+                        backoff = (backoff * 2).min(RECONNECT_BACKOFF_MAX);
                     }
                 }
             }
@@ -153,3 +419,71 @@ async fn run_loop(app: AppHandle, config: FrigateMqttConfig, mut shutdown_rx: on
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event_payload(kind: &str, id: &str, camera: &str, start: f64, end: Option<f64>) -> String {
+        serde_json::json!({
+            "type": kind,
+            "before": {},
+            "after": {
+                "id": id,
+                "camera": camera,
+                "label": "person",
+                "top_score": 0.82,
+                "start_time": start,
+                "end_time": end,
+                "has_snapshot": true,
+                "has_clip": false
+            }
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_parse_event() {
+        let record = parse_event(&event_payload("new", "1700000000.5-abc", "front", 1_700_000_000.5, None)).unwrap();
+        assert_eq!(record.id, "1700000000.5-abc");
+        assert_eq!(record.camera, "front");
+        assert_eq!(record.label, "person");
+        assert_eq!(record.score, 0.82);
+        assert!(record.has_snapshot);
+        assert_eq!(record.end_time, None);
+        assert!(parse_event("not json").is_none());
+    }
+
+    #[test]
+    fn test_buffer_updates_filters_and_prunes() {
+        let now = 10_000.0;
+        let mut buffer = EventBuffer {
+            events: VecDeque::new(),
+            retention_secs: 3600.0,
+        };
+        buffer.upsert(parse_event(&event_payload("new", "a", "front", now - 100.0, None)).unwrap(), now);
+        buffer.upsert(parse_event(&event_payload("new", "b", "garden", now - 50.0, None)).unwrap(), now);
+        buffer.upsert(parse_event(&event_payload("end", "a", "front", now - 100.0, Some(now - 10.0))).unwrap(), now);
+
+        let all = buffer.recent(None, None, 10);
+        assert_eq!(all.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec!["b", "a"]);
+        assert_eq!(all[1].event_type, "end");
+
+        let front = buffer.recent(Some("FRONT"), Some("person"), 10);
+        assert_eq!(front.len(), 1);
+        assert!(buffer.recent(None, Some("car"), 10).is_empty());
+
+        buffer.prune(now + 3600.0 - 20.0);
+        assert_eq!(buffer.recent(None, None, 10).len(), 1);
+    }
+
+    #[test]
+    fn test_snapshot_url_validates_id() {
+        assert_eq!(
+            snapshot_url("http://frigate:5000/", "1700000000.5-abc").unwrap(),
+            "http://frigate:5000/api/events/1700000000.5-abc/snapshot.jpg"
+        );
+        assert!(snapshot_url("http://frigate:5000", "../config").is_err());
+        assert!(snapshot_url("http://frigate:5000", "").is_err());
+    }
+}
@@ -0,0 +1,318 @@
+//! Reverse geocoding for camera / location metadata.
+//!
+//! Uses Nominatim (OpenStreetMap) by default. Any Nominatim-compatible
+//! `/reverse` endpoint can be configured via `BROXEEN_GEOCODE_URL`.
+//!
+//! Nominatim usage policy: max 1 request/second and an identifying
+//! User-Agent. Results are cached on disk, keyed by coordinates rounded to
+//! 4 decimal places (~11 m), so repeated lookups never re-hit the API.
+//!
+//! Offline mode (`BROXEEN_OFFLINE=1`) serves from cache only.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::logging::{backend_info, backend_warn};
+
+const DEFAULT_GEOCODE_URL: &str = "https://nominatim.openstreetmap.org/reverse";
+const USER_AGENT: &str = "Broxeen/1.0 (https://github.com/wronai/broxeen)";
+const MIN_REQUEST_INTERVAL: Duration = Duration::from_millis(1000);
+const CACHE_FILE: &str = "geocode_cache.json";
+
+// ── Types ────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeocodeResult {
+    pub lat: f64,
+    pub lon: f64,
+    /// Short human-readable location, e.g. "Lipowa 12, Gdańsk".
+    pub label: String,
+    /// Full `display_name` as returned by the provider.
+    pub display_name: String,
+    pub cached: bool,
+    pub fetched_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GeocodeCacheStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub api_requests: u64,
+    pub api_errors: u64,
+    pub offline: bool,
+    pub provider_url: String,
+    pub cache_path: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct NominatimResponse {
+    display_name: Option<String>,
+    address: Option<HashMap<String, String>>,
+    error: Option<String>,
+}
+
+// ── Global state ─────────────────────────────────────────────────
+
+lazy_static::lazy_static! {
+    static ref CACHE: Mutex<Option<HashMap<String, GeocodeResult>>> = Mutex::new(None);
+    static ref LAST_REQUEST: tokio::sync::Mutex<Option<Instant>> = tokio::sync::Mutex::new(None);
+}
+
+static CACHE_HITS: AtomicU64 = AtomicU64::new(0);
+static CACHE_MISSES: AtomicU64 = AtomicU64::new(0);
+static API_REQUESTS: AtomicU64 = AtomicU64::new(0);
+static API_ERRORS: AtomicU64 = AtomicU64::new(0);
+
+fn cache_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("broxeen")
+        .join(CACHE_FILE)
+}
+
+fn provider_url() -> String {
+    std::env::var("BROXEEN_GEOCODE_URL")
+        .ok()
+        .filter(|u| !u.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_GEOCODE_URL.to_string())
+}
+
+pub fn is_offline() -> bool {
+    std::env::var("BROXEEN_OFFLINE")
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Cache key: coordinates rounded to 4 decimal places.
+fn cache_key(lat: f64, lon: f64) -> String {
+    format!("{:.4},{:.4}", lat, lon)
+}
+
+fn with_cache<R>(f: impl FnOnce(&mut HashMap<String, GeocodeResult>) -> R) -> R {
+    let mut guard = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    let cache = guard.get_or_insert_with(|| {
+        std::fs::read_to_string(cache_path())
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default()
+    });
+    f(cache)
+}
+
+fn persist_cache(cache: &HashMap<String, GeocodeResult>) {
+    let path = cache_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    match serde_json::to_string_pretty(cache) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(&path, json) {
+                backend_warn(format!("Failed to write geocode cache {}: {}", path.display(), e));
+            }
+        }
+        Err(e) => backend_warn(format!("Failed to serialize geocode cache: {}", e)),
+    }
+}
+
+/// Build a short label from the Nominatim address parts (street + number, city).
+fn short_label(address: &HashMap<String, String>, display_name: &str) -> String {
+    let street = ["road", "pedestrian", "footway", "path", "square"]
+        .iter()
+        .find_map(|k| address.get(*k));
+    let city = ["city", "town", "village", "hamlet", "suburb"]
+        .iter()
+        .find_map(|k| address.get(*k));
+
+    let mut parts = Vec::new();
+    if let Some(street) = street {
+        match address.get("house_number") {
+            Some(num) => parts.push(format!("{} {}", street, num)),
+            None => parts.push(street.clone()),
+        }
+    }
+    if let Some(city) = city {
+        parts.push(city.clone());
+    }
+
+    if parts.is_empty() {
+        display_name.split(',').take(2).map(str::trim).collect::<Vec<_>>().join(", ")
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// Wait until at least `MIN_REQUEST_INTERVAL` has passed since the last API call.
+async fn throttle() {
+    let mut last = LAST_REQUEST.lock().await;
+    if let Some(prev) = *last {
+        let elapsed = prev.elapsed();
+        if elapsed < MIN_REQUEST_INTERVAL {
+            tokio::time::sleep(MIN_REQUEST_INTERVAL - elapsed).await;
+        }
+    }
+    *last = Some(Instant::now());
+}
+
+// ── Public API ───────────────────────────────────────────────────
+
+/// Reverse-geocode coordinates, serving from cache when possible.
+pub async fn reverse(lat: f64, lon: f64) -> Result<GeocodeResult, String> {
+    if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+        return Err(format!("Nieprawidłowe współrzędne: {}, {}", lat, lon));
+    }
+
+    let key = cache_key(lat, lon);
+    if let Some(mut hit) = with_cache(|c| c.get(&key).cloned()) {
+        CACHE_HITS.fetch_add(1, Ordering::Relaxed);
+        hit.cached = true;
+        return Ok(hit);
+    }
+    CACHE_MISSES.fetch_add(1, Ordering::Relaxed);
+
+    if is_offline() {
+        return Err(format!("Tryb offline — brak lokalizacji w cache dla {}", key));
+    }
+
+    throttle().await;
+    API_REQUESTS.fetch_add(1, Ordering::Relaxed);
+
+    let url = provider_url();
+    backend_info(format!("Geocoding reverse lookup {} via {}", key, url));
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .user_agent(USER_AGENT)
+        .build()
+        .map_err(|e| format!("HTTP client error: {}", e))?;
+
+    let lat_s = format!("{:.6}", lat);
+    let lon_s = format!("{:.6}", lon);
+    let response = client
+        .get(&url)
+        .query(&[
+            ("format", "jsonv2"),
+            ("lat", lat_s.as_str()),
+            ("lon", lon_s.as_str()),
+            ("zoom", "18"),
+            ("addressdetails", "1"),
+        ])
+        .send()
+        .await
+        .map_err(|e| {
+            API_ERRORS.fetch_add(1, Ordering::Relaxed);
+            format!("Geocoding request failed: {}", e)
+        })?;
+
+    if !response.status().is_success() {
+        API_ERRORS.fetch_add(1, Ordering::Relaxed);
+        return Err(format!("Geocoding HTTP {}", response.status()));
+    }
+
+    let body: NominatimResponse = response.json().await.map_err(|e| {
+        API_ERRORS.fetch_add(1, Ordering::Relaxed);
+        format!("Geocoding parse error: {}", e)
+    })?;
+
+    if let Some(err) = body.error {
+        API_ERRORS.fetch_add(1, Ordering::Relaxed);
+        return Err(format!("Geocoding error: {}", err));
+    }
+
+    let display_name = body.display_name.unwrap_or_default();
+    let label = short_label(&body.address.unwrap_or_default(), &display_name);
+
+    let result = GeocodeResult {
+        lat,
+        lon,
+        label,
+        display_name,
+        cached: false,
+        fetched_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    with_cache(|c| {
+        c.insert(key, result.clone());
+        persist_cache(c);
+    });
+
+    Ok(result)
+}
+
+/// Human-readable location for templates (`{camera_location}`).
+/// Never fails — falls back to the raw coordinates.
+#[cfg_attr(not(feature = "vision"), allow(dead_code))]
+pub async fn location_label(lat: f64, lon: f64) -> String {
+    match reverse(lat, lon).await {
+        Ok(r) if !r.label.is_empty() => r.label,
+        Ok(_) => cache_key(lat, lon),
+        Err(e) => {
+            backend_warn(format!("Geocoding fallback to coordinates: {}", e));
+            cache_key(lat, lon)
+        }
+    }
+}
+
+pub fn cache_stats() -> GeocodeCacheStats {
+    GeocodeCacheStats {
+        entries: with_cache(|c| c.len()),
+        hits: CACHE_HITS.load(Ordering::Relaxed),
+        misses: CACHE_MISSES.load(Ordering::Relaxed),
+        api_requests: API_REQUESTS.load(Ordering::Relaxed),
+        api_errors: API_ERRORS.load(Ordering::Relaxed),
+        offline: is_offline(),
+        provider_url: provider_url(),
+        cache_path: cache_path().display().to_string(),
+    }
+}
+
+// ── Tauri commands ───────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn geocode_reverse(lat: f64, lon: f64) -> Result<GeocodeResult, String> {
+    reverse(lat, lon).await
+}
+
+#[tauri::command]
+pub fn geocode_cache_stats() -> GeocodeCacheStats {
+    cache_stats()
+}
+
+#[tauri::command]
+pub fn geocode_cache_clear() -> Result<usize, String> {
+    let removed = with_cache(|c| {
+        let n = c.len();
+        c.clear();
+        persist_cache(c);
+        n
+    });
+    backend_info(format!("Geocode cache cleared ({} entries)", removed));
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_key_rounds_coordinates() {
+        assert_eq!(cache_key(54.352025, 18.646638), "54.3520,18.6466");
+        assert_eq!(cache_key(54.35201, 18.64661), cache_key(54.35204, 18.64664));
+    }
+
+    #[test]
+    fn test_short_label_street_and_city() {
+        let mut addr = HashMap::new();
+        addr.insert("road".to_string(), "Lipowa".to_string());
+        addr.insert("house_number".to_string(), "12".to_string());
+        addr.insert("city".to_string(), "Gdańsk".to_string());
+        assert_eq!(short_label(&addr, ""), "Lipowa 12, Gdańsk");
+
+        let empty = HashMap::new();
+        assert_eq!(short_label(&empty, "Lipowa, Gdańsk, Polska"), "Lipowa, Gdańsk");
+    }
+}
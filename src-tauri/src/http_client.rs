@@ -0,0 +1,308 @@
+//! http_client.rs — Shared HTTP client factory with rate-limit aware retries.
+//!
+//! OpenRouter, registries, Nominatim and some camera firmwares answer
+//! 429 / 503 with `Retry-After`. `send_with_retry` handles that uniformly:
+//!
+//! - `Retry-After` (delta-seconds or HTTP-date) is honoured up to
+//!   `RetryPolicy::max_retry_after`; a longer wait is not slept through —
+//!   the response is returned so the caller can report it,
+//! - other 5xx and connection errors use jittered exponential backoff,
+//! - non-idempotent methods (POST, PATCH) are only retried when the caller
+//!   opts in with `RetryPolicy::allow_non_idempotent`,
+//! - every outcome carries `RetryMeta` (retries + total backoff), and errors
+//!   mention it.
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::Serialize;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::BroxeenError;
+use crate::logging::backend_warn;
+
+pub const USER_AGENT: &str = "Broxeen/1.0";
+
+// ── Client factory ───────────────────────────────────
+
+/// Builder with the app-wide defaults (User-Agent, timeout).
+pub fn client_builder(timeout: Duration) -> reqwest::ClientBuilder {
+    reqwest::Client::builder().timeout(timeout).user_agent(USER_AGENT)
+}
+
+pub fn client(timeout: Duration) -> Result<reqwest::Client, String> {
+    client_builder(timeout)
+        .build()
+        .map_err(|e| format!("HTTP client error: {}", e))
+}
+
+// ── Retry policy ─────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    /// Longest `Retry-After` we are willing to sleep through.
+    pub max_retry_after: Duration,
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            max_retry_after: Duration::from_secs(30),
+            base_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn max_retries(mut self, n: u32) -> Self {
+        self.max_retries = n;
+        self
+    }
+
+    pub fn max_retry_after(mut self, cap: Duration) -> Self {
+        self.max_retry_after = cap;
+        self
+    }
+
+    #[cfg_attr(not(feature = "vision"), allow(dead_code))]
+    pub fn base_backoff(mut self, base: Duration) -> Self {
+        self.base_backoff = base;
+        self
+    }
+
+    /// Opt in to retrying POST/PATCH (only safe when the server did not act,
+    /// e.g. 429, or the receiver deduplicates).
+    pub fn allow_non_idempotent(mut self) -> Self {
+        self.retry_non_idempotent = true;
+        self
+    }
+
+    /// Jittered exponential backoff for attempt `n` (0-based): base·2ⁿ ± 25 %.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self.base_backoff.saturating_mul(1u32 << attempt.min(16));
+        let capped = exp.min(self.max_backoff);
+        let jitter = (jitter_unit() - 0.5) * 0.5; // -0.25 … +0.25
+        capped.mul_f64(1.0 + jitter)
+    }
+}
+
+/// Pseudo-random value in [0, 1) — good enough to de-synchronise retries.
+fn jitter_unit() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    (nanos % 1_000_000) as f64 / 1_000_000.0
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct RetryMeta {
+    pub retries: u32,
+    pub total_backoff_ms: u64,
+}
+
+impl RetryMeta {
+    /// Suffix for error messages, empty when nothing was retried.
+    pub fn describe(&self) -> String {
+        if self.retries == 0 {
+            String::new()
+        } else {
+            format!(" (retries: {}, backoff: {} ms)", self.retries, self.total_backoff_ms)
+        }
+    }
+}
+
+// ── Retry-After ──────────────────────────────────────
+
+/// Parse `Retry-After`: delta-seconds ("120") or HTTP-date
+/// ("Wed, 21 Oct 2015 07:28:00 GMT"). Dates in the past mean "now".
+pub fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS | Method::TRACE
+    )
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+// ── Send ─────────────────────────────────────────────
+
+/// Send `request`, retrying per `policy`. Returns the final response (which
+/// may still be a non-success status) together with the retry metadata; a
+/// failed send keeps the reqwest error's code (timeout, unreachable …).
+pub async fn send_with_retry(
+    request: RequestBuilder,
+    policy: &RetryPolicy,
+) -> Result<(Response, RetryMeta), BroxeenError> {
+    let mut meta = RetryMeta::default();
+
+    let method = request
+        .try_clone()
+        .and_then(|r| r.build().ok())
+        .map(|r| r.method().clone());
+    // Streaming bodies cannot be cloned → single attempt
+    let may_retry = match &method {
+        Some(m) => policy.retry_non_idempotent || is_idempotent(m),
+        None => false,
+    };
+
+    let mut pending = Some(request);
+    loop {
+        let current = pending.take().expect("request present");
+        let next = if may_retry && meta.retries < policy.max_retries {
+            current.try_clone()
+        } else {
+            None
+        };
+
+        let wait = match current.send().await {
+            Ok(resp) => {
+                let status = resp.status();
+                if !is_retryable_status(status) || next.is_none() {
+                    return Ok((resp, meta));
+                }
+                match parse_retry_after(resp.headers()) {
+                    Some(after) if after > policy.max_retry_after => {
+                        backend_warn(format!(
+                            "HTTP {} asks to retry after {}s (> cap {}s) — giving up{}",
+                            status,
+                            after.as_secs(),
+                            policy.max_retry_after.as_secs(),
+                            meta.describe()
+                        ));
+                        return Ok((resp, meta));
+                    }
+                    Some(after) => after,
+                    None => policy.backoff(meta.retries),
+                }
+            }
+            Err(e) => {
+                let transient = e.is_connect() || e.is_timeout() || e.is_request();
+                if !transient || next.is_none() {
+                    let mut err = BroxeenError::from(e);
+                    err.message.push_str(&meta.describe());
+                    return Err(err);
+                }
+                policy.backoff(meta.retries)
+            }
+        };
+
+        meta.retries += 1;
+        meta.total_backoff_ms += wait.as_millis() as u64;
+        tokio::time::sleep(wait).await;
+        pending = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Minimal HTTP server answering each connection with the next scripted
+    /// response; returns its base URL.
+    async fn scripted_server(responses: Vec<&'static str>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            for raw in responses {
+                let (mut sock, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = sock.read(&mut buf).await;
+                let _ = sock.write_all(raw.as_bytes()).await;
+                let _ = sock.shutdown().await;
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    const TOO_MANY: &str =
+        "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const UNAVAILABLE: &str =
+        "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const OK: &str = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok";
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy::default().base_backoff(Duration::from_millis(5))
+    }
+
+    #[tokio::test]
+    async fn test_429_then_200_is_retried() {
+        let url = scripted_server(vec![TOO_MANY, UNAVAILABLE, OK]).await;
+        let client = client(Duration::from_secs(5)).unwrap();
+        let (resp, meta) = send_with_retry(client.get(&url), &fast_policy()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.text().await.unwrap(), "ok");
+        assert_eq!(meta.retries, 2);
+    }
+
+    #[tokio::test]
+    async fn test_post_is_not_retried_without_opt_in() {
+        let url = scripted_server(vec![TOO_MANY, OK]).await;
+        let client = client(Duration::from_secs(5)).unwrap();
+        let (resp, meta) = send_with_retry(client.post(&url).body("x"), &fast_policy()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(meta.retries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_post_retried_with_opt_in() {
+        let url = scripted_server(vec![TOO_MANY, OK]).await;
+        let client = client(Duration::from_secs(5)).unwrap();
+        let policy = fast_policy().allow_non_idempotent();
+        let (resp, meta) = send_with_retry(client.post(&url).body("x"), &policy).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(meta.retries, 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_above_cap_gives_up() {
+        const LONG_WAIT: &str =
+            "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 3600\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        let url = scripted_server(vec![LONG_WAIT, OK]).await;
+        let client = client(Duration::from_secs(5)).unwrap();
+        let (resp, meta) = send_with_retry(client.get(&url), &fast_policy()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(meta, RetryMeta::default());
+    }
+
+    #[test]
+    fn test_parse_retry_after_forms() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, "120".parse().unwrap());
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(120)));
+
+        headers.insert(RETRY_AFTER, "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap());
+        assert_eq!(parse_retry_after(&headers), Some(Duration::ZERO));
+
+        headers.insert(RETRY_AFTER, "soon".parse().unwrap());
+        assert_eq!(parse_retry_after(&headers), None);
+    }
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let policy = RetryPolicy::default();
+        assert!(policy.backoff(0) <= Duration::from_millis(625));
+        assert!(policy.backoff(10) <= Duration::from_secs(10));
+        assert!(policy.backoff(10) >= Duration::from_secs(6));
+    }
+}
@@ -0,0 +1,328 @@
+//! http_policy.rs — Politeness layer for outbound HTTP to public sites.
+//!
+//! `browse` and `http_fetch_base64` call `before_fetch` right before a
+//! request: robots.txt of the host is fetched once per hour and the path is
+//! checked against the rules for our user agent (unless the `ignore_robots`
+//! setting is on), then the call waits until the host's minimum interval has
+//! passed. `browse_site` shares the same robots cache and host slots. Hosts
+//! on the local network (cameras, NAS, routers) are exempt — they are the
+//! user's own devices, polled often.
+//! A robots.txt refusal is a `permission_denied` error whose message starts
+//! with `ROBOTS_DISALLOWED`.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::error::{BroxeenError, ErrorCode};
+use crate::logging::{backend_info, backend_warn};
+
+/// Prefix of the error returned when robots.txt forbids a fetch
+pub const ROBOTS_DENIED_PREFIX: &str = "ROBOTS_DISALLOWED";
+/// Product token matched against robots.txt `User-agent` lines
+pub const ROBOTS_AGENT: &str = "broxeen";
+const ROBOTS_TTL: Duration = Duration::from_secs(3600);
+/// Retry sooner when robots.txt could not be fetched at all
+const ROBOTS_ERROR_TTL: Duration = Duration::from_secs(300);
+const ROBOTS_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_CRAWL_DELAY: Duration = Duration::from_secs(10);
+/// Minimum spacing between two fetches from one host
+pub const MIN_HOST_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Fetched at, ttl, rules
+type CachedRobots = (Instant, Duration, Arc<RobotsRules>);
+
+lazy_static::lazy_static! {
+    /// origin → cached robots.txt rules
+    static ref ROBOTS_CACHE: Mutex<HashMap<String, CachedRobots>> = Mutex::new(HashMap::new());
+    /// host → earliest time of the next request
+    static ref HOST_SLOTS: tokio::sync::Mutex<HashMap<String, Instant>> = tokio::sync::Mutex::new(HashMap::new());
+}
+
+// ── robots.txt ───────────────────────────────────────
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct RobotsRules {
+    /// (allow, path pattern) for our user agent group
+    rules: Vec<(bool, String)>,
+    pub crawl_delay: Option<Duration>,
+    pub sitemaps: Vec<String>,
+}
+
+/// One robots.txt group: user agents, (allow, path) rules, crawl delay
+type RobotsGroup = (Vec<String>, Vec<(bool, String)>, Option<Duration>);
+
+/// Parse robots.txt, keeping the group for `agent` (or `*` when none names it).
+pub(crate) fn parse_robots(body: &str, agent: &str) -> RobotsRules {
+    let agent = agent.to_ascii_lowercase();
+    let mut sitemaps = Vec::new();
+    let mut groups: Vec<RobotsGroup> = Vec::new();
+    let mut in_agent_lines = false;
+
+    for line in body.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let Some((key, value)) = line.split_once(':') else { continue };
+        let key = key.trim().to_ascii_lowercase();
+        let value = value.trim();
+        match key.as_str() {
+            "user-agent" => {
+                if !in_agent_lines || groups.is_empty() {
+                    groups.push((Vec::new(), Vec::new(), None));
+                }
+                in_agent_lines = true;
+                if let Some(group) = groups.last_mut() {
+                    group.0.push(value.to_ascii_lowercase());
+                }
+            }
+            "allow" | "disallow" => {
+                in_agent_lines = false;
+                if let Some(group) = groups.last_mut() {
+                    if !value.is_empty() {
+                        group.1.push((key == "allow", value.to_string()));
+                    }
+                }
+            }
+            "crawl-delay" => {
+                in_agent_lines = false;
+                if let (Some(group), Ok(secs)) = (groups.last_mut(), value.parse::<f64>()) {
+                    group.2 = Some(Duration::from_secs_f64(secs.max(0.0)).min(MAX_CRAWL_DELAY));
+                }
+            }
+            "sitemap" => sitemaps.push(value.to_string()),
+            _ => {}
+        }
+    }
+
+    let named = groups
+        .iter()
+        .find(|(agents, _, _)| agents.iter().any(|a| a != "*" && agent.contains(a.as_str())));
+    let group = named.or_else(|| groups.iter().find(|(agents, _, _)| agents.iter().any(|a| a == "*")));
+    RobotsRules {
+        rules: group.map(|g| g.1.clone()).unwrap_or_default(),
+        crawl_delay: group.and_then(|g| g.2),
+        sitemaps,
+    }
+}
+
+/// robots.txt path pattern: prefix match with `*` wildcards and `$` anchor.
+fn robots_pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let mut pos = 0;
+    for (i, part) in parts.iter().enumerate() {
+        if i == 0 {
+            if !path.starts_with(part) {
+                return false;
+            }
+            pos = part.len();
+        } else if let Some(idx) = path[pos..].find(part) {
+            pos += idx + part.len();
+        } else {
+            return false;
+        }
+    }
+    if !anchored {
+        return true;
+    }
+    // Anchored: the last literal part must end the path
+    match parts.last() {
+        Some(last) if parts.len() > 1 => path.ends_with(last),
+        _ => pos == path.len(),
+    }
+}
+
+impl RobotsRules {
+    /// Longest matching rule wins; Allow wins ties.
+    pub(crate) fn is_allowed(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|(_, pattern)| robots_pattern_matches(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .is_none_or(|(allow, _)| *allow)
+    }
+}
+
+pub(crate) fn path_and_query(url: &url::Url) -> String {
+    match url.query() {
+        Some(q) => format!("{}?{}", url.path(), q),
+        None => url.path().to_string(),
+    }
+}
+
+fn origin_of(url: &url::Url) -> String {
+    url.origin().ascii_serialization()
+}
+
+/// Loopback, private, link-local and `.local` hosts skip robots.txt and
+/// throttling.
+pub(crate) fn is_local_host(url: &url::Url) -> bool {
+    match url.host() {
+        Some(url::Host::Ipv4(ip)) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        Some(url::Host::Ipv6(ip)) => {
+            ip.is_loopback() || (ip.segments()[0] & 0xfe00) == 0xfc00 || (ip.segments()[0] & 0xffc0) == 0xfe80
+        }
+        Some(url::Host::Domain(host)) => {
+            let host = host.to_ascii_lowercase();
+            host == "localhost" || host.ends_with(".local") || host.ends_with(".lan")
+                || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+        }
+        None => true,
+    }
+}
+
+async fn fetch_robots(origin: &str) -> (Duration, RobotsRules) {
+    let robots_url = format!("{}/robots.txt", origin);
+    let client = match crate::http_client::client(ROBOTS_TIMEOUT) {
+        Ok(client) => client,
+        Err(_) => return (ROBOTS_ERROR_TTL, RobotsRules::default()),
+    };
+    match client.get(&robots_url).send().await {
+        Ok(resp) if resp.status().is_success() => match resp.text().await {
+            Ok(body) => (ROBOTS_TTL, parse_robots(&body, ROBOTS_AGENT)),
+            Err(_) => (ROBOTS_ERROR_TTL, RobotsRules::default()),
+        },
+        // 4xx: no robots.txt — everything allowed
+        Ok(resp) if resp.status().is_client_error() => (ROBOTS_TTL, RobotsRules::default()),
+        Ok(resp) => {
+            backend_warn(format!("robots.txt for {} returned HTTP {}", origin, resp.status()));
+            (ROBOTS_ERROR_TTL, RobotsRules::default())
+        }
+        Err(e) => {
+            backend_warn(format!("robots.txt for {} unavailable: {}", origin, e));
+            (ROBOTS_ERROR_TTL, RobotsRules::default())
+        }
+    }
+}
+
+/// Rules for the URL's origin, from the cache when fresh.
+pub(crate) async fn robots_for(url: &url::Url) -> Arc<RobotsRules> {
+    let origin = origin_of(url);
+    {
+        let cache = ROBOTS_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((fetched, ttl, rules)) = cache.get(&origin) {
+            if fetched.elapsed() < *ttl {
+                return rules.clone();
+            }
+        }
+    }
+    let (ttl, rules) = fetch_robots(&origin).await;
+    let rules = Arc::new(rules);
+    ROBOTS_CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(origin, (Instant::now(), ttl, rules.clone()));
+    rules
+}
+
+/// Whether robots.txt lets us fetch `url` (always true with the
+/// `ignore_robots` setting, passed in by the caller, or for local hosts).
+pub(crate) async fn robots_allows(url: &url::Url, ignore_robots: bool) -> bool {
+    if ignore_robots || is_local_host(url) {
+        return true;
+    }
+    robots_for(url).await.is_allowed(&path_and_query(url))
+}
+
+/// Wait for the host's next free slot and reserve the one after it.
+pub(crate) async fn throttle(host: &str, interval: Duration) {
+    let wait = {
+        let mut slots = HOST_SLOTS.lock().await;
+        let now = Instant::now();
+        let slot = slots.get(host).copied().filter(|t| *t > now).unwrap_or(now);
+        slots.insert(host.to_string(), slot + interval);
+        slot - now
+    };
+    if !wait.is_zero() {
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// robots.txt check alone, for content served without a request (the
+/// browse cache) — a page disallowed since it was cached is not served.
+pub(crate) async fn check_robots(raw_url: &str) -> Result<url::Url, BroxeenError> {
+    let url = url::Url::parse(raw_url)
+        .map_err(|e| BroxeenError::invalid_input(format!("Nieprawidłowy URL {}: {}", raw_url, e)))?;
+    if !robots_allows(&url, crate::settings::load_settings().ignore_robots).await {
+        backend_info(format!("http_policy: robots.txt disallows {}", url));
+        let message = format!(
+            "{}: robots.txt serwisu {} nie pozwala na pobranie {}",
+            ROBOTS_DENIED_PREFIX,
+            url.host_str().unwrap_or_default(),
+            url.path()
+        );
+        return Err(BroxeenError::new(ErrorCode::PermissionDenied, message)
+            .with_details(serde_json::json!({ "reason": "robots_txt" })));
+    }
+    Ok(url)
+}
+
+/// robots.txt check plus per-host throttling; call right before fetching.
+pub(crate) async fn before_fetch(raw_url: &str) -> Result<(), BroxeenError> {
+    let url = check_robots(raw_url).await?;
+    if !is_local_host(&url) {
+        throttle(url.host_str().unwrap_or_default(), MIN_HOST_INTERVAL).await;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_robots_groups() {
+        let body = "\
+User-agent: *
+Disallow: /admin
+Disallow: /*.php$
+Allow: /admin/public
+
+User-agent: Broxeen
+User-agent: OtherBot
+Disallow: /private
+Crawl-delay: 2
+
+Sitemap: https://example.com/sitemap_index.xml
+";
+        let ours = parse_robots(body, "broxeen");
+        assert_eq!(ours.rules, vec![(false, "/private".to_string())]);
+        assert_eq!(ours.crawl_delay, Some(Duration::from_secs(2)));
+        assert_eq!(ours.sitemaps, vec!["https://example.com/sitemap_index.xml"]);
+        assert!(!ours.is_allowed("/private/x"));
+        assert!(ours.is_allowed("/admin"));
+
+        let generic = parse_robots(body, "somebot");
+        assert!(!generic.is_allowed("/admin/users"));
+        assert!(generic.is_allowed("/admin/public/info"));
+        assert!(!generic.is_allowed("/index.php"));
+        assert!(generic.is_allowed("/index.php?x=1"));
+        assert!(generic.is_allowed("/about"));
+    }
+
+    #[test]
+    fn test_local_hosts_are_exempt() {
+        let local = |u: &str| is_local_host(&url::Url::parse(u).unwrap());
+        assert!(local("http://192.168.1.10/snapshot.jpg"));
+        assert!(local("http://127.0.0.1:8080/"));
+        assert!(local("http://kamera.local/"));
+        assert!(local("http://[fe80::1]/"));
+        assert!(!local("https://example.com/"));
+        assert!(!local("http://8.8.8.8/"));
+    }
+
+    #[tokio::test]
+    async fn test_throttle_spaces_requests_per_host() {
+        let interval = Duration::from_millis(80);
+        let started = Instant::now();
+        throttle("throttle-test.example", interval).await;
+        throttle("throttle-test.example", interval).await;
+        throttle("other-host.example", interval).await;
+        let elapsed = started.elapsed();
+        assert!(elapsed >= interval, "second request not delayed: {:?}", elapsed);
+        assert!(elapsed < interval * 2, "other host was delayed too: {:?}", elapsed);
+    }
+}
@@ -0,0 +1,337 @@
+//! Idempotency keys for externally-triggered actions.
+//!
+//! Webhooks, HTTP callers and email rules may deliver the same trigger more
+//! than once (retries). Actions that accept an `idempotency_key` record their
+//! result here; a repeat within the TTL returns the original result instead of
+//! running the action again (no duplicate TTS announcements, clips, mails).
+//!
+//! Keys are scoped per action type and bounded (oldest evicted first).
+//! Detection-driven automations derive their key with [`detection_key`].
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::logging::backend_info;
+
+pub const DEFAULT_TTL: Duration = Duration::from_secs(15 * 60);
+const MAX_KEYS_PER_ACTION: usize = 512;
+const MAX_RECENT_HITS: usize = 100;
+
+// ── Types ────────────────────────────────────────────────────────
+
+struct Entry {
+    /// `None` while the first delivery is still running
+    result: Option<Value>,
+    recorded_at: Instant,
+    recorded_at_utc: String,
+}
+
+#[derive(Default)]
+struct ActionKeys {
+    entries: HashMap<String, Entry>,
+    order: VecDeque<String>,
+}
+
+/// What a delivery of `(action, key)` should do.
+enum Begin {
+    /// First delivery: the key is now marked in-flight
+    Run,
+    /// Already done within the TTL
+    Done(Value),
+    /// An earlier delivery is still running
+    Running,
+}
+
+/// Clears an in-flight mark that was never completed (error, cancellation).
+struct InFlight<'a> {
+    store: &'a IdempotencyStore,
+    action: &'a str,
+    key: &'a str,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.store.forget_in_flight(self.action, self.key);
+    }
+}
+
+/// A repeated delivery that was answered from the store.
+#[derive(Debug, Clone, Serialize)]
+pub struct IdempotencyHit {
+    pub action: String,
+    pub key: String,
+    pub first_seen: String,
+    pub hit_at: String,
+}
+
+pub struct IdempotencyStore {
+    ttl: Duration,
+    capacity: usize,
+    actions: Mutex<HashMap<String, ActionKeys>>,
+    hits: Mutex<VecDeque<IdempotencyHit>>,
+}
+
+impl IdempotencyStore {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity: capacity.max(1),
+            actions: Mutex::new(HashMap::new()),
+            hits: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Remember the result of `(action, key)`.
+    pub fn record(&self, action: &str, key: &str, result: Value) {
+        let mut actions = self.actions.lock().unwrap_or_else(|e| e.into_inner());
+        let keys = actions.entry(action.to_string()).or_default();
+        self.expire(keys);
+        self.insert(keys, key, Some(result));
+    }
+
+    /// Claim a key for a fire-and-forget action. Returns `false` if it was
+    /// already claimed (the caller must not run the action again).
+    #[cfg_attr(not(feature = "vision"), allow(dead_code))]
+    pub fn claim(&self, action: &str, key: &str) -> bool {
+        match self.begin(action, key) {
+            Begin::Run => {
+                self.record(action, key, Value::Null);
+                true
+            }
+            Begin::Done(_) | Begin::Running => false,
+        }
+    }
+
+    /// Check and mark `(action, key)` in-flight under one lock, so two
+    /// concurrent deliveries cannot both run the action.
+    fn begin(&self, action: &str, key: &str) -> Begin {
+        let mut actions = self.actions.lock().unwrap_or_else(|e| e.into_inner());
+        let keys = actions.entry(action.to_string()).or_default();
+        self.expire(keys);
+        let (result, first_seen) = match keys.entries.get(key) {
+            Some(Entry { result: Some(result), recorded_at_utc, .. }) => (result.clone(), recorded_at_utc.clone()),
+            Some(_) => return Begin::Running,
+            None => {
+                self.insert(keys, key, None);
+                return Begin::Run;
+            }
+        };
+        drop(actions);
+
+        self.note_hit(action, key, first_seen);
+        Begin::Done(result)
+    }
+
+    /// Drop an in-flight mark that did not end in `record`.
+    fn forget_in_flight(&self, action: &str, key: &str) {
+        let mut actions = self.actions.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(keys) = actions.get_mut(action) else { return };
+        if keys.entries.get(key).is_some_and(|e| e.result.is_none()) {
+            keys.entries.remove(key);
+            keys.order.retain(|k| k != key);
+        }
+    }
+
+    fn insert(&self, keys: &mut ActionKeys, key: &str, result: Option<Value>) {
+        if keys.entries.contains_key(key) {
+            keys.order.retain(|k| k != key);
+        }
+        while keys.order.len() >= self.capacity {
+            if let Some(oldest) = keys.order.pop_front() {
+                keys.entries.remove(&oldest);
+            }
+        }
+        keys.order.push_back(key.to_string());
+        keys.entries.insert(key.to_string(), Entry {
+            result,
+            recorded_at: Instant::now(),
+            recorded_at_utc: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+
+    fn note_hit(&self, action: &str, key: &str, first_seen: String) {
+        backend_info(format!("Idempotency hit: {} key={} (first seen {})", action, key, first_seen));
+        let mut hits = self.hits.lock().unwrap_or_else(|e| e.into_inner());
+        if hits.len() >= MAX_RECENT_HITS {
+            hits.pop_front();
+        }
+        hits.push_back(IdempotencyHit {
+            action: action.to_string(),
+            key: key.to_string(),
+            first_seen,
+            hit_at: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+
+    pub fn recent_hits(&self) -> Vec<IdempotencyHit> {
+        self.hits.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect()
+    }
+
+    fn expire(&self, keys: &mut ActionKeys) {
+        while let Some(oldest) = keys.order.front() {
+            match keys.entries.get(oldest) {
+                Some(e) if e.recorded_at.elapsed() < self.ttl => break,
+                _ => {
+                    let oldest = keys.order.pop_front().unwrap();
+                    keys.entries.remove(&oldest);
+                }
+            }
+        }
+    }
+}
+
+// ── Global store ─────────────────────────────────────────────────
+
+lazy_static::lazy_static! {
+    static ref STORE: IdempotencyStore = IdempotencyStore::new(DEFAULT_TTL, MAX_KEYS_PER_ACTION);
+}
+
+pub fn store() -> &'static IdempotencyStore {
+    &STORE
+}
+
+/// Run `f` unless `(action, key)` was already executed successfully within
+/// the TTL, in which case the original result is returned. A delivery that
+/// arrives while the first one is still running fails instead of running
+/// twice. Without a key the action always runs. Errors are not recorded, so
+/// a failed (or cancelled) attempt can be retried.
+pub async fn run_once<T, E, F, Fut>(action: &str, key: Option<&str>, f: F) -> Result<T, E>
+where
+    T: Serialize + DeserializeOwned,
+    E: From<String>,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    run_once_in(store(), action, key, f).await
+}
+
+async fn run_once_in<T, E, F, Fut>(
+    store: &IdempotencyStore,
+    action: &str,
+    key: Option<&str>,
+    f: F,
+) -> Result<T, E>
+where
+    T: Serialize + DeserializeOwned,
+    E: From<String>,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let Some(key) = key.map(str::trim).filter(|k| !k.is_empty()) else {
+        return f().await;
+    };
+    match store.begin(action, key) {
+        Begin::Run => {}
+        Begin::Done(previous) => {
+            return serde_json::from_value(previous)
+                .map_err(|e| E::from(format!("Idempotency: cannot decode stored result: {}", e)));
+        }
+        Begin::Running => {
+            return Err(E::from(format!("{}: key {} is already being processed", action, key)));
+        }
+    }
+    let _in_flight = InFlight { store, action, key };
+    let result = f().await?;
+    if let Ok(value) = serde_json::to_value(&result) {
+        store.record(action, key, value);
+    }
+    Ok(result)
+}
+
+/// Key for detection-driven automations: one firing per (camera, track, rule).
+#[cfg_attr(not(feature = "vision"), allow(dead_code))]
+pub fn detection_key(camera_id: &str, track_id: &str, rule: &str) -> String {
+    format!("{}|{}|{}", camera_id, track_id, rule)
+}
+
+// ── Tauri commands ───────────────────────────────────────────────
+
+#[tauri::command]
+pub fn idempotency_recent_hits() -> Vec<IdempotencyHit> {
+    store().recent_hits()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_duplicate_delivery_returns_original_result() {
+        let store = IdempotencyStore::new(DEFAULT_TTL, 8);
+        let runs = AtomicUsize::new(0);
+
+        for _ in 0..3 {
+            let result: String = run_once_in(&store, "tts_speak", Some("evt-1"), || async {
+                let n = runs.fetch_add(1, Ordering::SeqCst);
+                Ok::<_, String>(format!("run-{}", n))
+            })
+            .await
+            .unwrap();
+            assert_eq!(result, "run-0");
+        }
+
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(store.recent_hits().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_attempt_is_not_recorded() {
+        let store = IdempotencyStore::new(DEFAULT_TTL, 8);
+        let first: Result<u32, String> =
+            run_once_in(&store, "clip", Some("k"), || async { Err("ffmpeg failed".to_string()) }).await;
+        assert!(first.is_err());
+
+        let retry: u32 = run_once_in(&store, "clip", Some("k"), || async { Ok::<_, String>(7) }).await.unwrap();
+        assert_eq!(retry, 7);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_delivery_does_not_run_twice() {
+        let store = IdempotencyStore::new(DEFAULT_TTL, 8);
+        let (release_tx, release_rx) = tokio::sync::oneshot::channel::<()>();
+        let first = run_once_in(&store, "email_send", Some("m-1"), || async {
+            let _ = release_rx.await;
+            Ok::<_, String>(1)
+        });
+        let second = async {
+            let result = run_once_in(&store, "email_send", Some("m-1"), || async { Ok::<_, String>(2) }).await;
+            release_tx.send(()).unwrap();
+            result
+        };
+        let (first, second) = tokio::join!(first, second);
+        assert_eq!(first, Ok(1));
+        assert_eq!(second, Err("email_send: key m-1 is already being processed".to_string()));
+        assert_eq!(run_once_in(&store, "email_send", Some("m-1"), || async { Ok::<_, String>(3) }).await, Ok(1));
+
+        // A cancelled attempt releases the key
+        let cancelled = run_once_in(&store, "email_send", Some("m-2"), std::future::pending::<Result<u32, String>>);
+        assert!(tokio::time::timeout(Duration::from_millis(10), cancelled).await.is_err());
+        assert_eq!(run_once_in(&store, "email_send", Some("m-2"), || async { Ok::<_, String>(4) }).await, Ok(4));
+    }
+
+    #[test]
+    fn test_keys_are_scoped_per_action_and_bounded() {
+        let store = IdempotencyStore::new(DEFAULT_TTL, 2);
+        assert!(store.claim("clip", "a"));
+        assert!(store.claim("tts", "a"));
+        assert!(!store.claim("clip", "a"));
+
+        store.record("clip", "b", Value::Null);
+        store.record("clip", "c", Value::Null);
+        // "a" evicted by capacity 2
+        assert!(store.claim("clip", "a"));
+    }
+
+    #[test]
+    fn test_expired_keys_run_again() {
+        let store = IdempotencyStore::new(Duration::from_millis(0), 8);
+        assert!(store.claim("clip", &detection_key("cam1", "42", "person")));
+        assert!(store.claim("clip", &detection_key("cam1", "42", "person")));
+    }
+}
@@ -0,0 +1,395 @@
+//! image_meta.rs — Thumbnails and EXIF metadata for image search results.
+//!
+//! `file_search(include_thumbnails = true)` attaches a small JPEG thumbnail
+//! and `ImageMeta` (dimensions, EXIF DateTimeOriginal, GPS) to raster images
+//! so the frontend can render photo results without extra reads. EXIF is
+//! read by a minimal TIFF/IFD parser that only looks at the tags we need.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// Longest thumbnail edge in pixels
+pub const THUMBNAIL_MAX_PX: u32 = 128;
+const THUMBNAIL_QUALITY: u8 = 75;
+/// Larger files are skipped (decoding would take too long / too much RAM)
+pub const MAX_THUMBNAIL_SOURCE_BYTES: u64 = 25 * 1024 * 1024;
+/// EXIF lives in the first APP1 segment; no need to read whole photos
+const EXIF_SCAN_BYTES: u64 = 256 * 1024;
+
+pub const RASTER_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp", "bmp"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GpsPosition {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ImageMeta {
+    pub width: u32,
+    pub height: u32,
+    /// EXIF DateTimeOriginal as `YYYY-MM-DDTHH:MM:SS` (camera local time)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub taken_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gps: Option<GpsPosition>,
+}
+
+/// Encoding of stored detection thumbnails (`database.thumbnail_format`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ThumbnailFormat {
+    #[default]
+    Jpeg,
+    Webp,
+}
+
+impl ThumbnailFormat {
+    /// Recognise a thumbnail by its magic bytes; rows written before the
+    /// format was configurable carry no format column.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(ThumbnailFormat::Jpeg)
+        } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+            Some(ThumbnailFormat::Webp)
+        } else {
+            None
+        }
+    }
+
+    /// Value of the `detections.thumbnail_format` column
+    #[cfg_attr(not(feature = "vision"), allow(dead_code))]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThumbnailFormat::Jpeg => "jpeg",
+            ThumbnailFormat::Webp => "webp",
+        }
+    }
+
+    /// MIME type for data URLs and HTTP responses
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ThumbnailFormat::Jpeg => "image/jpeg",
+            ThumbnailFormat::Webp => "image/webp",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ThumbnailFormat::Jpeg => "jpg",
+            ThumbnailFormat::Webp => "webp",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct ExifData {
+    taken_at: Option<String>,
+    gps: Option<GpsPosition>,
+}
+
+pub fn is_raster_image(extension: &str) -> bool {
+    RASTER_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+}
+
+/// Dimensions plus EXIF (JPEG only).
+pub fn read_image_meta(path: &Path) -> Result<ImageMeta, String> {
+    let (width, height) = image::image_dimensions(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let exif = read_jpeg_prefix(path).map(|bytes| parse_jpeg_exif(&bytes)).unwrap_or_default();
+    Ok(ImageMeta { width, height, taken_at: exif.taken_at, gps: exif.gps })
+}
+
+/// Base64 JPEG thumbnail with the longest edge ≤ `THUMBNAIL_MAX_PX`.
+pub fn thumbnail_base64(path: &Path) -> Result<String, String> {
+    use base64::Engine;
+
+    let img = image::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let thumb = img.thumbnail(THUMBNAIL_MAX_PX, THUMBNAIL_MAX_PX).to_rgb8();
+    let mut out = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, THUMBNAIL_QUALITY)
+        .encode_image(&thumb)
+        .map_err(|e| format!("JPEG encode failed: {}", e))?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(&out))
+}
+
+fn read_jpeg_prefix(path: &Path) -> Option<Vec<u8>> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    if ext != "jpg" && ext != "jpeg" {
+        return None;
+    }
+    let mut bytes = Vec::new();
+    fs::File::open(path).ok()?.take(EXIF_SCAN_BYTES).read_to_end(&mut bytes).ok()?;
+    Some(bytes)
+}
+
+// ── EXIF parser ──────────────────────────────────────
+
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_GPS_IFD: u16 = 0x8825;
+const TAG_DATETIME_ORIGINAL: u16 = 0x9003;
+const TAG_GPS_LAT_REF: u16 = 0x0001;
+const TAG_GPS_LAT: u16 = 0x0002;
+const TAG_GPS_LON_REF: u16 = 0x0003;
+const TAG_GPS_LON: u16 = 0x0004;
+const TYPE_ASCII: u16 = 2;
+const TYPE_LONG: u16 = 4;
+const TYPE_RATIONAL: u16 = 5;
+
+/// TIFF block with its byte order.
+struct Tiff<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+/// One IFD entry: tag, type, count and the raw 4-byte value/offset field.
+struct IfdEntry {
+    tag: u16,
+    kind: u16,
+    count: u32,
+    value_offset: usize,
+}
+
+impl<'a> Tiff<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let little_endian = match data.get(0..2)? {
+            b"II" => true,
+            b"MM" => false,
+            _ => return None,
+        };
+        let tiff = Tiff { data, little_endian };
+        (tiff.u16_at(2)? == 42).then_some(tiff)
+    }
+
+    fn u16_at(&self, pos: usize) -> Option<u16> {
+        let b: [u8; 2] = self.data.get(pos..pos + 2)?.try_into().ok()?;
+        Some(if self.little_endian { u16::from_le_bytes(b) } else { u16::from_be_bytes(b) })
+    }
+
+    fn u32_at(&self, pos: usize) -> Option<u32> {
+        let b: [u8; 4] = self.data.get(pos..pos + 4)?.try_into().ok()?;
+        Some(if self.little_endian { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) })
+    }
+
+    fn entries(&self, ifd_offset: usize) -> Vec<IfdEntry> {
+        let Some(count) = self.u16_at(ifd_offset) else { return Vec::new() };
+        (0..count as usize)
+            .filter_map(|i| {
+                let pos = ifd_offset + 2 + i * 12;
+                Some(IfdEntry {
+                    tag: self.u16_at(pos)?,
+                    kind: self.u16_at(pos + 2)?,
+                    count: self.u32_at(pos + 4)?,
+                    value_offset: pos + 8,
+                })
+            })
+            .collect()
+    }
+
+    /// Start of the entry's data: inline when it fits into 4 bytes.
+    fn data_pos(&self, entry: &IfdEntry, size: usize) -> Option<usize> {
+        if size <= 4 {
+            Some(entry.value_offset)
+        } else {
+            self.u32_at(entry.value_offset).map(|o| o as usize)
+        }
+    }
+
+    fn ascii(&self, entry: &IfdEntry) -> Option<String> {
+        if entry.kind != TYPE_ASCII {
+            return None;
+        }
+        let pos = self.data_pos(entry, entry.count as usize)?;
+        let raw = self.data.get(pos..pos + entry.count as usize)?;
+        let text = String::from_utf8_lossy(raw).trim_end_matches('\0').trim().to_string();
+        (!text.is_empty()).then_some(text)
+    }
+
+    fn long(&self, entry: &IfdEntry) -> Option<usize> {
+        (entry.kind == TYPE_LONG).then(|| self.u32_at(entry.value_offset).map(|v| v as usize))?
+    }
+
+    /// Degrees/minutes/seconds rationals → decimal degrees.
+    fn dms(&self, entry: &IfdEntry) -> Option<f64> {
+        if entry.kind != TYPE_RATIONAL || entry.count < 3 {
+            return None;
+        }
+        let pos = self.data_pos(entry, 24)?;
+        let rational = |i: usize| -> Option<f64> {
+            let num = self.u32_at(pos + i * 8)? as f64;
+            let den = self.u32_at(pos + i * 8 + 4)? as f64;
+            (den != 0.0).then(|| num / den)
+        };
+        Some(rational(0)? + rational(1)? / 60.0 + rational(2)? / 3600.0)
+    }
+}
+
+/// EXIF "2024:07:14 18:03:22" → "2024-07-14T18:03:22".
+fn exif_datetime(raw: &str) -> Option<String> {
+    let parsed = chrono::NaiveDateTime::parse_from_str(raw, "%Y:%m:%d %H:%M:%S").ok()?;
+    Some(parsed.format("%Y-%m-%dT%H:%M:%S").to_string())
+}
+
+fn parse_tiff_exif(tiff: &Tiff) -> ExifData {
+    let mut out = ExifData::default();
+    let Some(ifd0) = tiff.u32_at(4) else { return out };
+    let ifd0 = tiff.entries(ifd0 as usize);
+
+    if let Some(exif_ifd) = ifd0.iter().find(|e| e.tag == TAG_EXIF_IFD).and_then(|e| tiff.long(e)) {
+        out.taken_at = tiff
+            .entries(exif_ifd)
+            .iter()
+            .find(|e| e.tag == TAG_DATETIME_ORIGINAL)
+            .and_then(|e| tiff.ascii(e))
+            .and_then(|raw| exif_datetime(&raw));
+    }
+
+    if let Some(gps_ifd) = ifd0.iter().find(|e| e.tag == TAG_GPS_IFD).and_then(|e| tiff.long(e)) {
+        let gps = tiff.entries(gps_ifd);
+        let find = |tag: u16| gps.iter().find(|e| e.tag == tag);
+        let lat = find(TAG_GPS_LAT).and_then(|e| tiff.dms(e));
+        let lon = find(TAG_GPS_LON).and_then(|e| tiff.dms(e));
+        if let (Some(lat), Some(lon)) = (lat, lon) {
+            let lat_ref = find(TAG_GPS_LAT_REF).and_then(|e| tiff.ascii(e)).unwrap_or_default();
+            let lon_ref = find(TAG_GPS_LON_REF).and_then(|e| tiff.ascii(e)).unwrap_or_default();
+            out.gps = Some(GpsPosition {
+                latitude: if lat_ref == "S" { -lat } else { lat },
+                longitude: if lon_ref == "W" { -lon } else { lon },
+            });
+        }
+    }
+    out
+}
+
+/// Find the APP1 "Exif" segment in JPEG bytes and parse it.
+fn parse_jpeg_exif(bytes: &[u8]) -> ExifData {
+    if bytes.get(0..2) != Some(&[0xFF, 0xD8]) {
+        return ExifData::default();
+    }
+    let mut pos = 2;
+    while pos + 4 <= bytes.len() {
+        if bytes[pos] != 0xFF {
+            break;
+        }
+        let marker = bytes[pos + 1];
+        // Start of scan / end of image: no metadata after this point
+        if marker == 0xDA || marker == 0xD9 {
+            break;
+        }
+        let len = u16::from_be_bytes([bytes[pos + 2], bytes[pos + 3]]) as usize;
+        let segment = bytes.get(pos + 4..(pos + 2 + len).min(bytes.len())).unwrap_or_default();
+        if marker == 0xE1 && segment.starts_with(b"Exif\0\0") {
+            return Tiff::new(&segment[6..]).map(|t| parse_tiff_exif(&t)).unwrap_or_default();
+        }
+        pos += 2 + len;
+    }
+    ExifData::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Big-endian TIFF with DateTimeOriginal and a GPS position
+    /// (52°13'48" N, 21°0'36" E).
+    fn sample_tiff() -> Vec<u8> {
+        let mut t = Vec::new();
+        let push16 = |t: &mut Vec<u8>, v: u16| t.extend_from_slice(&v.to_be_bytes());
+        let push32 = |t: &mut Vec<u8>, v: u32| t.extend_from_slice(&v.to_be_bytes());
+        let entry = |t: &mut Vec<u8>, tag: u16, kind: u16, count: u32, value: u32| {
+            t.extend_from_slice(&tag.to_be_bytes());
+            t.extend_from_slice(&kind.to_be_bytes());
+            t.extend_from_slice(&count.to_be_bytes());
+            t.extend_from_slice(&value.to_be_bytes());
+        };
+
+        // Header, IFD0 at 8 with two pointers
+        t.extend_from_slice(b"MM");
+        push16(&mut t, 42);
+        push32(&mut t, 8);
+        push16(&mut t, 2);
+        entry(&mut t, TAG_EXIF_IFD, TYPE_LONG, 1, 38);
+        entry(&mut t, TAG_GPS_IFD, TYPE_LONG, 1, 76);
+        push32(&mut t, 0);
+        // Exif IFD at 38: DateTimeOriginal → string at 56
+        assert_eq!(t.len(), 38);
+        push16(&mut t, 1);
+        entry(&mut t, TAG_DATETIME_ORIGINAL, TYPE_ASCII, 20, 56);
+        push32(&mut t, 0);
+        assert_eq!(t.len(), 56);
+        t.extend_from_slice(b"2024:07:14 18:03:22\0");
+        // GPS IFD at 76: refs inline, rationals at 130 and 154
+        assert_eq!(t.len(), 76);
+        push16(&mut t, 4);
+        entry(&mut t, TAG_GPS_LAT_REF, TYPE_ASCII, 2, u32::from_be_bytes(*b"N\0\0\0"));
+        entry(&mut t, TAG_GPS_LAT, TYPE_RATIONAL, 3, 130);
+        entry(&mut t, TAG_GPS_LON_REF, TYPE_ASCII, 2, u32::from_be_bytes(*b"E\0\0\0"));
+        entry(&mut t, TAG_GPS_LON, TYPE_RATIONAL, 3, 154);
+        push32(&mut t, 0);
+        assert_eq!(t.len(), 130);
+        for (num, den) in [(52, 1), (13, 1), (48, 1), (21, 1), (0, 1), (36, 1)] {
+            push32(&mut t, num);
+            push32(&mut t, den);
+        }
+        t
+    }
+
+    fn jpeg_with_exif(tiff: &[u8]) -> Vec<u8> {
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        jpeg.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+        jpeg.extend_from_slice(b"Exif\0\0");
+        jpeg.extend_from_slice(tiff);
+        jpeg.extend_from_slice(&[0xFF, 0xD9]);
+        jpeg
+    }
+
+    #[test]
+    fn test_parse_jpeg_exif() {
+        let exif = parse_jpeg_exif(&jpeg_with_exif(&sample_tiff()));
+        assert_eq!(exif.taken_at.as_deref(), Some("2024-07-14T18:03:22"));
+        let gps = exif.gps.unwrap();
+        assert!((gps.latitude - 52.23).abs() < 1e-9);
+        assert!((gps.longitude - 21.01).abs() < 1e-9);
+
+        // Truncated / foreign data must not panic
+        let jpeg = jpeg_with_exif(&sample_tiff());
+        for cut in [3, 10, 40, 100, jpeg.len() - 30] {
+            let _ = parse_jpeg_exif(&jpeg[..cut]);
+        }
+        assert_eq!(parse_jpeg_exif(b"not a jpeg"), ExifData::default());
+    }
+
+    #[test]
+    fn test_thumbnail_and_meta_from_png() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wakacje.png");
+        image::RgbImage::from_pixel(640, 320, image::Rgb([200, 120, 40])).save(&path).unwrap();
+
+        let meta = read_image_meta(&path).unwrap();
+        assert_eq!((meta.width, meta.height), (640, 320));
+        assert!(meta.taken_at.is_none());
+
+        use base64::Engine;
+        let jpeg = base64::engine::general_purpose::STANDARD.decode(thumbnail_base64(&path).unwrap()).unwrap();
+        let thumb = image::load_from_memory(&jpeg).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (128, 64));
+
+        let broken = dir.path().join("broken.jpg");
+        fs::write(&broken, b"\xFF\xD8garbage").unwrap();
+        assert!(thumbnail_base64(&broken).is_err());
+        assert!(read_image_meta(&broken).is_err());
+    }
+
+    #[test]
+    fn test_thumbnail_format_detect() {
+        let mut webp = Vec::new();
+        image::RgbImage::from_pixel(8, 8, image::Rgb([1, 2, 3]))
+            .write_to(&mut std::io::Cursor::new(&mut webp), image::ImageFormat::WebP)
+            .unwrap();
+        assert_eq!(ThumbnailFormat::detect(&webp), Some(ThumbnailFormat::Webp));
+        assert_eq!(ThumbnailFormat::detect(&jpeg_with_exif(&sample_tiff())), Some(ThumbnailFormat::Jpeg));
+        assert_eq!(ThumbnailFormat::detect(b"RIFF\0\0\0\0WAVE"), None);
+        assert_eq!(ThumbnailFormat::detect(b""), None);
+    }
+}
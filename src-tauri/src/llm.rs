@@ -1,10 +1,42 @@
 //! llm.rs — OpenRouter API client for Tauri backend.
 //! Handles API calls server-side to avoid CORS and protect API key.
+//! When OpenRouter is unreachable (or no key is set) `llm_chat` falls back to
+//! a local OpenAI-compatible endpoint (Ollama) from settings.
+//! `llm_chat_stream` does the same with `stream: true`, emitting tokens as
+//! `broxeen:llm_token` events until `broxeen:llm_done`.
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+
+use crate::http_client::{send_with_retry, RetryMeta, RetryPolicy};
+use crate::llm_conversations::{self, ChatMessage, DEFAULT_CONTEXT_TOKENS};
 
 const OPENROUTER_URL: &str = "https://openrouter.ai/api/v1/chat/completions";
+const OPENROUTER_MODELS_URL: &str = "https://openrouter.ai/api/v1/models";
+const STATUS_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_MAX_TOKENS: u32 = 1024;
+const DEFAULT_TEMPERATURE: f32 = 0.7;
+/// Largest completion budget OpenRouter accepts for any model we route to
+const OPENROUTER_MAX_TOKENS: u32 = 65_536;
+/// Ollama's OpenAI endpoint; larger requests are clamped for the fallback
+const LOCAL_MAX_TOKENS: u32 = 32_768;
+/// Whole-request timeout for streamed answers, which may run for minutes
+const STREAM_TIMEOUT: Duration = Duration::from_secs(600);
+const TOKEN_EVENT: &str = "broxeen:llm_token";
+const DONE_EVENT: &str = "broxeen:llm_done";
+
+static STREAM_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+lazy_static::lazy_static! {
+    /// Running `llm_chat_stream` calls by request id
+    static ref STREAMS: Mutex<HashMap<String, Arc<Notify>>> = Mutex::new(HashMap::new());
+}
 
 // ── Types ────────────────────────────────────────────
 
@@ -12,96 +44,230 @@ const OPENROUTER_URL: &str = "https://openrouter.ai/api/v1/chat/completions";
 pub struct LlmResponse {
     pub text: String,
     pub model: String,
+    /// Retries spent on 429 / 5xx before this answer
+    #[serde(default)]
+    pub retries: u32,
+    #[serde(default)]
+    pub retry_backoff_ms: u64,
+    /// Provider that answered: "openrouter" or "local"
+    #[serde(default)]
+    pub provider: String,
+    /// Parameters this call actually used (after overrides and defaults)
+    #[serde(default)]
+    pub params: Option<LlmCallParams>,
 }
 
-// ── Tauri command ────────────────────────────────────
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LlmCallParams {
+    pub model: String,
+    pub temperature: f32,
+    pub max_tokens: u32,
+    /// Set when the call replaced the system prompt
+    pub system_prompt: Option<String>,
+}
 
-/// Tauri command: send chat completion to OpenRouter.
-/// `messages` is a JSON string of the messages array.
-#[tauri::command]
-pub async fn llm_chat(
-    messages: String,
-    api_key: String,
-    model: String,
-    max_tokens: u32,
-    temperature: f32,
-) -> Result<LlmResponse, String> {
-    crate::backend_info(format!(
-        "Command llm_chat invoked (model='{}', max_tokens={})",
-        model, max_tokens
-    ));
+#[derive(Debug, Serialize, Clone)]
+pub struct LlmProviderStatus {
+    /// "openrouter" or "local"
+    pub provider: String,
+    pub model: String,
+    pub base_url: String,
+    pub configured: bool,
+    pub reachable: bool,
+    pub status_code: Option<u16>,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Payload of `broxeen:llm_token`.
+#[derive(Debug, Clone, Serialize)]
+pub struct LlmTokenEvent {
+    pub request_id: String,
+    pub delta: String,
+    /// 0-based position of this delta in the answer
+    pub index: usize,
+}
+
+/// Payload of `broxeen:llm_done`, emitted once per `llm_chat_stream` call.
+#[derive(Debug, Clone, Serialize)]
+pub struct LlmDoneEvent {
+    pub request_id: String,
+    /// Full answer (partial when cancelled or failed mid-stream)
+    pub text: String,
+    pub model: String,
+    pub provider: String,
+    /// `usage` object of the last chunk, when the provider sent one
+    pub usage: Option<serde_json::Value>,
+    pub cancelled: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Provider {
+    OpenRouter { api_key: String, model: String },
+    Local { base_url: String, model: String },
+}
 
-    let key = if api_key.is_empty() {
+impl Provider {
+    fn name(&self) -> &'static str {
+        match self {
+            Provider::OpenRouter { .. } => "openrouter",
+            Provider::Local { .. } => "local",
+        }
+    }
+
+    fn model(&self) -> &str {
+        match self {
+            Provider::OpenRouter { model, .. } | Provider::Local { model, .. } => model,
+        }
+    }
+
+    fn chat_url(&self) -> String {
+        match self {
+            Provider::OpenRouter { .. } => OPENROUTER_URL.to_string(),
+            Provider::Local { base_url, .. } => format!("{}/chat/completions", base_url.trim_end_matches('/')),
+        }
+    }
+
+    fn max_tokens_limit(&self) -> u32 {
+        match self {
+            Provider::OpenRouter { .. } => OPENROUTER_MAX_TOKENS,
+            Provider::Local { .. } => LOCAL_MAX_TOKENS,
+        }
+    }
+
+    fn models_url(&self) -> String {
+        match self {
+            Provider::OpenRouter { .. } => OPENROUTER_MODELS_URL.to_string(),
+            Provider::Local { base_url, .. } => format!("{}/models", base_url.trim_end_matches('/')),
+        }
+    }
+}
+
+/// Why a provider call failed; `fallback` marks errors another provider may fix
+/// (network, 429/5xx, missing key) as opposed to a bad request.
+#[derive(Debug)]
+struct ProviderError {
+    message: String,
+    fallback: bool,
+}
+
+fn resolve_key(api_key: String) -> String {
+    if api_key.is_empty() {
         crate::backend_info("API key not provided in payload, falling back to OPENROUTER_API_KEY env var");
         env::var("OPENROUTER_API_KEY").unwrap_or_default()
     } else {
         api_key
-    };
-
-    if key.is_empty() {
-        crate::backend_error("OPENROUTER_API_KEY not set and no fallback available");
-        return Err("OPENROUTER_API_KEY not set".into());
     }
+}
 
-    let mdl = if model.is_empty() {
+fn resolve_model(model: String) -> String {
+    if model.is_empty() {
         crate::backend_info("Model not provided in payload, falling back to LLM_MODEL env var or default");
         env::var("LLM_MODEL").unwrap_or_else(|_| env::var("VITE_LLM_MODEL").unwrap_or_else(|_| "google/gemini-3-flash-preview".into()))
     } else {
         model
-    };
+    }
+}
 
-    // Parse messages from JSON string
-    let msgs: serde_json::Value =
-        serde_json::from_str(&messages).map_err(|e| {
-            crate::backend_error(format!("Failed to parse messages JSON: {}", e));
-            format!("Invalid messages JSON: {e}")
-        })?;
+/// Reject overrides OpenRouter would answer with an opaque 400.
+fn validate_overrides(model: Option<&str>, temperature: f32, max_tokens: u32) -> Result<(), String> {
+    if let Some(model) = model {
+        // vendor/model[:variant], e.g. "google/gemini-2.5-flash" or "meta-llama/llama-3.1-8b-instruct:free"
+        let valid = regex_lite::Regex::new(r"^[a-z0-9][a-z0-9._-]*/[A-Za-z0-9][A-Za-z0-9._-]*(:[a-z0-9._-]+)?$")
+            .map(|re| re.is_match(model))
+            .unwrap_or(false);
+        if !valid {
+            return Err(format!("Nieprawidłowy model '{}' — oczekiwano formatu 'dostawca/model'", model));
+        }
+    }
+    if !temperature.is_finite() || !(0.0..=2.0).contains(&temperature) {
+        return Err(format!("Temperatura {} poza zakresem 0–2", temperature));
+    }
+    if max_tokens == 0 || max_tokens > OPENROUTER_MAX_TOKENS {
+        return Err(format!("max_tokens {} poza zakresem 1–{}", max_tokens, OPENROUTER_MAX_TOKENS));
+    }
+    Ok(())
+}
 
-    crate::backend_info(format!(
-        "LLM payload prepared for {} (messages={})",
-        mdl,
-        msgs.as_array().map_or(0, |a| a.len())
-    ));
+/// Replace the leading system message with `prompt`, or insert one.
+fn apply_system_prompt(msgs: &mut serde_json::Value, prompt: &str) {
+    let Some(list) = msgs.as_array_mut() else { return };
+    let message = serde_json::json!({ "role": "system", "content": prompt });
+    match list.first_mut() {
+        Some(first) if first["role"] == "system" => *first = message,
+        _ => list.insert(0, message),
+    }
+}
 
-    let payload = serde_json::json!({
-        "model": mdl,
-        "messages": msgs,
-        "max_tokens": max_tokens,
-        "temperature": temperature,
-    });
+/// Local fallback from settings; None when `llm_local_base_url` is empty.
+fn local_provider() -> Option<Provider> {
+    let settings = crate::settings::load_settings();
+    let base_url = settings.llm_local_base_url.trim().to_string();
+    (!base_url.is_empty()).then(|| Provider::Local {
+        base_url,
+        model: settings.llm_local_model.trim().to_string(),
+    })
+}
+
+/// POST `payload` to the provider's chat endpoint; a non-2xx answer is an error.
+async fn send_chat(
+    client: &reqwest::Client,
+    provider: &Provider,
+    payload: &serde_json::Value,
+) -> Result<(reqwest::Response, RetryMeta), ProviderError> {
+    let fail = |message: String, fallback: bool| ProviderError { message, fallback };
 
-    let client = reqwest::Client::new();
-    let resp = client
-        .post(OPENROUTER_URL)
-        .header("Authorization", format!("Bearer {key}"))
+    let mut request = client
+        .post(provider.chat_url())
         .header("Content-Type", "application/json")
-        .header("HTTP-Referer", "https://broxeen.local")
-        .header("X-Title", "broxeen")
-        .json(&payload)
-        .send()
+        .json(payload);
+    request = match provider {
+        Provider::OpenRouter { api_key, .. } => request
+            .header("Authorization", format!("Bearer {api_key}"))
+            .header("HTTP-Referer", "https://broxeen.local")
+            .header("X-Title", "broxeen"),
+        Provider::Local { .. } => request.header("Authorization", "Bearer local"),
+    };
+
+    // 429/503 from OpenRouter means the completion was not started — safe to resend
+    let policy = RetryPolicy::default().allow_non_idempotent();
+    let (resp, meta): (_, RetryMeta) = send_with_retry(request, &policy)
         .await
-        .map_err(|e| {
-            crate::backend_error(format!("LLM HTTP request failed: {}", e));
-            format!("Request failed: {e}")
-        })?;
+        .map_err(|e| fail(format!("Request failed: {e}"), true))?;
 
     if !resp.status().is_success() {
         let status = resp.status();
         let body = resp.text().await.unwrap_or_default();
         let truncated = &body[..body.len().min(300)];
-        crate::backend_error(format!("LLM HTTP error {}: {}", status, truncated));
-        return Err(format!("HTTP {status}: {truncated}"));
+        let fallback = status.is_server_error() || status.as_u16() == 429;
+        return Err(fail(format!("HTTP {status}: {truncated}{}", meta.describe()), fallback));
     }
+    Ok((resp, meta))
+}
+
+async fn call_provider(
+    provider: &Provider,
+    msgs: &serde_json::Value,
+    max_tokens: u32,
+    temperature: f32,
+) -> Result<(LlmResponse, serde_json::Value), ProviderError> {
+    let fail = |message: String, fallback: bool| ProviderError { message, fallback };
+
+    let payload = serde_json::json!({
+        "model": provider.model(),
+        "messages": msgs,
+        "max_tokens": max_tokens,
+        "temperature": temperature,
+    });
 
-    crate::backend_info("LLM HTTP response received successfully");
+    let client = crate::http_client::client(Duration::from_secs(120)).map_err(|e| fail(e, false))?;
+    let (resp, meta) = send_chat(&client, provider, &payload).await?;
 
     let data: serde_json::Value = resp
         .json()
         .await
-        .map_err(|e| {
-            crate::backend_error(format!("Failed to parse LLM JSON response: {}", e));
-            format!("JSON parse error: {e}")
-        })?;
+        .map_err(|e| fail(format!("JSON parse error: {e}"), false))?;
 
     let text = data["choices"][0]["message"]["content"]
         .as_str()
@@ -110,17 +276,707 @@ pub async fn llm_chat(
 
     let response_model = data["model"]
         .as_str()
-        .unwrap_or(&mdl)
+        .unwrap_or(provider.model())
         .to_string();
 
+    Ok((
+        LlmResponse {
+            text,
+            model: response_model,
+            retries: meta.retries,
+            retry_backoff_ms: meta.total_backoff_ms,
+            provider: provider.name().to_string(),
+            params: None,
+        },
+        data,
+    ))
+}
+
+/// OpenRouter (when a key is available) followed by the local fallback.
+fn provider_chain(api_key: String, model: Option<String>) -> Result<Vec<Provider>, String> {
+    let key = resolve_key(api_key);
+    let local = local_provider();
+    let mut chain = Vec::new();
+    if !key.is_empty() {
+        chain.push(Provider::OpenRouter { api_key: key, model: resolve_model(model.unwrap_or_default()) });
+    } else if let Some(local) = &local {
+        crate::backend_warn(format!("OPENROUTER_API_KEY not set — using the local LLM provider at {}", local.chat_url()));
+    } else {
+        crate::backend_error("OPENROUTER_API_KEY not set and no local LLM configured (llm_local_base_url)");
+        return Err("OPENROUTER_API_KEY not set".into());
+    }
+    chain.extend(local);
+    Ok(chain)
+}
+
+/// Parse the `messages` JSON, prepend the conversation history and apply the
+/// system prompt. Also returns the new turn to record after the answer.
+fn prepare_messages(
+    messages: &str,
+    conversation_id: Option<i64>,
+    context_tokens: Option<usize>,
+    system_prompt: Option<&str>,
+) -> Result<(serde_json::Value, Vec<ChatMessage>), String> {
+    // Parse messages from JSON string
+    let mut msgs: serde_json::Value =
+        serde_json::from_str(messages).map_err(|e| {
+            crate::backend_error(format!("Failed to parse messages JSON: {}", e));
+            format!("Invalid messages JSON: {e}")
+        })?;
+
+    let mut new_turn = Vec::new();
+    if let Some(id) = conversation_id {
+        new_turn = serde_json::from_value::<Vec<ChatMessage>>(msgs.clone())
+            .map_err(|e| format!("Invalid messages for conversation: {e}"))?;
+        let budget = context_tokens.unwrap_or(DEFAULT_CONTEXT_TOKENS);
+        let context = llm_conversations::build_context(id, &new_turn, budget)?;
+        msgs = serde_json::to_value(&context).map_err(|e| e.to_string())?;
+    }
+    if let Some(prompt) = system_prompt {
+        apply_system_prompt(&mut msgs, prompt);
+    }
+    Ok((msgs, new_turn))
+}
+
+fn log_payload(provider: &Provider, msgs: &serde_json::Value) {
+    crate::backend_info(format!(
+        "LLM payload prepared for {}/{} (messages={})",
+        provider.name(),
+        provider.model(),
+        msgs.as_array().map_or(0, |a| a.len())
+    ));
+}
+
+// ── Tauri command ────────────────────────────────────
+
+/// Tauri command: send chat completion to OpenRouter, falling back to the
+/// local provider on network / 5xx errors or a missing key.
+/// `messages` is a JSON string of the messages array. With `conversation_id`
+/// it holds only the new turn: the stored history is prepended, windowed to
+/// `context_tokens`, and the turn plus the answer are saved afterwards.
+///
+/// `model`, `temperature` (0–2), `max_tokens` and `system_prompt` override
+/// the defaults for this call only; `model` applies to OpenRouter (the local
+/// fallback keeps `llm_local_model`). The effective values are echoed in
+/// `LlmResponse::params`.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn llm_chat(
+    messages: String,
+    api_key: String,
+    model: Option<String>,
+    max_tokens: Option<u32>,
+    temperature: Option<f32>,
+    system_prompt: Option<String>,
+    conversation_id: Option<i64>,
+    context_tokens: Option<usize>,
+) -> Result<LlmResponse, String> {
+    let model = model.map(|m| m.trim().to_string()).filter(|m| !m.is_empty());
+    let max_tokens = max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+    let temperature = temperature.unwrap_or(DEFAULT_TEMPERATURE);
+    let system_prompt = system_prompt.filter(|p| !p.trim().is_empty());
     crate::backend_info(format!(
-        "LLM response extracted (model='{}', text_len={})",
-        response_model,
-        text.len()
+        "Command llm_chat invoked (model={:?}, max_tokens={}, temperature={}, system_prompt={}, conversation={:?})",
+        model, max_tokens, temperature, system_prompt.is_some(), conversation_id
     ));
+    validate_overrides(model.as_deref(), temperature, max_tokens)?;
 
-    Ok(LlmResponse {
-        text,
-        model: response_model,
-    })
+    let chain = provider_chain(api_key, model)?;
+    let (msgs, new_turn) = prepare_messages(&messages, conversation_id, context_tokens, system_prompt.as_deref())?;
+
+    let (provider, mut response, data, max_tokens) =
+        call_chain(&chain, &msgs, max_tokens, temperature, "llm_chat").await?;
+    response.params = Some(LlmCallParams {
+        model: provider.model().to_string(),
+        temperature,
+        max_tokens,
+        system_prompt: system_prompt.clone(),
+    });
+    if matches!(provider, Provider::OpenRouter { .. }) {
+        crate::llm_usage::record("llm_chat", provider.model(), &data);
+    }
+    crate::backend_info(format!(
+        "LLM response extracted (provider={}, model='{}', text_len={})",
+        response.provider,
+        response.model,
+        response.text.len()
+    ));
+    // The answer is already paid for; a failed save must not discard it
+    if let Some(id) = conversation_id {
+        if let Err(e) = llm_conversations::record_turn(id, &new_turn, &response.text) {
+            crate::backend_warn(format!("Conversation {}: failed to save turn: {}", id, e));
+        }
+    }
+    Ok(response)
+}
+
+/// Ask each provider in turn until one answers. Network errors, 429 and 5xx
+/// move on to the next one; any other failure ends the chain. Returns the
+/// provider that answered and the `max_tokens` actually sent to it.
+async fn call_chain<'a>(
+    chain: &'a [Provider],
+    msgs: &serde_json::Value,
+    max_tokens: u32,
+    temperature: f32,
+    feature: &str,
+) -> Result<(&'a Provider, LlmResponse, serde_json::Value, u32), String> {
+    let mut errors = Vec::new();
+    for provider in chain {
+        log_payload(provider, msgs);
+        let max_tokens = max_tokens.min(provider.max_tokens_limit());
+        match call_provider(provider, msgs, max_tokens, temperature).await {
+            Ok((response, data)) => return Ok((provider, response, data, max_tokens)),
+            Err(e) => {
+                crate::backend_warn(format!("LLM {} failed for {}: {}", provider.name(), feature, e.message));
+                errors.push(format!("{}: {}", provider.name(), e.message));
+                if !e.fallback {
+                    break;
+                }
+            }
+        }
+    }
+    Err(errors.join("; "))
+}
+
+/// One-shot completion for backend features (no conversation, no overrides):
+/// the configured OpenRouter model with the local fallback, usage recorded
+/// under `feature`.
+pub(crate) async fn complete(
+    feature: &str,
+    messages: serde_json::Value,
+    max_tokens: u32,
+    temperature: f32,
+) -> Result<LlmResponse, String> {
+    let chain = provider_chain(String::new(), None)?;
+    let (provider, response, data, _) = call_chain(&chain, &messages, max_tokens, temperature, feature).await?;
+    if matches!(provider, Provider::OpenRouter { .. }) {
+        crate::llm_usage::record(feature, provider.model(), &data);
+    }
+    Ok(response)
+}
+
+// ── Streaming ────────────────────────────────────────
+
+/// One `data:` line of an OpenAI-style SSE stream.
+#[derive(Debug, PartialEq)]
+enum SseEvent {
+    Data(serde_json::Value),
+    Done,
+}
+
+/// Splits SSE bytes into `data:` events; a line may span several chunks.
+/// Comments (`: OPENROUTER PROCESSING`), other fields and unparsable data are
+/// skipped.
+#[derive(Default)]
+struct SseParser {
+    buf: Vec<u8>,
+}
+
+impl SseParser {
+    fn push(&mut self, bytes: &[u8]) -> Vec<SseEvent> {
+        self.buf.extend_from_slice(bytes);
+        let mut events = Vec::new();
+        while let Some(end) = self.buf.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buf.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim_end().strip_prefix("data:") else { continue };
+            match data.trim_start() {
+                "[DONE]" => events.push(SseEvent::Done),
+                json => {
+                    if let Ok(value) = serde_json::from_str(json) {
+                        events.push(SseEvent::Data(value));
+                    }
+                }
+            }
+        }
+        events
+    }
+}
+
+/// A streamed answer; `data` mimics a non-streamed response (`model`,
+/// `usage`) for `llm_usage::record`.
+struct StreamedReply {
+    response: LlmResponse,
+    data: serde_json::Value,
+    cancelled: bool,
+}
+
+/// Why a stream failed; `text` is what arrived before the failure. Only a
+/// failure before the first delta may fall back to the next provider.
+struct StreamError {
+    error: ProviderError,
+    text: String,
+}
+
+/// Stream one completion, passing every delta to `on_delta`. `cancel` drops
+/// the HTTP request, mid-stream or while connecting.
+async fn stream_provider(
+    provider: &Provider,
+    msgs: &serde_json::Value,
+    max_tokens: u32,
+    temperature: f32,
+    cancel: &Notify,
+    mut on_delta: impl FnMut(&str),
+) -> Result<StreamedReply, StreamError> {
+    let fail = |message: String, fallback: bool, text: &str| StreamError {
+        error: ProviderError { message, fallback },
+        text: text.to_string(),
+    };
+
+    let payload = serde_json::json!({
+        "model": provider.model(),
+        "messages": msgs,
+        "max_tokens": max_tokens,
+        "temperature": temperature,
+        "stream": true,
+        "stream_options": { "include_usage": true },
+    });
+
+    let mut reply = StreamedReply {
+        response: LlmResponse {
+            text: String::new(),
+            model: provider.model().to_string(),
+            retries: 0,
+            retry_backoff_ms: 0,
+            provider: provider.name().to_string(),
+            params: None,
+        },
+        data: serde_json::json!({}),
+        cancelled: false,
+    };
+
+    let client = crate::http_client::client(STREAM_TIMEOUT).map_err(|e| fail(e, false, ""))?;
+    let (mut resp, meta) = tokio::select! {
+        sent = send_chat(&client, provider, &payload) => {
+            sent.map_err(|error| StreamError { error, text: String::new() })?
+        }
+        _ = cancel.notified() => {
+            reply.cancelled = true;
+            return Ok(reply);
+        }
+    };
+    reply.response.retries = meta.retries;
+    reply.response.retry_backoff_ms = meta.total_backoff_ms;
+
+    let mut parser = SseParser::default();
+    let mut usage = serde_json::Value::Null;
+    'stream: loop {
+        let chunk = tokio::select! {
+            chunk = resp.chunk() => chunk,
+            _ = cancel.notified() => {
+                reply.cancelled = true;
+                break;
+            }
+        };
+        let text = &reply.response.text;
+        let bytes = match chunk {
+            Ok(Some(bytes)) => bytes,
+            Ok(None) => break,
+            Err(e) => return Err(fail(format!("Stream interrupted: {e}"), text.is_empty(), text)),
+        };
+        for event in parser.push(&bytes) {
+            let data = match event {
+                SseEvent::Done => break 'stream,
+                SseEvent::Data(data) => data,
+            };
+            // OpenRouter reports mid-stream failures as an `error` chunk
+            if let Some(error) = data.get("error") {
+                let message = error["message"].as_str().map_or_else(|| error.to_string(), str::to_string);
+                let text = &reply.response.text;
+                return Err(fail(format!("Stream error: {message}"), text.is_empty(), text));
+            }
+            if let Some(model) = data["model"].as_str() {
+                reply.response.model = model.to_string();
+            }
+            if data["usage"].is_object() {
+                usage = data["usage"].clone();
+            }
+            if let Some(delta) = data["choices"][0]["delta"]["content"].as_str().filter(|d| !d.is_empty()) {
+                reply.response.text.push_str(delta);
+                on_delta(delta);
+            }
+        }
+    }
+    // Dropping `resp` here closes the connection of a cancelled stream
+
+    reply.data = serde_json::json!({ "model": reply.response.model, "usage": usage });
+    Ok(reply)
+}
+
+/// Tauri command: like `llm_chat`, but streams the answer. Every delta is
+/// emitted as `broxeen:llm_token` and, once the request is under way, the call
+/// ends with one `broxeen:llm_done` (full text and usage, or the error). The
+/// result is the same `LlmResponse` as `llm_chat` returns.
+///
+/// The local provider is tried only when OpenRouter fails before its first
+/// token; a later failure ends the call with the partial text.
+/// `llm_chat_cancel(request_id)` aborts the request and returns what arrived
+/// so far; a cancelled answer is not saved to the conversation.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn llm_chat_stream(
+    app: AppHandle,
+    messages: String,
+    api_key: String,
+    model: Option<String>,
+    max_tokens: Option<u32>,
+    temperature: Option<f32>,
+    system_prompt: Option<String>,
+    conversation_id: Option<i64>,
+    context_tokens: Option<usize>,
+    request_id: Option<String>,
+) -> Result<LlmResponse, String> {
+    let model = model.map(|m| m.trim().to_string()).filter(|m| !m.is_empty());
+    let max_tokens = max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+    let temperature = temperature.unwrap_or(DEFAULT_TEMPERATURE);
+    let system_prompt = system_prompt.filter(|p| !p.trim().is_empty());
+    let request_id = request_id
+        .filter(|id| !id.trim().is_empty())
+        .unwrap_or_else(|| format!("llm-{}", STREAM_COUNTER.fetch_add(1, Ordering::Relaxed) + 1));
+    crate::backend_info(format!(
+        "Command llm_chat_stream invoked [{}] (model={:?}, max_tokens={}, temperature={}, system_prompt={}, conversation={:?})",
+        request_id, model, max_tokens, temperature, system_prompt.is_some(), conversation_id
+    ));
+    validate_overrides(model.as_deref(), temperature, max_tokens)?;
+
+    let chain = provider_chain(api_key, model)?;
+    let (msgs, new_turn) = prepare_messages(&messages, conversation_id, context_tokens, system_prompt.as_deref())?;
+
+    let cancel = register_stream(&request_id)?;
+    let result = stream_chain(&app, &request_id, &cancel, &chain, &msgs, max_tokens, temperature, &system_prompt).await;
+    STREAMS.lock().unwrap_or_else(|e| e.into_inner()).remove(&request_id);
+
+    let mut done = LlmDoneEvent {
+        request_id,
+        text: String::new(),
+        model: String::new(),
+        provider: String::new(),
+        usage: None,
+        cancelled: false,
+        error: None,
+    };
+    let result = match result {
+        Ok(StreamedReply { response, data, cancelled }) => {
+            done.text = response.text.clone();
+            done.model = response.model.clone();
+            done.provider = response.provider.clone();
+            done.usage = data["usage"].is_object().then(|| data["usage"].clone());
+            done.cancelled = cancelled;
+            if let Some(id) = conversation_id.filter(|_| !cancelled) {
+                if let Err(e) = llm_conversations::record_turn(id, &new_turn, &response.text) {
+                    crate::backend_warn(format!("Conversation {}: failed to save turn: {}", id, e));
+                }
+            }
+            Ok(response)
+        }
+        Err(StreamError { error, text }) => {
+            done.text = text;
+            Err(error.message)
+        }
+    };
+    if let Err(e) = &result {
+        done.error = Some(e.clone());
+    }
+    let _ = app.emit(DONE_EVENT, done);
+    result
+}
+
+/// Cancellation handle for a new stream; an id still in use is rejected so
+/// `llm_chat_cancel` can never reach the wrong request.
+fn register_stream(request_id: &str) -> Result<Arc<Notify>, String> {
+    let mut streams = STREAMS.lock().unwrap_or_else(|e| e.into_inner());
+    if streams.contains_key(request_id) {
+        return Err(format!("Żądanie LLM o id '{}' już trwa", request_id));
+    }
+    let cancel = Arc::new(Notify::new());
+    streams.insert(request_id.to_string(), cancel.clone());
+    Ok(cancel)
+}
+
+/// Stream from each provider in turn until one answers, emitting
+/// `broxeen:llm_token` per delta. The error carries the partial text.
+#[allow(clippy::too_many_arguments)]
+async fn stream_chain(
+    app: &AppHandle,
+    request_id: &str,
+    cancel: &Notify,
+    chain: &[Provider],
+    msgs: &serde_json::Value,
+    max_tokens: u32,
+    temperature: f32,
+    system_prompt: &Option<String>,
+) -> Result<StreamedReply, StreamError> {
+    let mut errors = Vec::new();
+    for provider in chain {
+        log_payload(provider, msgs);
+        let max_tokens = max_tokens.min(provider.max_tokens_limit());
+        let mut index = 0;
+        let on_delta = |delta: &str| {
+            let event = LlmTokenEvent { request_id: request_id.to_string(), delta: delta.to_string(), index };
+            let _ = app.emit(TOKEN_EVENT, event);
+            index += 1;
+        };
+        match stream_provider(provider, msgs, max_tokens, temperature, cancel, on_delta).await {
+            Ok(mut reply) => {
+                reply.response.params = Some(LlmCallParams {
+                    model: provider.model().to_string(),
+                    temperature,
+                    max_tokens,
+                    system_prompt: system_prompt.clone(),
+                });
+                if matches!(provider, Provider::OpenRouter { .. }) {
+                    crate::llm_usage::record("llm_chat", provider.model(), &reply.data);
+                }
+                crate::backend_info(format!(
+                    "LLM stream finished [{}] (provider={}, model='{}', text_len={}, cancelled={})",
+                    request_id,
+                    reply.response.provider,
+                    reply.response.model,
+                    reply.response.text.len(),
+                    reply.cancelled
+                ));
+                return Ok(reply);
+            }
+            Err(e) => {
+                crate::backend_error(format!("LLM {} stream failed [{}]: {}", provider.name(), request_id, e.error.message));
+                errors.push(format!("{}: {}", provider.name(), e.error.message));
+                if !e.error.fallback || !e.text.is_empty() {
+                    return Err(StreamError { error: ProviderError { message: errors.join("; "), fallback: false }, text: e.text });
+                }
+            }
+        }
+    }
+
+    Err(StreamError { error: ProviderError { message: errors.join("; "), fallback: false }, text: String::new() })
+}
+
+/// Abort a running `llm_chat_stream`; false when no stream has that id.
+#[tauri::command]
+pub fn llm_chat_cancel(request_id: String) -> bool {
+    let cancel = STREAMS.lock().unwrap_or_else(|e| e.into_inner()).get(&request_id).cloned();
+    match cancel {
+        Some(cancel) => {
+            crate::backend_info(format!("llm_chat_cancel [{}]", request_id));
+            cancel.notify_one();
+            true
+        }
+        None => false,
+    }
+}
+
+async fn probe(client: &reqwest::Client, provider: &Provider) -> LlmProviderStatus {
+    let mut status = LlmProviderStatus {
+        provider: provider.name().to_string(),
+        model: provider.model().to_string(),
+        base_url: provider.models_url(),
+        configured: true,
+        reachable: false,
+        status_code: None,
+        latency_ms: None,
+        error: None,
+    };
+    let mut request = client.get(provider.models_url());
+    if let Provider::OpenRouter { api_key, .. } = provider {
+        request = request.header("Authorization", format!("Bearer {api_key}"));
+    }
+    let started = Instant::now();
+    match request.send().await {
+        Ok(resp) => {
+            status.latency_ms = Some(started.elapsed().as_millis() as u64);
+            status.status_code = Some(resp.status().as_u16());
+            status.reachable = resp.status().is_success();
+            if !status.reachable {
+                status.error = Some(format!("HTTP {}", resp.status()));
+            }
+        }
+        Err(e) => status.error = Some(e.to_string()),
+    }
+    status
+}
+
+/// Reachability of each configured provider (GET /models, 5 s timeout).
+#[tauri::command]
+pub async fn llm_providers_status(api_key: Option<String>) -> Result<Vec<LlmProviderStatus>, String> {
+    crate::backend_info("Command llm_providers_status invoked");
+    let client = crate::http_client::client(STATUS_TIMEOUT)?;
+    let key = resolve_key(api_key.unwrap_or_default());
+
+    let mut out = Vec::new();
+    let openrouter = Provider::OpenRouter { api_key: key.clone(), model: resolve_model(String::new()) };
+    if key.is_empty() {
+        out.push(LlmProviderStatus {
+            provider: openrouter.name().to_string(),
+            model: openrouter.model().to_string(),
+            base_url: openrouter.models_url(),
+            configured: false,
+            reachable: false,
+            status_code: None,
+            latency_ms: None,
+            error: Some("OPENROUTER_API_KEY not set".into()),
+        });
+    } else {
+        out.push(probe(&client, &openrouter).await);
+    }
+
+    match local_provider() {
+        Some(local) => out.push(probe(&client, &local).await),
+        None => out.push(LlmProviderStatus {
+            provider: "local".into(),
+            model: String::new(),
+            base_url: String::new(),
+            configured: false,
+            reachable: false,
+            status_code: None,
+            latency_ms: None,
+            error: Some("llm_local_base_url not set".into()),
+        }),
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_are_validated() {
+        assert!(validate_overrides(Some("google/gemini-2.5-flash"), 0.2, 512).is_ok());
+        assert!(validate_overrides(Some("meta-llama/llama-3.1-8b-instruct:free"), 2.0, 1).is_ok());
+        assert!(validate_overrides(None, 0.0, OPENROUTER_MAX_TOKENS).is_ok());
+        assert!(validate_overrides(Some("gpt4o"), 0.7, 512).unwrap_err().contains("dostawca/model"));
+        assert!(validate_overrides(Some("google/gemini 2.5"), 0.7, 512).is_err());
+        assert!(validate_overrides(None, 2.5, 512).unwrap_err().contains("0–2"));
+        assert!(validate_overrides(None, f32::NAN, 512).is_err());
+        assert!(validate_overrides(None, 0.7, 0).is_err());
+        assert!(validate_overrides(None, 0.7, OPENROUTER_MAX_TOKENS + 1).is_err());
+    }
+
+    #[test]
+    fn test_system_prompt_replaces_leading_system_message() {
+        let mut msgs = serde_json::json!([
+            { "role": "system", "content": "old" },
+            { "role": "user", "content": "hi" },
+        ]);
+        apply_system_prompt(&mut msgs, "new");
+        assert_eq!(msgs.as_array().unwrap().len(), 2);
+        assert_eq!(msgs[0]["content"], "new");
+
+        let mut msgs = serde_json::json!([{ "role": "user", "content": "hi" }]);
+        apply_system_prompt(&mut msgs, "be brief");
+        assert_eq!(msgs[0]["role"], "system");
+        assert_eq!(msgs[1]["content"], "hi");
+    }
+
+    #[test]
+    fn test_sse_parser_handles_split_lines() {
+        let mut parser = SseParser::default();
+        let first = "data: {\"choices\":[{\"delta\":{\"content\":\"Cze";
+        assert!(parser.push(b": OPENROUTER PROCESSING\n\n").is_empty());
+        assert!(parser.push(first.as_bytes()).is_empty());
+        let events = parser.push("ść\"}}]}\r\n\ndata: {\"usage\":{\"total_tokens\":7}}\ndata: [DONE]\n".as_bytes());
+        assert_eq!(events.len(), 3);
+        assert!(matches!(&events[0], SseEvent::Data(d) if d["choices"][0]["delta"]["content"] == "Cześć"));
+        assert!(matches!(&events[1], SseEvent::Data(d) if d["usage"]["total_tokens"] == 7));
+        assert_eq!(events[2], SseEvent::Done);
+        assert!(parser.push(b"data: {not json\nevent: ping\n").is_empty());
+    }
+
+    /// Answers every request with `status` and `body`
+    async fn canned_server(status: &'static str, body: &'static str) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut sock, _)) = listener.accept().await {
+                let mut buf = [0u8; 4096];
+                let _ = sock.read(&mut buf).await;
+                let reply = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nRetry-After: 0\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = sock.write_all(reply.as_bytes()).await;
+                let _ = sock.shutdown().await;
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    fn local(base_url: String, model: &str) -> Provider {
+        Provider::Local { base_url, model: model.into() }
+    }
+
+    #[tokio::test]
+    async fn test_chain_falls_back_on_server_error_only() {
+        const ANSWER: &str = r#"{"model":"llama3","choices":[{"message":{"content":"Hej"}}]}"#;
+        let down = canned_server("503 Service Unavailable", "{}").await;
+        let bad_request = canned_server("400 Bad Request", r#"{"error":"bad model"}"#).await;
+        let up = canned_server("200 OK", ANSWER).await;
+        let msgs = serde_json::json!([{ "role": "user", "content": "hi" }]);
+
+        let chain = [local(down.clone(), "primary"), local(up.clone(), "llama3")];
+        let (provider, response, _, max_tokens) = call_chain(&chain, &msgs, 100_000, 0.7, "test").await.unwrap();
+        assert_eq!(provider.model(), "llama3");
+        assert_eq!((response.text.as_str(), response.provider.as_str()), ("Hej", "local"));
+        assert_eq!(max_tokens, LOCAL_MAX_TOKENS);
+
+        // A rejected request is not retried elsewhere
+        let chain = [local(bad_request, "primary"), local(up, "llama3")];
+        let err = call_chain(&chain, &msgs, 64, 0.7, "test").await.unwrap_err();
+        assert!(err.starts_with("local: HTTP 400") && err.contains("bad model") && !err.contains(';'), "{}", err);
+
+        let chain = [local(down, "primary")];
+        assert!(call_chain(&chain, &msgs, 64, 0.7, "test").await.unwrap_err().contains("HTTP 503"));
+    }
+
+    #[test]
+    fn test_duplicate_request_id_is_rejected() {
+        let first = register_stream("dup-test").unwrap();
+        assert!(register_stream("dup-test").unwrap_err().contains("dup-test"));
+        // The running stream keeps its handle
+        assert!(Arc::ptr_eq(&first, STREAMS.lock().unwrap().get("dup-test").unwrap()));
+        STREAMS.lock().unwrap().remove("dup-test");
+        assert!(register_stream("dup-test").is_ok());
+        STREAMS.lock().unwrap().remove("dup-test");
+    }
+
+    #[tokio::test]
+    async fn test_stream_cancel_aborts_mid_stream() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Sends one delta, then keeps the connection open
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut sock, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = sock.read(&mut buf).await;
+            let body = "data: {\"model\":\"llama3\",\"choices\":[{\"delta\":{\"content\":\"Hej\"}}]}\n\n";
+            let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nTransfer-Encoding: chunked\r\n\r\n";
+            let _ = sock.write_all(format!("{}{:x}\r\n{}\r\n", head, body.len(), body).as_bytes()).await;
+            // Returns once the client hangs up
+            let _ = sock.read(&mut buf).await;
+        });
+
+        let provider = Provider::Local { base_url: format!("http://{}", addr), model: "llama3".into() };
+        let msgs = serde_json::json!([{ "role": "user", "content": "hi" }]);
+        let cancel = Notify::new();
+        let mut deltas = Vec::new();
+        let reply = tokio::time::timeout(
+            Duration::from_secs(5),
+            stream_provider(&provider, &msgs, 64, 0.7, &cancel, |d| {
+                deltas.push(d.to_string());
+                cancel.notify_one();
+            }),
+        )
+        .await
+        .expect("cancel must end the stream")
+        .unwrap_or_else(|e| panic!("{}", e.error.message));
+
+        assert!(reply.cancelled);
+        assert_eq!(deltas, vec!["Hej"]);
+        assert_eq!((reply.response.text.as_str(), reply.response.model.as_str()), ("Hej", "llama3"));
+    }
 }
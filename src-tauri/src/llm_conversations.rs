@@ -0,0 +1,390 @@
+//! llm_conversations.rs — Persistent LLM conversations and context windowing.
+//!
+//! Conversations live in `broxeen_conversations.db` so chats survive a
+//! restart and the frontend no longer has to resend the whole history:
+//! `llm_chat` with a `conversation_id` loads the stored messages, trims them
+//! to a token budget (`window_messages`) and appends the new turn plus the
+//! answer. Token counts are estimates (≈ 4 characters per token).
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::logging::backend_info;
+
+const CONVERSATIONS_DB: &str = "broxeen_conversations.db";
+/// Default prompt budget for stored history
+pub const DEFAULT_CONTEXT_TOKENS: usize = 8000;
+/// Role/formatting overhead per message
+const TOKENS_PER_MESSAGE: usize = 4;
+
+lazy_static::lazy_static! {
+    static ref CONN: Mutex<Option<Connection>> = Mutex::new(None);
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Conversation {
+    pub id: i64,
+    pub title: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub message_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredMessage {
+    pub id: i64,
+    pub role: String,
+    pub content: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationDetail {
+    pub conversation: Conversation,
+    /// Oldest first
+    pub messages: Vec<StoredMessage>,
+}
+
+// ── Windowing ────────────────────────────────────────
+
+pub fn estimate_tokens(message: &ChatMessage) -> usize {
+    message.content.chars().count().div_ceil(4) + TOKENS_PER_MESSAGE
+}
+
+/// Fit `messages` into `budget` tokens: system messages are always kept,
+/// then the newest other messages until the budget runs out. The newest
+/// message is kept even when it alone exceeds the budget. Order is preserved.
+pub fn window_messages(messages: &[ChatMessage], budget: usize) -> Vec<ChatMessage> {
+    let system_tokens: usize = messages
+        .iter()
+        .filter(|m| m.role == "system")
+        .map(estimate_tokens)
+        .sum();
+    let mut remaining = budget.saturating_sub(system_tokens);
+    let mut keep = vec![false; messages.len()];
+    let mut newest_kept = false;
+
+    for (i, message) in messages.iter().enumerate().rev() {
+        if message.role == "system" {
+            keep[i] = true;
+            continue;
+        }
+        let cost = estimate_tokens(message);
+        if cost <= remaining || !newest_kept {
+            keep[i] = true;
+            remaining = remaining.saturating_sub(cost);
+            newest_kept = true;
+        } else {
+            // Older messages are dropped even if a shorter one would still fit,
+            // so the kept history stays contiguous
+            remaining = 0;
+        }
+    }
+
+    messages
+        .iter()
+        .zip(keep)
+        .filter(|(_, keep)| *keep)
+        .map(|(m, _)| m.clone())
+        .collect()
+}
+
+// ── Storage ──────────────────────────────────────────
+
+fn db_path() -> PathBuf {
+    let base = dirs::data_local_dir()
+        .or_else(dirs::data_dir)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("broxeen");
+    let _ = std::fs::create_dir_all(&base);
+    base.join(CONVERSATIONS_DB)
+}
+
+fn init_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "PRAGMA foreign_keys = ON;
+        CREATE TABLE IF NOT EXISTS llm_conversations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            title TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS llm_messages (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            conversation_id INTEGER NOT NULL REFERENCES llm_conversations(id) ON DELETE CASCADE,
+            role TEXT NOT NULL CHECK (role IN ('system', 'user', 'assistant')),
+            content TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_llm_messages_conversation ON llm_messages(conversation_id, id);",
+    )
+}
+
+fn with_conn<T>(f: impl FnOnce(&Connection) -> Result<T, String>) -> Result<T, String> {
+    let mut guard = CONN.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.is_none() {
+        let path = db_path();
+        let conn = Connection::open(&path).map_err(|e| format!("Conversations DB open failed: {}", e))?;
+        init_schema(&conn).map_err(|e| format!("Conversations DB schema failed: {}", e))?;
+        backend_info(format!("LLM conversations opened at {}", path.display()));
+        *guard = Some(conn);
+    }
+    f(guard.as_ref().expect("conversations connection initialised"))
+}
+
+fn sql_err(e: rusqlite::Error) -> String {
+    format!("Conversations DB error: {}", e)
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339()
+}
+
+fn validate_role(role: &str) -> Result<(), String> {
+    match role {
+        "system" | "user" | "assistant" => Ok(()),
+        other => Err(format!("Unknown message role '{}' (system, user, assistant)", other)),
+    }
+}
+
+fn create(conn: &Connection, title: &str, system_prompt: Option<&str>) -> Result<Conversation, String> {
+    let ts = now();
+    conn.execute(
+        "INSERT INTO llm_conversations (title, created_at, updated_at) VALUES (?1, ?2, ?2)",
+        params![title, ts],
+    )
+    .map_err(sql_err)?;
+    let id = conn.last_insert_rowid();
+    if let Some(prompt) = system_prompt.filter(|p| !p.trim().is_empty()) {
+        append(conn, id, "system", prompt)?;
+    }
+    get_conversation(conn, id)?.ok_or_else(|| format!("Conversation {} not found", id))
+}
+
+fn get_conversation(conn: &Connection, id: i64) -> Result<Option<Conversation>, String> {
+    conn.query_row(
+        "SELECT c.id, c.title, c.created_at, c.updated_at,
+                (SELECT COUNT(*) FROM llm_messages m WHERE m.conversation_id = c.id)
+         FROM llm_conversations c WHERE c.id = ?1",
+        [id],
+        |row| {
+            Ok(Conversation {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                created_at: row.get(2)?,
+                updated_at: row.get(3)?,
+                message_count: row.get(4)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(sql_err)
+}
+
+fn append(conn: &Connection, id: i64, role: &str, content: &str) -> Result<StoredMessage, String> {
+    validate_role(role)?;
+    let ts = now();
+    let updated = conn
+        .execute(
+            "UPDATE llm_conversations SET updated_at = ?1 WHERE id = ?2",
+            params![ts, id],
+        )
+        .map_err(sql_err)?;
+    if updated == 0 {
+        return Err(format!("Conversation {} not found", id));
+    }
+    conn.execute(
+        "INSERT INTO llm_messages (conversation_id, role, content, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![id, role, content, ts],
+    )
+    .map_err(sql_err)?;
+    Ok(StoredMessage {
+        id: conn.last_insert_rowid(),
+        role: role.to_string(),
+        content: content.to_string(),
+        created_at: ts,
+    })
+}
+
+/// The last `limit` messages (all when None), oldest first.
+fn messages(conn: &Connection, id: i64, limit: Option<usize>) -> Result<Vec<StoredMessage>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, role, content, created_at FROM llm_messages
+             WHERE conversation_id = ?1 ORDER BY id DESC LIMIT ?2",
+        )
+        .map_err(sql_err)?;
+    let limit = limit.map(|l| l as i64).unwrap_or(-1);
+    let rows = stmt
+        .query_map(params![id, limit], |row| {
+            Ok(StoredMessage {
+                id: row.get(0)?,
+                role: row.get(1)?,
+                content: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        })
+        .map_err(sql_err)?;
+    let mut out = rows.collect::<Result<Vec<_>, _>>().map_err(sql_err)?;
+    out.reverse();
+    Ok(out)
+}
+
+/// Stored history plus `new_messages`, windowed to `budget` tokens — the
+/// prompt `llm_chat` sends for a conversation.
+pub fn build_context(id: i64, new_messages: &[ChatMessage], budget: usize) -> Result<Vec<ChatMessage>, String> {
+    with_conn(|conn| {
+        if get_conversation(conn, id)?.is_none() {
+            return Err(format!("Conversation {} not found", id));
+        }
+        let mut history: Vec<ChatMessage> = messages(conn, id, None)?
+            .into_iter()
+            .map(|m| ChatMessage { role: m.role, content: m.content })
+            .collect();
+        history.extend(new_messages.iter().cloned());
+        Ok(window_messages(&history, budget))
+    })
+}
+
+/// Persist a completed turn: the new messages and the assistant answer.
+pub fn record_turn(id: i64, new_messages: &[ChatMessage], answer: &str) -> Result<(), String> {
+    with_conn(|conn| {
+        let tx = conn.unchecked_transaction().map_err(sql_err)?;
+        for message in new_messages {
+            append(&tx, id, &message.role, &message.content)?;
+        }
+        append(&tx, id, "assistant", answer)?;
+        tx.commit().map_err(sql_err)
+    })
+}
+
+// ── Tauri commands ───────────────────────────────────
+
+#[tauri::command]
+pub fn llm_conversation_create(title: Option<String>, system_prompt: Option<String>) -> Result<Conversation, String> {
+    let title = title
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| "Nowa rozmowa".to_string());
+    backend_info(format!("Command llm_conversation_create invoked (title='{}')", title));
+    with_conn(|conn| create(conn, &title, system_prompt.as_deref()))
+}
+
+#[tauri::command]
+pub fn llm_conversation_append(id: i64, role: String, content: String) -> Result<StoredMessage, String> {
+    with_conn(|conn| append(conn, id, &role, &content))
+}
+
+/// Most recently updated first.
+#[tauri::command]
+pub fn llm_conversation_list() -> Result<Vec<Conversation>, String> {
+    with_conn(|conn| {
+        let mut stmt = conn
+            .prepare("SELECT id FROM llm_conversations ORDER BY updated_at DESC, id DESC")
+            .map_err(sql_err)?;
+        let ids = stmt
+            .query_map([], |row| row.get::<_, i64>(0))
+            .map_err(sql_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(sql_err)?;
+        Ok(ids
+            .into_iter()
+            .filter_map(|id| get_conversation(conn, id).ok().flatten())
+            .collect())
+    })
+}
+
+#[tauri::command]
+pub fn llm_conversation_get(id: i64, limit: Option<usize>) -> Result<ConversationDetail, String> {
+    with_conn(|conn| {
+        let conversation = get_conversation(conn, id)?.ok_or_else(|| format!("Conversation {} not found", id))?;
+        Ok(ConversationDetail {
+            conversation,
+            messages: messages(conn, id, limit)?,
+        })
+    })
+}
+
+#[tauri::command]
+pub fn llm_conversation_delete(id: i64) -> Result<bool, String> {
+    backend_info(format!("Command llm_conversation_delete invoked (id={})", id));
+    with_conn(|conn| {
+        conn.execute("DELETE FROM llm_messages WHERE conversation_id = ?1", [id])
+            .map_err(sql_err)?;
+        let deleted = conn
+            .execute("DELETE FROM llm_conversations WHERE id = ?1", [id])
+            .map_err(sql_err)?;
+        Ok(deleted > 0)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(role: &str, chars: usize) -> ChatMessage {
+        ChatMessage { role: role.to_string(), content: "x".repeat(chars) }
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(&msg("user", 0)), 4);
+        assert_eq!(estimate_tokens(&msg("user", 1)), 5);
+        assert_eq!(estimate_tokens(&msg("user", 400)), 104);
+    }
+
+    #[test]
+    fn test_window_drops_oldest_and_keeps_system() {
+        // system 104, then four 104-token turns
+        let history = vec![
+            msg("system", 400),
+            msg("user", 400),
+            msg("assistant", 400),
+            msg("user", 400),
+            msg("assistant", 400),
+        ];
+        // 104 (system) + 2 × 104 fit into 320, the third turn would not
+        let windowed = window_messages(&history, 320);
+        assert_eq!(windowed, vec![history[0].clone(), history[3].clone(), history[4].clone()]);
+
+        // Everything fits
+        assert_eq!(window_messages(&history, 520).len(), 5);
+
+        // Budget below the system prompt: system + newest message only
+        let windowed = window_messages(&history, 50);
+        assert_eq!(windowed, vec![history[0].clone(), history[4].clone()]);
+    }
+
+    #[test]
+    fn test_window_keeps_history_contiguous() {
+        let history = vec![msg("user", 40), msg("assistant", 4000), msg("user", 40)];
+        // The huge middle message doesn't fit; the small first one must not
+        // come back without it
+        let windowed = window_messages(&history, 100);
+        assert_eq!(windowed, vec![history[2].clone()]);
+    }
+
+    #[test]
+    fn test_store_roundtrip() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let conv = create(&conn, "Kamery", Some("Jesteś asystentem.")).unwrap();
+        assert_eq!(conv.message_count, 1);
+        append(&conn, conv.id, "user", "Ile kamer?").unwrap();
+        append(&conn, conv.id, "assistant", "Trzy.").unwrap();
+        assert!(append(&conn, conv.id, "tool", "x").is_err());
+        assert!(append(&conn, 999, "user", "x").is_err());
+
+        let last_two = messages(&conn, conv.id, Some(2)).unwrap();
+        assert_eq!(last_two.iter().map(|m| m.content.as_str()).collect::<Vec<_>>(), vec!["Ile kamer?", "Trzy."]);
+        assert_eq!(messages(&conn, conv.id, None).unwrap().len(), 3);
+    }
+}
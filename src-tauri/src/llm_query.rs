@@ -7,6 +7,7 @@
 //! Replaces hardcoded keyword matching (nl_to_sql, extract_date_filter).
 
 use std::env;
+use crate::local_time::LocalClock;
 use crate::query_schema::{self, DataSource};
 
 const OPENROUTER_URL: &str = "https://openrouter.ai/api/v1/chat/completions";
@@ -30,6 +31,16 @@ pub async fn text_to_sql(question: &str, data_source: DataSource) -> Result<Stri
     text_to_sql_remote(question, data_source).await
 }
 
+/// Clock for the prompt's "today" / "yesterday": the zone recorded in the
+/// data source's DB (`db_meta`), else the system zone.
+pub(crate) fn query_clock(data_source: DataSource) -> LocalClock {
+    let path = crate::motion_detection::resolve_db_path(data_source.db_filename());
+    let tz = rusqlite::Connection::open_with_flags(&path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map(|conn| crate::local_time::of_db(&conn))
+        .unwrap_or_else(|_| crate::local_time::system());
+    LocalClock::now(tz)
+}
+
 /// Call remote OpenRouter API to generate SQL
 pub async fn text_to_sql_remote(question: &str, data_source: DataSource) -> Result<String, String> {
     let api_key = env::var("OPENROUTER_API_KEY").unwrap_or_default();
@@ -40,7 +51,7 @@ pub async fn text_to_sql_remote(question: &str, data_source: DataSource) -> Resu
     let model = env::var("LLM_MODEL")
         .unwrap_or_else(|_| env::var("VITE_LLM_MODEL").unwrap_or_else(|_| "google/gemini-2.0-flash-exp:free".into()));
 
-    let system_prompt = query_schema::build_text_to_sql_prompt(data_source.schema());
+    let system_prompt = crate::query_nl::text_to_sql_prompt(data_source.schema(), &query_clock(data_source));
 
     let payload = serde_json::json!({
         "model": model,
@@ -183,7 +194,7 @@ pub async fn execute_sql_with_query(
     }
 
     // Execute against SQLite
-    let conn = rusqlite::Connection::open(&resolved).map_err(|e| {
+    let conn = crate::motion_detection::open_monitoring_db(&resolved).map_err(|e| {
         format!("Cannot open DB {}: {}", resolved, e)
     })?;
 
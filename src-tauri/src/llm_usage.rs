@@ -0,0 +1,291 @@
+//! llm_usage.rs — Token usage and cost tracking for OpenRouter calls.
+//!
+//! Every completion that goes through OpenRouter (`llm_chat`, STT, the browse
+//! vision fallback, the vision pipeline worker) hands the response's `usage`
+//! object to `record`. Rows land in `broxeen_llm_usage.db`; `llm_usage_stats`
+//! sums them per feature, model or day. Costs come from OpenRouter's own
+//! `usage.cost` when present, otherwise from the static `PRICES` table.
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::logging::{backend_info, backend_warn};
+
+const USAGE_DB: &str = "broxeen_llm_usage.db";
+
+/// USD per million (prompt, completion) tokens, matched by model id prefix.
+/// Approximate list prices — good enough for a daily budget overview.
+const PRICES: &[(&str, f64, f64)] = &[
+    ("google/gemini-3-flash", 0.50, 3.00),
+    ("google/gemini-2.5-flash-lite", 0.10, 0.40),
+    ("google/gemini-2.5-flash", 0.30, 2.50),
+    ("google/gemini-2.5-pro", 1.25, 10.00),
+    ("google/gemini-2.0-flash", 0.10, 0.40),
+    ("openai/gpt-4o-mini", 0.15, 0.60),
+    ("openai/gpt-4o", 2.50, 10.00),
+    ("anthropic/claude-3.5-haiku", 0.80, 4.00),
+    ("anthropic/claude-3.5-sonnet", 3.00, 15.00),
+    ("anthropic/claude-sonnet-4", 3.00, 15.00),
+    ("meta-llama/llama-3.1-8b-instruct", 0.02, 0.05),
+];
+/// Rate for models missing from `PRICES`
+const UNKNOWN_PRICE: (f64, f64) = (1.00, 3.00);
+
+lazy_static::lazy_static! {
+    static ref CONN: Mutex<Option<Connection>> = Mutex::new(None);
+}
+
+#[derive(Debug, Clone, Serialize, Default, PartialEq)]
+pub struct UsageGroup {
+    /// Feature, model or `YYYY-MM-DD`, depending on `group_by`
+    pub key: String,
+    pub calls: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub estimated_cost_usd: f64,
+    /// Calls priced with the "unknown" fallback rate
+    pub unknown_price_calls: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LlmUsageStats {
+    pub group_by: String,
+    pub since: Option<i64>,
+    pub groups: Vec<UsageGroup>,
+    pub total: UsageGroup,
+}
+
+// ── Pricing ──────────────────────────────────────────
+
+/// Returns (cost in USD, price known).
+pub fn estimate_cost(model: &str, prompt_tokens: i64, completion_tokens: i64) -> (f64, bool) {
+    let model = model.to_ascii_lowercase();
+    let (rate, known) = PRICES
+        .iter()
+        .find(|(prefix, _, _)| model.starts_with(prefix))
+        .map(|(_, p, c)| ((*p, *c), true))
+        .unwrap_or((UNKNOWN_PRICE, false));
+    let cost = (prompt_tokens as f64 * rate.0 + completion_tokens as f64 * rate.1) / 1_000_000.0;
+    (cost, known)
+}
+
+// ── Storage ──────────────────────────────────────────
+
+fn db_path() -> PathBuf {
+    let base = dirs::data_local_dir()
+        .or_else(dirs::data_dir)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("broxeen");
+    let _ = std::fs::create_dir_all(&base);
+    base.join(USAGE_DB)
+}
+
+fn init_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS llm_usage (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            ts INTEGER NOT NULL,
+            day TEXT NOT NULL,
+            feature TEXT NOT NULL,
+            model TEXT NOT NULL,
+            prompt_tokens INTEGER NOT NULL,
+            completion_tokens INTEGER NOT NULL,
+            estimated_cost REAL NOT NULL,
+            price_known INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_llm_usage_ts ON llm_usage(ts);",
+    )
+}
+
+fn with_conn<T>(f: impl FnOnce(&Connection) -> Result<T, String>) -> Result<T, String> {
+    let mut guard = CONN.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.is_none() {
+        let path = db_path();
+        let conn = Connection::open(&path).map_err(|e| format!("Usage DB open failed: {}", e))?;
+        init_schema(&conn).map_err(|e| format!("Usage DB schema failed: {}", e))?;
+        backend_info(format!("LLM usage store opened at {}", path.display()));
+        *guard = Some(conn);
+    }
+    f(guard.as_ref().expect("usage connection initialised"))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn insert(
+    conn: &Connection,
+    ts: i64,
+    day: &str,
+    feature: &str,
+    model: &str,
+    prompt_tokens: i64,
+    completion_tokens: i64,
+    reported_cost: Option<f64>,
+) -> rusqlite::Result<()> {
+    let (estimated, known) = estimate_cost(model, prompt_tokens, completion_tokens);
+    let (cost, known) = match reported_cost {
+        Some(cost) => (cost, true),
+        None => (estimated, known),
+    };
+    conn.execute(
+        "INSERT INTO llm_usage (ts, day, feature, model, prompt_tokens, completion_tokens, estimated_cost, price_known)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![ts, day, feature, model, prompt_tokens, completion_tokens, cost, known],
+    )?;
+    Ok(())
+}
+
+/// Record the `usage` object of an OpenRouter chat completion response.
+/// Never fails the caller — a broken usage store only logs a warning.
+pub fn record(feature: &str, model: &str, response: &Value) {
+    let usage = &response["usage"];
+    if !usage.is_object() {
+        return;
+    }
+    let prompt_tokens = usage["prompt_tokens"].as_i64().unwrap_or(0);
+    let completion_tokens = usage["completion_tokens"].as_i64().unwrap_or(0);
+    let reported_cost = usage["cost"].as_f64();
+    let model = response["model"].as_str().unwrap_or(model);
+    let now = chrono::Local::now();
+    let day = now.format("%Y-%m-%d").to_string();
+
+    let result = with_conn(|conn| {
+        insert(
+            conn,
+            now.timestamp_millis(),
+            &day,
+            feature,
+            model,
+            prompt_tokens,
+            completion_tokens,
+            reported_cost,
+        )
+        .map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        backend_warn(format!("LLM usage not recorded ({} / {}): {}", feature, model, e));
+    }
+}
+
+fn stats(conn: &Connection, since: Option<i64>, group_by: &str) -> Result<LlmUsageStats, String> {
+    let column = match group_by {
+        "feature" => "feature",
+        "model" => "model",
+        "day" => "day",
+        other => return Err(format!("Nieznane grupowanie '{}' (feature, model, day)", other)),
+    };
+    let sql = format!(
+        "SELECT {col}, COUNT(*), SUM(prompt_tokens), SUM(completion_tokens), SUM(estimated_cost),
+                SUM(CASE WHEN price_known = 0 THEN 1 ELSE 0 END)
+         FROM llm_usage WHERE ts >= ?1 GROUP BY {col} ORDER BY {col}",
+        col = column
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let groups = stmt
+        .query_map([since.unwrap_or(i64::MIN)], |row| {
+            Ok(UsageGroup {
+                key: row.get(0)?,
+                calls: row.get(1)?,
+                prompt_tokens: row.get(2)?,
+                completion_tokens: row.get(3)?,
+                estimated_cost_usd: row.get(4)?,
+                unknown_price_calls: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let total = groups.iter().fold(
+        UsageGroup { key: "total".to_string(), ..Default::default() },
+        |mut acc, g| {
+            acc.calls += g.calls;
+            acc.prompt_tokens += g.prompt_tokens;
+            acc.completion_tokens += g.completion_tokens;
+            acc.estimated_cost_usd += g.estimated_cost_usd;
+            acc.unknown_price_calls += g.unknown_price_calls;
+            acc
+        },
+    );
+    Ok(LlmUsageStats { group_by: column.to_string(), since, groups, total })
+}
+
+/// Log today's totals (called once at launch).
+pub fn log_daily_total() {
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let result = with_conn(|conn| stats(conn, None, "day"));
+    match result {
+        Ok(s) => {
+            let t = s.groups.into_iter().find(|g| g.key == today).unwrap_or_default();
+            backend_info(format!(
+                "LLM usage today: {} calls, {} prompt + {} completion tokens, ~${:.4}",
+                t.calls, t.prompt_tokens, t.completion_tokens, t.estimated_cost_usd
+            ));
+        }
+        Err(e) => backend_warn(format!("LLM usage summary unavailable: {}", e)),
+    }
+}
+
+// ── Tauri command ────────────────────────────────────
+
+/// Usage totals since `since` (RFC 3339 or Unix ms; default: all time),
+/// grouped by `feature` (default), `model` or `day`.
+#[tauri::command]
+pub fn llm_usage_stats(since: Option<String>, group_by: Option<String>) -> Result<LlmUsageStats, String> {
+    let since_ms = match since.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        None => None,
+        Some(s) => Some(match s.parse::<i64>() {
+            Ok(ms) => ms,
+            Err(_) => chrono::DateTime::parse_from_rfc3339(s)
+                .map_err(|_| format!("Nieprawidłowa data 'since': {}", s))?
+                .timestamp_millis(),
+        }),
+    };
+    let group_by = group_by.unwrap_or_else(|| "feature".to_string());
+    with_conn(|conn| stats(conn, since_ms, &group_by))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_cost() {
+        let (cost, known) = estimate_cost("openai/gpt-4o-mini", 1_000_000, 1_000_000);
+        assert!(known);
+        assert!((cost - 0.75).abs() < 1e-9);
+
+        // gpt-4o-mini must not fall into the gpt-4o rate
+        let (cost, _) = estimate_cost("openai/gpt-4o-2024-08-06", 1_000_000, 0);
+        assert!((cost - 2.5).abs() < 1e-9);
+
+        let (cost, known) = estimate_cost("some/new-model", 1_000_000, 1_000_000);
+        assert!(!known);
+        assert!((cost - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_stats_grouping() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        insert(&conn, 1_000, "2026-01-01", "llm_chat", "openai/gpt-4o-mini", 1000, 500, None).unwrap();
+        insert(&conn, 2_000, "2026-01-01", "stt", "some/new-model", 200, 10, None).unwrap();
+        insert(&conn, 3_000, "2026-01-02", "llm_chat", "openai/gpt-4o-mini", 100, 50, Some(0.5)).unwrap();
+
+        let by_feature = stats(&conn, None, "feature").unwrap();
+        assert_eq!(by_feature.groups.len(), 2);
+        assert_eq!(by_feature.groups[0].key, "llm_chat");
+        assert_eq!(by_feature.groups[0].calls, 2);
+        assert_eq!(by_feature.groups[0].prompt_tokens, 1100);
+        assert_eq!(by_feature.groups[1].unknown_price_calls, 1);
+        assert_eq!(by_feature.total.calls, 3);
+        assert_eq!(by_feature.total.completion_tokens, 560);
+
+        let by_day = stats(&conn, Some(2_500), "day").unwrap();
+        assert_eq!(by_day.groups.len(), 1);
+        assert!((by_day.total.estimated_cost_usd - 0.5).abs() < 1e-9);
+
+        assert!(stats(&conn, None, "camera").is_err());
+    }
+}
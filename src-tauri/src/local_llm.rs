@@ -12,7 +12,7 @@
 use std::env;
 use std::sync::OnceLock;
 
-use crate::query_schema::{self, DataSource};
+use crate::query_schema::DataSource;
 
 /// Default model for Bielik
 const DEFAULT_MODEL: &str = "bielik:1.5b";
@@ -94,7 +94,7 @@ impl LocalLlm {
     }
 
     async fn generate_sql_local(&self, question: &str, data_source: DataSource) -> Result<String, String> {
-        let schema_prompt = query_schema::build_text_to_sql_prompt(data_source.schema());
+        let schema_prompt = crate::query_nl::text_to_sql_prompt(data_source.schema(), &crate::llm_query::query_clock(data_source));
         let full_prompt = format!("{}\n\n{}", SQL_SYSTEM_PROMPT, schema_prompt);
         
         // Build completion prompt
@@ -0,0 +1,201 @@
+//! local_time.rs — Explicit time zone for local dates in the monitoring DB.
+//!
+//! Detections are stored with a UTC `timestamp`; `local_date` / `local_hour`
+//! and "today" / "yesterday" filters use one IANA zone (`database.timezone`,
+//! default: the system zone) instead of SQLite's `localtime`, so a DST switch
+//! or a changed system zone does not shift the buckets. The zone used for
+//! inserts is recorded in the DB's `db_meta` table.
+//!
+//! [`LocalClock::parse_instant`] reads the time bounds users type into
+//! filters ("2h", "yesterday", "2024-06-12", RFC 3339), so every command
+//! accepting `since` / `until` understands the same forms.
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// `db_meta` key holding the zone of `local_date` / `local_hour`
+pub const META_TIMEZONE: &str = "timezone";
+
+/// The system zone, UTC when it cannot be determined.
+pub fn system() -> Tz {
+    iana_time_zone::get_timezone()
+        .ok()
+        .and_then(|name| name.parse().ok())
+        .unwrap_or(Tz::UTC)
+}
+
+/// An IANA zone name; empty or `"system"` means the system zone.
+#[cfg_attr(not(feature = "vision"), allow(dead_code))]
+pub fn parse(name: &str) -> Result<Tz, String> {
+    match name.trim() {
+        "" | "system" => Ok(system()),
+        name => name
+            .parse()
+            .map_err(|_| format!("Nieznana strefa czasowa '{}' (oczekiwano np. Europe/Warsaw)", name)),
+    }
+}
+
+/// Zone recorded in the DB's `db_meta`, else the system zone.
+pub fn of_db(conn: &rusqlite::Connection) -> Tz {
+    conn.query_row("SELECT value FROM db_meta WHERE key = ?1", [META_TIMEZONE], |r| r.get::<_, String>(0))
+        .ok()
+        .and_then(|name| name.parse().ok())
+        .unwrap_or_else(system)
+}
+
+/// `YYYY-MM-DD HH:MM:SS` in UTC, as SQLite's `datetime()` returns it.
+pub fn sqlite_utc(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// A zone plus "now", turning local days into UTC ranges.
+#[derive(Debug, Clone, Copy)]
+pub struct LocalClock {
+    pub tz: Tz,
+    pub now: DateTime<Utc>,
+}
+
+impl LocalClock {
+    pub fn now(tz: Tz) -> Self {
+        Self { tz, now: Utc::now() }
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.now.with_timezone(&self.tz).date_naive()
+    }
+
+    /// First instant of `date` in the zone (the first valid time when
+    /// midnight falls into a DST gap).
+    fn start_of(&self, date: NaiveDate) -> DateTime<Utc> {
+        let midnight = date.and_hms_opt(0, 0, 0).expect("valid midnight");
+        (0..=3)
+            .find_map(|h| self.tz.from_local_datetime(&(midnight + Duration::hours(h))).earliest())
+            .map(|t| t.with_timezone(&Utc))
+            .unwrap_or_else(|| Utc.from_utc_datetime(&midnight))
+    }
+
+    /// UTC `[start, end)` of the local day `days_ago` days before today
+    /// (23 or 25 hours long on DST switch days).
+    pub fn day_range(&self, days_ago: u32) -> (DateTime<Utc>, DateTime<Utc>) {
+        let day = self.today() - Duration::days(days_ago as i64);
+        (self.start_of(day), self.start_of(day + Duration::days(1)))
+    }
+
+    /// A point in time from a filter bound:
+    /// - `now`, `today` / `yesterday` (start of the local day)
+    /// - `<n><unit>` before now, unit `s`, `m`/`min`, `h`, `d`, `w`
+    ///   (`"2h"`, `"30 min"`, `"7d ago"`)
+    /// - `YYYY-MM-DD` (local midnight), `YYYY-MM-DD HH:MM[:SS]` (local)
+    /// - RFC 3339 (`2024-06-12T22:00:00Z`)
+    pub fn parse_instant(&self, raw: &str) -> Result<DateTime<Utc>, String> {
+        let text = raw.trim().to_lowercase();
+        match text.as_str() {
+            "now" => return Ok(self.now),
+            "today" => return Ok(self.day_range(0).0),
+            "yesterday" => return Ok(self.day_range(1).0),
+            _ => {}
+        }
+        if let Some(ago) = parse_relative(&text) {
+            return Ok(self.now - ago);
+        }
+        if let Ok(at) = DateTime::parse_from_rfc3339(raw.trim()) {
+            return Ok(at.with_timezone(&Utc));
+        }
+        if let Ok(date) = NaiveDate::parse_from_str(&text, "%Y-%m-%d") {
+            return Ok(self.start_of(date));
+        }
+        ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dt%H:%M:%S", "%Y-%m-%dt%H:%M"]
+            .iter()
+            .find_map(|f| NaiveDateTime::parse_from_str(&text, f).ok())
+            .and_then(|local| self.tz.from_local_datetime(&local).earliest())
+            .map(|at| at.with_timezone(&Utc))
+            .ok_or_else(|| {
+                format!(
+                    "Nieprawidłowy czas '{}' (oczekiwano np. 2h, 30m, 7d, today, yesterday, 2024-06-12 lub RFC 3339)",
+                    raw.trim()
+                )
+            })
+    }
+
+    /// SQL condition (with leading ` AND`) for rows of that local day.
+    pub fn day_filter(&self, days_ago: u32) -> String {
+        let (start, end) = self.day_range(days_ago);
+        format!(
+            " AND datetime(timestamp) >= '{}' AND datetime(timestamp) < '{}'",
+            sqlite_utc(start),
+            sqlite_utc(end)
+        )
+    }
+}
+
+/// `"2h"`, `"30 min"`, `"7d ago"` → the duration.
+fn parse_relative(text: &str) -> Option<Duration> {
+    let text = text.strip_suffix("ago").unwrap_or(text).trim();
+    let split = text.find(|c: char| !c.is_ascii_digit())?;
+    let amount: i64 = text[..split].parse().ok()?;
+    match text[split..].trim() {
+        "s" | "sec" => Some(Duration::seconds(amount)),
+        "m" | "min" => Some(Duration::minutes(amount)),
+        "h" => Some(Duration::hours(amount)),
+        "d" => Some(Duration::days(amount)),
+        "w" => Some(Duration::weeks(amount)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_day_ranges_around_dst() {
+        let warsaw: Tz = parse("Europe/Warsaw").unwrap();
+
+        // Spring forward: 23-hour day
+        let clock = LocalClock { tz: warsaw, now: at("2024-03-31T12:00:00Z") };
+        assert_eq!(clock.day_range(0), (at("2024-03-30T23:00:00Z"), at("2024-03-31T22:00:00Z")));
+        assert_eq!(clock.day_range(1), (at("2024-03-29T23:00:00Z"), at("2024-03-30T23:00:00Z")));
+
+        // Fall back: 25-hour day
+        let clock = LocalClock { tz: warsaw, now: at("2024-10-27T23:30:00Z") };
+        assert_eq!(clock.today(), NaiveDate::from_ymd_opt(2024, 10, 28).unwrap());
+        let clock = LocalClock { tz: warsaw, now: at("2024-10-27T12:00:00Z") };
+        assert_eq!(clock.day_range(0), (at("2024-10-26T22:00:00Z"), at("2024-10-27T23:00:00Z")));
+        assert_eq!(
+            clock.day_filter(0),
+            " AND datetime(timestamp) >= '2024-10-26 22:00:00' AND datetime(timestamp) < '2024-10-27 23:00:00'"
+        );
+
+        // Midnight skipped by DST (Santiago, 2024-09-08 00:00 → 01:00)
+        let clock = LocalClock { tz: parse("America/Santiago").unwrap(), now: at("2024-09-08T12:00:00Z") };
+        assert_eq!(clock.day_range(0).0, at("2024-09-08T04:00:00Z"));
+
+        assert!(parse("Mars/Olympus").is_err());
+        assert_eq!(parse("").unwrap(), system());
+    }
+
+    #[test]
+    fn test_parse_instant_relative_and_absolute() {
+        let clock = LocalClock { tz: parse("Europe/Warsaw").unwrap(), now: at("2024-06-12T10:30:00Z") };
+        assert_eq!(clock.parse_instant("now").unwrap(), clock.now);
+        assert_eq!(clock.parse_instant("2h").unwrap(), at("2024-06-12T08:30:00Z"));
+        assert_eq!(clock.parse_instant("30 min").unwrap(), at("2024-06-12T10:00:00Z"));
+        assert_eq!(clock.parse_instant("7d ago").unwrap(), at("2024-06-05T10:30:00Z"));
+        assert_eq!(clock.parse_instant("1w").unwrap(), at("2024-06-05T10:30:00Z"));
+        assert_eq!(clock.parse_instant("45s").unwrap(), at("2024-06-12T10:29:15Z"));
+        // Local days: Warsaw is UTC+2 in June
+        assert_eq!(clock.parse_instant("Today").unwrap(), at("2024-06-11T22:00:00Z"));
+        assert_eq!(clock.parse_instant("yesterday").unwrap(), at("2024-06-10T22:00:00Z"));
+        assert_eq!(clock.parse_instant("2024-06-01").unwrap(), at("2024-05-31T22:00:00Z"));
+        assert_eq!(clock.parse_instant("2024-06-01 08:15").unwrap(), at("2024-06-01T06:15:00Z"));
+        assert_eq!(clock.parse_instant("2024-06-01T08:15:00+00:00").unwrap(), at("2024-06-01T08:15:00Z"));
+
+        for bad in ["", "2", "h", "2 fortnights", "-3h", "2024-13-01", "last tuesday"] {
+            assert!(clock.parse_instant(bad).is_err(), "{bad}");
+        }
+    }
+}
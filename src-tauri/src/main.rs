@@ -1,41 +1,79 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod audio_capture;
+mod audio_level;
+mod audit;
+mod arp_raw;
 mod autostart;
 mod audio_commands;
+mod bandwidth;
+mod browse_cache;
 mod browse_rendered;
+mod command_governor;
 mod motion_detection;
 mod content_cleaning;
+mod credentials;
 mod content_extraction;
+mod content_language;
+mod device_catalog;
 mod disk_info;
+mod disk_watch;
 mod docker;
 mod email;
+mod error;
 mod frigate_mqtt;
+mod geocoding;
+mod http_client;
+mod http_policy;
+mod idempotency;
 mod file_search;
+mod image_meta;
 mod llm;
+mod llm_conversations;
+mod llm_usage;
 mod llm_query;
+mod local_time;
 #[cfg(feature = "local-llm")]
 mod local_llm;
 mod logging;
+mod mqtt;
+mod net_watch;
 mod network;
 mod network_info;
 mod network_scan;
+mod onvif_ptz;
+mod query_nl;
 mod query_schema;
 mod remote_machine;
+mod remote_metrics;
 mod rss_parser;
+mod rss_watch;
+mod scan_history;
 mod settings;
+mod settings_migrations;
+mod settings_profile;
+mod shutdown;
+mod site_crawl;
+mod sounds;
 mod ssh;
+mod startup;
 mod stt;
+mod stt_whisper;
 mod toonic_sidecar;
 mod tts;
 mod tts_backend;
+mod tts_text;
 mod wake_word;
 
 #[cfg(feature = "vision")]
 mod vision_capture;
 #[cfg(feature = "vision")]
+mod vision_clips;
+#[cfg(feature = "vision")]
 mod vision_config;
 #[cfg(feature = "vision")]
+mod vision_contact_sheet;
+#[cfg(feature = "vision")]
 mod vision_db;
 #[cfg(feature = "vision")]
 mod vision_detector;
@@ -50,9 +88,18 @@ mod vision_pipeline;
 #[cfg(feature = "vision")]
 mod vision_query_engine;
 #[cfg(feature = "vision")]
+mod vision_reload;
+#[cfg(feature = "vision")]
 mod vision_scene_buffer;
 #[cfg(feature = "vision")]
 mod vision_tracker;
+#[cfg(feature = "vision")]
+mod vision_webhook;
+#[cfg(feature = "vision")]
+mod vision_ws;
+mod vision_export;
+mod vision_visits;
+mod vision_zones;
 
 use audio_capture::SharedRecordingState;
 use wake_word::SharedWakeWordState;
@@ -61,17 +108,17 @@ use std::sync::{Arc, Mutex};
 
 use crate::logging::{backend_info, backend_warn, backend_error, init_logging};
 use crate::content_cleaning::{
-    strip_cookie_banner_text, truncate_to_chars, normalize_whitespace,
-    MIN_READABLE_CONTENT_LENGTH, MAX_BACKEND_CONTENT_CHARS,
+    boilerplate_patterns, clean_content, content_chunk, content_max_chars,
+    MIN_READABLE_CONTENT_LENGTH,
 };
 use crate::content_extraction::{
-    extract_search_results, extract_with_scraper,
+    extract_search_results, extract_tier1, SearchResultItem,
 };
 
 // AudioSettings is defined in settings.rs — re-export for crate-wide access
 pub use settings::AudioSettings;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BrowseResult {
     pub url: String,
     pub title: String,
@@ -90,6 +137,28 @@ pub struct BrowseResult {
     pub github_url: Option<String>,
     pub youtube_url: Option<String>,
     pub instagram_url: Option<String>,
+    /// Characters removed by boilerplate / link-list cleaning
+    #[serde(default)]
+    pub removed_chars: usize,
+    /// Structured results when the page is a search engine results page
+    #[serde(default)]
+    pub search_results: Vec<SearchResultItem>,
+    /// ISO 639-1 code of the extracted content (before any translation)
+    #[serde(default)]
+    pub detected_language: Option<String>,
+    /// Characters of the extracted content before any translation
+    #[serde(default)]
+    pub original_content_length: usize,
+    /// Characters of `content` before any translation; the next chunk
+    /// starts at `content_offset` + this
+    #[serde(default)]
+    pub content_chunk_chars: usize,
+    /// Characters of the whole extracted content; `content` is the chunk of
+    /// it starting at `content_offset` (more via `browse_content_page`)
+    #[serde(default)]
+    pub content_total_chars: usize,
+    #[serde(default)]
+    pub content_offset: usize,
 }
 
 
@@ -99,22 +168,135 @@ async fn get_app_version() -> Result<String, String> {
     Ok(env!("CARGO_PKG_VERSION").to_string())
 }
 
+/// Fetch and extract a page. Results are cached on disk (see browse_cache.rs);
+/// `fresh: true` bypasses the cache. The content's language is detected;
+/// with `translate_to` (e.g. "pl") content in another language is translated
+/// by the LLM and `resolve_type` gets a "+translated" suffix. `content` is the
+/// first `browse_content_max_chars` of the page, cut at a sentence end.
 #[tauri::command]
-async fn browse(url: String) -> Result<BrowseResult, String> {
-    backend_info(format!("Command browse invoked for URL: {}", url));
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(15))
-        .user_agent("Broxeen/1.0")
-        .build()
-        .map_err(|e| {
-            backend_error(format!("Failed to build HTTP client for {}: {}", url, e));
-            e.to_string()
-        })?;
+async fn browse(
+    governor: tauri::State<'_, command_governor::CommandGovernor>,
+    url: String,
+    fresh: Option<bool>,
+    translate_to: Option<String>,
+) -> Result<BrowseResult, error::BroxeenError> {
+    backend_info(format!(
+        "Command browse invoked for URL: {} (fresh={:?}, translate_to={:?})",
+        url, fresh, translate_to
+    ));
+    governor
+        .run(command_governor::CommandClass::Browse, "browse", |_| async {
+            let mut result = fetch_and_extract(url, fresh).await?;
+            page_content(&mut result, 0, content_max_chars());
+            content_language::apply(&mut result, translate_to.as_deref()).await;
+            Ok(result)
+        })
+        .await
+}
+
+/// Next chunks of a page `browse` returned in part (`content_total_chars`
+/// above what it sent): `length` chars (default `browse_content_max_chars`)
+/// from char `offset`, served from the browse cache of the same `url`.
+#[tauri::command]
+async fn browse_content_page(
+    governor: tauri::State<'_, command_governor::CommandGovernor>,
+    url: String,
+    offset: usize,
+    length: Option<usize>,
+    translate_to: Option<String>,
+) -> Result<BrowseResult, error::BroxeenError> {
+    backend_info(format!(
+        "Command browse_content_page invoked for URL: {} (offset={}, length={:?})",
+        url, offset, length
+    ));
+    governor
+        .run(command_governor::CommandClass::Browse, "browse_content_page", |_| async {
+            http_policy::check_robots(&url).await.map_err(|e| {
+                backend_warn(format!("browse_content_page refused for {}: {}", url, e));
+                e
+            })?;
+            let Some(mut result) = browse_cache::cached_result(&url) else {
+                return Err(error::BroxeenError::not_found(format!(
+                    "No cached content for {} — browse the page first",
+                    url
+                )));
+            };
+            let total = result.content.chars().count();
+            if offset > total {
+                return Err(error::BroxeenError::invalid_input(format!(
+                    "Offset {} is past the end of {} ({} chars)",
+                    offset, url, total
+                )));
+            }
+            let length = match length {
+                Some(0) => return Err(error::BroxeenError::invalid_input("Chunk length must be at least 1")),
+                Some(n) => n,
+                None => content_max_chars(),
+            };
+            page_content(&mut result, offset, length);
+            content_language::apply(&mut result, translate_to.as_deref()).await;
+            Ok(result)
+        })
+        .await
+}
 
-    let response = client.get(&url).send().await.map_err(|e| {
-        backend_error(format!("HTTP request failed for {}: {}", url, e));
-        e.to_string()
+/// Replace the full extracted content with its chunk at `offset`.
+fn page_content(result: &mut BrowseResult, offset: usize, max_chars: usize) {
+    let full = std::mem::take(&mut result.content);
+    result.content_total_chars = full.chars().count();
+    result.original_content_length = result.content_total_chars;
+    result.content_offset = offset.min(result.content_total_chars);
+    result.content = content_chunk(&full, result.content_offset, max_chars);
+    result.content_chunk_chars = result.content.chars().count();
+}
+
+/// The whole extracted content is kept (and cached); `browse` pages it.
+async fn fetch_and_extract(url: String, fresh: Option<bool>) -> Result<BrowseResult, error::BroxeenError> {
+    http_policy::check_robots(&url).await.map_err(|e| {
+        backend_warn(format!("browse refused for {}: {}", url, e));
+        e
+    })?;
+    let stale = if fresh.unwrap_or(false) {
+        None
+    } else {
+        match browse_cache::lookup(&url) {
+            browse_cache::Lookup::Fresh(result) => {
+                backend_info(format!("browse cache hit for {}", url));
+                return Ok(result);
+            }
+            browse_cache::Lookup::Stale(entry) => Some(entry),
+            browse_cache::Lookup::Miss => None,
+        }
+    };
+
+    let client = http_client::client(std::time::Duration::from_secs(15)).map_err(|e| {
+        backend_error(format!("Failed to build HTTP client for {}: {}", url, e));
+        e
+    })?;
+
+    // Interactive: don't keep the user waiting on long Retry-After values
+    let policy = http_client::RetryPolicy::default()
+        .max_retries(2)
+        .max_retry_after(std::time::Duration::from_secs(5));
+    http_policy::before_fetch(&url).await.map_err(|e| {
+        backend_warn(format!("browse refused for {}: {}", url, e));
+        e
     })?;
+    let mut request = client.get(&url);
+    if let Some(entry) = &stale {
+        if let Some(etag) = &entry.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(modified) = &entry.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, modified);
+        }
+    }
+    let (response, retry_meta) = http_client::send_with_retry(request, &policy)
+        .await
+        .map_err(|e| {
+            backend_error(format!("HTTP request failed for {}: {}", url, e));
+            e
+        })?;
     let status = response.status();
     let final_url = response.url().to_string();
     let content_type = response
@@ -123,38 +305,52 @@ async fn browse(url: String) -> Result<BrowseResult, String> {
         .and_then(|v| v.to_str().ok())
         .unwrap_or("unknown")
         .to_string();
+    let header = |name: reqwest::header::HeaderName| {
+        response.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string)
+    };
+    let etag = header(reqwest::header::ETAG);
+    let last_modified = header(reqwest::header::LAST_MODIFIED);
 
     backend_info(format!(
         "HTTP response received for {}: {} (final_url={}, content_type={})",
         url, status, final_url, content_type
     ));
 
+    if status == reqwest::StatusCode::NOT_MODIFIED {
+        if let Some(entry) = stale {
+            backend_info(format!("browse cache revalidated for {} (304)", url));
+            return Ok(browse_cache::revalidated(entry));
+        }
+    }
+
     if !status.is_success() {
         let message = format!(
-            "HTTP {} while fetching {} (requested: {})",
-            status, final_url, url
+            "HTTP {} while fetching {} (requested: {}){}",
+            status, final_url, url, retry_meta.describe()
         );
         backend_warn(message.as_str());
-        return Err(message);
+        let code = error::ErrorCode::from_http_status(status.as_u16());
+        return Err(error::BroxeenError::new(code, message)
+            .with_details(serde_json::json!({ "status": status.as_u16(), "url": final_url })));
     }
 
     let html = response.text().await.map_err(|e| {
         backend_error(format!("Failed to decode response body for {}: {}", url, e));
-        e.to_string()
+        error::BroxeenError::from(e)
     })?;
     backend_info(format!("Fetched {} bytes for {}", html.len(), url));
 
     // ── Search results detection ─────────────────────
-    if let Some(search_content) = extract_search_results(&html, &url) {
-        backend_info("Detected DuckDuckGo search results page, extracting results directly");
+    if let Some(search) = extract_search_results(&html, &url) {
+        backend_info(format!("Detected {} search results page, extracting results directly", search.engine));
         let search_title = format!("Wyniki wyszukiwania: {}", 
             url::Url::parse(&url)
                 .ok()
                 .and_then(|u| u.query_pairs().find(|(k, _)| k == "q").map(|(_, v)| v.to_string()))
                 .unwrap_or_else(|| url.clone())
         );
-        let final_content = truncate_to_chars(&search_content, MAX_BACKEND_CONTENT_CHARS);
-        return Ok(BrowseResult {
+        let final_content = search.text;
+        let result = BrowseResult {
             url: final_url,
             title: search_title,
             content: final_content,
@@ -172,30 +368,36 @@ async fn browse(url: String) -> Result<BrowseResult, String> {
             github_url: None,
             youtube_url: None,
             instagram_url: None,
-        });
+            removed_chars: 0,
+            search_results: search.items,
+            detected_language: None,
+            original_content_length: 0,
+            content_chunk_chars: 0,
+            content_total_chars: 0,
+            content_offset: 0,
+        };
+        browse_cache::store(&url, &html, etag, last_modified, &result);
+        return Ok(result);
     }
 
-    let parsed_url = match url::Url::parse(&url) {
-        Ok(parsed) => parsed,
-        Err(err) => {
-            backend_warn(format!(
-                "Failed to parse requested URL {} for readability context: {}. Using final URL {}.",
-                url, err, final_url
-            ));
-            url::Url::parse(&final_url)
-                .unwrap_or_else(|_| url::Url::parse("https://example.com").unwrap())
-        }
-    };
-    let mut cursor = std::io::Cursor::new(html.clone());
 
-    // Extract action links (RSS, Contact, Phone) from the raw HTML
-    let action_links = {
+    // Extract action links (RSS, Contact, Phone) and text blocks for
+    // link-density cleaning from the raw HTML
+    let (action_links, text_blocks) = {
         let document = scraper::Html::parse_document(&html);
-        crate::content_extraction::extract_action_links(&document)
+        (
+            crate::content_extraction::extract_action_links(&document, &final_url),
+            crate::content_extraction::extract_text_blocks(&document),
+        )
     };
+    let boilerplate_patterns = boilerplate_patterns();
 
-    // Try capturing a screenshot if available
-    let screenshot_base64 = if browse_rendered::is_available() {
+    // Try capturing a screenshot if available (never inlined in low-bandwidth mode)
+    let low_bandwidth = bandwidth::is_active();
+    if low_bandwidth && browse_rendered::is_available() {
+        bandwidth::record_screenshot_skipped();
+    }
+    let screenshot_base64 = if !low_bandwidth && browse_rendered::is_available() {
         match browse_rendered::capture_screenshot(&url, 10) {
             Ok(b64) => Some(b64),
             Err(e) => {
@@ -208,37 +410,7 @@ async fn browse(url: String) -> Result<BrowseResult, String> {
     };
 
     // ── Tier 1: reqwest + readability/scraper ─────────
-    let (title, content) = match readability::extractor::extract(&mut cursor, &parsed_url) {
-        Ok(product) => {
-            let readable_title = normalize_whitespace(&product.title);
-            let readable_content = normalize_whitespace(&product.text);
-
-            if readable_content.len() >= MIN_READABLE_CONTENT_LENGTH {
-                backend_info("Tier 1: Readability extraction successful");
-                (
-                    if readable_title.is_empty() {
-                        url.clone()
-                    } else {
-                        readable_title
-                    },
-                    readable_content,
-                )
-            } else {
-                backend_warn(format!(
-                    "Readability returned short content ({} chars). Falling back to scraper.",
-                    readable_content.len()
-                ));
-                extract_with_scraper(&html, &final_url)
-            }
-        }
-        Err(e) => {
-            backend_warn(format!(
-                "Readability extraction failed: {}. Falling back to scraper.",
-                e
-            ));
-            extract_with_scraper(&html, &final_url)
-        }
-    };
+    let (title, content) = extract_tier1(&html, &url, &final_url);
 
     let mut final_title = if title.trim().is_empty() {
         final_url.clone()
@@ -246,8 +418,9 @@ async fn browse(url: String) -> Result<BrowseResult, String> {
         title
     };
 
-    let cookie_stripped = strip_cookie_banner_text(&content);
-    let mut final_content = truncate_to_chars(&cookie_stripped, MAX_BACKEND_CONTENT_CHARS);
+    let cleaned = clean_content(&content, &text_blocks, &boilerplate_patterns);
+    let mut removed_chars = cleaned.removed_chars;
+    let mut final_content = cleaned.text;
     let mut resolve_type = "exact".to_string();
 
     // ── Tier 2: Chrome headless --dump-dom ────────────
@@ -268,7 +441,9 @@ async fn browse(url: String) -> Result<BrowseResult, String> {
                     if !rendered_title.is_empty() {
                         final_title = rendered_title;
                     }
-                    final_content = truncate_to_chars(&rendered_content, MAX_BACKEND_CONTENT_CHARS);
+                    let cleaned = clean_content(&rendered_content, &[], &boilerplate_patterns);
+                    removed_chars = cleaned.removed_chars;
+                    final_content = cleaned.text;
                     resolve_type = "rendered".to_string();
                 } else {
                     backend_warn("Tier 2: Chrome rendering didn't improve content");
@@ -303,7 +478,8 @@ async fn browse(url: String) -> Result<BrowseResult, String> {
                         if final_title == final_url {
                             final_title = vision_title;
                         }
-                        final_content = truncate_to_chars(&vision_content, MAX_BACKEND_CONTENT_CHARS);
+                        final_content = vision_content;
+                        removed_chars = 0;
                         resolve_type = "vision".to_string();
                     }
                 }
@@ -317,14 +493,15 @@ async fn browse(url: String) -> Result<BrowseResult, String> {
     }
 
     backend_info(format!(
-        "Content extracted for {} (title_len={}, content_len={}, method={})",
+        "Content extracted for {} (title_len={}, content_len={}, removed_chars={}, method={})",
         final_url,
         final_title.len(),
         final_content.len(),
+        removed_chars,
         resolve_type
     ));
 
-    Ok(BrowseResult {
+    let result = BrowseResult {
         url: final_url,
         title: final_title,
         content: final_content,
@@ -342,11 +519,233 @@ async fn browse(url: String) -> Result<BrowseResult, String> {
         github_url: action_links.github_url,
         youtube_url: action_links.youtube_url,
         instagram_url: action_links.instagram_url,
-    })
+        removed_chars,
+        search_results: Vec::new(),
+        detected_language: None,
+        original_content_length: 0,
+        content_chunk_chars: 0,
+        content_total_chars: 0,
+        content_offset: 0,
+    };
+    browse_cache::store(&url, &html, etag, last_modified, &result);
+    Ok(result)
 }
 
 
 
+/// `COMMAND_NAMES` and `invoke_handler` (`tauri::generate_handler!`) from
+/// one list, so the startup log cannot drift from what is registered.
+macro_rules! command_handlers {
+    ($($($segment:ident)::+),* $(,)?) => {
+        const COMMAND_NAMES: &[&str] = &[$(stringify!($($segment)::+)),*];
+
+        fn invoke_handler(invoke: tauri::ipc::Invoke) -> bool {
+            let handler: fn(tauri::ipc::Invoke) -> bool = tauri::generate_handler![$($($segment)::+),*];
+            handler(invoke)
+        }
+    };
+}
+
+command_handlers![
+    get_app_version,
+    settings::get_settings,
+    settings::save_settings,
+    settings::settings_pending_restarts,
+    settings_profile::settings_export,
+    settings_profile::settings_import,
+    browse,
+    browse_content_page,
+    browse_cache::browse_cache_clear,
+    site_crawl::browse_site,
+    site_crawl::browse_site_cancel,
+    llm::llm_chat,
+    llm::llm_chat_stream,
+    llm::llm_chat_cancel,
+    llm_conversations::llm_conversation_create,
+    llm_conversations::llm_conversation_append,
+    llm_conversations::llm_conversation_list,
+    llm_conversations::llm_conversation_get,
+    llm_conversations::llm_conversation_delete,
+    llm_usage::llm_usage_stats,
+    llm::llm_providers_status,
+    stt::stt_transcribe,
+    stt_whisper::whisper_status,
+    stt_whisper::whisper_install,
+    audio_commands::stt_start,
+    audio_commands::stt_stop,
+    audio_commands::stt_status,
+    audio_commands::backend_tts_speak,
+    audio_commands::backend_tts_stop,
+    audio_commands::backend_tts_pause,
+    audio_commands::backend_tts_resume,
+    audio_commands::backend_tts_speak_base64,
+    tts_text::tts_dictionary_set,
+    audio_commands::backend_tts_info,
+    audio_commands::backend_audio_devices,
+    audio_commands::piper_install,
+    audio_commands::piper_is_installed,
+    tts::tts_is_available,
+    tts::tts_speak,
+    tts::tts_stop,
+    network_scan::ping_host_simple,
+    network_scan::ping_host,
+    network_scan::scan_ports,
+    network_scan::scan_ports_cancel,
+    network_scan::arp_scan,
+    network_scan::discover_onvif_cameras,
+    onvif_ptz::onvif_ptz_move,
+    onvif_ptz::onvif_ptz_goto_preset,
+    onvif_ptz::onvif_ptz_list_presets,
+    network_scan::discover_mdns,
+    network_scan::scan_network,
+    network_scan::scan_network_cancel,
+    network_scan::rtsp_capture_frame,
+    credentials::camera_credentials_set,
+    credentials::camera_credentials_get,
+    credentials::camera_credentials_delete,
+    credentials::camera_credentials_list,
+    credentials::camera_credentials_unlock,
+    network_scan::rtsp_worker_stats,
+    network_scan::rtsp_stop_worker,
+    network_scan::rtsp_stop_all_workers,
+    network_scan::rtsp_record_clip,
+    network_scan::http_fetch_base64,
+    network_scan::camera_health_check,
+    scan_history::scan_network_diff,
+    scan_history::device_set_alias,
+    scan_history::device_get_metadata,
+    scan_history::device_metadata_export,
+    scan_history::device_metadata_import,
+    network_scan::resize_image,
+    bandwidth::low_bandwidth_status,
+    network_info::get_local_network_info,
+    network_info::list_network_interfaces,
+    net_watch::network_watch_status,
+    disk_info::get_disk_info,
+    disk_info::get_disk_usage,
+    disk_info::disk_usage_tree,
+    disk_info::disk_usage_cancel,
+    disk_info::get_disk_smart,
+    disk_info::disk_mount,
+    disk_info::disk_unmount,
+    ssh::ssh_execute,
+    ssh::ssh_execute_stream,
+    ssh::ssh_cancel,
+    ssh::ssh_sftp_upload,
+    ssh::ssh_sftp_download,
+    ssh::ssh_sftp_list,
+    ssh::ssh_test_connection,
+    ssh::ssh_list_known_hosts,
+    ssh::ssh_known_hosts_add,
+    ssh::ssh_known_hosts_remove,
+    network::db_execute,
+    network::db_query,
+    network::db_close,
+    audio_commands::stt_is_silence,
+    audio_commands::stt_get_mic_level,
+    file_search::file_search,
+    file_search::file_read_content,
+    file_search::file_delete,
+    email::email_send,
+    email::email_poll_inbox,
+    email::email_get_attachments,
+    email::email_download_attachment,
+    email::email_test_config,
+    frigate_mqtt::frigate_mqtt_start,
+    frigate_mqtt::frigate_mqtt_stop,
+    frigate_mqtt::frigate_mqtt_status,
+    frigate_mqtt::frigate_recent_events,
+    frigate_mqtt::frigate_get_snapshot,
+    mqtt::mqtt_publish,
+    mqtt::mqtt_subscribe,
+    mqtt::mqtt_unsubscribe,
+    geocoding::geocode_reverse,
+    geocoding::geocode_cache_stats,
+    geocoding::geocode_cache_clear,
+    idempotency::idempotency_recent_hits,
+    query_schema::query_schema_describe,
+    query_schema::query_schema_validate,
+    audit::audit_query,
+    audit::audit_verify,
+    audit::audit_export_jsonl,
+    motion_detection::motion_pipeline_start,
+    motion_detection::motion_pipeline_stop,
+    motion_detection::motion_pipeline_status,
+    motion_detection::motion_pipeline_health,
+    motion_detection::motion_pipeline_stats,
+    motion_detection::motion_pipeline_detections,
+    motion_detection::vision_query,
+    motion_detection::vision_query_direct,
+    motion_detection::vision_get_thumbnail,
+    motion_detection::vision_zones_set,
+    motion_detection::vision_snapshot,
+    motion_detection::vision_detector_info,
+    motion_detection::vision_thumbnails_export,
+    motion_detection::vision_heatmap,
+    motion_detection::vision_clips_list,
+    vision_export::vision_export,
+    vision_visits::vision_visits,
+    sounds::notification_sound_play,
+    autostart::autostart_enable,
+    autostart::autostart_disable,
+    autostart::autostart_status,
+    startup::startup_profile_get,
+    startup::startup_profile_set,
+    startup::startup_report,
+    audio_commands::wake_word_start,
+    audio_commands::wake_word_test,
+    audio_commands::wake_word_stop,
+    audio_commands::wake_word_check_triggered,
+    audio_commands::wake_word_resume_status,
+    wake_word::wake_word_get_level,
+    logging::get_backend_logs,
+    logging::set_log_level,
+    logging::get_log_file_path,
+    docker::docker_is_available,
+    docker::docker_info,
+    docker::docker_list_containers,
+    docker::docker_list_images,
+    docker::docker_list_volumes,
+    docker::docker_list_networks,
+    docker::docker_start_container,
+    docker::docker_stop_container,
+    docker::docker_restart_container,
+    docker::docker_remove_container,
+    docker::docker_list_stacks,
+    docker::docker_stack_stop,
+    docker::docker_stack_restart,
+    docker::docker_container_stats,
+    docker::docker_exec,
+    docker::docker_logs_follow,
+    docker::docker_logs_stop,
+    rss_parser::parse_rss_feed_command,
+    rss_watch::rss_watch_add,
+    rss_watch::rss_watch_remove,
+    rss_watch::rss_watch_list,
+    docker::docker_get_logs,
+    remote_machine::remote_test_connection,
+    remote_machine::remote_execute_command,
+    remote_machine::remote_get_system_info,
+    remote_machine::remote_list_processes,
+    remote_machine::remote_copy_file,
+    remote_machine::remote_check_docker,
+    remote_machine::remote_docker_list_containers,
+    remote_machine::remote_docker_start,
+    remote_machine::remote_docker_stop,
+    remote_machine::remote_docker_restart,
+    remote_machine::remote_docker_get_logs,
+    remote_metrics::remote_metrics_start,
+    remote_metrics::remote_metrics_stop,
+    remote_metrics::remote_metrics_status,
+    remote_metrics::remote_metrics_query,
+    toonic_sidecar::toonic_start,
+    toonic_sidecar::toonic_stop,
+    toonic_sidecar::toonic_status,
+    toonic_sidecar::toonic_proxy_get,
+    toonic_sidecar::toonic_proxy_post,
+    toonic_sidecar::toonic_proxy_delete,
+];
+
 fn main() {
     // Load environment variables from .env file (in project root)
     match dotenvy::from_filename("../.env") {
@@ -354,6 +753,15 @@ fn main() {
         Err(e) => backend_warn(format!("Failed to load .env: {}", e)),
     }
     
+    #[cfg(feature = "vision")]
+    if std::env::args().any(|a| a == "--vision-query") {
+        if let Err(e) = vision_query_engine::run_cli() {
+            eprintln!("vision query: {:#}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     init_logging();
     backend_info("Booting Broxeen Tauri backend...");
     
@@ -373,123 +781,76 @@ fn main() {
         backend_info(format!("Piper setup hint:\n{}", instructions));
     }
 
-    backend_info(
-        "Registering command handlers: get_app_version, get_settings, save_settings, browse, llm_chat, stt_transcribe, stt_start, stt_stop, stt_status, backend_tts_speak, backend_tts_speak_base64, backend_tts_info, backend_audio_devices, tts_is_available, tts_speak, tts_stop, ping_host, scan_ports, arp_scan, discover_onvif_cameras, discover_mdns, scan_network, file_search, file_read_content, email_send, email_poll_inbox, email_test_config, frigate_mqtt_start, frigate_mqtt_stop, motion_pipeline_start, motion_pipeline_stop, motion_pipeline_status, motion_pipeline_stats, motion_pipeline_detections",
-    );
+    backend_info(format!(
+        "Registering {} command handlers: {}",
+        COMMAND_NAMES.len(),
+        COMMAND_NAMES
+            .iter()
+            .map(|path| path.rsplit("::").next().unwrap_or(path).trim())
+            .collect::<Vec<_>>()
+            .join(", ")
+    ));
 
     let recording_state: SharedRecordingState = Arc::new(Mutex::new(audio_capture::RecordingState::new()));
     let wake_word_state: SharedWakeWordState = Arc::new(Mutex::new(wake_word::WakeWordState::new()));
     let active_stream = audio_commands::ActiveStream(Arc::new(Mutex::new(None)));
     let active_wake_word_stream = audio_commands::ActiveWakeWordStream(Arc::new(Mutex::new(None)));
     let active_tts = audio_commands::ActiveTts(Arc::new(Mutex::new(None)));
+    let wake_word_resume = audio_commands::WakeWordResume(Arc::new(Mutex::new(Default::default())));
+    let governor = command_governor::CommandGovernor::from_settings(&settings::load_settings());
 
-    if let Err(err) = tauri::Builder::default()
+    let app = match tauri::Builder::default()
         .manage(recording_state)
         .manage(wake_word_state)
         .manage(active_stream)
         .manage(active_wake_word_stream)
         .manage(active_tts)
+        .manage(wake_word_resume)
+        .manage(governor)
         .plugin(tauri_plugin_shell::init())
-        .invoke_handler(tauri::generate_handler![
-            get_app_version,
-            settings::get_settings,
-            settings::save_settings,
-            browse,
-            llm::llm_chat,
-            stt::stt_transcribe,
-            audio_commands::stt_start,
-            audio_commands::stt_stop,
-            audio_commands::stt_status,
-            audio_commands::backend_tts_speak,
-            audio_commands::backend_tts_stop,
-            audio_commands::backend_tts_pause,
-            audio_commands::backend_tts_resume,
-            audio_commands::backend_tts_speak_base64,
-            audio_commands::backend_tts_info,
-            audio_commands::backend_audio_devices,
-            audio_commands::piper_install,
-            audio_commands::piper_is_installed,
-            tts::tts_is_available,
-            tts::tts_speak,
-            tts::tts_stop,
-            network_scan::ping_host_simple,
-            network_scan::ping_host,
-            network_scan::scan_ports,
-            network_scan::arp_scan,
-            network_scan::discover_onvif_cameras,
-            network_scan::discover_mdns,
-            network_scan::scan_network,
-            network_scan::rtsp_capture_frame,
-            network_scan::rtsp_worker_stats,
-            network_scan::rtsp_stop_worker,
-            network_scan::rtsp_stop_all_workers,
-            network_scan::http_fetch_base64,
-            network_scan::camera_health_check,
-            network_scan::resize_image,
-            network_info::get_local_network_info,
-            network_info::list_network_interfaces,
-            disk_info::get_disk_info,
-            disk_info::get_disk_usage,
-            ssh::ssh_execute,
-            ssh::ssh_test_connection,
-            ssh::ssh_list_known_hosts,
-            network::db_execute,
-            network::db_query,
-            network::db_close,
-            audio_commands::stt_is_silence,
-            audio_commands::stt_get_mic_level,
-            file_search::file_search,
-            file_search::file_read_content,
-            email::email_send,
-            email::email_poll_inbox,
-            email::email_test_config,
-            frigate_mqtt::frigate_mqtt_start,
-            frigate_mqtt::frigate_mqtt_stop,
-            motion_detection::motion_pipeline_start,
-            motion_detection::motion_pipeline_stop,
-            motion_detection::motion_pipeline_status,
-            motion_detection::motion_pipeline_stats,
-            motion_detection::motion_pipeline_detections,
-            motion_detection::vision_query,
-            motion_detection::vision_query_direct,
-            autostart::autostart_enable,
-            autostart::autostart_disable,
-            autostart::autostart_status,
-            audio_commands::wake_word_start,
-            audio_commands::wake_word_stop,
-            audio_commands::wake_word_check_triggered,
-            wake_word::wake_word_get_level,
-            logging::get_backend_logs,
-            docker::docker_is_available,
-            docker::docker_info,
-            docker::docker_list_containers,
-            docker::docker_list_images,
-            docker::docker_list_volumes,
-            docker::docker_list_networks,
-            docker::docker_start_container,
-            docker::docker_stop_container,
-            docker::docker_restart_container,
-            docker::docker_remove_container,
-            rss_parser::parse_rss_feed_command,
-            docker::docker_get_logs,
-            remote_machine::remote_test_connection,
-            remote_machine::remote_execute_command,
-            remote_machine::remote_get_system_info,
-            remote_machine::remote_list_processes,
-            remote_machine::remote_copy_file,
-            remote_machine::remote_check_docker,
-            toonic_sidecar::toonic_start,
-            toonic_sidecar::toonic_stop,
-            toonic_sidecar::toonic_status,
-            toonic_sidecar::toonic_proxy_get,
-            toonic_sidecar::toonic_proxy_post,
-            toonic_sidecar::toonic_proxy_delete,
-        ])
-        .run(tauri::generate_context!())
+        .on_window_event(|_window, event| {
+            if let tauri::WindowEvent::Focused(focused) = event {
+                audio_level::set_window_focused(*focused);
+            }
+        })
+        .setup(|app| {
+            shutdown::reap_orphans();
+            rss_watch::start_scheduler(app.handle().clone());
+            net_watch::start(app.handle().clone());
+            disk_watch::start(app.handle().clone());
+            llm_usage::log_daily_total();
+            startup::apply(app.handle().clone());
+            logging::watch_log_settings();
+            sounds::watch_sound_settings();
+            #[cfg(feature = "vision")]
+            vision_ws::watch_ws_settings();
+            let handle = app.handle().clone();
+            settings::watch_settings("command_governor", move |change| {
+                use tauri::Manager;
+                if change.changed_keys.iter().any(|k| k == "command_limits") {
+                    handle
+                        .state::<command_governor::CommandGovernor>()
+                        .apply(&change.settings.command_limits);
+                }
+            });
+            #[cfg(feature = "vision")]
+            vision_reload::start();
+            Ok(())
+        })
+        .invoke_handler(invoke_handler)
+        .build(tauri::generate_context!())
     {
-        backend_error(format!("Error while running Broxeen: {}", err));
-        panic!("error while running Broxeen");
-    }
+        Ok(app) => app,
+        Err(err) => {
+            backend_error(format!("Error while running Broxeen: {}", err));
+            panic!("error while running Broxeen");
+        }
+    };
 
-    backend_info("Broxeen backend stopped gracefully");
+    // Quitting must not leave ffmpeg / motion_pipeline.py orphans behind.
+    app.run(|_handle, event| {
+        if matches!(event, tauri::RunEvent::ExitRequested { .. } | tauri::RunEvent::Exit) {
+            shutdown::run();
+        }
+    });
 }
@@ -8,13 +8,18 @@
  *   Manages Python subprocess running motion_pipeline.py.
  *
  * Commands:
- *   motion_pipeline_start      — start pipeline for a camera
+ *   motion_pipeline_start      — start pipeline for a camera; returns
+ *                                backend + warnings for deprecated/ignored fields
  *   motion_pipeline_stop       — stop pipeline for a camera
  *   motion_pipeline_status     — list active pipelines + stats
+ *   motion_pipeline_health     — is a pipeline alive and are frames flowing
  *   motion_pipeline_stats      — query SQLite detections DB
  *   motion_pipeline_detections — get detection rows
  *   vision_query               — natural language → SQL → real DB results
+ *                                (optionally with thumbnail refs / inline thumbnails)
+ *   vision_get_thumbnail       — fetch a detection thumbnail by id
  *   vision_query_direct        — run raw SQL SELECT on monitoring DB
+ *   vision_zones_set           — replace include/exclude zones on a running pipeline
  */
 
 use serde::{Deserialize, Serialize};
@@ -29,6 +34,8 @@ use std::process::{Child, Command, Stdio};
 use std::env;
 #[cfg(not(feature = "vision"))]
 use std::thread;
+#[cfg(not(feature = "vision"))]
+use std::sync::Arc;
 
 #[cfg(feature = "vision")]
 use crate::vision_db::{VisionDatabase, SCHEMA};
@@ -37,6 +44,8 @@ use crate::vision_llm::LlmClient;
 #[cfg(feature = "vision")]
 use crate::vision_query_engine::QueryEngine;
 
+use crate::error::{BroxeenError, ErrorCode};
+use crate::image_meta::ThumbnailFormat;
 use crate::logging::{backend_info, backend_warn, backend_error};
 
 // ── Shared state ──────────────────────────────────────────────────────────────
@@ -46,8 +55,38 @@ use crate::logging::{backend_info, backend_warn, backend_error};
 struct PipelineProcess {
     child: Child,
     camera_id: String,
-    rtsp_url: String,
     started_at: u64,
+    _pid: crate::shutdown::ChildGuard,
+    progress: Arc<Mutex<PythonProgress>>,
+    /// `--stats-interval` based: frames are only reported with stats events
+    stall_ms: u64,
+}
+
+/// What the stdout/stderr readers learned about a Python pipeline.
+#[cfg(not(feature = "vision"))]
+#[derive(Debug, Default)]
+struct PythonProgress {
+    frames: u64,
+    last_frame_at: Option<u64>,
+    last_stderr: Option<String>,
+    /// Last `STDERR_TAIL_LINES` lines, for classifying a crash
+    stderr_tail: std::collections::VecDeque<String>,
+}
+
+#[cfg(not(feature = "vision"))]
+const STDERR_TAIL_LINES: usize = 30;
+
+/// Restart bookkeeping of a Python pipeline, kept while its process is
+/// running or waiting out a backoff; removed on stop or when giving up.
+#[cfg(not(feature = "vision"))]
+#[derive(Debug)]
+struct Supervision {
+    /// The start request (RTSP URL already resolved), replayed on restart
+    request: StartPipelineRequest,
+    /// Tells a stale supervisor thread from the current one after stop + start
+    generation: u64,
+    restart_count: u32,
+    last_exit_reason: Option<String>,
 }
 
 #[cfg(feature = "vision")]
@@ -59,14 +98,47 @@ struct NativePipeline {
 lazy_static::lazy_static! {
     static ref PIPELINES: Mutex<HashMap<String, PipelineProcess>> =
         Mutex::new(HashMap::new());
+    static ref SUPERVISED: Mutex<HashMap<String, Supervision>> =
+        Mutex::new(HashMap::new());
 }
 
+#[cfg(not(feature = "vision"))]
+static SUPERVISION_GENERATION: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
 #[cfg(feature = "vision")]
 lazy_static::lazy_static! {
     static ref PIPELINES_NATIVE: Mutex<HashMap<String, NativePipeline>> =
         Mutex::new(HashMap::new());
 }
 
+lazy_static::lazy_static! {
+    /// Why a camera's pipeline last stopped on its own (cleared on start)
+    static ref LAST_FAILURES: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+}
+
+/// No frame for this long = the stream stalled.
+const FRAME_STALL_MS: u64 = 15_000;
+
+fn record_failure(camera_id: &str, reason: String) {
+    if let Ok(mut failures) = LAST_FAILURES.lock() {
+        failures.insert(camera_id.to_string(), reason);
+    }
+}
+
+fn clear_failure(camera_id: &str) {
+    if let Ok(mut failures) = LAST_FAILURES.lock() {
+        failures.remove(camera_id);
+    }
+}
+
+fn last_failure(camera_id: &str) -> Option<String> {
+    LAST_FAILURES.lock().ok()?.get(camera_id).cloned()
+}
+
+fn frames_flowing(last_frame_at: Option<u64>, now: u64, stall_ms: u64) -> bool {
+    last_frame_at.is_some_and(|t| now.saturating_sub(t) <= stall_ms)
+}
+
 fn now_ms() -> u64 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -76,26 +148,189 @@ fn now_ms() -> u64 {
 
 // ── Request / Response types ──────────────────────────────────────────────────
 
-#[derive(Debug, Deserialize)]
+/// Request for `motion_pipeline_start`.
+///
+/// Split into fields understood by both backends plus per-backend extensions.
+/// Fields meaningful only to the other backend are accepted (so old frontend
+/// payloads keep working) but reported back as warnings in [`PipelineStartResult`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StartPipelineRequest {
+    #[serde(flatten)]
+    pub core: PipelineCoreOptions,
+    #[serde(flatten)]
+    pub native: NativePipelineOptions,
+    #[serde(flatten)]
+    pub python: PythonPipelineOptions,
+    /// Anything we don't recognise — surfaced as warnings instead of silently dropped
+    #[serde(flatten)]
+    pub unknown: HashMap<String, serde_json::Value>,
+}
+
+/// Fields used by both the native and the Python pipeline.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineCoreOptions {
     pub camera_id: String,
+    /// Empty (or without password) = taken from the credentials vault
+    #[serde(default)]
     pub rtsp_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub db_path: Option<String>,
-    pub python_path: Option<String>,
-    pub pipeline_script: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub process_every: Option<u32>,
-    pub min_area: Option<u32>,
-    pub max_area: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub var_threshold: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub bg_history: Option<u32>,
-    pub llm_threshold: Option<f32>,
-    pub cooldown_sec: Option<f32>,
-    pub max_crop_px: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub llm_model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub api_key: Option<String>,
-    pub platform: Option<String>,
+    /// Deprecated: meant detector confidence in the native build and
+    /// LLM-verify threshold in the Python build. Use `detector_confidence`
+    /// or `llm_verify_below` instead.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub llm_threshold: Option<f32>,
+    /// Force night (IR) motion parameters regardless of measured luminance
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub night_mode: Option<bool>,
+}
+
+/// Fields used only by the native Rust (vision) pipeline.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NativePipelineOptions {
+    /// YOLO confidence threshold (default 0.50)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detector_confidence: Option<f32>,
+    /// Optional camera GPS coordinates, used for `{camera_location}` in narratives
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+}
+
+/// Fields used only by the Python `motion_pipeline.py` subprocess.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PythonPipelineOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub python_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pipeline_script: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub platform: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub stats_interval: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_area: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_area: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cooldown_sec: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_crop_px: Option<u32>,
+    /// Send detections below this local confidence to the LLM for verification (default 0.6)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub llm_verify_below: Option<f32>,
+    /// Automatic restarts after a crash before giving up (default 5, 0 = never)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_restarts: Option<u32>,
+}
+
+impl NativePipelineOptions {
+    fn set_fields(&self) -> Vec<&'static str> {
+        let mut f = Vec::new();
+        if self.detector_confidence.is_some() { f.push("detector_confidence"); }
+        if self.latitude.is_some() { f.push("latitude"); }
+        if self.longitude.is_some() { f.push("longitude"); }
+        if self.location.is_some() { f.push("location"); }
+        f
+    }
+}
+
+impl PythonPipelineOptions {
+    fn set_fields(&self) -> Vec<&'static str> {
+        let mut f = Vec::new();
+        if self.python_path.is_some() { f.push("python_path"); }
+        if self.pipeline_script.is_some() { f.push("pipeline_script"); }
+        if self.platform.is_some() { f.push("platform"); }
+        if self.stats_interval.is_some() { f.push("stats_interval"); }
+        if self.min_area.is_some() { f.push("min_area"); }
+        if self.max_area.is_some() { f.push("max_area"); }
+        if self.cooldown_sec.is_some() { f.push("cooldown_sec"); }
+        if self.max_crop_px.is_some() { f.push("max_crop_px"); }
+        if self.llm_verify_below.is_some() { f.push("llm_verify_below"); }
+        if self.max_restarts.is_some() { f.push("max_restarts"); }
+        f
+    }
+}
+
+impl StartPipelineRequest {
+    /// Detector confidence; legacy `llm_threshold` keeps its old native meaning.
+    #[cfg_attr(not(feature = "vision"), allow(dead_code))]
+    pub fn detector_confidence(&self) -> f32 {
+        self.native.detector_confidence.or(self.core.llm_threshold).unwrap_or(0.50)
+    }
+
+    /// LLM verification threshold; legacy `llm_threshold` keeps its old Python meaning.
+    pub fn llm_verify_below(&self) -> f32 {
+        self.python.llm_verify_below.or(self.core.llm_threshold).unwrap_or(0.6)
+    }
+
+    /// Structured warnings for deprecated, ignored or unknown fields.
+    pub fn warnings(&self, native_backend: bool) -> Vec<PipelineWarning> {
+        let mut warnings = Vec::new();
+
+        if self.core.llm_threshold.is_some() {
+            let replacement = if native_backend { "detector_confidence" } else { "llm_verify_below" };
+            warnings.push(PipelineWarning {
+                field: "llm_threshold".into(),
+                kind: "deprecated".into(),
+                message: format!("llm_threshold is deprecated, use {}", replacement),
+            });
+        }
+
+        let (ignored, other) = if native_backend {
+            (self.python.set_fields(), "Python")
+        } else {
+            (self.native.set_fields(), "native")
+        };
+        for field in ignored {
+            warnings.push(PipelineWarning {
+                field: field.into(),
+                kind: "ignored".into(),
+                message: format!("{} is only used by the {} pipeline", field, other),
+            });
+        }
+
+        let mut unknown: Vec<&String> = self.unknown.keys().collect();
+        unknown.sort();
+        for field in unknown {
+            warnings.push(PipelineWarning {
+                field: field.clone(),
+                kind: "unknown".into(),
+                message: format!("Unknown field {} was ignored", field),
+            });
+        }
+
+        warnings
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PipelineWarning {
+    pub field: String,
+    /// "deprecated" | "ignored" | "unknown"
+    pub kind: String,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PipelineStartResult {
+    pub message: String,
+    /// "native" | "python"
+    pub backend: String,
+    pub warnings: Vec<PipelineWarning>,
 }
 
 #[derive(Debug, Serialize)]
@@ -104,6 +339,47 @@ pub struct PipelineStatus {
     pub rtsp_url: String,
     pub started_at: u64,
     pub running: bool,
+    /// Native only: the stall watchdog's reconnects brought no frames
+    pub degraded: bool,
+    // Live counters — only tracked by the native (vision) pipeline
+    pub frames_captured: Option<u64>,
+    pub frames_processed: Option<u64>,
+    pub motion_events: Option<u64>,
+    pub detections_saved: Option<u64>,
+    /// Detections dropped by the detector's class whitelist / per-class thresholds
+    pub detections_filtered: Option<u64>,
+    pub llm_calls: Option<u64>,
+    /// Detections covered by batched LLM scene requests
+    pub llm_batched: Option<u64>,
+    /// Detections skipped by the LLM requests-per-minute limit
+    pub llm_skipped: Option<u64>,
+    pub channel_drops: Option<u64>,
+    pub webhooks_delivered: Option<u64>,
+    pub webhooks_failed: Option<u64>,
+    pub last_frame_at: Option<u64>,
+    pub frames_per_second: Option<f64>,
+    /// Detector execution provider actually in use ("OpenVINO (GPU)", "CPU")
+    pub execution_provider: Option<String>,
+    pub inference_ms_ema: Option<f64>,
+    /// Python only: automatic restarts since the last stable run
+    pub restart_count: u32,
+    /// Python: why the process last exited ("import_error: …"); native: the
+    /// capture loop's failure
+    pub last_exit_reason: Option<String>,
+}
+
+/// Answer of `motion_pipeline_health`.
+#[derive(Debug, Serialize)]
+pub struct PipelineHealth {
+    pub camera_id: String,
+    /// Registered and its capture loop / process still alive
+    pub running: bool,
+    /// A frame arrived recently (Python: since the previous stats event)
+    pub frames_flowing: bool,
+    pub last_frame_at: Option<u64>,
+    pub frames_per_second: Option<f64>,
+    /// Why the pipeline stopped on its own, if it did
+    pub last_error: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -142,6 +418,18 @@ pub struct DetectionStats {
 
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
+/// How long read commands wait for a running pipeline's write to finish.
+const MONITORING_DB_BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Read-only connection to a detections / monitoring DB that a pipeline may be
+/// writing: waits for the writer instead of failing with SQLITE_BUSY.
+pub fn open_monitoring_db(path: &str) -> rusqlite::Result<rusqlite::Connection> {
+    let conn = rusqlite::Connection::open(path)?;
+    conn.busy_timeout(MONITORING_DB_BUSY_TIMEOUT)?;
+    conn.execute_batch("PRAGMA query_only=ON;")?;
+    Ok(conn)
+}
+
 pub fn resolve_db_path(db_path: &str) -> String {
     if std::path::Path::new(db_path).is_absolute() {
         return db_path.to_string();
@@ -191,32 +479,49 @@ fn resolve_script_path(script: &str) -> String {
 pub async fn motion_pipeline_start(
     app_handle: tauri::AppHandle,
     request: StartPipelineRequest,
-) -> Result<String, String> {
-    let camera_id = request.camera_id.clone();
+) -> Result<PipelineStartResult, BroxeenError> {
+    let camera_id = request.core.camera_id.clone();
+    let warnings = request.warnings(true);
+    for w in &warnings {
+        backend_warn(format!("motion_pipeline_start[{}]: {}", camera_id, w.message));
+    }
+    let mut request = request;
+    request.core.rtsp_url = crate::credentials::resolve_rtsp_url(&camera_id, &request.core.rtsp_url)
+        .await
+        .map_err(BroxeenError::not_configured)?;
 
     {
-        let pipelines = PIPELINES_NATIVE.lock().map_err(|e| e.to_string())?;
+        let mut pipelines = PIPELINES_NATIVE.lock().map_err(|e| e.to_string())?;
+        // A pipeline whose capture loop died must not block a retry
+        if pipelines.get(&camera_id).is_some_and(|p| p.handle.failure().is_some()) {
+            pipelines.remove(&camera_id);
+        }
         if pipelines.contains_key(&camera_id) {
-            return Err(format!("Pipeline already running for camera: {}", camera_id));
+            return Err(already_running(&camera_id));
         }
     }
+    preflight(&camera_id, &request.core.rtsp_url).await?;
 
     backend_info(format!(
         "Starting native vision pipeline: camera={} rtsp={}",
-        camera_id, crate::network_scan::anonymize_rtsp_url(&request.rtsp_url)
+        camera_id, crate::network_scan::anonymize_rtsp_url(&request.core.rtsp_url)
     ));
 
     // Build VisionConfig from StartPipelineRequest fields (v0.3)
     let mut vision_cfg = crate::vision_config::default_config();
-    vision_cfg.camera.url = request.rtsp_url.clone();
+    vision_cfg.camera.url = request.core.rtsp_url.clone();
     vision_cfg.camera.camera_id = camera_id.clone();
-    vision_cfg.detector.confidence_threshold = request.llm_threshold.unwrap_or(0.50);
-    vision_cfg.pipeline.process_every_n_frames = request.process_every.unwrap_or(4);
-    vision_cfg.pipeline.bg_history = request.bg_history.unwrap_or(500) as i32;
-    vision_cfg.pipeline.bg_var_threshold = request.var_threshold.unwrap_or(40) as f64;
-    vision_cfg.database.path = request.db_path.unwrap_or_else(|| "monitoring.db".to_string());
+    vision_cfg.camera.latitude = request.native.latitude;
+    vision_cfg.camera.longitude = request.native.longitude;
+    vision_cfg.camera.location = request.native.location.clone();
+    vision_cfg.detector.confidence_threshold = request.detector_confidence();
+    vision_cfg.pipeline.process_every_n_frames = request.core.process_every.unwrap_or(4);
+    vision_cfg.pipeline.bg_history = request.core.bg_history.unwrap_or(500) as i32;
+    vision_cfg.pipeline.bg_var_threshold = request.core.var_threshold.unwrap_or(40) as f64;
+    vision_cfg.pipeline.force_night = request.core.night_mode.unwrap_or(false);
+    vision_cfg.database.path = request.core.db_path.clone().unwrap_or_else(|| "monitoring.db".to_string());
     // LLM: prefer OpenRouter key from request or env
-    if let Some(ref key) = request.api_key {
+    if let Some(ref key) = request.core.api_key {
         if !key.is_empty() {
             vision_cfg.llm.openrouter_api_key = Some(key.clone());
         }
@@ -226,13 +531,23 @@ pub async fn motion_pipeline_start(
             if !key.is_empty() { vision_cfg.llm.openrouter_api_key = Some(key); }
         }
     }
-    if let Some(ref model) = request.llm_model {
+    if let Some(ref model) = request.core.llm_model {
         vision_cfg.llm.openrouter_model = model.clone();
     }
 
-    let pipeline = crate::vision_pipeline::Pipeline::new(vision_cfg);
+    let hook_camera_id = camera_id.clone();
+    let pipeline = crate::vision_pipeline::Pipeline::new(vision_cfg).on_fatal_exit(move |reason| {
+        backend_warn(format!("Native vision pipeline for {} exited: {}", hook_camera_id, reason));
+        record_failure(&hook_camera_id, reason);
+        if let Ok(mut pipelines) = PIPELINES_NATIVE.lock() {
+            if pipelines.get(&hook_camera_id).is_some_and(|p| p.handle.failure().is_some()) {
+                pipelines.remove(&hook_camera_id);
+            }
+        }
+    });
     let handle = pipeline.start(Some(app_handle)).map_err(|e| {
         backend_error(format!("Failed to start native vision pipeline: {}", e));
+        crate::sounds::play_notification(crate::sounds::NotificationKind::Error);
         format!("Failed to start pipeline: {}", e)
     })?;
 
@@ -240,9 +555,14 @@ pub async fn motion_pipeline_start(
         let mut pipelines = PIPELINES_NATIVE.lock().map_err(|e| e.to_string())?;
         pipelines.insert(camera_id.clone(), NativePipeline { handle });
     }
+    clear_failure(&camera_id);
 
     backend_info(format!("Native vision pipeline started for camera: {}", camera_id));
-    Ok(format!("Pipeline started for camera: {} (native Rust)", camera_id))
+    Ok(PipelineStartResult {
+        message: format!("Pipeline started for camera: {} (native Rust)", camera_id),
+        backend: "native".into(),
+        warnings,
+    })
 }
 
 #[cfg(not(feature = "vision"))]
@@ -250,36 +570,85 @@ pub async fn motion_pipeline_start(
 pub async fn motion_pipeline_start(
     app_handle: tauri::AppHandle,
     request: StartPipelineRequest,
-) -> Result<String, String> {
-    let camera_id = request.camera_id.clone();
+) -> Result<PipelineStartResult, BroxeenError> {
+    let camera_id = request.core.camera_id.clone();
+    let warnings = request.warnings(false);
+    for w in &warnings {
+        backend_warn(format!("motion_pipeline_start[{}]: {}", camera_id, w.message));
+    }
+    let mut request = request;
+    request.core.rtsp_url = crate::credentials::resolve_rtsp_url(&camera_id, &request.core.rtsp_url)
+        .await
+        .map_err(BroxeenError::not_configured)?;
 
     {
-        let pipelines = PIPELINES.lock().map_err(|e| e.to_string())?;
-        if pipelines.contains_key(&camera_id) {
-            return Err(format!("Pipeline already running for camera: {}", camera_id));
+        let supervised = SUPERVISED.lock().map_err(|e| e.to_string())?;
+        if supervised.contains_key(&camera_id) {
+            return Err(already_running(&camera_id));
         }
     }
+    preflight(&camera_id, &request.core.rtsp_url).await?;
 
-    let python = request.python_path.unwrap_or_else(|| "python3".to_string());
-    let script_rel = request
+    let process = spawn_python(&app_handle, &request)?;
+    let generation = SUPERVISION_GENERATION.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+    {
+        let mut supervised = SUPERVISED.lock().map_err(|e| e.to_string())?;
+        let mut pipelines = PIPELINES.lock().map_err(|e| e.to_string())?;
+        if supervised.contains_key(&camera_id) {
+            drop(pipelines);
+            let mut process = process;
+            let _ = process.child.kill();
+            let _ = process.child.wait();
+            return Err(already_running(&camera_id));
+        }
+        pipelines.insert(camera_id.clone(), process);
+        supervised.insert(camera_id.clone(), Supervision {
+            request,
+            generation,
+            restart_count: 0,
+            last_exit_reason: None,
+        });
+    }
+    clear_failure(&camera_id);
+    emit_pipeline_state(&app_handle, &camera_id, "started", 0, None, None);
+    let app_supervisor = app_handle.clone();
+    let cam_supervisor = camera_id.clone();
+    thread::spawn(move || supervise_python(app_supervisor, cam_supervisor, generation));
+
+    backend_info(format!("Motion pipeline started for camera: {}", camera_id));
+    Ok(PipelineStartResult {
+        message: format!("Pipeline started for camera: {} (Python)", camera_id),
+        backend: "python".into(),
+        warnings,
+    })
+}
+
+/// Spawn `motion_pipeline.py` for `request` with its stdout/stderr readers.
+#[cfg(not(feature = "vision"))]
+fn spawn_python(app_handle: &tauri::AppHandle, request: &StartPipelineRequest) -> Result<PipelineProcess, BroxeenError> {
+    let camera_id = request.core.camera_id.clone();
+    let llm_threshold = request.llm_verify_below().to_string();
+    let (core, opts) = (request.core.clone(), request.python.clone());
+
+    let python = opts.python_path.unwrap_or_else(|| "python3".to_string());
+    let script_rel = opts
         .pipeline_script
         .unwrap_or_else(|| "scripts/motion_pipeline.py".to_string());
     let script = resolve_script_path(&script_rel);
 
-    let db_raw = request
+    let db_raw = core
         .db_path
         .unwrap_or_else(|| "detections.db".to_string());
     let db = resolve_db_path(&db_raw);
 
-    let process_every = request.process_every.unwrap_or(5).to_string();
-    let min_area = request.min_area.unwrap_or(2000).to_string();
-    let max_area = request.max_area.unwrap_or(200000).to_string();
-    let var_threshold = request.var_threshold.unwrap_or(50).to_string();
-    let bg_history = request.bg_history.unwrap_or(500).to_string();
-    let llm_threshold = request.llm_threshold.unwrap_or(0.6).to_string();
-    let cooldown = request.cooldown_sec.unwrap_or(10.0).to_string();
-    let max_crop = request.max_crop_px.unwrap_or(500).to_string();
-    let llm_model = request
+    let process_every = core.process_every.unwrap_or(5).to_string();
+    let min_area = opts.min_area.unwrap_or(2000).to_string();
+    let max_area = opts.max_area.unwrap_or(200000).to_string();
+    let var_threshold = core.var_threshold.unwrap_or(50).to_string();
+    let bg_history = core.bg_history.unwrap_or(500).to_string();
+    let cooldown = opts.cooldown_sec.unwrap_or(10.0).to_string();
+    let max_crop = opts.max_crop_px.unwrap_or(500).to_string();
+    let llm_model = core
         .llm_model
         .unwrap_or_else(|| {
             env::var("MOTION_LLM_VERIFY_MODEL").unwrap_or_else(|_| {
@@ -287,12 +656,12 @@ pub async fn motion_pipeline_start(
                     .unwrap_or_else(|_| "anthropic/claude-haiku-4-5".to_string())
             })
         });
-    let platform = request.platform.unwrap_or_else(|| "auto".to_string());
-    let stats_interval = request.stats_interval.unwrap_or(60).to_string();
+    let platform = opts.platform.unwrap_or_else(|| "auto".to_string());
+    let stats_interval = opts.stats_interval.unwrap_or(60).to_string();
 
     let mut cmd = Command::new(&python);
     cmd.arg(&script)
-        .arg("--rtsp").arg(&request.rtsp_url)
+        .arg("--rtsp").arg(&core.rtsp_url)
         .arg("--camera-id").arg(&camera_id)
         .arg("--db").arg(&db)
         .arg("--platform").arg(&platform)
@@ -310,11 +679,11 @@ pub async fn motion_pipeline_start(
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
 
-    if request.night_mode.unwrap_or(false) {
+    if core.night_mode.unwrap_or(false) {
         cmd.arg("--night-mode");
     }
 
-    if let Some(ref key) = request.api_key {
+    if let Some(ref key) = core.api_key {
         if !key.is_empty() {
             cmd.arg("--api-key").arg(key);
         }
@@ -327,24 +696,42 @@ pub async fn motion_pipeline_start(
 
     backend_info(format!(
         "Starting motion pipeline: camera={} rtsp={} script={}",
-        camera_id, request.rtsp_url, script
+        camera_id, core.rtsp_url, script
     ));
 
     let mut child = cmd.spawn().map_err(|e| {
         backend_error(format!("Failed to spawn motion_pipeline.py: {}", e));
-        format!("Failed to start pipeline: {}. Is python3 installed and opencv/ultralytics available?", e)
+        crate::sounds::play_notification(crate::sounds::NotificationKind::Error);
+        let code = if e.kind() == std::io::ErrorKind::NotFound { ErrorCode::ExternalToolMissing } else { ErrorCode::Internal };
+        BroxeenError::new(code, format!("Failed to start pipeline: {}. Is python3 installed and opencv/ultralytics available?", e))
     })?;
 
     // Spawn stdout reader thread — forwards JSON events to frontend
+    let progress = Arc::new(Mutex::new(PythonProgress::default()));
     let stdout = child.stdout.take().ok_or("No stdout")?;
     let cam_id_clone = camera_id.clone();
     let app_clone = app_handle.clone();
+    let progress_out = Arc::clone(&progress);
     thread::spawn(move || {
         let reader = BufReader::new(stdout);
         for line in reader.lines() {
             match line {
                 Ok(l) if !l.trim().is_empty() => {
                     backend_info(format!("[motion:{}] {}", cam_id_clone, &l[..l.len().min(200)]));
+                    if let Ok(event) = serde_json::from_str::<serde_json::Value>(&l) {
+                        if event["type"] == "detection" {
+                            crate::sounds::notify_detection(event["confidence"].as_f64().unwrap_or(0.0) as f32);
+                        }
+                        if event["type"] == "stats" {
+                            if let Some(frames) = event["pipeline"]["frames"].as_u64() {
+                                let mut p = progress_out.lock().unwrap_or_else(|e| e.into_inner());
+                                if frames > p.frames {
+                                    p.frames = frames;
+                                    p.last_frame_at = Some(now_ms());
+                                }
+                            }
+                        }
+                    }
                     use tauri::Emitter;
                     let _ = app_clone.emit("broxeen:motion_event", serde_json::json!({
                         "camera_id": cam_id_clone,
@@ -364,37 +751,230 @@ pub async fn motion_pipeline_start(
     // Spawn stderr reader thread — logs warnings
     let stderr = child.stderr.take().ok_or("No stderr")?;
     let cam_id_err = camera_id.clone();
+    let progress_err = Arc::clone(&progress);
     thread::spawn(move || {
         let reader = BufReader::new(stderr);
         for line in reader.lines() {
             if let Ok(l) = line {
                 if !l.trim().is_empty() {
+                    let l = crate::network_scan::anonymize_rtsp_url(&l);
                     backend_warn(format!("[motion:{}] {}", cam_id_err, l));
+                    let mut p = progress_err.lock().unwrap_or_else(|e| e.into_inner());
+                    if p.stderr_tail.len() == STDERR_TAIL_LINES {
+                        p.stderr_tail.pop_front();
+                    }
+                    p.stderr_tail.push_back(l.clone());
+                    p.last_stderr = Some(l);
                 }
             }
         }
     });
 
-    let started_at = now_ms();
-    let process = PipelineProcess {
+    Ok(PipelineProcess {
+        _pid: crate::shutdown::track_child(child.id(), "motion_pipeline", &camera_id),
         child,
         camera_id: camera_id.clone(),
-        rtsp_url: request.rtsp_url.clone(),
-        started_at,
-    };
+        started_at: now_ms(),
+        progress,
+        stall_ms: (opts.stats_interval.unwrap_or(60) as u64 * 2 + 5) * 1000,
+    })
+}
 
-    {
-        let mut pipelines = PIPELINES.lock().map_err(|e| e.to_string())?;
-        pipelines.insert(camera_id.clone(), process);
+/// Why `motion_pipeline.py` exited, from its stderr tail and exit status.
+#[cfg(not(feature = "vision"))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PythonExit {
+    /// A Python module (ultralytics, cv2…) is missing — restarting won't help
+    ImportError,
+    /// The camera rejected the credentials — restarting won't help
+    RtspAuth,
+    /// MemoryError, or killed with SIGKILL (the kernel OOM killer)
+    OutOfMemory,
+    Crashed,
+}
+
+#[cfg(not(feature = "vision"))]
+impl PythonExit {
+    fn classify(stderr_tail: &[String], signal: Option<i32>) -> Self {
+        let has = |needles: &[&str]| {
+            stderr_tail.iter().any(|l| {
+                let l = l.to_lowercase();
+                needles.iter().any(|n| l.contains(n))
+            })
+        };
+        if has(&["modulenotfounderror", "importerror", "no module named", "can't open file"]) {
+            PythonExit::ImportError
+        } else if has(&["unauthorized", "authentication failed", "auth failed"]) {
+            PythonExit::RtspAuth
+        } else if has(&["memoryerror", "out of memory", "cannot allocate memory"]) || signal == Some(9) {
+            PythonExit::OutOfMemory
+        } else {
+            PythonExit::Crashed
+        }
     }
 
-    backend_info(format!("Motion pipeline started for camera: {}", camera_id));
-    Ok(format!("Pipeline started for camera: {} (Python)", camera_id))
+    fn as_str(&self) -> &'static str {
+        match self {
+            PythonExit::ImportError => "import_error",
+            PythonExit::RtspAuth => "rtsp_auth",
+            PythonExit::OutOfMemory => "oom",
+            PythonExit::Crashed => "crashed",
+        }
+    }
+
+    fn recoverable(&self) -> bool {
+        matches!(self, PythonExit::OutOfMemory | PythonExit::Crashed)
+    }
+}
+
+/// Restart delay before attempt `attempt` (1-based): 2 s doubling to 60 s.
+#[cfg(not(feature = "vision"))]
+fn restart_backoff(attempt: u32) -> std::time::Duration {
+    std::time::Duration::from_secs((2u64 << attempt.saturating_sub(1).min(5)).min(60))
+}
+
+/// A run this long resets the restart count: the next crash is a new problem.
+#[cfg(not(feature = "vision"))]
+const STABLE_RUN_MS: u64 = 5 * 60 * 1000;
+#[cfg(not(feature = "vision"))]
+const DEFAULT_MAX_RESTARTS: u32 = 5;
+#[cfg(not(feature = "vision"))]
+const SUPERVISE_POLL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// `broxeen:motion_pipeline_state` — `state` is "started", "crashed",
+/// "restarting" or "gave_up".
+#[cfg(not(feature = "vision"))]
+fn emit_pipeline_state(
+    app: &tauri::AppHandle,
+    camera_id: &str,
+    state: &str,
+    restart_count: u32,
+    reason: Option<&str>,
+    delay_ms: Option<u64>,
+) {
+    use tauri::Emitter;
+    let _ = app.emit("broxeen:motion_pipeline_state", serde_json::json!({
+        "camera_id": camera_id,
+        "state": state,
+        "restart_count": restart_count,
+        "reason": reason,
+        "delay_ms": delay_ms,
+    }));
+}
+
+/// Is `generation` still the camera's current supervision (not stopped or restarted by the user)?
+#[cfg(not(feature = "vision"))]
+fn supervision_current(camera_id: &str, generation: u64) -> bool {
+    SUPERVISED
+        .lock()
+        .map(|s| s.get(camera_id).is_some_and(|s| s.generation == generation))
+        .unwrap_or(false)
+}
+
+/// Watch a camera's `motion_pipeline.py` and restart it after a crash with
+/// exponential backoff, up to `max_restarts` times; import errors and
+/// rejected RTSP credentials end supervision at once.
+#[cfg(not(feature = "vision"))]
+fn supervise_python(app: tauri::AppHandle, camera_id: String, generation: u64) {
+    loop {
+        thread::sleep(SUPERVISE_POLL);
+        if !supervision_current(&camera_id, generation) {
+            return;
+        }
+        let exited = {
+            let mut pipelines = PIPELINES.lock().unwrap_or_else(|e| e.into_inner());
+            let status = match pipelines.get_mut(&camera_id) {
+                Some(p) => p.child.try_wait().ok().flatten(),
+                None => return,
+            };
+            status.and_then(|status| pipelines.remove(&camera_id).map(|p| (p, status)))
+        };
+        let Some((process, status)) = exited else { continue };
+
+        let (tail, last_line) = {
+            let progress = process.progress.lock().unwrap_or_else(|e| e.into_inner());
+            (progress.stderr_tail.iter().cloned().collect::<Vec<_>>(), progress.last_stderr.clone())
+        };
+        #[cfg(unix)]
+        let signal = std::os::unix::process::ExitStatusExt::signal(&status);
+        #[cfg(not(unix))]
+        let signal = None;
+        let kind = PythonExit::classify(&tail, signal);
+        let reason = match last_line {
+            Some(line) => format!("{}: motion_pipeline.py exited ({}): {}", kind.as_str(), status, line),
+            None => format!("{}: motion_pipeline.py exited ({})", kind.as_str(), status),
+        };
+        let stable = now_ms().saturating_sub(process.started_at) >= STABLE_RUN_MS;
+        drop(process);
+
+        let (request, attempt, max_restarts) = {
+            let mut supervised = SUPERVISED.lock().unwrap_or_else(|e| e.into_inner());
+            let Some(sup) = supervised.get_mut(&camera_id).filter(|s| s.generation == generation) else { return };
+            if stable {
+                sup.restart_count = 0;
+            }
+            sup.last_exit_reason = Some(reason.clone());
+            let max_restarts = sup.request.python.max_restarts.unwrap_or(DEFAULT_MAX_RESTARTS);
+            (sup.request.clone(), sup.restart_count + 1, max_restarts)
+        };
+        backend_warn(format!("Motion pipeline for camera {} exited: {}", camera_id, reason));
+        emit_pipeline_state(&app, &camera_id, "crashed", attempt - 1, Some(&reason), None);
+
+        if !kind.recoverable() || attempt > max_restarts {
+            backend_error(format!(
+                "Motion pipeline for camera {}: giving up after {} restart(s) ({})",
+                camera_id, attempt - 1, kind.as_str()
+            ));
+            SUPERVISED.lock().unwrap_or_else(|e| e.into_inner()).remove(&camera_id);
+            record_failure(&camera_id, reason.clone());
+            crate::sounds::play_notification(crate::sounds::NotificationKind::Error);
+            emit_pipeline_state(&app, &camera_id, "gave_up", attempt - 1, Some(&reason), None);
+            return;
+        }
+
+        let delay = restart_backoff(attempt);
+        if let Some(sup) = SUPERVISED.lock().unwrap_or_else(|e| e.into_inner()).get_mut(&camera_id) {
+            sup.restart_count = attempt;
+        }
+        backend_info(format!(
+            "Restarting motion pipeline for camera {} in {}s (attempt {}/{})",
+            camera_id, delay.as_secs(), attempt, max_restarts
+        ));
+        emit_pipeline_state(&app, &camera_id, "restarting", attempt, Some(&reason), Some(delay.as_millis() as u64));
+        thread::sleep(delay);
+        if !supervision_current(&camera_id, generation) {
+            return;
+        }
+
+        match spawn_python(&app, &request) {
+            Ok(mut process) => {
+                // Same lock order as motion_pipeline_start: SUPERVISED, then PIPELINES
+                let supervised = SUPERVISED.lock().unwrap_or_else(|e| e.into_inner());
+                if supervised.get(&camera_id).is_none_or(|s| s.generation != generation) {
+                    drop(supervised);
+                    let _ = process.child.kill();
+                    let _ = process.child.wait();
+                    return;
+                }
+                PIPELINES.lock().unwrap_or_else(|e| e.into_inner()).insert(camera_id.clone(), process);
+                drop(supervised);
+                emit_pipeline_state(&app, &camera_id, "started", attempt, None, None);
+            }
+            Err(e) => {
+                // Nothing to watch: give up like a non-recoverable exit
+                let reason = format!("crashed: restart failed: {}", e);
+                SUPERVISED.lock().unwrap_or_else(|e| e.into_inner()).remove(&camera_id);
+                record_failure(&camera_id, reason.clone());
+                emit_pipeline_state(&app, &camera_id, "gave_up", attempt, Some(&reason), None);
+                return;
+            }
+        }
+    }
 }
 
 #[cfg(feature = "vision")]
 #[tauri::command]
-pub async fn motion_pipeline_stop(camera_id: String) -> Result<String, String> {
+pub async fn motion_pipeline_stop(camera_id: String) -> Result<String, BroxeenError> {
     let mut pipelines = PIPELINES_NATIVE.lock().map_err(|e| e.to_string())?;
 
     if let Some(native) = pipelines.remove(&camera_id) {
@@ -403,13 +983,15 @@ pub async fn motion_pipeline_stop(camera_id: String) -> Result<String, String> {
         backend_info(format!("Native vision pipeline stopped for camera: {}", camera_id));
         Ok(format!("Pipeline stopped for camera: {}", camera_id))
     } else {
-        Err(format!("No active pipeline for camera: {}", camera_id))
+        Err(no_pipeline(&camera_id))
     }
 }
 
 #[cfg(not(feature = "vision"))]
 #[tauri::command]
-pub async fn motion_pipeline_stop(camera_id: String) -> Result<String, String> {
+pub async fn motion_pipeline_stop(camera_id: String) -> Result<String, BroxeenError> {
+    // Supervision first, so the supervisor doesn't restart what we kill
+    let supervised = SUPERVISED.lock().map_err(|e| e.to_string())?.remove(&camera_id);
     let mut pipelines = PIPELINES.lock().map_err(|e| e.to_string())?;
 
     if let Some(mut process) = pipelines.remove(&camera_id) {
@@ -418,23 +1000,194 @@ pub async fn motion_pipeline_stop(camera_id: String) -> Result<String, String> {
         let _ = process.child.wait();
         backend_info(format!("Motion pipeline stopped for camera: {}", camera_id));
         Ok(format!("Pipeline stopped for camera: {}", camera_id))
+    } else if supervised.is_some() {
+        backend_info(format!("Motion pipeline for camera {} stopped while waiting to restart", camera_id));
+        Ok(format!("Pipeline stopped for camera: {}", camera_id))
     } else {
-        Err(format!("No active pipeline for camera: {}", camera_id))
+        Err(no_pipeline(&camera_id))
+    }
+}
+
+fn already_running(camera_id: &str) -> BroxeenError {
+    BroxeenError::new(ErrorCode::Busy, format!("Pipeline already running for camera: {}", camera_id))
+}
+
+fn no_pipeline(camera_id: &str) -> BroxeenError {
+    BroxeenError::not_found(format!("No active pipeline for camera: {}", camera_id))
+}
+
+/// RTSP pre-flight before a pipeline is registered. The message starts with
+/// `rtsp_unreachable:`, `rtsp_auth_required:` or `rtsp_wrong_path:`.
+async fn preflight(camera_id: &str, rtsp_url: &str) -> Result<(), BroxeenError> {
+    crate::network_scan::rtsp_preflight(rtsp_url).await.map_err(|e| {
+        backend_warn(format!("motion_pipeline_start[{}]: pre-flight failed: {}", camera_id, e));
+        BroxeenError::from(e)
+    })
+}
+
+/// Health of a camera that has no registered pipeline.
+fn stopped_health(camera_id: String) -> Result<PipelineHealth, BroxeenError> {
+    match last_failure(&camera_id) {
+        Some(reason) => Ok(PipelineHealth {
+            camera_id,
+            running: false,
+            frames_flowing: false,
+            last_frame_at: None,
+            frames_per_second: None,
+            last_error: Some(reason),
+        }),
+        None => Err(no_pipeline(&camera_id)),
     }
 }
 
 #[cfg(feature = "vision")]
 #[tauri::command]
-pub async fn motion_pipeline_status() -> Result<PipelineListResult, String> {
+pub async fn motion_pipeline_health(camera_id: String) -> Result<PipelineHealth, BroxeenError> {
+    {
+        let pipelines = PIPELINES_NATIVE.lock().map_err(|e| e.to_string())?;
+        if let Some(native) = pipelines.get(&camera_id) {
+            let stats = native.handle.stats.snapshot();
+            let failure = native.handle.failure();
+            return Ok(PipelineHealth {
+                camera_id,
+                running: failure.is_none(),
+                frames_flowing: failure.is_none() && frames_flowing(stats.last_frame_at, now_ms(), FRAME_STALL_MS),
+                last_frame_at: stats.last_frame_at,
+                frames_per_second: Some(stats.frames_per_second),
+                last_error: failure,
+            });
+        }
+    }
+    stopped_health(camera_id)
+}
+
+#[cfg(not(feature = "vision"))]
+#[tauri::command]
+pub async fn motion_pipeline_health(camera_id: String) -> Result<PipelineHealth, BroxeenError> {
+    let last_exit = SUPERVISED
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(&camera_id)
+        .map(|s| s.last_exit_reason.clone());
+    {
+        let mut pipelines = PIPELINES.lock().map_err(|e| e.to_string())?;
+        if let Some(p) = pipelines.get_mut(&camera_id) {
+            // Exited but not yet seen by the supervisor
+            let alive = matches!(p.child.try_wait(), Ok(None));
+            let progress = p.progress.lock().unwrap_or_else(|e| e.into_inner());
+            return Ok(PipelineHealth {
+                camera_id,
+                running: alive,
+                frames_flowing: alive && frames_flowing(progress.last_frame_at, now_ms(), p.stall_ms.max(FRAME_STALL_MS)),
+                last_frame_at: progress.last_frame_at,
+                frames_per_second: None,
+                last_error: last_exit.flatten(),
+            });
+        }
+    }
+    // Waiting out a restart backoff
+    if let Some(reason) = last_exit {
+        return Ok(PipelineHealth {
+            camera_id,
+            running: false,
+            frames_flowing: false,
+            last_frame_at: None,
+            frames_per_second: None,
+            last_error: reason,
+        });
+    }
+    stopped_health(camera_id)
+}
+
+/// Stop every pipeline on app exit and wait up to `grace` for the capture
+/// loops to return, so they release the stream and the database. A loop
+/// stuck in a blocking read cannot be killed; it is logged and left behind.
+/// Returns how many were running.
+#[cfg(feature = "vision")]
+pub fn stop_all_pipelines(grace: std::time::Duration) -> usize {
+    let drained: Vec<NativePipeline> = match PIPELINES_NATIVE.lock() {
+        Ok(mut pipelines) => pipelines.drain().map(|(_, p)| p).collect(),
+        Err(_) => return 0,
+    };
+    for native in &drained {
+        native.handle.stop();
+    }
+
+    let deadline = std::time::Instant::now() + grace;
+    while drained.iter().any(|n| !n.handle.has_exited()) && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    for native in drained.iter().filter(|n| !n.handle.has_exited()) {
+        backend_warn(format!(
+            "Motion pipeline {} did not stop within {:?}",
+            native.handle.camera_id, grace
+        ));
+    }
+    drained.len()
+}
+
+/// Stop every pipeline on app exit: SIGTERM so `motion_pipeline.py` can
+/// close its database, then SIGKILL whatever is still alive after `grace`.
+#[cfg(not(feature = "vision"))]
+pub fn stop_all_pipelines(grace: std::time::Duration) -> usize {
+    if let Ok(mut supervised) = SUPERVISED.lock() {
+        supervised.clear();
+    }
+    let mut drained: Vec<PipelineProcess> = match PIPELINES.lock() {
+        Ok(mut pipelines) => pipelines.drain().map(|(_, p)| p).collect(),
+        Err(_) => return 0,
+    };
+    for process in &drained {
+        crate::shutdown::signal(process.child.id(), false);
+    }
+
+    let deadline = std::time::Instant::now() + grace;
+    for process in &mut drained {
+        while matches!(process.child.try_wait(), Ok(None)) && std::time::Instant::now() < deadline {
+            thread::sleep(std::time::Duration::from_millis(100));
+        }
+        if matches!(process.child.try_wait(), Ok(None)) {
+            backend_warn(format!("Motion pipeline {} ignored SIGTERM, killing", process.camera_id));
+            let _ = process.child.kill();
+            let _ = process.child.wait();
+        }
+    }
+    drained.len()
+}
+
+#[cfg(feature = "vision")]
+#[tauri::command]
+pub async fn motion_pipeline_status() -> Result<PipelineListResult, BroxeenError> {
     let pipelines = PIPELINES_NATIVE.lock().map_err(|e| e.to_string())?;
 
     let statuses: Vec<PipelineStatus> = pipelines
         .values()
-        .map(|n| PipelineStatus {
-            camera_id: n.handle.camera_id.clone(),
-            rtsp_url: crate::network_scan::anonymize_rtsp_url(&n.handle.rtsp_url),
-            started_at: n.handle.started_at,
-            running: true,
+        .map(|n| {
+            let stats = n.handle.stats.snapshot();
+            PipelineStatus {
+                camera_id: n.handle.camera_id.clone(),
+                rtsp_url: crate::network_scan::anonymize_rtsp_url(&n.handle.rtsp_url),
+                started_at: n.handle.started_at,
+                running: n.handle.failure().is_none(),
+                degraded: n.handle.degraded(),
+                frames_captured: Some(stats.frames_captured),
+                frames_processed: Some(stats.frames_processed),
+                motion_events: Some(stats.motion_events),
+                detections_saved: Some(stats.detections_saved),
+                detections_filtered: Some(stats.detections_filtered),
+                llm_calls: Some(stats.llm_calls),
+                llm_batched: Some(stats.llm_batched),
+                llm_skipped: Some(stats.llm_skipped),
+                channel_drops: Some(stats.channel_drops),
+                webhooks_delivered: Some(stats.webhooks_delivered),
+                webhooks_failed: Some(stats.webhooks_failed),
+                last_frame_at: stats.last_frame_at,
+                frames_per_second: Some(stats.frames_per_second),
+                execution_provider: stats.detector.as_ref().map(|d| d.execution_provider.clone()),
+                inference_ms_ema: stats.detector.as_ref().and_then(|d| d.inference_ms_ema),
+                restart_count: 0,
+                last_exit_reason: n.handle.failure(),
+            }
         })
         .collect();
 
@@ -447,34 +1200,44 @@ pub async fn motion_pipeline_status() -> Result<PipelineListResult, String> {
 
 #[cfg(not(feature = "vision"))]
 #[tauri::command]
-pub async fn motion_pipeline_status() -> Result<PipelineListResult, String> {
+pub async fn motion_pipeline_status() -> Result<PipelineListResult, BroxeenError> {
+    let supervised = SUPERVISED.lock().map_err(|e| e.to_string())?;
     let mut pipelines = PIPELINES.lock().map_err(|e| e.to_string())?;
 
-    // Reap any finished processes
-    let dead_keys: Vec<String> = pipelines
-        .iter_mut()
-        .filter_map(|(k, p)| {
-            match p.child.try_wait() {
-                Ok(Some(_)) => Some(k.clone()),
-                _ => None,
+    // Supervised cameras include those waiting out a restart backoff
+    let mut statuses: Vec<PipelineStatus> = supervised
+        .iter()
+        .map(|(camera_id, sup)| {
+            let process = pipelines.get_mut(camera_id);
+            let started_at = process.as_ref().map_or(0, |p| p.started_at);
+            let running = process.is_some_and(|p| matches!(p.child.try_wait(), Ok(None)));
+            PipelineStatus {
+                camera_id: camera_id.clone(),
+                rtsp_url: crate::network_scan::anonymize_rtsp_url(&sup.request.core.rtsp_url),
+                started_at,
+                running,
+                degraded: false,
+                frames_captured: None,
+                frames_processed: None,
+                motion_events: None,
+                detections_saved: None,
+                detections_filtered: None,
+                llm_calls: None,
+                llm_batched: None,
+                llm_skipped: None,
+                channel_drops: None,
+                webhooks_delivered: None,
+                webhooks_failed: None,
+                last_frame_at: None,
+                frames_per_second: None,
+                execution_provider: None,
+                inference_ms_ema: None,
+                restart_count: sup.restart_count,
+                last_exit_reason: sup.last_exit_reason.clone(),
             }
         })
         .collect();
-
-    for k in &dead_keys {
-        backend_warn(format!("Motion pipeline for camera {} exited unexpectedly", k));
-        pipelines.remove(k);
-    }
-
-    let statuses: Vec<PipelineStatus> = pipelines
-        .values()
-        .map(|p| PipelineStatus {
-            camera_id: p.camera_id.clone(),
-            rtsp_url: crate::network_scan::anonymize_rtsp_url(&p.rtsp_url),
-            started_at: p.started_at,
-            running: true,
-        })
-        .collect();
+    statuses.sort_by(|a, b| a.camera_id.cmp(&b.camera_id));
 
     let count = statuses.len();
     Ok(PipelineListResult {
@@ -483,18 +1246,256 @@ pub async fn motion_pipeline_status() -> Result<PipelineListResult, String> {
     })
 }
 
+/// Replace polygon zones of a running native pipeline (no restart needed).
+#[cfg(feature = "vision")]
+#[tauri::command]
+pub async fn vision_zones_set(
+    camera_id: String,
+    zones: Vec<crate::vision_zones::Zone>,
+) -> Result<usize, String> {
+    crate::vision_zones::validate_zones(&zones)?;
+    let pipelines = PIPELINES_NATIVE.lock().map_err(|e| e.to_string())?;
+    let native = pipelines
+        .get(&camera_id)
+        .ok_or_else(|| format!("No active pipeline for camera: {}", camera_id))?;
+    let count = zones.len();
+    native.handle.set_zones(zones);
+    backend_info(format!("vision_zones_set: {} zone(s) for camera {}", count, camera_id));
+    Ok(count)
+}
+
+#[cfg(not(feature = "vision"))]
+#[tauri::command]
+pub async fn vision_zones_set(
+    camera_id: String,
+    zones: Vec<crate::vision_zones::Zone>,
+) -> Result<usize, String> {
+    crate::vision_zones::validate_zones(&zones)?;
+    Err(format!(
+        "Zones need the native vision pipeline (build with --features vision); camera: {}",
+        camera_id
+    ))
+}
+
+/// Push live parameters changed in broxeen.toml (`old` → `new`) to every
+/// running native pipeline; returns how many were updated.
+#[cfg(feature = "vision")]
+pub fn reconfigure_native_pipelines(
+    old: &crate::vision_config::VisionConfig,
+    new: &crate::vision_config::VisionConfig,
+) -> usize {
+    let pipelines = PIPELINES_NATIVE.lock().unwrap_or_else(|e| e.into_inner());
+    let running: Vec<_> = pipelines.values().filter(|p| p.handle.failure().is_none()).collect();
+    for native in &running {
+        native.handle.reconfigure(old, new);
+    }
+    running.len()
+}
+
+/// Latest frame of a running native pipeline as JPEG, with the most recent
+/// detection boxes drawn on it.
+#[cfg(feature = "vision")]
+#[tauri::command]
+pub async fn vision_snapshot(camera_id: String) -> Result<crate::vision_pipeline::AnnotatedSnapshot, String> {
+    let tap = {
+        let pipelines = PIPELINES_NATIVE.lock().map_err(|e| e.to_string())?;
+        let native = pipelines
+            .get(&camera_id)
+            .filter(|p| p.handle.failure().is_none())
+            .ok_or_else(|| format!("pipeline not running: {}", camera_id))?;
+        native.handle.frame_tap()
+    };
+    let snapshot = tokio::task::spawn_blocking(move || tap.snapshot())
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+    backend_info(format!(
+        "vision_snapshot: camera {} ({} box(es), frame age {} ms)",
+        camera_id,
+        snapshot.boxes.len(),
+        snapshot.frame_age_ms
+    ));
+    Ok(snapshot)
+}
+
+#[cfg(not(feature = "vision"))]
+#[tauri::command]
+pub async fn vision_snapshot(camera_id: String) -> Result<serde_json::Value, String> {
+    Err(format!(
+        "Snapshots need the native vision pipeline (build with --features vision); camera: {}",
+        camera_id
+    ))
+}
+
+/// Execution provider, fallback and inference latency of a running native
+/// pipeline's detector.
+#[cfg(feature = "vision")]
+#[tauri::command]
+pub async fn vision_detector_info(camera_id: String) -> Result<crate::vision_detector::DetectorInfo, String> {
+    let pipelines = PIPELINES_NATIVE.lock().map_err(|e| e.to_string())?;
+    let native = pipelines
+        .get(&camera_id)
+        .filter(|p| p.handle.failure().is_none())
+        .ok_or_else(|| format!("pipeline not running: {}", camera_id))?;
+    native
+        .handle
+        .stats
+        .snapshot()
+        .detector
+        .ok_or_else(|| format!("detector for {} is still initialising", camera_id))
+}
+
+#[cfg(not(feature = "vision"))]
+#[tauri::command]
+pub async fn vision_detector_info(camera_id: String) -> Result<serde_json::Value, String> {
+    Err(format!(
+        "Detector info needs the native vision pipeline (build with --features vision); camera: {}",
+        camera_id
+    ))
+}
+
+/// Write the thumbnails of matching detections into `output_dir` and, with
+/// `contact_sheet`, tile the first `contact_sheet_max` (default 100) into one
+/// captioned JPEG. `since` / `until` take the forms of
+/// `LocalClock::parse_instant` in the DB's zone.
+#[cfg(feature = "vision")]
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn vision_thumbnails_export(
+    db_path: Option<String>,
+    camera_id: Option<String>,
+    label: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    min_confidence: Option<f32>,
+    output_dir: String,
+    contact_sheet: Option<String>,
+    contact_sheet_max: Option<usize>,
+) -> Result<crate::vision_db::ThumbnailExport, String> {
+    let resolved = resolve_db_path(&db_path.unwrap_or_else(|| "monitoring.db".to_string()));
+    let db = crate::vision_db::VisionDatabase::shared(&resolved)
+        .map_err(|e| format!("Cannot open detections DB at {}: {}", resolved, e))?;
+    let clock = crate::local_time::LocalClock::now(db.timezone());
+    let bound = |raw: Option<String>| {
+        raw.filter(|s| !s.trim().is_empty()).map(|s| clock.parse_instant(&s)).transpose()
+    };
+    let filter = crate::vision_db::ThumbnailFilter {
+        camera_id: camera_id.filter(|c| !c.is_empty()),
+        label: label.filter(|l| !l.is_empty()),
+        since: bound(since)?,
+        until: bound(until)?,
+        min_confidence,
+    };
+    backend_info(format!(
+        "vision_thumbnails_export: db={} {:?} → {} (sheet: {:?})",
+        resolved, filter, output_dir, contact_sheet
+    ));
+
+    let result = tokio::task::spawn_blocking(move || {
+        let sheet = contact_sheet.as_deref().map(|p| (std::path::Path::new(p), contact_sheet_max.unwrap_or(100).max(1)));
+        db.export_thumbnails(&filter, std::path::Path::new(&output_dir), sheet)
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?
+    .map_err(|e| format!("Thumbnail export failed: {}", e))?;
+
+    backend_info(format!(
+        "vision_thumbnails_export done: {} file(s), {} bytes, sheet {:?} ({} tiles)",
+        result.exported, result.bytes, result.contact_sheet, result.sheet_tiles
+    ));
+    Ok(result)
+}
+
+#[cfg(not(feature = "vision"))]
+#[tauri::command]
+pub async fn vision_thumbnails_export(output_dir: String) -> Result<serde_json::Value, String> {
+    Err(format!(
+        "Thumbnail export needs the native vision pipeline (build with --features vision); output: {}",
+        output_dir
+    ))
+}
+
+/// Where detections of `camera_id` happened in the frame over the last
+/// `hours` (default 24), binned server-side into a `grid_w`×`grid_h`
+/// (default 32×18, at most 256×256) matrix normalised to the busiest cell.
+#[cfg(feature = "vision")]
+#[tauri::command]
+pub async fn vision_heatmap(
+    db_path: Option<String>,
+    camera_id: String,
+    hours: Option<u32>,
+    grid_w: Option<u32>,
+    grid_h: Option<u32>,
+    label: Option<String>,
+) -> Result<crate::vision_db::Heatmap, String> {
+    let hours = hours.unwrap_or(24);
+    let (grid_w, grid_h) = (grid_w.unwrap_or(32).clamp(1, 256), grid_h.unwrap_or(18).clamp(1, 256));
+    let resolved = resolve_db_path(&db_path.unwrap_or_else(|| "monitoring.db".to_string()));
+    backend_info(format!(
+        "vision_heatmap: db={} camera={} label={:?} hours={} grid={}x{}",
+        resolved, camera_id, label, hours, grid_w, grid_h
+    ));
+
+    tokio::task::spawn_blocking(move || {
+        let db = crate::vision_db::VisionDatabase::shared(&resolved)?;
+        db.heatmap(&camera_id, label.as_deref(), hours, grid_w, grid_h)
+    })
+    .await
+    .map_err(|e| format!("Heatmap task failed: {}", e))?
+    .map_err(|e| format!("Heatmap query failed: {}", e))
+}
+
+#[cfg(not(feature = "vision"))]
+#[tauri::command]
+pub async fn vision_heatmap(camera_id: String) -> Result<serde_json::Value, String> {
+    Err(format!(
+        "Detection heatmap needs the native vision pipeline (build with --features vision); camera: {}",
+        camera_id
+    ))
+}
+
+/// Most recent automatic clips (newest first), optionally for one camera —
+/// the clip entries of the timeline.
+#[cfg(feature = "vision")]
+#[tauri::command]
+pub async fn vision_clips_list(
+    db_path: Option<String>,
+    camera_id: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<crate::vision_db::ClipRecord>, String> {
+    let limit = limit.unwrap_or(50).clamp(1, 500);
+    let resolved = resolve_db_path(&db_path.unwrap_or_else(|| "monitoring.db".to_string()));
+    backend_info(format!("vision_clips_list: db={} camera={:?} limit={}", resolved, camera_id, limit));
+
+    tokio::task::spawn_blocking(move || {
+        let db = crate::vision_db::VisionDatabase::shared(&resolved)?;
+        db.get_recent_clips(camera_id.as_deref(), limit)
+    })
+    .await
+    .map_err(|e| format!("Clip list task failed: {}", e))?
+    .map_err(|e| format!("Clip list query failed: {}", e))
+}
+
+#[cfg(not(feature = "vision"))]
+#[tauri::command]
+pub async fn vision_clips_list(camera_id: Option<String>) -> Result<serde_json::Value, String> {
+    Err(format!(
+        "Clips need the native vision pipeline (build with --features vision); camera: {:?}",
+        camera_id
+    ))
+}
+
 #[tauri::command]
 pub async fn motion_pipeline_stats(
     db_path: String,
     camera_id: Option<String>,
     hours: Option<u32>,
-) -> Result<DetectionStats, String> {
+) -> Result<DetectionStats, BroxeenError> {
     let db = resolve_db_path(&db_path);
     let hours = hours.unwrap_or(24);
 
-    let conn = rusqlite::Connection::open(&db).map_err(|e| {
-        format!("Cannot open detections DB at {}: {}", db, e)
-    })?;
+    let conn = open_monitoring_db(&db)
+        .map_err(|e| BroxeenError::from(e).context(format_args!("Cannot open detections DB at {}", db)))?;
 
     let where_base = format!("timestamp > datetime('now', '-{} hours')", hours);
     let where_clause = if let Some(ref cam) = camera_id {
@@ -528,11 +1529,9 @@ pub async fn motion_pipeline_stats(
             .prepare(&format!(
                 "SELECT label, COUNT(*) FROM detections WHERE {} GROUP BY label ORDER BY 2 DESC",
                 where_clause
-            ))
-            .map_err(|e| e.to_string())?;
+            ))?;
         let rows = stmt
-            .query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)))
-            .map_err(|e| e.to_string())?;
+            .query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)))?;
         for row in rows.flatten() {
             by_class.insert(row.0, row.1);
         }
@@ -544,11 +1543,9 @@ pub async fn motion_pipeline_stats(
             .prepare(&format!(
                 "SELECT strftime('%H', timestamp), COUNT(*) FROM detections WHERE {} GROUP BY 1",
                 where_clause
-            ))
-            .map_err(|e| e.to_string())?;
+            ))?;
         let rows = stmt
-            .query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)))
-            .map_err(|e| e.to_string())?;
+            .query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)))?;
         for row in rows.flatten() {
             by_hour.insert(row.0, row.1);
         }
@@ -582,6 +1579,57 @@ pub async fn motion_pipeline_stats(
     })
 }
 
+/// Filters of `motion_pipeline_detections`, bound as SQL parameters.
+#[derive(Debug, Clone, PartialEq)]
+struct DetectionsQuery {
+    camera_id: Option<String>,
+    label: Option<String>,
+    since: chrono::DateTime<chrono::Utc>,
+    /// Exclusive
+    until: Option<chrono::DateTime<chrono::Utc>>,
+    limit: u32,
+    include_thumbnails: bool,
+}
+
+impl DetectionsQuery {
+    /// Newest first; `datetime(timestamp)` so ISO and SQLite-style stamps compare alike.
+    fn to_sql(&self) -> (String, Vec<rusqlite::types::Value>) {
+        use crate::local_time::sqlite_utc;
+        use rusqlite::types::Value;
+        let mut conditions = vec!["datetime(timestamp) >= ?1".to_string()];
+        let mut values = vec![Value::Text(sqlite_utc(self.since))];
+        let mut push = |condition: &str, value: Value| {
+            values.push(value);
+            conditions.push(format!("{condition} ?{}", values.len()));
+        };
+        if let Some(until) = self.until {
+            push("datetime(timestamp) <", Value::Text(sqlite_utc(until)));
+        }
+        if let Some(cam) = &self.camera_id {
+            push("camera_id =", Value::Text(cam.clone()));
+        }
+        if let Some(label) = &self.label {
+            push("label =", Value::Text(label.clone()));
+        }
+        values.push(Value::Integer(self.limit as i64));
+
+        let thumb_col = if self.include_thumbnails { "thumbnail" } else { "NULL as thumbnail" };
+        let sql = format!(
+            "SELECT id, timestamp, camera_id, label, confidence, llm_label, llm_description, \
+             bbox_x1, bbox_y1, bbox_x2, bbox_y2, area, sent_to_llm, {} \
+             FROM detections WHERE {} ORDER BY timestamp DESC LIMIT ?{}",
+            thumb_col,
+            conditions.join(" AND "),
+            values.len()
+        );
+        (sql, values)
+    }
+}
+
+/// Newest detections. `since` / `until` take the forms of
+/// `LocalClock::parse_instant` ("2h", "yesterday", "2024-06-12", RFC 3339)
+/// in the DB's zone; without `since` the last `hours` (default 24) are listed.
+#[allow(clippy::too_many_arguments)]
 #[tauri::command]
 pub async fn motion_pipeline_detections(
     db_path: String,
@@ -590,36 +1638,38 @@ pub async fn motion_pipeline_detections(
     hours: Option<u32>,
     limit: Option<u32>,
     include_thumbnails: Option<bool>,
-) -> Result<Vec<DetectionRow>, String> {
+    since: Option<String>,
+    until: Option<String>,
+) -> Result<Vec<DetectionRow>, BroxeenError> {
     let db = resolve_db_path(&db_path);
-    let hours = hours.unwrap_or(24);
-    let limit = limit.unwrap_or(50);
-    let include_thumbs = include_thumbnails.unwrap_or(false);
-
-    let conn = rusqlite::Connection::open(&db).map_err(|e| {
-        format!("Cannot open detections DB at {}: {}", db, e)
-    })?;
 
-    let mut conditions = vec![format!("timestamp > datetime('now', '-{} hours')", hours)];
-    if let Some(ref cam) = camera_id {
-        conditions.push(format!("camera_id = '{}'", cam.replace('\'', "''")));
-    }
-    if let Some(ref lbl) = label {
-        conditions.push(format!("label = '{}'", lbl.replace('\'', "''")));
-    }
-    let where_clause = conditions.join(" AND ");
+    let conn = open_monitoring_db(&db)
+        .map_err(|e| BroxeenError::from(e).context(format_args!("Cannot open detections DB at {}", db)))?;
 
-    let thumb_col = if include_thumbs { "thumbnail" } else { "NULL as thumbnail" };
-    let sql = format!(
-        "SELECT id, timestamp, camera_id, label, confidence, llm_label, llm_description, \
-         bbox_x1, bbox_y1, bbox_x2, bbox_y2, area, sent_to_llm, {} \
-         FROM detections WHERE {} ORDER BY timestamp DESC LIMIT {}",
-        thumb_col, where_clause, limit
-    );
+    let clock = crate::local_time::LocalClock::now(crate::local_time::of_db(&conn));
+    let bound = |raw: Option<String>| {
+        raw.filter(|s| !s.trim().is_empty())
+            .map(|s| clock.parse_instant(&s))
+            .transpose()
+            .map_err(BroxeenError::invalid_input)
+    };
+    let since = match bound(since)? {
+        Some(since) => since,
+        None => clock.now - chrono::Duration::hours(hours.unwrap_or(24) as i64),
+    };
+    let query = DetectionsQuery {
+        camera_id: camera_id.filter(|c| !c.is_empty()),
+        label: label.map(|l| l.trim().to_lowercase()).filter(|l| !l.is_empty()),
+        since,
+        until: bound(until)?,
+        limit: limit.unwrap_or(50),
+        include_thumbnails: include_thumbnails.unwrap_or(false),
+    };
+    let (sql, values) = query.to_sql();
 
-    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(&sql)?;
     let rows = stmt
-        .query_map([], |r| {
+        .query_map(rusqlite::params_from_iter(values), |r| {
             let thumb_bytes: Option<Vec<u8>> = r.get(13).ok();
             Ok(DetectionRow {
                 id: r.get(0)?,
@@ -639,12 +1689,11 @@ pub async fn motion_pipeline_detections(
                     &base64::engine::general_purpose::STANDARD, b
                 )),
             })
-        })
-        .map_err(|e| e.to_string())?;
+        })?;
 
     let mut result = Vec::new();
     for row in rows {
-        result.push(row.map_err(|e| e.to_string())?);
+        result.push(row?);
     }
 
     Ok(result)
@@ -660,8 +1709,40 @@ pub struct VisionQueryResult {
     pub rows: Vec<Vec<String>>,
     pub row_count: usize,
     pub source: String,
+    /// Per-row base64 JPEG (≤128px) in `inline_small` mode.
+    /// `None` entries are over the payload budget — resolve via `thumbnail_id`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnails: Option<Vec<Option<String>>>,
+}
+
+/// How `vision_query` attaches detection thumbnails to result rows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThumbnailMode {
+    /// Text rows only (default)
+    None,
+    /// Append a `thumbnail_id` column resolvable via `vision_get_thumbnail`
+    Refs,
+    /// Refs + small inline JPEGs, capped at `INLINE_THUMB_BUDGET_BYTES`
+    InlineSmall,
 }
 
+impl ThumbnailMode {
+    pub fn parse(mode: Option<&str>) -> Result<Self, String> {
+        match mode.map(|m| m.trim().to_lowercase()).as_deref() {
+            None | Some("") | Some("none") => Ok(ThumbnailMode::None),
+            Some("refs") => Ok(ThumbnailMode::Refs),
+            Some("inline_small") => Ok(ThumbnailMode::InlineSmall),
+            Some(other) => Err(format!(
+                "Unknown include_thumbnails mode: {} (expected none | refs | inline_small)",
+                other
+            )),
+        }
+    }
+}
+
+const INLINE_THUMB_MAX_PX: u32 = 128;
+const INLINE_THUMB_BUDGET_BYTES: usize = 256 * 1024;
+
 /// Natural language → SQL → real DB results.
 /// Strategy: LLM text-to-SQL (via llm_query module) → keyword fallback.
 /// The LLM approach uses schemas from query_schema.rs to generate correct SQL
@@ -670,6 +1751,20 @@ pub struct VisionQueryResult {
 pub async fn vision_query(
     question: String,
     db_path: Option<String>,
+    include_thumbnails: Option<String>,
+) -> Result<VisionQueryResult, String> {
+    let mode = ThumbnailMode::parse(include_thumbnails.as_deref())?;
+    let mut result = run_vision_query(question, db_path).await?;
+    if mode != ThumbnailMode::None {
+        let db = result.source.clone();
+        attach_thumbnails(&mut result, mode, &db);
+    }
+    Ok(result)
+}
+
+async fn run_vision_query(
+    question: String,
+    db_path: Option<String>,
 ) -> Result<VisionQueryResult, String> {
     let start = std::time::Instant::now();
 
@@ -691,6 +1786,7 @@ pub async fn vision_query(
                 rows: result.rows,
                 row_count: result.row_count,
                 source: result.db_path,
+                thumbnails: None,
             });
         }
         Err(e) => {
@@ -709,7 +1805,7 @@ pub async fn vision_query(
 
 async fn keyword_based_query(question: &str, db_path: &str) -> Result<VisionQueryResult, String> {
     // Open the DB directly with rusqlite (works with both old and new schema)
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| {
+    let conn = open_monitoring_db(db_path).map_err(|e| {
         format!("Cannot open monitoring DB at {}: {}", db_path, e)
     })?;
 
@@ -717,9 +1813,14 @@ async fn keyword_based_query(question: &str, db_path: &str) -> Result<VisionQuer
     let has_new_schema = conn
         .prepare("SELECT track_id FROM detections LIMIT 1")
         .is_ok();
+    let has_speed_mps = conn
+        .prepare("SELECT speed_mps FROM detections LIMIT 1")
+        .is_ok();
 
-    // Convert natural language to SQL using keyword matching
-    let sql = nl_to_sql(question, has_new_schema);
+    // Convert natural language to SQL using keyword matching; "today" is the
+    // day in the zone the DB records local dates in
+    let clock = crate::local_time::LocalClock::now(crate::local_time::of_db(&conn));
+    let sql = crate::query_nl::nl_to_sql(question, has_new_schema, has_speed_mps, &clock);
 
     // Execute the query
     let mut stmt = conn.prepare(&sql).map_err(|e| {
@@ -757,9 +1858,154 @@ async fn keyword_based_query(question: &str, db_path: &str) -> Result<VisionQuer
         rows,
         row_count,
         source: db_path.to_string(),
+        thumbnails: None,
     })
 }
 
+/// Append a `thumbnail_id` column (and inline thumbnails in `inline_small` mode)
+/// for result rows that reference a detection with a stored crop.
+fn attach_thumbnails(result: &mut VisionQueryResult, mode: ThumbnailMode, db_path: &str) {
+    let sql_lower = result.sql.to_lowercase();
+    if !sql_lower.contains("detections") && !sql_lower.contains("monitoring_history") {
+        return;
+    }
+    let Some(id_idx) = result.columns.iter().position(|c| c.eq_ignore_ascii_case("id")) else {
+        return;
+    };
+
+    let conn = match open_monitoring_db(db_path) {
+        Ok(c) => c,
+        Err(e) => {
+            backend_warn(format!("attach_thumbnails: cannot open {}: {}", db_path, e));
+            return;
+        }
+    };
+
+    let ids: Vec<Option<i64>> = result.rows.iter()
+        .map(|row| row.get(id_idx).and_then(|v| v.parse::<i64>().ok()))
+        .collect();
+
+    let mut with_thumb = std::collections::HashSet::new();
+    let id_list: Vec<String> = ids.iter().flatten().map(|i| i.to_string()).collect();
+    if !id_list.is_empty() {
+        let sql = format!(
+            "SELECT id FROM detections WHERE id IN ({}) AND thumbnail IS NOT NULL AND length(thumbnail) > 0",
+            id_list.join(",")
+        );
+        if let Ok(mut stmt) = conn.prepare(&sql) {
+            if let Ok(rows) = stmt.query_map([], |r| r.get::<_, i64>(0)) {
+                with_thumb.extend(rows.filter_map(|r| r.ok()));
+            }
+        }
+    }
+
+    result.columns.push("thumbnail_id".into());
+    for (row, id) in result.rows.iter_mut().zip(&ids) {
+        row.push(match id {
+            Some(id) if with_thumb.contains(id) => id.to_string(),
+            _ => "—".into(),
+        });
+    }
+
+    if mode != ThumbnailMode::InlineSmall {
+        return;
+    }
+
+    let mut budget = INLINE_THUMB_BUDGET_BYTES;
+    let thumbs = ids.iter().map(|id| {
+        let id = (*id).filter(|i| with_thumb.contains(i))?;
+        let blob: Vec<u8> = conn
+            .query_row("SELECT thumbnail FROM detections WHERE id=?1", [id], |r| r.get(0))
+            .ok()?;
+        let b64 = base64::Engine::encode(
+            &base64::engine::general_purpose::STANDARD,
+            downscale_jpeg(&blob, INLINE_THUMB_MAX_PX)?,
+        );
+        // Rows beyond the budget fall back to thumbnail_id refs
+        if b64.len() > budget {
+            budget = 0;
+            return None;
+        }
+        budget -= b64.len();
+        Some(b64)
+    }).collect();
+    result.thumbnails = Some(thumbs);
+}
+
+/// Re-encode a JPEG/WebP thumbnail as JPEG so that neither side exceeds `max_px`.
+fn downscale_jpeg(bytes: &[u8], max_px: u32) -> Option<Vec<u8>> {
+    let img = image::load_from_memory(bytes).ok()?;
+    let img = if img.width() > max_px || img.height() > max_px {
+        img.thumbnail(max_px, max_px)
+    } else {
+        img
+    };
+    let mut out = Vec::new();
+    image::DynamicImage::ImageRgb8(img.to_rgb8())
+        .write_to(&mut std::io::Cursor::new(&mut out), image::ImageFormat::Jpeg)
+        .ok()?;
+    Some(out)
+}
+
+/// Fetch a detection thumbnail by id as a `data:` URL: the stored bytes
+/// (JPEG or WebP per the row's `thumbnail_format`, sniffed for older rows),
+/// or a JPEG when downscaled to `max_px`.
+#[tauri::command]
+pub async fn vision_get_thumbnail(
+    id: i64,
+    db_path: Option<String>,
+    max_px: Option<u32>,
+) -> Result<String, String> {
+    let db_file = db_path.unwrap_or_else(|| "monitoring.db".to_string());
+    let resolved = resolve_db_path(&db_file);
+    let conn = open_monitoring_db(&resolved).map_err(|e| {
+        format!("Cannot open monitoring DB at {}: {}", resolved, e)
+    })?;
+
+    let (blob, stored_format) = stored_thumbnail(&conn, id)?;
+    let (bytes, format) = match max_px {
+        Some(px) => (
+            downscale_jpeg(&blob, px).ok_or("Failed to resize thumbnail")?,
+            ThumbnailFormat::Jpeg,
+        ),
+        None => (blob, stored_format),
+    };
+    Ok(format!(
+        "data:{};base64,{}",
+        format.mime_type(),
+        base64::Engine::encode(&base64::engine::general_purpose::STANDARD, bytes)
+    ))
+}
+
+/// Thumbnail bytes of a detection and their format. DBs from the Python
+/// pipeline, or not yet migrated by vision_db.rs, have no `thumbnail_format`
+/// column (the connection is read-only), so the bytes are sniffed instead.
+fn stored_thumbnail(conn: &rusqlite::Connection, id: i64) -> Result<(Vec<u8>, ThumbnailFormat), String> {
+    let has_format: bool = conn
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('detections') WHERE name = 'thumbnail_format'",
+            [],
+            |r| r.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let sql = if has_format {
+        "SELECT thumbnail, thumbnail_format FROM detections WHERE id=?1"
+    } else {
+        "SELECT thumbnail, NULL FROM detections WHERE id=?1"
+    };
+    let (blob, stored_format): (Option<Vec<u8>>, Option<String>) = conn
+        .query_row(sql, [id], |r| Ok((r.get(0)?, r.get(1)?)))
+        .map_err(|e| format!("Detection {} not found: {}", id, e))?;
+    let blob = blob.filter(|b| !b.is_empty())
+        .ok_or_else(|| format!("Detection {} has no thumbnail", id))?;
+    let format = match stored_format.as_deref() {
+        Some("webp") => ThumbnailFormat::Webp,
+        Some("jpeg") => ThumbnailFormat::Jpeg,
+        _ => ThumbnailFormat::detect(&blob).unwrap_or_default(),
+    };
+    Ok((blob, format))
+}
+
 /// Run a raw SQL SELECT on the monitoring DB (for advanced users / frontend).
 #[tauri::command]
 pub async fn vision_query_direct(
@@ -774,7 +2020,7 @@ pub async fn vision_query_direct(
     let db_file = db_path.unwrap_or_else(|| "monitoring.db".to_string());
     let resolved = resolve_db_path(&db_file);
 
-    let conn = rusqlite::Connection::open(&resolved).map_err(|e| {
+    let conn = open_monitoring_db(&resolved).map_err(|e| {
         format!("Cannot open monitoring DB at {}: {}", resolved, e)
     })?;
 
@@ -813,146 +2059,216 @@ pub async fn vision_query_direct(
         rows,
         row_count,
         source: resolved,
+        thumbnails: None,
     })
 }
 
-/// Keyword-based natural language → SQL converter (legacy fallback).
-/// Used only when LLM text-to-SQL is unavailable (no OPENROUTER_API_KEY).
-/// Delegates to generic regex extractors instead of hardcoded keyword lists.
-fn nl_to_sql(question: &str, _new_schema: bool) -> String {
-    let q = question.to_lowercase();
-    let time_filter = extract_time_filter(&q);
-    let label_filter = extract_label_filter(&q);
-    let limit = extract_limit(&q).unwrap_or(20);
-
-    // Counting query: "ile", "policz", "liczba", "count"
-    if q.contains("ile") || q.contains("policz") || q.contains("liczba") || q.contains("count") {
-        let lbl = if label_filter.is_empty() { String::new() } else { label_filter };
-        return format!(
-            "SELECT COUNT(*) as count, MIN(timestamp) as first_seen, MAX(timestamp) as last_seen \
-             FROM detections WHERE 1=1 {}{}", lbl, time_filter
-        );
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detections_query_binds_filters() {
+        let at = |s: &str| s.parse::<chrono::DateTime<chrono::Utc>>().unwrap();
+        let mut query = DetectionsQuery {
+            camera_id: None,
+            label: None,
+            since: at("2024-06-12T08:00:00Z"),
+            until: None,
+            limit: 20,
+            include_thumbnails: false,
+        };
+        let (sql, values) = query.to_sql();
+        assert!(sql.contains("WHERE datetime(timestamp) >= ?1 ORDER BY timestamp DESC LIMIT ?2"), "{sql}");
+        assert!(sql.contains("NULL as thumbnail"));
+        assert_eq!(values.len(), 2);
+
+        query.camera_id = Some("front'; DROP TABLE detections; --".to_string());
+        query.label = Some("person".to_string());
+        query.until = Some(at("2024-06-12T10:00:00Z"));
+        query.include_thumbnails = true;
+        let (sql, values) = query.to_sql();
+        assert!(sql.contains(
+            "datetime(timestamp) >= ?1 AND datetime(timestamp) < ?2 AND camera_id = ?3 AND label = ?4 \
+             ORDER BY timestamp DESC LIMIT ?5"
+        ), "{sql}");
+        assert!(!sql.contains("DROP"));
+        assert!(sql.contains(", thumbnail FROM"));
+        use rusqlite::types::Value;
+        assert_eq!(values, vec![
+            Value::Text("2024-06-12 08:00:00".into()),
+            Value::Text("2024-06-12 10:00:00".into()),
+            Value::Text("front'; DROP TABLE detections; --".into()),
+            Value::Text("person".into()),
+            Value::Integer(20),
+        ]);
+
+        // Run it: only the row inside the window and matching both filters
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE detections (id INTEGER PRIMARY KEY, timestamp TEXT, camera_id TEXT, label TEXT,
+               confidence REAL, llm_label TEXT, llm_description TEXT, bbox_x1 REAL, bbox_y1 REAL,
+               bbox_x2 REAL, bbox_y2 REAL, area REAL, sent_to_llm INTEGER, thumbnail BLOB);
+             INSERT INTO detections (timestamp, camera_id, label, sent_to_llm) VALUES
+               ('2024-06-12T09:00:00+00:00', 'gate', 'person', 0),
+               ('2024-06-12 09:30:00', 'gate', 'car', 0),
+               ('2024-06-12 11:00:00', 'gate', 'person', 0),
+               ('2024-06-12 07:00:00', 'gate', 'person', 0);",
+        )
+        .unwrap();
+        query.camera_id = Some("gate".to_string());
+        let (sql, values) = query.to_sql();
+        let ids: Vec<i64> = conn
+            .prepare(&sql)
+            .unwrap()
+            .query_map(rusqlite::params_from_iter(values), |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(ids, vec![1]);
     }
 
-    // Statistics: "statystyki", "podsumowanie", "summary", "stats"
-    if q.contains("statyst") || q.contains("podsumow") || q.contains("summary") || q.contains("stats") {
-        return format!(
-            "SELECT label, COUNT(*) as count, ROUND(AVG(confidence),2) as avg_conf, \
-             MIN(timestamp) as first, MAX(timestamp) as last \
-             FROM detections WHERE 1=1 {}{} GROUP BY label ORDER BY count DESC",
-            label_filter, time_filter
-        );
+    #[cfg(not(feature = "vision"))]
+    #[test]
+    fn test_python_exit_classification_and_backoff() {
+        let tail = |lines: &[&str]| lines.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        let import = tail(&["Traceback (most recent call last):", "ModuleNotFoundError: No module named 'ultralytics'"]);
+        assert_eq!(PythonExit::classify(&import, None), PythonExit::ImportError);
+        let auth = tail(&["[rtsp @ 0x55] method DESCRIBE failed: 401 Unauthorized", "ERROR: cannot open stream"]);
+        assert_eq!(PythonExit::classify(&auth, None), PythonExit::RtspAuth);
+        assert_eq!(PythonExit::classify(&tail(&["MemoryError"]), None), PythonExit::OutOfMemory);
+        assert_eq!(PythonExit::classify(&[], Some(9)), PythonExit::OutOfMemory);
+        assert_eq!(PythonExit::classify(&tail(&["Segmentation fault"]), Some(11)), PythonExit::Crashed);
+
+        assert!(!PythonExit::ImportError.recoverable() && !PythonExit::RtspAuth.recoverable());
+        assert!(PythonExit::OutOfMemory.recoverable() && PythonExit::Crashed.recoverable());
+
+        let secs: Vec<u64> = (1..=8).map(|a| restart_backoff(a).as_secs()).collect();
+        assert_eq!(secs, vec![2, 4, 8, 16, 32, 60, 60, 60]);
     }
 
-    // Camera breakdown: "kamera", "camera"
-    if q.contains("kamer") || q.contains("camera") {
-        return format!(
-            "SELECT camera_id, COUNT(*) as detections, \
-             MIN(timestamp) as first, MAX(timestamp) as last \
-             FROM detections WHERE 1=1 {}{} GROUP BY camera_id ORDER BY detections DESC",
-            label_filter, time_filter
-        );
+    #[test]
+    fn test_frames_flowing_window() {
+        assert!(!frames_flowing(None, 100_000, FRAME_STALL_MS));
+        assert!(frames_flowing(Some(90_000), 100_000, FRAME_STALL_MS));
+        assert!(!frames_flowing(Some(80_000), 100_000, FRAME_STALL_MS));
+        // Clock skew: a frame "from the future" still counts
+        assert!(frames_flowing(Some(100_500), 100_000, FRAME_STALL_MS));
     }
 
-    // Default: list recent detections with all extracted filters
-    format!(
-        "SELECT id, timestamp, camera_id, label, confidence \
-         FROM detections WHERE 1=1 {}{} ORDER BY timestamp DESC LIMIT {}",
-        label_filter, time_filter, limit
-    )
-}
+    #[test]
+    fn test_stopped_health_reports_failure_or_not_found() {
+        let err = stopped_health("health-test-never-started".into()).unwrap_err();
+        assert_eq!(err, BroxeenError::not_found("No active pipeline for camera: health-test-never-started"));
 
-// ── Generic regex-based extractors (replace hardcoded keyword lists) ──────
-
-/// Extract a time filter from natural language using regex.
-/// Handles ANY number + time unit: "7 minut", "42 min", "3 godziny", "2 dni", etc.
-fn extract_time_filter(q: &str) -> String {
-    // Named day keywords
-    if q.contains("dzisiaj") || q.contains("dziś") || q.contains("dzis") || q.contains("today") {
-        return " AND date(timestamp) = date('now')".into();
-    }
-    if q.contains("wczoraj") || q.contains("yesterday") {
-        return " AND date(timestamp) = date('now','-1 day')".into();
+        record_failure("health-test-crashed", "crashed: exit status 1".into());
+        let health = stopped_health("health-test-crashed".into()).unwrap();
+        assert!(!health.running);
+        assert_eq!(health.last_error.as_deref(), Some("crashed: exit status 1"));
     }
 
-    // Generic: N minutes — matches "7 minut", "42 min", "120 minut", "pół godziny" etc.
-    if let Some(n) = extract_number_before(q, &["minut", "min "]) {
-        return format!(" AND timestamp > datetime('now', '-{} minutes')", n);
-    }
-    // "pół godziny" = 30 minutes
-    if q.contains("pół godziny") || q.contains("pol godziny") {
-        return " AND timestamp > datetime('now', '-30 minutes')".into();
+    fn roundtrip(raw: serde_json::Value) -> StartPipelineRequest {
+        let req: StartPipelineRequest = serde_json::from_value(raw).expect("deserialize");
+        let again: StartPipelineRequest =
+            serde_json::from_value(serde_json::to_value(&req).expect("serialize")).expect("re-deserialize");
+        assert_eq!(req, again);
+        req
     }
 
-    // Generic: N hours — matches "3 godziny", "2h", "1 hour"
-    if let Some(n) = extract_number_before(q, &["godzin", "hour", "h "]) {
-        return format!(" AND timestamp > datetime('now', '-{} hours')", n);
+    #[test]
+    fn test_legacy_motion_plugin_payload() {
+        // Payload sent by motionDetectionPlugin.ts before the schema split
+        let req = roundtrip(serde_json::json!({
+            "camera_id": "front",
+            "rtsp_url": "rtsp://192.168.1.10:554/stream",
+            "db_path": "detections.db",
+            "python_path": "python3",
+            "pipeline_script": "scripts/motion_pipeline.py",
+            "process_every": 5,
+            "min_area": 2000,
+            "max_area": 200000,
+            "var_threshold": 50,
+            "bg_history": 500,
+            "llm_threshold": 0.7,
+            "cooldown_sec": 10.0,
+            "max_crop_px": 500,
+            "llm_model": "anthropic/claude-haiku-4-5",
+            "api_key": "",
+            "platform": "auto",
+            "night_mode": false,
+            "stats_interval": 60
+        }));
+
+        assert_eq!(req.core.camera_id, "front");
+        assert_eq!(req.python.min_area, Some(2000));
+        assert!(req.unknown.is_empty());
+        // Legacy llm_threshold keeps each backend's previous meaning
+        assert_eq!(req.detector_confidence(), 0.7);
+        assert_eq!(req.llm_verify_below(), 0.7);
+
+        let native = req.warnings(true);
+        assert!(native.iter().any(|w| w.field == "llm_threshold" && w.kind == "deprecated"));
+        assert!(native.iter().any(|w| w.field == "python_path" && w.kind == "ignored"));
+        let python = req.warnings(false);
+        assert!(python.iter().all(|w| w.kind != "ignored"));
     }
 
-    // Generic: N days — matches "2 dni", "7 days"
-    if let Some(n) = extract_number_before(q, &["dni", "day"]) {
-        return format!(" AND timestamp > datetime('now', '-{} days')", n);
+    #[test]
+    fn test_minimal_payload_defaults() {
+        let req = roundtrip(serde_json::json!({
+            "camera_id": "cam0",
+            "rtsp_url": "rtsp://10.0.0.2/live"
+        }));
+        assert_eq!(req.detector_confidence(), 0.50);
+        assert_eq!(req.llm_verify_below(), 0.6);
+        assert!(req.warnings(true).is_empty());
+        assert!(req.warnings(false).is_empty());
     }
 
-    String::new()
-}
+    #[test]
+    fn test_new_fields_take_precedence_and_unknown_reported() {
+        let req = roundtrip(serde_json::json!({
+            "camera_id": "cam0",
+            "rtsp_url": "rtsp://10.0.0.2/live",
+            "llm_threshold": 0.9,
+            "detector_confidence": 0.4,
+            "llm_verify_below": 0.55,
+            "frobnicate": true
+        }));
+        assert_eq!(req.detector_confidence(), 0.4);
+        assert_eq!(req.llm_verify_below(), 0.55);
+        assert!(req.warnings(true).iter().any(|w| w.field == "frobnicate" && w.kind == "unknown"));
+    }
 
-/// Extract a number that appears before one of the given suffixes.
-/// E.g. extract_number_before("ostatnich 7 minut", &["minut"]) → Some(7)
-fn extract_number_before(q: &str, suffixes: &[&str]) -> Option<u32> {
-    for suffix in suffixes {
-        let pattern = format!(r"(\d+)\s*{}", regex_lite::escape(suffix));
-        if let Ok(re) = regex_lite::Regex::new(&pattern) {
-            if let Some(caps) = re.captures(q) {
-                if let Some(n) = caps.get(1).and_then(|m| m.as_str().parse::<u32>().ok()) {
-                    return Some(n);
-                }
-            }
-        }
+    #[test]
+    fn test_missing_camera_id_rejected() {
+        let res: Result<StartPipelineRequest, _> =
+            serde_json::from_value(serde_json::json!({ "rtsp_url": "rtsp://x" }));
+        assert!(res.is_err());
     }
-    None
-}
-
-/// Extract label filter using a data-driven lookup table.
-const LABEL_MAP: &[(&[&str], &str)] = &[
-    (&["osob", "osób", "person", "ludzi", "człowiek", "czlowiek", "ktoś", "ktos"], " AND label='person'"),
-    (&["samochod", "samochód", "car", "auto ", "auta"], " AND label IN ('car','truck','bus')"),
-    (&["rower", "bicycle"], " AND label='bicycle'"),
-    (&["motocykl", "motorcycle"], " AND label='motorcycle'"),
-    (&["pies", "dog"], " AND label='dog'"),
-    (&["kot", "cat"], " AND label='cat'"),
-    (&["ptak", "bird"], " AND label='bird'"),
-    (&["koń", "kon", "horse"], " AND label='horse'"),
-    (&["plecak", "backpack"], " AND label='backpack'"),
-    (&["parasol", "umbrella"], " AND label='umbrella'"),
-    (&["laptop"], " AND label='laptop'"),
-    (&["telefon", "cell phone", "phone"], " AND label='cell phone'"),
-    (&["krzesło", "krzeslo", "chair"], " AND label='chair'"),
-    (&["butelk", "bottle"], " AND label='bottle'"),
-    (&["zegar", "clock"], " AND label='clock'"),
-    (&["walizk", "suitcase"], " AND label='suitcase'"),
-    (&["torebk", "handbag"], " AND label='handbag'"),
-];
-
-fn extract_label_filter(q: &str) -> String {
-    for (keywords, sql_filter) in LABEL_MAP {
-        for kw in *keywords {
-            if q.contains(kw) {
-                return sql_filter.to_string();
-            }
-        }
+
+    #[test]
+    fn test_thumbnail_mode_parse() {
+        assert_eq!(ThumbnailMode::parse(None).unwrap(), ThumbnailMode::None);
+        assert_eq!(ThumbnailMode::parse(Some("refs")).unwrap(), ThumbnailMode::Refs);
+        assert_eq!(ThumbnailMode::parse(Some("inline_small")).unwrap(), ThumbnailMode::InlineSmall);
+        assert!(ThumbnailMode::parse(Some("huge")).is_err());
     }
-    String::new()
-}
 
-/// Extract a LIMIT number from the query.
-fn extract_limit(q: &str) -> Option<u32> {
-    // "ostatnie 5" / "recent 10" / "pokaż 20"
-    let re = regex_lite::Regex::new(r"(?:ostatni\w*|recent|pokaz|pokaż|wyświetl|limit)\s+(\d+)").ok()?;
-    if let Some(caps) = re.captures(q) {
-        return caps.get(1)?.as_str().parse().ok();
+    #[test]
+    fn test_thumbnail_without_format_column_is_sniffed() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        // Python pipeline schema: no thumbnail_format
+        conn.execute_batch("CREATE TABLE detections (id INTEGER PRIMARY KEY, thumbnail BLOB)").unwrap();
+        let webp = b"RIFF\x10\0\0\0WEBPVP8 ".to_vec();
+        conn.execute("INSERT INTO detections (id, thumbnail) VALUES (1, ?1), (2, NULL)", [&webp]).unwrap();
+
+        assert_eq!(stored_thumbnail(&conn, 1).unwrap(), (webp.clone(), ThumbnailFormat::Webp));
+        assert!(stored_thumbnail(&conn, 2).unwrap_err().contains("no thumbnail"));
+        assert!(stored_thumbnail(&conn, 3).unwrap_err().contains("not found"));
+
+        conn.execute_batch("ALTER TABLE detections ADD COLUMN thumbnail_format TEXT").unwrap();
+        conn.execute("UPDATE detections SET thumbnail_format = 'jpeg' WHERE id = 1", []).unwrap();
+        assert_eq!(stored_thumbnail(&conn, 1).unwrap().1, ThumbnailFormat::Jpeg);
     }
-    // "5 ostatnich" / "10 wykryć"
-    let re2 = regex_lite::Regex::new(r"(\d+)\s+(?:ostatni|recent|wykry|detect|rekord|record|wynik)").ok()?;
-    re2.captures(q).and_then(|c| c.get(1)?.as_str().parse().ok())
 }
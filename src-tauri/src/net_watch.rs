@@ -0,0 +1,278 @@
+//! net_watch.rs — Background watcher for network interface changes.
+//!
+//! Keeps a cached snapshot of IPv4 interfaces and the primary (default-route)
+//! interface so `scan_network` doesn't shell out to `ip route` per call. On
+//! Linux changes are pushed by `ip -o monitor link address` (netlink); a
+//! periodic re-check covers other platforms and a missing `ip` tool.
+//! Every change of the interface set or primary address emits
+//! `broxeen:network_changed`.
+
+use serde::Serialize;
+use std::io::{BufRead, BufReader};
+use std::net::IpAddr;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::sync::RwLock;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::logging::{backend_info, backend_warn};
+
+const NETWORK_CHANGED_EVENT: &str = "broxeen:network_changed";
+/// Re-check interval without push notifications
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Safety-net re-check when `ip monitor` is running
+const MONITOR_RECHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// Bursts of netlink messages (link up → address → route) settle into one check
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Serialize, PartialEq, Default)]
+pub struct InterfaceAddress {
+    pub name: String,
+    pub ip: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Default)]
+pub struct NetworkSnapshot {
+    /// Non-loopback IPv4 interfaces, sorted by name
+    pub interfaces: Vec<InterfaceAddress>,
+    pub primary_interface: Option<String>,
+    pub primary_ip: Option<String>,
+    /// First three octets, as used by `scan_network` (e.g. `192.168.1`)
+    pub subnet: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct NetworkChangedEvent {
+    pub old_interface: Option<String>,
+    pub new_interface: Option<String>,
+    pub old_ip: Option<String>,
+    pub new_ip: Option<String>,
+    pub old_subnet: Option<String>,
+    pub new_subnet: Option<String>,
+    /// `name ip` entries that appeared / disappeared
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct NetworkWatchStatus {
+    pub running: bool,
+    /// "netlink" (ip monitor) or "polling"
+    pub mode: String,
+    pub snapshot: NetworkSnapshot,
+    pub changes: u64,
+    pub last_change_at: Option<i64>,
+    pub last_checked_at: Option<i64>,
+}
+
+lazy_static::lazy_static! {
+    static ref STATUS: RwLock<NetworkWatchStatus> = RwLock::new(NetworkWatchStatus::default());
+}
+
+fn subnet_of(ip: &str) -> Option<String> {
+    let octets: Vec<&str> = ip.split('.').collect();
+    (octets.len() == 4).then(|| format!("{}.{}.{}", octets[0], octets[1], octets[2]))
+}
+
+fn take_snapshot() -> NetworkSnapshot {
+    let mut interfaces: Vec<InterfaceAddress> = local_ip_address::list_afinet_netifas()
+        .map(|list| {
+            list.into_iter()
+                .filter(|(_, ip)| matches!(ip, IpAddr::V4(v4) if !v4.is_loopback()))
+                .map(|(name, ip)| InterfaceAddress { name, ip: ip.to_string() })
+                .collect()
+        })
+        .unwrap_or_default();
+    interfaces.sort_by(|a, b| (&a.name, &a.ip).cmp(&(&b.name, &b.ip)));
+
+    let primary_ip = match local_ip_address::local_ip() {
+        Ok(IpAddr::V4(ip)) => Some(ip.to_string()),
+        _ => None,
+    };
+    let primary_interface = primary_ip
+        .as_ref()
+        .and_then(|ip| interfaces.iter().find(|i| &i.ip == ip).map(|i| i.name.clone()));
+
+    NetworkSnapshot {
+        subnet: primary_ip.as_deref().and_then(subnet_of),
+        interfaces,
+        primary_interface,
+        primary_ip,
+    }
+}
+
+/// None when nothing relevant changed.
+fn diff_snapshots(old: &NetworkSnapshot, new: &NetworkSnapshot) -> Option<NetworkChangedEvent> {
+    if old == new {
+        return None;
+    }
+    let describe = |i: &InterfaceAddress| format!("{} {}", i.name, i.ip);
+    Some(NetworkChangedEvent {
+        old_interface: old.primary_interface.clone(),
+        new_interface: new.primary_interface.clone(),
+        old_ip: old.primary_ip.clone(),
+        new_ip: new.primary_ip.clone(),
+        old_subnet: old.subnet.clone(),
+        new_subnet: new.subnet.clone(),
+        added: new.interfaces.iter().filter(|i| !old.interfaces.contains(i)).map(describe).collect(),
+        removed: old.interfaces.iter().filter(|i| !new.interfaces.contains(i)).map(describe).collect(),
+        timestamp: chrono::Utc::now().timestamp_millis(),
+    })
+}
+
+/// Spawn `ip -o monitor` and forward a tick per line. Returns false when
+/// netlink monitoring is unavailable.
+fn spawn_netlink_monitor(tx: mpsc::Sender<()>) -> bool {
+    if !cfg!(target_os = "linux") {
+        return false;
+    }
+    let child = Command::new("ip")
+        .args(["-o", "monitor", "link", "address"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    let Ok(mut child) = child else { return false };
+    let Some(stdout) = child.stdout.take() else { return false };
+
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            if line.is_err() || tx.send(()).is_err() {
+                break;
+            }
+        }
+        let _ = child.kill();
+        let _ = child.wait();
+        backend_warn("net_watch: ip monitor exited, falling back to polling");
+    });
+    true
+}
+
+fn refresh(app: &AppHandle) {
+    let snapshot = take_snapshot();
+    let now = chrono::Utc::now().timestamp_millis();
+    let event = {
+        let Ok(mut status) = STATUS.write() else { return };
+        status.last_checked_at = Some(now);
+        let event = diff_snapshots(&status.snapshot, &snapshot);
+        if event.is_some() {
+            status.snapshot = snapshot;
+            status.changes += 1;
+            status.last_change_at = Some(now);
+        }
+        event
+    };
+    if let Some(event) = event {
+        backend_info(format!(
+            "net_watch: network changed {:?}/{:?} → {:?}/{:?} (+{:?} -{:?})",
+            event.old_interface, event.old_subnet, event.new_interface, event.new_subnet, event.added, event.removed
+        ));
+        if let Err(e) = app.emit(NETWORK_CHANGED_EVENT, event) {
+            backend_warn(format!("net_watch: emit failed: {}", e));
+        }
+    }
+}
+
+/// Take the initial snapshot and start watching (called once at launch).
+pub fn start(app: AppHandle) {
+    let (tx, rx) = mpsc::channel::<()>();
+    let netlink = spawn_netlink_monitor(tx);
+    if let Ok(mut status) = STATUS.write() {
+        *status = NetworkWatchStatus {
+            running: true,
+            mode: if netlink { "netlink" } else { "polling" }.to_string(),
+            snapshot: take_snapshot(),
+            last_checked_at: Some(chrono::Utc::now().timestamp_millis()),
+            ..Default::default()
+        };
+    }
+    backend_info(format!("net_watch started ({})", if netlink { "netlink" } else { "polling" }));
+
+    std::thread::spawn(move || {
+        let mut interval = if netlink { MONITOR_RECHECK_INTERVAL } else { POLL_INTERVAL };
+        loop {
+            match rx.recv_timeout(interval) {
+                Ok(()) => {
+                    std::thread::sleep(DEBOUNCE);
+                    while rx.try_recv().is_ok() {}
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    if interval != POLL_INTERVAL {
+                        interval = POLL_INTERVAL;
+                        if let Ok(mut status) = STATUS.write() {
+                            status.mode = "polling".to_string();
+                        }
+                    }
+                    std::thread::sleep(interval);
+                }
+            }
+            refresh(&app);
+        }
+    });
+}
+
+/// Subnet of the primary interface from the watcher cache; None before
+/// `start` or when no IPv4 address is up.
+pub fn cached_subnet() -> Option<String> {
+    let status = STATUS.read().ok()?;
+    if !status.running {
+        return None;
+    }
+    status.snapshot.subnet.clone()
+}
+
+#[tauri::command]
+pub fn network_watch_status() -> Result<NetworkWatchStatus, String> {
+    STATUS
+        .read()
+        .map(|s| s.clone())
+        .map_err(|_| "net_watch status lock poisoned".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(interfaces: &[(&str, &str)], primary: Option<(&str, &str)>) -> NetworkSnapshot {
+        NetworkSnapshot {
+            interfaces: interfaces
+                .iter()
+                .map(|(name, ip)| InterfaceAddress { name: name.to_string(), ip: ip.to_string() })
+                .collect(),
+            primary_interface: primary.map(|(name, _)| name.to_string()),
+            primary_ip: primary.map(|(_, ip)| ip.to_string()),
+            subnet: primary.and_then(|(_, ip)| subnet_of(ip)),
+        }
+    }
+
+    #[test]
+    fn test_ethernet_to_wifi_switch() {
+        let wired = snapshot(&[("eth0", "192.168.1.20")], Some(("eth0", "192.168.1.20")));
+        let wifi = snapshot(&[("wlan0", "10.0.0.7")], Some(("wlan0", "10.0.0.7")));
+
+        let event = diff_snapshots(&wired, &wifi).unwrap();
+        assert_eq!(event.old_interface.as_deref(), Some("eth0"));
+        assert_eq!(event.new_interface.as_deref(), Some("wlan0"));
+        assert_eq!(event.old_subnet.as_deref(), Some("192.168.1"));
+        assert_eq!(event.new_subnet.as_deref(), Some("10.0.0"));
+        assert_eq!(event.added, vec!["wlan0 10.0.0.7"]);
+        assert_eq!(event.removed, vec!["eth0 192.168.1.20"]);
+
+        assert!(diff_snapshots(&wifi, &wifi.clone()).is_none());
+    }
+
+    #[test]
+    fn test_secondary_interface_change_is_reported() {
+        let before = snapshot(&[("eth0", "192.168.1.20")], Some(("eth0", "192.168.1.20")));
+        let after = snapshot(
+            &[("docker0", "172.17.0.1"), ("eth0", "192.168.1.20")],
+            Some(("eth0", "192.168.1.20")),
+        );
+        let event = diff_snapshots(&before, &after).unwrap();
+        assert_eq!(event.old_subnet, event.new_subnet);
+        assert_eq!(event.added, vec!["docker0 172.17.0.1"]);
+        assert!(event.removed.is_empty());
+    }
+}
@@ -0,0 +1,419 @@
+//! query_nl.rs — Natural language → SQL over the detections database.
+//!
+//! One place for both ways a question becomes SQL:
+//! - [`nl_to_sql`]: the keyword matcher (Polish + English) used when no LLM
+//!   is reachable — label mapping, named days / "ostatnie N minut" filters,
+//!   limits, visit and speed questions;
+//! - [`text_to_sql_prompt`]: the prompt every LLM text-to-SQL path sends
+//!   (`llm_query`, `local_llm`, the vision query engine / REPL).
+//!
+//! The prompt's label hints are generated from the same [`LABEL_MAP`] the
+//! matcher uses, so "samochód" means the same labels whichever path answers.
+
+use crate::local_time::LocalClock;
+
+/// Colloquial keywords → YOLO labels. The first matching entry wins.
+pub const LABEL_MAP: &[(&[&str], &[&str])] = &[
+    (&["osob", "osób", "person", "ludzi", "człowiek", "czlowiek", "ktoś", "ktos"], &["person"]),
+    (&["samochod", "samochód", "car", "auto ", "auta"], &["car", "truck", "bus"]),
+    (&["rower", "bicycle"], &["bicycle"]),
+    (&["motocykl", "motorcycle"], &["motorcycle"]),
+    (&["pies", "dog"], &["dog"]),
+    (&["kot", "cat"], &["cat"]),
+    (&["ptak", "bird"], &["bird"]),
+    (&["koń", "kon", "horse"], &["horse"]),
+    (&["plecak", "backpack"], &["backpack"]),
+    (&["parasol", "umbrella"], &["umbrella"]),
+    (&["laptop"], &["laptop"]),
+    (&["telefon", "cell phone", "phone"], &["cell phone"]),
+    (&["krzesło", "krzeslo", "chair"], &["chair"]),
+    (&["butelk", "bottle"], &["bottle"]),
+    (&["zegar", "clock"], &["clock"]),
+    (&["walizk", "suitcase"], &["suitcase"]),
+    (&["torebk", "handbag"], &["handbag"]),
+];
+
+/// Keyword-based natural language → SQL converter (legacy fallback).
+/// Used only when LLM text-to-SQL is unavailable (no OPENROUTER_API_KEY).
+/// Delegates to generic regex extractors instead of hardcoded keyword lists.
+pub fn nl_to_sql(question: &str, new_schema: bool, has_speed_mps: bool, clock: &LocalClock) -> String {
+    let q = question.to_lowercase();
+    let time_filter = extract_time_filter(&q, clock);
+    let label_filter = extract_label_filter(&q);
+    let limit = extract_limit(&q).unwrap_or(20);
+
+    // Visits: "ile razy ktoś przyszedł", "ile wizyt" — several tracks of one
+    // arrival count once (see vision_visits)
+    if is_visit_question(&q) {
+        return format!(
+            "SELECT label, COUNT(*) AS visits, SUM(tracks) AS tracks, MIN(start) AS first_seen, \
+             MAX(end) AS last_seen, MAX(id) AS id FROM ({}) GROUP BY label ORDER BY visits DESC",
+            crate::vision_visits::visits_sql(&format!("{}{}", label_filter, time_filter), crate::vision_visits::DEFAULT_VISIT_GAP_SECS)
+        );
+    }
+
+    // Fast movement: "czy ktoś biegł?", "kto jechał szybko" — needs the native
+    // pipeline's speed_label (speed_mps only with a calibrated camera)
+    if new_schema && is_speed_question(&q) {
+        let mps = if has_speed_mps { ", ROUND(speed_mps, 1) AS speed_mps" } else { "" };
+        return format!(
+            "SELECT id, timestamp, camera_id, label, speed_label{} \
+             FROM detections WHERE speed_label = 'fast' {}{} ORDER BY timestamp DESC LIMIT {}",
+            mps, label_filter, time_filter, limit
+        );
+    }
+
+    // Counting query: "ile", "policz", "liczba", "count"
+    if q.contains("ile") || q.contains("policz") || q.contains("liczba") || q.contains("count") {
+        return format!(
+            "SELECT COUNT(*) as count, MIN(timestamp) as first_seen, MAX(timestamp) as last_seen, \
+             MAX(id) as id FROM detections WHERE 1=1 {}{}", label_filter, time_filter
+        );
+    }
+
+    // Statistics: "statystyki", "podsumowanie", "summary", "stats"
+    if q.contains("statyst") || q.contains("podsumow") || q.contains("summary") || q.contains("stats") {
+        return format!(
+            "SELECT label, COUNT(*) as count, ROUND(AVG(confidence),2) as avg_conf, \
+             MIN(timestamp) as first, MAX(timestamp) as last, MAX(id) as id \
+             FROM detections WHERE 1=1 {}{} GROUP BY label ORDER BY count DESC",
+            label_filter, time_filter
+        );
+    }
+
+    // Camera breakdown: "kamera", "camera"
+    if q.contains("kamer") || q.contains("camera") {
+        return format!(
+            "SELECT camera_id, COUNT(*) as detections, \
+             MIN(timestamp) as first, MAX(timestamp) as last, MAX(id) as id \
+             FROM detections WHERE 1=1 {}{} GROUP BY camera_id ORDER BY detections DESC",
+            label_filter, time_filter
+        );
+    }
+
+    // Default: list recent detections with all extracted filters
+    format!(
+        "SELECT id, timestamp, camera_id, label, confidence \
+         FROM detections WHERE 1=1 {}{} ORDER BY timestamp DESC LIMIT {}",
+        label_filter, time_filter, limit
+    )
+}
+
+/// System prompt for LLM text-to-SQL over `schema`; the question goes in the
+/// user message (or is appended by the caller). "Today" / "yesterday" are
+/// spelled out as `clock`'s local dates, not SQLite's `'localtime'`.
+pub fn text_to_sql_prompt(schema: &str, clock: &LocalClock) -> String {
+    let today = clock.today();
+    format!(
+        r#"You are a SQLite query generator for a monitoring system.
+
+Given the database schema below, convert the user's natural language question into a single SQLite SELECT query.
+
+SCHEMA:
+```sql
+{schema}
+```
+
+RULES:
+- Output ONLY the SQL query, nothing else
+- No markdown, no explanation, no backticks
+- Only SELECT queries (never INSERT, UPDATE, DELETE, DROP)
+- Use only tables and columns from the schema
+- For time filters use: datetime('now', '-N minutes') or datetime('now', '-N hours')
+- 'today' = local_date = '{today}'; 'yesterday' = local_date = '{yesterday}'
+- Group by day/hour with local_date / local_hour, not date(timestamp): timestamp is UTC
+- Labels are lowercase: 'person', 'car', 'truck', 'bus', 'bicycle', etc.
+- Words in the question map to labels:
+{labels}
+- Always use COUNT(*) for counting questions
+- Include MIN(timestamp) as first_seen, MAX(timestamp) as last_seen for time ranges
+- Running / fast movement ('biegł', 'biegać', 'szybko', 'ran'): speed_label = 'fast'; show speed_mps too
+- Use GROUP BY camera_id when asking per-camera stats
+- When selecting from detections or monitoring_history, always include the id column (MAX(id) AS id in aggregates)
+- Default ORDER BY timestamp DESC
+- Default LIMIT 50 unless user specifies otherwise"#,
+        schema = schema,
+        today = today,
+        yesterday = today - chrono::Duration::days(1),
+        labels = label_hints()
+    )
+}
+
+/// One "  'osob'/'ludzi'… → person" line per `LABEL_MAP` entry.
+fn label_hints() -> String {
+    LABEL_MAP
+        .iter()
+        .map(|(keywords, labels)| {
+            let words: Vec<String> = keywords
+                .iter()
+                .map(|kw| kw.trim())
+                .filter(|kw| !labels.contains(kw))
+                .map(|kw| format!("'{}'", kw))
+                .collect();
+            let words = if words.is_empty() { format!("'{}'", labels[0]) } else { words.join("/") };
+            format!("  {} → {}", words, labels.join(", "))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// ── Generic regex-based extractors (replace hardcoded keyword lists) ──────
+
+/// "ile razy … przyszedł/przyjechał/był", "wizyty", "how many times … came".
+fn is_visit_question(q: &str) -> bool {
+    const ARRIVAL: &[&str] = &["przysz", "przyjech", "przyjezdż", "odwiedz", "wizyt", "był", "byl ", "pojawi", "came", "visit"];
+    let asks_how_often = q.contains("ile razy") || q.contains("how many times") || q.contains("wizyt") || q.contains("visits");
+    asks_how_often && ARRIVAL.iter().any(|kw| q.contains(kw))
+}
+
+/// "biegł", "biegnie", "szybko", "pędził", "running", "fast".
+fn is_speed_question(q: &str) -> bool {
+    const FAST: &[&str] = &["bieg", "szybk", "pędzi", "pedzi", "running", "fast", "speed"];
+    FAST.iter().any(|kw| q.contains(kw))
+}
+
+/// Extract a time filter from natural language using regex.
+/// Handles ANY number + time unit: "7 minut", "42 min", "3 godziny", "2 dni", etc.
+/// Named days are local days in `clock`'s zone, as UTC ranges.
+fn extract_time_filter(q: &str, clock: &LocalClock) -> String {
+    // Named day keywords
+    if q.contains("dzisiaj") || q.contains("dziś") || q.contains("dzis") || q.contains("today") {
+        return clock.day_filter(0);
+    }
+    if q.contains("wczoraj") || q.contains("yesterday") {
+        return clock.day_filter(1);
+    }
+
+    // Generic: N minutes — matches "7 minut", "42 min", "120 minut", "pół godziny" etc.
+    if let Some(n) = extract_number_before(q, &["minut", "min "]) {
+        return format!(" AND datetime(timestamp) > datetime('now', '-{} minutes')", n);
+    }
+    // "pół godziny" = 30 minutes
+    if q.contains("pół godziny") || q.contains("pol godziny") {
+        return " AND datetime(timestamp) > datetime('now', '-30 minutes')".into();
+    }
+
+    // Generic: N hours — matches "3 godziny", "2h", "1 hour"
+    if let Some(n) = extract_number_before(q, &["godzin", "hour", "h "]) {
+        return format!(" AND datetime(timestamp) > datetime('now', '-{} hours')", n);
+    }
+
+    // Generic: N days — matches "2 dni", "7 days"
+    if let Some(n) = extract_number_before(q, &["dni", "day"]) {
+        return format!(" AND datetime(timestamp) > datetime('now', '-{} days')", n);
+    }
+
+    String::new()
+}
+
+/// Extract a number that appears before one of the given suffixes.
+/// E.g. extract_number_before("ostatnich 7 minut", &["minut"]) → Some(7)
+fn extract_number_before(q: &str, suffixes: &[&str]) -> Option<u32> {
+    for suffix in suffixes {
+        let pattern = format!(r"(\d+)\s*{}", regex_lite::escape(suffix));
+        if let Ok(re) = regex_lite::Regex::new(&pattern) {
+            if let Some(caps) = re.captures(q) {
+                if let Some(n) = caps.get(1).and_then(|m| m.as_str().parse::<u32>().ok()) {
+                    return Some(n);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Label condition (with leading ` AND`) for the first `LABEL_MAP` keyword in `q`.
+fn extract_label_filter(q: &str) -> String {
+    let Some((_, labels)) = LABEL_MAP.iter().find(|(keywords, _)| keywords.iter().any(|kw| q.contains(kw))) else {
+        return String::new();
+    };
+    match labels {
+        [label] => format!(" AND label='{}'", label),
+        _ => {
+            let quoted: Vec<String> = labels.iter().map(|l| format!("'{}'", l)).collect();
+            format!(" AND label IN ({})", quoted.join(","))
+        }
+    }
+}
+
+/// Extract a LIMIT number from the query.
+fn extract_limit(q: &str) -> Option<u32> {
+    // "ostatnie 5" / "recent 10" / "pokaż 20"
+    let re = regex_lite::Regex::new(r"(?:ostatni\w*|recent|pokaz|pokaż|wyświetl|limit)\s+(\d+)").ok()?;
+    if let Some(caps) = re.captures(q) {
+        return caps.get(1)?.as_str().parse().ok();
+    }
+    // "5 ostatnich" / "10 wykryć"
+    let re2 = regex_lite::Regex::new(r"(\d+)\s+(?:ostatni|recent|wykry|detect|rekord|record|wynik)").ok()?;
+    re2.captures(q).and_then(|c| c.get(1)?.as_str().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock() -> LocalClock {
+        LocalClock { tz: chrono_tz::Tz::UTC, now: "2024-06-12T10:00:00Z".parse().unwrap() }
+    }
+
+    const TODAY: &str = " AND datetime(timestamp) >= '2024-06-12 00:00:00' AND datetime(timestamp) < '2024-06-13 00:00:00'";
+    const YESTERDAY: &str = " AND datetime(timestamp) >= '2024-06-11 00:00:00' AND datetime(timestamp) < '2024-06-12 00:00:00'";
+
+    /// Question → fragments the SQL must contain / must not contain.
+    const CASES: &[(&str, &[&str], &[&str])] = &[
+        ("ile osób dzisiaj", &["SELECT COUNT(*) as count", " AND label='person'", TODAY], &["visit_no", "LIMIT"]),
+        ("ile samochodów wczoraj?", &["COUNT(*)", " AND label IN ('car','truck','bus')", YESTERDAY], &[]),
+        ("count detections today", &["COUNT(*)", TODAY], &["label="]),
+        ("ile osób w ciągu ostatnich 7 minut", &["label='person'", "datetime('now', '-7 minutes')"], &[]),
+        ("ile kotów przez pół godziny", &["label='cat'", "datetime('now', '-30 minutes')"], &[]),
+        ("rowery w ciągu 3 godzin", &["label='bicycle'", "datetime('now', '-3 hours')", "LIMIT 20"], &[]),
+        ("ptaki w ciągu 2 dni", &["label='bird'", "datetime('now', '-2 days')"], &[]),
+        ("pokaż ostatnie 5", &["ORDER BY timestamp DESC LIMIT 5"], &["label="]),
+        ("10 ostatnich wykryć telefonu", &["label='cell phone'", "LIMIT 10"], &[]),
+        ("statystyki dzisiaj", &["GROUP BY label", "AVG(confidence)", TODAY], &[]),
+        ("wykrycia per kamera", &["GROUP BY camera_id"], &[]),
+        ("czy ktoś biegł dzisiaj?", &["speed_label = 'fast'", "speed_mps", "label='person'", TODAY], &["COUNT"]),
+        ("ile razy ktoś dziś przyszedł", &["visit_no", "COUNT(*) AS visits", "label='person'", TODAY], &[]),
+        ("how many times did a car visit", &["visit_no", "label IN ('car','truck','bus')"], &[]),
+    ];
+
+    #[test]
+    fn test_question_to_sql_cases() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE detections (id INTEGER PRIMARY KEY, timestamp TEXT, camera_id TEXT, track_id TEXT,
+                                      label TEXT, confidence REAL, speed_label TEXT, speed_mps REAL);",
+        )
+        .unwrap();
+        for (question, present, absent) in CASES {
+            let sql = nl_to_sql(question, true, true, &clock());
+            for fragment in *present {
+                assert!(sql.contains(fragment), "{:?}: missing {:?} in {}", question, fragment, sql);
+            }
+            for fragment in *absent {
+                assert!(!sql.contains(fragment), "{:?}: unexpected {:?} in {}", question, fragment, sql);
+            }
+            // Every answer selects an id (thumbnails) and is valid SQL
+            assert!(sql.contains(" id") || sql.contains("(id)"), "{:?}: missing id in {}", question, sql);
+            conn.prepare(&sql).unwrap_or_else(|e| panic!("{:?}: {} in {}", question, e, sql));
+        }
+    }
+
+    #[test]
+    fn test_prompt_maps_the_same_words_to_labels() {
+        let prompt = text_to_sql_prompt("CREATE TABLE detections (id INTEGER);", &clock());
+        assert!(prompt.contains("CREATE TABLE detections (id INTEGER);"));
+        assert!(prompt.contains("  'osob'/'osób'/'ludzi'/'człowiek'/'czlowiek'/'ktoś'/'ktos' → person"));
+        assert!(prompt.contains("  'samochod'/'samochód'/'auto'/'auta' → car, truck, bus"));
+        assert!(prompt.contains("  'laptop' → laptop"));
+        for (keywords, labels) in LABEL_MAP {
+            // What the matcher filters on is what the prompt tells the LLM
+            let filter = extract_label_filter(keywords[0]);
+            assert!(labels.iter().all(|l| filter.contains(&format!("'{}'", l))), "{}", filter);
+            assert!(prompt.contains(&format!("→ {}\n", labels.join(", "))), "{:?}", labels);
+        }
+    }
+
+    #[test]
+    fn test_prompt_spells_out_local_days() {
+        // 23:30Z is already the 31st in Warsaw
+        let clock = LocalClock {
+            tz: "Europe/Warsaw".parse().unwrap(),
+            now: "2024-03-30T23:30:00Z".parse().unwrap(),
+        };
+        let prompt = text_to_sql_prompt("", &clock);
+        assert!(prompt.contains("'today' = local_date = '2024-03-31'; 'yesterday' = local_date = '2024-03-30'"));
+        assert!(!prompt.contains("localtime"));
+    }
+
+    #[test]
+    fn test_nl_to_sql_routes_visit_questions() {
+        let sql = nl_to_sql("ile razy ktoś dziś przyszedł", true, true, &clock());
+        assert!(sql.contains("visit_no"), "{}", sql);
+        assert!(sql.contains("label='person'"));
+        assert!(sql.contains(&clock().day_filter(0)));
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE detections (id INTEGER PRIMARY KEY, timestamp TEXT, camera_id TEXT, label TEXT, confidence REAL);",
+        )
+        .unwrap();
+        let now = chrono::Utc::now();
+        for secs in [0, 30, 70, 900] {
+            conn.execute(
+                "INSERT INTO detections (timestamp, camera_id, label, confidence) VALUES (?1, 'front', 'person', 0.8)",
+                [(now - chrono::Duration::seconds(1000 - secs)).to_rfc3339()],
+            )
+            .unwrap();
+        }
+        let sql = nl_to_sql("ile razy ktoś przyszedł w ciągu 2 godzin", true, true, &clock());
+        let visits: i64 = conn.query_row(&sql, [], |r| r.get(1)).unwrap();
+        assert_eq!(visits, 2);
+
+        // Plain counts still count rows
+        assert!(!nl_to_sql("ile osób dzisiaj", true, true, &clock()).contains("visit_no"));
+    }
+
+    #[test]
+    fn test_nl_to_sql_always_selects_id() {
+        for q in ["ile osób dzisiaj", "statystyki", "kamery", "pokaż ostatnie 5", "czy ktoś biegł"] {
+            let sql = nl_to_sql(q, true, true, &clock());
+            assert!(sql.contains(" id") || sql.contains("(id)"), "missing id in: {}", sql);
+        }
+    }
+
+    #[test]
+    fn test_nl_to_sql_speed_questions() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE detections (id INTEGER PRIMARY KEY, timestamp TEXT, camera_id TEXT, track_id TEXT,
+                                      label TEXT, speed_label TEXT, speed_mps REAL);
+             INSERT INTO detections (timestamp, camera_id, track_id, label, speed_label, speed_mps) VALUES
+                (datetime('now'), 'front', 't1', 'person', 'fast', 3.14),
+                (datetime('now'), 'front', 't2', 'person', 'slow', 0.6),
+                (datetime('now'), 'front', 't3', 'car', 'fast', 9.0);",
+        )
+        .unwrap();
+
+        let sql = nl_to_sql("czy ktoś biegł dzisiaj?", true, true, &LocalClock::now(chrono_tz::Tz::UTC));
+        let rows: Vec<(String, f64)> = conn
+            .prepare(&sql).unwrap()
+            .query_map([], |r| Ok((r.get(3)?, r.get(5)?))).unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(rows, vec![("person".to_string(), 3.1)]);
+
+        // Uncalibrated DBs have no speed_mps column; Python DBs no speed_label at all
+        assert!(!nl_to_sql("czy ktoś biegł", true, false, &clock()).contains("speed_mps"));
+        assert!(!nl_to_sql("czy ktoś biegł", false, false, &clock()).contains("speed_label"));
+    }
+
+    #[test]
+    fn test_today_filter_follows_zone_across_dst() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE detections (id INTEGER PRIMARY KEY, timestamp TEXT, camera_id TEXT, label TEXT, confidence REAL);",
+        )
+        .unwrap();
+        // Warsaw, 2024-03-31 (02:00 → 03:00): the local day is 23:00Z..22:00Z
+        for at in ["2024-03-30T22:30:00+00:00", "2024-03-30T23:30:00+00:00", "2024-03-31T21:30:00+00:00", "2024-03-31T22:30:00+00:00"] {
+            conn.execute(
+                "INSERT INTO detections (timestamp, camera_id, label, confidence) VALUES (?1, 'front', 'person', 0.8)",
+                [at],
+            )
+            .unwrap();
+        }
+        let clock = LocalClock {
+            tz: "Europe/Warsaw".parse().unwrap(),
+            now: "2024-03-31T12:00:00Z".parse().unwrap(),
+        };
+        let count = |q: &str| -> i64 { conn.query_row(&nl_to_sql(q, true, false, &clock), [], |r| r.get(0)).unwrap() };
+        assert_eq!(count("ile osób dzisiaj"), 2);
+        assert_eq!(count("ile osób wczoraj"), 1);
+
+        // In UTC 23:30Z belongs to the day before
+        let utc = LocalClock { tz: chrono_tz::Tz::UTC, ..clock };
+        let sql = nl_to_sql("ile osób wczoraj", true, false, &utc);
+        assert_eq!(conn.query_row(&sql, [], |r| r.get::<_, i64>(0)).unwrap(), 2);
+    }
+}
@@ -5,6 +5,14 @@
 //!
 //! Instead of hardcoded keyword matching (extract_date_filter, nl_to_sql),
 //! the LLM receives these schemas and generates correct SQL/commands.
+//!
+//! Structured actions (`{"action": "...", "params": {...}}`) are described
+//! once in `ACTIONS`; the same table produces the JSON Schema handed to the
+//! LLM (`query_schema_describe`) and drives `validate_query`, which reports
+//! every problem with its path before anything is dispatched.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
 
 /// SQLite schema for the monitoring/detections database.
 /// Used as context for LLM text-to-SQL generation.
@@ -13,19 +21,21 @@ pub const DETECTIONS_SCHEMA: &str = r#"
 CREATE TABLE detections (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
     timestamp TEXT NOT NULL,        -- ISO 8601 UTC, e.g. '2026-02-23T18:05:30Z'
-    local_date TEXT NOT NULL,       -- 'YYYY-MM-DD' in local timezone
-    local_hour INTEGER NOT NULL,    -- 0-23 in local timezone
+    local_date TEXT NOT NULL,       -- 'YYYY-MM-DD' in the DB time zone (database.timezone)
+    local_hour INTEGER NOT NULL,    -- 0-23 in the DB time zone
     camera_id TEXT NOT NULL,        -- e.g. 'camera-192.168.188.176'
     track_id TEXT NOT NULL,         -- UUID of tracked object
     label TEXT NOT NULL,            -- YOLO class: 'person','car','truck','bus','bicycle','motorcycle','dog','cat','bird','horse','backpack','handbag','suitcase','umbrella','bottle','chair','laptop','cell phone','clock'
     confidence REAL NOT NULL,       -- 0.0-1.0 detection confidence
     movement TEXT,                  -- description: 'walking left', 'standing', 'running'
     direction TEXT,                 -- 'left','right','up','down','stationary'
-    speed_label TEXT,               -- 'slow','moderate','fast'
+    speed_label TEXT,               -- 'stationary','slow','moderate','fast' ('fast' ≈ running or a vehicle)
+    speed_mps REAL,                 -- ground speed in m/s, NULL when the camera is not calibrated
     entry_zone TEXT,                -- where object entered: 'left','right','upper-left','centre','bottom'
     exit_zone TEXT,                 -- where object exited (empty if still present)
     duration_s REAL NOT NULL DEFAULT 0, -- how long object was tracked (seconds)
-    thumbnail BLOB                  -- optional JPEG crop
+    thumbnail BLOB,                 -- optional JPEG crop
+    lighting TEXT                   -- 'day','night' (night = IR / low luminance)
 );
 
 -- Table: llm_events (LLM-generated scene narratives, ~1 per minute)
@@ -38,13 +48,25 @@ CREATE TABLE IF NOT EXISTS llm_events (
     labels TEXT                      -- comma-separated labels seen
 );
 
+-- Table: clips (RTSP video clips recorded around matching detections)
+CREATE TABLE IF NOT EXISTS clips (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp TEXT NOT NULL,
+    camera_id TEXT NOT NULL,
+    detection_id INTEGER,            -- detections.id that triggered the clip
+    label TEXT NOT NULL,
+    path TEXT NOT NULL,              -- .mp4 file on disk
+    duration_s INTEGER NOT NULL
+);
+
 -- View: monitoring_history (combined view for queries)
 CREATE VIEW IF NOT EXISTS monitoring_history AS
 SELECT
     d.id, d.timestamp, d.camera_id, d.label, d.confidence,
-    d.movement, d.direction, d.speed_label,
-    d.entry_zone, d.exit_zone, d.duration_s,
-    e.narrative
+    d.movement, d.direction, d.speed_label, d.speed_mps,
+    d.entry_zone, d.exit_zone, d.duration_s, d.lighting,
+    e.narrative,
+    (SELECT c.path FROM clips c WHERE c.detection_id = d.id LIMIT 1) AS clip_path
 FROM detections d
 LEFT JOIN llm_events e ON d.camera_id = e.camera_id
     AND abs(julianday(d.timestamp) - julianday(e.timestamp)) < 0.0007;
@@ -127,38 +149,6 @@ CREATE TABLE command_history (
 );
 "#;
 
-/// System prompt for text-to-SQL generation.
-/// Constrains LLM output to valid SQLite SELECT statements.
-pub fn build_text_to_sql_prompt(schema: &str) -> String {
-    format!(
-        r#"You are a SQLite query generator for a monitoring system.
-
-Given the database schema below, convert the user's natural language question into a single SQLite SELECT query.
-
-SCHEMA:
-```sql
-{schema}
-```
-
-RULES:
-- Output ONLY the SQL query, nothing else
-- No markdown, no explanation, no backticks
-- Only SELECT queries (never INSERT, UPDATE, DELETE, DROP)
-- Use only tables and columns from the schema
-- For time filters use: datetime('now', '-N minutes') or datetime('now', '-N hours')
-- 'today' = date(timestamp) = date('now') or local_date = date('now','localtime')
-- 'yesterday' = date(timestamp) = date('now','-1 day')
-- Labels are lowercase: 'person', 'car', 'truck', 'bus', 'bicycle', etc.
-- Always use COUNT(*) for counting questions
-- Include MIN(timestamp) as first_seen, MAX(timestamp) as last_seen for time ranges
-- For Polish: 'osób/osoby/ludzi' = person, 'samochód/auto' = car, 'rower' = bicycle
-- Use GROUP BY camera_id when asking per-camera stats
-- Default ORDER BY timestamp DESC
-- Default LIMIT 50 unless user specifies otherwise"#,
-        schema = schema
-    )
-}
-
 /// Available data sources that the LLM can query.
 /// Used to route queries to the correct database.
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -241,6 +231,371 @@ fn detect_data_source_keywords(question: &str) -> DataSource {
     DataSource::Monitoring
 }
 
+// ── Structured actions ───────────────────────────────
+
+#[derive(Debug, Clone, Copy)]
+enum FieldKind {
+    String,
+    Bool,
+    Integer { min: i64, max: i64 },
+    StringArray,
+    IntegerArray { min: i64, max: i64 },
+    Enum(&'static [&'static str]),
+}
+
+struct FieldSpec {
+    name: &'static str,
+    kind: FieldKind,
+    required: bool,
+    description: &'static str,
+}
+
+struct ActionSpec {
+    action: &'static str,
+    description: &'static str,
+    fields: &'static [FieldSpec],
+}
+
+const fn field(name: &'static str, kind: FieldKind, required: bool, description: &'static str) -> FieldSpec {
+    FieldSpec { name, kind, required, description }
+}
+
+const ACTIONS: &[ActionSpec] = &[
+    ActionSpec {
+        action: "file_search",
+        description: "Search local files by name (and optionally content)",
+        fields: &[
+            field("query", FieldKind::String, true, "File name fragment or keywords"),
+            field("search_path", FieldKind::String, false, "Directory to search; default: home"),
+            field("extensions", FieldKind::StringArray, false, "e.g. [\"pdf\", \"docx\"]"),
+            field("max_results", FieldKind::Integer { min: 1, max: 1000 }, false, "Default 50"),
+            field("max_depth", FieldKind::Integer { min: 1, max: 64 }, false, "Directory depth"),
+            field("search_content", FieldKind::Bool, false, "Also match file contents"),
+            field("content_query", FieldKind::String, false, "Text to find inside files"),
+            field("fuzzy", FieldKind::Bool, false, "Typo-tolerant name matching"),
+        ],
+    },
+    ActionSpec {
+        action: "file_read_content",
+        description: "Read a text file (or a hexdump of a binary one)",
+        fields: &[
+            field("path", FieldKind::String, true, "Absolute path"),
+            field("max_chars", FieldKind::Integer { min: 1, max: 1_000_000 }, false, "Window size"),
+            field("offset", FieldKind::Integer { min: 0, max: i64::MAX }, false, "Byte offset"),
+            field("encoding", FieldKind::Enum(&["text", "hex"]), false, "Default text"),
+            field("tail_lines", FieldKind::Integer { min: 1, max: 10_000 }, false, "Last N lines"),
+        ],
+    },
+    ActionSpec {
+        action: "scan_network",
+        description: "Discover devices in the local network",
+        fields: &[
+            field("subnet", FieldKind::String, false, "e.g. 192.168.1 (default: detected)"),
+            field("timeout", FieldKind::Integer { min: 100, max: 120_000 }, false, "Milliseconds"),
+            field("incremental", FieldKind::Bool, false, "Only hosts not seen recently"),
+            field("target_ranges", FieldKind::StringArray, false, "CIDR or a-b ranges"),
+        ],
+    },
+    ActionSpec {
+        action: "scan_ports",
+        description: "Check which TCP ports are open on a host",
+        fields: &[
+            field("host", FieldKind::String, true, "IP or hostname"),
+            field("ports", FieldKind::IntegerArray { min: 1, max: 65_535 }, true, "Ports to probe"),
+            field("timeout", FieldKind::Integer { min: 50, max: 30_000 }, false, "Milliseconds per port"),
+        ],
+    },
+    ActionSpec {
+        action: "ping_host",
+        description: "ICMP ping a host",
+        fields: &[
+            field("host", FieldKind::String, true, "IP or hostname"),
+            field("count", FieldKind::Integer { min: 1, max: 20 }, false, "Default 3"),
+        ],
+    },
+    ActionSpec {
+        action: "sql_query",
+        description: "Read-only SELECT against one of the local databases",
+        fields: &[
+            field("source", FieldKind::Enum(&["monitoring", "devices", "chat"]), true, "Database"),
+            field("sql", FieldKind::String, true, "A single SQLite SELECT statement"),
+        ],
+    },
+];
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FileSearchParams {
+    pub query: String,
+    pub search_path: Option<String>,
+    pub extensions: Option<Vec<String>>,
+    pub max_results: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub search_content: Option<bool>,
+    pub content_query: Option<String>,
+    pub fuzzy: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FileReadParams {
+    pub path: String,
+    pub max_chars: Option<usize>,
+    pub offset: Option<u64>,
+    pub encoding: Option<String>,
+    pub tail_lines: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ScanNetworkParams {
+    pub subnet: Option<String>,
+    pub timeout: Option<u64>,
+    pub incremental: Option<bool>,
+    pub target_ranges: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ScanPortsParams {
+    pub host: String,
+    pub ports: Vec<u16>,
+    pub timeout: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PingHostParams {
+    pub host: String,
+    pub count: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SqlQueryParams {
+    pub source: String,
+    pub sql: String,
+}
+
+/// A structured command that passed validation.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "action", content = "params", rename_all = "snake_case")]
+pub enum TypedQuery {
+    FileSearch(FileSearchParams),
+    FileReadContent(FileReadParams),
+    ScanNetwork(ScanNetworkParams),
+    ScanPorts(ScanPortsParams),
+    PingHost(PingHostParams),
+    SqlQuery(SqlQueryParams),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationError {
+    /// Dotted path, e.g. `params.ports[2]`; `$` is the document root
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+fn value_kind(v: &Value) -> &'static str {
+    match v {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn check_integer(path: &str, v: &Value, min: i64, max: i64, errors: &mut Vec<ValidationError>) {
+    let err = |message: String| ValidationError { path: path.to_string(), message };
+    match v.as_i64() {
+        Some(n) if (min..=max).contains(&n) => {}
+        Some(n) => errors.push(err(format!("{} out of range {}..={}", n, min, max))),
+        None if v.is_u64() => errors.push(err(format!("out of range {}..={}", min, max))),
+        None => errors.push(err(format!("expected integer, got {}", value_kind(v)))),
+    }
+}
+
+fn check_field(path: &str, kind: FieldKind, v: &Value, errors: &mut Vec<ValidationError>) {
+    let err = |message: String| ValidationError { path: path.to_string(), message };
+    match kind {
+        FieldKind::String if !v.is_string() => errors.push(err(format!("expected string, got {}", value_kind(v)))),
+        FieldKind::String if v.as_str().is_some_and(|s| s.trim().is_empty()) => {
+            errors.push(err("must not be empty".into()))
+        }
+        FieldKind::Bool if !v.is_boolean() => errors.push(err(format!("expected boolean, got {}", value_kind(v)))),
+        FieldKind::Integer { min, max } => check_integer(path, v, min, max, errors),
+        FieldKind::Enum(allowed) => match v.as_str() {
+            Some(s) if allowed.contains(&s) => {}
+            Some(s) => errors.push(err(format!("unknown value '{}', expected one of {}", s, allowed.join(", ")))),
+            None => errors.push(err(format!("expected string, got {}", value_kind(v)))),
+        },
+        FieldKind::StringArray | FieldKind::IntegerArray { .. } => {
+            let Some(items) = v.as_array() else {
+                errors.push(err(format!("expected array, got {}", value_kind(v))));
+                return;
+            };
+            for (i, item) in items.iter().enumerate() {
+                let item_path = format!("{}[{}]", path, i);
+                match kind {
+                    FieldKind::IntegerArray { min, max } => check_integer(&item_path, item, min, max, errors),
+                    _ if !item.is_string() => errors.push(ValidationError {
+                        path: item_path,
+                        message: format!("expected string, got {}", value_kind(item)),
+                    }),
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Validate LLM output against `ACTIONS`. All problems are reported, each
+/// with its path, e.g. `params.timeout: expected integer, got string`.
+pub fn validate_query(input: &str) -> Result<TypedQuery, Vec<ValidationError>> {
+    let root_err = |message: String| vec![ValidationError { path: "$".into(), message }];
+    let value: Value = serde_json::from_str(input.trim())
+        .map_err(|e| root_err(format!("invalid JSON: {}", e)))?;
+    let Some(obj) = value.as_object() else {
+        return Err(root_err(format!("expected object, got {}", value_kind(&value))));
+    };
+
+    let mut errors = Vec::new();
+    let action = match obj.get("action") {
+        Some(Value::String(a)) => a.as_str(),
+        Some(other) => {
+            return Err(vec![ValidationError {
+                path: "action".into(),
+                message: format!("expected string, got {}", value_kind(other)),
+            }])
+        }
+        None => return Err(vec![ValidationError { path: "action".into(), message: "missing required field".into() }]),
+    };
+    let Some(spec) = ACTIONS.iter().find(|a| a.action == action) else {
+        let known: Vec<&str> = ACTIONS.iter().map(|a| a.action).collect();
+        return Err(vec![ValidationError {
+            path: "action".into(),
+            message: format!("unknown action '{}', expected one of {}", action, known.join(", ")),
+        }]);
+    };
+    for key in obj.keys().filter(|k| *k != "action" && *k != "params") {
+        errors.push(ValidationError { path: key.clone(), message: "unknown field".into() });
+    }
+
+    let empty = Map::new();
+    let params = match obj.get("params") {
+        None | Some(Value::Null) => &empty,
+        Some(Value::Object(p)) => p,
+        Some(other) => {
+            errors.push(ValidationError {
+                path: "params".into(),
+                message: format!("expected object, got {}", value_kind(other)),
+            });
+            return Err(errors);
+        }
+    };
+
+    for f in spec.fields {
+        let path = format!("params.{}", f.name);
+        match params.get(f.name) {
+            None | Some(Value::Null) if f.required => {
+                errors.push(ValidationError { path, message: "missing required field".into() })
+            }
+            None | Some(Value::Null) => {}
+            Some(v) => check_field(&path, f.kind, v, &mut errors),
+        }
+    }
+    for key in params.keys().filter(|k| !spec.fields.iter().any(|f| f.name == k.as_str())) {
+        errors.push(ValidationError { path: format!("params.{}", key), message: "unknown field".into() });
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let typed = json!({ "action": action, "params": Value::Object(params.clone()) });
+    serde_json::from_value(typed).map_err(|e| root_err(e.to_string()))
+}
+
+fn field_schema(kind: FieldKind) -> Value {
+    match kind {
+        FieldKind::String => json!({ "type": "string", "minLength": 1 }),
+        FieldKind::Bool => json!({ "type": "boolean" }),
+        FieldKind::Integer { min, max } => json!({ "type": "integer", "minimum": min, "maximum": max }),
+        FieldKind::StringArray => json!({ "type": "array", "items": { "type": "string" } }),
+        FieldKind::IntegerArray { min, max } => {
+            json!({ "type": "array", "items": { "type": "integer", "minimum": min, "maximum": max } })
+        }
+        FieldKind::Enum(values) => json!({ "type": "string", "enum": values }),
+    }
+}
+
+/// JSON Schema (draft 2020-12) covering every structured action.
+pub fn actions_json_schema() -> Value {
+    let variants: Vec<Value> = ACTIONS
+        .iter()
+        .map(|spec| {
+            let mut properties = Map::new();
+            for f in spec.fields {
+                let mut schema = field_schema(f.kind);
+                schema["description"] = Value::from(f.description);
+                properties.insert(f.name.to_string(), schema);
+            }
+            let required: Vec<&str> = spec.fields.iter().filter(|f| f.required).map(|f| f.name).collect();
+            json!({
+                "type": "object",
+                "description": spec.description,
+                "properties": {
+                    "action": { "const": spec.action },
+                    "params": {
+                        "type": "object",
+                        "properties": properties,
+                        "required": required,
+                        "additionalProperties": false,
+                    },
+                },
+                "required": ["action", "params"],
+                "additionalProperties": false,
+            })
+        })
+        .collect();
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "Broxeen assistant action",
+        "oneOf": variants,
+    })
+}
+
+/// Machine-readable schema of all structured actions, for the LLM system prompt.
+#[tauri::command]
+pub fn query_schema_describe() -> Value {
+    crate::logging::backend_info("Command query_schema_describe invoked");
+    actions_json_schema()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryValidation {
+    pub query: Option<TypedQuery>,
+    pub errors: Vec<ValidationError>,
+}
+
+/// Validate LLM output before the frontend dispatches it.
+#[tauri::command]
+pub fn query_schema_validate(json: String) -> QueryValidation {
+    match validate_query(&json) {
+        Ok(query) => QueryValidation { query: Some(query), errors: Vec::new() },
+        Err(errors) => {
+            crate::logging::backend_warn(format!(
+                "query_schema_validate: {}",
+                errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; ")
+            ));
+            QueryValidation { query: None, errors }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -269,4 +624,70 @@ mod tests {
     fn test_default_monitoring() {
         assert_eq!(detect_data_source("co się dzieje"), DataSource::Monitoring);
     }
+
+    #[test]
+    fn test_validate_query_typed() {
+        let q = validate_query(r#"{"action":"scan_ports","params":{"host":"10.0.0.5","ports":[22,80,554]}}"#).unwrap();
+        assert_eq!(
+            q,
+            TypedQuery::ScanPorts(ScanPortsParams { host: "10.0.0.5".into(), ports: vec![22, 80, 554], timeout: None })
+        );
+        // params may be omitted when nothing is required
+        assert!(matches!(validate_query(r#"{"action":"scan_network"}"#), Ok(TypedQuery::ScanNetwork(_))));
+    }
+
+    #[test]
+    fn test_validate_query_error_paths() {
+        let errors = validate_query(
+            r#"{"action":"scan_ports","params":{"ports":[22,"80",70000],"timeout":"5s","verbose":true}}"#,
+        )
+        .unwrap_err();
+        let msgs: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert!(msgs.contains(&"params.host: missing required field".to_string()), "{:?}", msgs);
+        assert!(msgs.contains(&"params.ports[1]: expected integer, got string".to_string()));
+        assert!(msgs.contains(&"params.ports[2]: 70000 out of range 1..=65535".to_string()));
+        assert!(msgs.contains(&"params.timeout: expected integer, got string".to_string()));
+        assert!(msgs.contains(&"params.verbose: unknown field".to_string()));
+
+        let errors = validate_query(r#"{"action":"rm_rf","params":{}}"#).unwrap_err();
+        assert_eq!(errors[0].path, "action");
+        let errors = validate_query(r#"{"action":"sql_query","params":{"source":"prod","sql":" "}}"#).unwrap_err();
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn test_validate_query_survives_garbage() {
+        let valid = r#"{"action":"file_search","params":{"query":"faktura","extensions":["pdf"],"max_results":20,"fuzzy":true}}"#;
+        assert!(validate_query(valid).is_ok());
+        // Every truncation of a valid document fails cleanly
+        for end in 0..valid.len() {
+            if valid.is_char_boundary(end) {
+                assert!(validate_query(&valid[..end]).is_err(), "prefix {} accepted", end);
+            }
+        }
+        // Type confusion at every position a value can take
+        let confused = ["null", "1", "1.5", "-1", "true", "\"x\"", "[]", "{}", "[1,\"a\"]", "18446744073709551615"];
+        for spec in ACTIONS {
+            for f in spec.fields {
+                for v in confused {
+                    let doc = format!(r#"{{"action":"{}","params":{{"{}":{}}}}}"#, spec.action, f.name, v);
+                    let _ = validate_query(&doc);
+                }
+            }
+        }
+        for doc in ["", "[]", "42", "\"scan\"", "{\"action\":7}", "{\"action\":\"ping_host\",\"params\":[]}"] {
+            assert!(validate_query(doc).is_err(), "{}", doc);
+        }
+    }
+
+    #[test]
+    fn test_actions_json_schema() {
+        let schema = actions_json_schema();
+        let variants = schema["oneOf"].as_array().unwrap();
+        assert_eq!(variants.len(), ACTIONS.len());
+        let ports = &variants[3]["properties"]["params"];
+        assert_eq!(variants[3]["properties"]["action"]["const"], "scan_ports");
+        assert_eq!(ports["required"], json!(["host", "ports"]));
+        assert_eq!(ports["properties"]["ports"]["items"]["maximum"], 65_535);
+    }
 }
@@ -1,7 +1,10 @@
 use serde::{Deserialize, Serialize};
 use std::process::Command;
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::docker::{parse_containers, DockerContainer};
+use crate::error::{BroxeenError, ErrorCode};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RemoteMachine {
     pub host: String,
     pub port: u16,
@@ -11,7 +14,7 @@ pub struct RemoteMachine {
     pub description: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum AuthType {
     Password { password: String },
@@ -70,10 +73,13 @@ pub struct RemoteProcess {
 #[tauri::command]
 pub async fn remote_test_connection(machine: RemoteMachine) -> Result<bool, String> {
     let mut cmd = Command::new("ssh");
-    cmd.arg("-o")
+    cmd.args(crate::ssh::host_key_args())
+        .arg("-o")
         .arg("ConnectTimeout=5")
         .arg("-o")
-        .arg("BatchMode=yes");
+        .arg("BatchMode=yes")
+        .arg("-p")
+        .arg(machine.port.to_string());
 
     match &machine.auth_type {
         AuthType::Password { .. } => {
@@ -110,10 +116,13 @@ pub async fn remote_execute_command(
         }
     }
 
-    cmd.arg("-o")
+    cmd.args(crate::ssh::host_key_args())
+        .arg("-o")
         .arg("ConnectTimeout=10")
         .arg("-o")
         .arg("BatchMode=yes")
+        .arg("-p")
+        .arg(machine.port.to_string())
         .arg(format!("{}@{}", machine.username, machine.host))
         .arg(&command);
 
@@ -283,7 +292,13 @@ pub async fn remote_copy_file(
         }
     }
 
-    cmd.arg("-o").arg("ConnectTimeout=10").arg("-o").arg("BatchMode=yes");
+    cmd.args(crate::ssh::host_key_args())
+        .arg("-o")
+        .arg("ConnectTimeout=10")
+        .arg("-o")
+        .arg("BatchMode=yes")
+        .arg("-P")
+        .arg(machine.port.to_string());
 
     if direction == "upload" {
         cmd.arg(&local_path)
@@ -307,3 +322,171 @@ pub async fn remote_check_docker(machine: RemoteMachine) -> Result<bool, String>
     let result = remote_execute_command(machine, "docker --version".to_string()).await?;
     Ok(result.success)
 }
+
+// ── Docker over SSH ──────────────────────────────────
+
+/// Quote for the remote POSIX shell.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Code a failed remote `docker` call. A socket the SSH user may not open
+/// means Docker is only usable with sudo — a setup issue, not a failure.
+fn remote_docker_error(machine: &RemoteMachine, result: &RemoteCommandResult) -> BroxeenError {
+    let stderr = result.stderr.trim();
+    let lower = stderr.to_lowercase();
+    let details = serde_json::json!({ "host": machine.host, "exit_code": result.exit_code, "stderr": stderr });
+    let error = if result.exit_code == 255 {
+        // ssh itself failed (exit 255), the command never ran
+        if lower.contains("permission denied") {
+            BroxeenError::new(
+                ErrorCode::PermissionDenied,
+                format!("SSH login to {} as {} was refused: {}", machine.host, machine.username, stderr),
+            )
+        } else {
+            BroxeenError::new(ErrorCode::Unreachable, format!("SSH connection to {} failed: {}", machine.host, stderr))
+        }
+    } else if lower.contains("permission denied") && lower.contains("docker") {
+        BroxeenError::not_configured(format!(
+            "Docker on {host} requires root: user {user} cannot access /var/run/docker.sock. \
+             Add the user to the docker group (`sudo usermod -aG docker {user}`) and log in again",
+            host = machine.host,
+            user = machine.username,
+        ))
+    } else if result.exit_code == 127 || lower.contains("command not found") {
+        BroxeenError::tool_missing(format!("Docker is not installed on {}", machine.host))
+    } else if lower.contains("no such container") {
+        BroxeenError::not_found(format!("Container not found on {}: {}", machine.host, stderr))
+    } else {
+        BroxeenError::new(ErrorCode::Internal, format!("Docker command on {} failed: {}", machine.host, stderr))
+    };
+    error.with_details(details)
+}
+
+/// Run `docker <args>` on `machine`; `args` must already be shell-quoted.
+async fn remote_docker(machine: &RemoteMachine, args: &str) -> Result<RemoteCommandResult, BroxeenError> {
+    let result = remote_execute_command(machine.clone(), format!("docker {}", args)).await?;
+    if !result.success {
+        return Err(remote_docker_error(machine, &result));
+    }
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn remote_docker_list_containers(
+    machine: RemoteMachine,
+    all: Option<bool>,
+) -> Result<Vec<DockerContainer>, BroxeenError> {
+    let flags = if all.unwrap_or(false) { " -a" } else { "" };
+    let result = remote_docker(&machine, &format!("ps --format '{{{{json .}}}}'{}", flags)).await?;
+    Ok(parse_containers(&result.stdout)?)
+}
+
+/// `docker start|stop|restart` on the remote host, audited like the local ones.
+async fn remote_docker_action(
+    machine: RemoteMachine,
+    container_id: String,
+    action: &str,
+    done: &str,
+    initiator: Option<String>,
+) -> Result<String, BroxeenError> {
+    let params = serde_json::json!({ "host": machine.host, "container_id": container_id });
+    let result = remote_docker(&machine, &format!("{} {}", action, shell_quote(&container_id)))
+        .await
+        .map(|_| format!("Container {} {} on {}", container_id, done, machine.host));
+    crate::audit::audited(&format!("remote_docker_{}", action), initiator.as_deref(), params, result)
+}
+
+#[tauri::command]
+pub async fn remote_docker_start(
+    machine: RemoteMachine,
+    container_id: String,
+    initiator: Option<String>,
+) -> Result<String, BroxeenError> {
+    remote_docker_action(machine, container_id, "start", "started", initiator).await
+}
+
+#[tauri::command]
+pub async fn remote_docker_stop(
+    machine: RemoteMachine,
+    container_id: String,
+    initiator: Option<String>,
+) -> Result<String, BroxeenError> {
+    remote_docker_action(machine, container_id, "stop", "stopped", initiator).await
+}
+
+#[tauri::command]
+pub async fn remote_docker_restart(
+    machine: RemoteMachine,
+    container_id: String,
+    initiator: Option<String>,
+) -> Result<String, BroxeenError> {
+    remote_docker_action(machine, container_id, "restart", "restarted", initiator).await
+}
+
+/// `tail` limits to the last N lines, as in `docker_get_logs`.
+#[tauri::command]
+pub async fn remote_docker_get_logs(
+    machine: RemoteMachine,
+    container_id: String,
+    tail: Option<u32>,
+) -> Result<String, BroxeenError> {
+    let tail = tail.map(|n| format!("--tail {} ", n)).unwrap_or_default();
+    let result = remote_docker(&machine, &format!("logs {}{}", tail, shell_quote(&container_id))).await?;
+    // `docker logs` replays the container's stderr on ours
+    Ok(result.stdout + &result.stderr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn machine() -> RemoteMachine {
+        RemoteMachine {
+            host: "192.168.1.50".into(),
+            port: 22,
+            username: "pi".into(),
+            auth_type: AuthType::Key { private_key_path: "~/.ssh/id_ed25519".into(), passphrase: None },
+            name: None,
+            description: None,
+        }
+    }
+
+    fn failed(exit_code: i32, stderr: &str) -> RemoteCommandResult {
+        RemoteCommandResult { exit_code, stdout: String::new(), stderr: stderr.into(), success: false }
+    }
+
+    #[test]
+    fn test_remote_docker_errors_are_coded() {
+        let m = machine();
+        let err = remote_docker_error(&m, &failed(1,
+            "permission denied while trying to connect to the Docker daemon socket at unix:///var/run/docker.sock: \
+             Get \"http://%2Fvar%2Frun%2Fdocker.sock/v1.24/containers/json\": dial unix /var/run/docker.sock: connect: permission denied"));
+        assert_eq!(err.code, ErrorCode::NotConfigured);
+        assert!(err.message.contains("usermod -aG docker pi"));
+        assert_eq!(err.details.unwrap()["host"], "192.168.1.50");
+
+        assert_eq!(remote_docker_error(&m, &failed(127, "bash: docker: command not found")).code, ErrorCode::ExternalToolMissing);
+        assert_eq!(remote_docker_error(&m, &failed(255, "ssh: connect to host 192.168.1.50 port 22: No route to host")).code, ErrorCode::Unreachable);
+        assert_eq!(remote_docker_error(&m, &failed(255, "pi@192.168.1.50: Permission denied (publickey).")).code, ErrorCode::PermissionDenied);
+        assert_eq!(remote_docker_error(&m, &failed(1, "Error response from daemon: No such container: web")).code, ErrorCode::NotFound);
+        assert_eq!(remote_docker_error(&m, &failed(1, "Error response from daemon: conflict")).code, ErrorCode::Internal);
+    }
+
+    #[test]
+    fn test_remote_ps_output_parses_like_local() {
+        let stdout = concat!(
+            r#"{"Command":"\"/docker-entrypoint.…\"","CreatedAt":"2026-03-01 10:00:00 +0100 CET","ID":"a1b2c3","Image":"nginx:1.25","Names":"web","Ports":"0.0.0.0:80->80/tcp, :::80->80/tcp","State":"running","Status":"Up 2 hours"}"#,
+            "\n\n",
+            r#"{"CreatedAt":"2026-03-01 09:00:00 +0100 CET","ID":"d4e5f6","Image":"redis:7","Names":"cache","Ports":"","State":"exited","Status":"Exited (0) 1 hour ago"}"#,
+            "\n",
+        );
+        let containers = parse_containers(stdout).unwrap();
+        assert_eq!(containers.len(), 2);
+        assert_eq!(containers[0].name, "web");
+        assert_eq!(containers[0].ports, vec!["0.0.0.0:80->80/tcp", ":::80->80/tcp"]);
+        assert!(containers[1].ports.is_empty());
+        assert!(parse_containers("not json").is_err());
+        assert_eq!(shell_quote("web'; rm -rf /"), "'web'\\''; rm -rf /'");
+    }
+}
@@ -0,0 +1,427 @@
+//! remote_metrics.rs — Periodic CPU / memory / disk / temperature sampling
+//! of remote machines, stored in `broxeen_remote_metrics.db`.
+//!
+//! Each polled host gets one OpenSSH control master (`ControlMaster=auto`),
+//! so every sample reuses the same authenticated session. A poll runs inline
+//! in the host's loop with `MissedTickBehavior::Skip`: when it is still
+//! running at the next tick, that cycle is skipped rather than queued.
+//! Failed polls back off exponentially (capped at `MAX_BACKOFF_SECS`) and the
+//! master is re-established on the next attempt. Samples older than
+//! `remote_metrics_retention_days` are pruned after each successful poll.
+//! Control sockets live in `<data dir>/broxeen/ssh-control` (mode 0700) and
+//! host keys are checked against Broxeen's own `known_hosts`.
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+use crate::logging::{backend_info, backend_warn};
+use crate::remote_machine::{AuthType, RemoteMachine};
+
+const METRICS_DB: &str = "broxeen_remote_metrics.db";
+const DEFAULT_INTERVAL_SECS: u64 = 60;
+const MIN_INTERVAL_SECS: u64 = 10;
+const MAX_BACKOFF_SECS: u64 = 600;
+
+/// Metric names accepted by `remote_metrics_query`.
+pub const METRICS: &[&str] = &["cpu_percent", "load1", "mem_percent", "disk_percent", "temp_c"];
+
+/// One remote exec per poll; `/proc/stat` is read twice for CPU utilisation.
+const POLL_SCRIPT: &str = r#"
+head -1 /proc/stat; sleep 1; head -1 /proc/stat
+read l1 _ < /proc/loadavg; echo "load1:$l1"
+awk '/^MemTotal:/{t=$2} /^MemAvailable:/{a=$2} END{if(t>0) printf "mem_percent:%.1f\n", (t-a)*100/t}' /proc/meminfo
+df -P / | awk 'NR==2{sub("%","",$5); print "disk_percent:"$5}'
+max=""; for z in /sys/class/thermal/thermal_zone*/temp; do
+  [ -r "$z" ] || continue; t=$(cat "$z"); [ -z "$max" ] || [ "$t" -gt "$max" ] && max=$t
+done
+[ -n "$max" ] && echo "temp_c:$max"
+true
+"#;
+
+lazy_static::lazy_static! {
+    static ref CONN: Mutex<Option<Connection>> = Mutex::new(None);
+    static ref POLLERS: Mutex<HashMap<String, Poller>> = Mutex::new(HashMap::new());
+}
+
+struct Poller {
+    stop: Arc<Notify>,
+    interval_secs: u64,
+    started_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricPoint {
+    /// Unix ms
+    pub ts: i64,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteMetricsStatus {
+    pub host: String,
+    pub interval_secs: u64,
+    pub started_at: i64,
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+// ── Parsing ──────────────────────────────────────────
+
+/// (busy, total) jiffies from a `cpu  …` line of /proc/stat.
+fn cpu_jiffies(line: &str) -> Option<(u64, u64)> {
+    let mut fields = line.split_whitespace();
+    if fields.next()? != "cpu" {
+        return None;
+    }
+    let values: Vec<u64> = fields.filter_map(|f| f.parse().ok()).collect();
+    if values.len() < 4 {
+        return None;
+    }
+    let total: u64 = values.iter().sum();
+    // idle + iowait
+    let idle = values[3] + values.get(4).copied().unwrap_or(0);
+    Some((total - idle, total))
+}
+
+/// Turn the poll script's output into `(metric, value)` samples.
+fn parse_poll_output(stdout: &str) -> Vec<(&'static str, f64)> {
+    let mut samples = Vec::new();
+    let cpu: Vec<(u64, u64)> = stdout.lines().filter_map(cpu_jiffies).collect();
+    if let [(busy0, total0), (busy1, total1)] = cpu[..] {
+        if total1 > total0 {
+            let pct = (busy1.saturating_sub(busy0)) as f64 * 100.0 / (total1 - total0) as f64;
+            samples.push(("cpu_percent", (pct * 10.0).round() / 10.0));
+        }
+    }
+    for line in stdout.lines() {
+        let Some((key, value)) = line.split_once(':') else { continue };
+        let Some(metric) = METRICS.iter().find(|m| **m == key) else { continue };
+        let Ok(mut value) = value.trim().parse::<f64>() else { continue };
+        if *metric == "temp_c" {
+            // Thermal zones report millidegrees
+            value /= 1000.0;
+        }
+        samples.push((*metric, value));
+    }
+    samples
+}
+
+fn backoff_secs(interval_secs: u64, failures: u32) -> u64 {
+    interval_secs
+        .saturating_mul(1u64 << failures.min(10))
+        .min(MAX_BACKOFF_SECS.max(interval_secs))
+}
+
+// ── SSH ──────────────────────────────────────────────
+
+/// Directory for the control sockets, owner-only: anyone who can open a
+/// socket can run commands over the authenticated session.
+fn control_dir() -> Result<PathBuf, String> {
+    let dir = data_dir().join("ssh-control");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))
+            .map_err(|e| format!("Cannot restrict {}: {}", dir.display(), e))?;
+    }
+    Ok(dir)
+}
+
+/// `ssh` with the host's control master options; the caller appends the destination.
+fn ssh_base(machine: &RemoteMachine, persist_secs: u64) -> Result<Command, String> {
+    let mut cmd = Command::new("ssh");
+    match &machine.auth_type {
+        AuthType::Password { .. } => {
+            return Err("Password authentication not supported for metrics polling. Use SSH key authentication.".to_string());
+        }
+        AuthType::Key { private_key_path, .. } => {
+            cmd.arg("-i").arg(private_key_path);
+        }
+    }
+    // `%C` hashes host, port and user, keeping the path under the unix socket limit
+    let control_path = control_dir()?.join("%C");
+    cmd.args(crate::ssh::host_key_args())
+        .args(["-o", "ConnectTimeout=10", "-o", "BatchMode=yes", "-o", "ControlMaster=auto"])
+        .arg("-o")
+        .arg(format!("ControlPath={}", control_path.display()))
+        .arg("-o")
+        .arg(format!("ControlPersist={}", persist_secs))
+        .arg("-p")
+        .arg(machine.port.to_string());
+    Ok(cmd)
+}
+
+fn poll_once(machine: &RemoteMachine, interval_secs: u64) -> Result<Vec<(&'static str, f64)>, String> {
+    // Keep the master alive across a couple of missed cycles
+    let output = ssh_base(machine, interval_secs * 3 + 30)?
+        .arg(format!("{}@{}", machine.username, machine.host))
+        .arg(POLL_SCRIPT)
+        .output()
+        .map_err(|e| format!("SSH command failed: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "exit {}: {}",
+            output.status.code().unwrap_or(-1),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let samples = parse_poll_output(&String::from_utf8_lossy(&output.stdout));
+    if samples.is_empty() {
+        return Err("no metrics in poll output".into());
+    }
+    Ok(samples)
+}
+
+fn close_master(machine: &RemoteMachine) {
+    if let Ok(mut cmd) = ssh_base(machine, 0) {
+        let _ = cmd.args(["-O", "exit"]).arg(format!("{}@{}", machine.username, machine.host)).output();
+    }
+}
+
+// ── Storage ──────────────────────────────────────────
+
+fn data_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .or_else(dirs::data_dir)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("broxeen")
+}
+
+fn db_path() -> PathBuf {
+    let base = data_dir();
+    let _ = std::fs::create_dir_all(&base);
+    base.join(METRICS_DB)
+}
+
+fn init_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS remote_metrics (
+            host TEXT NOT NULL,
+            ts INTEGER NOT NULL,
+            metric TEXT NOT NULL,
+            value REAL NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_remote_metrics_series ON remote_metrics(host, metric, ts);
+        CREATE INDEX IF NOT EXISTS idx_remote_metrics_ts ON remote_metrics(ts);",
+    )
+}
+
+fn with_conn<T>(f: impl FnOnce(&Connection) -> Result<T, String>) -> Result<T, String> {
+    let mut guard = CONN.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.is_none() {
+        let path = db_path();
+        let conn = Connection::open(&path).map_err(|e| format!("Metrics DB open failed: {}", e))?;
+        init_schema(&conn).map_err(|e| format!("Metrics DB schema failed: {}", e))?;
+        backend_info(format!("Remote metrics store opened at {}", path.display()));
+        *guard = Some(conn);
+    }
+    f(guard.as_ref().expect("metrics connection initialised"))
+}
+
+fn store_samples(
+    conn: &Connection,
+    host: &str,
+    ts: i64,
+    samples: &[(&str, f64)],
+    retention_days: u32,
+) -> rusqlite::Result<usize> {
+    for (metric, value) in samples {
+        conn.execute(
+            "INSERT INTO remote_metrics (host, ts, metric, value) VALUES (?1, ?2, ?3, ?4)",
+            params![host, ts, metric, value],
+        )?;
+    }
+    let cutoff = ts - retention_days.max(1) as i64 * 86_400_000;
+    conn.execute("DELETE FROM remote_metrics WHERE ts < ?1", [cutoff])
+}
+
+fn query_series(conn: &Connection, host: &str, metric: &str, since: i64) -> rusqlite::Result<Vec<MetricPoint>> {
+    let mut stmt = conn.prepare(
+        "SELECT ts, value FROM remote_metrics WHERE host = ?1 AND metric = ?2 AND ts >= ?3 ORDER BY ts",
+    )?;
+    let rows = stmt.query_map(params![host, metric, since], |r| Ok(MetricPoint { ts: r.get(0)?, value: r.get(1)? }))?;
+    rows.collect()
+}
+
+// ── Poll loop ────────────────────────────────────────
+
+async fn poll_loop(machine: RemoteMachine, interval_secs: u64, stop: Arc<Notify>) {
+    let host = machine.host.clone();
+    let machine = Arc::new(machine);
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs));
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut failures = 0u32;
+    let mut retry_at: Option<Instant> = None;
+    let mut retention = crate::settings::load_settings().remote_metrics_retention_days;
+    let mut settings_rx = crate::settings::subscribe_settings();
+
+    loop {
+        tokio::select! {
+            _ = stop.notified() => break,
+            Ok(change) = settings_rx.recv() => {
+                retention = change.settings.remote_metrics_retention_days;
+                continue;
+            }
+            _ = ticker.tick() => {}
+        }
+        if retry_at.is_some_and(|at| Instant::now() < at) {
+            continue;
+        }
+
+        let m = Arc::clone(&machine);
+        let polled = tokio::task::spawn_blocking(move || poll_once(&m, interval_secs))
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()));
+
+        match polled {
+            Ok(samples) => {
+                if failures > 0 {
+                    backend_info(format!("remote_metrics[{}]: reconnected after {} failure(s)", host, failures));
+                }
+                failures = 0;
+                retry_at = None;
+                if let Err(e) = with_conn(|conn| {
+                    store_samples(conn, &host, now_ms(), &samples, retention).map_err(|e| e.to_string())
+                }) {
+                    backend_warn(format!("remote_metrics[{}]: store failed: {}", host, e));
+                }
+            }
+            Err(e) => {
+                failures += 1;
+                let wait = backoff_secs(interval_secs, failures);
+                retry_at = Some(Instant::now() + Duration::from_secs(wait));
+                backend_warn(format!(
+                    "remote_metrics[{}]: poll failed ({} in a row, retry in {}s): {}",
+                    host, failures, wait, e
+                ));
+                // Drop a possibly stale master so the retry reconnects
+                let m = Arc::clone(&machine);
+                let _ = tokio::task::spawn_blocking(move || close_master(&m)).await;
+            }
+        }
+    }
+
+    let _ = tokio::task::spawn_blocking(move || close_master(&machine)).await;
+    backend_info(format!("remote_metrics[{}]: stopped", host));
+}
+
+// ── Tauri commands ───────────────────────────────────
+
+/// Start sampling `machine` every `interval_secs` (default 60, min 10).
+#[tauri::command]
+pub fn remote_metrics_start(machine: RemoteMachine, interval_secs: Option<u64>) -> Result<RemoteMetricsStatus, String> {
+    if let AuthType::Password { .. } = machine.auth_type {
+        return Err("Password authentication not supported for metrics polling. Use SSH key authentication.".to_string());
+    }
+    let interval_secs = interval_secs.unwrap_or(DEFAULT_INTERVAL_SECS).max(MIN_INTERVAL_SECS);
+    let host = machine.host.clone();
+
+    let mut pollers = POLLERS.lock().unwrap_or_else(|e| e.into_inner());
+    if pollers.contains_key(&host) {
+        return Err(format!("Metrics polling already running for {}", host));
+    }
+    let stop = Arc::new(Notify::new());
+    let status = RemoteMetricsStatus { host: host.clone(), interval_secs, started_at: now_ms() };
+    pollers.insert(
+        host.clone(),
+        Poller { stop: Arc::clone(&stop), interval_secs, started_at: status.started_at },
+    );
+    drop(pollers);
+
+    backend_info(format!("remote_metrics_start: {} every {}s", host, interval_secs));
+    tauri::async_runtime::spawn(poll_loop(machine, interval_secs, stop));
+    Ok(status)
+}
+
+#[tauri::command]
+pub fn remote_metrics_stop(host: String) -> Result<bool, String> {
+    let poller = POLLERS.lock().unwrap_or_else(|e| e.into_inner()).remove(&host);
+    backend_info(format!("remote_metrics_stop: {} (running={})", host, poller.is_some()));
+    match poller {
+        Some(p) => {
+            p.stop.notify_one();
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+#[tauri::command]
+pub fn remote_metrics_status() -> Vec<RemoteMetricsStatus> {
+    POLLERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .map(|(host, p)| RemoteMetricsStatus { host: host.clone(), interval_secs: p.interval_secs, started_at: p.started_at })
+        .collect()
+}
+
+/// Time series of `metric` for `host` since `since` (RFC 3339 or Unix ms;
+/// default: last 24 h).
+#[tauri::command]
+pub fn remote_metrics_query(host: String, metric: String, since: Option<String>) -> Result<Vec<MetricPoint>, String> {
+    if !METRICS.contains(&metric.as_str()) {
+        return Err(format!("Nieznana metryka '{}' (dostępne: {})", metric, METRICS.join(", ")));
+    }
+    let since_ms = match since.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        None => now_ms() - 86_400_000,
+        Some(s) => match s.parse::<i64>() {
+            Ok(ms) => ms,
+            Err(_) => chrono::DateTime::parse_from_rfc3339(s)
+                .map_err(|_| format!("Nieprawidłowa data 'since': {}", s))?
+                .timestamp_millis(),
+        },
+    };
+    with_conn(|conn| query_series(conn, &host, &metric, since_ms).map_err(|e| e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_poll_output() {
+        let out = "cpu  100 0 100 700 100 0 0 0 0 0\n\
+                   cpu  150 0 150 750 150 0 0 0 0 0\n\
+                   load1:0.42\nmem_percent:63.5\ndisk_percent:81\ntemp_c:54250\nbogus:1\n";
+        let samples = parse_poll_output(out);
+        // busy +100 of total +200
+        assert_eq!(samples[0], ("cpu_percent", 50.0));
+        assert!(samples.contains(&("load1", 0.42)));
+        assert!(samples.contains(&("disk_percent", 81.0)));
+        assert!(samples.contains(&("temp_c", 54.25)));
+        assert_eq!(samples.len(), 5);
+
+        assert_eq!(backoff_secs(60, 1), 120);
+        assert_eq!(backoff_secs(60, 8), MAX_BACKOFF_SECS);
+        assert_eq!(backoff_secs(900, 3), 900);
+    }
+
+    #[test]
+    fn test_store_prunes_and_queries() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let day = 86_400_000;
+        let now = 100 * day;
+        store_samples(&conn, "nas", now - 10 * day, &[("cpu_percent", 5.0)], 30).unwrap();
+        store_samples(&conn, "nas", now - day, &[("cpu_percent", 7.0), ("temp_c", 40.0)], 30).unwrap();
+        store_samples(&conn, "pi", now, &[("cpu_percent", 90.0)], 30).unwrap();
+
+        let series = query_series(&conn, "nas", "cpu_percent", 0).unwrap();
+        assert_eq!(series.iter().map(|p| p.value).collect::<Vec<_>>(), vec![5.0, 7.0]);
+
+        // Tighter retention drops the 10-day-old sample
+        assert_eq!(store_samples(&conn, "nas", now, &[], 7).unwrap(), 1);
+        assert_eq!(query_series(&conn, "nas", "cpu_percent", 0).unwrap().len(), 1);
+        assert_eq!(query_series(&conn, "nas", "cpu_percent", now).unwrap().len(), 0);
+    }
+}
@@ -1,12 +1,23 @@
-/// RSS/Atom feed parsing for Broxeen.
+/// RSS/Atom/JSON Feed parsing for Broxeen.
 ///
-/// Provides structured extraction from RSS 2.0 and Atom feeds
-/// with proper XML parsing and article formatting.
+/// Provides structured extraction from RSS 2.0, Atom and JSON Feed
+/// documents, normalized into `Feed`/`FeedItem`, and article formatting.
+/// Dates are normalized to RFC 3339; an unparseable date becomes `None`
+/// instead of failing the feed. With `fetch_full_content` the linked
+/// articles are fetched and run through readability extraction.
 
 use crate::logging::{backend_info, backend_warn};
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use tauri::command;
 
+/// Items enriched with the full article, whatever `max_items` says
+const FULL_CONTENT_MAX_ITEMS: usize = 10;
+/// Article fetches running at once
+const FULL_CONTENT_CONCURRENCY: usize = 3;
+const FULL_CONTENT_TIMEOUT: Duration = Duration::from_secs(15);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RssItem {
     pub title: String,
@@ -15,6 +26,8 @@ pub struct RssItem {
     pub pub_date: Option<String>,
     pub guid: Option<String>,
     pub author: Option<String>,
+    /// `content:encoded`
+    pub content: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,6 +62,89 @@ pub struct AtomFeed {
     pub entries: Vec<AtomEntry>,
 }
 
+/// One item of any feed format.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct FeedItem {
+    pub title: String,
+    pub link: Option<String>,
+    pub guid: Option<String>,
+    /// RFC 3339; `None` when the feed has no date or it could not be parsed
+    pub published: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub content_html: Option<String>,
+    /// Readable text of the linked article (`fetch_full_content`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub full_text: Option<String>,
+}
+
+impl FeedItem {
+    /// Dedup key: guid, else link, else title.
+    pub fn key(&self) -> String {
+        self.guid
+            .as_deref()
+            .or(self.link.as_deref())
+            .filter(|k| !k.is_empty())
+            .unwrap_or(&self.title)
+            .to_string()
+    }
+}
+
+/// A feed of any supported format.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Feed {
+    pub title: String,
+    pub description: Option<String>,
+    pub link: Option<String>,
+    pub items: Vec<FeedItem>,
+}
+
+impl From<RssFeed> for Feed {
+    fn from(feed: RssFeed) -> Self {
+        let items = feed
+            .items
+            .into_iter()
+            .map(|i| FeedItem {
+                title: i.title,
+                link: i.link,
+                guid: i.guid,
+                published: i.pub_date.as_deref().and_then(normalize_date),
+                author: i.author,
+                summary: i.description,
+                content_html: i.content,
+                full_text: None,
+            })
+            .collect();
+        Feed { title: feed.title, description: feed.description, link: feed.link, items }
+    }
+}
+
+impl From<AtomFeed> for Feed {
+    fn from(feed: AtomFeed) -> Self {
+        let items = feed
+            .entries
+            .into_iter()
+            .map(|e| FeedItem {
+                title: e.title,
+                link: e.link,
+                guid: e.id,
+                published: e
+                    .published
+                    .as_deref()
+                    .and_then(normalize_date)
+                    .or_else(|| e.updated.as_deref().and_then(normalize_date)),
+                author: e.author,
+                summary: e.summary,
+                content_html: e.content,
+                full_text: None,
+            })
+            .collect();
+        Feed { title: feed.title, description: feed.subtitle, link: feed.link, items }
+    }
+}
+
 /// Parse RSS 2.0 feed from XML content
 pub fn parse_rss_feed(xml_content: &str) -> Result<RssFeed, String> {
     let document = roxmltree::Document::parse(xml_content)
@@ -81,7 +177,9 @@ pub fn parse_rss_feed(xml_content: &str) -> Result<RssFeed, String> {
             description: extract_text(&item_node, "description"),
             pub_date: extract_text(&item_node, "pubDate"),
             guid: extract_text(&item_node, "guid"),
-            author: extract_text(&item_node, "author"),
+            // `dc:creator` is what most feeds use instead of <author>
+            author: extract_text(&item_node, "author").or_else(|| extract_text(&item_node, "creator")),
+            content: extract_text(&item_node, "encoded"),
         };
         items.push(item);
     }
@@ -117,6 +215,8 @@ pub fn parse_atom_feed(xml_content: &str) -> Result<AtomFeed, String> {
     let updated = extract_text(&root, "updated");
     let language = root.attribute("xml:lang").map(|s| s.to_string());
 
+    let feed_author = extract_atom_author(&root);
+
     // Extract entries
     let mut entries = Vec::new();
     for entry_node in root.children().filter(|n| n.tag_name().name() == "entry") {
@@ -128,7 +228,7 @@ pub fn parse_atom_feed(xml_content: &str) -> Result<AtomFeed, String> {
             published: extract_text(&entry_node, "published"),
             updated: extract_text(&entry_node, "updated"),
             id: extract_text(&entry_node, "id"),
-            author: extract_atom_author(&entry_node),
+            author: extract_atom_author(&entry_node).or_else(|| feed_author.clone()),
         };
         entries.push(entry);
     }
@@ -156,10 +256,15 @@ fn extract_text(parent: &roxmltree::Node, tag_name: &str) -> Option<String> {
         })
 }
 
-/// Extract Atom link (href attribute)
+/// Extract Atom link (href attribute), preferring `rel="alternate"` over
+/// `self`/`enclosure` links
 fn extract_atom_link(parent: &roxmltree::Node) -> Option<String> {
-    parent.children()
-        .find(|n| n.tag_name().name() == "link")
+    let links: Vec<_> = parent.children()
+        .filter(|n| n.tag_name().name() == "link")
+        .collect();
+    links.iter()
+        .find(|n| n.attribute("rel").is_none_or(|rel| rel == "alternate"))
+        .or(links.first())
         .and_then(|n| n.attribute("href"))
         .map(|s| s.to_string())
 }
@@ -171,8 +276,148 @@ fn extract_atom_author(parent: &roxmltree::Node) -> Option<String> {
         .and_then(|author_node| extract_text(&author_node, "name"))
 }
 
-/// Format RSS feed as readable text
-pub fn format_rss_feed(feed: &RssFeed, max_items: usize) -> String {
+/// Parse JSON Feed (https://jsonfeed.org) 1.0/1.1
+pub fn parse_json_feed(content: &str) -> Result<Feed, String> {
+    let root: serde_json::Value = serde_json::from_str(content)
+        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+
+    if !root["version"].as_str().is_some_and(|v| v.contains("jsonfeed.org")) {
+        return Err("Not a JSON Feed".to_string());
+    }
+
+    let title = json_text(&root["title"]).unwrap_or_else(|| "Untitled JSON Feed".to_string());
+    let feed_author = json_author(&root);
+
+    let items: Vec<FeedItem> = root["items"]
+        .as_array()
+        .map(|items| items.iter().map(|item| {
+            let summary = json_text(&item["summary"]).or_else(|| json_text(&item["content_text"]));
+            FeedItem {
+                // Titles are optional in JSON Feed (microblogs); use the start of the text
+                title: json_text(&item["title"])
+                    .or_else(|| summary.as_deref().map(|s| truncate_chars(&clean_html_text(s), 80)))
+                    .unwrap_or_else(|| "Untitled".to_string()),
+                link: json_text(&item["url"]).or_else(|| json_text(&item["external_url"])),
+                guid: json_text(&item["id"]),
+                published: json_text(&item["date_published"])
+                    .or_else(|| json_text(&item["date_modified"]))
+                    .as_deref()
+                    .and_then(normalize_date),
+                author: json_author(item).or_else(|| feed_author.clone()),
+                summary,
+                content_html: json_text(&item["content_html"]),
+                full_text: None,
+            }
+        }).collect())
+        .unwrap_or_default();
+
+    backend_info(format!("Parsed JSON Feed: {} with {} items", title, items.len()));
+
+    Ok(Feed {
+        title,
+        description: json_text(&root["description"]),
+        link: json_text(&root["home_page_url"]),
+        items,
+    })
+}
+
+/// Non-empty string (or number, e.g. numeric ids) from a JSON value
+fn json_text(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) => Some(s.trim().to_string()).filter(|s| !s.is_empty()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// `author.name` (1.0) or the first of `authors[].name` (1.1)
+fn json_author(value: &serde_json::Value) -> Option<String> {
+    json_text(&value["author"]["name"]).or_else(|| json_text(&value["authors"][0]["name"]))
+}
+
+/// Feed date → RFC 3339. Accepts RFC 3339, RFC 2822 and a bare
+/// `YYYY-MM-DD HH:MM:SS` (taken as UTC); anything else is `None`.
+fn normalize_date(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    if let Ok(date) = chrono::DateTime::parse_from_rfc3339(raw) {
+        return Some(date.to_rfc3339());
+    }
+    if let Ok(date) = chrono::DateTime::parse_from_rfc2822(raw) {
+        return Some(date.to_rfc3339());
+    }
+    chrono::NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .map(|naive| naive.and_utc().to_rfc3339())
+}
+
+/// Parse any supported feed into the common model
+pub fn parse_feed(content: &str) -> Result<Feed, String> {
+    match detect_feed_type(content) {
+        Some("rss") => parse_rss_feed(content).map(Feed::from),
+        Some("atom") => parse_atom_feed(content).map(Feed::from),
+        Some("json") => parse_json_feed(content),
+        _ => Err("Content does not appear to be RSS, Atom or JSON Feed".to_string()),
+    }
+}
+
+/// Fill `full_text` of the first `limit` items (at most
+/// `FULL_CONTENT_MAX_ITEMS`) with the readable text of their links.
+/// Each link goes through `http_policy` (robots.txt, per-host throttle);
+/// disallowed links and failures are logged and leave the item as it was.
+pub async fn fetch_item_articles(items: &mut [FeedItem], limit: usize) {
+    let client = match crate::http_client::client(FULL_CONTENT_TIMEOUT) {
+        Ok(client) => client,
+        Err(e) => {
+            backend_warn(format!("Full content fetch skipped: {}", e));
+            return;
+        }
+    };
+
+    let jobs: Vec<(usize, String)> = items
+        .iter()
+        .take(limit.min(FULL_CONTENT_MAX_ITEMS))
+        .enumerate()
+        .filter_map(|(i, item)| item.link.clone().map(|link| (i, link)))
+        .collect();
+
+    let results: Vec<(usize, Option<String>)> = futures::stream::iter(jobs)
+        .map(|(i, link)| {
+            let client = client.clone();
+            async move { (i, fetch_article_text(&client, &link).await) }
+        })
+        .buffer_unordered(FULL_CONTENT_CONCURRENCY)
+        .collect()
+        .await;
+
+    for (i, text) in results {
+        items[i].full_text = text;
+    }
+}
+
+async fn fetch_article_text(client: &reqwest::Client, url: &str) -> Option<String> {
+    if let Err(e) = crate::http_policy::before_fetch(url).await {
+        backend_info(format!("Full content skipped for {}: {}", url, e));
+        return None;
+    }
+    let response = match client.get(url).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            backend_warn(format!("Full content fetch failed for {}: {}", url, e));
+            return None;
+        }
+    };
+    if !response.status().is_success() {
+        backend_warn(format!("Full content fetch failed for {}: HTTP {}", url, response.status()));
+        return None;
+    }
+    let final_url = response.url().to_string();
+    let html = response.text().await.ok()?;
+    let (_, text) = crate::content_extraction::extract_tier1(&html, url, &final_url);
+    Some(text).filter(|t| !t.trim().is_empty())
+}
+
+/// Format a feed as readable text
+pub fn format_feed(feed: &Feed, max_items: usize) -> String {
     let mut result = String::new();
     
     result.push_str(&format!("📰 **{}**\n\n", feed.title));
@@ -185,32 +430,26 @@ pub fn format_rss_feed(feed: &RssFeed, max_items: usize) -> String {
         result.push_str(&format!("🔗 {}\n\n", link));
     }
 
-    let items_to_show = feed.items.iter().take(max_items);
-    
-    for (i, item) in items_to_show.enumerate() {
+    for (i, item) in feed.items.iter().take(max_items).enumerate() {
         result.push_str(&format!("**{}. {}**\n", i + 1, item.title));
         
         if let Some(link) = &item.link {
             result.push_str(&format!("🔗 {}\n", link));
         }
         
-        if let Some(pub_date) = &item.pub_date {
-            result.push_str(&format!("📅 {}\n", pub_date));
+        if let Some(published) = &item.published {
+            result.push_str(&format!("📅 {}\n", published));
         }
         
         if let Some(author) = &item.author {
             result.push_str(&format!("✍️ {}\n", author));
         }
         
-        if let Some(description) = &item.description {
-            // Clean up HTML tags and truncate
-            let clean_desc = clean_html_text(description);
-            let truncated = if clean_desc.len() > 300 {
-                format!("{}...", &clean_desc[..300])
-            } else {
-                clean_desc
-            };
-            result.push_str(&format!("{}\n", truncated));
+        // Full article when fetched, else the feed's own summary/content
+        if let Some(text) = &item.full_text {
+            result.push_str(&format!("{}\n", truncate_chars(text, 2000)));
+        } else if let Some(content) = item.summary.as_ref().or(item.content_html.as_ref()) {
+            result.push_str(&format!("{}\n", truncate_chars(&clean_html_text(content), 300)));
         }
         
         result.push('\n');
@@ -223,57 +462,13 @@ pub fn format_rss_feed(feed: &RssFeed, max_items: usize) -> String {
     result
 }
 
-/// Format Atom feed as readable text
-pub fn format_atom_feed(feed: &AtomFeed, max_items: usize) -> String {
-    let mut result = String::new();
-    
-    result.push_str(&format!("🗞️ **{}**\n\n", feed.title));
-    
-    if let Some(subtitle) = &feed.subtitle {
-        result.push_str(&format!("{}\n\n", subtitle));
-    }
-    
-    if let Some(link) = &feed.link {
-        result.push_str(&format!("🔗 {}\n\n", link));
-    }
-
-    let entries_to_show = feed.entries.iter().take(max_items);
-    
-    for (i, entry) in entries_to_show.enumerate() {
-        result.push_str(&format!("**{}. {}**\n", i + 1, entry.title));
-        
-        if let Some(link) = &entry.link {
-            result.push_str(&format!("🔗 {}\n", link));
-        }
-        
-        // Use published or updated date
-        if let Some(date) = entry.published.as_ref().or(entry.updated.as_ref()) {
-            result.push_str(&format!("📅 {}\n", date));
-        }
-        
-        if let Some(author) = &entry.author {
-            result.push_str(&format!("✍️ {}\n", author));
-        }
-        
-        // Use summary or content
-        if let Some(content) = entry.summary.as_ref().or(entry.content.as_ref()) {
-            let clean_desc = clean_html_text(content);
-            let truncated = if clean_desc.len() > 300 {
-                format!("{}...", &clean_desc[..300])
-            } else {
-                clean_desc
-            };
-            result.push_str(&format!("{}\n", truncated));
-        }
-        
-        result.push('\n');
-    }
-    
-    if feed.entries.len() > max_items {
-        result.push_str(&format!("... i {} więcej wpisów\n", feed.entries.len() - max_items));
+/// First `max` characters, with "..." when cut
+fn truncate_chars(text: &str, max: usize) -> String {
+    if text.chars().count() > max {
+        format!("{}...", text.chars().take(max).collect::<String>())
+    } else {
+        text.to_string()
     }
-    
-    result
 }
 
 /// Clean HTML tags from text (basic implementation)
@@ -289,8 +484,13 @@ fn clean_html_text(html: &str) -> String {
         .join(" ")
 }
 
-/// Detect if content is RSS or Atom feed
+/// Detect if content is RSS, Atom or JSON Feed
 pub fn detect_feed_type(content: &str) -> Option<&'static str> {
+    let trimmed = content.trim_start();
+    if trimmed.starts_with('{') {
+        return trimmed.contains("jsonfeed.org").then_some("json");
+    }
+
     let content_lower = content.to_lowercase();
     
     if content_lower.contains("<rss") && content_lower.contains("<channel>") {
@@ -302,22 +502,30 @@ pub fn detect_feed_type(content: &str) -> Option<&'static str> {
     }
 }
 
-/// Parse any feed (RSS or Atom) and return formatted text
-pub fn parse_and_format_feed(content: &str, max_items: usize) -> Result<String, String> {
-    let feed_type = detect_feed_type(content)
-        .ok_or_else(|| "Content does not appear to be RSS or Atom feed".to_string())?;
+/// Tauri command to parse RSS/Atom/JSON feed; `fetch_full_content` adds the
+/// readable text of the first items' articles
+#[command]
+pub async fn parse_rss_feed_command(
+    url: String,
+    content: String,
+    max_items: Option<usize>,
+    fetch_full_content: Option<bool>,
+) -> Result<String, String> {
+    let max_items = max_items.unwrap_or(10);
     
-    match feed_type {
-        "rss" => {
-            let feed = parse_rss_feed(content)?;
-            Ok(format_rss_feed(&feed, max_items))
-        }
-        "atom" => {
-            let feed = parse_atom_feed(content)?;
-            Ok(format_atom_feed(&feed, max_items))
-        }
-        _ => Err("Unsupported feed type".to_string()),
+    backend_info(format!("Parsing feed: {} (max_items: {})", url, max_items));
+    
+    let mut feed = parse_feed(&content).map_err(|error| {
+        backend_warn(format!("Failed to parse feed {}: {}", url, error));
+        error
+    })?;
+    backend_info(format!("Successfully parsed feed: {} items extracted", feed.items.len()));
+
+    if fetch_full_content.unwrap_or(false) {
+        fetch_item_articles(&mut feed.items, max_items).await;
     }
+
+    Ok(format_feed(&feed, max_items))
 }
 
 #[cfg(test)]
@@ -329,10 +537,13 @@ mod tests {
         let rss_content = r#"<rss version="2.0"><channel><title>Test</title></channel></rss>"#;
         let atom_content = r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>Test</title></feed>"#;
         let html_content = r#"<html><head><title>Test</title></head></html>"#;
+        let json_content = r#"{"version": "https://jsonfeed.org/version/1.1", "items": []}"#;
         
         assert_eq!(detect_feed_type(rss_content), Some("rss"));
         assert_eq!(detect_feed_type(atom_content), Some("atom"));
+        assert_eq!(detect_feed_type(json_content), Some("json"));
         assert_eq!(detect_feed_type(html_content), None);
+        assert_eq!(detect_feed_type(r#"{"items": []}"#), None);
     }
 
     #[test]
@@ -341,29 +552,73 @@ mod tests {
         let cleaned = clean_html_text(html);
         assert_eq!(cleaned, "Hello world!");
     }
-}
 
-/// Tauri command to parse RSS/Atom feed
-#[command]
-pub fn parse_rss_feed_command(url: String, content: String, max_items: Option<usize>) -> Result<String, String> {
-    let max_items = max_items.unwrap_or(10);
-    
-    backend_info(format!("Parsing feed: {} (max_items: {})", url, max_items));
-    
-    match parse_and_format_feed(&content, max_items) {
-        Ok(formatted_content) => {
-            backend_info(format!("Successfully parsed feed: {} items extracted", 
-                if formatted_content.matches("**").count() > 0 { 
-                    (formatted_content.matches("**").count() - 1) / 2 
-                } else { 
-                    0 
-                }
-            ));
-            Ok(formatted_content)
-        }
-        Err(error) => {
-            backend_warn(format!("Failed to parse feed {}: {}", url, error));
-            Err(error)
-        }
+    #[test]
+    fn test_rss_fixture() {
+        let feed = parse_feed(include_str!("../tests/fixtures/feed_rss.xml")).unwrap();
+        assert_eq!(feed.title, "Wiadomości Lokalne");
+        assert_eq!(feed.items.len(), 2);
+
+        let first = &feed.items[0];
+        assert_eq!(first.author.as_deref(), Some("Anna Nowak"));
+        assert_eq!(first.published.as_deref(), Some("2025-10-14T08:30:00+02:00"));
+        assert!(first.content_html.as_deref().unwrap().contains("<strong>już dziś</strong>"));
+
+        // Broken date and missing guid: item kept, keyed by link
+        let second = &feed.items[1];
+        assert_eq!(second.published, None);
+        assert_eq!(second.key(), "https://news.example.pl/most");
+    }
+
+    #[test]
+    fn test_atom_fixture() {
+        let feed = parse_feed(include_str!("../tests/fixtures/feed_atom.xml")).unwrap();
+        assert_eq!(feed.description.as_deref(), Some("Notes from the build team"));
+        assert_eq!(feed.link.as_deref(), Some("https://blog.example.com/"));
+
+        let first = &feed.items[0];
+        assert_eq!(first.author.as_deref(), Some("Sam Lee"));
+        assert_eq!(first.published.as_deref(), Some("2025-10-12T09:00:00+00:00"));
+        assert_eq!(first.content_html.as_deref(), Some("<p>We cut build times <em>in half</em> by caching.</p>"));
+
+        let second = &feed.items[1];
+        assert_eq!(second.published, None);
+        assert_eq!(second.guid, None);
+        assert_eq!(second.key(), "https://blog.example.com/release-notes");
+    }
+
+    #[test]
+    fn test_json_feed_fixture() {
+        let feed = parse_feed(include_str!("../tests/fixtures/feed_json.json")).unwrap();
+        assert_eq!(feed.title, "Podcast o technologii");
+        assert_eq!(feed.link.as_deref(), Some("https://podcast.example.org/"));
+
+        let first = &feed.items[0];
+        assert_eq!(first.guid.as_deref(), Some("42"));
+        assert_eq!(first.author.as_deref(), Some("Piotr Wiśniewski"));
+        assert_eq!(first.published.as_deref(), Some("2025-10-10T18:00:00+02:00"));
+        assert!(first.content_html.is_some());
+
+        // Numeric id, no title, unparseable date
+        let second = &feed.items[1];
+        assert_eq!(second.guid.as_deref(), Some("43"));
+        assert_eq!(second.title, "Krótki odcinek bez tytułu.");
+        assert_eq!(second.published, None);
+    }
+
+    #[test]
+    fn test_format_feed_truncates_on_char_boundary() {
+        let feed = Feed {
+            title: "T".into(),
+            description: None,
+            link: None,
+            items: vec![FeedItem {
+                title: "ą".into(),
+                summary: Some("ż".repeat(400)),
+                ..Default::default()
+            }],
+        };
+        let text = format_feed(&feed, 5);
+        assert!(text.contains(&format!("{}...", "ż".repeat(300))));
     }
 }
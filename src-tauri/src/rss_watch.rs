@@ -0,0 +1,384 @@
+//! RSS/Atom/JSON feed watcher — polls subscribed feeds and announces new items.
+//!
+//! Watches live in `broxeen_rss.db` (data dir) so they survive restarts.
+//! A background task checks which feeds are due, fetches them with
+//! conditional GET (ETag / Last-Modified), deduplicates items by
+//! guid → link → title and emits `broxeen:rss_new_items`.
+//!
+//! The first successful fetch of a feed only records a baseline; after 3
+//! consecutive failures the poll interval doubles per failure (max 24 h).
+
+use crate::logging::{backend_info, backend_warn};
+use crate::rss_parser::{parse_feed, FeedItem};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+const RSS_DB: &str = "broxeen_rss.db";
+const SCHEDULER_TICK: Duration = Duration::from_secs(30);
+const MIN_INTERVAL_MINUTES: u32 = 5;
+const MAX_INTERVAL_MINUTES: u32 = 24 * 60;
+const DEFAULT_INTERVAL_MINUTES: u32 = 30;
+/// Failures tolerated before the interval starts backing off
+const BACKOFF_AFTER_ERRORS: u32 = 3;
+/// Remembered item keys per feed
+const SEEN_PER_FEED: u32 = 1000;
+
+lazy_static::lazy_static! {
+    static ref RSS_CONN: Mutex<Option<Connection>> = Mutex::new(None);
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RssWatch {
+    pub url: String,
+    pub interval_minutes: u32,
+    pub last_checked: Option<i64>,
+    pub next_check: i64,
+    pub error_count: u32,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RssNewItems {
+    pub url: String,
+    pub feed_title: String,
+    pub items: Vec<FeedItem>,
+}
+
+// ── Storage ──────────────────────────────────────────
+
+fn db_path() -> PathBuf {
+    let base = dirs::data_local_dir()
+        .or_else(dirs::data_dir)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("broxeen");
+    let _ = std::fs::create_dir_all(&base);
+    base.join(RSS_DB)
+}
+
+fn init_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS rss_watches (
+            url TEXT PRIMARY KEY,
+            interval_minutes INTEGER NOT NULL,
+            etag TEXT,
+            last_modified TEXT,
+            last_checked INTEGER,
+            next_check INTEGER NOT NULL,
+            error_count INTEGER NOT NULL DEFAULT 0,
+            last_error TEXT
+        );
+        CREATE TABLE IF NOT EXISTS rss_seen (
+            url TEXT NOT NULL,
+            item_key TEXT NOT NULL,
+            first_seen INTEGER NOT NULL,
+            PRIMARY KEY (url, item_key)
+        );",
+    )
+}
+
+fn with_conn<T>(f: impl FnOnce(&Connection) -> Result<T, String>) -> Result<T, String> {
+    let mut guard = RSS_CONN.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.is_none() {
+        let conn = Connection::open(db_path()).map_err(|e| format!("RSS DB open failed: {}", e))?;
+        init_schema(&conn).map_err(|e| format!("RSS DB schema failed: {}", e))?;
+        *guard = Some(conn);
+    }
+    f(guard.as_ref().expect("rss connection initialised"))
+}
+
+fn now_secs() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+/// Poll interval after `errors` consecutive failures.
+fn effective_interval(interval_minutes: u32, errors: u32) -> u32 {
+    if errors < BACKOFF_AFTER_ERRORS {
+        return interval_minutes;
+    }
+    let factor = 1u32 << (errors - BACKOFF_AFTER_ERRORS + 1).min(8);
+    interval_minutes.saturating_mul(factor).min(MAX_INTERVAL_MINUTES)
+}
+
+fn row_to_watch(row: &rusqlite::Row) -> rusqlite::Result<RssWatch> {
+    Ok(RssWatch {
+        url: row.get(0)?,
+        interval_minutes: row.get(1)?,
+        last_checked: row.get(2)?,
+        next_check: row.get(3)?,
+        error_count: row.get(4)?,
+        last_error: row.get(5)?,
+    })
+}
+
+const WATCH_COLUMNS: &str =
+    "SELECT url, interval_minutes, last_checked, next_check, error_count, last_error FROM rss_watches";
+
+fn list_watches(conn: &Connection, due_only: bool) -> Result<Vec<RssWatch>, String> {
+    let (sql, args): (String, Vec<i64>) = if due_only {
+        (format!("{} WHERE next_check <= ?1 ORDER BY next_check", WATCH_COLUMNS), vec![now_secs()])
+    } else {
+        (format!("{} ORDER BY url", WATCH_COLUMNS), Vec::new())
+    };
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(args), row_to_watch)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Store `items` as seen and return those not seen before. When the feed
+/// has no history yet everything is recorded and nothing is returned.
+fn record_new_items(conn: &Connection, url: &str, items: &[FeedItem]) -> Result<Vec<FeedItem>, String> {
+    let has_history: bool = conn
+        .query_row("SELECT EXISTS(SELECT 1 FROM rss_seen WHERE url = ?1)", [url], |r| r.get(0))
+        .map_err(|e| e.to_string())?;
+
+    let now = now_secs();
+    let mut fresh = Vec::new();
+    for item in items {
+        let inserted = conn
+            .execute(
+                "INSERT OR IGNORE INTO rss_seen (url, item_key, first_seen) VALUES (?1, ?2, ?3)",
+                params![url, item.key(), now],
+            )
+            .map_err(|e| e.to_string())?;
+        if inserted > 0 && has_history {
+            fresh.push(item.clone());
+        }
+    }
+
+    // Items of one fetch share `first_seen`; among them the feed's top ones
+    // (inserted first) are kept, they are the ones it still lists
+    conn.execute(
+        "DELETE FROM rss_seen WHERE url = ?1 AND item_key NOT IN (
+            SELECT item_key FROM rss_seen WHERE url = ?1 ORDER BY first_seen DESC, rowid ASC LIMIT ?2)",
+        params![url, SEEN_PER_FEED],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(fresh)
+}
+
+// ── Fetch ────────────────────────────────────────────
+
+enum FetchOutcome {
+    NotModified,
+    Feed {
+        title: String,
+        items: Vec<FeedItem>,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    },
+}
+
+fn parse_feed_items(content: &str) -> Result<(String, Vec<FeedItem>), String> {
+    let feed = parse_feed(content)?;
+    Ok((feed.title, feed.items))
+}
+
+async fn fetch_feed(url: &str, etag: Option<String>, last_modified: Option<String>) -> Result<FetchOutcome, String> {
+    crate::http_policy::before_fetch(url).await.map_err(|e| e.to_string())?;
+    let client = crate::http_client::client(Duration::from_secs(20))?;
+    let mut request = client.get(url);
+    if let Some(etag) = etag {
+        request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = last_modified {
+        request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+    }
+
+    let policy = crate::http_client::RetryPolicy::default().max_retries(1);
+    let (response, meta) = crate::http_client::send_with_retry(request, &policy).await?;
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(FetchOutcome::NotModified);
+    }
+    if !status.is_success() {
+        return Err(format!("HTTP {}{}", status, meta.describe()));
+    }
+
+    let header = |name: reqwest::header::HeaderName| {
+        response.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string)
+    };
+    let etag = header(reqwest::header::ETAG);
+    let last_modified = header(reqwest::header::LAST_MODIFIED);
+    let body = response.text().await.map_err(|e| e.to_string())?;
+    let (title, items) = parse_feed_items(&body)?;
+    Ok(FetchOutcome::Feed { title, items, etag, last_modified })
+}
+
+async fn poll_watch(app: &AppHandle, watch: &RssWatch) {
+    let (etag, last_modified) = with_conn(|conn| {
+        conn.query_row(
+            "SELECT etag, last_modified FROM rss_watches WHERE url = ?1",
+            [&watch.url],
+            |r| Ok((r.get::<_, Option<String>>(0)?, r.get::<_, Option<String>>(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())
+    })
+    .ok()
+    .flatten()
+    .unwrap_or((None, None));
+
+    let outcome = fetch_feed(&watch.url, etag, last_modified).await;
+    let now = now_secs();
+
+    let result = with_conn(|conn| match outcome {
+        Ok(FetchOutcome::NotModified) => {
+            conn.execute(
+                "UPDATE rss_watches SET last_checked = ?2, next_check = ?3, error_count = 0, last_error = NULL WHERE url = ?1",
+                params![watch.url, now, now + watch.interval_minutes as i64 * 60],
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(None)
+        }
+        Ok(FetchOutcome::Feed { title, items, etag, last_modified }) => {
+            conn.execute(
+                "UPDATE rss_watches SET last_checked = ?2, next_check = ?3, error_count = 0, last_error = NULL,
+                    etag = ?4, last_modified = ?5 WHERE url = ?1",
+                params![watch.url, now, now + watch.interval_minutes as i64 * 60, etag, last_modified],
+            )
+            .map_err(|e| e.to_string())?;
+            let fresh = record_new_items(conn, &watch.url, &items)?;
+            Ok(Some(RssNewItems { url: watch.url.clone(), feed_title: title, items: fresh }))
+        }
+        Err(err) => {
+            let errors = watch.error_count + 1;
+            let interval = effective_interval(watch.interval_minutes, errors);
+            backend_warn(format!(
+                "RSS watch {} failed ({} in a row, next try in {} min): {}",
+                watch.url, errors, interval, err
+            ));
+            conn.execute(
+                "UPDATE rss_watches SET last_checked = ?2, next_check = ?3, error_count = ?4, last_error = ?5 WHERE url = ?1",
+                params![watch.url, now, now + interval as i64 * 60, errors, err],
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(None)
+        }
+    });
+
+    match result {
+        Ok(Some(new_items)) if !new_items.items.is_empty() => {
+            backend_info(format!("RSS watch {}: {} new item(s)", watch.url, new_items.items.len()));
+            let _ = app.emit("broxeen:rss_new_items", new_items);
+        }
+        Ok(_) => {}
+        Err(e) => backend_warn(format!("RSS watch {} bookkeeping failed: {}", watch.url, e)),
+    }
+}
+
+/// Start the polling loop; called once from app setup.
+pub fn start_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match with_conn(|conn| list_watches(conn, true)) {
+                Ok(due) => {
+                    for watch in &due {
+                        poll_watch(&app, watch).await;
+                    }
+                }
+                Err(e) => backend_warn(format!("RSS scheduler: {}", e)),
+            }
+            tokio::time::sleep(SCHEDULER_TICK).await;
+        }
+    });
+}
+
+// ── Tauri commands ───────────────────────────────────
+
+/// Watch `url` (or change its interval); the first poll happens on the next tick.
+#[tauri::command]
+pub fn rss_watch_add(url: String, interval_minutes: Option<u32>) -> Result<RssWatch, String> {
+    let url = url.trim().to_string();
+    reqwest::Url::parse(&url).map_err(|e| format!("Nieprawidłowy URL kanału: {}", e))?;
+    let interval = interval_minutes
+        .unwrap_or(DEFAULT_INTERVAL_MINUTES)
+        .clamp(MIN_INTERVAL_MINUTES, MAX_INTERVAL_MINUTES);
+    backend_info(format!("rss_watch_add: {} every {} min", url, interval));
+
+    with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO rss_watches (url, interval_minutes, next_check) VALUES (?1, ?2, ?3)
+             ON CONFLICT(url) DO UPDATE SET interval_minutes = ?2, next_check = MIN(next_check, ?3 + ?2 * 60)",
+            params![url, interval, now_secs()],
+        )
+        .map_err(|e| e.to_string())?;
+        conn.query_row(&format!("{} WHERE url = ?1", WATCH_COLUMNS), [&url], row_to_watch)
+            .map_err(|e| e.to_string())
+    })
+}
+
+#[tauri::command]
+pub fn rss_watch_remove(url: String) -> Result<bool, String> {
+    backend_info(format!("rss_watch_remove: {}", url));
+    with_conn(|conn| {
+        conn.execute("DELETE FROM rss_seen WHERE url = ?1", [&url]).map_err(|e| e.to_string())?;
+        let removed = conn
+            .execute("DELETE FROM rss_watches WHERE url = ?1", [&url])
+            .map_err(|e| e.to_string())?;
+        Ok(removed > 0)
+    })
+}
+
+#[tauri::command]
+pub fn rss_watch_list() -> Result<Vec<RssWatch>, String> {
+    with_conn(|conn| list_watches(conn, false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(title: &str, guid: Option<&str>) -> FeedItem {
+        FeedItem {
+            title: title.into(),
+            link: Some(format!("https://example.com/{}", title)),
+            guid: guid.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_first_fetch_is_baseline_then_only_new_items() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let url = "https://example.com/feed";
+
+        let first = record_new_items(&conn, url, &[item("a", Some("1")), item("b", None)]).unwrap();
+        assert!(first.is_empty());
+
+        let second = record_new_items(&conn, url, &[item("c", Some("3")), item("a", Some("1"))]).unwrap();
+        assert_eq!(second, vec![item("c", Some("3"))]);
+    }
+
+    #[test]
+    fn test_trim_keeps_the_top_of_one_fetch() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let url = "https://example.com/feed";
+        let items: Vec<FeedItem> = (0..=SEEN_PER_FEED).map(|i| item(&i.to_string(), None)).collect();
+        record_new_items(&conn, url, &items).unwrap();
+
+        let seen = |key: &str| -> bool {
+            conn.query_row("SELECT EXISTS(SELECT 1 FROM rss_seen WHERE item_key = ?1)", [key], |r| r.get(0))
+                .unwrap()
+        };
+        assert!(seen(&items[0].key()));
+        assert!(seen(&items[SEEN_PER_FEED as usize - 1].key()));
+        assert!(!seen(&items[SEEN_PER_FEED as usize].key()));
+    }
+
+    #[test]
+    fn test_effective_interval_backs_off() {
+        assert_eq!(effective_interval(30, 0), 30);
+        assert_eq!(effective_interval(30, 2), 30);
+        assert_eq!(effective_interval(30, 3), 60);
+        assert_eq!(effective_interval(30, 4), 120);
+        assert_eq!(effective_interval(30, 20), MAX_INTERVAL_MINUTES);
+    }
+}
@@ -0,0 +1,646 @@
+//! scan_history.rs — Persistence of `scan_network` results in broxeen_devices.db.
+//!
+//! Every scan is recorded as a run with the devices (and open ports) it saw,
+//! so "what appeared since yesterday?" can be answered by diffing runs.
+//! Devices are keyed by MAC when the ARP cache knows it, otherwise by IP;
+//! `scan_devices` keeps first/last seen per key. Found devices are also
+//! upserted into the plugin's `devices` table (created here when the
+//! frontend has not migrated the database yet), which `camera_health_check`
+//! reads.
+//!
+//! User labels (alias, notes, icon) live in `device_metadata` under the same
+//! keys, so a MAC-keyed alias follows the device to a new IP.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::logging::backend_info;
+use crate::network_scan::NetworkDevice;
+
+const DEVICES_DB: &str = "broxeen_devices.db";
+/// Scan runs kept for diffing
+const MAX_RUNS: i64 = 500;
+
+fn open_db() -> Result<Connection, String> {
+    let path = crate::network_scan::resolve_db_path(DEVICES_DB)?;
+    let conn = Connection::open(path).map_err(|e| format!("Devices DB open failed: {}", e))?;
+    migrate(&conn).map_err(|e| format!("Devices DB migration failed: {}", e))?;
+    Ok(conn)
+}
+
+fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        r#"
+        -- Same shape as the frontend migration (src/persistence/migrations.ts)
+        CREATE TABLE IF NOT EXISTS devices (
+            id TEXT PRIMARY KEY,
+            ip TEXT NOT NULL UNIQUE,
+            hostname TEXT,
+            mac TEXT,
+            vendor TEXT,
+            last_seen INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_devices_last_seen ON devices(last_seen);
+
+        CREATE TABLE IF NOT EXISTS scan_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            subnet TEXT NOT NULL,
+            full_scan INTEGER NOT NULL,
+            finished_at INTEGER NOT NULL,
+            device_count INTEGER NOT NULL,
+            new_devices INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_scan_runs_subnet ON scan_runs(subnet, finished_at);
+
+        CREATE TABLE IF NOT EXISTS scan_devices (
+            device_key TEXT PRIMARY KEY,
+            ip TEXT NOT NULL,
+            mac TEXT,
+            hostname TEXT,
+            vendor TEXT,
+            open_ports TEXT NOT NULL,
+            first_seen INTEGER NOT NULL,
+            last_seen INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS scan_run_devices (
+            scan_id INTEGER NOT NULL,
+            device_key TEXT NOT NULL,
+            ip TEXT NOT NULL,
+            open_ports TEXT NOT NULL,
+            PRIMARY KEY (scan_id, device_key)
+        );
+
+        CREATE TABLE IF NOT EXISTS device_metadata (
+            device_key TEXT PRIMARY KEY,
+            alias TEXT NOT NULL,
+            notes TEXT,
+            icon TEXT,
+            updated_at INTEGER NOT NULL
+        );
+        "#,
+    )
+}
+
+/// Lowercase, colon-separated MAC, or None for missing / all-zero ones.
+fn normalize_mac(mac: &str) -> Option<String> {
+    let mac = mac.trim().to_lowercase().replace('-', ":");
+    let valid = mac.len() == 17
+        && mac.split(':').all(|part| part.len() == 2 && part.chars().all(|c| c.is_ascii_hexdigit()));
+    (valid && mac != "00:00:00:00:00:00").then_some(mac)
+}
+
+/// `mac:aa:bb:…` when the MAC is usable, else `ip:192.168.1.10`.
+fn key_for(ip: &str, mac: Option<&str>) -> String {
+    match mac.and_then(normalize_mac) {
+        Some(mac) => format!("mac:{}", mac),
+        None => format!("ip:{}", ip.trim()),
+    }
+}
+
+fn device_key(device: &NetworkDevice) -> String {
+    key_for(&device.ip, device.mac.as_deref())
+}
+
+fn ports_json(ports: &[u16]) -> String {
+    let mut sorted = ports.to_vec();
+    sorted.sort_unstable();
+    serde_json::to_string(&sorted).unwrap_or_else(|_| "[]".into())
+}
+
+/// Store a finished scan; returns how many devices were never seen before.
+fn record_scan_in(
+    conn: &Connection,
+    subnet: &str,
+    full_scan: bool,
+    devices: &[NetworkDevice],
+    now_ms: i64,
+) -> rusqlite::Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "INSERT INTO scan_runs (subnet, full_scan, finished_at, device_count, new_devices)
+         VALUES (?1, ?2, ?3, ?4, 0)",
+        params![subnet, full_scan, now_ms, devices.len() as i64],
+    )?;
+    let scan_id = tx.last_insert_rowid();
+    let mut new_devices = 0;
+
+    for device in devices {
+        let key = device_key(device);
+        let ports = ports_json(&device.open_ports);
+
+        // First time with a MAC for a device previously known only by IP
+        if key.starts_with("mac:") {
+            for table in ["scan_devices", "device_metadata"] {
+                tx.execute(
+                    &format!(
+                        "UPDATE {table} SET device_key = ?1
+                         WHERE device_key = ?2 AND NOT EXISTS (SELECT 1 FROM {table} WHERE device_key = ?1)"
+                    ),
+                    params![key, format!("ip:{}", device.ip)],
+                )?;
+            }
+        }
+
+        let known: bool = tx
+            .query_row("SELECT 1 FROM scan_devices WHERE device_key = ?1", [&key], |_| Ok(true))
+            .optional()?
+            .unwrap_or(false);
+        if !known {
+            new_devices += 1;
+        }
+        tx.execute(
+            "INSERT INTO scan_devices (device_key, ip, mac, hostname, vendor, open_ports, first_seen, last_seen)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)
+             ON CONFLICT(device_key) DO UPDATE SET
+               ip = excluded.ip,
+               mac = COALESCE(excluded.mac, mac),
+               hostname = COALESCE(excluded.hostname, hostname),
+               vendor = COALESCE(excluded.vendor, vendor),
+               open_ports = excluded.open_ports,
+               last_seen = excluded.last_seen",
+            params![key, device.ip, device.mac, device.hostname, device.vendor, ports, now_ms],
+        )?;
+        tx.execute(
+            "INSERT OR REPLACE INTO scan_run_devices (scan_id, device_key, ip, open_ports) VALUES (?1, ?2, ?3, ?4)",
+            params![scan_id, key, device.ip, ports],
+        )?;
+        tx.execute(
+            "INSERT INTO devices (id, ip, hostname, mac, vendor, last_seen, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6, ?6)
+             ON CONFLICT(ip) DO UPDATE SET
+               hostname = COALESCE(excluded.hostname, hostname),
+               mac = COALESCE(excluded.mac, mac),
+               vendor = COALESCE(excluded.vendor, vendor),
+               last_seen = excluded.last_seen,
+               updated_at = excluded.updated_at",
+            params![format!("scan:{}", device.ip), device.ip, device.hostname, device.mac, device.vendor, now_ms],
+        )?;
+    }
+
+    tx.execute(
+        "UPDATE scan_runs SET new_devices = ?1 WHERE id = ?2",
+        params![new_devices as i64, scan_id],
+    )?;
+    tx.execute(
+        "DELETE FROM scan_run_devices WHERE scan_id <= ?1",
+        [scan_id - MAX_RUNS],
+    )?;
+    tx.execute("DELETE FROM scan_runs WHERE id <= ?1", [scan_id - MAX_RUNS])?;
+    tx.commit()?;
+    Ok(new_devices)
+}
+
+/// Persist a `scan_network` result (called by the scan itself).
+pub fn record_scan(subnet: &str, full_scan: bool, devices: &[NetworkDevice]) -> Result<usize, String> {
+    let conn = open_db()?;
+    let new_devices = record_scan_in(&conn, subnet, full_scan, devices, chrono::Utc::now().timestamp_millis())
+        .map_err(|e| format!("Saving scan failed: {}", e))?;
+    backend_info(format!(
+        "scan_history: stored {} devices for {} ({} new)",
+        devices.len(),
+        subnet,
+        new_devices
+    ));
+    Ok(new_devices)
+}
+
+// ── Diff ─────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ScanRunInfo {
+    pub id: i64,
+    pub subnet: String,
+    pub full_scan: bool,
+    pub finished_at: i64,
+    pub device_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DiffDevice {
+    pub device_key: String,
+    pub ip: String,
+    pub open_ports: Vec<u16>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PortChange {
+    pub device_key: String,
+    pub ip: String,
+    pub added_ports: Vec<u16>,
+    pub removed_ports: Vec<u16>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ScanDiff {
+    pub current: Option<ScanRunInfo>,
+    pub baseline: Option<ScanRunInfo>,
+    pub new_devices: Vec<DiffDevice>,
+    /// Only reported when the current scan covered the whole subnet
+    pub disappeared: Vec<DiffDevice>,
+    pub changed_ports: Vec<PortChange>,
+}
+
+fn run_info(row: &rusqlite::Row) -> rusqlite::Result<ScanRunInfo> {
+    Ok(ScanRunInfo {
+        id: row.get(0)?,
+        subnet: row.get(1)?,
+        full_scan: row.get(2)?,
+        finished_at: row.get(3)?,
+        device_count: row.get(4)?,
+    })
+}
+
+fn run_devices(conn: &Connection, scan_id: i64) -> rusqlite::Result<BTreeMap<String, DiffDevice>> {
+    let mut stmt = conn.prepare("SELECT device_key, ip, open_ports FROM scan_run_devices WHERE scan_id = ?1")?;
+    let rows = stmt.query_map([scan_id], |row| {
+        let ports: String = row.get(2)?;
+        Ok(DiffDevice {
+            device_key: row.get(0)?,
+            ip: row.get(1)?,
+            open_ports: serde_json::from_str(&ports).unwrap_or_default(),
+        })
+    })?;
+    rows.map(|r| r.map(|d| (d.device_key.clone(), d))).collect()
+}
+
+/// Latest scan compared with the last scan of the same subnet finished at or
+/// before `since_ms`, or with the previous scan when `since_ms` is None.
+fn diff_in(conn: &Connection, since_ms: Option<i64>) -> rusqlite::Result<ScanDiff> {
+    const RUN_COLUMNS: &str = "id, subnet, full_scan, finished_at, device_count";
+    let current = conn
+        .query_row(
+            &format!("SELECT {} FROM scan_runs ORDER BY id DESC LIMIT 1", RUN_COLUMNS),
+            [],
+            run_info,
+        )
+        .optional()?;
+    let Some(current) = current else {
+        return Ok(ScanDiff {
+            current: None,
+            baseline: None,
+            new_devices: Vec::new(),
+            disappeared: Vec::new(),
+            changed_ports: Vec::new(),
+        });
+    };
+
+    let baseline = conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM scan_runs WHERE subnet = ?1 AND id < ?2 AND finished_at <= ?3
+                 ORDER BY id DESC LIMIT 1",
+                RUN_COLUMNS
+            ),
+            params![current.subnet, current.id, since_ms.unwrap_or(i64::MAX)],
+            run_info,
+        )
+        .optional()?;
+
+    let now = run_devices(conn, current.id)?;
+    let before = match &baseline {
+        Some(b) => run_devices(conn, b.id)?,
+        None => BTreeMap::new(),
+    };
+
+    let new_devices = now.values().filter(|d| !before.contains_key(&d.device_key)).cloned().collect();
+    let disappeared = if current.full_scan {
+        before.values().filter(|d| !now.contains_key(&d.device_key)).cloned().collect()
+    } else {
+        Vec::new()
+    };
+    let changed_ports = now
+        .values()
+        .filter_map(|d| {
+            let old = before.get(&d.device_key)?;
+            let added: Vec<u16> = d.open_ports.iter().filter(|p| !old.open_ports.contains(p)).copied().collect();
+            let removed: Vec<u16> = old.open_ports.iter().filter(|p| !d.open_ports.contains(p)).copied().collect();
+            (!added.is_empty() || !removed.is_empty()).then(|| PortChange {
+                device_key: d.device_key.clone(),
+                ip: d.ip.clone(),
+                added_ports: added,
+                removed_ports: removed,
+            })
+        })
+        .collect();
+
+    Ok(ScanDiff {
+        current: Some(current),
+        baseline,
+        new_devices,
+        disappeared,
+        changed_ports,
+    })
+}
+
+/// Devices new, gone or with changed ports in the latest scan. `since`
+/// (RFC 3339 or Unix ms) picks the baseline scan; default: the previous scan.
+#[tauri::command]
+pub fn scan_network_diff(since: Option<String>) -> Result<ScanDiff, String> {
+    backend_info(format!("Command scan_network_diff invoked (since={:?})", since));
+    let since_ms = match since.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        None => None,
+        Some(s) => Some(match s.parse::<i64>() {
+            Ok(ms) => ms,
+            Err(_) => chrono::DateTime::parse_from_rfc3339(s)
+                .map_err(|_| format!("Nieprawidłowa data 'since': {}", s))?
+                .timestamp_millis(),
+        }),
+    };
+    let conn = open_db()?;
+    diff_in(&conn, since_ms).map_err(|e| format!("Scan diff failed: {}", e))
+}
+
+// ── Device metadata ──────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeviceMetadata {
+    /// `mac:…` or `ip:…`, as in `scan_devices`
+    pub key: String,
+    pub alias: String,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+fn metadata_row(row: &rusqlite::Row) -> rusqlite::Result<DeviceMetadata> {
+    Ok(DeviceMetadata {
+        key: row.get(0)?,
+        alias: row.get(1)?,
+        notes: row.get(2)?,
+        icon: row.get(3)?,
+        updated_at: row.get(4)?,
+    })
+}
+
+/// Key for user input: `mac:…`/`ip:…` as is, a bare MAC, or an IP — which
+/// maps to the MAC key when a scan has already seen that IP with a MAC.
+fn resolve_key(conn: &Connection, input: &str) -> rusqlite::Result<String> {
+    let input = input.trim();
+    if let Some(mac) = input.strip_prefix("mac:").and_then(normalize_mac) {
+        return Ok(format!("mac:{}", mac));
+    }
+    if let Some(mac) = normalize_mac(input) {
+        return Ok(format!("mac:{}", mac));
+    }
+    let ip = input.strip_prefix("ip:").unwrap_or(input);
+    let by_mac = conn
+        .query_row(
+            "SELECT device_key FROM scan_devices WHERE ip = ?1 AND device_key LIKE 'mac:%'
+             ORDER BY last_seen DESC LIMIT 1",
+            [ip],
+            |r| r.get::<_, String>(0),
+        )
+        .optional()?;
+    Ok(by_mac.unwrap_or_else(|| format!("ip:{}", ip)))
+}
+
+fn upsert_metadata(conn: &Connection, meta: &DeviceMetadata) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO device_metadata (device_key, alias, notes, icon, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(device_key) DO UPDATE SET
+           alias = excluded.alias,
+           notes = excluded.notes,
+           icon = excluded.icon,
+           updated_at = excluded.updated_at",
+        params![meta.key, meta.alias, meta.notes, meta.icon, meta.updated_at],
+    )?;
+    Ok(())
+}
+
+fn all_metadata_in(conn: &Connection) -> rusqlite::Result<HashMap<String, DeviceMetadata>> {
+    let mut stmt =
+        conn.prepare("SELECT device_key, alias, notes, icon, updated_at FROM device_metadata ORDER BY device_key")?;
+    let rows = stmt.query_map([], metadata_row)?;
+    rows.map(|r| r.map(|m| (m.key.clone(), m))).collect()
+}
+
+/// Metadata for a device: its MAC entry first, then its IP entry.
+pub fn find_metadata<'a>(
+    all: &'a HashMap<String, DeviceMetadata>,
+    ip: &str,
+    mac: Option<&str>,
+) -> Option<&'a DeviceMetadata> {
+    all.get(&key_for(ip, mac)).or_else(|| all.get(&format!("ip:{}", ip.trim())))
+}
+
+/// Every stored alias, keyed by device key.
+pub fn all_metadata() -> Result<HashMap<String, DeviceMetadata>, String> {
+    all_metadata_in(&open_db()?).map_err(|e| format!("Device metadata read failed: {}", e))
+}
+
+/// Copy aliases and notes into scan results.
+pub fn annotate_devices(devices: &mut [NetworkDevice]) -> Result<(), String> {
+    let all = all_metadata()?;
+    for device in devices.iter_mut() {
+        if let Some(meta) = find_metadata(&all, &device.ip, device.mac.as_deref()) {
+            device.alias = Some(meta.alias.clone());
+            device.notes = meta.notes.clone();
+        }
+    }
+    Ok(())
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// Label a device by MAC, IP or device key. An empty alias removes the entry.
+#[tauri::command]
+pub fn device_set_alias(
+    key: String,
+    alias: String,
+    notes: Option<String>,
+    icon: Option<String>,
+) -> Result<Option<DeviceMetadata>, String> {
+    if key.trim().is_empty() {
+        return Err("Klucz urządzenia (MAC lub IP) nie może być pusty".to_string());
+    }
+    let conn = open_db()?;
+    let device_key = resolve_key(&conn, &key).map_err(|e| e.to_string())?;
+    let alias = alias.trim().to_string();
+    if alias.is_empty() {
+        conn.execute("DELETE FROM device_metadata WHERE device_key = ?1", [&device_key])
+            .map_err(|e| e.to_string())?;
+        backend_info(format!("Device alias removed for {}", device_key));
+        return Ok(None);
+    }
+    let meta = DeviceMetadata {
+        key: device_key,
+        alias,
+        notes: non_empty(notes),
+        icon: non_empty(icon),
+        updated_at: chrono::Utc::now().timestamp_millis(),
+    };
+    upsert_metadata(&conn, &meta).map_err(|e| format!("Saving device alias failed: {}", e))?;
+    backend_info(format!("Device alias set: {} = {}", meta.key, meta.alias));
+    Ok(Some(meta))
+}
+
+#[tauri::command]
+pub fn device_get_metadata(key: String) -> Result<Option<DeviceMetadata>, String> {
+    let conn = open_db()?;
+    let device_key = resolve_key(&conn, &key).map_err(|e| e.to_string())?;
+    let all = all_metadata_in(&conn).map_err(|e| e.to_string())?;
+    let ip = key.trim().strip_prefix("ip:").unwrap_or(key.trim());
+    Ok(all.get(&device_key).or_else(|| all.get(&format!("ip:{}", ip))).cloned())
+}
+
+/// All aliases as a JSON array (input for `device_metadata_import`).
+#[tauri::command]
+pub fn device_metadata_export() -> Result<String, String> {
+    let mut entries: Vec<DeviceMetadata> = all_metadata()?.into_values().collect();
+    entries.sort_by(|a, b| a.key.cmp(&b.key));
+    serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())
+}
+
+/// Merge aliases exported by `device_metadata_export`; returns how many were stored.
+#[tauri::command]
+pub fn device_metadata_import(json: String) -> Result<usize, String> {
+    let entries: Vec<DeviceMetadata> =
+        serde_json::from_str(&json).map_err(|e| format!("Nieprawidłowy plik aliasów: {}", e))?;
+    let conn = open_db()?;
+    let imported = import_in(&conn, entries, chrono::Utc::now().timestamp_millis())
+        .map_err(|e| format!("Device alias import failed: {}", e))?;
+    backend_info(format!("Imported {} device aliases", imported));
+    Ok(imported)
+}
+
+fn import_in(conn: &Connection, entries: Vec<DeviceMetadata>, now_ms: i64) -> rusqlite::Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let mut imported = 0;
+    for mut meta in entries {
+        if meta.alias.trim().is_empty() || meta.key.trim().is_empty() {
+            continue;
+        }
+        meta.key = resolve_key(&tx, &meta.key)?;
+        meta.alias = meta.alias.trim().to_string();
+        if meta.updated_at == 0 {
+            meta.updated_at = now_ms;
+        }
+        upsert_metadata(&tx, &meta)?;
+        imported += 1;
+    }
+    tx.commit()?;
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(ip: &str, mac: Option<&str>, ports: &[u16]) -> NetworkDevice {
+        NetworkDevice {
+            ip: ip.to_string(),
+            mac: mac.map(str::to_string),
+            hostname: None,
+            vendor: None,
+            open_ports: ports.to_vec(),
+            response_time: 1,
+            last_seen: String::new(),
+            device_type: None,
+            device_type_confidence: None,
+            alias: None,
+            notes: None,
+        }
+    }
+
+    fn memory_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_diff_between_scans() {
+        let conn = memory_db();
+        let first = vec![
+            device("192.168.1.10", Some("AA-BB-CC-DD-EE-01"), &[80]),
+            device("192.168.1.11", None, &[554]),
+        ];
+        assert_eq!(record_scan_in(&conn, "192.168.1", true, &first, 1_000).unwrap(), 2);
+
+        // .10 changed IP (same MAC) and opened 443, .11 is gone, .12 is new
+        let second = vec![
+            device("192.168.1.20", Some("aa:bb:cc:dd:ee:01"), &[443, 80]),
+            device("192.168.1.12", None, &[22]),
+        ];
+        assert_eq!(record_scan_in(&conn, "192.168.1", true, &second, 2_000).unwrap(), 1);
+
+        let diff = diff_in(&conn, None).unwrap();
+        assert_eq!(diff.baseline.as_ref().unwrap().finished_at, 1_000);
+        assert_eq!(diff.new_devices.iter().map(|d| d.ip.as_str()).collect::<Vec<_>>(), vec!["192.168.1.12"]);
+        assert_eq!(diff.disappeared.iter().map(|d| d.ip.as_str()).collect::<Vec<_>>(), vec!["192.168.1.11"]);
+        assert_eq!(diff.changed_ports.len(), 1);
+        assert_eq!(diff.changed_ports[0].ip, "192.168.1.20");
+        assert_eq!(diff.changed_ports[0].added_ports, vec![443]);
+
+        // Baseline before the first scan → everything is new
+        let diff = diff_in(&conn, Some(500)).unwrap();
+        assert!(diff.baseline.is_none());
+        assert_eq!(diff.new_devices.len(), 2);
+
+        let plugin_rows: i64 = conn.query_row("SELECT COUNT(*) FROM devices", [], |r| r.get(0)).unwrap();
+        assert_eq!(plugin_rows, 4);
+    }
+
+    #[test]
+    fn test_ip_keyed_device_adopts_mac() {
+        let conn = memory_db();
+        record_scan_in(&conn, "10.0.0", false, &[device("10.0.0.5", None, &[80])], 1_000).unwrap();
+        let new = record_scan_in(&conn, "10.0.0", false, &[device("10.0.0.5", Some("aa:bb:cc:dd:ee:ff"), &[80])], 2_000)
+            .unwrap();
+        assert_eq!(new, 0);
+        let first_seen: i64 = conn
+            .query_row(
+                "SELECT first_seen FROM scan_devices WHERE device_key = 'mac:aa:bb:cc:dd:ee:ff'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(first_seen, 1_000);
+
+        // Partial scans never report disappearances
+        record_scan_in(&conn, "10.0.0", false, &[], 3_000).unwrap();
+        assert!(diff_in(&conn, None).unwrap().disappeared.is_empty());
+    }
+
+    #[test]
+    fn test_alias_follows_mac_across_ip_change() {
+        let conn = memory_db();
+        // Labelled by IP before any scan saw the MAC
+        let meta = DeviceMetadata {
+            key: resolve_key(&conn, "192.168.1.42").unwrap(),
+            alias: "drukarka w biurze".into(),
+            notes: Some("HP, 2. piętro".into()),
+            icon: None,
+            updated_at: 1,
+        };
+        assert_eq!(meta.key, "ip:192.168.1.42");
+        upsert_metadata(&conn, &meta).unwrap();
+
+        record_scan_in(&conn, "192.168.1", false, &[device("192.168.1.42", Some("AA-BB-CC-00-11-22"), &[631])], 1_000)
+            .unwrap();
+        assert_eq!(resolve_key(&conn, "192.168.1.42").unwrap(), "mac:aa:bb:cc:00:11:22");
+
+        let all = all_metadata_in(&conn).unwrap();
+        assert!(all.contains_key("mac:aa:bb:cc:00:11:22"));
+        let moved = find_metadata(&all, "192.168.1.77", Some("aa:bb:cc:00:11:22")).unwrap();
+        assert_eq!(moved.alias, "drukarka w biurze");
+        assert!(find_metadata(&all, "192.168.1.42", None).is_none());
+
+        // Export → import into a fresh database keeps the entry
+        let json = serde_json::to_string(&all.values().cloned().collect::<Vec<_>>()).unwrap();
+        let fresh = memory_db();
+        let entries: Vec<DeviceMetadata> = serde_json::from_str(&json).unwrap();
+        assert_eq!(import_in(&fresh, entries, 5).unwrap(), 1);
+        assert_eq!(all_metadata_in(&fresh).unwrap()["mac:aa:bb:cc:00:11:22"].notes.as_deref(), Some("HP, 2. piętro"));
+    }
+}
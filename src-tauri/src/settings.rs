@@ -1,13 +1,36 @@
 /// Settings management — load, save, and migrate audio settings.
+///
+/// The file carries a schema `version`; older files are upgraded by
+/// `settings_migrations` on load (after a `settings.json.bak-{version}`
+/// backup) and keys unknown to this build are preserved in `extra`.
+///
+/// `save_settings` broadcasts the list of changed keys so long-lived
+/// subsystems (e.g. the wake word stream) can apply them live or flag
+/// themselves as needing a restart (see `settings_pending_restarts`).
+///
+/// Secrets (non-empty strings under keys like `smtp_password` or `api_key`,
+/// at any depth) are kept out of `settings.json`: they are written to
+/// `secrets.json` next to it (owner-only) and merged back on load, so the
+/// settings file can be shared. Export/import lives in settings_profile.rs.
 
 use crate::logging::{backend_info, backend_warn, backend_error};
+use crate::settings_migrations::{self, CURRENT_VERSION};
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tokio::sync::broadcast;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AudioSettings {
+    /// Schema version of the file (see settings_migrations.rs)
+    #[serde(default)]
+    pub version: u32,
+    /// Set by `get_settings` when the file was upgraded on this load; never persisted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub migrated_from: Option<u32>,
     #[serde(default = "default_tts_enabled")]
     pub tts_enabled: bool,
     #[serde(default = "default_tts_rate")]
@@ -32,12 +55,112 @@ pub struct AudioSettings {
     pub mic_enabled: bool,
     #[serde(default = "default_device_id")]
     pub mic_device_id: String,
+    /// Capture rate asked of the microphone (Hz, 0 = device default);
+    /// recordings are resampled to 16 kHz for STT either way
+    #[serde(default = "default_mic_sample_rate")]
+    pub mic_sample_rate: u32,
+    /// Input channel to record on multi-channel interfaces (1-based, 0 = mix all)
+    #[serde(default)]
+    pub mic_channel: u16,
     #[serde(default = "default_device_id")]
     pub speaker_device_id: String,
     #[serde(default = "default_auto_listen")]
     pub auto_listen: bool,
+    /// "auto" (follow the metered flag), "on" or "off" — see bandwidth.rs
+    #[serde(default = "default_low_bandwidth_mode")]
+    pub low_bandwidth_mode: String,
+    #[serde(default = "default_wake_word_phrase")]
+    pub wake_word_phrase: String,
+    /// 0.0 (strict, fewer false triggers) … 1.0 (sensitive)
+    #[serde(default = "default_wake_word_sensitivity")]
+    pub wake_word_sensitivity: f32,
+    /// Write backend logs to rotating files (applied at next start)
+    #[serde(default = "default_log_file_enabled")]
+    pub log_file_enabled: bool,
+    /// Log directory; empty = `<data dir>/broxeen/logs`
+    #[serde(default)]
+    pub log_dir: String,
+    #[serde(default = "default_log_max_file_mb")]
+    pub log_max_file_mb: u32,
+    /// Number of log files kept
+    #[serde(default = "default_log_retention_files")]
+    pub log_retention_files: u32,
+    /// OpenAI-compatible endpoint used when OpenRouter fails; empty disables the fallback
+    #[serde(default = "default_llm_local_base_url")]
+    pub llm_local_base_url: String,
+    #[serde(default = "default_llm_local_model")]
+    pub llm_local_model: String,
+    /// Extra boilerplate phrases stripped from browsed pages, on top of the
+    /// built-in Polish/English set
+    #[serde(default)]
+    pub content_boilerplate_patterns: Vec<String>,
+    /// Fetch pages even when the site's robots.txt disallows it
+    #[serde(default)]
+    pub ignore_robots: bool,
+    /// Serve cached `browse` results this long before revalidating (see browse_cache.rs)
+    #[serde(default = "default_browse_cache_ttl_secs")]
+    pub browse_cache_ttl_secs: u64,
+    #[serde(default = "default_browse_cache_max_mb")]
+    pub browse_cache_max_mb: u64,
+    /// Characters of page content per `browse` / `browse_content_page` chunk
+    #[serde(default = "default_browse_content_max_chars")]
+    pub browse_content_max_chars: usize,
+    /// Short chimes on wake word, detections and errors (see sounds.rs)
+    #[serde(default = "default_notification_sounds_enabled")]
+    pub notification_sounds_enabled: bool,
+    #[serde(default = "default_notification_volume")]
+    pub notification_volume_detection: f32,
+    #[serde(default = "default_notification_volume")]
+    pub notification_volume_wake_word: f32,
+    #[serde(default = "default_notification_volume")]
+    pub notification_volume_error: f32,
+    /// Vision detections below this confidence stay silent
+    #[serde(default = "default_notification_detection_min_confidence")]
+    pub notification_detection_min_confidence: f32,
+    /// Trust and record SSH host keys in ~/.ssh/known_hosts instead of Broxeen's own file
+    #[serde(default)]
+    pub ssh_use_system_known_hosts: bool,
+    /// `ssh_execute` keeps at most this much of stdout and of stderr
+    #[serde(default = "default_ssh_output_max_kb")]
+    pub ssh_output_max_kb: u64,
+    /// Keep emitting `broxeen:audio_level` while the window is unfocused (see audio_level.rs)
+    #[serde(default)]
+    pub audio_level_when_unfocused: bool,
+    /// Days of `remote_metrics` samples kept per host
+    #[serde(default = "default_remote_metrics_retention_days")]
+    pub remote_metrics_retention_days: u32,
+    /// Bind address of the live vision WebSocket feed, e.g. "0.0.0.0:8931";
+    /// empty = off (see vision_ws.rs)
+    #[serde(default)]
+    pub vision_ws_listen: String,
+    /// `?token=` vision WebSocket clients must present; empty = open
+    #[serde(default)]
+    pub vision_ws_token: String,
+    /// Largest attachment `email_download_attachment` writes to disk
+    #[serde(default = "default_email_attachment_max_mb")]
+    pub email_attachment_max_mb: u64,
+    /// Per-class concurrency / timeout overrides for heavy commands (see command_governor.rs)
+    #[serde(default)]
+    pub command_limits: BTreeMap<String, crate::command_governor::CommandLimit>,
+    /// TTS pronunciation dictionary: word → how to say it (see tts_text.rs)
+    #[serde(default)]
+    pub tts_dictionary: BTreeMap<String, String>,
+    /// Features started automatically at launch (see startup.rs)
+    #[serde(default)]
+    pub on_start: crate::startup::StartupProfile,
+    /// Keys written by newer builds or the UI that this build doesn't model
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
+/// Sibling of `settings.json` holding the secrets split off it
+const SECRETS_FILE: &str = "secrets.json";
+/// Maps whose keys are user data (words, not field names)
+const NON_SECRET_MAPS: &[&str] = &["tts_dictionary"];
+
+/// JSON pointer → secret value
+pub type Secrets = BTreeMap<String, serde_json::Value>;
+
 fn default_tts_enabled() -> bool { true }
 fn default_tts_rate() -> f32 { 1.0 }
 fn default_tts_pitch() -> f32 { 1.0 }
@@ -54,11 +177,31 @@ fn default_stt_model() -> String {
 }
 fn default_mic_enabled() -> bool { true }
 fn default_device_id() -> String { "default".to_string() }
+fn default_mic_sample_rate() -> u32 { 16_000 }
 fn default_auto_listen() -> bool { false }
+fn default_low_bandwidth_mode() -> String { "auto".to_string() }
+fn default_wake_word_phrase() -> String { "heyken".to_string() }
+fn default_wake_word_sensitivity() -> f32 { 0.5 }
+fn default_log_file_enabled() -> bool { true }
+fn default_log_max_file_mb() -> u32 { 10 }
+fn default_log_retention_files() -> u32 { 7 }
+fn default_llm_local_base_url() -> String { "http://localhost:11434/v1".to_string() }
+fn default_llm_local_model() -> String { "bielik:1.5b".to_string() }
+fn default_browse_cache_ttl_secs() -> u64 { 600 }
+fn default_browse_cache_max_mb() -> u64 { 100 }
+fn default_browse_content_max_chars() -> usize { crate::content_cleaning::MAX_BACKEND_CONTENT_CHARS }
+fn default_notification_sounds_enabled() -> bool { true }
+fn default_notification_volume() -> f32 { 0.6 }
+fn default_notification_detection_min_confidence() -> f32 { 0.6 }
+fn default_remote_metrics_retention_days() -> u32 { 30 }
+fn default_email_attachment_max_mb() -> u64 { 25 }
+fn default_ssh_output_max_kb() -> u64 { 512 }
 
 impl Default for AudioSettings {
     fn default() -> Self {
         AudioSettings {
+            version: CURRENT_VERSION,
+            migrated_from: None,
             tts_enabled: default_tts_enabled(),
             tts_rate: default_tts_rate(),
             tts_pitch: default_tts_pitch(),
@@ -71,8 +214,40 @@ impl Default for AudioSettings {
             stt_model: default_stt_model(),
             mic_enabled: default_mic_enabled(),
             mic_device_id: default_device_id(),
+            mic_sample_rate: default_mic_sample_rate(),
+            mic_channel: 0,
             speaker_device_id: default_device_id(),
             auto_listen: default_auto_listen(),
+            low_bandwidth_mode: default_low_bandwidth_mode(),
+            wake_word_phrase: default_wake_word_phrase(),
+            wake_word_sensitivity: default_wake_word_sensitivity(),
+            log_file_enabled: default_log_file_enabled(),
+            log_dir: String::new(),
+            log_max_file_mb: default_log_max_file_mb(),
+            log_retention_files: default_log_retention_files(),
+            llm_local_base_url: default_llm_local_base_url(),
+            llm_local_model: default_llm_local_model(),
+            content_boilerplate_patterns: Vec::new(),
+            ignore_robots: false,
+            browse_cache_ttl_secs: default_browse_cache_ttl_secs(),
+            browse_cache_max_mb: default_browse_cache_max_mb(),
+            browse_content_max_chars: default_browse_content_max_chars(),
+            notification_sounds_enabled: default_notification_sounds_enabled(),
+            notification_volume_detection: default_notification_volume(),
+            notification_volume_wake_word: default_notification_volume(),
+            notification_volume_error: default_notification_volume(),
+            notification_detection_min_confidence: default_notification_detection_min_confidence(),
+            ssh_use_system_known_hosts: false,
+            ssh_output_max_kb: default_ssh_output_max_kb(),
+            audio_level_when_unfocused: false,
+            remote_metrics_retention_days: default_remote_metrics_retention_days(),
+            vision_ws_listen: String::new(),
+            vision_ws_token: String::new(),
+            email_attachment_max_mb: default_email_attachment_max_mb(),
+            command_limits: BTreeMap::new(),
+            tts_dictionary: BTreeMap::new(),
+            on_start: Default::default(),
+            extra: serde_json::Map::new(),
         }
     }
 }
@@ -93,16 +268,28 @@ pub fn settings_path() -> PathBuf {
     path
 }
 
-/// Load settings from disk (used by tts.rs, audio_commands.rs, etc.)
-pub fn load_settings() -> AudioSettings {
-    let path = settings_path();
+/// Parse settings JSON, running schema migrations. Returns the settings and
+/// the version the data was stored at.
+fn parse_and_migrate(data: &str) -> Result<(AudioSettings, u32), String> {
+    let value: serde_json::Value = serde_json::from_str(data).map_err(|e| e.to_string())?;
+    let serde_json::Value::Object(mut map) = value else {
+        return Err("settings root is not a JSON object".into());
+    };
+    let from = settings_migrations::migrate(&mut map);
+    let settings = serde_json::from_value::<AudioSettings>(serde_json::Value::Object(map))
+        .map_err(|e| e.to_string())?;
+    Ok((settings, from))
+}
 
+/// Read settings from `path`, upgrading the file in place (after a
+/// `settings.json.bak-{version}` copy) when it is older than this build.
+fn read_settings_file(path: &Path) -> AudioSettings {
     if !path.exists() {
         backend_warn(format!("Settings file not found at {}. Using defaults.", path.display()));
         return AudioSettings::default();
     }
 
-    let data = match fs::read_to_string(&path) {
+    let data = match fs::read_to_string(path) {
         Ok(data) => data,
         Err(err) => {
             backend_error(format!("Failed to read settings file {}: {}", path.display(), err));
@@ -110,112 +297,496 @@ pub fn load_settings() -> AudioSettings {
         }
     };
 
-    match serde_json::from_str::<AudioSettings>(&data) {
-        Ok(settings) => settings,
+    let (mut settings, from) = match parse_and_migrate(&data) {
+        Ok(parsed) => parsed,
         Err(err) => {
-            backend_error(format!("Failed to parse settings: {}", err));
-            AudioSettings::default()
+            backend_error(format!("Failed to parse settings JSON from {}: {}", path.display(), err));
+            return AudioSettings::default();
+        }
+    };
+    let inline_secrets = secret_count(&settings);
+    let stored = read_secrets_file(&secrets_path(path));
+    if !stored.is_empty() {
+        match with_secrets(&settings, &stored) {
+            Ok(merged) => settings = merged,
+            Err(e) => backend_error(format!("Failed to merge {}: {}", SECRETS_FILE, e)),
         }
     }
-}
 
-#[tauri::command]
-pub fn get_settings() -> AudioSettings {
-    backend_info("Command get_settings invoked");
-    let path = settings_path();
+    if inline_secrets > 0 && from == CURRENT_VERSION {
+        backend_info(format!("Moving {} secrets from {} to {}", inline_secrets, path.display(), SECRETS_FILE));
+        if let Err(e) = write_settings_file(path, &settings) {
+            backend_error(format!("Failed to move secrets out of settings: {}", e));
+        }
+    }
 
-    if !path.exists() {
+    if from < CURRENT_VERSION {
+        let backup = PathBuf::from(format!("{}.bak-{}", path.display(), from));
+        backend_info(format!(
+            "Migrating settings v{} → v{} (backup: {})",
+            from, CURRENT_VERSION, backup.display()
+        ));
+        match fs::copy(path, &backup) {
+            Ok(_) => {
+                if let Err(e) = write_settings_file(path, &settings) {
+                    backend_error(format!("Failed to save migrated settings: {}", e));
+                }
+            }
+            Err(e) => backend_error(format!(
+                "Settings backup {} failed, not rewriting {}: {}",
+                backup.display(),
+                path.display(),
+                e
+            )),
+        }
+        settings.migrated_from = Some(from);
+    } else if from > CURRENT_VERSION {
         backend_warn(format!(
-            "Settings file not found at {}. Using defaults.",
-            path.display()
+            "Settings were written by a newer build (v{} > v{}); unknown keys are kept as-is",
+            from, CURRENT_VERSION
         ));
-        return AudioSettings::default();
     }
+    settings
+}
 
-    let data = match fs::read_to_string(&path) {
-        Ok(data) => data,
-        Err(err) => {
-            backend_error(format!(
-                "Failed to read settings file {}: {}",
-                path.display(),
-                err
-            ));
-            return AudioSettings::default();
+fn write_settings_file(path: &Path, settings: &AudioSettings) -> Result<(), String> {
+    let (profile, secrets) = split_secrets(settings).map_err(|e| {
+        backend_error(format!("Failed to serialize settings: {}", e));
+        e
+    })?;
+    let json = serde_json::to_string_pretty(&profile).map_err(|e| e.to_string())?;
+    write_atomic(path, json.as_bytes(), false).map_err(|e| {
+        backend_error(format!("Failed to write settings file {}: {}", path.display(), e));
+        e.to_string()
+    })?;
+    write_secrets_file(&secrets_path(path), &secrets)
+}
+
+/// Write `bytes` to a temp file next to `path`, sync it and rename it over
+/// `path`: readers and crashes see the old or the new file, never half of it.
+fn write_atomic(path: &Path, bytes: &[u8], owner_only: bool) -> std::io::Result<()> {
+    use std::io::Write;
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("settings");
+    let tmp = path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()));
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    if owner_only {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = owner_only;
+    let written = options.open(&tmp).and_then(|mut file| {
+        file.write_all(bytes)?;
+        file.sync_all()
+    });
+    written.and_then(|_| fs::rename(&tmp, path)).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+// ── Secrets ──────────────────────────────────────────────────────
+
+fn secrets_path(settings_path: &Path) -> PathBuf {
+    settings_path.with_file_name(SECRETS_FILE)
+}
+
+fn is_secret(key: &str, value: &serde_json::Value) -> bool {
+    value.as_str().is_some_and(|s| !s.is_empty()) && crate::audit::is_secret_key(key)
+}
+
+/// Move secrets out of `value` into `out`, keyed by JSON pointer.
+fn take_secrets(pointer: &str, value: &mut serde_json::Value, out: &mut Secrets) {
+    use serde_json::Value;
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                let child_pointer = format!("{}/{}", pointer, key.replace('~', "~0").replace('/', "~1"));
+                if is_secret(key, child) {
+                    out.insert(child_pointer, std::mem::replace(child, Value::String(String::new())));
+                } else if !(pointer.is_empty() && NON_SECRET_MAPS.contains(&key.as_str())) {
+                    take_secrets(&child_pointer, child, out);
+                }
+            }
         }
+        Value::Array(items) => {
+            for (i, child) in items.iter_mut().enumerate() {
+                take_secrets(&format!("{}/{}", pointer, i), child, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Settings as a JSON object without secrets, plus the secrets. Secret
+/// fields stay in the object as empty strings.
+pub fn split_secrets(settings: &AudioSettings) -> Result<(serde_json::Value, Secrets), String> {
+    let mut value = serde_json::to_value(settings).map_err(|e| e.to_string())?;
+    let mut secrets = Secrets::new();
+    take_secrets("", &mut value, &mut secrets);
+    Ok((value, secrets))
+}
+
+fn secret_count(settings: &AudioSettings) -> usize {
+    split_secrets(settings).map(|(_, secrets)| secrets.len()).unwrap_or(0)
+}
+
+/// Put `value` at `pointer`, creating missing objects on the way.
+pub fn put_pointer(root: &mut serde_json::Value, pointer: &str, value: serde_json::Value) {
+    use serde_json::{Map, Value};
+    if let Some(slot) = root.pointer_mut(pointer) {
+        *slot = value;
+        return;
+    }
+    let parts: Vec<String> = pointer
+        .split('/')
+        .skip(1)
+        .map(|p| p.replace("~1", "/").replace("~0", "~"))
+        .collect();
+    let Some((last, parents)) = parts.split_last() else { return };
+    let mut current = root;
+    for part in parents {
+        let Value::Object(map) = current else { return };
+        current = map.entry(part.clone()).or_insert_with(|| Value::Object(Map::new()));
+    }
+    if let Value::Object(map) = current {
+        map.insert(last.clone(), value);
+    }
+}
+
+/// `settings` with `secrets` merged in.
+pub fn with_secrets(settings: &AudioSettings, secrets: &Secrets) -> Result<AudioSettings, String> {
+    let mut value = serde_json::to_value(settings).map_err(|e| e.to_string())?;
+    for (pointer, secret) in secrets {
+        put_pointer(&mut value, pointer, secret.clone());
+    }
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+fn read_secrets_file(path: &Path) -> Secrets {
+    match fs::read_to_string(path) {
+        Ok(data) => serde_json::from_str(&data).unwrap_or_else(|e| {
+            backend_error(format!("Failed to parse {}: {}", path.display(), e));
+            Secrets::new()
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Secrets::new(),
+        Err(e) => {
+            backend_error(format!("Failed to read {}: {}", path.display(), e));
+            Secrets::new()
+        }
+    }
+}
+
+fn write_secrets_file(path: &Path, secrets: &Secrets) -> Result<(), String> {
+    if secrets.is_empty() && !path.exists() {
+        return Ok(());
+    }
+    let json = serde_json::to_vec_pretty(secrets).map_err(|e| e.to_string())?;
+    write_atomic(path, &json, true).map_err(|e| {
+        backend_error(format!("Failed to write {}: {}", path.display(), e));
+        e.to_string()
+    })
+}
+
+/// Load settings from disk (used by tts.rs, audio_commands.rs, etc.)
+pub fn load_settings() -> AudioSettings {
+    let mut settings = read_settings_file(&settings_path());
+    settings.migrated_from = None;
+    settings
+}
+
+/// Settings for the UI; `version` is the stored schema version and
+/// `migrated_from` is set when this load upgraded the file.
+#[tauri::command]
+pub fn get_settings() -> AudioSettings {
+    backend_info("Command get_settings invoked");
+    let settings = read_settings_file(&settings_path());
+    backend_info(format!(
+        "Settings loaded (version={}, migrated_from={:?})",
+        settings.version, settings.migrated_from
+    ));
+    settings
+}
+
+// ── Change broadcast ─────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+pub struct SettingsChange {
+    pub changed_keys: Vec<String>,
+    /// Saved settings, for subscribers that apply changes live
+    pub settings: AudioSettings,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingRestart {
+    pub subsystem: String,
+    pub changed_keys: Vec<String>,
+    pub since_ms: u64,
+}
+
+lazy_static::lazy_static! {
+    static ref SETTINGS_TX: broadcast::Sender<SettingsChange> = broadcast::channel(16).0;
+    static ref PENDING_RESTARTS: Mutex<HashMap<String, PendingRestart>> = Mutex::new(HashMap::new());
+    static ref SETTINGS_WATCHERS: Mutex<HashMap<String, tauri::async_runtime::JoinHandle<()>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Subscribe to settings changes published by `save_settings`.
+pub fn subscribe_settings() -> broadcast::Receiver<SettingsChange> {
+    SETTINGS_TX.subscribe()
+}
+
+/// Top-level keys whose values differ between two settings snapshots.
+pub fn diff_settings(old: &AudioSettings, new: &AudioSettings) -> Vec<String> {
+    let (Ok(serde_json::Value::Object(old)), Ok(serde_json::Value::Object(new))) =
+        (serde_json::to_value(old), serde_json::to_value(new))
+    else {
+        return Vec::new();
     };
+    let mut keys: Vec<String> = new
+        .iter()
+        .filter(|(k, v)| old.get(*k) != Some(*v))
+        .map(|(k, _)| k.clone())
+        .collect();
+    keys.sort();
+    keys
+}
 
-    // Try to parse as current AudioSettings, if fails try legacy and migrate
-    match serde_json::from_str::<AudioSettings>(&data) {
-        Ok(settings) => {
-            backend_info("Settings loaded successfully from disk");
-            settings
+/// Flag a running subsystem as using stale settings.
+pub fn mark_restart_required(subsystem: &str, keys: &[String]) {
+    let mut pending = PENDING_RESTARTS.lock().unwrap_or_else(|e| e.into_inner());
+    let entry = pending.entry(subsystem.to_string()).or_insert_with(|| PendingRestart {
+        subsystem: subsystem.to_string(),
+        changed_keys: Vec::new(),
+        since_ms: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64,
+    });
+    for key in keys {
+        if !entry.changed_keys.contains(key) {
+            entry.changed_keys.push(key.clone());
         }
-        Err(_) => {
-            // Try legacy format (without new fields)
-            #[derive(Deserialize)]
-            struct LegacyAudioSettings {
-                pub tts_enabled: bool,
-                pub tts_rate: f32,
-                pub tts_pitch: f32,
-                pub tts_volume: f32,
-                pub tts_voice: String,
-                pub tts_lang: String,
-                pub mic_enabled: bool,
-                pub mic_device_id: String,
-                pub speaker_device_id: String,
-                pub auto_listen: bool,
-            }
+    }
+    backend_warn(format!("Subsystem '{}' needs restart to apply: {}", subsystem, keys.join(", ")));
+}
 
-            match serde_json::from_str::<LegacyAudioSettings>(&data) {
-                Ok(legacy) => {
-                    backend_info("Migrating legacy settings to new format");
-                    let migrated = AudioSettings {
-                        tts_enabled: legacy.tts_enabled,
-                        tts_rate: legacy.tts_rate,
-                        tts_pitch: legacy.tts_pitch,
-                        tts_volume: legacy.tts_volume,
-                        tts_voice: legacy.tts_voice,
-                        tts_lang: legacy.tts_lang,
-                        tts_engine: "auto".to_string(),
-                        stt_enabled: true,
-                        stt_engine: "openrouter".to_string(),
-                        stt_model: "whisper-1".to_string(),
-                        mic_enabled: legacy.mic_enabled,
-                        mic_device_id: legacy.mic_device_id,
-                        speaker_device_id: legacy.speaker_device_id,
-                        auto_listen: legacy.auto_listen,
-                    };
-                    // Save migrated settings immediately
-                    if let Err(e) = save_settings(migrated.clone()) {
-                        backend_error(format!("Failed to save migrated settings: {}", e));
-                    }
-                    migrated
-                }
-                Err(err) => {
-                    backend_error(format!(
-                        "Failed to parse settings JSON from {}: {}",
-                        path.display(),
-                        err
-                    ));
-                    AudioSettings::default()
-                }
+/// Call when a subsystem (re)starts or stops — it no longer runs with old values.
+pub fn clear_restart_required(subsystem: &str) {
+    PENDING_RESTARTS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(subsystem);
+}
+
+/// Run `on_change` for every settings change until `unwatch_settings(name)`.
+/// Registering `name` again replaces its previous watcher, so subsystems can
+/// call this on every (re)start without piling up tasks.
+pub fn watch_settings<F>(name: &str, mut on_change: F)
+where
+    F: FnMut(&SettingsChange) + Send + 'static,
+{
+    let mut rx = subscribe_settings();
+    let handle = tauri::async_runtime::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(change) => on_change(&change),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
             }
         }
+    });
+    let previous = SETTINGS_WATCHERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name.to_string(), handle);
+    if let Some(previous) = previous {
+        previous.abort();
+    }
+}
+
+/// Stop the watcher registered under `name`, if any.
+pub fn unwatch_settings(name: &str) {
+    let handle = SETTINGS_WATCHERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(name);
+    if let Some(handle) = handle {
+        handle.abort();
     }
 }
 
+/// Watch settings on behalf of a long-lived subsystem: changes to any of
+/// `restart_keys` mark it restart-required. Call `unwatch_settings(subsystem)`
+/// when it stops.
+pub fn watch_for_restart(subsystem: &'static str, restart_keys: &'static [&'static str]) {
+    watch_settings(subsystem, move |change| {
+        let stale: Vec<String> = change
+            .changed_keys
+            .iter()
+            .filter(|k| restart_keys.contains(&k.as_str()))
+            .cloned()
+            .collect();
+        if !stale.is_empty() {
+            mark_restart_required(subsystem, &stale);
+        }
+    });
+}
+
+/// Subsystems still running with settings older than the saved ones.
 #[tauri::command]
-pub fn save_settings(settings: AudioSettings) -> Result<(), String> {
+pub fn settings_pending_restarts() -> Vec<PendingRestart> {
+    let pending = PENDING_RESTARTS.lock().unwrap_or_else(|e| e.into_inner());
+    let mut list: Vec<PendingRestart> = pending.values().cloned().collect();
+    list.sort_by(|a, b| a.subsystem.cmp(&b.subsystem));
+    list
+}
+
+#[tauri::command]
+pub fn save_settings(mut settings: AudioSettings) -> Result<(), String> {
     backend_info("Command save_settings invoked");
+    let previous = load_settings();
     let path = settings_path();
-    let json = serde_json::to_string_pretty(&settings).map_err(|e| {
-        backend_error(format!("Failed to serialize settings: {}", e));
-        e.to_string()
-    })?;
-    fs::write(&path, json).map_err(|e| {
-        backend_error(format!("Failed to write settings file {}: {}", path.display(), e));
-        e.to_string()
-    })?;
+
+    // Never downgrade a file from a newer build, and keep keys the caller didn't send
+    settings.version = previous.version.max(CURRENT_VERSION);
+    settings.migrated_from = None;
+    for (key, value) in &previous.extra {
+        settings.extra.entry(key.clone()).or_insert_with(|| value.clone());
+    }
+
+    write_settings_file(&path, &settings)?;
     backend_info(format!("Settings saved to {}", path.display()));
+
+    // TTS/STT re-read settings on every call, so only long-lived subsystems need this
+    let changed_keys = diff_settings(&previous, &settings);
+    if !changed_keys.is_empty() {
+        backend_info(format!("Settings changed: {}", changed_keys.join(", ")));
+        let _ = SETTINGS_TX.send(SettingsChange { changed_keys, settings });
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_settings_lists_changed_keys() {
+        let old = AudioSettings::default();
+        let mut new = old.clone();
+        assert!(diff_settings(&old, &new).is_empty());
+
+        new.tts_voice = "pl_PL-gosia-medium".into();
+        new.mic_device_id = "hw:1".into();
+        assert_eq!(diff_settings(&old, &new), vec!["mic_device_id", "tts_voice"]);
+    }
+
+    #[test]
+    fn test_pending_restart_merges_keys() {
+        mark_restart_required("test_subsystem", &["a".to_string()]);
+        mark_restart_required("test_subsystem", &["a".to_string(), "b".to_string()]);
+        let entry = settings_pending_restarts()
+            .into_iter()
+            .find(|p| p.subsystem == "test_subsystem")
+            .unwrap();
+        assert_eq!(entry.changed_keys, vec!["a", "b"]);
+        clear_restart_required("test_subsystem");
+        assert!(settings_pending_restarts().iter().all(|p| p.subsystem != "test_subsystem"));
+    }
+
+    #[test]
+    fn test_rewatching_replaces_previous_watcher() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let counter = |hits: &Arc<AtomicUsize>| {
+            let hits = Arc::clone(hits);
+            move |change: &SettingsChange| {
+                if change.changed_keys.iter().any(|k| k == "test_watch_key") {
+                    hits.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        };
+        let (first, second) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        watch_settings("test_watch", counter(&first));
+        watch_settings("test_watch", counter(&second));
+
+        let send = || {
+            let change = SettingsChange { changed_keys: vec!["test_watch_key".into()], settings: AudioSettings::default() };
+            let _ = SETTINGS_TX.send(change);
+        };
+        send();
+        for _ in 0..100 {
+            if second.load(Ordering::SeqCst) > 0 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!((first.load(Ordering::SeqCst), second.load(Ordering::SeqCst)), (0, 1));
+
+        unwatch_settings("test_watch");
+        send();
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        assert_eq!(second.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_legacy_file_without_version_is_migrated_with_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        fs::write(&path, r#"{"tts_enabled": false, "tts_rate": 1.2, "stt_engine": "whisper"}"#).unwrap();
+
+        let settings = read_settings_file(&path);
+        assert!(!settings.tts_enabled);
+        assert_eq!(settings.stt_engine, "openrouter");
+        assert_eq!(settings.version, CURRENT_VERSION);
+        assert_eq!(settings.migrated_from, Some(0));
+        assert!(dir.path().join("settings.json.bak-0").exists());
+
+        // Rewritten file is current and loads without another migration
+        let again = read_settings_file(&path);
+        assert_eq!(again.migrated_from, None);
+        assert!(!fs::read_to_string(&path).unwrap().contains("migrated_from"));
+    }
+
+    #[test]
+    fn test_unknown_future_keys_round_trip() {
+        let data = r#"{"version": 1, "tts_enabled": true, "auto_listen_silence_ms": 1500, "future_flag": {"a": 1}}"#;
+        let (settings, from) = parse_and_migrate(data).unwrap();
+        assert_eq!(from, 1);
+        assert_eq!(settings.extra["auto_listen_silence_ms"], 1500);
+
+        let written: serde_json::Value = serde_json::to_value(&settings).unwrap();
+        assert_eq!(written["future_flag"]["a"], 1);
+        assert_eq!(written["auto_listen_silence_ms"], 1500);
+    }
+
+    #[test]
+    fn test_secrets_are_written_apart_and_merged_on_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        let mut settings = AudioSettings::default();
+        settings.tts_dictionary.insert("token".into(), "tołken".into());
+        settings.extra.insert(
+            "email".into(),
+            serde_json::json!({ "smtp_host": "smtp.example.com", "smtp_password": "hunter2", "api_key": "" }),
+        );
+        write_settings_file(&path, &settings).unwrap();
+
+        let on_disk = fs::read_to_string(&path).unwrap();
+        assert!(!on_disk.contains("hunter2"));
+        assert!(on_disk.contains("tołken"));
+        let secrets = read_secrets_file(&dir.path().join(SECRETS_FILE));
+        assert_eq!(secrets.len(), 1);
+        assert_eq!(secrets["/email/smtp_password"], "hunter2");
+        // Temp files were renamed into place
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+
+        let loaded = read_settings_file(&path);
+        assert_eq!(loaded.extra["email"]["smtp_password"], "hunter2");
+        assert_eq!(loaded.extra["email"]["api_key"], "");
+        assert_eq!(loaded.tts_dictionary["token"], "tołken");
+    }
+}
@@ -0,0 +1,69 @@
+//! settings_migrations.rs — Versioned upgrades of the persisted settings JSON.
+//!
+//! Migrations operate on the raw `serde_json::Map` before it is deserialized
+//! into `AudioSettings`, so they can rename keys or rewrite values. Keys this
+//! build does not know are left untouched and round-trip through
+//! `AudioSettings::extra`.
+//!
+//! To evolve the schema: append a `fn(&mut Map)` to `MIGRATIONS` and bump
+//! `CURRENT_VERSION` — `MIGRATIONS[n]` upgrades version n to n + 1.
+
+use serde_json::{Map, Value};
+
+pub const CURRENT_VERSION: u32 = 1;
+
+type Migration = fn(&mut Map<String, Value>);
+
+const MIGRATIONS: &[Migration] = &[v0_to_v1];
+
+/// Files written before versioning have no `version` key → v0.
+pub fn file_version(map: &Map<String, Value>) -> u32 {
+    map.get("version").and_then(Value::as_u64).unwrap_or(0) as u32
+}
+
+/// Upgrade `map` in place to `CURRENT_VERSION`. Returns the version it was
+/// loaded at. Files from a newer build are left as they are.
+pub fn migrate(map: &mut Map<String, Value>) -> u32 {
+    let from = file_version(map);
+    for (version, step) in MIGRATIONS.iter().enumerate().skip(from as usize) {
+        step(map);
+        map.insert("version".into(), Value::from(version as u32 + 1));
+    }
+    debug_assert_eq!(MIGRATIONS.len() as u32, CURRENT_VERSION);
+    from
+}
+
+// ── Steps ────────────────────────────────────────────
+
+/// v1: `stt_engine = "whisper"` (older UI) meant cloud Whisper via OpenRouter.
+fn v0_to_v1(map: &mut Map<String, Value>) {
+    if map.get("stt_engine").and_then(Value::as_str) == Some("whisper") {
+        map.insert("stt_engine".into(), Value::from("openrouter"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_legacy_file_is_upgraded() {
+        let Value::Object(mut map) = json!({ "tts_enabled": true, "stt_engine": "whisper" }) else {
+            unreachable!()
+        };
+        assert_eq!(migrate(&mut map), 0);
+        assert_eq!(file_version(&map), CURRENT_VERSION);
+        assert_eq!(map["stt_engine"], "openrouter");
+    }
+
+    #[test]
+    fn test_future_version_is_untouched() {
+        let Value::Object(mut map) = json!({ "version": 99, "stt_engine": "whisper" }) else {
+            unreachable!()
+        };
+        assert_eq!(migrate(&mut map), 99);
+        assert_eq!(map["version"], 99);
+        assert_eq!(map["stt_engine"], "whisper");
+    }
+}
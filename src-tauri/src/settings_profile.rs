@@ -0,0 +1,292 @@
+//! settings_profile.rs — Export/import of settings for moving to another machine.
+//!
+//! `settings_export` returns a JSON blob with the shareable profile (TTS/STT
+//! preferences, UI keys such as cameras and scan defaults) — everything
+//! except machine-local keys (audio device ids, log directory) and secrets.
+//! Secrets are only included on request, sealed with a passphrase.
+//!
+//! `settings_import` merges a blob key by key: keys absent from the blob are
+//! never touched, and without `overwrite` only keys still at their default
+//! are replaced. `dry_run` reports the changes without saving.
+
+use crate::credentials::{open_with_passphrase, seal_with_passphrase, PassphraseSealed};
+use crate::logging::{backend_info, backend_warn};
+use crate::settings::{self, AudioSettings, Secrets};
+use crate::settings_migrations::{self, CURRENT_VERSION};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+const EXPORT_FORMAT: &str = "broxeen-settings";
+/// Associated data of the sealed secrets
+const SECRETS_AAD: &str = "settings-secrets";
+/// Keys that only make sense on the machine they were set on
+const MACHINE_KEYS: &[&str] = &["version", "migrated_from", "mic_device_id", "speaker_device_id", "log_dir"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingsExport {
+    pub format: String,
+    /// Settings schema version of `profile`
+    pub version: u32,
+    #[serde(default)]
+    pub exported_at: String,
+    pub profile: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<PassphraseSealed>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SettingChange {
+    pub key: String,
+    pub from: Value,
+    pub to: Value,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SettingsImportReport {
+    pub dry_run: bool,
+    pub changed: Vec<SettingChange>,
+    /// In the blob but left as is: customised here and `overwrite` was off
+    pub kept: Vec<String>,
+    /// JSON pointers of secrets taken from the blob (values never reported)
+    pub secrets: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+fn export_blob(settings: &AudioSettings, secrets_passphrase: Option<&str>) -> Result<SettingsExport, String> {
+    let (profile, secrets) = settings::split_secrets(settings)?;
+    let Value::Object(mut profile) = profile else {
+        return Err("Ustawienia nie są obiektem JSON".to_string());
+    };
+    for key in MACHINE_KEYS {
+        profile.remove(*key);
+    }
+    let secrets = match secrets_passphrase {
+        Some(passphrase) => {
+            let plain = serde_json::to_vec(&secrets).map_err(|e| e.to_string())?;
+            Some(seal_with_passphrase(passphrase, SECRETS_AAD, &plain)?)
+        }
+        None => None,
+    };
+    Ok(SettingsExport {
+        format: EXPORT_FORMAT.to_string(),
+        version: CURRENT_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        profile,
+        secrets,
+    })
+}
+
+fn parse_blob(blob: &str) -> Result<SettingsExport, String> {
+    let export: SettingsExport =
+        serde_json::from_str(blob).map_err(|e| format!("Nieprawidłowy plik ustawień: {}", e))?;
+    if export.format != EXPORT_FORMAT {
+        return Err(format!("Nieznany format pliku ustawień: {}", export.format));
+    }
+    Ok(export)
+}
+
+/// Merge `export` into `current`; returns the merged settings and what changed.
+fn plan_import(
+    current: &AudioSettings,
+    export: &SettingsExport,
+    overwrite: bool,
+    passphrase: Option<&str>,
+) -> Result<(AudioSettings, SettingsImportReport), String> {
+    let mut report = SettingsImportReport::default();
+
+    // Upgrade profiles exported by older builds like a stored file
+    let mut profile = export.profile.clone();
+    profile.insert("version".into(), Value::from(export.version));
+    settings_migrations::migrate(&mut profile);
+    if export.version > CURRENT_VERSION {
+        report
+            .warnings
+            .push(format!("Plik z nowszej wersji (v{} > v{})", export.version, CURRENT_VERSION));
+    }
+
+    // Profiles are compared without secrets, which are put back afterwards
+    let (mut merged, current_secrets) = settings::split_secrets(current)?;
+    let defaults = serde_json::to_value(AudioSettings::default()).map_err(|e| e.to_string())?;
+    // A key is replaced with `overwrite`, or when it is missing / still at its default here
+    let replaceable = |pointer: &str, now: Option<&Value>| {
+        overwrite || now.is_none_or(|v| v.is_null() || v == &Value::from("") || defaults.pointer(pointer) == Some(v))
+    };
+
+    for (key, value) in &profile {
+        if MACHINE_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        let pointer = format!("/{}", key.replace('~', "~0").replace('/', "~1"));
+        let now = merged.pointer(&pointer);
+        if now == Some(value) {
+            continue;
+        }
+        if !replaceable(&pointer, now) {
+            report.kept.push(key.clone());
+            continue;
+        }
+        report.changed.push(SettingChange {
+            key: key.clone(),
+            from: now.cloned().unwrap_or(Value::Null),
+            to: value.clone(),
+        });
+        settings::put_pointer(&mut merged, &pointer, value.clone());
+    }
+
+    for (pointer, value) in current_secrets {
+        settings::put_pointer(&mut merged, &pointer, value);
+    }
+
+    match (&export.secrets, passphrase) {
+        (Some(sealed), Some(passphrase)) => {
+            let plain = open_with_passphrase(passphrase, SECRETS_AAD, sealed)
+                .map_err(|_| "Nieprawidłowe hasło do sekretów w pliku ustawień".to_string())?;
+            let secrets: Secrets = serde_json::from_slice(&plain).map_err(|e| e.to_string())?;
+            for (pointer, value) in secrets {
+                let now = merged.pointer(&pointer);
+                if now == Some(&value) {
+                    continue;
+                }
+                if !replaceable(&pointer, now) {
+                    report.kept.push(pointer);
+                    continue;
+                }
+                settings::put_pointer(&mut merged, &pointer, value);
+                report.secrets.push(pointer);
+            }
+        }
+        (Some(_), None) => report
+            .warnings
+            .push("Plik zawiera zaszyfrowane sekrety — podaj hasło, aby je zaimportować".to_string()),
+        (None, _) => {}
+    }
+
+    let settings: AudioSettings =
+        serde_json::from_value(merged).map_err(|e| format!("Nieprawidłowe wartości w pliku ustawień: {}", e))?;
+    Ok((settings, report))
+}
+
+/// Shareable settings as JSON. With `include_secrets` the secrets are added,
+/// sealed with `passphrase` (required then).
+#[tauri::command]
+pub fn settings_export(include_secrets: bool, passphrase: Option<String>) -> Result<String, String> {
+    backend_info(format!("Command settings_export invoked (include_secrets={})", include_secrets));
+    let passphrase = if include_secrets {
+        let passphrase = passphrase.filter(|p| !p.is_empty());
+        Some(passphrase.ok_or_else(|| "Eksport sekretów wymaga hasła".to_string())?)
+    } else {
+        None
+    };
+    let export = export_blob(&settings::load_settings(), passphrase.as_deref())?;
+    serde_json::to_string_pretty(&export).map_err(|e| e.to_string())
+}
+
+/// Merge an exported blob into the current settings (see module docs).
+#[tauri::command]
+pub fn settings_import(
+    blob: String,
+    overwrite: bool,
+    dry_run: Option<bool>,
+    passphrase: Option<String>,
+) -> Result<SettingsImportReport, String> {
+    let dry_run = dry_run.unwrap_or(false);
+    backend_info(format!(
+        "Command settings_import invoked (overwrite={}, dry_run={})",
+        overwrite, dry_run
+    ));
+    let export = parse_blob(&blob)?;
+    let (merged, mut report) = plan_import(&settings::load_settings(), &export, overwrite, passphrase.as_deref())?;
+    report.dry_run = dry_run;
+    for warning in &report.warnings {
+        backend_warn(format!("settings_import: {}", warning));
+    }
+    if !dry_run && (!report.changed.is_empty() || !report.secrets.is_empty()) {
+        settings::save_settings(merged)?;
+        backend_info(format!(
+            "Imported settings: {} changed, {} secrets, {} kept",
+            report.changed.len(),
+            report.secrets.len(),
+            report.kept.len()
+        ));
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn customised() -> AudioSettings {
+        let mut settings = AudioSettings {
+            tts_rate: 1.3,
+            tts_voice: "pl_PL-gosia-medium".into(),
+            mic_device_id: "hw:2".into(),
+            ..AudioSettings::default()
+        };
+        settings.extra.insert(
+            "email".into(),
+            serde_json::json!({ "smtp_host": "smtp.example.com", "smtp_password": "hunter2" }),
+        );
+        settings
+    }
+
+    fn round_trip(export: &SettingsExport) -> SettingsExport {
+        parse_blob(&serde_json::to_string(export).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_export_import_round_trip_is_stable() {
+        let source = customised();
+        let export = round_trip(&export_blob(&source, Some("pass")).unwrap());
+        let text = serde_json::to_string(&export).unwrap();
+        assert!(!text.contains("hunter2"));
+        assert!(!text.contains("hw:2"));
+
+        let (imported, report) = plan_import(&AudioSettings::default(), &export, false, Some("pass")).unwrap();
+        assert_eq!(imported.tts_rate, 1.3);
+        assert_eq!(imported.extra["email"]["smtp_password"], "hunter2");
+        assert_eq!(imported.mic_device_id, "default");
+        assert_eq!(report.secrets, vec!["/email/smtp_password"]);
+        assert!(report.kept.is_empty());
+
+        // Exporting the imported settings gives the same profile, and
+        // re-importing it changes nothing
+        let again = export_blob(&imported, None).unwrap();
+        assert_eq!(again.profile, export.profile);
+        let (_, report) = plan_import(&imported, &again, true, None).unwrap();
+        assert!(report.changed.is_empty());
+
+        assert!(plan_import(&AudioSettings::default(), &export, false, Some("wrong")).is_err());
+        let (_, report) = plan_import(&AudioSettings::default(), &export, false, None).unwrap();
+        assert!(report.secrets.is_empty());
+        assert_eq!(report.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_import_merges_without_clobbering() {
+        let mut export = round_trip(&export_blob(&customised(), None).unwrap());
+        export.profile.retain(|key, _| key == "tts_rate" || key == "tts_voice");
+
+        // Mutated locally after the export
+        let current = AudioSettings {
+            tts_voice: "pl_PL-darkman-medium".into(),
+            tts_pitch: 0.8,
+            ..AudioSettings::default()
+        };
+
+        let (merged, report) = plan_import(&current, &export, false, None).unwrap();
+        assert_eq!(merged.tts_rate, 1.3);
+        assert_eq!(merged.tts_voice, "pl_PL-darkman-medium");
+        assert_eq!(merged.tts_pitch, 0.8);
+        assert_eq!(report.kept, vec!["tts_voice"]);
+        assert_eq!(report.changed.len(), 1);
+
+        let (merged, _) = plan_import(&current, &export, true, None).unwrap();
+        assert_eq!(merged.tts_voice, "pl_PL-gosia-medium");
+        assert_eq!(merged.tts_pitch, 0.8);
+
+        export.profile.insert("tts_rate".into(), Value::from("fast"));
+        assert!(plan_import(&current, &export, true, None).is_err());
+        assert!(parse_blob(r#"{"format": "other", "version": 1, "profile": {}}"#).is_err());
+    }
+}
@@ -0,0 +1,281 @@
+//! shutdown.rs — Orderly exit and orphan cleanup.
+//!
+//! On Tauri's `ExitRequested`/`Exit` events `run` stops the RTSP workers,
+//! every motion pipeline, the vision WebSocket feed and the Frigate MQTT
+//! bridge, gives the child processes a few seconds to exit, kills what is
+//! left and flushes the log.
+//!
+//! ffmpeg workers and `motion_pipeline.py` children are recorded in
+//! `<data_local_dir>/broxeen/children.json` while they run. A crashed
+//! instance leaves the file behind; `reap_orphans` at the next start kills
+//! the listed processes that still run the same program.
+
+use crate::logging::{backend_info, backend_warn};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long children get to exit on their own before `kill -9`.
+const GRACE_PERIOD: Duration = Duration::from_secs(3);
+const MQTT_STOP_TIMEOUT: Duration = Duration::from_secs(2);
+
+static SHUTDOWN_STARTED: AtomicBool = AtomicBool::new(false);
+static CHILDREN: Mutex<Vec<ChildEntry>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChildEntry {
+    pub pid: u32,
+    /// Substring of the command line identifying the process ("ffmpeg").
+    pub marker: String,
+    /// Camera id or other owner, for the logs.
+    pub label: String,
+}
+
+fn pid_file() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("broxeen")
+        .join("children.json")
+}
+
+fn save(children: &[ChildEntry]) {
+    let path = pid_file();
+    if children.is_empty() {
+        let _ = std::fs::remove_file(&path);
+        return;
+    }
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    match serde_json::to_vec(children) {
+        Ok(bytes) => {
+            if let Err(e) = std::fs::write(&path, bytes) {
+                backend_warn(format!("Cannot write {}: {}", path.display(), e));
+            }
+        }
+        Err(e) => backend_warn(format!("Cannot serialize child pids: {}", e)),
+    }
+}
+
+fn parse_pid_file(text: &str) -> Vec<ChildEntry> {
+    serde_json::from_str(text).unwrap_or_default()
+}
+
+/// Unregisters the child when dropped — keep it alive as long as the process.
+#[derive(Debug)]
+pub struct ChildGuard(u32);
+
+impl Drop for ChildGuard {
+    fn drop(&mut self) {
+        if let Ok(mut children) = CHILDREN.lock() {
+            children.retain(|c| c.pid != self.0);
+            save(&children);
+        }
+    }
+}
+
+/// Record a spawned child so shutdown (or the next start) can clean it up.
+pub fn track_child(pid: u32, marker: &str, label: &str) -> ChildGuard {
+    if let Ok(mut children) = CHILDREN.lock() {
+        children.retain(|c| c.pid != pid);
+        children.push(ChildEntry {
+            pid,
+            marker: marker.to_string(),
+            label: label.to_string(),
+        });
+        save(&children);
+    }
+    ChildGuard(pid)
+}
+
+fn tracked_pids() -> Vec<u32> {
+    CHILDREN
+        .lock()
+        .map(|c| c.iter().map(|e| e.pid).collect())
+        .unwrap_or_default()
+}
+
+/// `ps -o stat=,command=` line → command, `None` for zombies.
+#[cfg_attr(windows, allow(dead_code))]
+fn parse_ps_line(line: &str) -> Option<String> {
+    let line = line.trim();
+    let (stat, command) = line.split_once(char::is_whitespace)?;
+    if stat.starts_with('Z') {
+        return None;
+    }
+    Some(command.trim().to_string())
+}
+
+/// Command line of a running process, `None` when it is gone.
+#[cfg(unix)]
+fn process_command(pid: u32) -> Option<String> {
+    let out = Command::new("ps")
+        .args(["-p", &pid.to_string(), "-o", "stat=,command="])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&out.stdout).lines().find_map(parse_ps_line)
+}
+
+/// Command line of a running process, `None` when it is gone. `tasklist`
+/// only reports the image name ("python.exe"), which never contains a
+/// marker such as "motion_pipeline", so ask CIM for the full command line.
+#[cfg(windows)]
+fn process_command(pid: u32) -> Option<String> {
+    let query = format!(
+        "(Get-CimInstance Win32_Process -Filter 'ProcessId={}').CommandLine",
+        pid
+    );
+    let out = Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &query])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let line = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    (!line.is_empty()).then_some(line)
+}
+
+/// Whether `command` still runs the program `marker` names. Windows paths
+/// and image names are case-insensitive ("FFMPEG.EXE").
+fn runs_marker(command: &str, marker: &str) -> bool {
+    if cfg!(windows) {
+        command.to_lowercase().contains(&marker.to_lowercase())
+    } else {
+        command.contains(marker)
+    }
+}
+
+/// Ask a process to exit (`force` = SIGKILL / `taskkill /F`).
+#[cfg(unix)]
+pub fn signal(pid: u32, force: bool) {
+    let sig = if force { "-KILL" } else { "-TERM" };
+    let _ = Command::new("kill")
+        .args([sig, &pid.to_string()])
+        .stderr(Stdio::null())
+        .status();
+}
+
+#[cfg(windows)]
+pub fn signal(pid: u32, force: bool) {
+    let pid = pid.to_string();
+    let mut args = vec!["/PID", pid.as_str(), "/T"];
+    if force {
+        args.push("/F");
+    }
+    let _ = Command::new("taskkill")
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+/// Kill children left behind by a crashed previous instance. Called once
+/// from the Tauri `setup` hook, before anything is spawned.
+pub fn reap_orphans() {
+    let path = pid_file();
+    let Ok(text) = std::fs::read_to_string(&path) else {
+        return;
+    };
+    let _ = std::fs::remove_file(&path);
+
+    let own_pid = std::process::id();
+    for entry in parse_pid_file(&text) {
+        if entry.pid == own_pid {
+            continue;
+        }
+        // PIDs get reused: only kill a process that still runs the same program.
+        match process_command(entry.pid) {
+            Some(cmd) if runs_marker(&cmd, &entry.marker) => {
+                backend_warn(format!(
+                    "Killing orphaned {} (pid {}, {}) from a previous run",
+                    entry.marker, entry.pid, entry.label
+                ));
+                signal(entry.pid, true);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// TERM every tracked child, wait up to `grace`, then KILL the survivors.
+/// Returns how many had to be killed.
+fn terminate_children(grace: Duration) -> usize {
+    let pids = tracked_pids();
+    for pid in &pids {
+        signal(*pid, false);
+    }
+
+    let deadline = Instant::now() + grace;
+    let mut alive = pids;
+    while !alive.is_empty() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(100));
+        let tracked = tracked_pids();
+        alive.retain(|pid| tracked.contains(pid) && process_command(*pid).is_some());
+    }
+
+    for pid in &alive {
+        backend_warn(format!("Child pid {} ignored SIGTERM, killing", pid));
+        signal(*pid, true);
+    }
+    alive.len()
+}
+
+/// Stop everything Broxeen started. Safe to call more than once; only the
+/// first call does the work.
+pub fn run() {
+    if SHUTDOWN_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    backend_info("Shutting down: stopping workers and pipelines");
+
+    if let Err(e) = crate::network_scan::rtsp_stop_all_workers() {
+        backend_warn(format!("Stopping RTSP workers failed: {}", e));
+    }
+
+    #[cfg(feature = "vision")]
+    crate::vision_ws::stop();
+
+    let pipelines = crate::motion_detection::stop_all_pipelines(GRACE_PERIOD);
+    if pipelines > 0 {
+        backend_info(format!("Stopped {} motion pipeline(s)", pipelines));
+    }
+
+    let mqtt = tauri::async_runtime::block_on(async {
+        tokio::time::timeout(MQTT_STOP_TIMEOUT, crate::frigate_mqtt::frigate_mqtt_stop()).await
+    });
+    match mqtt {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => backend_warn(format!("Stopping Frigate MQTT failed: {}", e)),
+        Err(_) => backend_warn("Frigate MQTT did not stop in time"),
+    }
+
+    let killed = terminate_children(GRACE_PERIOD);
+    if killed > 0 {
+        backend_warn(format!("Killed {} child process(es) at shutdown", killed));
+    }
+    backend_info("Broxeen backend stopped gracefully");
+    crate::logging::flush_log_file();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ps_lines_and_pid_file() {
+        assert_eq!(
+            parse_ps_line("Ss   ffmpeg -hide_banner -i rtsp://cam/1"),
+            Some("ffmpeg -hide_banner -i rtsp://cam/1".to_string())
+        );
+        assert_eq!(parse_ps_line("Z+   [python3] <defunct>"), None);
+        assert_eq!(parse_ps_line(""), None);
+
+        let entries = vec![ChildEntry { pid: 42, marker: "ffmpeg".into(), label: "front".into() }];
+        let text = serde_json::to_string(&entries).unwrap();
+        assert_eq!(parse_pid_file(&text), entries);
+        assert!(parse_pid_file("not json").is_empty());
+    }
+}
@@ -0,0 +1,419 @@
+//! site_crawl.rs — Multi-page site crawl for "summarize the whole site".
+//!
+//! `browse_site` takes the page list from the sitemap (robots.txt `Sitemap:`
+//! lines, then `/sitemap.xml`) or, without one, follows in-page links from
+//! the start URL. Pages are fetched a few at a time with a per-host delay,
+//! robots.txt `Disallow` rules are respected, and every page goes through the
+//! same Tier 1 extraction + cleaning as `browse`. Progress is emitted as
+//! `broxeen:crawl_progress`; `browse_site_cancel` stops a crawl by request id.
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::content_cleaning::{boilerplate_patterns_from, clean_content, truncate_to_chars};
+use crate::content_extraction::{extract_text_blocks, extract_tier1};
+use crate::http_policy::{self, RobotsRules};
+use crate::logging::{backend_info, backend_warn};
+
+const CRAWL_PROGRESS_EVENT: &str = "broxeen:crawl_progress";
+const DEFAULT_MAX_PAGES: usize = 20;
+const MAX_PAGES_LIMIT: usize = 200;
+/// Pages in flight at once
+const CONCURRENCY: usize = 4;
+/// Minimum spacing between requests to one host (raised by `Crawl-delay`),
+/// the same as for single `browse` calls
+const HOST_DELAY: Duration = http_policy::MIN_HOST_INTERVAL;
+const PAGE_TIMEOUT: Duration = Duration::from_secs(15);
+const MAX_PAGE_BYTES: usize = 3 * 1024 * 1024;
+/// Content kept per page in the result
+const PAGE_CONTENT_CHARS: usize = 4000;
+/// Nested sitemaps followed from a sitemap index
+const MAX_CHILD_SITEMAPS: usize = 5;
+const SKIPPED_EXTENSIONS: &[&str] = &[
+    ".pdf", ".jpg", ".jpeg", ".png", ".gif", ".webp", ".svg", ".zip", ".gz", ".mp3", ".mp4",
+    ".avi", ".mov", ".doc", ".docx", ".xls", ".xlsx", ".ppt", ".pptx", ".css", ".js", ".xml",
+];
+
+static CRAWL_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+lazy_static::lazy_static! {
+    static ref CRAWLS: Mutex<HashMap<String, Arc<AtomicBool>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrawledPage {
+    pub url: String,
+    pub title: String,
+    pub content: String,
+    pub resolve_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct CrawlStats {
+    /// "sitemap" or "links"
+    pub source: String,
+    pub pages_fetched: usize,
+    pub pages_failed: usize,
+    pub skipped_robots: usize,
+    pub total_chars: usize,
+    pub duration_ms: u64,
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SiteCrawlResult {
+    pub request_id: String,
+    pub start_url: String,
+    pub pages: Vec<CrawledPage>,
+    pub stats: CrawlStats,
+}
+
+/// Payload of `broxeen:crawl_progress`; `status` is "ok", "error",
+/// "skipped" (robots.txt) or "done".
+#[derive(Debug, Clone, Serialize)]
+pub struct CrawlProgressEvent {
+    pub request_id: String,
+    pub url: String,
+    pub status: &'static str,
+    pub done: usize,
+    pub planned: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// ── Sitemaps and links ───────────────────────────────
+
+/// `<loc>` entries and whether the document is a sitemap index.
+fn parse_sitemap(xml: &str) -> (Vec<String>, bool) {
+    let loc = regex_lite::Regex::new(r"(?is)<loc>\s*(.*?)\s*</loc>").expect("valid regex");
+    let locs = loc
+        .captures_iter(xml)
+        .map(|c| {
+            c[1].trim()
+                .trim_start_matches("<![CDATA[")
+                .trim_end_matches("]]>")
+                .replace("&amp;", "&")
+        })
+        .collect();
+    (locs, xml.to_ascii_lowercase().contains("<sitemapindex"))
+}
+
+fn host_key(url: &url::Url) -> String {
+    url.host_str().unwrap_or_default().trim_start_matches("www.").to_ascii_lowercase()
+}
+
+/// Normalize a crawl candidate: http(s) only, no fragment, no binary assets,
+/// same site when `same_domain`.
+fn crawl_candidate(raw: &str, base: &url::Url, start: &url::Url, same_domain: bool) -> Option<url::Url> {
+    let mut url = base.join(raw.trim()).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    url.set_fragment(None);
+    let path = url.path().to_ascii_lowercase();
+    if SKIPPED_EXTENSIONS.iter().any(|ext| path.ends_with(ext)) {
+        return None;
+    }
+    if same_domain && host_key(&url) != host_key(start) {
+        return None;
+    }
+    Some(url)
+}
+
+fn extract_links(document: &scraper::Html, base: &url::Url, start: &url::Url, same_domain: bool) -> Vec<url::Url> {
+    let sel = scraper::Selector::parse("a[href]").expect("valid selector");
+    document
+        .select(&sel)
+        .filter_map(|a| a.value().attr("href"))
+        .filter_map(|href| crawl_candidate(href, base, start, same_domain))
+        .collect()
+}
+
+// ── Fetching ─────────────────────────────────────────
+
+/// GET `url` as text. The body is capped at `MAX_PAGE_BYTES` while it
+/// streams in and decoded with the Content-Type charset (UTF-8 by default).
+async fn fetch_text(client: &reqwest::Client, url: &str) -> Result<(String, String), String> {
+    let mut resp = client.get(url).send().await.map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("HTTP {}", resp.status()));
+    }
+    let final_url = resp.url().to_string();
+    let content_type = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    if !content_type.is_empty() && !content_type.contains("html") && !content_type.contains("xml") && !content_type.contains("text") {
+        return Err(format!("Pominięto typ treści {}", content_type));
+    }
+    if let Some(len) = resp.content_length().filter(|len| *len > MAX_PAGE_BYTES as u64) {
+        return Err(format!("Strona za duża ({} B)", len));
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = resp.chunk().await.map_err(|e| e.to_string())? {
+        if body.len() + chunk.len() > MAX_PAGE_BYTES {
+            return Err(format!("Strona za duża (ponad {} B)", MAX_PAGE_BYTES));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let encoding = content_type
+        .split(';')
+        .find_map(|param| param.trim().strip_prefix("charset="))
+        .and_then(|label| encoding_rs::Encoding::for_label(label.trim_matches('"').as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    Ok((final_url, encoding.decode(&body).0.into_owned()))
+}
+
+/// Page URLs from the sitemaps named in robots.txt, else `/sitemap.xml`.
+async fn sitemap_urls(client: &reqwest::Client, start: &url::Url, robots: &RobotsRules, limit: usize) -> Vec<String> {
+    let mut queue: VecDeque<String> = robots.sitemaps.iter().cloned().collect();
+    if queue.is_empty() {
+        if let Ok(default) = start.join("/sitemap.xml") {
+            queue.push_back(default.to_string());
+        }
+    }
+    let mut pages = Vec::new();
+    let mut fetched = 0;
+    while let Some(sitemap) = queue.pop_front() {
+        if fetched > MAX_CHILD_SITEMAPS || pages.len() >= limit {
+            break;
+        }
+        fetched += 1;
+        let Ok((_, xml)) = fetch_text(client, &sitemap).await else { continue };
+        let (locs, is_index) = parse_sitemap(&xml);
+        if is_index {
+            queue.extend(locs);
+        } else {
+            pages.extend(locs);
+        }
+    }
+    pages
+}
+
+/// Tier 1 extraction + cleaning, plus outgoing links for link crawling.
+fn process_page(html: &str, url: &str, final_url: &str, patterns: &[String], start: &url::Url, same_domain: bool) -> (CrawledPage, Vec<url::Url>) {
+    let (title, content) = extract_tier1(html, url, final_url);
+    let base = url::Url::parse(final_url).unwrap_or_else(|_| start.clone());
+    let (blocks, links) = {
+        let document = scraper::Html::parse_document(html);
+        (extract_text_blocks(&document), extract_links(&document, &base, start, same_domain))
+    };
+    let cleaned = clean_content(&content, &blocks, patterns);
+    let page = CrawledPage {
+        url: final_url.to_string(),
+        title: if title.trim().is_empty() { final_url.to_string() } else { title },
+        content: truncate_to_chars(&cleaned.text, PAGE_CONTENT_CHARS),
+        resolve_type: "exact".to_string(),
+    };
+    (page, links)
+}
+
+// ── Tauri commands ───────────────────────────────────
+
+/// Crawl up to `max_pages` pages of a site (default 20) and return their
+/// extracted content. `same_domain_only` (default true) keeps the crawl on
+/// the start URL's host. Cancel with `browse_site_cancel(request_id)`.
+#[tauri::command]
+pub async fn browse_site(
+    app: AppHandle,
+    url: String,
+    max_pages: Option<usize>,
+    same_domain_only: Option<bool>,
+    request_id: Option<String>,
+) -> Result<SiteCrawlResult, String> {
+    let started = Instant::now();
+    let max_pages = max_pages.unwrap_or(DEFAULT_MAX_PAGES).clamp(1, MAX_PAGES_LIMIT);
+    let same_domain = same_domain_only.unwrap_or(true);
+    let request_id = request_id
+        .filter(|id| !id.trim().is_empty())
+        .unwrap_or_else(|| format!("crawl-{}", CRAWL_COUNTER.fetch_add(1, Ordering::Relaxed) + 1));
+    backend_info(format!(
+        "Command browse_site invoked [{}] (url={}, max_pages={}, same_domain_only={})",
+        request_id, url, max_pages, same_domain
+    ));
+
+    let start = url::Url::parse(url.trim()).map_err(|e| format!("Nieprawidłowy URL {}: {}", url, e))?;
+    if !matches!(start.scheme(), "http" | "https") {
+        return Err(format!("Nieobsługiwany schemat URL: {}", start.scheme()));
+    }
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    CRAWLS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(request_id.clone(), cancelled.clone());
+
+    let result = crawl(&app, &request_id, &start, max_pages, same_domain, &cancelled, started).await;
+    CRAWLS.lock().unwrap_or_else(|e| e.into_inner()).remove(&request_id);
+    result
+}
+
+async fn crawl(
+    app: &AppHandle,
+    request_id: &str,
+    start: &url::Url,
+    max_pages: usize,
+    same_domain: bool,
+    cancelled: &Arc<AtomicBool>,
+    started: Instant,
+) -> Result<SiteCrawlResult, String> {
+    let client = crate::http_client::client(PAGE_TIMEOUT)?;
+    let robots = http_policy::robots_for(start).await;
+    let delay = robots.crawl_delay.unwrap_or(HOST_DELAY).max(HOST_DELAY);
+    // Settings are read once; a change saved mid-crawl applies to the next one
+    let settings = crate::settings::load_settings();
+    let patterns = Arc::new(boilerplate_patterns_from(&settings));
+    let ignore_robots = settings.ignore_robots;
+
+    let mut frontier: VecDeque<url::Url> = VecDeque::from([start.clone()]);
+    let mut seen: HashSet<String> = HashSet::from([start.to_string()]);
+    let from_sitemap: Vec<url::Url> = sitemap_urls(&client, start, &robots, MAX_PAGES_LIMIT * 5)
+        .await
+        .iter()
+        .filter_map(|loc| crawl_candidate(loc, start, start, same_domain))
+        .collect();
+    let sitemap_mode = !from_sitemap.is_empty();
+    for url in from_sitemap {
+        if seen.insert(url.to_string()) {
+            frontier.push_back(url);
+        }
+    }
+
+    let mut stats = CrawlStats {
+        source: if sitemap_mode { "sitemap" } else { "links" }.to_string(),
+        ..Default::default()
+    };
+    let mut pages = Vec::new();
+    let planned = |frontier: &VecDeque<url::Url>, done: usize| (done + frontier.len()).min(max_pages);
+    let emit = |url: &str, status: &'static str, done: usize, planned: usize, error: Option<String>| {
+        let _ = app.emit(
+            CRAWL_PROGRESS_EVENT,
+            CrawlProgressEvent { request_id: request_id.to_string(), url: url.to_string(), status, done, planned, error },
+        );
+    };
+
+    while !frontier.is_empty() && pages.len() < max_pages {
+        if cancelled.load(Ordering::Relaxed) {
+            stats.cancelled = true;
+            break;
+        }
+        let mut tasks = tokio::task::JoinSet::new();
+        while tasks.len() < CONCURRENCY.min(max_pages - pages.len()) {
+            let Some(url) = frontier.pop_front() else { break };
+            if !http_policy::robots_allows(&url, ignore_robots).await {
+                stats.skipped_robots += 1;
+                emit(url.as_str(), "skipped", pages.len(), planned(&frontier, pages.len()), None);
+                continue;
+            }
+            let (client, patterns, cancelled, start) =
+                (client.clone(), patterns.clone(), cancelled.clone(), start.clone());
+            tasks.spawn(async move {
+                http_policy::throttle(url.host_str().unwrap_or_default(), delay).await;
+                if cancelled.load(Ordering::Relaxed) {
+                    return (url, Err("cancelled".to_string()));
+                }
+                let fetched = fetch_text(&client, url.as_str()).await;
+                let processed = fetched.map(|(final_url, html)| {
+                    process_page(&html, url.as_str(), &final_url, &patterns, &start, same_domain)
+                });
+                (url, processed)
+            });
+        }
+
+        while let Some(joined) = tasks.join_next().await {
+            let Ok((url, processed)) = joined else { continue };
+            match processed {
+                Ok((page, links)) => {
+                    stats.pages_fetched += 1;
+                    stats.total_chars += page.content.chars().count();
+                    pages.push(page);
+                    if !sitemap_mode {
+                        for link in links {
+                            if seen.insert(link.to_string()) {
+                                frontier.push_back(link);
+                            }
+                        }
+                    }
+                    emit(url.as_str(), "ok", pages.len(), planned(&frontier, pages.len()), None);
+                }
+                Err(e) if e == "cancelled" => stats.cancelled = true,
+                Err(e) => {
+                    stats.pages_failed += 1;
+                    backend_warn(format!("browse_site [{}]: {} failed: {}", request_id, url, e));
+                    emit(url.as_str(), "error", pages.len(), planned(&frontier, pages.len()), Some(e));
+                }
+            }
+        }
+    }
+
+    pages.truncate(max_pages);
+    stats.duration_ms = started.elapsed().as_millis() as u64;
+    emit(start.as_str(), "done", pages.len(), pages.len(), None);
+    backend_info(format!(
+        "browse_site [{}] finished: {} pages ({} failed, {} blocked by robots.txt, source={}, cancelled={}) in {} ms",
+        request_id, stats.pages_fetched, stats.pages_failed, stats.skipped_robots, stats.source, stats.cancelled, stats.duration_ms
+    ));
+
+    Ok(SiteCrawlResult {
+        request_id: request_id.to_string(),
+        start_url: start.to_string(),
+        pages,
+        stats,
+    })
+}
+
+/// Stop a running crawl; pages fetched so far are still returned by
+/// `browse_site`. Returns false for unknown or finished crawls.
+#[tauri::command]
+pub fn browse_site_cancel(request_id: String) -> bool {
+    let flag = CRAWLS.lock().unwrap_or_else(|e| e.into_inner()).get(&request_id).cloned();
+    match flag {
+        Some(flag) => {
+            backend_info(format!("browse_site_cancel [{}]", request_id));
+            flag.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sitemap_and_index() {
+        let urlset = r#"<?xml version="1.0"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url><loc>https://example.com/</loc></url>
+  <url><loc> https://example.com/o-nas?a=1&amp;b=2 </loc></url>
+</urlset>"#;
+        let (locs, index) = parse_sitemap(urlset);
+        assert!(!index);
+        assert_eq!(locs, vec!["https://example.com/", "https://example.com/o-nas?a=1&b=2"]);
+
+        let (locs, index) = parse_sitemap("<sitemapindex><sitemap><loc>https://example.com/s1.xml</loc></sitemap></sitemapindex>");
+        assert!(index);
+        assert_eq!(locs, vec!["https://example.com/s1.xml"]);
+    }
+
+    #[test]
+    fn test_crawl_candidate_filters() {
+        let start = url::Url::parse("https://www.example.com/").unwrap();
+        let base = url::Url::parse("https://www.example.com/blog/").unwrap();
+        let ok = |raw: &str, same: bool| crawl_candidate(raw, &base, &start, same).map(|u| u.to_string());
+
+        assert_eq!(ok("wpis-1#komentarze", true).as_deref(), Some("https://www.example.com/blog/wpis-1"));
+        assert_eq!(ok("https://example.com/kontakt", true).as_deref(), Some("https://example.com/kontakt"));
+        assert_eq!(ok("https://other.org/", true), None);
+        assert!(ok("https://other.org/", false).is_some());
+        assert_eq!(ok("/files/cennik.pdf", true), None);
+        assert_eq!(ok("mailto:biuro@example.com", true), None);
+    }
+}
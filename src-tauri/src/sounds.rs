@@ -0,0 +1,195 @@
+//! sounds.rs — Short audible cues for eyes-free use.
+//!
+//! Chimes are synthesized as tiny WAV clips (no bundled assets) and played on
+//! their own output stream + sink, so a notification mixes over speech and
+//! never touches the `ActiveTts` slot. Repeats of the same kind within
+//! `MIN_REPEAT` are dropped so a busy camera does not turn into a buzzer.
+//! The notification settings are read once and kept current by
+//! `watch_sound_settings`, so a detection never touches settings.json.
+
+use crate::logging::{backend_info, backend_warn};
+use crate::settings::AudioSettings;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+const SAMPLE_RATE: u32 = 22_050;
+const MIN_REPEAT: Duration = Duration::from_millis(1500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    Detection,
+    WakeWord,
+    Error,
+}
+
+impl NotificationKind {
+    /// (frequency Hz, duration ms) per note
+    fn notes(&self) -> &'static [(f32, u32)] {
+        match self {
+            NotificationKind::Detection => &[(880.0, 110), (1320.0, 140)],
+            NotificationKind::WakeWord => &[(660.0, 80), (990.0, 120)],
+            NotificationKind::Error => &[(440.0, 160), (330.0, 220)],
+        }
+    }
+
+    fn volume(&self, settings: &SoundSettings) -> f32 {
+        match self {
+            NotificationKind::Detection => settings.volume_detection,
+            NotificationKind::WakeWord => settings.volume_wake_word,
+            NotificationKind::Error => settings.volume_error,
+        }
+    }
+}
+
+/// The notification part of `AudioSettings`.
+#[derive(Debug, Clone)]
+struct SoundSettings {
+    enabled: bool,
+    volume_detection: f32,
+    volume_wake_word: f32,
+    volume_error: f32,
+    detection_min_confidence: f32,
+    speaker_device_id: String,
+}
+
+impl SoundSettings {
+    fn from_settings(settings: &AudioSettings) -> Self {
+        Self {
+            enabled: settings.notification_sounds_enabled,
+            volume_detection: settings.notification_volume_detection,
+            volume_wake_word: settings.notification_volume_wake_word,
+            volume_error: settings.notification_volume_error,
+            detection_min_confidence: settings.notification_detection_min_confidence,
+            speaker_device_id: settings.speaker_device_id.clone(),
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref LAST_PLAYED: Mutex<HashMap<NotificationKind, Instant>> = Mutex::new(HashMap::new());
+    /// Filled on first use, replaced on every settings save
+    static ref SOUND_SETTINGS: RwLock<Option<SoundSettings>> = RwLock::new(None);
+}
+
+fn sound_settings() -> SoundSettings {
+    if let Some(settings) = SOUND_SETTINGS.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        return settings.clone();
+    }
+    let settings = SoundSettings::from_settings(&crate::settings::load_settings());
+    *SOUND_SETTINGS.write().unwrap_or_else(|e| e.into_inner()) = Some(settings.clone());
+    settings
+}
+
+/// Keep the cached notification settings in sync with saved settings.
+pub fn watch_sound_settings() {
+    crate::settings::watch_settings("notification_sounds", |change| {
+        *SOUND_SETTINGS.write().unwrap_or_else(|e| e.into_inner()) = Some(SoundSettings::from_settings(&change.settings));
+    });
+}
+
+/// 16-bit mono PCM WAV of the kind's chime, with short fades against clicks.
+pub fn chime_wav(kind: NotificationKind) -> Result<Vec<u8>, String> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let fade = (SAMPLE_RATE / 200) as usize; // 5 ms
+    let mut cursor = Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut cursor, spec).map_err(|e| format!("WAV writer error: {e}"))?;
+    for &(freq, ms) in kind.notes() {
+        let n = (SAMPLE_RATE * ms / 1000) as usize;
+        for i in 0..n {
+            let envelope = (i.min(n - 1 - i) as f32 / fade as f32).min(1.0);
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let v = (2.0 * std::f32::consts::PI * freq * t).sin() * envelope * 0.6;
+            writer
+                .write_sample((v * i16::MAX as f32) as i16)
+                .map_err(|e| format!("WAV write error: {e}"))?;
+        }
+    }
+    writer.finalize().map_err(|e| format!("WAV finalize error: {e}"))?;
+    Ok(cursor.into_inner())
+}
+
+/// Claim the slot for `kind`; false when it played less than `MIN_REPEAT` ago.
+fn claim(kind: NotificationKind, now: Instant) -> bool {
+    let mut last = LAST_PLAYED.lock().unwrap_or_else(|e| e.into_inner());
+    match last.get(&kind) {
+        Some(prev) if now.duration_since(*prev) < MIN_REPEAT => false,
+        _ => {
+            last.insert(kind, now);
+            true
+        }
+    }
+}
+
+/// Play the chime for `kind` in the background if notification sounds are
+/// enabled. Never blocks and never interrupts TTS.
+pub fn play_notification(kind: NotificationKind) {
+    play_with_settings(kind, &sound_settings());
+}
+
+fn play_with_settings(kind: NotificationKind, settings: &SoundSettings) {
+    if !settings.enabled || !claim(kind, Instant::now()) {
+        return;
+    }
+    let volume = kind.volume(settings).clamp(0.0, 1.0);
+    if volume <= 0.0 {
+        return;
+    }
+
+    let device = settings.speaker_device_id.clone();
+    std::thread::spawn(move || {
+        // Separate stream + sink: the output mixes it with any active TTS
+        let played = crate::tts_backend::open_sink(&device, volume).and_then(|(_stream, sink)| {
+            crate::tts_backend::append_wav(&sink, &chime_wav(kind)?)?;
+            sink.sleep_until_end();
+            Ok(())
+        });
+        if let Err(e) = played {
+            backend_warn(format!("Notification sound {:?} failed: {}", kind, e));
+        }
+    });
+}
+
+/// Detection chime, only above `notification_detection_min_confidence`.
+pub fn notify_detection(confidence: f32) {
+    let settings = sound_settings();
+    if confidence >= settings.detection_min_confidence {
+        play_with_settings(NotificationKind::Detection, &settings);
+    }
+}
+
+/// Play a cue from the UI, e.g. when it shows a backend error to the user.
+#[tauri::command]
+pub fn notification_sound_play(kind: NotificationKind) {
+    backend_info(format!("Command notification_sound_play invoked: {:?}", kind));
+    play_notification(kind);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chime_wav_and_repeat_guard() {
+        let wav = chime_wav(NotificationKind::Detection).unwrap();
+        let mut reader = hound::WavReader::new(Cursor::new(wav)).unwrap();
+        assert_eq!((reader.spec().channels, reader.spec().sample_rate), (1, SAMPLE_RATE));
+        assert_eq!(reader.len(), SAMPLE_RATE * 250 / 1000);
+        // Faded in: first sample is silent
+        assert_eq!(reader.samples::<i16>().next().unwrap().unwrap(), 0);
+
+        let t0 = Instant::now();
+        assert!(claim(NotificationKind::Error, t0));
+        assert!(!claim(NotificationKind::Error, t0 + Duration::from_millis(200)));
+        assert!(claim(NotificationKind::WakeWord, t0 + Duration::from_millis(200)));
+        assert!(claim(NotificationKind::Error, t0 + MIN_REPEAT));
+    }
+}
@@ -1,17 +1,29 @@
 /**
  * SSH commands for Tauri backend.
- * Provides: ssh_execute, ssh_test_connection, ssh_list_known_hosts
+ * Provides: ssh_execute, ssh_execute_stream, ssh_cancel, ssh_sftp_upload,
+ * ssh_sftp_download, ssh_sftp_list, ssh_test_connection, ssh_list_known_hosts,
+ * ssh_known_hosts_add, ssh_known_hosts_remove
  * Supports text2ssh: natural language → SSH command translation and execution.
  */
 
 use serde::{Deserialize, Serialize};
-use std::process::Command;
-use std::time::Instant;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
 
 use crate::logging::{backend_info, backend_warn, backend_error};
 
 // ─── SSH Execute ─────────────────────────────────────────────
 
+/// `stdout` / `stderr` are lossy UTF-8 of at most `ssh_output_max_kb` each;
+/// `truncated` is set when either stream produced more — the command is then
+/// stopped (`exit_code` -1) and `*_bytes` count what arrived until then.
+/// With `base64_output` the kept bytes are also returned unaltered in
+/// `*_base64`.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SshResult {
     pub host: String,
@@ -20,19 +32,58 @@ pub struct SshResult {
     pub stderr: String,
     pub exit_code: i32,
     pub duration_ms: u64,
+    #[serde(default)]
+    pub truncated: bool,
+    #[serde(default)]
+    pub stdout_bytes: u64,
+    #[serde(default)]
+    pub stderr_bytes: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdout_base64: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr_base64: Option<String>,
 }
 
+/// Run `command` on `host`; each call is recorded in the audit log.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn ssh_execute(
     host: String,
     command: String,
     user: Option<String>,
     port: Option<u16>,
     timeout: Option<u64>,
+    initiator: Option<String>,
+    base64_output: Option<bool>,
+) -> Result<SshResult, String> {
+    let audit_params = serde_json::json!({
+        "host": host,
+        "user": user,
+        "port": port,
+        "command": command,
+    });
+    let result = run_ssh(host, command, user, port, timeout, base64_output.unwrap_or(false)).await;
+    let outcome = match &result {
+        Ok(r) if r.exit_code == 0 => Ok(()),
+        Ok(r) => Err(format!("exit code {}", r.exit_code)),
+        Err(e) => Err(e.clone()),
+    };
+    crate::audit::record("ssh_execute", initiator.as_deref(), audit_params, outcome);
+    result
+}
+
+async fn run_ssh(
+    host: String,
+    command: String,
+    user: Option<String>,
+    port: Option<u16>,
+    timeout: Option<u64>,
+    base64_output: bool,
 ) -> Result<SshResult, String> {
     let ssh_user = user.unwrap_or_else(|| "root".to_string());
     let ssh_port = port.unwrap_or(22);
     let timeout_secs = timeout.unwrap_or(10);
+    let max_bytes = crate::settings::load_settings().ssh_output_max_kb.max(1) as usize * 1024;
 
     backend_info(format!(
         "ssh_execute: {}@{}:{} cmd='{}' timeout={}s",
@@ -41,42 +92,373 @@ pub async fn ssh_execute(
 
     let t0 = Instant::now();
 
-    let output = Command::new("ssh")
-        .args([
-            "-o", "StrictHostKeyChecking=no",
-            "-o", "ConnectTimeout=5",
-            "-o", &format!("ServerAliveInterval={}", timeout_secs),
-            "-o", "BatchMode=yes",
-            "-p", &ssh_port.to_string(),
-            &format!("{}@{}", ssh_user, host),
-            &command,
-        ])
-        .output()
-        .map_err(|e| {
-            backend_error(format!("ssh_execute failed to spawn: {}", e));
-            format!("Nie można uruchomić SSH: {}", e)
-        })?;
+    let mut cmd = ssh_command(&ssh_user, &host, ssh_port, timeout_secs, false);
+    cmd.arg(&command);
+    let output = capture_capped(cmd, max_bytes).map_err(|e| {
+        backend_error(format!("ssh_execute failed to spawn: {}", e));
+        format!("Nie można uruchomić SSH: {}", e)
+    })?;
 
     let duration_ms = t0.elapsed().as_millis() as u64;
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-    let exit_code = output.status.code().unwrap_or(-1);
-
+    let truncated = output.stdout.truncated || output.stderr.truncated;
     backend_info(format!(
-        "ssh_execute: exit={}, stdout_len={}, stderr_len={}, duration={}ms",
-        exit_code, stdout.len(), stderr.len(), duration_ms
+        "ssh_execute: exit={}, stdout_len={}, stderr_len={}, truncated={}, duration={}ms",
+        output.exit_code, output.stdout.total, output.stderr.total, truncated, duration_ms
     ));
 
+    use base64::{engine::general_purpose, Engine as _};
+    let encode = |s: &CappedStream| base64_output.then(|| general_purpose::STANDARD.encode(&s.bytes));
     Ok(SshResult {
         host,
         command,
-        stdout,
-        stderr,
-        exit_code,
+        stdout: String::from_utf8_lossy(&output.stdout.bytes).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr.bytes).into_owned(),
+        exit_code: output.exit_code,
         duration_ms,
+        truncated,
+        stdout_bytes: output.stdout.total,
+        stderr_bytes: output.stderr.total,
+        stdout_base64: encode(&output.stdout),
+        stderr_base64: encode(&output.stderr),
+    })
+}
+
+/// First `max` bytes of a stream, and how many it produced in total (up to
+/// the process being stopped).
+struct CappedStream {
+    bytes: Vec<u8>,
+    total: u64,
+    truncated: bool,
+}
+
+struct CappedOutput {
+    stdout: CappedStream,
+    stderr: CappedStream,
+    exit_code: i32,
+}
+
+/// Keep the first `max` bytes of `source`. One byte more calls `on_cap`
+/// (which stops the child); what is still in the pipe is drained and counted.
+fn read_capped<R: Read>(mut source: R, max: usize, on_cap: impl FnOnce()) -> std::io::Result<CappedStream> {
+    let mut bytes = Vec::new();
+    (&mut source).take(max as u64 + 1).read_to_end(&mut bytes)?;
+    let truncated = bytes.len() > max;
+    let mut total = bytes.len() as u64;
+    if truncated {
+        bytes.truncate(max);
+        on_cap();
+        total += std::io::copy(&mut source, &mut std::io::sink())?;
+    }
+    Ok(CappedStream { bytes, total, truncated })
+}
+
+/// `Command::output()` with each stream capped at `max` bytes; the child is
+/// killed as soon as either stream goes over.
+fn capture_capped(mut cmd: Command, max: usize) -> std::io::Result<CappedOutput> {
+    let mut child = cmd.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let (out, err) = (child.stdout.take(), child.stderr.take());
+    let child = Arc::new(Mutex::new(child));
+    let kill = |child: &Arc<Mutex<Child>>| {
+        let child = Arc::clone(child);
+        move || {
+            let _ = child.lock().unwrap_or_else(|e| e.into_inner()).kill();
+        }
+    };
+    let stderr = err.map(|e| {
+        let on_cap = kill(&child);
+        std::thread::spawn(move || read_capped(e, max, on_cap))
+    });
+    let stdout = match out {
+        Some(out) => read_capped(out, max, kill(&child)),
+        None => Ok(CappedStream { bytes: Vec::new(), total: 0, truncated: false }),
+    };
+    let stderr = match stderr {
+        Some(handle) => handle.join().unwrap_or_else(|_| Err(std::io::Error::other("stderr reader panicked"))),
+        None => Ok(CappedStream { bytes: Vec::new(), total: 0, truncated: false }),
+    };
+    let status = child.lock().unwrap_or_else(|e| e.into_inner()).wait()?;
+    Ok(CappedOutput { stdout: stdout?, stderr: stderr?, exit_code: status.code().unwrap_or(-1) })
+}
+
+/// `ssh` invocation shared by the one-shot and streaming commands; the remote
+/// command is appended by the caller. `pty` forces a remote tty so the remote
+/// process gets SIGHUP when the channel is closed.
+/// Host keys are trusted on first use (`accept-new`) and recorded in
+/// Broxeen's own known_hosts file, so a changed key fails the connection
+/// instead of being silently accepted.
+fn ssh_command(user: &str, host: &str, port: u16, alive_interval_secs: u64, pty: bool) -> Command {
+    let mut cmd = Command::new("ssh");
+    cmd.args(host_key_args());
+    cmd.args([
+        "-o", "ConnectTimeout=5",
+        "-o", &format!("ServerAliveInterval={}", alive_interval_secs.max(1)),
+        "-o", "BatchMode=yes",
+        "-p", &port.to_string(),
+    ]);
+    if pty {
+        cmd.arg("-tt");
+    }
+    cmd.arg(format!("{}@{}", user, host));
+    cmd
+}
+
+// ─── SSH Streaming Execute ───────────────────────────────────
+
+/// Default limit for streamed commands; `timeout: Some(0)` disables it.
+const STREAM_DEFAULT_TIMEOUT_SECS: u64 = 600;
+const STREAM_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+struct StreamSession {
+    child: Mutex<Child>,
+    cancelled: AtomicBool,
+}
+
+lazy_static::lazy_static! {
+    static ref SSH_SESSIONS: Mutex<HashMap<String, Arc<StreamSession>>> = Mutex::new(HashMap::new());
+}
+
+static SESSION_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Payload of `broxeen:ssh_output`. `kind` is "stdout", "stderr" or — for the
+/// final event — "exit", which carries `exit_code` and `reason`
+/// ("completed", "timeout", "cancelled").
+#[derive(Debug, Clone, Serialize)]
+pub struct SshOutputEvent {
+    pub session_id: String,
+    pub host: String,
+    pub kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
+/// Without a pty the remote command survives a closed channel, so it runs
+/// in a wrapper that records its process group: `stream_stop_remote` kills
+/// that group on cancel/timeout, and a normal exit removes the pid file.
+fn stream_remote_command(command: &str, pid_file: &str) -> String {
+    format!(
+        "echo $$ > {pid}; sh -c {cmd}; rc=$?; rm -f {pid}; exit $rc",
+        pid = pid_file,
+        cmd = shell_quote(command),
+    )
+}
+
+fn stream_pid_file(session_id: &str) -> String {
+    format!("/tmp/.broxeen-{}.pid", session_id)
+}
+
+/// Kill the remote process group of a cancelled / timed-out session (best effort).
+fn stream_stop_remote(user: &str, host: &str, port: u16, pid_file: &str) {
+    let kill = format!(
+        "test -f {pid} && kill -TERM -- -$(cat {pid}) 2>/dev/null; rm -f {pid}",
+        pid = pid_file
+    );
+    match ssh_command(user, host, port, 5, false).arg(kill).stdin(Stdio::null()).output() {
+        Ok(out) if out.status.success() => {}
+        Ok(out) => backend_warn(format!(
+            "ssh: could not stop remote command on {}: {}",
+            host,
+            String::from_utf8_lossy(&out.stderr).trim()
+        )),
+        Err(e) => backend_warn(format!("ssh: could not stop remote command on {}: {}", host, e)),
+    }
+}
+
+fn spawn_line_reader<R: Read + Send + 'static>(
+    app: AppHandle,
+    session_id: String,
+    host: String,
+    kind: &'static str,
+    source: R,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(source);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    let line = String::from_utf8_lossy(&buf).trim_end_matches(['\r', '\n']).to_string();
+                    let _ = app.emit("broxeen:ssh_output", SshOutputEvent {
+                        session_id: session_id.clone(),
+                        host: host.clone(),
+                        kind,
+                        line: Some(line),
+                        exit_code: None,
+                        reason: None,
+                        duration_ms: None,
+                    });
+                }
+            }
+        }
     })
 }
 
+/// Start `command` on `host` and stream its output as `broxeen:ssh_output`
+/// events. Returns the session id immediately; use `ssh_cancel` to stop it.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn ssh_execute_stream(
+    app: AppHandle,
+    host: String,
+    command: String,
+    user: Option<String>,
+    port: Option<u16>,
+    timeout: Option<u64>,
+    pty: Option<bool>,
+    initiator: Option<String>,
+) -> Result<String, String> {
+    let ssh_user = user.unwrap_or_else(|| "root".to_string());
+    let ssh_port = port.unwrap_or(22);
+    let timeout_secs = timeout.unwrap_or(STREAM_DEFAULT_TIMEOUT_SECS);
+    let session_id = format!(
+        "ssh-{}-{}",
+        SESSION_COUNTER.fetch_add(1, Ordering::Relaxed),
+        chrono::Utc::now().timestamp_millis()
+    );
+
+    backend_info(format!(
+        "ssh_execute_stream [{}]: {}@{}:{} cmd='{}' timeout={}s",
+        session_id, ssh_user, host, ssh_port, command, timeout_secs
+    ));
+
+    let audit_params = serde_json::json!({
+        "session_id": session_id,
+        "host": host,
+        "user": ssh_user,
+        "port": ssh_port,
+        "command": command,
+    });
+
+    let pty = pty.unwrap_or(false);
+    let pid_file = stream_pid_file(&session_id);
+    let remote_command = if pty { command.clone() } else { stream_remote_command(&command, &pid_file) };
+    let mut child = match ssh_command(&ssh_user, &host, ssh_port, 15, pty)
+        .arg(&remote_command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            backend_error(format!("ssh_execute_stream failed to spawn: {}", e));
+            let err = format!("Nie można uruchomić SSH: {}", e);
+            crate::audit::record("ssh_execute_stream", initiator.as_deref(), audit_params, Err(err.clone()));
+            return Err(err);
+        }
+    };
+
+    let readers = [
+        child.stdout.take().map(|out| spawn_line_reader(app.clone(), session_id.clone(), host.clone(), "stdout", out)),
+        child.stderr.take().map(|err| spawn_line_reader(app.clone(), session_id.clone(), host.clone(), "stderr", err)),
+    ];
+
+    let session = Arc::new(StreamSession {
+        child: Mutex::new(child),
+        cancelled: AtomicBool::new(false),
+    });
+    SSH_SESSIONS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(session_id.clone(), Arc::clone(&session));
+
+    let id = session_id.clone();
+    std::thread::spawn(move || {
+        let t0 = Instant::now();
+        let deadline = (timeout_secs > 0).then(|| Duration::from_secs(timeout_secs));
+        let mut timed_out = false;
+
+        let status = loop {
+            let mut child = session.child.lock().unwrap_or_else(|e| e.into_inner());
+            match child.try_wait() {
+                Ok(Some(status)) => break Some(status),
+                Ok(None) => {}
+                Err(e) => {
+                    backend_warn(format!("ssh_execute_stream [{}]: wait failed: {}", id, e));
+                    break None;
+                }
+            }
+            if !timed_out && deadline.is_some_and(|d| t0.elapsed() >= d) {
+                backend_warn(format!("ssh_execute_stream [{}]: timeout after {}s", id, timeout_secs));
+                timed_out = true;
+                let _ = child.kill();
+            }
+            drop(child);
+            std::thread::sleep(STREAM_POLL_INTERVAL);
+        };
+
+        // Flush remaining output before the final event
+        for reader in readers.into_iter().flatten() {
+            let _ = reader.join();
+        }
+        SSH_SESSIONS.lock().unwrap_or_else(|e| e.into_inner()).remove(&id);
+        // A pty hangs up the remote side by itself
+        if !pty && (timed_out || session.cancelled.load(Ordering::Relaxed)) {
+            stream_stop_remote(&ssh_user, &host, ssh_port, &pid_file);
+        }
+
+        let exit_code = status.and_then(|s| s.code()).unwrap_or(-1);
+        let reason = if session.cancelled.load(Ordering::Relaxed) {
+            "cancelled"
+        } else if timed_out {
+            "timeout"
+        } else {
+            "completed"
+        };
+        let duration_ms = t0.elapsed().as_millis() as u64;
+        backend_info(format!(
+            "ssh_execute_stream [{}]: {} exit={} duration={}ms",
+            id, reason, exit_code, duration_ms
+        ));
+
+        let outcome = if reason == "completed" && exit_code == 0 {
+            Ok(())
+        } else {
+            Err(format!("{} (exit code {})", reason, exit_code))
+        };
+        crate::audit::record("ssh_execute_stream", initiator.as_deref(), audit_params, outcome);
+
+        let _ = app.emit("broxeen:ssh_output", SshOutputEvent {
+            session_id: id,
+            host,
+            kind: "exit",
+            line: None,
+            exit_code: Some(exit_code),
+            reason: Some(reason),
+            duration_ms: Some(duration_ms),
+        });
+    });
+
+    Ok(session_id)
+}
+
+/// Close a streaming session's channel (kills the local ssh client; without
+/// a pty the remote process group is then killed over a second connection).
+/// Returns false when the session is unknown or already finished.
+#[tauri::command]
+pub async fn ssh_cancel(session_id: String) -> Result<bool, String> {
+    let session = SSH_SESSIONS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&session_id)
+        .cloned();
+    let Some(session) = session else {
+        return Ok(false);
+    };
+    backend_info(format!("ssh_cancel [{}]", session_id));
+    session.cancelled.store(true, Ordering::Relaxed);
+    let mut child = session.child.lock().unwrap_or_else(|e| e.into_inner());
+    child.kill().map_err(|e| format!("Nie można przerwać sesji SSH: {}", e))?;
+    Ok(true)
+}
+
 // ─── SSH Test Connection ─────────────────────────────────────
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -88,6 +470,26 @@ pub struct SshTestResult {
     pub ssh_version: Option<String>,
     pub duration_ms: u64,
     pub error: Option<String>,
+    /// Machine-readable form of `error`
+    #[serde(default)]
+    pub error_kind: Option<SshTestError>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum SshTestError {
+    Unreachable,
+    AuthFailed { message: String },
+    /// The server's key differs from the stored one — offer `ssh_known_hosts_add`
+    HostKeyMismatch {
+        expected_fingerprint: Option<String>,
+        actual_fingerprint: Option<String>,
+    },
+    Other { message: String },
+}
+
+fn is_host_key_error(stderr: &str) -> bool {
+    stderr.contains("REMOTE HOST IDENTIFICATION HAS CHANGED") || stderr.contains("Host key verification failed")
 }
 
 #[tauri::command]
@@ -123,19 +525,13 @@ pub async fn ssh_test_connection(
             ssh_version: None,
             duration_ms,
             error: Some("Port SSH niedostępny".to_string()),
+            error_kind: Some(SshTestError::Unreachable),
         });
     }
 
     // Try SSH command 'echo ok'
-    let output = Command::new("ssh")
-        .args([
-            "-o", "StrictHostKeyChecking=no",
-            "-o", "ConnectTimeout=5",
-            "-o", "BatchMode=yes",
-            "-p", &ssh_port.to_string(),
-            &format!("{}@{}", ssh_user, host),
-            "echo", "broxeen-ssh-ok",
-        ])
+    let output = ssh_command(&ssh_user, &host, ssh_port, 5, false)
+        .args(["echo", "broxeen-ssh-ok"])
         .output();
 
     let duration_ms = t0.elapsed().as_millis() as u64;
@@ -149,6 +545,24 @@ pub async fn ssh_test_connection(
             // Try to get SSH version from banner
             let ssh_version = get_ssh_banner(&host, ssh_port);
 
+            let (error, error_kind) = if auth_ok {
+                (None, None)
+            } else if is_host_key_error(&stderr) {
+                let expected = known_host_fingerprint(&known_hosts_path(), &host, ssh_port);
+                let actual = scan_host_key(&host, ssh_port).ok().map(|k| k.fingerprint);
+                backend_warn(format!(
+                    "ssh_test_connection: host key mismatch for {}:{} (stored {:?}, offered {:?})",
+                    host, ssh_port, expected, actual
+                ));
+                (
+                    Some("Klucz hosta się zmienił — potwierdź nowy odcisk, aby połączyć".to_string()),
+                    Some(SshTestError::HostKeyMismatch { expected_fingerprint: expected, actual_fingerprint: actual }),
+                )
+            } else {
+                let message = stderr.lines().next().unwrap_or("Auth failed").to_string();
+                (Some(message.clone()), Some(SshTestError::AuthFailed { message }))
+            };
+
             Ok(SshTestResult {
                 host,
                 port: ssh_port,
@@ -156,7 +570,8 @@ pub async fn ssh_test_connection(
                 auth_ok,
                 ssh_version,
                 duration_ms,
-                error: if auth_ok { None } else { Some(stderr.lines().next().unwrap_or("Auth failed").to_string()) },
+                error,
+                error_kind,
             })
         }
         Err(e) => {
@@ -169,25 +584,555 @@ pub async fn ssh_test_connection(
                 ssh_version: None,
                 duration_ms,
                 error: Some(format!("SSH binary error: {}", e)),
+                error_kind: Some(SshTestError::Other { message: e.to_string() }),
             })
         }
     }
 }
 
+// ─── SFTP-style Transfers ────────────────────────────────────
+//
+// Transfers run over a plain ssh exec channel (`cat` on the remote side), so
+// they work wherever `ssh_execute` does — no sftp-server or extra crate
+// needed — and are streamed in chunks rather than buffered in memory.
+
+const TRANSFER_CHUNK: usize = 64 * 1024;
+const PROGRESS_EVERY_BYTES: u64 = 1024 * 1024;
+
+static TRANSFER_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Payload of `broxeen:sftp_progress`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SftpProgress {
+    pub transfer_id: String,
+    pub host: String,
+    /// "upload" or "download"
+    pub direction: &'static str,
+    pub path: String,
+    pub bytes: u64,
+    pub total: Option<u64>,
+    pub done: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SftpEntry {
+    pub name: String,
+    pub size: u64,
+    /// Unix seconds
+    pub mtime: i64,
+    /// `ls -l` style, e.g. "-rw-r--r--"
+    pub permissions: String,
+    pub is_dir: bool,
+}
+
+/// Map ssh / remote shell stderr to a user-facing error.
+fn classify_remote_error(stderr: &str, path: &str) -> String {
+    let first = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("").trim();
+    if stderr.contains("Permission denied (") || stderr.contains("Authentication failed") {
+        format!("Błąd uwierzytelnienia SSH: {}", first)
+    } else if stderr.contains("Is a directory") {
+        format!("Ścieżka zdalna jest katalogiem: {}", path)
+    } else if stderr.contains("No such file or directory") {
+        format!("Ścieżka zdalna nie istnieje: {}", path)
+    } else if stderr.contains("Permission denied") {
+        format!("Brak uprawnień do ścieżki zdalnej: {}", path)
+    } else if first.is_empty() {
+        format!("Operacja SSH na {} nie powiodła się", path)
+    } else {
+        format!("Operacja SSH na {} nie powiodła się: {}", path, first)
+    }
+}
+
+/// Quote for the remote POSIX shell.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Shell prefix failing a file transfer on a remote directory with a clear
+/// "Is a directory" instead of whatever `cat` / `wc` make of it.
+fn reject_directory(quoted_path: &str) -> String {
+    format!("if [ -d {} ]; then echo 'Is a directory' >&2; exit 21; fi; ", quoted_path)
+}
+
+fn next_transfer_id() -> String {
+    format!(
+        "sftp-{}-{}",
+        TRANSFER_COUNTER.fetch_add(1, Ordering::Relaxed),
+        chrono::Utc::now().timestamp_millis()
+    )
+}
+
+/// Copy `reader` → `writer` in chunks, emitting progress every ~1 MB.
+fn copy_with_progress(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    mut progress: impl FnMut(u64),
+) -> std::io::Result<u64> {
+    let mut buf = vec![0u8; TRANSFER_CHUNK];
+    let mut copied = 0u64;
+    let mut last_report = 0u64;
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        writer.write_all(&buf[..n])?;
+        copied += n as u64;
+        if copied - last_report >= PROGRESS_EVERY_BYTES {
+            last_report = copied;
+            progress(copied);
+        }
+    }
+    writer.flush()?;
+    Ok(copied)
+}
+
+fn read_stderr(child: &mut Child) -> String {
+    let mut stderr = String::new();
+    if let Some(mut err) = child.stderr.take() {
+        let _ = err.read_to_string(&mut stderr);
+    }
+    stderr
+}
+
+fn upload_blocking(
+    app: &AppHandle,
+    transfer_id: &str,
+    host: &str,
+    user: &str,
+    port: u16,
+    local_path: &str,
+    remote_path: &str,
+) -> Result<u64, String> {
+    let mut file = std::fs::File::open(local_path)
+        .map_err(|e| format!("Nie można otworzyć pliku lokalnego {}: {}", local_path, e))?;
+    let total = file.metadata().ok().map(|m| m.len());
+
+    let quoted = shell_quote(remote_path);
+    let mut child = ssh_command(user, host, port, 15, false)
+        .arg(format!("{}cat > {}", reject_directory(&quoted), quoted))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Nie można uruchomić SSH: {}", e))?;
+
+    let emit = |bytes: u64, done: bool| {
+        let _ = app.emit("broxeen:sftp_progress", SftpProgress {
+            transfer_id: transfer_id.to_string(),
+            host: host.to_string(),
+            direction: "upload",
+            path: remote_path.to_string(),
+            bytes,
+            total,
+            done,
+        });
+    };
+
+    let copied = {
+        let mut stdin = child.stdin.take().ok_or("SSH stdin unavailable")?;
+        copy_with_progress(&mut file, &mut stdin, |b| emit(b, false))
+        // stdin dropped here → remote `cat` sees EOF
+    };
+    let stderr = read_stderr(&mut child);
+    let status = child.wait().map_err(|e| e.to_string())?;
+
+    match copied {
+        Ok(bytes) if status.success() => {
+            emit(bytes, true);
+            Ok(bytes)
+        }
+        // A broken pipe means the remote side failed — its stderr says why
+        _ => Err(classify_remote_error(&stderr, remote_path)),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn download_blocking(
+    app: &AppHandle,
+    transfer_id: &str,
+    host: &str,
+    user: &str,
+    port: u16,
+    remote_path: &str,
+    local_path: &str,
+    overwrite: bool,
+) -> Result<u64, String> {
+    let local = std::path::Path::new(local_path);
+    if local.exists() && !overwrite {
+        return Err(format!(
+            "Plik lokalny {} już istnieje — użyj overwrite, aby go nadpisać",
+            local_path
+        ));
+    }
+
+    // Size first, so progress has a total and a missing path fails before creating the local file
+    let quoted = shell_quote(remote_path);
+    let stat = ssh_command(user, host, port, 15, false)
+        .arg(format!("{}test -r {q} && wc -c < {q} || ls -- {q}", reject_directory(&quoted), q = quoted))
+        .output()
+        .map_err(|e| format!("Nie można uruchomić SSH: {}", e))?;
+    if !stat.status.success() {
+        return Err(classify_remote_error(&String::from_utf8_lossy(&stat.stderr), remote_path));
+    }
+    let total = String::from_utf8_lossy(&stat.stdout).trim().parse::<u64>().ok();
+    if total.is_none() {
+        return Err(format!("Brak uprawnień do ścieżki zdalnej: {}", remote_path));
+    }
+
+    let mut child = ssh_command(user, host, port, 15, false)
+        .arg(format!("cat -- {}", quoted))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Nie można uruchomić SSH: {}", e))?;
+
+    // Write to a sibling temp file so a failed transfer never clobbers the target
+    let part = local.with_extension(format!(
+        "{}part",
+        local.extension().map(|e| format!("{}.", e.to_string_lossy())).unwrap_or_default()
+    ));
+    let mut out = std::fs::File::create(&part)
+        .map_err(|e| format!("Nie można utworzyć pliku {}: {}", part.display(), e))?;
+
+    let emit = |bytes: u64, done: bool| {
+        let _ = app.emit("broxeen:sftp_progress", SftpProgress {
+            transfer_id: transfer_id.to_string(),
+            host: host.to_string(),
+            direction: "download",
+            path: remote_path.to_string(),
+            bytes,
+            total,
+            done,
+        });
+    };
+
+    let mut stdout = child.stdout.take().ok_or("SSH stdout unavailable")?;
+    let copied = copy_with_progress(&mut stdout, &mut out, |b| emit(b, false));
+    drop(out);
+    let stderr = read_stderr(&mut child);
+    let status = child.wait().map_err(|e| e.to_string())?;
+
+    match copied {
+        Ok(bytes) if status.success() => {
+            std::fs::rename(&part, local)
+                .map_err(|e| format!("Nie można zapisać {}: {}", local_path, e))?;
+            emit(bytes, true);
+            Ok(bytes)
+        }
+        Ok(_) => {
+            let _ = std::fs::remove_file(&part);
+            Err(classify_remote_error(&stderr, remote_path))
+        }
+        Err(e) => {
+            let _ = std::fs::remove_file(&part);
+            Err(format!("Błąd zapisu {}: {}", local_path, e))
+        }
+    }
+}
+
+/// Parse one line of `find -printf '%f\t%s\t%T@\t%M\n'`.
+fn parse_listing_line(line: &str) -> Option<SftpEntry> {
+    let mut parts = line.splitn(4, '\t');
+    let name = parts.next()?.to_string();
+    let size = parts.next()?.parse().ok()?;
+    let mtime = parts.next()?.split('.').next()?.parse().ok()?;
+    let permissions = parts.next()?.trim_end().to_string();
+    Some(SftpEntry {
+        is_dir: permissions.starts_with('d'),
+        name,
+        size,
+        mtime,
+        permissions,
+    })
+}
+
+/// Upload `local_path` to `remote_path` on `host`. Returns bytes sent.
+/// Each call is recorded in the audit log.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn ssh_sftp_upload(
+    app: AppHandle,
+    host: String,
+    local_path: String,
+    remote_path: String,
+    user: Option<String>,
+    port: Option<u16>,
+    initiator: Option<String>,
+) -> Result<u64, String> {
+    let ssh_user = user.unwrap_or_else(|| "root".to_string());
+    let ssh_port = port.unwrap_or(22);
+    let transfer_id = next_transfer_id();
+    backend_info(format!(
+        "ssh_sftp_upload [{}]: {} → {}@{}:{}",
+        transfer_id, local_path, ssh_user, host, remote_path
+    ));
+    let audit_params = transfer_audit_params(&host, &ssh_user, ssh_port, &local_path, &remote_path);
+
+    let result = tokio::task::spawn_blocking(move || {
+        upload_blocking(&app, &transfer_id, &host, &ssh_user, ssh_port, &local_path, &remote_path)
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r)
+    .map_err(|e| {
+        backend_warn(format!("ssh_sftp_upload failed: {}", e));
+        e
+    });
+    crate::audit::audited("ssh_sftp_upload", initiator.as_deref(), audit_params, result)
+}
+
+fn transfer_audit_params(host: &str, user: &str, port: u16, local_path: &str, remote_path: &str) -> serde_json::Value {
+    serde_json::json!({
+        "host": host,
+        "user": user,
+        "port": port,
+        "local_path": local_path,
+        "remote_path": remote_path,
+    })
+}
+
+/// Download `remote_path` from `host` into `local_path`. Existing local files
+/// are only replaced with `overwrite: true`. Returns bytes received.
+/// Each call is recorded in the audit log.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn ssh_sftp_download(
+    app: AppHandle,
+    host: String,
+    remote_path: String,
+    local_path: String,
+    overwrite: Option<bool>,
+    user: Option<String>,
+    port: Option<u16>,
+    initiator: Option<String>,
+) -> Result<u64, String> {
+    let ssh_user = user.unwrap_or_else(|| "root".to_string());
+    let ssh_port = port.unwrap_or(22);
+    let transfer_id = next_transfer_id();
+    backend_info(format!(
+        "ssh_sftp_download [{}]: {}@{}:{} → {}",
+        transfer_id, ssh_user, host, remote_path, local_path
+    ));
+    let mut audit_params = transfer_audit_params(&host, &ssh_user, ssh_port, &local_path, &remote_path);
+    audit_params["overwrite"] = overwrite.unwrap_or(false).into();
+
+    let result = tokio::task::spawn_blocking(move || {
+        download_blocking(
+            &app,
+            &transfer_id,
+            &host,
+            &ssh_user,
+            ssh_port,
+            &remote_path,
+            &local_path,
+            overwrite.unwrap_or(false),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r)
+    .map_err(|e| {
+        backend_warn(format!("ssh_sftp_download failed: {}", e));
+        e
+    });
+    crate::audit::audited("ssh_sftp_download", initiator.as_deref(), audit_params, result)
+}
+
+/// List `remote_dir` on `host` (requires GNU find on the remote side).
+#[tauri::command]
+pub async fn ssh_sftp_list(
+    host: String,
+    remote_dir: String,
+    user: Option<String>,
+    port: Option<u16>,
+) -> Result<Vec<SftpEntry>, String> {
+    let ssh_user = user.unwrap_or_else(|| "root".to_string());
+    let ssh_port = port.unwrap_or(22);
+    backend_info(format!("ssh_sftp_list: {}@{}:{}", ssh_user, host, remote_dir));
+
+    let output = ssh_command(&ssh_user, &host, ssh_port, 15, false)
+        .arg(format!(
+            "find {} -mindepth 1 -maxdepth 1 -printf '%f\\t%s\\t%T@\\t%M\\n'",
+            shell_quote(&remote_dir)
+        ))
+        .output()
+        .map_err(|e| format!("Nie można uruchomić SSH: {}", e))?;
+
+    if !output.status.success() {
+        return Err(classify_remote_error(&String::from_utf8_lossy(&output.stderr), &remote_dir));
+    }
+
+    let mut entries: Vec<SftpEntry> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(parse_listing_line)
+        .collect();
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+    backend_info(format!("ssh_sftp_list: {} entries", entries.len()));
+    Ok(entries)
+}
+
 // ─── Known Hosts ─────────────────────────────────────────────
+//
+// Broxeen keeps its own known_hosts (`<config>/broxeen/known_hosts`) and only
+// touches ~/.ssh/known_hosts when `ssh_use_system_known_hosts` is set.
+// Entries are written unhashed so they can be listed and removed here.
+
+/// Host key options for every `ssh` / `scp` Broxeen runs (here, in
+/// remote_machine and remote_metrics): trust on first use, checked against
+/// and recorded unhashed in Broxeen's known_hosts.
+pub(crate) fn host_key_args() -> [String; 6] {
+    [
+        "-o".into(),
+        "StrictHostKeyChecking=accept-new".into(),
+        "-o".into(),
+        format!("UserKnownHostsFile={}", known_hosts_path().display()),
+        "-o".into(),
+        "HashKnownHosts=no".into(),
+    ]
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct KnownHost {
     pub host: String,
     pub key_type: String,
+    /// `SHA256:…` as printed by ssh-keygen -l
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+}
+
+/// A scanned host key awaiting (or after) confirmation.
+#[derive(Debug, Clone, Serialize)]
+pub struct HostKeyScan {
+    pub host: String,
+    pub port: u16,
+    pub key_type: String,
+    pub fingerprint: String,
+    pub persisted: bool,
+    /// Fingerprint of the entry this one replaced, if any
+    pub replaced_fingerprint: Option<String>,
+    #[serde(skip)]
+    line: String,
+}
+
+fn known_hosts_path() -> std::path::PathBuf {
+    if crate::settings::load_settings().ssh_use_system_known_hosts {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(".ssh").join("known_hosts");
+        }
+    }
+    dirs::config_dir()
+        .unwrap_or_else(|| std::path::PathBuf::from("."))
+        .join("broxeen")
+        .join("known_hosts")
+}
+
+/// Pattern ssh uses in known_hosts for `host:port`.
+fn host_pattern(host: &str, port: u16) -> String {
+    if port == 22 {
+        host.to_string()
+    } else {
+        format!("[{}]:{}", host, port)
+    }
+}
+
+/// OpenSSH-style `SHA256:<base64 without padding>` of a base64 key blob.
+fn key_fingerprint(key_b64: &str) -> Option<String> {
+    use base64::Engine;
+    let blob = base64::engine::general_purpose::STANDARD.decode(key_b64).ok()?;
+    let digest = hmac_sha256::Hash::hash(&blob);
+    Some(format!("SHA256:{}", base64::engine::general_purpose::STANDARD_NO_PAD.encode(digest)))
+}
+
+/// Lines of a known_hosts file whose host field matches `pattern`.
+fn line_matches(line: &str, pattern: &str) -> bool {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return false;
+    }
+    let mut fields = line.split_whitespace();
+    let first = fields.next().unwrap_or("");
+    // Skip @cert-authority / @revoked markers
+    let hosts = if first.starts_with('@') { fields.next().unwrap_or("") } else { first };
+    hosts.split(',').any(|h| h == pattern)
+}
+
+fn known_host_fingerprint(path: &std::path::Path, host: &str, port: u16) -> Option<String> {
+    let pattern = host_pattern(host, port);
+    let content = std::fs::read_to_string(path).ok()?;
+    content
+        .lines()
+        .find(|l| line_matches(l, &pattern))
+        .and_then(|l| l.split_whitespace().nth(2))
+        .and_then(key_fingerprint)
+}
+
+/// Remove every entry for `pattern`; returns how many lines were dropped.
+fn remove_known_host(path: &std::path::Path, pattern: &str) -> Result<usize, String> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(format!("Cannot read known_hosts: {}", e)),
+    };
+    let kept: Vec<&str> = content.lines().filter(|l| !line_matches(l, pattern)).collect();
+    let removed = content.lines().count() - kept.len();
+    if removed > 0 {
+        let mut out = kept.join("\n");
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        std::fs::write(path, out).map_err(|e| format!("Cannot write known_hosts: {}", e))?;
+    }
+    Ok(removed)
+}
+
+/// Ask the server for its key with ssh-keyscan (ed25519 preferred).
+fn scan_host_key(host: &str, port: u16) -> Result<HostKeyScan, String> {
+    let output = Command::new("ssh-keyscan")
+        .args(["-T", "5", "-p", &port.to_string(), "-t", "ed25519,ecdsa,rsa", host])
+        .output()
+        .map_err(|e| format!("Nie można uruchomić ssh-keyscan: {}", e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let mut keys: Vec<(usize, &str, &str)> = stdout
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .filter_map(|l| {
+            let parts: Vec<&str> = l.split_whitespace().collect();
+            if parts.len() < 3 {
+                return None;
+            }
+            let rank = ["ssh-ed25519", "ecdsa-sha2-nistp256", "ssh-rsa"]
+                .iter()
+                .position(|t| *t == parts[1])
+                .unwrap_or(usize::MAX);
+            Some((rank, parts[1], parts[2]))
+        })
+        .collect();
+    keys.sort_by_key(|k| k.0);
+    let (_, key_type, key) = keys
+        .first()
+        .ok_or_else(|| format!("Serwer {}:{} nie zwrócił klucza hosta", host, port))?;
+
+    Ok(HostKeyScan {
+        host: host.to_string(),
+        port,
+        key_type: key_type.to_string(),
+        fingerprint: key_fingerprint(key).ok_or("Nieprawidłowy klucz hosta")?,
+        persisted: false,
+        replaced_fingerprint: None,
+        line: format!("{} {} {}", host_pattern(host, port), key_type, key),
+    })
 }
 
 #[tauri::command]
 pub async fn ssh_list_known_hosts() -> Result<Vec<KnownHost>, String> {
     backend_info("ssh_list_known_hosts invoked");
 
-    let home = dirs::home_dir().ok_or("Cannot find home directory")?;
-    let known_hosts_path = home.join(".ssh").join("known_hosts");
+    let known_hosts_path = known_hosts_path();
 
     if !known_hosts_path.exists() {
         return Ok(vec![]);
@@ -215,6 +1160,7 @@ pub async fn ssh_list_known_hosts() -> Result<Vec<KnownHost>, String> {
                 Some(KnownHost {
                     host,
                     key_type: parts[1].to_string(),
+                    fingerprint: parts.get(2).and_then(|k| key_fingerprint(k)),
                 })
             } else {
                 None
@@ -226,6 +1172,74 @@ pub async fn ssh_list_known_hosts() -> Result<Vec<KnownHost>, String> {
     Ok(hosts)
 }
 
+/// Scan the host key. Without `confirm_fingerprint` nothing is written — show
+/// the returned fingerprint to the user and call again with it to trust the
+/// key (replacing any previous entry for that host:port).
+#[tauri::command]
+pub async fn ssh_known_hosts_add(
+    host: String,
+    port: Option<u16>,
+    confirm_fingerprint: Option<String>,
+) -> Result<HostKeyScan, String> {
+    let port = port.unwrap_or(22);
+    backend_info(format!(
+        "ssh_known_hosts_add: {}:{} confirm={}",
+        host, port, confirm_fingerprint.is_some()
+    ));
+
+    let mut scan = tokio::task::spawn_blocking({
+        let host = host.clone();
+        move || scan_host_key(&host, port)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let path = known_hosts_path();
+    scan.replaced_fingerprint = known_host_fingerprint(&path, &host, port)
+        .filter(|fp| *fp != scan.fingerprint);
+
+    let Some(confirmed) = confirm_fingerprint else {
+        return Ok(scan);
+    };
+    if confirmed != scan.fingerprint {
+        return Err(format!(
+            "Odcisk klucza się zmienił w trakcie potwierdzania (oczekiwano {}, serwer podał {})",
+            confirmed, scan.fingerprint
+        ));
+    }
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+    }
+    remove_known_host(&path, &host_pattern(&host, port))?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Cannot open known_hosts: {}", e))?;
+    writeln!(file, "{}", scan.line).map_err(|e| format!("Cannot write known_hosts: {}", e))?;
+
+    backend_info(format!(
+        "ssh_known_hosts_add: trusted {} {} for {}:{} in {}",
+        scan.key_type, scan.fingerprint, host, port, path.display()
+    ));
+    scan.persisted = true;
+    Ok(scan)
+}
+
+/// Forget the stored key(s) for `host` (and `port`, default 22).
+#[tauri::command]
+pub async fn ssh_known_hosts_remove(host: String, port: Option<u16>) -> Result<usize, String> {
+    let port = port.unwrap_or(22);
+    let path = known_hosts_path();
+    let removed = remove_known_host(&path, &host_pattern(&host, port))?;
+    backend_info(format!(
+        "ssh_known_hosts_remove: {}:{} removed {} entries from {}",
+        host, port, removed, path.display()
+    ));
+    Ok(removed)
+}
+
 fn get_ssh_banner(host: &str, port: u16) -> Option<String> {
     use std::io::Read;
     use std::net::TcpStream;
@@ -252,6 +1266,116 @@ fn get_ssh_banner(host: &str, port: u16) -> Option<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_ssh_command_args() {
+        let cmd = ssh_command("pi", "10.0.0.7", 2222, 0, true);
+        let args: Vec<String> = cmd.get_args().map(|a| a.to_string_lossy().into_owned()).collect();
+        assert!(args.windows(2).any(|w| w == ["-p", "2222"]));
+        assert!(args.contains(&"ServerAliveInterval=1".to_string()));
+        assert!(args.contains(&"-tt".to_string()));
+        assert_eq!(args.last().unwrap(), "pi@10.0.0.7");
+    }
+
+    #[test]
+    fn test_stream_wrapper_records_and_cleans_pid() {
+        let wrapped = stream_remote_command("echo 'hi'", "/tmp/.broxeen-ssh-1.pid");
+        assert_eq!(
+            wrapped,
+            r#"echo $$ > /tmp/.broxeen-ssh-1.pid; sh -c 'echo '\''hi'\'''; rc=$?; rm -f /tmp/.broxeen-ssh-1.pid; exit $rc"#
+        );
+    }
+
+    #[test]
+    fn test_classify_remote_error() {
+        assert!(classify_remote_error("root@h: Permission denied (publickey,password).", "/x")
+            .starts_with("Błąd uwierzytelnienia"));
+        assert!(classify_remote_error("cat: /x: No such file or directory", "/x").starts_with("Ścieżka zdalna nie istnieje"));
+        assert_eq!(classify_remote_error("Is a directory\n", "/etc"), "Ścieżka zdalna jest katalogiem: /etc");
+        assert!(classify_remote_error("sh: 1: cannot create /x: Permission denied", "/x").starts_with("Brak uprawnień"));
+    }
+
+    #[test]
+    fn test_parse_listing_line_and_quote() {
+        let entry = parse_listing_line("logs\t4096\t1700000000.1234\tdrwxr-xr-x").unwrap();
+        assert!(entry.is_dir);
+        assert_eq!((entry.size, entry.mtime), (4096, 1_700_000_000));
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    }
+
+    #[test]
+    fn test_copy_with_progress_reports_per_megabyte() {
+        let data = vec![7u8; 3 * 1024 * 1024 + 10];
+        let mut out = Vec::new();
+        let mut reports = Vec::new();
+        let copied = copy_with_progress(&mut data.as_slice(), &mut out, |b| reports.push(b)).unwrap();
+        assert_eq!(copied, data.len() as u64);
+        assert_eq!(out.len(), data.len());
+        assert_eq!(reports.len(), 3);
+    }
+
+    #[test]
+    fn test_known_hosts_edit_and_fingerprint() {
+        // Fingerprint as printed by `ssh-keygen -lf` for this ed25519 key
+        let key = "AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl";
+        assert_eq!(
+            key_fingerprint(key).unwrap(),
+            "SHA256:+DiY3wvvV6TuJJhbpZisF/zLDA0zPMSvHdkr4UvCOqU"
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("known_hosts");
+        std::fs::write(
+            &path,
+            format!(
+                "10.0.0.7 ssh-ed25519 {key}\n[10.0.0.7]:2222 ssh-ed25519 {key}\nnas,10.0.0.9 ssh-rsa AAAA\n"
+            ),
+        )
+        .unwrap();
+
+        assert!(known_host_fingerprint(&path, "10.0.0.7", 2222).is_some());
+        assert_eq!(known_host_fingerprint(&path, "10.0.0.8", 22), None);
+        assert_eq!(remove_known_host(&path, &host_pattern("10.0.0.7", 22)).unwrap(), 1);
+        assert_eq!(remove_known_host(&path, "nas").unwrap(), 1);
+        let rest = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(rest.lines().count(), 1);
+        assert!(rest.starts_with("[10.0.0.7]:2222 "));
+        assert!(is_host_key_error("Host key verification failed."));
+    }
+
+    #[test]
+    fn test_capture_keeps_nul_bytes() {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "printf 'a\\000b\\377'; printf 'err' >&2; exit 3"]);
+        let out = capture_capped(cmd, 1024).unwrap();
+        assert_eq!(out.stdout.bytes, b"a\0b\xff");
+        assert_eq!(out.stderr.bytes, b"err");
+        assert_eq!(out.exit_code, 3);
+        assert!(!out.stdout.truncated);
+        // Lossy text keeps the NUL, replaces the invalid byte and still serialises
+        let text = String::from_utf8_lossy(&out.stdout.bytes);
+        assert_eq!(text, "a\0b\u{FFFD}");
+        assert_eq!(serde_json::to_string(&text).unwrap(), "\"a\\u0000b\u{FFFD}\"");
+    }
+
+    #[test]
+    fn test_capture_stops_command_over_cap() {
+        // Would never end on its own
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "exec cat /dev/zero"]);
+        let out = capture_capped(cmd, 64 * 1024).unwrap();
+        assert_eq!(out.stdout.bytes.len(), 64 * 1024);
+        assert!(out.stdout.truncated && out.stdout.total > 64 * 1024);
+        assert!(!out.stderr.truncated);
+        assert_eq!(out.exit_code, -1);
+
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "head -c 1000 /dev/zero; exec cat /dev/zero >&2"]);
+        let out = capture_capped(cmd, 64 * 1024).unwrap();
+        assert_eq!((out.stdout.total, out.stdout.truncated), (1000, false));
+        assert!(out.stderr.truncated);
+        assert_eq!(out.exit_code, -1);
+    }
+
     #[test]
     fn test_ssh_banner_localhost() {
         // This test only works if SSH is running locally
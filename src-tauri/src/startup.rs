@@ -0,0 +1,225 @@
+//! startup.rs — Per-feature startup profile.
+//!
+//! `autostart_enable` only launches the app; the `on_start` profile in
+//! settings decides what is running once it is up: wake word listening,
+//! vision pipelines for a list of cameras and the Frigate MQTT bridge.
+//! Features start in a background task after the Tauri setup, one by one;
+//! a failure is recorded in the startup report and the rest still start.
+//!
+//! `--no-autostart` (or `BROXEEN_NO_AUTOSTART=1`) skips the profile, handy
+//! when debugging a feature that crashes at boot.
+
+use crate::frigate_mqtt::FrigateMqttConfig;
+use crate::logging::{backend_info, backend_warn};
+use crate::motion_detection::{PipelineCoreOptions, StartPipelineRequest};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StartupProfile {
+    #[serde(default)]
+    pub wake_word: bool,
+    /// `camera_id=rtsp://…`, or a bare stream URL (camera id = its host)
+    #[serde(default)]
+    pub vision_cameras: Vec<String>,
+    #[serde(default)]
+    pub frigate_mqtt: bool,
+    /// Broker used when `frigate_mqtt` is on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frigate_mqtt_config: Option<FrigateMqttConfig>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupFeatureResult {
+    /// "wake_word", "vision:<camera_id>" or "frigate_mqtt"
+    pub feature: String,
+    pub ok: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StartupReport {
+    /// Profile skipped by `--no-autostart` / `BROXEEN_NO_AUTOSTART`
+    pub skipped: bool,
+    /// All features of the profile have been attempted
+    pub finished: bool,
+    pub results: Vec<StartupFeatureResult>,
+}
+
+lazy_static::lazy_static! {
+    static ref REPORT: Mutex<StartupReport> = Mutex::new(StartupReport::default());
+}
+
+pub fn autostart_disabled() -> bool {
+    std::env::args().any(|a| a == "--no-autostart")
+        || std::env::var("BROXEEN_NO_AUTOSTART")
+            .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false)
+}
+
+/// `front=rtsp://10.0.0.5/stream` → ("front", url); a bare URL uses its host as id.
+pub fn parse_camera_entry(entry: &str) -> Result<(String, String), String> {
+    let entry = entry.trim();
+    let (id, url) = match entry.split_once('=') {
+        Some((id, url)) if !id.contains("://") && !id.contains('/') => (id.trim().to_string(), url.trim()),
+        _ => (String::new(), entry),
+    };
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return Err(format!("Nieprawidłowy adres kamery: {}", entry));
+    };
+    if scheme.is_empty() || rest.is_empty() {
+        return Err(format!("Nieprawidłowy adres kamery: {}", entry));
+    }
+    if !id.is_empty() {
+        return Ok((id, url.to_string()));
+    }
+    let authority = rest.split('/').next().unwrap_or(rest);
+    let host_port = authority.rsplit('@').next().unwrap_or(authority);
+    let host = host_port.split(':').next().unwrap_or(host_port);
+    if host.is_empty() {
+        return Err(format!("Brak hosta w adresie kamery: {}", entry));
+    }
+    Ok((host.to_string(), url.to_string()))
+}
+
+fn record(feature: impl Into<String>, result: Result<String, String>) {
+    let feature = feature.into();
+    let (ok, message) = match result {
+        Ok(m) => {
+            backend_info(format!("Startup profile: {} started ({})", feature, m));
+            (true, m)
+        }
+        Err(e) => {
+            backend_warn(format!("Startup profile: {} failed: {}", feature, e));
+            (false, e)
+        }
+    };
+    let mut report = REPORT.lock().unwrap_or_else(|e| e.into_inner());
+    report.results.push(StartupFeatureResult { feature, ok, message });
+}
+
+/// Start the features of the saved profile in the background. Called once
+/// from the Tauri `setup` hook.
+pub fn apply(app: AppHandle) {
+    if autostart_disabled() {
+        backend_info("Startup profile skipped (--no-autostart)");
+        let mut report = REPORT.lock().unwrap_or_else(|e| e.into_inner());
+        report.skipped = true;
+        report.finished = true;
+        return;
+    }
+    let profile = crate::settings::load_settings().on_start;
+    tauri::async_runtime::spawn(async move {
+        run_profile(&app, profile).await;
+        REPORT.lock().unwrap_or_else(|e| e.into_inner()).finished = true;
+    });
+}
+
+async fn run_profile(app: &AppHandle, profile: StartupProfile) {
+    if profile.wake_word {
+        let result = crate::audio_commands::wake_word_start(
+            app.state(),
+            app.state(),
+            app.clone(),
+            None,
+            None,
+        );
+        record("wake_word", result);
+    }
+
+    for entry in &profile.vision_cameras {
+        let (camera_id, rtsp_url) = match parse_camera_entry(entry) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                record(format!("vision:{}", entry), Err(e));
+                continue;
+            }
+        };
+        let request = StartPipelineRequest {
+            core: PipelineCoreOptions {
+                camera_id: camera_id.clone(),
+                rtsp_url,
+                db_path: None,
+                process_every: None,
+                var_threshold: None,
+                bg_history: None,
+                llm_model: None,
+                api_key: None,
+                llm_threshold: None,
+                night_mode: None,
+            },
+            native: Default::default(),
+            python: Default::default(),
+            unknown: HashMap::new(),
+        };
+        let result = crate::motion_detection::motion_pipeline_start(app.clone(), request)
+            .await
+            .map(|started| started.message)
+            .map_err(String::from);
+        record(format!("vision:{}", camera_id), result);
+    }
+
+    if profile.frigate_mqtt {
+        let result = match profile.frigate_mqtt_config {
+            Some(config) => crate::frigate_mqtt::frigate_mqtt_start(app.clone(), config).await,
+            None => Err("Brak konfiguracji brokera MQTT w profilu startowym".to_string()),
+        };
+        record("frigate_mqtt", result);
+    }
+}
+
+#[tauri::command]
+pub fn startup_profile_get() -> StartupProfile {
+    backend_info("Command startup_profile_get invoked");
+    crate::settings::load_settings().on_start
+}
+
+/// Validate and save the profile; it applies from the next launch.
+#[tauri::command]
+pub fn startup_profile_set(profile: StartupProfile) -> Result<StartupProfile, String> {
+    backend_info(format!(
+        "Command startup_profile_set invoked: wake_word={} cameras={} frigate_mqtt={}",
+        profile.wake_word,
+        profile.vision_cameras.len(),
+        profile.frigate_mqtt
+    ));
+    for entry in &profile.vision_cameras {
+        parse_camera_entry(entry)?;
+    }
+    if profile.frigate_mqtt && profile.frigate_mqtt_config.is_none() {
+        return Err("Włączono Frigate MQTT bez konfiguracji brokera".to_string());
+    }
+    let mut settings = crate::settings::load_settings();
+    settings.on_start = profile.clone();
+    crate::settings::save_settings(settings)?;
+    Ok(profile)
+}
+
+/// What the startup profile started at boot and what failed.
+#[tauri::command]
+pub fn startup_report() -> StartupReport {
+    REPORT.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_camera_entry() {
+        assert_eq!(
+            parse_camera_entry("front = rtsp://admin:pw@192.168.1.20:554/stream1").unwrap(),
+            ("front".to_string(), "rtsp://admin:pw@192.168.1.20:554/stream1".to_string())
+        );
+        // Bare URL: host becomes the id, '=' in the query is not a separator
+        assert_eq!(
+            parse_camera_entry("rtsp://user:p@ss@cam.local:8554/live?ch=1").unwrap(),
+            ("cam.local".to_string(), "rtsp://user:p@ss@cam.local:8554/live?ch=1".to_string())
+        );
+        assert!(parse_camera_entry("garden").is_err());
+        assert!(parse_camera_entry("garden=").is_err());
+        assert!(parse_camera_entry("rtsp:///stream").is_err());
+    }
+}
@@ -1,5 +1,7 @@
 //! stt.rs — Speech-to-Text via OpenRouter multimodal API.
 //! Accepts WAV base64 from native audio capture (audio_capture.rs).
+//! With `stt_engine = "whisper-local"` audio is transcribed offline instead
+//! (see stt_whisper.rs).
 
 use std::env;
 
@@ -234,6 +236,8 @@ pub async fn transcribe_wav_base64(
         .await
         .map_err(|e| format!("STT: błąd parsowania JSON: {e}"))?;
 
+    crate::llm_usage::record("stt", &model, &data);
+
     let text = data["choices"]
         .get(0)
         .and_then(|c| c["message"]["content"].as_str())
@@ -251,9 +255,25 @@ pub async fn transcribe_wav_base64(
     Ok(text)
 }
 
+/// Transcribe with the engine selected in settings (`stt_engine`).
+/// `"whisper-local"` runs offline; anything else uses the cloud path.
+pub async fn transcribe_with_engine(
+    engine: &str,
+    wav_base64: &str,
+    lang: &str,
+    api_key_override: Option<&str>,
+    model_override: Option<&str>,
+) -> Result<String, String> {
+    if engine == crate::stt_whisper::ENGINE_ID {
+        let local_model = crate::settings::load_settings().stt_model;
+        return crate::stt_whisper::transcribe_wav_base64(wav_base64, lang, Some(&local_model)).await;
+    }
+    transcribe_wav_base64(wav_base64, lang, api_key_override, model_override).await
+}
+
 /// Reject transcriptions that look like audio-processing glitches
 /// (e.g. a single word repeated dozens of times).
-fn detect_artifacts(text: &str) -> Result<(), String> {
+pub(crate) fn detect_artifacts(text: &str) -> Result<(), String> {
     let words: Vec<&str> = text.split_whitespace().collect();
     let total = words.len();
     if total < 10 {
@@ -292,12 +312,50 @@ fn detect_artifacts(text: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Inserted for a segment of a long recording that failed every retry.
+pub const UNINTELLIGIBLE_MARKER: &str = "[niezrozumiały fragment]";
+
+/// Longest word run checked when de-duplicating a segment join.
+const MAX_JOIN_OVERLAP_WORDS: usize = 8;
+
+/// Append the transcript of the next segment to `acc`. Segments overlap by
+/// about a second, so the words at the join usually appear twice; the longest
+/// suffix of `acc` that is also a prefix of `next` (ignoring case and
+/// punctuation) is dropped from `next`.
+pub fn stitch_transcripts(acc: &str, next: &str) -> String {
+    fn norm(w: &str) -> String {
+        w.to_lowercase().chars().filter(|c| c.is_alphanumeric()).collect()
+    }
+    let acc_words: Vec<&str> = acc.split_whitespace().collect();
+    let next_words: Vec<&str> = next.split_whitespace().collect();
+    let max = MAX_JOIN_OVERLAP_WORDS.min(acc_words.len()).min(next_words.len());
+
+    let overlap = (1..=max)
+        .rev()
+        .find(|&k| {
+            let tail = &acc_words[acc_words.len() - k..];
+            let head = &next_words[..k];
+            tail.iter().zip(head).all(|(a, b)| {
+                let a = norm(a);
+                !a.is_empty() && a == norm(b)
+            })
+        })
+        .unwrap_or(0);
+
+    let rest = next_words[overlap..].join(" ");
+    match (acc.trim().is_empty(), rest.is_empty()) {
+        (true, _) => rest,
+        (false, true) => acc.trim().to_string(),
+        (false, false) => format!("{} {}", acc.trim(), rest),
+    }
+}
+
 // ── Tauri commands ────────────────────────────────────────────────────────────
 
 /// Called by useStt.ts: `invoke("stt_transcribe", { audioBase64, format, language })`
 ///
 /// Legacy path: frontend captured audio via MediaRecorder and sends it directly.
-/// For the native Tauri flow (cpal → stop_and_encode_wav) use `stt_stop` in
+/// For the native Tauri flow (cpal → stop_and_encode_wav_segments) use `stt_stop` in
 /// audio_commands.rs instead.
 #[tauri::command]
 pub async fn stt_transcribe(
@@ -309,17 +367,45 @@ pub async fn stt_transcribe(
 ) -> Result<String, String> {
     let lang = language.as_deref().unwrap_or("pl");
 
+    let engine = crate::settings::load_settings().stt_engine;
+
     // Whisper / Gemini accept WAV; other container formats (webm, ogg) are
     // passed through — the model handles them on the server side.
     if format != "wav" {
+        if engine == crate::stt_whisper::ENGINE_ID {
+            return Err(format!("Lokalny Whisper obsługuje tylko WAV (otrzymano '{format}')"));
+        }
         println!("[stt] Uwaga: format '{format}' przekazywany bez konwersji");
     }
 
-    transcribe_wav_base64(
+    transcribe_with_engine(
+        &engine,
         &audio_base64,
         lang,
         api_key.as_deref(),
         model.as_deref(),
     )
     .await
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stitch_transcripts_overlap() {
+        assert_eq!(
+            stitch_transcripts("Jutro rano jedziemy do Krakowa,", "do krakowa i wracamy w piątek."),
+            "Jutro rano jedziemy do Krakowa, i wracamy w piątek."
+        );
+        // No common words at the join
+        assert_eq!(stitch_transcripts("Ala ma kota", "a kot ma Alę"), "Ala ma kota a kot ma Alę");
+        // Whole segment repeated at the join
+        assert_eq!(stitch_transcripts("raz dwa trzy", "dwa trzy"), "raz dwa trzy");
+        assert_eq!(stitch_transcripts("", "początek"), "początek");
+        assert_eq!(
+            stitch_transcripts("koniec", UNINTELLIGIBLE_MARKER),
+            "koniec [niezrozumiały fragment]"
+        );
+    }
+}
@@ -0,0 +1,359 @@
+//! stt_whisper.rs — Offline Speech-to-Text via whisper.cpp (whisper-rs).
+//!
+//! Selected with `stt_engine = "whisper-local"`. Models are ggml files under
+//! ~/.local/share/broxeen/whisper/ (`ggml-tiny.bin`, `ggml-base.bin`,
+//! `ggml-small.bin`), downloaded with the `whisper_install` command.
+//! `WHISPER_MODEL` overrides the model path.
+//!
+//! Inference needs the `whisper-local` cargo feature; without it the engine
+//! reports a clear error and the cloud path stays the default.
+
+use std::path::PathBuf;
+
+pub const ENGINE_ID: &str = "whisper-local";
+const MODEL_SIZES: &[&str] = &["tiny", "base", "small"];
+const DEFAULT_MODEL_SIZE: &str = "base";
+const MODEL_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+
+// ── Model paths ──────────────────────────────────────
+
+/// Where ggml Whisper models live.
+/// Default: ~/.local/share/broxeen/whisper/
+fn whisper_dir() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".into());
+    PathBuf::from(home)
+        .join(".local/share/broxeen/whisper")
+}
+
+fn model_file(size: &str) -> PathBuf {
+    whisper_dir().join(format!("ggml-{size}.bin"))
+}
+
+fn normalize_size(size: &str) -> Option<&'static str> {
+    let size = size.trim().to_lowercase();
+    MODEL_SIZES.iter().copied().find(|s| *s == size)
+}
+
+/// Resolve the model to load: `WHISPER_MODEL` env, then the requested size
+/// (`stt_model` = tiny/base/small), then any installed model (base first).
+pub fn resolve_model(requested: Option<&str>) -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("WHISPER_MODEL") {
+        let path = PathBuf::from(path);
+        if path.exists() {
+            return Some(path);
+        }
+    }
+    if let Some(size) = requested.and_then(normalize_size) {
+        let path = model_file(size);
+        if path.exists() {
+            return Some(path);
+        }
+    }
+    ["base", "small", "tiny"]
+        .iter()
+        .map(|s| model_file(s))
+        .find(|p| p.exists())
+}
+
+pub fn installed_models() -> Vec<String> {
+    MODEL_SIZES
+        .iter()
+        .filter(|s| model_file(s).exists())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+fn missing_model_error() -> String {
+    format!(
+        "Lokalny Whisper: brak modelu w {}. \
+         Zainstaluj go komendą whisper_install (tiny/base/small) lub ustaw WHISPER_MODEL=/ścieżka/ggml-base.bin",
+        whisper_dir().display()
+    )
+}
+
+// ── WAV decoding ─────────────────────────────────────
+
+/// Decode a PCM16 WAV to 16 kHz mono f32 samples (what whisper.cpp expects).
+fn wav_to_whisper_input(wav: &[u8]) -> Result<(Vec<i16>, Vec<f32>), String> {
+    let reader = hound::WavReader::new(std::io::Cursor::new(wav))
+        .map_err(|e| format!("Lokalny Whisper: nieprawidłowy plik WAV ({e})"))?;
+    let spec = reader.spec();
+    if spec.sample_format != hound::SampleFormat::Int || spec.bits_per_sample != 16 {
+        return Err(format!(
+            "Lokalny Whisper: obsługiwany tylko PCM16 (plik ma {} bit)",
+            spec.bits_per_sample
+        ));
+    }
+
+    let interleaved: Vec<i16> = reader
+        .into_samples::<i16>()
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Lokalny Whisper: uszkodzone dane WAV ({e})"))?;
+    let mono: Vec<i16> = interleaved
+        .chunks(spec.channels.max(1) as usize)
+        .map(|frame| (frame.iter().map(|&s| s as i32).sum::<i32>() / frame.len() as i32) as i16)
+        .collect();
+
+    let resampled = resample_linear(&mono, spec.sample_rate, 16_000);
+    let floats = resampled.iter().map(|&s| s as f32 / 32768.0).collect();
+    Ok((resampled, floats))
+}
+
+fn resample_linear(samples: &[i16], from: u32, to: u32) -> Vec<i16> {
+    if from == to || from == 0 || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let ratio = from as f64 / to as f64;
+    let out_len = (samples.len() as f64 / ratio) as usize;
+    (0..out_len)
+        .map(|i| {
+            let pos = i as f64 * ratio;
+            let idx = pos as usize;
+            let frac = pos - idx as f64;
+            let a = samples[idx] as f64;
+            let b = *samples.get(idx + 1).unwrap_or(&samples[idx]) as f64;
+            (a + (b - a) * frac) as i16
+        })
+        .collect()
+}
+
+// ── Inference ────────────────────────────────────────
+
+#[cfg(feature = "whisper-local")]
+mod engine {
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+    use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+    lazy_static::lazy_static! {
+        static ref CONTEXT: Mutex<Option<(PathBuf, Arc<WhisperContext>)>> = Mutex::new(None);
+    }
+
+    /// Load (or reuse) the context for `model` — loading takes seconds, so keep it.
+    fn context(model: &Path) -> Result<Arc<WhisperContext>, String> {
+        let mut guard = CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((path, ctx)) = guard.as_ref() {
+            if path == model {
+                return Ok(Arc::clone(ctx));
+            }
+        }
+        crate::backend_info(format!("[stt-whisper] Loading model {}", model.display()));
+        let ctx = WhisperContext::new_with_params(
+            &model.to_string_lossy(),
+            WhisperContextParameters::default(),
+        )
+        .map_err(|e| format!("Lokalny Whisper: nie można wczytać modelu: {e}"))?;
+        let ctx = Arc::new(ctx);
+        *guard = Some((model.to_path_buf(), Arc::clone(&ctx)));
+        Ok(ctx)
+    }
+
+    pub fn transcribe(model: &Path, samples: &[f32], lang: &str) -> Result<String, String> {
+        let ctx = context(model)?;
+        let mut state = ctx
+            .create_state()
+            .map_err(|e| format!("Lokalny Whisper: błąd stanu: {e}"))?;
+
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_language(Some(lang));
+        params.set_translate(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_special(false);
+        params.set_print_timestamps(false);
+        params.set_n_threads(
+            std::thread::available_parallelism().map(|n| n.get().min(8) as i32).unwrap_or(4),
+        );
+
+        state
+            .full(params, samples)
+            .map_err(|e| format!("Lokalny Whisper: błąd transkrypcji: {e}"))?;
+
+        let segments = state
+            .full_n_segments()
+            .map_err(|e| format!("Lokalny Whisper: {e}"))?;
+        let mut text = String::new();
+        for i in 0..segments {
+            if let Ok(segment) = state.full_get_segment_text(i) {
+                text.push_str(&segment);
+            }
+        }
+        Ok(text)
+    }
+}
+
+/// Transcribe WAV audio (base64) locally. Same contract as
+/// `stt::transcribe_wav_base64`: a trimmed transcript or a Polish error.
+pub async fn transcribe_wav_base64(
+    wav_base64: &str,
+    lang: &str,
+    model_size: Option<&str>,
+) -> Result<String, String> {
+    let model = resolve_model(model_size).ok_or_else(missing_model_error)?;
+
+    let wav = {
+        use base64::Engine;
+        base64::engine::general_purpose::STANDARD
+            .decode(wav_base64)
+            .map_err(|e| format!("Base64 decode error: {e}"))?
+    };
+    let (pcm, samples) = wav_to_whisper_input(&wav)?;
+
+    let vad = crate::stt::detect_voice_activity(&pcm, 16_000);
+    if !vad.is_speech {
+        return Err(format!(
+            "STT: brak mowy (rms={:.4}, zcr={:.3}, confidence={:.2})",
+            vad.rms, vad.zcr, vad.confidence
+        ));
+    }
+
+    // whisper.cpp wants a 2-letter code ("pl-PL" → "pl")
+    let lang = lang.split(['-', '_']).next().unwrap_or("pl").to_lowercase();
+    crate::backend_info(format!(
+        "[stt-whisper] Transcribing {:.1}s of audio with {}",
+        samples.len() as f32 / 16_000.0,
+        model.display()
+    ));
+
+    let text = run_inference(model, samples, lang).await?;
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return Err("STT: pusty wynik transkrypcji (za cicho lub cisza?)".into());
+    }
+    crate::stt::detect_artifacts(&text)?;
+
+    crate::backend_info(format!(
+        "[stt-whisper] Wynik: \"{}\"",
+        text.chars().take(120).collect::<String>()
+    ));
+    Ok(text)
+}
+
+#[cfg(feature = "whisper-local")]
+async fn run_inference(model: PathBuf, samples: Vec<f32>, lang: String) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || engine::transcribe(&model, &samples, &lang))
+        .await
+        .map_err(|e| format!("Task join error: {e}"))?
+}
+
+#[cfg(not(feature = "whisper-local"))]
+async fn run_inference(_model: PathBuf, _samples: Vec<f32>, _lang: String) -> Result<String, String> {
+    Err("Lokalny Whisper niedostępny — zbuduj aplikację z funkcją `whisper-local` \
+         (cargo build --features whisper-local)".into())
+}
+
+// ── Tauri commands ───────────────────────────────────
+
+#[derive(serde::Serialize)]
+pub struct WhisperStatus {
+    pub feature_enabled: bool,
+    pub model_dir: String,
+    pub installed_models: Vec<String>,
+    pub active_model: Option<String>,
+}
+
+#[tauri::command]
+pub fn whisper_status() -> WhisperStatus {
+    let settings = crate::settings::load_settings();
+    WhisperStatus {
+        feature_enabled: cfg!(feature = "whisper-local"),
+        model_dir: whisper_dir().display().to_string(),
+        installed_models: installed_models(),
+        active_model: resolve_model(Some(&settings.stt_model)).map(|p| p.display().to_string()),
+    }
+}
+
+/// Download a ggml Whisper model (`tiny`, `base` or `small`; default base).
+#[tauri::command]
+pub async fn whisper_install(size: Option<String>) -> Result<String, String> {
+    let requested = size.unwrap_or_else(|| DEFAULT_MODEL_SIZE.to_string());
+    let size = normalize_size(&requested).ok_or_else(|| {
+        format!("Nieznany model Whisper '{}' — dostępne: {}", requested, MODEL_SIZES.join(", "))
+    })?;
+    crate::backend_info(format!("Command whisper_install invoked (size={})", size));
+
+    let dest = model_file(size);
+    if dest.exists() {
+        return Ok(format!("Model Whisper '{}' już zainstalowany: {}", size, dest.display()));
+    }
+    std::fs::create_dir_all(whisper_dir())
+        .map_err(|e| format!("Cannot create {}: {e}", whisper_dir().display()))?;
+
+    let url = format!("{MODEL_BASE_URL}/ggml-{size}.bin");
+    let part = dest.with_extension("bin.part");
+    let bytes = download_to(&url, &part).await?;
+    std::fs::rename(&part, &dest)
+        .map_err(|e| format!("Cannot move {} → {}: {e}", part.display(), dest.display()))?;
+
+    Ok(format!(
+        "Whisper '{}' zainstalowany pomyślnie w {} ({} MB)",
+        size,
+        dest.display(),
+        bytes / 1_048_576
+    ))
+}
+
+/// Stream a large file to disk without buffering it in memory.
+async fn download_to(url: &str, dest: &std::path::Path) -> Result<u64, String> {
+    use std::io::Write;
+
+    crate::backend_info(format!("[whisper-setup] Downloading {} ...", url));
+
+    // The large models are ~3 GB, so the whole-request timeout has to cover
+    // the body as well; Hugging Face rate-limits with 429 + Retry-After.
+    let client = crate::http_client::client(std::time::Duration::from_secs(30 * 60))?;
+    let policy = crate::http_client::RetryPolicy::default()
+        .max_retry_after(std::time::Duration::from_secs(60));
+    let (mut response, meta) = crate::http_client::send_with_retry(client.get(url), &policy)
+        .await
+        .map_err(|e| format!("HTTP request failed for {url}: {e}"))?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {} for {url}{}", response.status(), meta.describe()));
+    }
+
+    let mut file = std::fs::File::create(dest)
+        .map_err(|e| format!("Cannot create {}: {e}", dest.display()))?;
+    let mut total = 0u64;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to read response body from {url}: {e}"))?
+    {
+        file.write_all(&chunk)
+            .map_err(|e| format!("Cannot write to {}: {e}", dest.display()))?;
+        total += chunk.len() as u64;
+    }
+
+    crate::backend_info(format!("[whisper-setup] Saved {} ({} bytes)", dest.display(), total));
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stereo_8k_wav_is_mixed_and_resampled() {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 8_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        {
+            let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+            for _ in 0..800 {
+                writer.write_sample(1000i16).unwrap();
+                writer.write_sample(3000i16).unwrap();
+            }
+            writer.finalize().unwrap();
+        }
+
+        let (pcm, floats) = wav_to_whisper_input(cursor.get_ref()).unwrap();
+        assert_eq!(pcm.len(), 1600);
+        assert!(pcm.iter().all(|&s| s == 2000));
+        assert!((floats[0] - 2000.0 / 32768.0).abs() < 1e-6);
+        assert!(wav_to_whisper_input(b"RIFF....WAVEfmt ").is_err());
+    }
+}
@@ -1,7 +1,8 @@
 //! tts_backend.rs — Text-to-Speech via Piper (neural) + espeak-ng (fallback).
 //! Plays audio through ALSA using rodio, bypassing WebKitGTK entirely.
 
-use rodio::{Decoder, OutputStream, Sink};
+use cpal::traits::{DeviceTrait, HostTrait};
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink};
 use std::io::{BufReader, Cursor};
 use std::path::PathBuf;
 use std::process::Command;
@@ -235,23 +236,173 @@ pub fn play_wav_async(wav_data: Vec<u8>, volume: f32) -> Result<(), String> {
     Ok(())
 }
 
-/// Play WAV bytes and return stoppable sink and stream.
-pub fn play_wav_stoppable(wav_data: &[u8], volume: f32) -> Result<(OutputStream, Sink), String> {
-    let (stream, handle) = OutputStream::try_default()
-        .map_err(|e| format!("Cannot open audio output: {e}"))?;
+/// Names of available audio output devices (speakers).
+pub fn list_output_devices() -> Result<Vec<String>, String> {
+    let host = cpal::default_host();
+    let devices = host
+        .output_devices()
+        .map_err(|e| format!("Cannot enumerate output devices: {e}"))?;
+
+    Ok(devices.filter_map(|d| d.name().ok()).collect())
+}
+
+fn is_default_device(device_id: &str) -> bool {
+    let id = device_id.trim();
+    id.is_empty() || id.eq_ignore_ascii_case("default")
+}
+
+/// Whether `device_id` (from `speaker_device_id`) is currently present.
+/// The system default always counts as available.
+pub fn output_device_available(device_id: &str) -> bool {
+    is_default_device(device_id)
+        || list_output_devices().map(|d| d.iter().any(|n| n == device_id)).unwrap_or(false)
+}
+
+/// Open an output stream + empty sink on `device_id` (`speaker_device_id`),
+/// falling back to the default output when that device is missing or fails.
+/// Chunks are appended with `append_wav`.
+pub fn open_sink(device_id: &str, volume: f32) -> Result<(OutputStream, Sink), String> {
+    let (stream, handle) = open_output_stream(device_id)?;
 
     let sink = Sink::try_new(&handle)
         .map_err(|e| format!("Cannot create audio sink: {e}"))?;
 
     sink.set_volume(volume.clamp(0.0, 1.0));
+    Ok((stream, sink))
+}
 
+fn open_output_stream(device_id: &str) -> Result<(OutputStream, OutputStreamHandle), String> {
+    if !is_default_device(device_id) {
+        let device = cpal::default_host()
+            .output_devices()
+            .ok()
+            .and_then(|mut devices| devices.find(|d| d.name().map(|n| n == device_id).unwrap_or(false)));
+
+        match device {
+            Some(device) => match OutputStream::try_from_device(&device) {
+                Ok(pair) => return Ok(pair),
+                Err(e) => crate::backend_warn(format!(
+                    "Cannot open speaker '{}': {} — falling back to default output", device_id, e
+                )),
+            },
+            None => crate::backend_warn(format!(
+                "Speaker '{}' not found — falling back to default output", device_id
+            )),
+        }
+    }
+
+    OutputStream::try_default().map_err(|e| format!("Cannot open audio output: {e}"))
+}
+
+/// Queue WAV bytes at the end of an existing sink.
+pub fn append_wav(sink: &Sink, wav_data: &[u8]) -> Result<(), String> {
     let cursor = Cursor::new(wav_data.to_vec());
     let source = Decoder::new(BufReader::new(cursor))
         .map_err(|e| format!("Cannot decode WAV: {e}"))?;
-
     sink.append(source);
+    Ok(())
+}
 
-    Ok((stream, sink))
+// ── Sentence chunking (streaming TTS) ────────────────
+
+/// Abbreviations that end with a dot but do not end a sentence (lowercase).
+const ABBREVIATIONS: &[&str] = &[
+    // Polish
+    "np", "itd", "itp", "tzn", "tzw", "tj", "m.in", "ok", "ul", "al", "pl", "nr", "godz",
+    "min", "sek", "str", "dr", "inż", "mgr", "prof", "hab", "płk", "gen", "ks", "św",
+    "wg", "ws", "zob", "por", "ww", "jw", "cd", "ds",
+    // English
+    "e.g", "i.e", "etc", "vs", "mr", "mrs", "ms", "st", "no", "approx", "fig",
+];
+
+/// Split text into sentence-sized chunks for incremental synthesis.
+///
+/// Breaks after `.`, `!`, `?`, `…` and newlines, but not after known
+/// abbreviations or single initials (decimals never match: no space
+/// follows). Very short sentences are merged with the next one; very long
+/// ones are cut at commas / spaces so the first chunk stays quick to
+/// synthesize.
+pub fn split_sentences(text: &str, max_chunk_chars: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut sentences: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        current.push(c);
+        let next = chars.get(i + 1).copied();
+        let boundary = match c {
+            '\n' => true,
+            '!' | '?' | '…' => next.is_none_or(char::is_whitespace),
+            '.' => next.is_none_or(char::is_whitespace) && !is_abbreviation(&current),
+            _ => false,
+        };
+        if boundary {
+            push_sentence(&mut sentences, &current);
+            current.clear();
+        }
+    }
+    push_sentence(&mut sentences, &current);
+
+    // Merge fragments shorter than ~20 chars into the following sentence
+    let mut merged: Vec<String> = Vec::new();
+    for s in sentences {
+        match merged.last_mut() {
+            Some(last) if last.chars().count() < 20 => {
+                last.push(' ');
+                last.push_str(&s);
+            }
+            _ => merged.push(s),
+        }
+    }
+
+    merged.into_iter().flat_map(|s| split_long(&s, max_chunk_chars)).collect()
+}
+
+fn push_sentence(out: &mut Vec<String>, s: &str) {
+    let s = s.trim();
+    if !s.is_empty() {
+        out.push(s.to_string());
+    }
+}
+
+/// Does the text end with a known abbreviation or a single-letter initial + "."?
+fn is_abbreviation(text: &str) -> bool {
+    let without_dot = text.trim_end_matches('.');
+    let word = without_dot
+        .rsplit(|c: char| c.is_whitespace() || c == '(' || c == '"')
+        .next()
+        .unwrap_or("")
+        .to_lowercase();
+    if word.is_empty() {
+        return false;
+    }
+    if word.chars().count() == 1 && word.chars().all(char::is_alphabetic) {
+        return true;
+    }
+    ABBREVIATIONS.contains(&word.as_str())
+}
+
+/// Cut an over-long sentence at the last comma (or space) before `max` chars.
+fn split_long(s: &str, max: usize) -> Vec<String> {
+    if max == 0 || s.chars().count() <= max {
+        return vec![s.to_string()];
+    }
+    let mut out = Vec::new();
+    let mut rest = s.trim();
+    while rest.chars().count() > max {
+        let limit = rest.char_indices().nth(max).map(|(i, _)| i).unwrap_or(rest.len());
+        let head = &rest[..limit];
+        let cut = head.rfind(", ").map(|i| i + 1)
+            .or_else(|| head.rfind(' '))
+            .filter(|&i| i > 0)
+            .unwrap_or(limit);
+        out.push(rest[..cut].trim().to_string());
+        rest = rest[cut..].trim_start();
+    }
+    if !rest.is_empty() {
+        out.push(rest.to_string());
+    }
+    out
 }
 
 // ── Speak text end-to-end ────────────────────────────
@@ -388,14 +539,15 @@ async fn download_file(client: &reqwest::Client, url: &str, dest: &std::path::Pa
 
     println!("[piper-setup] Downloading {} ...", url);
 
-    let response = client
-        .get(url)
-        .send()
+    // GitHub / Hugging Face rate-limit anonymous downloads with 429 + Retry-After
+    let policy = crate::http_client::RetryPolicy::default()
+        .max_retry_after(std::time::Duration::from_secs(60));
+    let (response, meta) = crate::http_client::send_with_retry(client.get(url), &policy)
         .await
         .map_err(|e| format!("HTTP request failed for {url}: {e}"))?;
 
     if !response.status().is_success() {
-        return Err(format!("HTTP {} for {url}", response.status()));
+        return Err(format!("HTTP {} for {url}{}", response.status(), meta.describe()));
     }
 
     let bytes = response
@@ -425,11 +577,7 @@ pub async fn download_and_install_piper() -> Result<String, String> {
     std::fs::create_dir_all(&dir)
         .map_err(|e| format!("Cannot create {}: {e}", dir.display()))?;
 
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(300))
-        .user_agent("Broxeen/1.0")
-        .build()
-        .map_err(|e| format!("Cannot create HTTP client: {e}"))?;
+    let client = crate::http_client::client(std::time::Duration::from_secs(300))?;
 
     // ── 1. Download and extract Piper binary ─────────
     let tar_path = dir.join("piper_linux_x86_64.tar.gz");
@@ -525,3 +673,37 @@ pub async fn download_and_install_piper() -> Result<String, String> {
         engine
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_sentences_respects_abbreviations() {
+        let text = "Na kamerze widać np. samochód i dr. Kowalskiego. Drugie zdanie jest tutaj dłuższe! A to trzecie?";
+        let chunks = split_sentences(text, 400);
+        assert_eq!(chunks, vec![
+            "Na kamerze widać np. samochód i dr. Kowalskiego.",
+            "Drugie zdanie jest tutaj dłuższe!",
+            "A to trzecie?",
+        ]);
+    }
+
+    #[test]
+    fn split_sentences_merges_short_and_cuts_long() {
+        let chunks = split_sentences("Tak. To jest wystarczająco długie zdanie.", 400);
+        assert_eq!(chunks, vec!["Tak. To jest wystarczająco długie zdanie."]);
+
+        let long = "raz, dwa, trzy, cztery, pięć, sześć, siedem";
+        let chunks = split_sentences(long, 20);
+        assert!(chunks.iter().all(|c| c.chars().count() <= 20));
+        assert_eq!(chunks.join(" "), long);
+    }
+
+    #[test]
+    fn split_sentences_keeps_decimals() {
+        let chunks = split_sentences("Temperatura wynosi 21.5 stopnia dzisiaj rano. Jutro będzie cieplej niż dziś.", 400);
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].contains("21.5"));
+    }
+}
@@ -0,0 +1,454 @@
+//! tts_text.rs — Text preprocessing before speech synthesis.
+//!
+//! Piper and espeak read what they get: "ul." becomes "ul", an IP address a
+//! string of digits and markdown markup is spoken aloud. `prepare` rewrites
+//! text into something worth listening to:
+//!
+//! 1. user dictionary (`tts_dictionary` setting, whole words, case-insensitive)
+//! 2. markdown markup stripped, links reduced to their label
+//! 3. URLs replaced by their host
+//! 4. IP addresses read octet by octet
+//! 5. Polish only: numbers, amounts, percentages and common abbreviations
+//!    verbalized
+//!
+//! Callers skip it for exact readback (`exact` flag on the TTS commands).
+
+use crate::logging::backend_info;
+use regex_lite::{Captures, Regex};
+use std::collections::{BTreeMap, HashMap};
+
+/// Abbreviation → expansion. `true` when the abbreviation is written with a
+/// trailing dot (which is consumed with it).
+const ABBREVIATIONS_PL: &[(&str, &str, bool)] = &[
+    ("ul", "ulica", true),
+    ("al", "aleja", true),
+    ("np", "na przykład", true),
+    ("tzn", "to znaczy", true),
+    ("tzw", "tak zwany", true),
+    ("itd", "i tak dalej", true),
+    ("itp", "i tym podobne", true),
+    ("godz", "godzina", true),
+    ("tel", "telefon", true),
+    ("min", "minut", true),
+    ("sek", "sekund", true),
+    ("wg", "według", false),
+    ("nr", "numer", false),
+    ("zł", "złotych", false),
+    ("pln", "złotych", false),
+];
+/// Abbreviations that commonly close a sentence ("…, kable itd. Potem…")
+const SENTENCE_FINAL_PL: &[&str] = &["itd", "itp", "min", "sek"];
+
+const UNITS: [&str; 10] = ["zero", "jeden", "dwa", "trzy", "cztery", "pięć", "sześć", "siedem", "osiem", "dziewięć"];
+const TEENS: [&str; 10] = [
+    "dziesięć", "jedenaście", "dwanaście", "trzynaście", "czternaście",
+    "piętnaście", "szesnaście", "siedemnaście", "osiemnaście", "dziewiętnaście",
+];
+const TENS: [&str; 10] = [
+    "", "", "dwadzieścia", "trzydzieści", "czterdzieści",
+    "pięćdziesiąt", "sześćdziesiąt", "siedemdziesiąt", "osiemdziesiąt", "dziewięćdziesiąt",
+];
+const HUNDREDS: [&str; 10] = [
+    "", "sto", "dwieście", "trzysta", "czterysta", "pięćset", "sześćset", "siedemset", "osiemset", "dziewięćset",
+];
+/// Digits longer than this (phone numbers, serials) are read one by one
+const MAX_VERBAL_DIGITS: usize = 9;
+
+fn is_polish(lang: &str) -> bool {
+    lang.trim().to_lowercase().starts_with("pl")
+}
+
+/// Full preprocessing pipeline; see module docs.
+pub fn prepare(text: &str, lang: &str, dictionary: &BTreeMap<String, String>) -> String {
+    let polish = is_polish(lang);
+    let mut out = apply_dictionary(text, dictionary);
+    out = strip_markdown(&out);
+    out = replace_urls(&out);
+    out = replace_ips(&out, polish);
+    if polish {
+        out = verbalize_pl(&out);
+    }
+    collapse_spaces(&out)
+}
+
+// ── Dictionary ───────────────────────────────────────
+
+fn is_word_char(c: Option<char>) -> bool {
+    c.is_some_and(char::is_alphanumeric)
+}
+
+/// Replace whole-word occurrences of every dictionary key, ignoring case.
+pub fn apply_dictionary(text: &str, dictionary: &BTreeMap<String, String>) -> String {
+    let mut out = text.to_string();
+    for (word, replacement) in dictionary {
+        let word = word.trim();
+        if word.is_empty() {
+            continue;
+        }
+        let needle: Vec<char> = word.to_lowercase().chars().collect();
+        let chars: Vec<char> = out.chars().collect();
+        let mut result = String::with_capacity(out.len());
+        let mut i = 0;
+        while i < chars.len() {
+            let candidate = chars.get(i..i + needle.len());
+            let matches = candidate.is_some_and(|c| {
+                c.iter().zip(&needle).all(|(a, b)| a.to_lowercase().eq(std::iter::once(*b)))
+            });
+            let bounded = matches
+                && !is_word_char(i.checked_sub(1).map(|p| chars[p]))
+                && !is_word_char(chars.get(i + needle.len()).copied());
+            if bounded {
+                result.push_str(replacement);
+                i += needle.len();
+            } else {
+                result.push(chars[i]);
+                i += 1;
+            }
+        }
+        out = result;
+    }
+    out
+}
+
+// ── Markdown, URLs, IPs ──────────────────────────────
+
+fn re(pattern: &str) -> Regex {
+    Regex::new(pattern).expect("valid built-in regex")
+}
+
+lazy_static::lazy_static! {
+    static ref MD_FENCE: Regex = re(r"(?m)^\s*```.*$");
+    static ref MD_IMAGE: Regex = re(r"!\[([^\]]*)\]\([^)]*\)");
+    static ref MD_LINK: Regex = re(r"\[([^\]]+)\]\([^)]*\)");
+    static ref MD_HEADING: Regex = re(r"(?m)^\s{0,3}#{1,6}\s+");
+    static ref MD_QUOTE: Regex = re(r"(?m)^\s*>\s?");
+    static ref MD_BULLET: Regex = re(r"(?m)^\s*[-*+]\s+");
+    static ref MD_TABLE_RULE: Regex = re(r"(?m)^\s*\|?(\s*:?-{3,}:?\s*\|)+\s*$");
+    static ref URL: Regex = re(r"(?:https?://|www\.)([A-Za-z0-9.-]+)[^\s)\]>]*");
+    static ref IPV4: Regex = re(r"\b(\d{1,3})\.(\d{1,3})\.(\d{1,3})\.(\d{1,3})\b");
+    // "ok." only means "około" before a number ("ok. 5 minut"), not "OK."
+    static ref APPROX_PL: Regex = re(&format!(r"(^|[^{}])([Oo])k\.\s*(\d)", PL_LETTERS));
+    static ref AMOUNT_PL: Regex = re(&format!(r"(\d+)\s*(?:zł|PLN)([^{}]|$)", PL_LETTERS));
+    static ref PERCENT: Regex = re(r"(\d+)\s*%");
+    static ref DATE: Regex = re(r"\b(\d{1,2})\.(\d{1,2})\.(\d{4})\b");
+    static ref DECIMAL: Regex = re(r"\b(\d+)[.,](\d+)\b");
+    static ref TIME: Regex = re(r"\b(\d{1,2}):(\d{2})\b");
+}
+
+/// Drop markup that would otherwise be read out ("gwiazdka gwiazdka").
+pub fn strip_markdown(text: &str) -> String {
+    let mut out = MD_FENCE.replace_all(text, "").into_owned();
+    out = MD_IMAGE.replace_all(&out, "$1").into_owned();
+    out = MD_LINK.replace_all(&out, "$1").into_owned();
+    out = MD_HEADING.replace_all(&out, "").into_owned();
+    out = MD_QUOTE.replace_all(&out, "").into_owned();
+    out = MD_BULLET.replace_all(&out, "").into_owned();
+    out = MD_TABLE_RULE.replace_all(&out, "").into_owned();
+    out.replace("**", "").replace("__", "").replace("~~", "").replace(['`', '*', '|'], " ")
+}
+
+/// `https://www.example.com/a/b?c` → `example.com`
+pub fn replace_urls(text: &str) -> String {
+    URL.replace_all(text, |caps: &Captures| {
+            caps[1].trim_start_matches("www.").trim_end_matches('.').to_string()
+        })
+        .into_owned()
+}
+
+pub fn replace_ips(text: &str, polish: bool) -> String {
+    IPV4.replace_all(text, |caps: &Captures| {
+            let octets: Vec<String> = (1..=4)
+                .map(|i| {
+                    let octet = &caps[i];
+                    match octet.parse::<u64>() {
+                        Ok(n) if polish => number_pl(n),
+                        _ => octet.to_string(),
+                    }
+                })
+                .collect();
+            octets.join(if polish { " kropka " } else { " dot " })
+        })
+        .into_owned()
+}
+
+// ── Polish numbers ───────────────────────────────────
+
+/// Pick the Polish plural form for `n` (1 / 2–4 / 5+).
+pub fn plural_pl<'a>(n: u64, one: &'a str, few: &'a str, many: &'a str) -> &'a str {
+    let (last, last_two) = (n % 10, n % 100);
+    if n == 1 {
+        one
+    } else if (2..=4).contains(&last) && !(12..=14).contains(&last_two) {
+        few
+    } else {
+        many
+    }
+}
+
+fn below_thousand(n: u64, words: &mut Vec<&'static str>) {
+    let (h, rest) = ((n / 100) as usize, n % 100);
+    if h > 0 {
+        words.push(HUNDREDS[h]);
+    }
+    match rest {
+        0 => {}
+        1..=9 => words.push(UNITS[rest as usize]),
+        10..=19 => words.push(TEENS[(rest - 10) as usize]),
+        _ => {
+            words.push(TENS[(rest / 10) as usize]);
+            if rest % 10 > 0 {
+                words.push(UNITS[(rest % 10) as usize]);
+            }
+        }
+    }
+}
+
+/// Cardinal number in words (nominative), e.g. 2024 → "dwa tysiące dwadzieścia cztery".
+pub fn number_pl(n: u64) -> String {
+    if n == 0 {
+        return UNITS[0].to_string();
+    }
+    if n >= 1_000_000_000 {
+        return digits_pl(&n.to_string());
+    }
+    let mut words = Vec::new();
+    for (scale, forms) in [
+        (1_000_000, ("milion", "miliony", "milionów")),
+        (1_000, ("tysiąc", "tysiące", "tysięcy")),
+    ] {
+        let count = (n / scale) % 1000;
+        if count == 1 {
+            words.push(forms.0);
+        } else if count > 1 {
+            below_thousand(count, &mut words);
+            words.push(plural_pl(count, forms.0, forms.1, forms.2));
+        }
+    }
+    below_thousand(n % 1000, &mut words);
+    words.join(" ")
+}
+
+fn digits_pl(digits: &str) -> String {
+    digits
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|d| UNITS[d as usize])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A run of digits as spoken: short numbers as words, long or zero-padded
+/// ones (phone numbers, codes) digit by digit.
+fn digit_run_pl(digits: &str) -> String {
+    if digits.len() > MAX_VERBAL_DIGITS || (digits.len() > 1 && digits.starts_with('0')) {
+        return digits_pl(digits);
+    }
+    digits.parse::<u64>().map(number_pl).unwrap_or_else(|_| digits_pl(digits))
+}
+
+const PL_LETTERS: &str = "A-Za-z0-9ąćęłńóśźżĄĆĘŁŃÓŚŹŻ";
+
+/// Numbers, amounts, percentages and abbreviations → words.
+pub fn verbalize_pl(text: &str) -> String {
+    let mut out = text
+        .replace("m.in.", "między innymi")
+        .replace("°C", " stopni Celsjusza")
+        .replace("km/h", " kilometrów na godzinę");
+    out = APPROX_PL
+        .replace_all(&out, |caps: &Captures| {
+            let o = if &caps[2] == "O" { "Około" } else { "około" };
+            format!("{}{} {}", &caps[1], o, &caps[3])
+        })
+        .into_owned();
+
+    // Amounts: "5 zł" → "pięć złotych", "2 zł" → "dwa złote"
+    out = AMOUNT_PL
+        .replace_all(&out, |caps: &Captures| {
+            let n: u64 = caps[1].parse().unwrap_or(0);
+            format!("{} {}{}", digit_run_pl(&caps[1]), plural_pl(n, "złoty", "złote", "złotych"), &caps[2])
+        })
+        .into_owned();
+    out = PERCENT
+        .replace_all(&out, |caps: &Captures| format!("{} procent", digit_run_pl(&caps[1])))
+        .into_owned();
+    // Dates, decimals and times before plain digit runs
+    out = DATE
+        .replace_all(&out, |caps: &Captures| {
+            let part = |i: usize| digit_run_pl(caps[i].trim_start_matches('0'));
+            format!("{} {} {}", part(1), part(2), part(3))
+        })
+        .into_owned();
+    out = DECIMAL
+        .replace_all(&out, |caps: &Captures| {
+            format!("{} przecinek {}", digit_run_pl(&caps[1]), digit_run_pl(&caps[2]))
+        })
+        .into_owned();
+    out = TIME
+        .replace_all(&out, |caps: &Captures| {
+            let minutes = if &caps[2] == "00" { String::new() } else { format!(" {}", digit_run_pl(caps[2].trim_start_matches('0'))) };
+            format!("{}{}", digit_run_pl(&caps[1]), minutes)
+        })
+        .into_owned();
+
+    expand_tokens(&out)
+}
+
+/// Walk alphanumeric runs: expand abbreviations and verbalize remaining digits.
+fn expand_tokens(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        if !chars[i].is_alphanumeric() {
+            out.push(chars[i]);
+            i += 1;
+            continue;
+        }
+        let start = i;
+        // Digits and letters form separate tokens ("5km" → "5" + "km")
+        let numeric = chars[i].is_ascii_digit();
+        while i < chars.len() && chars[i].is_alphanumeric() && chars[i].is_ascii_digit() == numeric {
+            i += 1;
+        }
+        let token: String = chars[start..i].iter().collect();
+        if numeric {
+            out.push_str(&digit_run_pl(&token));
+            continue;
+        }
+
+        let lower = token.to_lowercase();
+        let after_dot = chars.get(i) == Some(&'.');
+        let abbr = ABBREVIATIONS_PL
+            .iter()
+            .find(|(abbr, _, dotted)| *abbr == lower && (after_dot || !dotted));
+        match abbr {
+            Some((abbr, expansion, dotted)) => {
+                let mut expansion = expansion.to_string();
+                if token.chars().next().is_some_and(char::is_uppercase) {
+                    expansion = capitalize(&expansion);
+                }
+                out.push_str(&expansion);
+                if *dotted {
+                    i += 1;
+                    // The dot may also end the sentence: keep it at end of text
+                    // or line, and before a capital for abbreviations that
+                    // close an enumeration ("ul. Długa" is not a sentence end)
+                    let next = chars[i..].iter().copied().find(|c| *c == '\n' || !c.is_whitespace());
+                    let ends = match next {
+                        None | Some('\n') => true,
+                        Some(c) => c.is_uppercase() && SENTENCE_FINAL_PL.contains(abbr),
+                    };
+                    if ends {
+                        out.push('.');
+                    }
+                }
+            }
+            None => out.push_str(&token),
+        }
+    }
+    out
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Merge `entries` into the `tts_dictionary` setting (an empty value removes
+/// the word); with `replace` the dictionary is rebuilt from `entries` alone.
+/// Returns the resulting number of entries.
+#[tauri::command]
+pub fn tts_dictionary_set(entries: HashMap<String, String>, replace: Option<bool>) -> Result<usize, String> {
+    backend_info(format!(
+        "Command tts_dictionary_set invoked: {} entries, replace={:?}",
+        entries.len(),
+        replace
+    ));
+    let mut settings = crate::settings::load_settings();
+    if replace.unwrap_or(false) {
+        settings.tts_dictionary.clear();
+    }
+    for (word, spoken) in entries {
+        let word = word.trim();
+        if word.is_empty() {
+            return Err("Słowo w słowniku wymowy nie może być puste".to_string());
+        }
+        if spoken.trim().is_empty() {
+            settings.tts_dictionary.remove(word);
+        } else {
+            settings.tts_dictionary.insert(word.to_string(), spoken.trim().to_string());
+        }
+    }
+    let count = settings.tts_dictionary.len();
+    crate::settings::save_settings(settings)?;
+    Ok(count)
+}
+
+fn collapse_spaces(text: &str) -> String {
+    text.lines()
+        .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numbers_pl() {
+        assert_eq!(number_pl(0), "zero");
+        assert_eq!(number_pl(12), "dwanaście");
+        assert_eq!(number_pl(192), "sto dziewięćdziesiąt dwa");
+        assert_eq!(number_pl(1000), "tysiąc");
+        assert_eq!(number_pl(2024), "dwa tysiące dwadzieścia cztery");
+        assert_eq!(number_pl(15_000), "piętnaście tysięcy");
+        assert_eq!(number_pl(2_500_000), "dwa miliony pięćset tysięcy");
+        assert_eq!(digit_run_pl("0612"), "zero sześć jeden dwa");
+        assert_eq!(plural_pl(22, "złoty", "złote", "złotych"), "złote");
+        assert_eq!(plural_pl(12, "złoty", "złote", "złotych"), "złotych");
+    }
+
+    #[test]
+    fn test_prepare_polish_text() {
+        let dict = BTreeMap::new();
+        assert_eq!(
+            prepare("Mieszkam przy ul. Długiej 5, np. obok sklepu.", "pl-PL", &dict),
+            "Mieszkam przy ulica Długiej pięć, na przykład obok sklepu."
+        );
+        assert_eq!(
+            prepare("Kamera 192.168.1.10 kosztuje 2 zł", "pl-PL", &dict),
+            "Kamera sto dziewięćdziesiąt dwa kropka sto sześćdziesiąt osiem kropka jeden kropka dziesięć kosztuje dwa złote"
+        );
+        assert_eq!(prepare("Zużycie CPU: 45%, temp 3,5°C", "pl", &dict), "Zużycie CPU: czterdzieści pięć procent, temp trzy przecinek pięć stopni Celsjusza");
+        // Sentence-final abbreviation keeps its full stop
+        assert_eq!(prepare("Kable, wtyczki itd. Potem reszta.", "pl", &dict), "Kable, wtyczki i tak dalej. Potem reszta.");
+        assert_eq!(prepare("Spotkanie o 10:30", "pl", &dict), "Spotkanie o dziesięć trzydzieści");
+        // "ok." is "około" only before a number
+        assert_eq!(prepare("Czekaj ok. 5 min.", "pl", &dict), "Czekaj około pięć minut.");
+        assert_eq!(prepare("Wszystko ok. Dzięki.", "pl", &dict), "Wszystko ok. Dzięki.");
+    }
+
+    #[test]
+    fn test_markdown_urls_and_dictionary() {
+        let md = "## Wynik\n\n- **Serwer**: zobacz [panel](http://nas.local:5000/ui) lub https://www.example.com/a?b=1\n```bash\nls\n```";
+        assert_eq!(
+            prepare(md, "en-US", &BTreeMap::new()),
+            "Wynik\nSerwer: zobacz panel lub example.com\nls"
+        );
+
+        let mut dict = BTreeMap::new();
+        dict.insert("Kubernetes".to_string(), "kubernetis".to_string());
+        dict.insert("NAS".to_string(), "nas".to_string());
+        assert_eq!(
+            apply_dictionary("kubernetes na NAS-ie, nie NASA", &dict),
+            "kubernetis na nas-ie, nie NASA"
+        );
+        // English text keeps digits for the engine
+        assert_eq!(prepare("Ping 10.0.0.1 in 5 ms", "en", &BTreeMap::new()), "Ping 10 dot 0 dot 0 dot 1 in 5 ms");
+    }
+}
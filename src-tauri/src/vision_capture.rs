@@ -5,13 +5,33 @@
 
 use anyhow::{bail, Result};
 use opencv::{
-    core::Mat,
+    core::{Mat, Vector},
     prelude::*,
-    videoio::{VideoCapture, CAP_FFMPEG, CAP_PROP_BUFFERSIZE, CAP_PROP_FPS},
+    videoio::{
+        VideoCapture, CAP_FFMPEG, CAP_PROP_BUFFERSIZE, CAP_PROP_FPS, CAP_PROP_OPEN_TIMEOUT_MSEC,
+        CAP_PROP_READ_TIMEOUT_MSEC,
+    },
 };
 use std::time::Duration;
 use tracing::{error, info, warn};
 
+/// Connecting gives up after this long
+const OPEN_TIMEOUT_MS: i32 = 10_000;
+/// A read waiting this long for a frame fails, so a camera that keeps the
+/// connection open but sends nothing cannot block the capture loop
+const READ_TIMEOUT_MS: i32 = 10_000;
+
+/// `VideoCapture` over FFmpeg with the open / read timeouts set.
+fn open_capture(url: &str) -> opencv::Result<VideoCapture> {
+    let params = Vector::<i32>::from_slice(&[
+        CAP_PROP_OPEN_TIMEOUT_MSEC,
+        OPEN_TIMEOUT_MS,
+        CAP_PROP_READ_TIMEOUT_MSEC,
+        READ_TIMEOUT_MS,
+    ]);
+    VideoCapture::from_file_with_params(url, CAP_FFMPEG, &params)
+}
+
 /// Opened RTSP stream.
 pub struct CaptureStream {
     cap: VideoCapture,
@@ -27,7 +47,7 @@ impl CaptureStream {
     pub fn open(url: &str, camera_id: &str, process_every: u32) -> Result<Self> {
         info!("Opening camera {} at {}", camera_id, url);
 
-        let mut cap = VideoCapture::from_file(url, CAP_FFMPEG)?;
+        let mut cap = open_capture(url)?;
 
         if !cap.is_opened()? {
             bail!("Failed to open RTSP stream: {}", url);
@@ -75,7 +95,7 @@ impl CaptureStream {
         let mut delay = Duration::from_secs(1);
         for attempt in 1..=10 {
             std::thread::sleep(delay);
-            match VideoCapture::from_file(&self.url, CAP_FFMPEG) {
+            match open_capture(&self.url) {
                 Ok(cap) if cap.is_opened().unwrap_or(false) => {
                     self.cap = cap;
                     self.cap.set(CAP_PROP_BUFFERSIZE as i32, 1.0).ok();
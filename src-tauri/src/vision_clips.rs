@@ -0,0 +1,218 @@
+//! Detection-driven RTSP clip capture.
+//!
+//! When a saved detection matches a `[[clips.rules]]` entry (label, confidence,
+//! local-time window, hourly quota), an ffmpeg stream-copy clip is recorded in
+//! the background, linked to the detection in the `clips` table, and announced
+//! via `broxeen:vision_clip_ready` (+ webhook).
+
+use chrono::Timelike;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::vision_config::{ClipRule, ClipsConfig};
+use crate::vision_db::VisionDatabase;
+use crate::vision_webhook::WebhookSink;
+
+const QUOTA_WINDOW: Duration = Duration::from_secs(3600);
+
+pub struct ClipRecorder {
+    rules: Vec<ClipRule>,
+    output_dir: PathBuf,
+    pre_roll: bool,
+    camera_id: String,
+    rtsp_url: String,
+    /// Trigger times per rule index, for `max_per_hour`
+    recent: Mutex<HashMap<usize, VecDeque<Instant>>>,
+    /// One recording at a time per camera — overlapping triggers are skipped
+    busy: Arc<AtomicBool>,
+}
+
+/// Detection that may trigger a clip.
+pub struct ClipTrigger<'a> {
+    pub detection_id: i64,
+    pub track_id: &'a str,
+    pub label: &'a str,
+    pub confidence: f32,
+}
+
+impl ClipRecorder {
+    /// Returns `None` when no clip rules are configured.
+    pub fn new(cfg: &ClipsConfig, camera_id: &str, rtsp_url: &str) -> Option<Self> {
+        if cfg.rules.is_empty() {
+            return None;
+        }
+        if cfg.pre_roll_secs > 0 {
+            crate::network_scan::enable_pre_roll(camera_id, rtsp_url, cfg.pre_roll_secs);
+        }
+        let output_dir = cfg.output_dir.as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| crate::network_scan::default_clip_dir(camera_id));
+        info!("Clip recorder: {} rule(s) for cam={} → {}", cfg.rules.len(), camera_id, output_dir.display());
+        Some(Self {
+            rules: cfg.rules.clone(),
+            output_dir,
+            pre_roll: cfg.pre_roll_secs > 0,
+            camera_id: camera_id.to_string(),
+            rtsp_url: rtsp_url.to_string(),
+            recent: Mutex::new(HashMap::new()),
+            busy: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Check rules and start a background recording if one matches.
+    /// Returns true when a recording was started.
+    pub fn maybe_record(
+        &self,
+        trigger: ClipTrigger<'_>,
+        db: Arc<VisionDatabase>,
+        app: Option<tauri::AppHandle>,
+        webhook: Option<Arc<WebhookSink>>,
+    ) -> bool {
+        let hour = chrono::Local::now().hour();
+        let Some(rule_idx) = matching_rule(&self.rules, trigger.label, trigger.confidence, hour) else {
+            return false;
+        };
+        let rule = &self.rules[rule_idx];
+
+        {
+            let mut recent = self.recent.lock().unwrap();
+            let times = recent.entry(rule_idx).or_default();
+            let now = Instant::now();
+            while times.front().map_or(false, |t| now.duration_since(*t) > QUOTA_WINDOW) {
+                times.pop_front();
+            }
+            if times.len() >= rule.max_per_hour as usize {
+                return false;
+            }
+            if self.busy.swap(true, Ordering::AcqRel) {
+                return false;
+            }
+            // A re-emitted detection for the same track must not double-fire.
+            // Claimed only now so a quota/busy rejection leaves the key free.
+            let key = crate::idempotency::detection_key(
+                &self.camera_id, trigger.track_id, &format!("{}#{}", rule.label, rule_idx),
+            );
+            if !crate::idempotency::store().claim("vision_clip", &key) {
+                self.busy.store(false, Ordering::Release);
+                return false;
+            }
+            times.push_back(now);
+        }
+
+        let busy = Arc::clone(&self.busy);
+        let url = self.rtsp_url.clone();
+        let camera_id = self.camera_id.clone();
+        let dir = self.output_dir.clone();
+        let duration = rule.duration_secs.max(1);
+        let pre_roll = if self.pre_roll {
+            crate::network_scan::pre_roll_frames(&camera_id, &url)
+        } else {
+            Vec::new()
+        };
+        let detection_id = trigger.detection_id;
+        let track_id = trigger.track_id.to_string();
+        let label = trigger.label.to_string();
+
+        tokio::spawn(async move {
+            let cam = camera_id.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                crate::network_scan::record_clip_blocking(&url, &cam, duration, &dir, &pre_roll)
+            }).await;
+            busy.store(false, Ordering::Release);
+
+            let clip = match result {
+                Ok(Ok(clip)) => clip,
+                Ok(Err(e)) => { warn!("Clip recording failed cam={}: {}", camera_id, e); return; }
+                Err(e) => { warn!("Clip task panicked cam={}: {}", camera_id, e); return; }
+            };
+
+            let clip_id = match db.insert_clip(
+                &camera_id, Some(detection_id), Some(&track_id), &label,
+                &clip.path, clip.preroll_path.as_deref(), clip.duration_secs, clip.size_bytes,
+            ) {
+                Ok(id) => id,
+                Err(e) => { warn!("DB insert_clip: {}", e); return; }
+            };
+            info!("🎬 Clip saved cam={} label={} → {}", camera_id, label, clip.path);
+
+            let payload = serde_json::json!({
+                "clip_id": clip_id,
+                "camera_id": camera_id,
+                "detection_id": detection_id,
+                "track_id": track_id,
+                "label": label,
+                "path": clip.path,
+                "preroll_path": clip.preroll_path,
+                "duration_s": clip.duration_secs,
+                "size_bytes": clip.size_bytes,
+            });
+            if let Some(hook) = webhook {
+                hook.notify_clip(payload.clone());
+            }
+            if let Some(app) = app {
+                use tauri::Emitter;
+                let _ = app.emit("broxeen:vision_clip_ready", payload);
+            }
+        });
+
+        true
+    }
+}
+
+/// Index of the first rule matching label, confidence and local hour.
+fn matching_rule(rules: &[ClipRule], label: &str, confidence: f32, hour: u32) -> Option<usize> {
+    rules.iter().position(|r| {
+        r.label.eq_ignore_ascii_case(label)
+            && confidence >= r.min_confidence
+            && in_hour_window(hour, r.start_hour, r.end_hour)
+    })
+}
+
+/// `[start, end)` in local hours; wraps midnight when start > end.
+fn in_hour_window(hour: u32, start: Option<u32>, end: Option<u32>) -> bool {
+    match (start, end) {
+        (Some(s), Some(e)) if s <= e => hour >= s && hour < e,
+        (Some(s), Some(e)) => hour >= s || hour < e,
+        (Some(s), None) => hour >= s,
+        (None, Some(e)) => hour < e,
+        (None, None) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(label: &str, start: Option<u32>, end: Option<u32>) -> ClipRule {
+        ClipRule {
+            label: label.into(),
+            min_confidence: 0.6,
+            start_hour: start,
+            end_hour: end,
+            duration_secs: 20,
+            max_per_hour: 4,
+        }
+    }
+
+    #[test]
+    fn hour_window_wraps_midnight() {
+        assert!(in_hour_window(23, Some(22), Some(6)));
+        assert!(in_hour_window(3, Some(22), Some(6)));
+        assert!(!in_hour_window(12, Some(22), Some(6)));
+        assert!(in_hour_window(9, Some(8), Some(17)));
+        assert!(!in_hour_window(17, Some(8), Some(17)));
+    }
+
+    #[test]
+    fn rule_matching_checks_label_and_confidence() {
+        let rules = vec![rule("person", Some(22), Some(6)), rule("car", None, None)];
+        assert_eq!(matching_rule(&rules, "person", 0.9, 23), Some(0));
+        assert_eq!(matching_rule(&rules, "person", 0.5, 23), None);
+        assert_eq!(matching_rule(&rules, "person", 0.9, 12), None);
+        assert_eq!(matching_rule(&rules, "Car", 0.7, 12), Some(1));
+    }
+}
@@ -10,8 +10,12 @@
 ///   - DetectorConfig: model_path now default yolov8s, input_size 640, 20 classes
 
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::env;
 
+use crate::image_meta::ThumbnailFormat;
+use crate::vision_detector::{DetectorDevice, ObjectClass};
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct VisionConfig {
     pub camera: CameraConfig,
@@ -27,6 +31,13 @@ pub struct VisionConfig {
     pub database: DatabaseConfig,
     #[serde(default)]
     pub llm: LlmConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub clips: ClipsConfig,
+    /// Polygon include/exclude zones (see `vision_zones`)
+    #[serde(default)]
+    pub zones: Vec<crate::vision_zones::Zone>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -35,11 +46,64 @@ pub struct CameraConfig {
     #[serde(default = "default_camera_id")]
     pub camera_id: String,
     pub fps: Option<f64>,
+    /// Optional GPS coordinates — resolved to `{camera_location}` via reverse geocoding
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Manual location label; takes precedence over geocoding
+    pub location: Option<String>,
+    /// Frame pixels per metre on the ground — enables `speed_mps`
+    pub pixels_per_meter: Option<f32>,
+    /// Alternative to `pixels_per_meter`: two frame points a known distance apart
+    pub speed_reference: Option<SpeedReference>,
+    /// Upper bounds (m/s) of stationary / slow / moderate; anything faster is "fast"
+    #[serde(default = "default_speed_cutoffs_mps")]
+    pub speed_cutoffs_mps: [f32; 3],
+}
+
+/// Two pixel positions in the captured frame `meters` apart on the ground,
+/// e.g. both ends of a 5 m driveway.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpeedReference {
+    pub from: [f32; 2],
+    pub to: [f32; 2],
+    pub meters: f32,
 }
 
 fn default_camera_id() -> String {
     "cam0".to_string()
 }
+fn default_speed_cutoffs_mps() -> [f32; 3] {
+    [0.2, 1.0, 2.5]
+}
+
+impl CameraConfig {
+    /// Pixels per metre from `pixels_per_meter` or `speed_reference`.
+    pub fn calibrated_pixels_per_meter(&self) -> Option<f32> {
+        self.pixels_per_meter.or_else(|| {
+            let r = self.speed_reference.as_ref()?;
+            let (dx, dy) = (r.to[0] - r.from[0], r.to[1] - r.from[1]);
+            Some((dx * dx + dy * dy).sqrt() / r.meters)
+        })
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(r) = &self.speed_reference {
+            if r.meters <= 0.0 || r.from == r.to {
+                return Err("camera.speed_reference needs two distinct points and meters > 0".into());
+            }
+        }
+        if let Some(ppm) = self.calibrated_pixels_per_meter() {
+            if !ppm.is_finite() || ppm <= 0.0 {
+                return Err(format!("camera.pixels_per_meter must be > 0, got {}", ppm));
+            }
+        }
+        let c = self.speed_cutoffs_mps;
+        if !(0.0 <= c[0] && c[0] < c[1] && c[1] < c[2]) {
+            return Err(format!("camera.speed_cutoffs_mps must be increasing, got {:?}", c));
+        }
+        Ok(())
+    }
+}
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct DetectorConfig {
@@ -53,8 +117,18 @@ pub struct DetectorConfig {
     pub input_size: u32,
     #[serde(default = "default_use_openvino")]
     pub use_openvino: bool,
+    /// Inference device: "cpu", "gpu" (OpenVINO GPU, CPU if it fails) or
+    /// "auto" (OpenVINO GPU, then OpenVINO CPU, then plain CPU)
+    #[serde(default)]
+    pub device: DetectorDevice,
     #[serde(default = "default_intra_threads")]
     pub intra_threads: u16,
+    /// Per-label overrides of `confidence_threshold`, e.g. { person = 0.35, bird = 0.7 }
+    #[serde(default)]
+    pub class_thresholds: HashMap<String, f32>,
+    /// Only keep these labels (empty = all); others never reach the DB or the LLM
+    #[serde(default)]
+    pub enabled_classes: Vec<String>,
 }
 
 fn default_model_path() -> String {
@@ -84,9 +158,70 @@ impl Default for DetectorConfig {
             nms_threshold: default_nms_threshold(),
             input_size: default_input_size(),
             use_openvino: default_use_openvino(),
+            device: DetectorDevice::default(),
             intra_threads: default_intra_threads(),
+            class_thresholds: HashMap::new(),
+            enabled_classes: Vec::new(),
+        }
+    }
+}
+
+impl DetectorConfig {
+    /// `device`, with `use_openvino = false` keeping "auto" on the CPU.
+    pub fn effective_device(&self) -> DetectorDevice {
+        match self.device {
+            DetectorDevice::Auto if !self.use_openvino => DetectorDevice::Cpu,
+            device => device,
         }
     }
+
+    /// Threshold for `label`: its `class_thresholds` entry or the global one.
+    pub fn threshold_for(&self, label: &str) -> f32 {
+        self.class_thresholds
+            .iter()
+            .find(|(k, _)| k.trim().eq_ignore_ascii_case(label))
+            .map(|(_, t)| *t)
+            .unwrap_or(self.confidence_threshold)
+    }
+
+    /// Lowest threshold of any class — the cut applied inside the detector,
+    /// so that lowered per-class thresholds still see their candidates.
+    pub fn min_threshold(&self) -> f32 {
+        self.class_thresholds
+            .values()
+            .copied()
+            .fold(self.confidence_threshold, f32::min)
+    }
+
+    /// Whether a detection passes `enabled_classes` and its class threshold.
+    pub fn accepts(&self, label: &str, confidence: f32) -> bool {
+        let enabled = self.enabled_classes.is_empty()
+            || self.enabled_classes.iter().any(|c| c.trim().eq_ignore_ascii_case(label));
+        enabled && confidence > self.threshold_for(label)
+    }
+
+    /// Reject labels the detector never reports and thresholds outside 0..=1.
+    pub fn validate(&self) -> Result<(), String> {
+        let valid = || {
+            ObjectClass::ALL.iter().map(|c| c.as_str()).collect::<Vec<_>>().join(", ")
+        };
+        let labels = self.class_thresholds.keys().chain(self.enabled_classes.iter());
+        for label in labels {
+            if ObjectClass::from_label(label).is_none() {
+                return Err(format!(
+                    "detector: unknown class \"{}\" — valid labels: {}",
+                    label, valid()
+                ));
+            }
+        }
+        let thresholds = self.class_thresholds.iter().map(|(k, v)| (k.as_str(), *v));
+        for (label, t) in std::iter::once(("confidence_threshold", self.confidence_threshold)).chain(thresholds) {
+            if !(0.0..=1.0).contains(&t) {
+                return Err(format!("detector: threshold for {} must be between 0 and 1, got {}", label, t));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -99,6 +234,24 @@ pub struct PipelineConfig {
     pub bg_var_threshold: f64,
     #[serde(default = "default_min_activity_area")]
     pub min_activity_area: f64,
+    /// MOG2 variance threshold used at night (IR noise needs a higher value)
+    #[serde(default = "default_night_var_threshold")]
+    pub night_var_threshold: f64,
+    /// Minimum contour area at night, in px² of the 640px-wide frame
+    #[serde(default = "default_night_min_contour_area")]
+    pub night_min_contour_area: f64,
+    /// Mean frame luminance (0–255) below which the camera counts as "night"
+    #[serde(default = "default_night_luminance")]
+    pub night_luminance: f64,
+    /// Luminance must rise this much above `night_luminance` to switch back to day
+    #[serde(default = "default_luminance_hysteresis")]
+    pub luminance_hysteresis: f64,
+    /// Always use night parameters (set by `night_mode` in motion_pipeline_start)
+    #[serde(default)]
+    pub force_night: bool,
+    /// Seconds without a frame before the watchdog forces a reconnect
+    #[serde(default = "default_stall_timeout_secs")]
+    pub stall_timeout_secs: u64,
 }
 
 fn default_process_every() -> u32 {
@@ -113,6 +266,21 @@ fn default_bg_var_threshold() -> f64 {
 fn default_min_activity_area() -> f64 {
     1500.0
 }
+fn default_night_var_threshold() -> f64 {
+    80.0
+}
+fn default_night_min_contour_area() -> f64 {
+    4000.0
+}
+fn default_night_luminance() -> f64 {
+    50.0
+}
+fn default_luminance_hysteresis() -> f64 {
+    15.0
+}
+fn default_stall_timeout_secs() -> u64 {
+    120
+}
 
 impl Default for PipelineConfig {
     fn default() -> Self {
@@ -121,6 +289,12 @@ impl Default for PipelineConfig {
             bg_history: default_bg_history(),
             bg_var_threshold: default_bg_var_threshold(),
             min_activity_area: default_min_activity_area(),
+            night_var_threshold: default_night_var_threshold(),
+            night_min_contour_area: default_night_min_contour_area(),
+            night_luminance: default_night_luminance(),
+            luminance_hysteresis: default_luminance_hysteresis(),
+            force_night: false,
+            stall_timeout_secs: default_stall_timeout_secs(),
         }
     }
 }
@@ -181,6 +355,13 @@ pub struct SceneConfig {
     /// Max crops to send per LLM call
     #[serde(default = "default_max_crops_per_batch")]
     pub max_crops_per_batch: usize,
+    /// Narrative prompt template. Placeholders: {timeline}, {camera_id}, {camera_location}
+    #[serde(default = "default_narrative_template")]
+    pub narrative_template: String,
+    /// Cap (MB of crops per camera) on buffered events kept in the DB until
+    /// the flush, so a restart can recover them; 0 = no spill
+    #[serde(default = "default_spill_max_mb")]
+    pub spill_max_mb: u64,
 }
 
 fn default_flush_interval_secs() -> u64 {
@@ -195,6 +376,19 @@ fn default_ring_capacity() -> usize {
 fn default_max_crops_per_batch() -> usize {
     10
 }
+fn default_spill_max_mb() -> u64 {
+    50
+}
+fn default_narrative_template() -> String {
+    "{timeline}\n\nThese images show detected objects from camera '{camera_id}' \
+     (location: {camera_location}) during this period.\n\
+     Write a concise 2-4 sentence summary of what happened:\n\
+     - Which objects appeared and when\n\
+     - Their movement (direction, behaviour)\n\
+     - Any notable patterns\n\
+     Be specific with times. Past tense. No lists."
+        .to_string()
+}
 
 impl Default for SceneConfig {
     fn default() -> Self {
@@ -203,6 +397,8 @@ impl Default for SceneConfig {
             min_crops_for_llm: default_min_crops_for_llm(),
             ring_capacity: default_ring_capacity(),
             max_crops_per_batch: default_max_crops_per_batch(),
+            narrative_template: default_narrative_template(),
+            spill_max_mb: default_spill_max_mb(),
         }
     }
 }
@@ -211,16 +407,39 @@ impl Default for SceneConfig {
 pub struct DatabaseConfig {
     #[serde(default = "default_db_path")]
     pub path: String,
+    /// Encoder quality 1–100 for stored detection thumbnails
+    #[serde(default = "default_thumbnail_quality")]
+    pub thumbnail_quality: u8,
+    /// "jpeg" or "webp"
+    #[serde(default)]
+    pub thumbnail_format: ThumbnailFormat,
+    /// Longest thumbnail edge; crops are resized before encoding
+    #[serde(default = "default_thumbnail_max_px")]
+    pub thumbnail_max_px: u32,
+    /// IANA zone (e.g. "Europe/Warsaw") for local_date/local_hour and day
+    /// filters; empty = system zone (see local_time.rs)
+    #[serde(default)]
+    pub timezone: String,
 }
 
 fn default_db_path() -> String {
     "monitoring.db".to_string()
 }
+fn default_thumbnail_quality() -> u8 {
+    75
+}
+fn default_thumbnail_max_px() -> u32 {
+    400
+}
 
 impl Default for DatabaseConfig {
     fn default() -> Self {
         Self {
             path: default_db_path(),
+            thumbnail_quality: default_thumbnail_quality(),
+            thumbnail_format: ThumbnailFormat::default(),
+            thumbnail_max_px: default_thumbnail_max_px(),
+            timezone: String::new(),
         }
     }
 }
@@ -248,6 +467,16 @@ pub struct LlmConfig {
     /// Max tokens for scene narrative
     #[serde(default = "default_max_narrative_tokens")]
     pub max_narrative_tokens: u32,
+
+    // ── Budget ───────────────────────────────────────────────────────────
+    /// Send the scene batch early once this many tracks are waiting
+    /// (otherwise every `scene.flush_interval_secs`)
+    #[serde(default = "default_batch_max_items")]
+    pub batch_max_items: usize,
+    /// LLM requests per minute across all pipelines; batches over the limit
+    /// are marked `llm_skipped` on their detections (0 = unlimited)
+    #[serde(default = "default_max_requests_per_minute")]
+    pub max_requests_per_minute: u32,
 }
 
 fn default_openrouter_model() -> String {
@@ -274,6 +503,12 @@ fn default_max_tokens() -> u32 {
 fn default_max_narrative_tokens() -> u32 {
     400
 }
+fn default_batch_max_items() -> usize {
+    20
+}
+fn default_max_requests_per_minute() -> u32 {
+    6
+}
 
 impl Default for LlmConfig {
     fn default() -> Self {
@@ -284,6 +519,79 @@ impl Default for LlmConfig {
             local_model: default_local_model(),
             max_tokens: default_max_tokens(),
             max_narrative_tokens: default_max_narrative_tokens(),
+            batch_max_items: default_batch_max_items(),
+            max_requests_per_minute: default_max_requests_per_minute(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NotificationsConfig {
+    /// POST target for detection / narrative events (disabled when unset)
+    pub webhook_url: Option<String>,
+    /// Extra HTTP headers sent with every webhook (e.g. Authorization)
+    #[serde(default)]
+    pub webhook_headers: HashMap<String, String>,
+    /// Shared secret — payload signed as `X-Broxeen-Signature: sha256=<hex hmac>`
+    pub webhook_secret: Option<String>,
+    /// Skip detections below this confidence (narratives are always sent)
+    #[serde(default)]
+    pub min_confidence: f32,
+    /// Only notify for these labels (empty = all)
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClipsConfig {
+    /// Detection rules that trigger an automatic RTSP clip (empty = disabled)
+    #[serde(default)]
+    pub rules: Vec<ClipRule>,
+    /// Clip directory (default: <data_local_dir>/broxeen/clips/<camera_id>)
+    pub output_dir: Option<String>,
+    /// Seconds of 1 fps pre-roll kept in memory and saved next to each clip (0 = off)
+    #[serde(default)]
+    pub pre_roll_secs: u32,
+    /// Delete clips older than N days (0 = keep forever)
+    #[serde(default = "default_clip_retention_days")]
+    pub retention_days: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClipRule {
+    /// YOLO label, e.g. "person"
+    pub label: String,
+    #[serde(default = "default_clip_min_confidence")]
+    pub min_confidence: f32,
+    /// Local-time window [start_hour, end_hour); wraps midnight when start > end
+    pub start_hour: Option<u32>,
+    pub end_hour: Option<u32>,
+    #[serde(default = "default_clip_duration_secs")]
+    pub duration_secs: u32,
+    #[serde(default = "default_clip_max_per_hour")]
+    pub max_per_hour: u32,
+}
+
+fn default_clip_retention_days() -> u32 {
+    7
+}
+fn default_clip_min_confidence() -> f32 {
+    0.5
+}
+fn default_clip_duration_secs() -> u32 {
+    20
+}
+fn default_clip_max_per_hour() -> u32 {
+    4
+}
+
+impl Default for ClipsConfig {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            output_dir: None,
+            pre_roll_secs: 0,
+            retention_days: default_clip_retention_days(),
         }
     }
 }
@@ -305,6 +613,10 @@ pub fn load_config() -> Result<VisionConfig, config::ConfigError> {
 
     let settings = builder.build()?;
     let mut cfg = settings.try_deserialize::<VisionConfig>()?;
+    crate::vision_zones::validate_zones(&cfg.zones).map_err(config::ConfigError::Message)?;
+    cfg.detector.validate().map_err(config::ConfigError::Message)?;
+    cfg.camera.validate().map_err(config::ConfigError::Message)?;
+    crate::local_time::parse(&cfg.database.timezone).map_err(config::ConfigError::Message)?;
 
     // Convenience: OPENROUTER_API_KEY env var (without BROXEEN__ prefix)
     if cfg.llm.openrouter_api_key.is_none() {
@@ -324,6 +636,12 @@ pub fn default_config() -> VisionConfig {
             url: String::new(),
             camera_id: "cam0".into(),
             fps: None,
+            latitude: None,
+            longitude: None,
+            location: None,
+            pixels_per_meter: None,
+            speed_reference: None,
+            speed_cutoffs_mps: default_speed_cutoffs_mps(),
         },
         detector: DetectorConfig::default(),
         pipeline: PipelineConfig::default(),
@@ -331,5 +649,57 @@ pub fn default_config() -> VisionConfig {
         scene: SceneConfig::default(),
         database: DatabaseConfig::default(),
         llm: LlmConfig::default(),
+        notifications: NotificationsConfig::default(),
+        clips: ClipsConfig::default(),
+        zones: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detector_class_filters() {
+        let mut det = DetectorConfig::default();
+        det.class_thresholds.insert("person".into(), 0.35);
+        det.class_thresholds.insert("Bird".into(), 0.7);
+        assert!(det.validate().is_ok());
+        assert_eq!(det.min_threshold(), 0.35);
+
+        assert!(det.accepts("person", 0.4));
+        assert!(!det.accepts("bird", 0.6));
+        assert!(det.accepts("car", 0.6));
+        assert!(!det.accepts("car", 0.4));
+
+        det.enabled_classes = vec!["person".into(), "cell phone".into()];
+        assert!(!det.accepts("chair", 0.99));
+        assert!(det.accepts("cell phone", 0.9));
+
+        det.enabled_classes.push("sofa".into());
+        let err = det.validate().unwrap_err();
+        assert!(err.contains("\"sofa\"") && err.contains("person, car"), "{}", err);
+
+        det.enabled_classes.pop();
+        det.class_thresholds.insert("dog".into(), 1.5);
+        assert!(det.validate().unwrap_err().contains("dog"));
+    }
+
+    #[test]
+    fn camera_speed_calibration() {
+        let mut cam = default_config().camera;
+        assert_eq!(cam.calibrated_pixels_per_meter(), None);
+        assert!(cam.validate().is_ok());
+
+        cam.speed_reference = Some(SpeedReference { from: [100.0, 400.0], to: [400.0, 800.0], meters: 5.0 });
+        assert_eq!(cam.calibrated_pixels_per_meter(), Some(100.0));
+        cam.pixels_per_meter = Some(80.0);
+        assert_eq!(cam.calibrated_pixels_per_meter(), Some(80.0));
+
+        cam.speed_cutoffs_mps = [0.2, 3.0, 2.5];
+        assert!(cam.validate().is_err());
+        cam.speed_cutoffs_mps = default_speed_cutoffs_mps();
+        cam.speed_reference.as_mut().unwrap().meters = 0.0;
+        assert!(cam.validate().is_err());
     }
 }
@@ -0,0 +1,180 @@
+//! vision_contact_sheet.rs — One JPEG grid of detection thumbnails.
+//!
+//! Tiles are shrunk as they are added, so a sheet costs at most `max` small
+//! tiles of memory however many thumbnails are streamed past it. Each tile
+//! carries its timestamp, drawn with a built-in 3×5 pixel font (digits and
+//! `-:.` are all a timestamp needs), so no font file is required.
+
+use anyhow::{anyhow, Result};
+use image::{imageops, Rgb, RgbImage};
+use std::path::Path;
+
+const TILE_W: u32 = 240;
+const TILE_H: u32 = 180;
+const CAPTION_H: u32 = 16;
+const GAP: u32 = 4;
+/// Glyph pixel size on the sheet
+const FONT_SCALE: u32 = 2;
+const JPEG_QUALITY: u8 = 85;
+const BACKGROUND: Rgb<u8> = Rgb([24, 24, 24]);
+const CAPTION_COLOR: Rgb<u8> = Rgb([235, 235, 235]);
+
+/// Rows of a 3×5 glyph, top first; bit 2 is the left column.
+fn glyph(c: char) -> [u8; 5] {
+    match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        _ => [0; 5],
+    }
+}
+
+fn draw_text(img: &mut RgbImage, x: u32, y: u32, text: &str) {
+    let advance = 4 * FONT_SCALE;
+    for (i, c) in text.chars().enumerate() {
+        let gx = x + i as u32 * advance;
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..3u32 {
+                if bits & (0b100 >> col) == 0 {
+                    continue;
+                }
+                for dy in 0..FONT_SCALE {
+                    for dx in 0..FONT_SCALE {
+                        let (px, py) = (gx + col * FONT_SCALE + dx, y + row as u32 * FONT_SCALE + dy);
+                        if px < img.width() && py < img.height() {
+                            img.put_pixel(px, py, CAPTION_COLOR);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `(columns, rows)` of a near-square grid for `n` tiles.
+fn grid(n: usize) -> (u32, u32) {
+    let cols = (n as f64).sqrt().ceil().max(1.0) as u32;
+    (cols, (n as u32).div_ceil(cols))
+}
+
+pub struct ContactSheet {
+    max: usize,
+    tiles: Vec<(RgbImage, String)>,
+}
+
+impl ContactSheet {
+    pub fn new(max: usize) -> Self {
+        Self { max, tiles: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.tiles.len() >= self.max
+    }
+
+    /// Decode and shrink `thumbnail`. Returns false when the sheet is full or
+    /// the image cannot be decoded.
+    pub fn add(&mut self, thumbnail: &[u8], caption: &str) -> bool {
+        if self.is_full() {
+            return false;
+        }
+        match image::load_from_memory(thumbnail) {
+            Ok(img) => {
+                self.tiles.push((img.thumbnail(TILE_W, TILE_H).to_rgb8(), caption.to_string()));
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    pub fn render(&self) -> RgbImage {
+        let (cols, rows) = grid(self.tiles.len());
+        let cell_h = TILE_H + CAPTION_H;
+        let mut sheet = RgbImage::from_pixel(
+            GAP + cols * (TILE_W + GAP),
+            GAP + rows * (cell_h + GAP),
+            BACKGROUND,
+        );
+        for (i, (tile, caption)) in self.tiles.iter().enumerate() {
+            let (col, row) = (i as u32 % cols, i as u32 / cols);
+            let (x, y) = (GAP + col * (TILE_W + GAP), GAP + row * (cell_h + GAP));
+            // Centred in its cell; thumbnail() keeps the aspect ratio
+            let (dx, dy) = ((TILE_W - tile.width()) / 2, (TILE_H - tile.height()) / 2);
+            imageops::replace(&mut sheet, tile, (x + dx) as i64, (y + dy) as i64);
+            draw_text(&mut sheet, x + 2, y + TILE_H + (CAPTION_H - 5 * FONT_SCALE) / 2, caption);
+        }
+        sheet
+    }
+
+    /// Write the sheet as JPEG; fails when no tile was added.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if self.is_empty() {
+            return Err(anyhow!("contact sheet has no thumbnails"));
+        }
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.render()
+            .write_with_encoder(image::codecs::jpeg::JpegEncoder::new_with_quality(file, JPEG_QUALITY))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jpeg(w: u32, h: u32) -> Vec<u8> {
+        let mut out = std::io::Cursor::new(Vec::new());
+        RgbImage::from_pixel(w, h, Rgb([200, 30, 30]))
+            .write_to(&mut out, image::ImageFormat::Jpeg)
+            .unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn test_sheet_tiles_in_a_grid_with_captions() {
+        assert_eq!(grid(1), (1, 1));
+        assert_eq!(grid(5), (3, 2));
+        assert_eq!(grid(9), (3, 3));
+
+        let mut sheet = ContactSheet::new(5);
+        for _ in 0..7 {
+            sheet.add(&jpeg(480, 270), "2024-06-12 22:14:05");
+        }
+        assert!(!sheet.add(b"not an image", "x"));
+        assert_eq!(sheet.len(), 5);
+
+        let img = sheet.render();
+        assert_eq!(img.dimensions(), (GAP + 3 * (TILE_W + GAP), GAP + 2 * (TILE_H + CAPTION_H + GAP)));
+        // 480×270 shrinks to 240×135, centred vertically in the first cell
+        assert_eq!(img.get_pixel(GAP + 10, GAP + 2), &BACKGROUND);
+        assert!(img.get_pixel(GAP + 10, GAP + TILE_H / 2)[0] > 150);
+        // Caption pixels below the first tile, none in the empty last cell
+        let has_caption = |col: u32, row: u32| {
+            let (x, y) = (GAP + col * (TILE_W + GAP), GAP + row * (TILE_H + CAPTION_H + GAP) + TILE_H);
+            (y..y + CAPTION_H).any(|y| (x..x + TILE_W).any(|x| img.get_pixel(x, y) == &CAPTION_COLOR))
+        };
+        assert!(has_caption(0, 0));
+        assert!(has_caption(1, 1));
+        assert!(!has_caption(2, 1));
+    }
+}
@@ -4,12 +4,46 @@
 //! Track B — LLM:    Every minute batch  → `llm_events` table (confirmed descriptions)
 //!
 //! Combined view → `monitoring_history` (queryable via text-to-SQL)
+//! Detection-triggered RTSP clips → `clips` (linked to detections/tracks)
+//! Events waiting for the LLM flush → `scene_buffer` + `scene_buffer_crops`
+//! (internal, not part of [`SCHEMA`]; emptied once the batch is flushed)
+//!
+//! Concurrency: inserts and updates go through a channel to one writer
+//! thread that owns the only write connection, so detection bursts and LLM
+//! updates queue up instead of fighting over SQLite's write lock. Reads
+//! (statistics, text-to-SQL, thumbnails) borrow one of `READ_POOL_SIZE`
+//! `query_only` connections and run concurrently with the writer thanks to
+//! WAL. Every connection waits up to `BUSY_TIMEOUT` instead of failing with
+//! SQLITE_BUSY. Pipelines writing the same file share one instance
+//! ([`VisionDatabase::shared`]).
+//!
+//! Bulk thumbnail export ([`VisionDatabase::export_thumbnails`]) streams one
+//! row at a time into files, optionally tiling the first ones into a contact
+//! sheet.
+//!
+//! Heatmaps ([`VisionDatabase::heatmap`]) bin the stored track centres
+//! (`center_x` / `center_y`) into a grid in SQL; a covering index keeps that
+//! off the table pages, so thumbnail BLOBs are never read.
+//!
+//! Time zone: `timestamp` is UTC; `local_date` / `local_hour` are computed in
+//! the zone set with [`VisionDatabase::set_timezone`] (`database.timezone`),
+//! which is recorded per row (`timezone`) and in `db_meta` for query code.
 
-use anyhow::Result;
-use chrono::{DateTime, Local, Utc};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Timelike, Utc};
+use chrono_tz::Tz;
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::{mpsc, Arc, Condvar, Mutex, RwLock, Weak};
+use std::time::Duration;
+
+use crate::image_meta::ThumbnailFormat;
+use crate::vision_contact_sheet::ContactSheet;
+use crate::vision_movement::MovementSummary;
+use crate::vision_scene_buffer::ObjectEvent;
+use crate::vision_tracker::CropSnapshot;
 
 // ─── Structs ──────────────────────────────────────────────────────────────────
 
@@ -27,6 +61,7 @@ pub struct LocalDetection {
     pub exit_zone:   Option<String>,
     pub direction:   Option<String>,
     pub speed_label: Option<String>,
+    pub lighting:    Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,6 +77,20 @@ pub struct LlmEvent {
     pub context:      String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipRecord {
+    pub id:            i64,
+    pub timestamp:     DateTime<Utc>,
+    pub camera_id:     String,
+    pub detection_id:  Option<i64>,
+    pub track_id:      Option<String>,
+    pub label:         String,
+    pub path:          String,
+    pub preroll_path:  Option<String>,
+    pub duration_s:    u32,
+    pub size_bytes:    u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Statistics {
     pub period_hours:     u32,
@@ -52,10 +101,141 @@ pub struct Statistics {
     pub total_detections: u64,
 }
 
+/// Where in the frame detections happened, from [`VisionDatabase::heatmap`].
+#[derive(Debug, Clone, Serialize)]
+pub struct Heatmap {
+    pub camera_id:        String,
+    pub label:            Option<String>,
+    pub period_hours:     u32,
+    pub grid_w:           u32,
+    pub grid_h:           u32,
+    /// `grid_h` rows of `grid_w` cells, top-left first, scaled so the
+    /// busiest cell is 1.0
+    pub cells:            Vec<Vec<f32>>,
+    pub max_count:        u64,
+    /// Detections binned into `cells`
+    pub total_detections: u64,
+    /// Detections in the period without a stored centre (older rows)
+    pub unpositioned:     u64,
+    /// Centres are fractions of the frame, so the grid spans the whole
+    /// frame at any resolution: "normalized", x from the left, y from the top
+    pub coordinates:      &'static str,
+}
+
 // ─── Database ─────────────────────────────────────────────────────────────────
 
+const READ_POOL_SIZE: usize = 4;
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+type WriteJob = Box<dyn FnOnce(&Connection) + Send>;
+
+/// Open databases by resolved path, so pipelines sharing a file share the writer.
+static OPEN: Mutex<Option<HashMap<String, Weak<VisionDatabase>>>> = Mutex::new(None);
+
+/// Filters of [`VisionDatabase::export_thumbnails`]; `None` matches everything.
+#[derive(Debug, Clone, Default)]
+pub struct ThumbnailFilter {
+    pub camera_id:      Option<String>,
+    pub label:          Option<String>,
+    pub since:          Option<DateTime<Utc>>,
+    /// Exclusive
+    pub until:          Option<DateTime<Utc>>,
+    pub min_confidence: Option<f32>,
+}
+
+impl ThumbnailFilter {
+    fn where_clause(&self) -> (String, Vec<rusqlite::types::Value>) {
+        use rusqlite::types::Value;
+        let mut conditions = vec!["length(thumbnail) > 0".to_string()];
+        let mut values = Vec::new();
+        let mut push = |condition: &str, value: Value| {
+            values.push(value);
+            conditions.push(format!("{condition} ?{}", values.len()));
+        };
+        if let Some(cam) = &self.camera_id {
+            push("camera_id =", Value::Text(cam.clone()));
+        }
+        if let Some(label) = &self.label {
+            push("label =", Value::Text(label.trim().to_lowercase()));
+        }
+        if let Some(since) = self.since {
+            push("datetime(timestamp) >=", Value::Text(crate::local_time::sqlite_utc(since)));
+        }
+        if let Some(until) = self.until {
+            push("datetime(timestamp) <", Value::Text(crate::local_time::sqlite_utc(until)));
+        }
+        if let Some(min) = self.min_confidence {
+            push("confidence >=", Value::Real(min as f64));
+        }
+        (conditions.join(" AND "), values)
+    }
+}
+
+/// Result of [`VisionDatabase::export_thumbnails`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ThumbnailExport {
+    pub output_dir:    String,
+    pub exported:      u64,
+    pub bytes:         u64,
+    /// Written when a sheet was requested and at least one thumbnail decoded
+    pub contact_sheet: Option<String>,
+    pub sheet_tiles:   usize,
+}
+
+/// `20240612-221405_person_0.87` — local time of `at`, file-safe label.
+fn thumbnail_file_stem(at: &DateTime<Tz>, label: &str, confidence: f64) -> String {
+    let label: String = label
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    format!("{}_{}_{:.2}", at.format("%Y%m%d-%H%M%S"), label, confidence)
+}
+
 pub struct VisionDatabase {
-    conn: Connection,
+    writer: mpsc::Sender<WriteJob>,
+    readers: ReadPool,
+    /// Zone of `local_date` / `local_hour` for new rows
+    timezone: RwLock<Tz>,
+}
+
+/// Fixed set of read-only connections handed out one caller at a time.
+struct ReadPool {
+    idle: Mutex<Vec<Connection>>,
+    available: Condvar,
+}
+
+/// Returns the connection to the pool even if the reader panics.
+struct PooledConn<'a> {
+    pool: &'a ReadPool,
+    conn: Option<Connection>,
+}
+
+impl ReadPool {
+    fn get(&self) -> PooledConn<'_> {
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if let Some(conn) = idle.pop() {
+                return PooledConn { pool: self, conn: Some(conn) };
+            }
+            idle = self.available.wait(idle).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+}
+
+impl std::ops::Deref for PooledConn<'_> {
+    type Target = Connection;
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("pooled connection present until drop")
+    }
+}
+
+impl Drop for PooledConn<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool.idle.lock().unwrap_or_else(|e| e.into_inner()).push(conn);
+            self.pool.available.notify_one();
+        }
+    }
 }
 
 /// The SQL schema — exposed for text-to-SQL context.
@@ -64,8 +244,9 @@ pub const SCHEMA: &str = r#"
 CREATE TABLE detections (
     id          INTEGER PRIMARY KEY AUTOINCREMENT,
     timestamp   TEXT NOT NULL,          -- ISO8601 UTC
-    local_date  TEXT NOT NULL,          -- date('now') for daily grouping
-    local_hour  INTEGER NOT NULL,       -- 0..23 local hour
+    local_date  TEXT NOT NULL,          -- YYYY-MM-DD in the DB time zone, for daily grouping
+    local_hour  INTEGER NOT NULL,       -- 0..23 hour in the DB time zone
+    timezone    TEXT,                   -- IANA zone of local_date/local_hour, e.g. "Europe/Warsaw"
     camera_id   TEXT NOT NULL,          -- e.g. "front-door"
     track_id    TEXT NOT NULL,          -- UUID per tracked object
     label       TEXT NOT NULL,          -- person/car/truck/bus/motorcycle/bicycle/...
@@ -73,10 +254,16 @@ CREATE TABLE detections (
     movement    TEXT,                   -- e.g. "moving right, centre→right, 2.3s"
     direction   TEXT,                   -- left/right/up/down/upper-right/...
     speed_label TEXT,                   -- slow/moderate/fast/stationary
+    speed_mps   REAL,                   -- ground speed in m/s (NULL without camera calibration)
     entry_zone  TEXT,                   -- upper-left/top/centre/...
     exit_zone   TEXT,
     duration_s  REAL NOT NULL DEFAULT 0,
-    thumbnail   BLOB NOT NULL           -- JPEG ≤400px
+    thumbnail   BLOB NOT NULL,          -- JPEG or WebP, ≤400px by default
+    lighting    TEXT,                   -- "day" / "night" (luminance-based, IR at night)
+    llm_status  TEXT,                   -- "described" / "llm_skipped" (LLM rate limit) / NULL
+    llm_event_id INTEGER,               -- llm_events.id describing this detection
+    center_x    REAL,                   -- mean bbox centre over the track, 0.0 (left)..1.0
+    center_y    REAL                    -- 0.0 (top)..1.0; NULL for older rows
 );
 
 -- TABLE: llm_events  (LLM-confirmed scene descriptions, ~1 per minute)
@@ -84,6 +271,7 @@ CREATE TABLE llm_events (
     id           INTEGER PRIMARY KEY AUTOINCREMENT,
     timestamp    TEXT NOT NULL,
     local_date   TEXT NOT NULL,
+    timezone     TEXT,
     camera_id    TEXT NOT NULL,
     period_start TEXT NOT NULL,
     period_end   TEXT NOT NULL,
@@ -93,6 +281,26 @@ CREATE TABLE llm_events (
     context      TEXT NOT NULL          -- timeline sent to LLM
 );
 
+-- TABLE: clips  (RTSP video clips recorded when a clip rule matched a detection)
+CREATE TABLE clips (
+    id           INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp    TEXT NOT NULL,
+    camera_id    TEXT NOT NULL,
+    detection_id INTEGER,               -- detections.id that triggered the clip
+    track_id     TEXT,
+    label        TEXT NOT NULL,
+    path         TEXT NOT NULL,         -- MP4 file on disk
+    preroll_path TEXT,
+    duration_s   INTEGER NOT NULL,
+    size_bytes   INTEGER NOT NULL DEFAULT 0
+);
+
+-- TABLE: db_meta  (key/value; key 'timezone' = current IANA zone of local_date)
+CREATE TABLE db_meta (
+    key   TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
 -- VIEW: monitoring_history  (unified for NL queries)
 CREATE VIEW monitoring_history AS
 SELECT
@@ -100,6 +308,7 @@ SELECT
     d.timestamp,
     d.local_date  AS date,
     d.local_hour  AS hour,
+    d.timezone,
     d.camera_id,
     d.track_id,
     d.label       AS object_type,
@@ -107,15 +316,21 @@ SELECT
     d.movement,
     d.direction,
     d.speed_label AS speed,
+    d.speed_mps,
     d.entry_zone,
     d.exit_zone,
+    d.center_x,
+    d.center_y,
     d.duration_s,
+    d.lighting,
+    d.llm_status,
     (SELECT le.narrative
      FROM llm_events le
      WHERE le.camera_id = d.camera_id
        AND le.period_start <= d.timestamp
        AND le.period_end   >= d.timestamp
-     ORDER BY le.id DESC LIMIT 1) AS llm_narrative
+     ORDER BY le.id DESC LIMIT 1) AS llm_narrative,
+    (SELECT c.path FROM clips c WHERE c.detection_id = d.id LIMIT 1) AS clip_path
 FROM detections d;
 "#;
 
@@ -123,66 +338,83 @@ impl VisionDatabase {
     pub fn open(path: &str) -> Result<Self> {
         let resolved = resolve_db_path(path);
         let conn = Connection::open(&resolved)?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
         conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA synchronous=NORMAL;")?;
-        let db = Self { conn };
-        db.migrate()?;
-        Ok(db)
+        migrate(&conn)?;
+        let timezone = crate::local_time::of_db(&conn);
+
+        let mut readers = Vec::with_capacity(READ_POOL_SIZE);
+        for _ in 0..READ_POOL_SIZE {
+            let reader = Connection::open(&resolved)?;
+            reader.busy_timeout(BUSY_TIMEOUT)?;
+            reader.execute_batch("PRAGMA query_only=ON;")?;
+            readers.push(reader);
+        }
+
+        // The writer thread ends (closing the connection) when the last sender is dropped
+        let (writer, jobs) = mpsc::channel::<WriteJob>();
+        std::thread::Builder::new()
+            .name("vision-db-writer".into())
+            .spawn(move || {
+                for job in jobs {
+                    job(&conn);
+                }
+            })?;
+
+        Ok(Self {
+            writer,
+            readers: ReadPool { idle: Mutex::new(readers), available: Condvar::new() },
+            timezone: RwLock::new(timezone),
+        })
     }
 
-    fn migrate(&self) -> Result<()> {
-        self.conn.execute_batch("
-            CREATE TABLE IF NOT EXISTS detections (
-                id          INTEGER PRIMARY KEY AUTOINCREMENT,
-                timestamp   TEXT    NOT NULL,
-                local_date  TEXT    NOT NULL,
-                local_hour  INTEGER NOT NULL,
-                camera_id   TEXT    NOT NULL,
-                track_id    TEXT    NOT NULL,
-                label       TEXT    NOT NULL,
-                confidence  REAL    NOT NULL,
-                movement    TEXT,
-                direction   TEXT,
-                speed_label TEXT,
-                entry_zone  TEXT,
-                exit_zone   TEXT,
-                duration_s  REAL    NOT NULL DEFAULT 0,
-                thumbnail   BLOB    NOT NULL
-            );
+    /// Use `tz` for the local date/hour of new rows and record it in `db_meta`.
+    pub fn set_timezone(&self, tz: Tz) -> Result<()> {
+        *self.timezone.write().unwrap_or_else(|e| e.into_inner()) = tz;
+        self.write(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO db_meta(key,value) VALUES(?1,?2)",
+                params![crate::local_time::META_TIMEZONE, tz.name()],
+            )?;
+            Ok(())
+        })
+    }
 
-            CREATE TABLE IF NOT EXISTS llm_events (
-                id           INTEGER PRIMARY KEY AUTOINCREMENT,
-                timestamp    TEXT    NOT NULL,
-                local_date   TEXT    NOT NULL,
-                camera_id    TEXT    NOT NULL,
-                period_start TEXT    NOT NULL,
-                period_end   TEXT    NOT NULL,
-                narrative    TEXT    NOT NULL,
-                provider     TEXT    NOT NULL DEFAULT '',
-                crops_sent   INTEGER NOT NULL DEFAULT 0,
-                context      TEXT    NOT NULL DEFAULT ''
-            );
+    pub fn timezone(&self) -> Tz {
+        *self.timezone.read().unwrap_or_else(|e| e.into_inner())
+    }
 
-            CREATE VIEW IF NOT EXISTS monitoring_history AS
-            SELECT
-                d.id, d.timestamp, d.local_date AS date, d.local_hour AS hour,
-                d.camera_id, d.track_id, d.label AS object_type,
-                d.confidence, d.movement, d.direction, d.speed_label AS speed,
-                d.entry_zone, d.exit_zone, d.duration_s,
-                (SELECT le.narrative FROM llm_events le
-                 WHERE le.camera_id = d.camera_id
-                   AND le.period_start <= d.timestamp
-                   AND le.period_end   >= d.timestamp
-                 ORDER BY le.id DESC LIMIT 1) AS llm_narrative
-            FROM detections d;
-
-            CREATE INDEX IF NOT EXISTS idx_det_ts     ON detections(timestamp);
-            CREATE INDEX IF NOT EXISTS idx_det_cam    ON detections(camera_id);
-            CREATE INDEX IF NOT EXISTS idx_det_label  ON detections(label);
-            CREATE INDEX IF NOT EXISTS idx_det_date   ON detections(local_date);
-            CREATE INDEX IF NOT EXISTS idx_llm_ts     ON llm_events(timestamp);
-            CREATE INDEX IF NOT EXISTS idx_llm_cam    ON llm_events(camera_id);
-        ")?;
-        Ok(())
+    /// Open `path` or reuse the instance another pipeline already has open.
+    pub fn shared(path: &str) -> Result<Arc<Self>> {
+        let resolved = resolve_db_path(path);
+        let mut open = OPEN.lock().unwrap_or_else(|e| e.into_inner());
+        let open = open.get_or_insert_with(HashMap::new);
+        open.retain(|_, db| db.strong_count() > 0);
+        if let Some(db) = open.get(&resolved).and_then(Weak::upgrade) {
+            return Ok(db);
+        }
+        let db = Arc::new(Self::open(&resolved)?);
+        open.insert(resolved, Arc::downgrade(&db));
+        Ok(db)
+    }
+
+    /// Run `f` on the writer thread and wait for its result.
+    fn write<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::sync_channel(1);
+        self.writer
+            .send(Box::new(move |conn| {
+                let _ = tx.send(f(conn));
+            }))
+            .map_err(|_| anyhow!("vision DB writer stopped"))?;
+        rx.recv().map_err(|_| anyhow!("vision DB writer stopped"))?
+    }
+
+    fn read(&self) -> PooledConn<'_> {
+        self.readers.get()
     }
 
     // ─── Insert ──────────────────────────────────────────────────────────────
@@ -197,28 +429,43 @@ impl VisionDatabase {
         movement:    Option<&str>,
         direction:   Option<&str>,
         speed_label: Option<&str>,
+        speed_mps:   Option<f32>,
         entry_zone:  Option<&str>,
         exit_zone:   Option<&str>,
+        center:      Option<(f32, f32)>,
         duration_s:  f32,
         thumbnail:   &[u8],
+        thumbnail_format: Option<&str>,
+        lighting:    &str,
     ) -> Result<i64> {
         let now = Utc::now();
-        let local = now.with_timezone(&Local);
-        self.conn.execute(
-            "INSERT INTO detections
-             (timestamp,local_date,local_hour,camera_id,track_id,label,confidence,
-              movement,direction,speed_label,entry_zone,exit_zone,duration_s,thumbnail)
-             VALUES(?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14)",
-            params![
-                now.to_rfc3339(),
-                local.format("%Y-%m-%d").to_string(),
-                local.hour(),
-                camera_id, track_id, label, confidence,
-                movement, direction, speed_label, entry_zone, exit_zone,
-                duration_s, thumbnail,
-            ],
-        )?;
-        Ok(self.conn.last_insert_rowid())
+        let tz = self.timezone();
+        let (local_date, local_hour) = local_parts(tz, now);
+        let owned = |s: Option<&str>| s.map(String::from);
+        let (camera_id, track_id, label) = (camera_id.to_string(), track_id.to_string(), label.to_string());
+        let (movement, direction, speed_label) = (owned(movement), owned(direction), owned(speed_label));
+        let (entry_zone, exit_zone) = (owned(entry_zone), owned(exit_zone));
+        let (thumbnail, thumbnail_format, lighting) = (thumbnail.to_vec(), owned(thumbnail_format), lighting.to_string());
+        let (center_x, center_y) = (center.map(|c| c.0), center.map(|c| c.1));
+        self.write(move |conn| {
+            conn.execute(
+                "INSERT INTO detections
+                 (timestamp,local_date,local_hour,camera_id,track_id,label,confidence,
+                  movement,direction,speed_label,entry_zone,exit_zone,duration_s,thumbnail,lighting,
+                  thumbnail_format,speed_mps,timezone,center_x,center_y)
+                 VALUES(?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14,?15,?16,?17,?18,?19,?20)",
+                params![
+                    now.to_rfc3339(),
+                    local_date,
+                    local_hour,
+                    camera_id, track_id, label, confidence,
+                    movement, direction, speed_label, entry_zone, exit_zone,
+                    duration_s, thumbnail, lighting, thumbnail_format, speed_mps, tz.name(),
+                    center_x, center_y,
+                ],
+            )?;
+            Ok(conn.last_insert_rowid())
+        })
     }
 
     /// Insert LLM-generated event narrative.
@@ -233,36 +480,260 @@ impl VisionDatabase {
         context:      &str,
     ) -> Result<i64> {
         let now = Utc::now();
-        let local = now.with_timezone(&Local);
-        self.conn.execute(
-            "INSERT INTO llm_events
-             (timestamp,local_date,camera_id,period_start,period_end,
-              narrative,provider,crops_sent,context)
-             VALUES(?1,?2,?3,?4,?5,?6,?7,?8,?9)",
-            params![
-                now.to_rfc3339(),
-                local.format("%Y-%m-%d").to_string(),
-                camera_id,
-                period_start.to_rfc3339(),
-                period_end.to_rfc3339(),
-                narrative, provider, crops_sent, context,
-            ],
+        let tz = self.timezone();
+        let (local_date, _) = local_parts(tz, now);
+        let (camera_id, narrative, provider, context) =
+            (camera_id.to_string(), narrative.to_string(), provider.to_string(), context.to_string());
+        self.write(move |conn| {
+            conn.execute(
+                "INSERT INTO llm_events
+                 (timestamp,local_date,camera_id,period_start,period_end,
+                  narrative,provider,crops_sent,context,timezone)
+                 VALUES(?1,?2,?3,?4,?5,?6,?7,?8,?9,?10)",
+                params![
+                    now.to_rfc3339(),
+                    local_date,
+                    camera_id,
+                    period_start.to_rfc3339(),
+                    period_end.to_rfc3339(),
+                    narrative, provider, crops_sent, context, tz.name(),
+                ],
+            )?;
+            Ok(conn.last_insert_rowid())
+        })
+    }
+
+    /// Set the LLM outcome of a batch of detections: `"described"` with the
+    /// narrative's `llm_events.id`, or `"llm_skipped"` when over the rate limit.
+    pub fn mark_llm_status(&self, detection_ids: &[i64], status: &str, llm_event_id: Option<i64>) -> Result<usize> {
+        let (detection_ids, status) = (detection_ids.to_vec(), status.to_string());
+        self.write(move |conn| {
+            let tx = conn.unchecked_transaction()?;
+            let mut updated = 0;
+            {
+                let mut stmt = tx.prepare(
+                    "UPDATE detections SET llm_status=?1, llm_event_id=?2 WHERE id=?3",
+                )?;
+                for id in &detection_ids {
+                    updated += stmt.execute(params![status, llm_event_id, id])?;
+                }
+            }
+            tx.commit()?;
+            Ok(updated)
+        })
+    }
+
+    /// Record a clip file linked to the detection that triggered it.
+    pub fn insert_clip(
+        &self,
+        camera_id:    &str,
+        detection_id: Option<i64>,
+        track_id:     Option<&str>,
+        label:        &str,
+        path:         &str,
+        preroll_path: Option<&str>,
+        duration_s:   u32,
+        size_bytes:   u64,
+    ) -> Result<i64> {
+        let (camera_id, label, path) = (camera_id.to_string(), label.to_string(), path.to_string());
+        let (track_id, preroll_path) = (track_id.map(String::from), preroll_path.map(String::from));
+        self.write(move |conn| {
+            conn.execute(
+                "INSERT INTO clips
+                 (timestamp,camera_id,detection_id,track_id,label,path,preroll_path,duration_s,size_bytes)
+                 VALUES(?1,?2,?3,?4,?5,?6,?7,?8,?9)",
+                params![
+                    Utc::now().to_rfc3339(),
+                    camera_id, detection_id, track_id, label, path, preroll_path,
+                    duration_s, size_bytes as i64,
+                ],
+            )?;
+            Ok(conn.last_insert_rowid())
+        })
+    }
+
+    // ─── Retention ───────────────────────────────────────────────────────────
+
+    /// Delete clips (rows + files) older than `days`. Returns number removed.
+    pub fn prune_clips(&self, days: u32) -> Result<usize> {
+        let cutoff = (Utc::now() - chrono::Duration::days(days as i64)).to_rfc3339();
+        let old: Vec<(i64, String, Option<String>)> = {
+            let conn = self.read();
+            let mut stmt = conn.prepare(
+                "SELECT id, path, preroll_path FROM clips WHERE timestamp < ?1",
+            )?;
+            let rows = stmt.query_map(params![cutoff], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?;
+            rows.filter_map(|r| r.ok()).collect()
+        };
+        for (_, path, preroll) in &old {
+            let _ = std::fs::remove_file(path);
+            if let Some(p) = preroll {
+                let _ = std::fs::remove_file(p);
+            }
+        }
+        let ids: Vec<i64> = old.iter().map(|(id, _, _)| *id).collect();
+        self.write(move |conn| {
+            for id in &ids {
+                conn.execute("DELETE FROM clips WHERE id=?1", params![id])?;
+            }
+            Ok(ids.len())
+        })
+    }
+
+    // ─── Scene buffer spillover ──────────────────────────────────────────────
+
+    /// Persist a buffered event with its crops until its batch is flushed.
+    /// Then drops the camera's oldest spilled events while their crops take
+    /// more than `max_bytes`. Returns the `scene_buffer` id.
+    pub fn spill_scene_event(&self, camera_id: &str, event: &ObjectEvent, max_bytes: u64) -> Result<i64> {
+        let camera_id = camera_id.to_string();
+        let event = event.clone();
+        let crop_bytes: i64 = event.crops.iter().map(|c| c.jpeg_bytes.len() as i64).sum();
+        self.write(move |conn| {
+            let tx = conn.unchecked_transaction()?;
+            let mv = &event.movement;
+            tx.execute(
+                "INSERT INTO scene_buffer
+                 (camera_id,track_id,detection_id,label,confidence,movement,direction,speed_label,
+                  entry_zone,exit_zone,duration_s,speed_mps,finished_at,crop_bytes)
+                 VALUES(?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14)",
+                params![
+                    camera_id, event.track_id.to_string(), event.detection_id, event.class, event.confidence,
+                    mv.description, mv.direction, mv.speed_label, mv.entry_zone, mv.exit_zone,
+                    mv.duration_secs, mv.speed_mps, event.finished_at.to_rfc3339(), crop_bytes,
+                ],
+            )?;
+            let id = tx.last_insert_rowid();
+            {
+                let mut stmt = tx.prepare(
+                    "INSERT INTO scene_buffer_crops(event_id,jpeg,timestamp) VALUES(?1,?2,?3)",
+                )?;
+                for crop in &event.crops {
+                    stmt.execute(params![id, crop.jpeg_bytes, crop.timestamp.to_rfc3339()])?;
+                }
+            }
+            // Size cap: newest first, everything past `max_bytes` goes
+            tx.execute(
+                "DELETE FROM scene_buffer WHERE id IN (
+                     SELECT id FROM (
+                         SELECT id, SUM(crop_bytes) OVER (ORDER BY id DESC) AS total
+                         FROM scene_buffer WHERE camera_id=?1
+                     ) WHERE total > ?2 AND id <> ?3)",
+                params![camera_id, max_bytes as i64, id],
+            )?;
+            tx.execute(
+                "DELETE FROM scene_buffer_crops WHERE event_id NOT IN (SELECT id FROM scene_buffer)",
+                [],
+            )?;
+            tx.commit()?;
+            Ok(id)
+        })
+    }
+
+    /// Drop spilled events once their batch went to the LLM (or was skipped).
+    pub fn delete_scene_spill(&self, ids: &[i64]) -> Result<usize> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let ids = ids.to_vec();
+        self.write(move |conn| {
+            let tx = conn.unchecked_transaction()?;
+            let mut deleted = 0;
+            {
+                let mut events = tx.prepare("DELETE FROM scene_buffer WHERE id=?1")?;
+                let mut crops = tx.prepare("DELETE FROM scene_buffer_crops WHERE event_id=?1")?;
+                for id in &ids {
+                    deleted += events.execute(params![id])?;
+                    crops.execute(params![id])?;
+                }
+            }
+            tx.commit()?;
+            Ok(deleted)
+        })
+    }
+
+    /// Events of `camera_id` spilled but never flushed, oldest first.
+    pub fn load_scene_spill(&self, camera_id: &str) -> Result<Vec<ObjectEvent>> {
+        let conn = self.read();
+        let mut stmt = conn.prepare(
+            "SELECT id,track_id,detection_id,label,confidence,movement,direction,speed_label,
+                    entry_zone,exit_zone,duration_s,speed_mps,finished_at
+             FROM scene_buffer WHERE camera_id=?1 ORDER BY finished_at, id",
+        )?;
+        let mut events = stmt.query_map(params![camera_id], |r| {
+            Ok(ObjectEvent {
+                spill_id:     Some(r.get(0)?),
+                track_id:     uuid::Uuid::parse_str(&r.get::<_,String>(1)?).unwrap_or_default(),
+                detection_id: r.get(2)?,
+                class:        r.get(3)?,
+                confidence:   r.get(4)?,
+                movement: MovementSummary {
+                    description:   r.get(5)?,
+                    direction:     r.get(6)?,
+                    speed_label:   static_speed_label(&r.get::<_,String>(7)?),
+                    entry_zone:    r.get(8)?,
+                    exit_zone:     r.get(9)?,
+                    duration_secs: r.get(10)?,
+                    speed_mps:     r.get(11)?,
+                },
+                crops:        Vec::new(),
+                finished_at:  parse_dt(r.get::<_,String>(12)?),
+            })
+        })?.collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT jpeg,timestamp FROM scene_buffer_crops WHERE event_id=?1 ORDER BY id",
         )?;
-        Ok(self.conn.last_insert_rowid())
+        for event in &mut events {
+            event.crops = stmt.query_map(params![event.spill_id], |r| {
+                Ok(CropSnapshot { jpeg_bytes: r.get(0)?, timestamp: parse_dt(r.get::<_,String>(1)?) })
+            })?.collect::<rusqlite::Result<Vec<_>>>()?;
+        }
+        Ok(events)
     }
 
     // ─── Queries ─────────────────────────────────────────────────────────────
 
+    pub fn get_recent_clips(&self, camera_id: Option<&str>, limit: u32) -> Result<Vec<ClipRecord>> {
+        let cam_f = cam_filter(camera_id);
+        let sql = format!(
+            "SELECT id,timestamp,camera_id,detection_id,track_id,label,path,preroll_path,duration_s,size_bytes
+             FROM clips WHERE 1=1{cam_f} ORDER BY timestamp DESC LIMIT {limit}"
+        );
+        let conn = self.read();
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map([], |r| {
+            Ok(ClipRecord {
+                id:           r.get(0)?,
+                timestamp:    parse_dt(r.get::<_,String>(1)?),
+                camera_id:    r.get(2)?,
+                detection_id: r.get(3)?,
+                track_id:     r.get(4)?,
+                label:        r.get(5)?,
+                path:         r.get(6)?,
+                preroll_path: r.get(7)?,
+                duration_s:   r.get::<_,i64>(8)? as u32,
+                size_bytes:   r.get::<_,i64>(9)? as u64,
+            })
+        })?.filter_map(|r| r.ok()).collect();
+        Ok(rows)
+    }
+
     pub fn get_statistics(&self, camera_id: Option<&str>, hours: u32) -> Result<Statistics> {
+        self.statistics_at(camera_id, hours, Utc::now())
+    }
+
+    fn statistics_at(&self, camera_id: Option<&str>, hours: u32, now: DateTime<Utc>) -> Result<Statistics> {
         let cam_f = cam_filter(camera_id);
-        let tf = time_filter(hours);
+        let tf = time_filter(hours, now);
+        let conn = self.read();
 
         let by_class: Vec<(String, u64)> = {
             let sql = format!(
                 "SELECT label, COUNT(*) FROM detections WHERE {tf}{cam_f}
                  GROUP BY label ORDER BY 2 DESC"
             );
-            let mut s = self.conn.prepare(&sql)?;
+            let mut s = conn.prepare(&sql)?;
             s.query_map([], |r| Ok((r.get::<_,String>(0)?, r.get::<_,u64>(1)?)))?
                 .filter_map(|r| r.ok()).collect()
         };
@@ -272,17 +743,17 @@ impl VisionDatabase {
                 "SELECT CAST(local_hour AS TEXT), COUNT(*) FROM detections
                  WHERE {tf}{cam_f} GROUP BY local_hour ORDER BY local_hour"
             );
-            let mut s = self.conn.prepare(&sql)?;
+            let mut s = conn.prepare(&sql)?;
             s.query_map([], |r| Ok((r.get::<_,String>(0)?, r.get::<_,u64>(1)?)))?
                 .filter_map(|r| r.ok()).collect()
         };
 
-        let unique_entries: u64 = self.conn.query_row(
+        let unique_entries: u64 = conn.query_row(
             &format!("SELECT COUNT(DISTINCT track_id) FROM detections WHERE {tf}{cam_f}"),
             [], |r| r.get(0),
         )?;
 
-        let total: u64 = self.conn.query_row(
+        let total: u64 = conn.query_row(
             &format!("SELECT COUNT(*) FROM detections WHERE {tf}{cam_f}"),
             [], |r| r.get(0),
         )?;
@@ -294,6 +765,78 @@ impl VisionDatabase {
         })
     }
 
+    /// Bin the centres of `camera_id`'s detections from the last `hours`
+    /// (optionally one `label`) into a `grid_w`×`grid_h` grid.
+    pub fn heatmap(&self, camera_id: &str, label: Option<&str>, hours: u32, grid_w: u32, grid_h: u32) -> Result<Heatmap> {
+        self.heatmap_at(camera_id, label, hours, grid_w, grid_h, Utc::now())
+    }
+
+    fn heatmap_at(
+        &self,
+        camera_id: &str,
+        label: Option<&str>,
+        hours: u32,
+        grid_w: u32,
+        grid_h: u32,
+        now: DateTime<Utc>,
+    ) -> Result<Heatmap> {
+        let (grid_w, grid_h) = (grid_w.max(1), grid_h.max(1));
+        let label = label.map(|l| l.trim().to_lowercase()).filter(|l| !l.is_empty());
+        let label_f = if label.is_some() { " AND label = ?5" } else { "" };
+        // Row/column per detection, clamped so 1.0 lands in the last cell;
+        // NULL centres group under (NULL, NULL)
+        let sql = format!(
+            "SELECT MAX(0, MIN(?3 - 1, CAST(center_x * ?3 AS INTEGER))),
+                    MAX(0, MIN(?4 - 1, CAST(center_y * ?4 AS INTEGER))),
+                    COUNT(*)
+             FROM detections INDEXED BY idx_det_centroid
+             WHERE camera_id = ?1 AND datetime(timestamp) > ?2{label_f}
+             GROUP BY 1, 2"
+        );
+        let since = crate::local_time::sqlite_utc(now - chrono::Duration::hours(hours as i64));
+        let mut values: Vec<rusqlite::types::Value> = vec![
+            camera_id.to_string().into(),
+            since.into(),
+            (grid_w as i64).into(),
+            (grid_h as i64).into(),
+        ];
+        values.extend(label.clone().map(Into::into));
+
+        let conn = self.read();
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query(rusqlite::params_from_iter(values))?;
+        let mut counts = vec![vec![0u64; grid_w as usize]; grid_h as usize];
+        let (mut total, mut unpositioned) = (0u64, 0u64);
+        while let Some(row) = rows.next()? {
+            let count: u64 = row.get(2)?;
+            match (row.get::<_, Option<usize>>(0)?, row.get::<_, Option<usize>>(1)?) {
+                (Some(x), Some(y)) => {
+                    counts[y][x] += count;
+                    total += count;
+                }
+                _ => unpositioned += count,
+            }
+        }
+
+        let max_count = counts.iter().flatten().copied().max().unwrap_or(0);
+        let cells = counts
+            .iter()
+            .map(|row| row.iter().map(|&c| if max_count > 0 { c as f32 / max_count as f32 } else { 0.0 }).collect())
+            .collect();
+        Ok(Heatmap {
+            camera_id: camera_id.to_string(),
+            label,
+            period_hours: hours,
+            grid_w,
+            grid_h,
+            cells,
+            max_count,
+            total_detections: total,
+            unpositioned,
+            coordinates: "normalized",
+        })
+    }
+
     /// Execute a raw SQL SELECT query (from text-to-SQL).
     pub fn execute_query(&self, sql: &str) -> Result<(Vec<String>, Vec<Vec<String>>)> {
         let trimmed = sql.trim().to_uppercase();
@@ -301,7 +844,8 @@ impl VisionDatabase {
             anyhow::bail!("Only SELECT queries are allowed");
         }
 
-        let mut stmt = self.conn.prepare(sql)?;
+        let conn = self.read();
+        let mut stmt = conn.prepare(sql)?;
         let col_names: Vec<String> = stmt.column_names()
             .into_iter().map(String::from).collect();
 
@@ -326,11 +870,76 @@ impl VisionDatabase {
         Ok((col_names, rows))
     }
 
-    pub fn get_thumbnail(&self, id: i64) -> Result<Vec<u8>> {
-        Ok(self.conn.query_row(
+    /// Thumbnail bytes and their format, detected from the magic bytes
+    /// (JPEG for anything unrecognised, as before the format was configurable).
+    pub fn get_thumbnail(&self, id: i64) -> Result<(Vec<u8>, ThumbnailFormat)> {
+        let bytes: Vec<u8> = self.read().query_row(
             "SELECT thumbnail FROM detections WHERE id=?1",
             params![id], |r| r.get(0),
-        )?)
+        )?;
+        let format = ThumbnailFormat::detect(&bytes).unwrap_or_default();
+        Ok((bytes, format))
+    }
+
+    /// Write every thumbnail matching `filter` into `dir` as
+    /// `<local time>_<label>_<confidence>.<jpg|webp>` (the detection id is
+    /// appended when two share a name), oldest first. Rows are read one at a
+    /// time, so only one BLOB is in memory. With `contact_sheet = (path, n)`
+    /// the first `n` thumbnails are also tiled into a captioned JPEG grid.
+    pub fn export_thumbnails(
+        &self,
+        filter: &ThumbnailFilter,
+        dir: &Path,
+        contact_sheet: Option<(&Path, usize)>,
+    ) -> Result<ThumbnailExport> {
+        std::fs::create_dir_all(dir)?;
+        let tz = self.timezone();
+        let (conditions, values) = filter.where_clause();
+        let sql = format!(
+            "SELECT id, timestamp, label, confidence, thumbnail FROM detections
+             WHERE {conditions} ORDER BY timestamp, id"
+        );
+        let conn = self.read();
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query(rusqlite::params_from_iter(values))?;
+
+        let mut sheet = contact_sheet.map(|(_, max)| ContactSheet::new(max));
+        let mut result = ThumbnailExport {
+            output_dir:    dir.to_string_lossy().into_owned(),
+            exported:      0,
+            bytes:         0,
+            contact_sheet: None,
+            sheet_tiles:   0,
+        };
+        while let Some(row) = rows.next()? {
+            let id: i64 = row.get(0)?;
+            let at = parse_dt(row.get(1)?).with_timezone(&tz);
+            let label: String = row.get(2)?;
+            let confidence: f64 = row.get(3)?;
+            let blob = row.get_ref(4)?.as_blob()?;
+            let ext = ThumbnailFormat::detect(blob).unwrap_or_default().extension();
+
+            let stem = thumbnail_file_stem(&at, &label, confidence);
+            let file = std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(dir.join(format!("{stem}.{ext}")))
+                .or_else(|_| std::fs::File::create(dir.join(format!("{stem}_{id}.{ext}"))));
+            std::io::Write::write_all(&mut file?, blob)?;
+            result.exported += 1;
+            result.bytes += blob.len() as u64;
+
+            if let Some(sheet) = sheet.as_mut().filter(|s| !s.is_full()) {
+                sheet.add(blob, &at.format("%Y-%m-%d %H:%M:%S").to_string());
+            }
+        }
+
+        if let (Some(sheet), Some((path, _))) = (sheet.filter(|s| !s.is_empty()), contact_sheet) {
+            sheet.save(path)?;
+            result.contact_sheet = Some(path.to_string_lossy().into_owned());
+            result.sheet_tiles = sheet.len();
+        }
+        Ok(result)
     }
 
     pub fn get_recent_llm_events(&self, camera_id: Option<&str>, limit: u32) -> Result<Vec<LlmEvent>> {
@@ -339,7 +948,8 @@ impl VisionDatabase {
             "SELECT id,timestamp,camera_id,period_start,period_end,narrative,provider,crops_sent,context
              FROM llm_events WHERE 1=1{cam_f} ORDER BY timestamp DESC LIMIT {limit}"
         );
-        let mut stmt = self.conn.prepare(&sql)?;
+        let conn = self.read();
+        let mut stmt = conn.prepare(&sql)?;
         let rows = stmt.query_map([], |r| {
             Ok(LlmEvent {
                 id:           r.get(0)?,
@@ -359,6 +969,170 @@ impl VisionDatabase {
 
 // ─── Helpers ─────────────────────────────────────────────────────────────────
 
+fn migrate(conn: &Connection) -> Result<()> {
+    conn.execute_batch("
+        CREATE TABLE IF NOT EXISTS detections (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp   TEXT    NOT NULL,
+            local_date  TEXT    NOT NULL,
+            local_hour  INTEGER NOT NULL,
+            camera_id   TEXT    NOT NULL,
+            track_id    TEXT    NOT NULL,
+            label       TEXT    NOT NULL,
+            confidence  REAL    NOT NULL,
+            movement    TEXT,
+            direction   TEXT,
+            speed_label TEXT,
+            entry_zone  TEXT,
+            exit_zone   TEXT,
+            duration_s  REAL    NOT NULL DEFAULT 0,
+            thumbnail   BLOB    NOT NULL,
+            lighting    TEXT,
+            thumbnail_format TEXT,
+            llm_status  TEXT,
+            llm_event_id INTEGER,
+            speed_mps   REAL,
+            timezone    TEXT,
+            center_x    REAL,
+            center_y    REAL
+        );
+
+        CREATE TABLE IF NOT EXISTS llm_events (
+            id           INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp    TEXT    NOT NULL,
+            local_date   TEXT    NOT NULL,
+            timezone     TEXT,
+            camera_id    TEXT    NOT NULL,
+            period_start TEXT    NOT NULL,
+            period_end   TEXT    NOT NULL,
+            narrative    TEXT    NOT NULL,
+            provider     TEXT    NOT NULL DEFAULT '',
+            crops_sent   INTEGER NOT NULL DEFAULT 0,
+            context      TEXT    NOT NULL DEFAULT ''
+        );
+
+        CREATE TABLE IF NOT EXISTS clips (
+            id           INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp    TEXT    NOT NULL,
+            camera_id    TEXT    NOT NULL,
+            detection_id INTEGER,
+            track_id     TEXT,
+            label        TEXT    NOT NULL,
+            path         TEXT    NOT NULL,
+            preroll_path TEXT,
+            duration_s   INTEGER NOT NULL,
+            size_bytes   INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE TABLE IF NOT EXISTS db_meta (
+            key   TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+
+        -- MinuteBuffer spillover; rows live until their batch is flushed
+        CREATE TABLE IF NOT EXISTS scene_buffer (
+            id           INTEGER PRIMARY KEY AUTOINCREMENT,
+            camera_id    TEXT    NOT NULL,
+            track_id     TEXT    NOT NULL,
+            detection_id INTEGER,
+            label        TEXT    NOT NULL,
+            confidence   REAL    NOT NULL,
+            movement     TEXT    NOT NULL,
+            direction    TEXT    NOT NULL,
+            speed_label  TEXT    NOT NULL,
+            entry_zone   TEXT    NOT NULL,
+            exit_zone    TEXT    NOT NULL,
+            duration_s   REAL    NOT NULL,
+            speed_mps    REAL,
+            finished_at  TEXT    NOT NULL,
+            crop_bytes   INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE TABLE IF NOT EXISTS scene_buffer_crops (
+            id        INTEGER PRIMARY KEY AUTOINCREMENT,
+            event_id  INTEGER NOT NULL,
+            jpeg      BLOB    NOT NULL,
+            timestamp TEXT    NOT NULL
+        );
+
+    ")?;
+
+    // Columns added after the first release
+    if !has_column(conn, "detections", "lighting")? {
+        conn.execute_batch("ALTER TABLE detections ADD COLUMN lighting TEXT;")?;
+    }
+    // Old rows stay NULL — readers sniff the blob instead
+    if !has_column(conn, "detections", "thumbnail_format")? {
+        conn.execute_batch("ALTER TABLE detections ADD COLUMN thumbnail_format TEXT;")?;
+    }
+    if !has_column(conn, "detections", "llm_status")? {
+        conn.execute_batch("
+            ALTER TABLE detections ADD COLUMN llm_status TEXT;
+            ALTER TABLE detections ADD COLUMN llm_event_id INTEGER;
+        ")?;
+    }
+    if !has_column(conn, "detections", "speed_mps")? {
+        conn.execute_batch("ALTER TABLE detections ADD COLUMN speed_mps REAL;")?;
+    }
+    // No bbox was stored before, so older rows stay out of heatmaps
+    if !has_column(conn, "detections", "center_x")? {
+        conn.execute_batch("
+            ALTER TABLE detections ADD COLUMN center_x REAL;
+            ALTER TABLE detections ADD COLUMN center_y REAL;
+        ")?;
+    }
+    // Rows from before the column stay NULL: their zone was the system's at the time
+    for table in ["detections", "llm_events"] {
+        if !has_column(conn, table, "timezone")? {
+            conn.execute_batch(&format!("ALTER TABLE {table} ADD COLUMN timezone TEXT;"))?;
+        }
+    }
+
+    conn.execute_batch("
+        -- Views hold no data; recreate so older DBs pick up new columns
+        DROP VIEW IF EXISTS monitoring_history;
+        CREATE VIEW monitoring_history AS
+        SELECT
+            d.id, d.timestamp, d.local_date AS date, d.local_hour AS hour, d.timezone,
+            d.camera_id, d.track_id, d.label AS object_type,
+            d.confidence, d.movement, d.direction, d.speed_label AS speed, d.speed_mps,
+            d.entry_zone, d.exit_zone, d.center_x, d.center_y, d.duration_s, d.lighting, d.llm_status,
+            (SELECT le.narrative FROM llm_events le
+             WHERE le.camera_id = d.camera_id
+               AND le.period_start <= d.timestamp
+               AND le.period_end   >= d.timestamp
+             ORDER BY le.id DESC LIMIT 1) AS llm_narrative,
+            (SELECT c.path FROM clips c WHERE c.detection_id = d.id LIMIT 1) AS clip_path
+        FROM detections d;
+
+        CREATE INDEX IF NOT EXISTS idx_det_ts     ON detections(timestamp);
+        CREATE INDEX IF NOT EXISTS idx_det_cam    ON detections(camera_id);
+        CREATE INDEX IF NOT EXISTS idx_det_label  ON detections(label);
+        CREATE INDEX IF NOT EXISTS idx_det_date   ON detections(local_date);
+        -- Covers heatmap(): centres are read from the index, not the table
+        CREATE INDEX IF NOT EXISTS idx_det_centroid
+            ON detections(camera_id, label, timestamp, center_x, center_y);
+        CREATE INDEX IF NOT EXISTS idx_llm_ts     ON llm_events(timestamp);
+        CREATE INDEX IF NOT EXISTS idx_llm_cam    ON llm_events(camera_id);
+        CREATE INDEX IF NOT EXISTS idx_clip_ts    ON clips(timestamp);
+        CREATE INDEX IF NOT EXISTS idx_clip_det   ON clips(detection_id);
+        CREATE INDEX IF NOT EXISTS idx_spill_cam  ON scene_buffer(camera_id);
+        CREATE INDEX IF NOT EXISTS idx_spill_crop ON scene_buffer_crops(event_id);
+    ")?;
+    Ok(())
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})"))?;
+    let names = stmt.query_map([], |r| r.get::<_, String>(1))?;
+    for name in names {
+        if name? == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
 fn cam_filter(camera_id: Option<&str>) -> String {
     match camera_id {
         Some(id) => format!(" AND camera_id='{}'", id.replace('\'', "''")),
@@ -366,8 +1140,28 @@ fn cam_filter(camera_id: Option<&str>) -> String {
     }
 }
 
-fn time_filter(hours: u32) -> String {
-    format!("timestamp > datetime('now', '-{hours} hours')")
+/// Rows from the last `hours` before `now`; `datetime(timestamp)` normalises
+/// the stored RFC 3339 text so it compares with the SQLite UTC format.
+fn time_filter(hours: u32, now: DateTime<Utc>) -> String {
+    let since = now - chrono::Duration::hours(hours as i64);
+    format!("datetime(timestamp) > '{}'", crate::local_time::sqlite_utc(since))
+}
+
+/// `local_date` and `local_hour` of `at` in `tz`.
+fn local_parts(tz: Tz, at: DateTime<Utc>) -> (String, u32) {
+    let local = at.with_timezone(&tz);
+    (local.format("%Y-%m-%d").to_string(), local.hour())
+}
+
+/// `MovementSummary::speed_label` is a `&'static str`; map the stored text back.
+fn static_speed_label(label: &str) -> &'static str {
+    match label {
+        "stationary" => "stationary",
+        "slow"       => "slow",
+        "moderate"   => "moderate",
+        "fast"       => "fast",
+        _            => "unknown",
+    }
 }
 
 fn parse_dt(s: String) -> DateTime<Utc> {
@@ -390,3 +1184,334 @@ fn resolve_db_path(db_path: &str) -> String {
     }
     db_path.to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn insert(db: &VisionDatabase, i: usize) -> Result<i64> {
+        db.insert_detection(
+            "bench", &format!("track-{i}"), if i % 3 == 0 { "car" } else { "person" }, 0.8,
+            None, None, None, None, None, None, None, 1.5, &[0xFF, 0xD8, 0xFF], Some("jpeg"), "day",
+        )
+    }
+
+    #[test]
+    fn readers_are_query_only_and_shared_instances_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("monitoring.db").to_string_lossy().to_string();
+        let db = VisionDatabase::shared(&path).unwrap();
+        assert!(Arc::ptr_eq(&db, &VisionDatabase::shared(&path).unwrap()));
+        assert!(has_column(&db.read(), "detections", "speed_mps").unwrap());
+
+        let id = insert(&db, 1).unwrap();
+        assert_eq!(db.mark_llm_status(&[id], "described", Some(7)).unwrap(), 1);
+        let (_, rows) = db.execute_query("SELECT llm_status, llm_event_id FROM detections").unwrap();
+        assert_eq!(rows, vec![vec!["described".to_string(), "7".to_string()]]);
+
+        // A text-to-SQL CTE cannot sneak a write through a read connection
+        let _ = db.execute_query("WITH x AS (SELECT 1) DELETE FROM detections");
+        assert_eq!(db.get_statistics(None, 24).unwrap().total_detections, 1);
+    }
+
+    /// A detection at `at`, with the local parts computed like `insert_detection`.
+    fn insert_at(db: &VisionDatabase, at: &str) {
+        let at: DateTime<Utc> = at.parse().unwrap();
+        let tz = db.timezone();
+        let (date, hour) = local_parts(tz, at);
+        db.write(move |conn| {
+            conn.execute(
+                "INSERT INTO detections (timestamp,local_date,local_hour,camera_id,track_id,label,confidence,thumbnail,timezone)
+                 VALUES(?1,?2,?3,'cam','t','person',0.9,x'00',?4)",
+                params![at.to_rfc3339(), date, hour, tz.name()],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_statistics_by_hour_across_dst_fall_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dst.db").to_string_lossy().to_string();
+        let db = VisionDatabase::open(&path).unwrap();
+        db.set_timezone("Europe/Warsaw".parse().unwrap()).unwrap();
+        assert_eq!(crate::local_time::of_db(&db.read()).name(), "Europe/Warsaw");
+
+        // 2024-10-27: clocks go back 03:00 CEST → 02:00 CET, so 02:30 happens twice
+        insert_at(&db, "2024-10-26T21:30:00Z"); // 23:30 CEST, 26th
+        insert_at(&db, "2024-10-27T00:30:00Z"); // 02:30 CEST
+        insert_at(&db, "2024-10-27T01:30:00Z"); // 02:30 CET
+        insert_at(&db, "2024-10-27T02:30:00Z"); // 03:30 CET
+        insert_at(&db, "2024-10-25T12:00:00Z"); // outside the window
+
+        let now = "2024-10-27T03:00:00Z".parse().unwrap();
+        let stats = db.statistics_at(None, 24, now).unwrap();
+        assert_eq!(stats.total_detections, 4);
+        assert_eq!(stats.by_hour, vec![("2".to_string(), 2), ("3".to_string(), 1), ("23".to_string(), 1)]);
+
+        let (_, rows) = db
+            .execute_query("SELECT date, COUNT(*), timezone FROM monitoring_history GROUP BY date ORDER BY date")
+            .unwrap();
+        assert_eq!(rows[1..], [
+            vec!["2024-10-26".to_string(), "1".to_string(), "Europe/Warsaw".to_string()],
+            vec!["2024-10-27".to_string(), "3".to_string(), "Europe/Warsaw".to_string()],
+        ]);
+    }
+
+    #[test]
+    fn test_export_thumbnails_with_filters_and_contact_sheet() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = VisionDatabase::open(&dir.path().join("thumbs.db").to_string_lossy()).unwrap();
+        db.set_timezone("Europe/Warsaw".parse().unwrap()).unwrap();
+        let mut jpeg = std::io::Cursor::new(Vec::new());
+        image::RgbImage::from_pixel(64, 48, image::Rgb([0, 120, 255]))
+            .write_to(&mut jpeg, image::ImageFormat::Jpeg)
+            .unwrap();
+        let jpeg = jpeg.into_inner();
+        for (at, label, confidence) in [
+            ("2024-06-12T20:14:05Z", "person", 0.91),
+            ("2024-06-12T20:14:05Z", "person", 0.91),
+            ("2024-06-12T21:00:00Z", "car", 0.95),
+            ("2024-06-12T22:30:00Z", "person", 0.42),
+            ("2024-06-11T23:00:00Z", "person", 0.88),
+        ] {
+            let thumb = jpeg.clone();
+            db.write(move |conn| {
+                conn.execute(
+                    "INSERT INTO detections (timestamp,local_date,local_hour,camera_id,track_id,label,confidence,thumbnail)
+                     VALUES(?1,'',0,'front','t',?2,?3,?4)",
+                    params![at, label, confidence, thumb],
+                )?;
+                Ok(())
+            })
+            .unwrap();
+        }
+
+        let filter = ThumbnailFilter {
+            label: Some("Person".into()),
+            since: Some("2024-06-12T00:00:00Z".parse().unwrap()),
+            min_confidence: Some(0.5),
+            ..Default::default()
+        };
+        let out = dir.path().join("night");
+        let sheet = dir.path().join("sheet.jpg");
+        let result = db.export_thumbnails(&filter, &out, Some((&sheet, 1))).unwrap();
+        assert_eq!(result.exported, 2);
+        assert_eq!(result.bytes, 2 * jpeg.len() as u64);
+        let mut names: Vec<String> = std::fs::read_dir(&out)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        // Local (CEST) time; the second file with the same name gets its id
+        assert_eq!(names, vec!["20240612-221405_person_0.91.jpg", "20240612-221405_person_0.91_2.jpg"]);
+
+        assert_eq!(result.sheet_tiles, 1);
+        assert_eq!(result.contact_sheet.as_deref(), Some(sheet.to_string_lossy().as_ref()));
+        assert!(image::open(&sheet).is_ok());
+
+        // Nothing matches: no files, no sheet
+        let filter = ThumbnailFilter { camera_id: Some("back".into()), ..Default::default() };
+        let result = db.export_thumbnails(&filter, &dir.path().join("none"), Some((&dir.path().join("empty.jpg"), 9))).unwrap();
+        assert_eq!((result.exported, result.contact_sheet), (0, None));
+    }
+
+    fn insert_centered(db: &VisionDatabase, camera: &str, label: &str, center: Option<(f32, f32)>) {
+        db.insert_detection(
+            camera, "t", label, 0.9, None, None, None, None, None, None, center, 1.0, &[0xFF, 0xD8, 0xFF], None, "day",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_heatmap_bins_centres_per_camera_and_label() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = VisionDatabase::open(&dir.path().join("heat.db").to_string_lossy()).unwrap();
+        for _ in 0..3 {
+            insert_centered(&db, "gate", "person", Some((0.1, 0.1)));
+        }
+        insert_centered(&db, "gate", "car", Some((0.9, 0.6)));
+        insert_centered(&db, "gate", "car", Some((1.0, 1.0))); // edge → last cell
+        insert_centered(&db, "gate", "person", None);
+        insert_centered(&db, "yard", "person", Some((0.5, 0.5)));
+
+        let map = db.heatmap("gate", None, 24, 4, 2).unwrap();
+        assert_eq!((map.total_detections, map.unpositioned, map.max_count), (5, 1, 3));
+        assert_eq!(map.cells, vec![
+            vec![1.0, 0.0, 0.0, 0.0],
+            vec![0.0, 0.0, 0.0, 2.0 / 3.0],
+        ]);
+
+        let cars = db.heatmap("gate", Some(" Car "), 24, 4, 2).unwrap();
+        assert_eq!((cars.label.as_deref(), cars.total_detections, cars.max_count), (Some("car"), 2, 2));
+        assert_eq!(cars.cells[1][3], 1.0);
+
+        let later = Utc::now() + chrono::Duration::hours(2);
+        let empty = db.heatmap_at("gate", None, 1, 4, 2, later).unwrap();
+        assert_eq!((empty.total_detections, empty.max_count), (0, 0));
+        assert!(empty.cells.iter().flatten().all(|&c| c == 0.0));
+    }
+
+    /// `cargo test --release -- --ignored heatmap_100k`
+    #[test]
+    #[ignore]
+    fn heatmap_100k_rows_under_500ms() {
+        const N: usize = 100_000;
+        let dir = tempfile::tempdir().unwrap();
+        let db = VisionDatabase::open(&dir.path().join("heat-bench.db").to_string_lossy()).unwrap();
+        let now = Utc::now();
+        db.write(move |conn| {
+            let tx = conn.unchecked_transaction()?;
+            let mut stmt = tx.prepare(
+                "INSERT INTO detections (timestamp,local_date,local_hour,camera_id,track_id,label,confidence,thumbnail,center_x,center_y)
+                 VALUES(?1,'2024-01-01',0,'bench','t',?2,0.9,zeroblob(8000),?3,?4)",
+            )?;
+            for i in 0..N {
+                let at = now - chrono::Duration::seconds(i as i64 % 80_000);
+                let label = if i % 3 == 0 { "car" } else { "person" };
+                stmt.execute(params![at.to_rfc3339(), label, (i % 97) as f64 / 97.0, (i % 89) as f64 / 89.0])?;
+            }
+            drop(stmt);
+            tx.commit()?;
+            Ok(())
+        })
+        .unwrap();
+
+        let start = Instant::now();
+        let map = db.heatmap("bench", None, 24, 64, 36).unwrap();
+        let elapsed = start.elapsed();
+        println!("heatmap of {N} rows in {elapsed:?}");
+        assert_eq!(map.total_detections, N as u64);
+        assert!(elapsed < Duration::from_millis(500), "{elapsed:?}");
+    }
+
+    fn scene_event(class: &str, minute: u32, crop_len: usize) -> ObjectEvent {
+        let at: DateTime<Utc> = format!("2026-03-02T10:{minute:02}:00Z").parse().unwrap();
+        ObjectEvent {
+            track_id:     uuid::Uuid::new_v4(),
+            detection_id: Some(minute as i64),
+            class:        class.to_string(),
+            confidence:   0.9,
+            movement: MovementSummary {
+                description:   format!("{class} moving left→right"),
+                direction:     "left→right".into(),
+                speed_label:   "slow",
+                entry_zone:    "left".into(),
+                exit_zone:     "right".into(),
+                duration_secs: 4.0,
+                speed_mps:     Some(1.2),
+            },
+            crops: (0..2).map(|i| CropSnapshot { jpeg_bytes: vec![i; crop_len], timestamp: at }).collect(),
+            finished_at:  at,
+            spill_id:     None,
+        }
+    }
+
+    #[test]
+    fn test_scene_buffer_survives_crash_before_flush() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("monitoring.db").to_string_lossy().to_string();
+        let mut buf = crate::vision_scene_buffer::MinuteBuffer::new(60, 100, 3, 50);
+        {
+            let db = VisionDatabase::open(&path).unwrap();
+            for (i, class) in ["person", "car", "dog"].iter().enumerate() {
+                let mut event = scene_event(class, i as u32 + 1, 100);
+                event.spill_id = Some(db.spill_scene_event("front", &event, 1 << 20).unwrap());
+                buf.push(event);
+            }
+            db.spill_scene_event("back", &scene_event("cat", 9, 100), 1 << 20).unwrap();
+            // Crash: neither the buffer nor the DB handle get to flush
+        }
+        drop(buf);
+
+        let db = VisionDatabase::open(&path).unwrap();
+        let spilled = db.load_scene_spill("front").unwrap();
+        let classes: Vec<&str> = spilled.iter().map(|e| e.class.as_str()).collect();
+        assert_eq!(classes, vec!["person", "car", "dog"]);
+        assert_eq!(spilled[1].crops.len(), 2);
+        assert_eq!(spilled[1].crops[1].jpeg_bytes, vec![1u8; 100]);
+        assert_eq!(spilled[1].movement.speed_label, "slow");
+        assert_eq!(spilled[1].movement.speed_mps, Some(1.2));
+
+        let mut buf = crate::vision_scene_buffer::MinuteBuffer::new(60, 100, 10, 50);
+        buf.restore(spilled);
+        assert!(buf.is_recovering() && buf.should_flush());
+        let batch = buf.drain().unwrap();
+        assert!(batch.recovered && !buf.is_recovering());
+        assert_eq!(batch.period_start.to_rfc3339(), "2026-03-02T10:01:00+00:00");
+        assert_eq!(batch.period_end.to_rfc3339(), "2026-03-02T10:03:00+00:00");
+
+        let ids: Vec<i64> = batch.events.iter().filter_map(|e| e.spill_id).collect();
+        assert_eq!(db.delete_scene_spill(&ids).unwrap(), 3);
+        assert!(db.load_scene_spill("front").unwrap().is_empty());
+        assert_eq!(db.load_scene_spill("back").unwrap().len(), 1);
+        let (_, rows) = db.execute_query("SELECT COUNT(*) FROM scene_buffer_crops").unwrap();
+        assert_eq!(rows, vec![vec!["2".to_string()]]);
+    }
+
+    #[test]
+    fn test_scene_spill_cap_drops_oldest_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("monitoring.db").to_string_lossy().to_string();
+        let db = VisionDatabase::open(&path).unwrap();
+        // 2 crops × 400 bytes per event; 2000 bytes hold two events
+        for minute in 1..=4 {
+            db.spill_scene_event("front", &scene_event("person", minute, 400), 2000).unwrap();
+        }
+        let kept: Vec<i64> = db.load_scene_spill("front").unwrap().iter().filter_map(|e| e.detection_id).collect();
+        assert_eq!(kept, vec![3, 4]);
+        let (_, rows) = db.execute_query("SELECT COUNT(*) FROM scene_buffer_crops").unwrap();
+        assert_eq!(rows, vec![vec!["4".to_string()]]);
+    }
+
+    /// Bench-style: 10k inserts from the detection worker while two threads
+    /// keep querying. Before the single-writer + read pool split both sides
+    /// queued on one mutex-guarded connection. Prints timings; run with
+    /// `cargo test --features vision concurrent_inserts_while_querying -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn concurrent_inserts_while_querying() {
+        const N: usize = 10_000;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bench.db").to_string_lossy().to_string();
+        let db = VisionDatabase::shared(&path).unwrap();
+
+        let done = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let readers: Vec<_> = (0..2)
+            .map(|_| {
+                let (db, done) = (Arc::clone(&db), Arc::clone(&done));
+                std::thread::spawn(move || {
+                    let (mut queries, mut slowest) = (0u32, Duration::ZERO);
+                    while !done.load(std::sync::atomic::Ordering::Relaxed) {
+                        let t = Instant::now();
+                        db.get_statistics(Some("bench"), 24).unwrap();
+                        db.execute_query("SELECT label, COUNT(*) FROM monitoring_history GROUP BY label").unwrap();
+                        slowest = slowest.max(t.elapsed());
+                        queries += 1;
+                    }
+                    (queries, slowest)
+                })
+            })
+            .collect();
+
+        let start = Instant::now();
+        for i in 0..N {
+            insert(&db, i).unwrap();
+        }
+        let elapsed = start.elapsed();
+        done.store(true, std::sync::atomic::Ordering::Relaxed);
+
+        for (n, r) in readers.into_iter().enumerate() {
+            let (queries, slowest) = r.join().unwrap();
+            println!("reader {n}: {queries} query pairs, slowest {slowest:?}");
+            assert!(queries > 0);
+        }
+        println!(
+            "{N} inserts in {elapsed:?} ({:.0}/s)",
+            N as f64 / elapsed.as_secs_f64()
+        );
+        assert_eq!(db.get_statistics(Some("bench"), 24).unwrap().total_detections, N as u64);
+    }
+}
@@ -1,15 +1,21 @@
 /// Object Detector — YOLOv8s via ONNX Runtime
 ///
 /// Classifies detected objects into 20 classes.
-/// Platform selection at runtime:
-///   - Intel N5105: OpenVINO Execution Provider
+/// Platform selection at runtime (`detector.device`):
+///   - Intel N5105: OpenVINO Execution Provider (GPU, or CPU)
 ///   - RPi5: CPU (ARM NEON auto-detected by ort)
+/// A device that fails to initialise falls back to the next one in the chain
+/// (ending at plain CPU); the provider actually used, the fallback reason and
+/// the inference latency are reported by [`Detector::info`].
 
 use anyhow::{anyhow, Result};
 use ndarray::Array4;
 use opencv::{core::Mat, imgproc, prelude::*};
 use ort::session::Session;
-use tracing::debug;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::{debug, info, warn};
 
 /// The 20 classes we care about.
 /// Everything else from COCO maps to `Unknown`.
@@ -38,6 +44,21 @@ pub enum ObjectClass {
 }
 
 impl ObjectClass {
+    /// Every class the detector can report (without `Unknown`).
+    pub const ALL: [ObjectClass; 19] = [
+        ObjectClass::Person, ObjectClass::Car, ObjectClass::Truck, ObjectClass::Bus,
+        ObjectClass::Motorcycle, ObjectClass::Bicycle, ObjectClass::Dog, ObjectClass::Cat,
+        ObjectClass::Bird, ObjectClass::Horse, ObjectClass::Backpack, ObjectClass::Handbag,
+        ObjectClass::Suitcase, ObjectClass::Umbrella, ObjectClass::Bottle, ObjectClass::Chair,
+        ObjectClass::Laptop, ObjectClass::CellPhone, ObjectClass::Clock,
+    ];
+
+    /// Parse a label as written in the config ("person", "cell phone").
+    pub fn from_label(label: &str) -> Option<Self> {
+        let label = label.trim();
+        Self::ALL.into_iter().find(|c| c.as_str().eq_ignore_ascii_case(label))
+    }
+
     pub fn as_str(&self) -> &'static str {
         match self {
             ObjectClass::Person     => "person",
@@ -99,6 +120,65 @@ pub struct Detection {
     pub bbox_norm: (f32, f32, f32, f32),
 }
 
+/// Requested inference device (`detector.device`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DetectorDevice {
+    Cpu,
+    Gpu,
+    #[default]
+    Auto,
+}
+
+/// ONNX Runtime execution provider a session was built with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Provider {
+    OpenVinoGpu,
+    OpenVinoCpu,
+    Cpu,
+}
+
+impl Provider {
+    fn label(&self) -> &'static str {
+        match self {
+            Provider::OpenVinoGpu => "OpenVINO (GPU)",
+            Provider::OpenVinoCpu => "OpenVINO (CPU)",
+            Provider::Cpu         => "CPU",
+        }
+    }
+}
+
+/// Providers tried in order for `device`; the last one always works.
+fn provider_chain(device: DetectorDevice) -> &'static [Provider] {
+    match device {
+        DetectorDevice::Cpu  => &[Provider::Cpu],
+        DetectorDevice::Gpu  => &[Provider::OpenVinoGpu, Provider::Cpu],
+        DetectorDevice::Auto => &[Provider::OpenVinoGpu, Provider::OpenVinoCpu, Provider::Cpu],
+    }
+}
+
+/// Weight of the newest sample in `inference_ms_ema`.
+const LATENCY_EMA_ALPHA: f64 = 0.1;
+
+fn ema(prev: Option<f64>, sample: f64) -> f64 {
+    prev.map_or(sample, |p| p + LATENCY_EMA_ALPHA * (sample - p))
+}
+
+/// How the detector runs (`vision_detector_info`, pipeline stats).
+#[derive(Debug, Clone, Serialize)]
+pub struct DetectorInfo {
+    pub requested_device: DetectorDevice,
+    /// Provider in use: "OpenVINO (GPU)", "OpenVINO (CPU)" or "CPU"
+    pub execution_provider: String,
+    /// Why an earlier provider in the chain was not used; None without a fallback
+    pub fallback_reason: Option<String>,
+    /// Session creation, including failed attempts
+    pub init_ms: u64,
+    pub inferences: u64,
+    /// Moving average of the model run time (without pre/post-processing)
+    pub inference_ms_ema: Option<f64>,
+}
+
 /// YOLOv8n wrapper using ONNX Runtime (ort 2.0).
 pub struct Detector {
     session: Session,
@@ -106,6 +186,24 @@ pub struct Detector {
     conf_threshold: f32,
     #[allow(dead_code)]
     nms_threshold: f32,
+    info: Arc<Mutex<DetectorInfo>>,
+}
+
+fn build_session(model_path: &str, provider: Provider) -> Result<Session> {
+    // ort 2.0: global init is automatic, session builder directly
+    let builder = Session::builder()?
+        .with_optimization_level(ort::session::builder::GraphOptimizationLevel::Level3)?
+        .with_intra_threads(2)?;
+    let builder = match provider {
+        Provider::OpenVinoGpu | Provider::OpenVinoCpu => {
+            let device_type = if provider == Provider::OpenVinoGpu { "GPU" } else { "CPU" };
+            builder.with_execution_providers([
+                ort::ep::OpenVINO::default().with_device_type(device_type).build().error_on_failure(),
+            ])?
+        }
+        Provider::Cpu => builder,
+    };
+    Ok(builder.commit_from_file(model_path)?)
 }
 
 impl Detector {
@@ -114,22 +212,68 @@ impl Detector {
         input_size: u32,
         conf_threshold: f32,
         nms_threshold: f32,
-        _use_openvino: bool,
+        device: DetectorDevice,
     ) -> Result<Self> {
-        // ort 2.0: global init is automatic, session builder directly
-        let session = Session::builder()?
-            .with_optimization_level(ort::session::builder::GraphOptimizationLevel::Level3)?
-            .with_intra_threads(2)?
-            .commit_from_file(model_path)?;
+        let started = Instant::now();
+        let chain = provider_chain(device);
+        let mut fallback_reason = None;
+        let mut built = None;
+        for (i, provider) in chain.iter().enumerate() {
+            match build_session(model_path, *provider) {
+                Ok(session) => { built = Some((session, *provider)); break; }
+                Err(e) if i + 1 < chain.len() => {
+                    let reason = format!("{} unavailable: {}", provider.label(), e);
+                    if device == DetectorDevice::Gpu {
+                        warn!("Detector: {} — falling back to {}", reason, chain[i + 1].label());
+                    } else {
+                        info!("Detector: {} — trying {}", reason, chain[i + 1].label());
+                    }
+                    fallback_reason.get_or_insert(reason);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        let (session, provider) = built.ok_or_else(|| anyhow!("no execution provider for {:?}", device))?;
+        let init_ms = started.elapsed().as_millis() as u64;
+        info!("Detector: {} on {} (requested {:?}, {} ms)", model_path, provider.label(), device, init_ms);
 
         Ok(Self {
             session,
             input_size,
             conf_threshold,
             nms_threshold,
+            info: Arc::new(Mutex::new(DetectorInfo {
+                requested_device: device,
+                execution_provider: provider.label().to_string(),
+                fallback_reason,
+                init_ms,
+                inferences: 0,
+                inference_ms_ema: None,
+            })),
         })
     }
 
+    pub fn info(&self) -> DetectorInfo {
+        self.info.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// The live [`DetectorInfo`], for readers outside the capture thread.
+    pub fn shared_info(&self) -> Arc<Mutex<DetectorInfo>> {
+        Arc::clone(&self.info)
+    }
+
+    fn record_inference(&self, started: Instant) {
+        let ms = started.elapsed().as_secs_f64() * 1000.0;
+        let mut info = self.info.lock().unwrap_or_else(|e| e.into_inner());
+        info.inferences += 1;
+        info.inference_ms_ema = Some(ema(info.inference_ms_ema, ms));
+    }
+
+    /// Change the confidence cut at runtime (config reload).
+    pub fn set_conf_threshold(&mut self, conf_threshold: f32) {
+        self.conf_threshold = conf_threshold;
+    }
+
     /// Run inference on JPEG bytes (decode → detect).
     pub fn detect_from_jpeg(&self, jpeg_bytes: &[u8]) -> Result<Option<Detection>> {
         let img = image::load_from_memory_with_format(jpeg_bytes, image::ImageFormat::Jpeg)?;
@@ -162,7 +306,9 @@ impl Detector {
         imgproc::cvt_color(&letterboxed, &mut rgb, imgproc::COLOR_BGR2RGB, 0)?;
         let data = mat_to_chw_f32(&rgb, sz as usize)?;
         let array = Array4::from_shape_vec((1, 3, sz as usize, sz as usize), data)?;
+        let run_started = Instant::now();
         let outputs = self.session.run(ort::inputs!["images" => array.view()]?)?;
+        self.record_inference(run_started);
         let output_tensor = outputs[0]
             .try_extract_tensor::<f32>()
             .map_err(|e| anyhow!("Failed to extract output tensor: {}", e))?;
@@ -218,7 +364,9 @@ impl Detector {
         )?;
 
         // ── Run model (ort 2.0 API) ────────────────────────────────────────
+        let run_started = Instant::now();
         let outputs = self.session.run(ort::inputs!["images" => array.view()]?)?;
+        self.record_inference(run_started);
 
         // YOLOv8 output: [1, 84, 8400] (84 = 4 bbox + 80 class scores)
         let output_tensor = outputs[0]
@@ -342,3 +490,19 @@ fn mat_to_chw_f32(mat: &Mat, size: usize) -> Result<Vec<f32>> {
     }
     Ok(out)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_chain_ends_on_cpu() {
+        for device in [DetectorDevice::Cpu, DetectorDevice::Gpu, DetectorDevice::Auto] {
+            assert_eq!(provider_chain(device).last(), Some(&Provider::Cpu));
+        }
+        assert_eq!(provider_chain(DetectorDevice::Gpu)[0].label(), "OpenVINO (GPU)");
+
+        let avg = [10.0, 20.0, 20.0].into_iter().fold(None, |prev, ms| Some(ema(prev, ms)));
+        assert!((avg.unwrap() - 11.9).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,475 @@
+//! vision_export.rs — Export monitoring detections for people without Broxeen.
+//!
+//! `vision_export` writes either a CSV of detection rows or a zip archive with
+//! `detections.json`, `llm_events.json` and `thumbnails/<id>.jpg|webp`. Rows are
+//! streamed from SQLite straight into the output file, so a month of
+//! detections never sits in memory; the zip (stored entries, zip64 when
+//! needed) is written with the `zip` crate. Works with both the native (`vision`)
+//! and the Python pipeline schema — columns are taken from the table itself.
+//!
+//! Progress is emitted as `broxeen:vision_export_progress` every 500 rows.
+
+use rusqlite::types::ValueRef;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Seek, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::image_meta::ThumbnailFormat;
+use crate::logging::backend_info;
+
+const PROGRESS_EVERY: u64 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Zip,
+}
+
+impl ExportFormat {
+    fn parse(format: Option<&str>) -> Result<Self, String> {
+        match format.map(|f| f.trim().to_lowercase()).as_deref() {
+            None | Some("") | Some("zip") | Some("json") => Ok(ExportFormat::Zip),
+            Some("csv") => Ok(ExportFormat::Csv),
+            Some(other) => Err(format!("Unknown export format: {} (use csv or zip)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VisionExportResult {
+    pub output_path: String,
+    pub format: String,
+    pub detections: u64,
+    pub llm_events: u64,
+    pub thumbnails: u64,
+    pub size_bytes: u64,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportProgress {
+    pub output_path: String,
+    /// "detections", "llm_events" or "thumbnails"
+    pub phase: String,
+    pub rows: u64,
+}
+
+/// Filters shared by every query of one export.
+struct ExportFilter {
+    camera_id: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+}
+
+impl ExportFilter {
+    fn where_clause(&self) -> (String, Vec<String>) {
+        let mut conditions = vec!["1=1".to_string()];
+        let mut params = Vec::new();
+        if let Some(cam) = &self.camera_id {
+            params.push(cam.clone());
+            conditions.push(format!("camera_id = ?{}", params.len()));
+        }
+        if let Some(from) = &self.from {
+            params.push(from.clone());
+            conditions.push(format!("timestamp >= ?{}", params.len()));
+        }
+        if let Some(to) = &self.to {
+            params.push(to.clone());
+            conditions.push(format!("timestamp < ?{}", params.len()));
+        }
+        (conditions.join(" AND "), params)
+    }
+}
+
+/// `YYYY-MM-DD` (a whole day; `to` is inclusive) or RFC 3339 → text bound
+/// comparable with stored ISO timestamps.
+fn normalize_bound(raw: Option<&str>, end: bool) -> Result<Option<String>, String> {
+    let Some(raw) = raw.map(str::trim).filter(|s| !s.is_empty()) else {
+        return Ok(None);
+    };
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(raw, "%Y-%m-%d") {
+        let date = if end { date.succ_opt().unwrap_or(date) } else { date };
+        return Ok(Some(date.format("%Y-%m-%d").to_string()));
+    }
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(raw) {
+        return Ok(Some(dt.with_timezone(&chrono::Utc).format("%Y-%m-%dT%H:%M:%S").to_string()));
+    }
+    if let Ok(dt) = chrono::NaiveDateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M:%S") {
+        return Ok(Some(dt.format("%Y-%m-%dT%H:%M:%S").to_string()));
+    }
+    Err(format!("Invalid date: {} (use YYYY-MM-DD or RFC 3339)", raw))
+}
+
+fn table_exists(conn: &Connection, table: &str) -> bool {
+    conn.query_row(
+        "SELECT 1 FROM sqlite_master WHERE type IN ('table','view') AND name = ?1",
+        [table],
+        |_| Ok(()),
+    )
+    .is_ok()
+}
+
+// ── Row encoding ─────────────────────────────────────
+
+fn json_value(v: ValueRef) -> serde_json::Value {
+    match v {
+        ValueRef::Null => serde_json::Value::Null,
+        ValueRef::Integer(i) => i.into(),
+        ValueRef::Real(f) => serde_json::Number::from_f64(f).map_or(serde_json::Value::Null, Into::into),
+        ValueRef::Text(t) => String::from_utf8_lossy(t).into_owned().into(),
+        ValueRef::Blob(b) => format!("[BLOB {}B]", b.len()).into(),
+    }
+}
+
+fn csv_field(v: ValueRef) -> String {
+    let raw = match v {
+        ValueRef::Null => return String::new(),
+        ValueRef::Integer(i) => return i.to_string(),
+        ValueRef::Real(f) => return f.to_string(),
+        ValueRef::Text(t) => String::from_utf8_lossy(t).into_owned(),
+        ValueRef::Blob(b) => format!("[BLOB {}B]", b.len()),
+    };
+    csv_escape(&raw)
+}
+
+fn csv_escape(raw: &str) -> String {
+    if raw.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", raw.replace('"', "\"\""))
+    } else {
+        raw.to_string()
+    }
+}
+
+/// Exported columns of `table`: all but the thumbnail blob.
+fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})")).map_err(|e| e.to_string())?;
+    let names = stmt.query_map([], |r| r.get::<_, String>(1)).map_err(|e| e.to_string())?;
+    Ok(names.filter_map(|n| n.ok()).filter(|n| n != "thumbnail").collect())
+}
+
+/// Stream `SELECT <table_columns> FROM table` rows into `on_row`.
+fn for_each_row(
+    conn: &Connection,
+    table: &str,
+    filter: &ExportFilter,
+    mut on_row: impl FnMut(&[String], &rusqlite::Row) -> Result<(), String>,
+) -> Result<u64, String> {
+    let columns = table_columns(conn, table)?;
+    let (where_clause, params) = filter.where_clause();
+    let sql = format!(
+        "SELECT {} FROM {table} WHERE {where_clause} ORDER BY timestamp, id",
+        columns.iter().map(|c| format!("\"{}\"", c)).collect::<Vec<_>>().join(", ")
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let mut rows = stmt.query(rusqlite::params_from_iter(params.iter())).map_err(|e| e.to_string())?;
+    let mut count = 0;
+    while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        on_row(&columns, row)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn row_json(columns: &[String], row: &rusqlite::Row) -> Result<String, String> {
+    let mut obj = serde_json::Map::new();
+    for (i, col) in columns.iter().enumerate() {
+        obj.insert(col.clone(), json_value(row.get_ref(i).map_err(|e| e.to_string())?));
+    }
+    serde_json::to_string(&obj).map_err(|e| e.to_string())
+}
+
+// ── Export ───────────────────────────────────────────
+
+/// Stream one table as a JSON array into its own zip entry.
+fn zip_json_table<W: Write + Seek>(
+    zip: &mut ZipWriter<W>,
+    conn: &Connection,
+    table: &str,
+    filter: &ExportFilter,
+    progress: &mut impl FnMut(&str, u64),
+) -> Result<u64, String> {
+    // JSON of a long range may pass 4 GB; thumbnails never do
+    let options = zip_options().large_file(true);
+    zip.start_file(format!("{table}.json"), options).map_err(|e| e.to_string())?;
+    let write = |zip: &mut ZipWriter<W>, bytes: &[u8]| zip.write_all(bytes).map_err(|e| e.to_string());
+    write(zip, b"[")?;
+    let mut n = 0u64;
+    if table_exists(conn, table) {
+        for_each_row(conn, table, filter, |columns, row| {
+            if n > 0 {
+                write(zip, b",\n")?;
+            }
+            write(zip, row_json(columns, row)?.as_bytes())?;
+            n += 1;
+            if n.is_multiple_of(PROGRESS_EVERY) {
+                progress(table, n);
+            }
+            Ok(())
+        })?;
+    }
+    write(zip, b"]\n")?;
+    Ok(n)
+}
+
+/// Stored entries: JSON is small next to the JPEGs, which don't compress
+fn zip_options() -> SimpleFileOptions {
+    SimpleFileOptions::default().compression_method(CompressionMethod::Stored)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn export_to_file(
+    db_path: &str,
+    camera_id: Option<String>,
+    from: Option<&str>,
+    to: Option<&str>,
+    format: ExportFormat,
+    include_thumbnails: bool,
+    output_path: &Path,
+    mut progress: impl FnMut(&str, u64),
+) -> Result<VisionExportResult, String> {
+    let start = std::time::Instant::now();
+    let conn = crate::motion_detection::open_monitoring_db(db_path).map_err(|e| format!("Cannot open detections DB at {}: {}", db_path, e))?;
+    if !table_exists(&conn, "detections") {
+        return Err(format!("No detections table in {}", db_path));
+    }
+    let filter = ExportFilter {
+        camera_id: camera_id.filter(|c| !c.is_empty()),
+        from: normalize_bound(from, false)?,
+        to: normalize_bound(to, true)?,
+    };
+
+    if let Some(parent) = output_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Cannot create {}: {}", parent.display(), e))?;
+    }
+    let file = File::create(output_path).map_err(|e| format!("Cannot create {}: {}", output_path.display(), e))?;
+    let mut out = BufWriter::new(file);
+
+    let mut result = VisionExportResult {
+        output_path: output_path.to_string_lossy().to_string(),
+        format: if format == ExportFormat::Csv { "csv" } else { "zip" }.to_string(),
+        detections: 0,
+        llm_events: 0,
+        thumbnails: 0,
+        size_bytes: 0,
+        duration_ms: 0,
+    };
+
+    match format {
+        ExportFormat::Csv => {
+            let write = |out: &mut BufWriter<File>, line: String| {
+                out.write_all(line.as_bytes()).map_err(|e| e.to_string())
+            };
+            // The header is written even when no row matches
+            let header: Vec<String> = table_columns(&conn, "detections")?.iter().map(|c| csv_escape(c)).collect();
+            write(&mut out, header.join(",") + "\n")?;
+            let mut n = 0u64;
+            for_each_row(&conn, "detections", &filter, |columns, row| {
+                let fields: Vec<String> = (0..columns.len())
+                    .map(|i| row.get_ref(i).map(csv_field).map_err(|e| e.to_string()))
+                    .collect::<Result<_, _>>()?;
+                write(&mut out, fields.join(",") + "\n")?;
+                n += 1;
+                if n.is_multiple_of(PROGRESS_EVERY) {
+                    progress("detections", n);
+                }
+                Ok(())
+            })?;
+            result.detections = n;
+            out.flush().map_err(|e| e.to_string())?;
+        }
+        ExportFormat::Zip => {
+            let mut zip = ZipWriter::new(out);
+            result.detections = zip_json_table(&mut zip, &conn, "detections", &filter, &mut progress)?;
+            result.llm_events = zip_json_table(&mut zip, &conn, "llm_events", &filter, &mut progress)?;
+
+            if include_thumbnails {
+                let (where_clause, params) = filter.where_clause();
+                let sql = format!(
+                    "SELECT id, thumbnail FROM detections WHERE {where_clause} AND thumbnail IS NOT NULL ORDER BY timestamp, id"
+                );
+                let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+                let mut rows = stmt.query(rusqlite::params_from_iter(params.iter())).map_err(|e| e.to_string())?;
+                while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+                    let id: i64 = row.get(0).map_err(|e| e.to_string())?;
+                    let ValueRef::Blob(blob) = row.get_ref(1).map_err(|e| e.to_string())? else { continue };
+                    if blob.is_empty() {
+                        continue;
+                    }
+                    let ext = ThumbnailFormat::detect(blob).unwrap_or_default().extension();
+                    zip.start_file(format!("thumbnails/{id}.{ext}"), zip_options()).map_err(|e| e.to_string())?;
+                    zip.write_all(blob).map_err(|e| e.to_string())?;
+                    result.thumbnails += 1;
+                    if result.thumbnails.is_multiple_of(PROGRESS_EVERY) {
+                        progress("thumbnails", result.thumbnails);
+                    }
+                }
+            }
+
+            zip.finish().map_err(|e| e.to_string())?.flush().map_err(|e| e.to_string())?;
+        }
+    }
+
+    result.size_bytes = std::fs::metadata(output_path).map(|m| m.len()).unwrap_or(0);
+    result.duration_ms = start.elapsed().as_millis() as u64;
+    Ok(result)
+}
+
+/// Export detections (and, for zip, LLM narratives + thumbnails) to a file.
+/// `from` / `to` accept `YYYY-MM-DD` (whole days, `to` inclusive) or RFC 3339.
+/// CSV holds detection rows only; thumbnails go into the zip's `thumbnails/`.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn vision_export(
+    app: tauri::AppHandle,
+    db_path: String,
+    camera_id: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    format: Option<String>,
+    include_thumbnails: Option<bool>,
+    output_path: String,
+) -> Result<VisionExportResult, String> {
+    use tauri::Emitter;
+
+    let format = ExportFormat::parse(format.as_deref())?;
+    let db = crate::motion_detection::resolve_db_path(&db_path);
+    backend_info(format!(
+        "Command vision_export invoked: db={} camera={:?} from={:?} to={:?} format={:?} → {}",
+        db, camera_id, from, to, format, output_path
+    ));
+
+    let result = tokio::task::spawn_blocking(move || {
+        let target = output_path.clone();
+        export_to_file(
+            &db,
+            camera_id,
+            from.as_deref(),
+            to.as_deref(),
+            format,
+            include_thumbnails.unwrap_or(false),
+            Path::new(&output_path),
+            |phase, rows| {
+                let _ = app.emit(
+                    "broxeen:vision_export_progress",
+                    ExportProgress { output_path: target.clone(), phase: phase.to_string(), rows },
+                );
+            },
+        )
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))??;
+
+    backend_info(format!(
+        "vision_export done: {} detections, {} llm events, {} thumbnails, {} bytes in {}ms",
+        result.detections, result.llm_events, result.thumbnails, result.size_bytes, result.duration_ms
+    ));
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn python_schema_db(dir: &Path) -> String {
+        let path = dir.join("detections.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE detections (
+                id INTEGER PRIMARY KEY AUTOINCREMENT, timestamp TEXT NOT NULL, camera_id TEXT NOT NULL,
+                label TEXT NOT NULL, confidence REAL, llm_description TEXT, thumbnail BLOB NOT NULL
+            );",
+        )
+        .unwrap();
+        for i in 0..1200 {
+            let day = if i < 1100 { "2024-07-14" } else { "2024-07-15" };
+            conn.execute(
+                "INSERT INTO detections (timestamp, camera_id, label, confidence, llm_description, thumbnail)
+                 VALUES (?1, 'front', 'person', 0.9, ?2, ?3)",
+                rusqlite::params![
+                    format!("{}T12:{:02}:{:02}", day, (i / 60) % 60, i % 60),
+                    if i == 0 { "Kurier, \"DHL\"" } else { "" },
+                    vec![0xFFu8, 0xD8, i as u8],
+                ],
+            )
+            .unwrap();
+        }
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_csv_export_for_one_day() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = python_schema_db(dir.path());
+        let out = dir.path().join("out/day.csv");
+        let mut events = Vec::new();
+
+        let result = export_to_file(
+            &db, Some("front".into()), Some("2024-07-14"), Some("2024-07-14"),
+            ExportFormat::Csv, true, &out, |phase, rows| events.push((phase.to_string(), rows)),
+        )
+        .unwrap();
+
+        assert_eq!(result.detections, 1100);
+        assert_eq!(events, vec![("detections".to_string(), 500), ("detections".to_string(), 1000)]);
+        let csv = std::fs::read_to_string(&out).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("id,timestamp,camera_id,label,confidence,llm_description"));
+        assert!(lines.next().unwrap().ends_with(",\"Kurier, \"\"DHL\"\"\""));
+        assert_eq!(csv.lines().count(), 1101);
+        assert_eq!(result.size_bytes, csv.len() as u64);
+
+        // No matching rows: header only
+        let empty = dir.path().join("empty.csv");
+        let result = export_to_file(
+            &db, Some("garden".into()), None, None, ExportFormat::Csv, false, &empty, |_, _| {},
+        )
+        .unwrap();
+        assert_eq!(result.detections, 0);
+        assert_eq!(
+            std::fs::read_to_string(&empty).unwrap(),
+            "id,timestamp,camera_id,label,confidence,llm_description\n"
+        );
+    }
+
+    #[test]
+    fn test_zip_export_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = python_schema_db(dir.path());
+        let out = dir.path().join("export.zip");
+
+        let result = export_to_file(&db, None, None, None, ExportFormat::Zip, true, &out, |_, _| {}).unwrap();
+        assert_eq!(result.detections, 1200);
+        assert_eq!(result.llm_events, 0, "Python DB has no llm_events table");
+        assert_eq!(result.thumbnails, 1200);
+
+        let bytes = std::fs::read(&out).unwrap();
+        assert_eq!(result.size_bytes, bytes.len() as u64);
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes)).unwrap();
+        assert_eq!(archive.len(), 1202);
+        assert!(archive.by_name("thumbnails/1.jpg").is_ok());
+
+        // detections.json is valid JSON without the thumbnail column
+        let rows: Vec<serde_json::Value> = serde_json::from_reader(archive.by_name("detections.json").unwrap()).unwrap();
+        assert_eq!(rows.len(), 1200);
+        assert!(rows[0].get("thumbnail").is_none());
+        assert_eq!(rows[0]["label"], "person");
+        let events: Vec<serde_json::Value> = serde_json::from_reader(archive.by_name("llm_events.json").unwrap()).unwrap();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_bounds_and_format() {
+        assert_eq!(normalize_bound(Some("2024-07-14"), true).unwrap().as_deref(), Some("2024-07-15"));
+        assert_eq!(
+            normalize_bound(Some("2024-07-14T10:00:00+02:00"), false).unwrap().as_deref(),
+            Some("2024-07-14T08:00:00")
+        );
+        assert!(normalize_bound(Some("yesterday"), false).is_err());
+        assert_eq!(ExportFormat::parse(Some("CSV")).unwrap(), ExportFormat::Csv);
+        assert!(ExportFormat::parse(Some("xlsx")).is_err());
+    }
+}
@@ -11,6 +11,7 @@ use serde::Serialize;
 use serde_json::Value;
 use tracing::{debug, info, warn};
 
+use crate::local_time::LocalClock;
 use crate::vision_config::LlmConfig;
 
 // ─── Provider config ─────────────────────────────────────────────────────────
@@ -151,6 +152,7 @@ impl LlmClient {
     /// Send a scene batch (multiple crops + timeline) → narrative.
     pub async fn describe_scene(
         &self, crops: &[(Vec<u8>, String)], timeline: &str, camera_id: &str,
+        camera_location: &str, template: &str,
     ) -> Result<SceneNarrativeResult> {
         let mut content = Vec::new();
         for (i, (jpeg, ts)) in crops.iter().enumerate() {
@@ -163,15 +165,7 @@ impl LlmClient {
             });
         }
         content.push(ContentPart::Text {
-            text: format!(
-                "{}\n\nThese images show detected objects from camera '{}' during this period.\n\
-                 Write a concise 2-4 sentence summary of what happened:\n\
-                 - Which objects appeared and when\n\
-                 - Their movement (direction, behaviour)\n\
-                 - Any notable patterns\n\
-                 Be specific with times. Past tense. No lists.",
-                timeline, camera_id
-            ),
+            text: render_scene_prompt(template, timeline, camera_id, camera_location),
         });
         let messages = vec![Message { role: "user".into(), content }];
         let provider_label = self.active_provider_label();
@@ -182,23 +176,13 @@ impl LlmClient {
         })
     }
 
-    /// Text-to-SQL: convert natural language query → SQL against our schema.
-    pub async fn text_to_sql(&self, question: &str, schema: &str) -> Result<String> {
+    /// Text-to-SQL: convert natural language query → SQL against our schema,
+    /// with "today" / "yesterday" taken from `clock`.
+    pub async fn text_to_sql(&self, question: &str, schema: &str, clock: &LocalClock) -> Result<String> {
         let messages = vec![Message {
             role: "user".into(),
             content: vec![ContentPart::Text {
-                text: format!(
-                    "SQLite schema:\n```sql\n{schema}\n```\n\n\
-                     Convert this question to a single SQLite SELECT query.\n\
-                     Question: {question}\n\n\
-                     Rules:\n\
-                     - Output ONLY the SQL query, nothing else\n\
-                     - No markdown, no explanation\n\
-                     - Use only tables and columns from the schema\n\
-                     - For time filters: use datetime('now', '-N hours/days') or date comparisons\n\
-                     - 'today' means date(timestamp) = date('now')\n\
-                     - Labels are lowercase: 'person', 'car', 'truck', etc."
-                ),
+                text: format!("{}\n\nQuestion: {question}", crate::query_nl::text_to_sql_prompt(schema, clock)),
             }],
         }];
         let sql = self.call_with_fallback(messages, 200).await?;
@@ -235,6 +219,7 @@ impl LlmClient {
     async fn call_provider(
         &self, provider: &LlmProvider, messages: Vec<Message>, max_tokens: u32,
     ) -> Result<String> {
+        let metered = matches!(provider, LlmProvider::OpenRouter { .. });
         let (base_url, model, auth_value) = match provider {
             LlmProvider::OpenRouter { api_key, model } => (
                 "https://openrouter.ai/api/v1/chat/completions".to_string(),
@@ -249,7 +234,7 @@ impl LlmClient {
         };
 
         let req_body = ChatRequest {
-            model,
+            model: model.clone(),
             messages,
             max_tokens,
             temperature: Some(0.2),
@@ -274,6 +259,9 @@ impl LlmClient {
         }
 
         let json: Value = resp.json().await?;
+        if metered {
+            crate::llm_usage::record("vision", &model, &json);
+        }
         json["choices"][0]["message"]["content"]
             .as_str()
             .map(|s| s.to_string())
@@ -281,6 +269,19 @@ impl LlmClient {
     }
 }
 
+// ─── Prompt templates ────────────────────────────────────────────────────────
+
+/// Fill the scene narrative template placeholders.
+pub fn render_scene_prompt(
+    template: &str, timeline: &str, camera_id: &str, camera_location: &str,
+) -> String {
+    let location = if camera_location.is_empty() { "unknown" } else { camera_location };
+    template
+        .replace("{timeline}", timeline)
+        .replace("{camera_id}", camera_id)
+        .replace("{camera_location}", location)
+}
+
 // ─── Response parsers ────────────────────────────────────────────────────────
 
 const VALID_LABELS: &[&str] = &[
@@ -333,4 +334,12 @@ mod tests {
         assert_eq!(r.label, "horse");
         assert_eq!(r.description, "Brown horse trotting");
     }
+
+    #[test]
+    fn render_prompt_location() {
+        let p = render_scene_prompt("{camera_id} @ {camera_location}: {timeline}", "t", "cam1", "Lipowa 12");
+        assert_eq!(p, "cam1 @ Lipowa 12: t");
+        let p = render_scene_prompt("{camera_location}", "", "cam1", "");
+        assert_eq!(p, "unknown");
+    }
 }
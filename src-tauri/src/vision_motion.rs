@@ -2,10 +2,13 @@
 ///
 /// Processes frames, extracts foreground contours, crops moving objects
 /// and resizes them to ≤ max_output_px (longest edge).
+///
+/// `LightingMonitor` tracks day/night from mean frame luminance so the
+/// pipeline can switch MOG2 to less sensitive parameters under IR.
 
 use anyhow::Result;
 use opencv::{
-    core::{Mat, Point, Scalar, Size, Vector, BORDER_DEFAULT},
+    core::{no_array, Mat, Point, Scalar, Size, Vector, BORDER_DEFAULT},
     imgproc::{
         self, CHAIN_APPROX_SIMPLE, MORPH_CLOSE, MORPH_ELLIPSE, MORPH_OPEN, RETR_EXTERNAL,
     },
@@ -64,6 +67,13 @@ impl MotionDetector {
         })
     }
 
+    /// Switch sensitivity at runtime (day/night) without resetting the background model.
+    pub fn set_sensitivity(&mut self, var_threshold: f64, min_area: f64) -> Result<()> {
+        self.subtractor.set_var_threshold(var_threshold)?;
+        self.min_area = min_area;
+        Ok(())
+    }
+
     /// Process a single frame. Returns list of detected moving objects.
     /// `frame` should be BGR, full resolution from capture.
     pub fn process_frame(&mut self, frame: &Mat) -> Result<Vec<MovingObject>> {
@@ -184,3 +194,94 @@ impl MotionDetector {
         Ok(objects)
     }
 }
+
+// ─── Day / night ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lighting {
+    Day,
+    Night,
+}
+
+impl Lighting {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Lighting::Day => "day",
+            Lighting::Night => "night",
+        }
+    }
+}
+
+/// Mean luminance (0–255, BT.601 weights) of a BGR frame.
+pub fn mean_luminance(frame: &Mat) -> Result<f64> {
+    let m = opencv::core::mean(frame, &no_array())?;
+    if frame.channels() < 3 {
+        return Ok(m[0]);
+    }
+    Ok(0.114 * m[0] + 0.587 * m[1] + 0.299 * m[2])
+}
+
+/// Day/night state with hysteresis: night below `boundary`, day again only
+/// above `boundary + hysteresis`, so dusk does not flap between the two.
+pub struct LightingMonitor {
+    current: Option<Lighting>,
+    boundary: f64,
+    hysteresis: f64,
+    forced_night: bool,
+}
+
+impl LightingMonitor {
+    pub fn new(boundary: f64, hysteresis: f64, forced_night: bool) -> Self {
+        Self { current: None, boundary, hysteresis: hysteresis.max(0.0), forced_night }
+    }
+
+    pub fn current(&self) -> Lighting {
+        if self.forced_night {
+            return Lighting::Night;
+        }
+        self.current.unwrap_or(Lighting::Day)
+    }
+
+    /// Feed one luminance sample. Returns the new state when it changed
+    /// (including the very first sample).
+    pub fn update(&mut self, luminance: f64) -> Option<Lighting> {
+        let next = if self.forced_night {
+            Lighting::Night
+        } else {
+            match self.current {
+                Some(Lighting::Night) if luminance <= self.boundary + self.hysteresis => Lighting::Night,
+                Some(Lighting::Day) if luminance >= self.boundary => Lighting::Day,
+                _ if luminance < self.boundary => Lighting::Night,
+                _ => Lighting::Day,
+            }
+        };
+        if self.current == Some(next) {
+            return None;
+        }
+        self.current = Some(next);
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lighting_hysteresis() {
+        let mut m = LightingMonitor::new(50.0, 15.0, false);
+        assert_eq!(m.update(120.0), Some(Lighting::Day));
+        assert_eq!(m.update(55.0), None);
+        assert_eq!(m.update(49.0), Some(Lighting::Night));
+        // Between boundary and boundary + hysteresis: stays night
+        assert_eq!(m.update(52.0), None);
+        assert_eq!(m.update(64.0), None);
+        assert_eq!(m.current(), Lighting::Night);
+        assert_eq!(m.update(66.0), Some(Lighting::Day));
+
+        let mut forced = LightingMonitor::new(50.0, 15.0, true);
+        assert_eq!(forced.update(200.0), Some(Lighting::Night));
+        assert_eq!(forced.update(220.0), None);
+        assert_eq!(forced.current(), Lighting::Night);
+    }
+}
@@ -2,10 +2,15 @@
 //!
 //! Takes a completed track's position history and derives:
 //! - direction (left/right/up/down/stationary)
-//! - speed label (slow/moderate/fast/stationary)
+//! - speed label (slow/moderate/fast/stationary) and, with a ground
+//!   calibration (`camera.pixels_per_meter` / `speed_reference`), speed in m/s
 //! - entry/exit zones (upper-left, centre, bottom-right, etc.)
-//! - human-readable description
+//! - human-readable description with a direction arrow
+//!
+//! Calibrated speeds assume the object moves on the ground plane at roughly
+//! the calibrated distance from the camera; strong perspective skews them.
 
+use crate::vision_config::CameraConfig;
 use crate::vision_tracker::CompletedTrack;
 
 // ─── Movement summary ────────────────────────────────────────────────────────
@@ -18,6 +23,25 @@ pub struct MovementSummary {
     pub entry_zone:  String,
     pub exit_zone:   String,
     pub duration_secs: f32,
+    /// Ground speed; `None` without a calibration
+    pub speed_mps:   Option<f32>,
+}
+
+/// Ground calibration for real-world speeds.
+#[derive(Debug, Clone, Copy)]
+pub struct SpeedCalibration {
+    pub pixels_per_meter: f32,
+    /// Upper bounds (m/s) of stationary / slow / moderate
+    pub cutoffs_mps: [f32; 3],
+}
+
+impl SpeedCalibration {
+    pub fn from_camera(cam: &CameraConfig) -> Option<Self> {
+        Some(Self {
+            pixels_per_meter: cam.calibrated_pixels_per_meter()?,
+            cutoffs_mps: cam.speed_cutoffs_mps,
+        })
+    }
 }
 
 // ─── Zone classification ─────────────────────────────────────────────────────
@@ -36,10 +60,65 @@ fn bbox_center(bbox: &(f32, f32, f32, f32)) -> (f32, f32) {
     ((bbox.0 + bbox.2) / 2.0, (bbox.1 + bbox.3) / 2.0)
 }
 
+/// Arrow for a direction from [`analyse_movement`].
+pub fn direction_arrow(direction: &str) -> &'static str {
+    match direction {
+        "right"       => "→",
+        "lower-right" => "↘",
+        "down"        => "↓",
+        "lower-left"  => "↙",
+        "left"        => "←",
+        "upper-left"  => "↖",
+        "up"          => "↑",
+        "upper-right" => "↗",
+        _             => "•",
+    }
+}
+
+// ─── Speed ───────────────────────────────────────────────────────────────────
+
+/// Ground speed (m/s) between two normalised positions `duration_secs` apart.
+fn speed_mps(
+    from: (f32, f32),
+    to: (f32, f32),
+    frame_size: (u32, u32),
+    duration_secs: f32,
+    pixels_per_meter: f32,
+) -> f32 {
+    let dx = (to.0 - from.0) * frame_size.0 as f32;
+    let dy = (to.1 - from.1) * frame_size.1 as f32;
+    (dx * dx + dy * dy).sqrt() / pixels_per_meter / duration_secs.max(0.1)
+}
+
+fn speed_label_mps(speed: f32, cutoffs: [f32; 3]) -> &'static str {
+    if speed < cutoffs[0] {
+        "stationary"
+    } else if speed < cutoffs[1] {
+        "slow"
+    } else if speed < cutoffs[2] {
+        "moderate"
+    } else {
+        "fast"
+    }
+}
+
+/// Uncalibrated fallback: speed in frame widths per second.
+fn speed_label_relative(speed: f32) -> &'static str {
+    if speed < 0.02 {
+        "stationary"
+    } else if speed < 0.10 {
+        "slow"
+    } else if speed < 0.30 {
+        "moderate"
+    } else {
+        "fast"
+    }
+}
+
 // ─── Analysis ────────────────────────────────────────────────────────────────
 
 /// Analyse movement of a completed track.
-pub fn analyse_movement(track: &CompletedTrack) -> MovementSummary {
+pub fn analyse_movement(track: &CompletedTrack, calibration: Option<&SpeedCalibration>) -> MovementSummary {
     if track.positions.len() < 2 {
         return MovementSummary {
             description: format!("stationary {}", track.class),
@@ -52,6 +131,7 @@ pub fn analyse_movement(track: &CompletedTrack) -> MovementSummary {
                 .map(|p| classify_zone(bbox_center(p).0, bbox_center(p).1))
                 .unwrap_or_else(|| "centre".to_string()),
             duration_secs: 0.0,
+            speed_mps: None,
         };
     }
 
@@ -89,25 +169,25 @@ pub fn analyse_movement(track: &CompletedTrack) -> MovementSummary {
         }.to_string()
     };
 
-    // Speed (normalised pixels/sec — relative to frame size)
-    let speed = distance / duration_secs;
-    let speed_label = if speed < 0.02 {
-        "stationary"
-    } else if speed < 0.10 {
-        "slow"
-    } else if speed < 0.30 {
-        "moderate"
-    } else {
-        "fast"
+    // Speed: m/s when calibrated, otherwise relative to frame size
+    let speed_mps = calibration.map(|c| {
+        speed_mps((fx, fy), (lx, ly), track.frame_size, duration_secs, c.pixels_per_meter)
+    });
+    let speed_label = match (speed_mps, calibration) {
+        (Some(mps), Some(c)) => speed_label_mps(mps, c.cutoffs_mps),
+        _ => speed_label_relative(distance / duration_secs),
     };
 
     let entry_zone = classify_zone(fx, fy);
     let exit_zone = classify_zone(lx, ly);
 
-    let description = format!(
-        "moving {}, {}→{}, {:.1}s",
-        direction, entry_zone, exit_zone, duration_secs
+    let mut description = format!(
+        "moving {} {}, {}→{}, {:.1}s",
+        direction, direction_arrow(&direction), entry_zone, exit_zone, duration_secs
     );
+    if let Some(mps) = speed_mps {
+        description.push_str(&format!(", {:.1} m/s", mps));
+    }
 
     MovementSummary {
         description,
@@ -116,13 +196,74 @@ pub fn analyse_movement(track: &CompletedTrack) -> MovementSummary {
         entry_zone,
         exit_zone,
         duration_secs,
+        speed_mps,
     }
 }
 
 /// Short movement tag for DB column.
 pub fn movement_tag(summary: &MovementSummary, class: &str) -> String {
-    format!(
+    let mut tag = format!(
         "{} {} {}→{} {:.1}s",
         class, summary.direction, summary.entry_zone, summary.exit_zone, summary.duration_secs
-    )
+    );
+    if let Some(mps) = summary.speed_mps {
+        tag.push_str(&format!(" {:.1}m/s", mps));
+    }
+    tag
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    fn track(positions: Vec<(f32, f32, f32, f32)>, secs: i64) -> CompletedTrack {
+        let now = Utc::now();
+        CompletedTrack {
+            id: uuid::Uuid::nil(),
+            class: "person".into(),
+            confidence: 0.9,
+            crops: Vec::new(),
+            thumbnail: None,
+            positions,
+            first_seen: now - Duration::seconds(secs),
+            last_seen: now,
+            hit_count: 10,
+            frame_size: (1920, 1080),
+        }
+    }
+
+    #[test]
+    fn speed_math() {
+        // 960 px across a 1920 px frame at 100 px/m = 9.6 m in 4 s
+        let v = speed_mps((0.25, 0.5), (0.75, 0.5), (1920, 1080), 4.0, 100.0);
+        assert!((v - 2.4).abs() < 1e-4, "{}", v);
+        // Vertical motion uses the frame height: 540 px = 5.4 m in 2 s
+        let v = speed_mps((0.5, 0.25), (0.5, 0.75), (1920, 1080), 2.0, 100.0);
+        assert!((v - 2.7).abs() < 1e-4, "{}", v);
+
+        let cut = [0.2, 1.0, 2.5];
+        assert_eq!(speed_label_mps(0.1, cut), "stationary");
+        assert_eq!(speed_label_mps(0.8, cut), "slow");
+        assert_eq!(speed_label_mps(1.4, cut), "moderate");
+        assert_eq!(speed_label_mps(3.0, cut), "fast");
+    }
+
+    #[test]
+    fn calibrated_track_runs() {
+        // Person crosses 0.8 of the frame width (1536 px ≈ 15.4 m at 100 px/m) in 5 s
+        let t = track(vec![(0.05, 0.4, 0.15, 0.9), (0.5, 0.4, 0.6, 0.9), (0.85, 0.4, 0.95, 0.9)], 5);
+        let cal = SpeedCalibration { pixels_per_meter: 100.0, cutoffs_mps: [0.2, 1.0, 2.5] };
+
+        let s = analyse_movement(&t, Some(&cal));
+        assert_eq!(s.direction, "right");
+        assert_eq!(s.speed_label, "fast");
+        assert!((s.speed_mps.unwrap() - 3.072).abs() < 0.01);
+        assert!(s.description.contains("→") && s.description.ends_with("3.1 m/s"), "{}", s.description);
+        assert!(movement_tag(&s, "person").ends_with("5.0s 3.1m/s"));
+
+        let s = analyse_movement(&t, None);
+        assert_eq!(s.speed_mps, None);
+        assert_eq!(s.speed_label, "moderate");
+    }
 }
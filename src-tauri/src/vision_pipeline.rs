@@ -2,27 +2,366 @@
 //!
 //! Track A (immediate): YOLO detection → tracker → movement analysis → DB (detections)
 //! Track B (1/min):     MinuteBuffer → LLM (OpenRouter / local) → DB (llm_events)
+//! Buffered events are spilled to the DB (`scene.spill_max_mb`); a start after a
+//! crash first flushes what the last run left, tagging the provider "(recovered)".
 //!
-//! Emits `broxeen:vision_detection` and `broxeen:vision_llm_result` events to frontend.
+//! Emits `broxeen:vision_detection` and `broxeen:vision_llm_result` events to frontend,
+//! and mirrors them to the optional webhook sink (`[notifications]`) and the
+//! WebSocket feed (`vision_ws`, which also gets periodic stats).
+//! Detections matching `[clips]` rules trigger RTSP clip recording (`broxeen:vision_clip_ready`).
+//! Polygon `zones` gate motion/detections and name entry/exit zones; they can be
+//! replaced on a running pipeline through [`PipelineHandle::set_zones`].
+//! The capture loop keeps the latest processed frame for on-demand annotated
+//! snapshots ([`PipelineHandle::frame_tap`]).
+//! Both loops follow a config watch channel, so thresholds, MOG2 sensitivity,
+//! the flush interval and LLM settings reloaded from broxeen.toml apply
+//! without a restart ([`PipelineHandle::reconfigure`], `vision_reload`).
+//! A watchdog task forces a reconnect when no frame arrived for
+//! `pipeline.stall_timeout_secs`; after three reconnects that bring no frames
+//! (a request the capture loop has not picked up one tick later counts as
+//! one) it emits `broxeen:vision_pipeline_error` and marks the pipeline
+//! degraded. Reads time out in `vision_capture`, so a silent camera cannot
+//! block the loop for long.
 
-use anyhow::Result;
-use std::sync::Arc;
+use anyhow::{anyhow, Result};
+use opencv::{core::Mat, imgcodecs, imgproc, prelude::*};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
 use tokio::sync::watch;
 use tracing::{info, warn};
 
+use crate::image_meta::ThumbnailFormat;
 use crate::vision_capture::CaptureStream;
-use crate::vision_config::VisionConfig;
+use crate::vision_clips::{ClipRecorder, ClipTrigger};
+use crate::vision_config::{PipelineConfig, VisionConfig};
 use crate::vision_db::VisionDatabase;
-use crate::vision_detector::Detector;
+use crate::vision_detector::{Detection, Detector, DetectorInfo};
 use crate::vision_llm::LlmClient;
+use crate::vision_motion::{Lighting, LightingMonitor};
 use crate::vision_movement;
 use crate::vision_scene_buffer::{MinuteBuffer, ObjectEvent};
-use crate::vision_tracker::Tracker;
+use crate::vision_tracker::{ThumbnailSpec, Tracker};
+use crate::vision_webhook::WebhookSink;
+use crate::vision_zones::{self, Zone};
 
 /// Message from blocking capture thread → async LLM worker.
 struct TrackMsg {
     track:     crate::vision_tracker::CompletedTrack,
     camera_id: String,
+    lighting:  Lighting,
+}
+
+// ─── Runtime statistics ─────────────────────────────────────────────────────
+
+/// Window used for the rolling `frames_per_second` figure.
+const FPS_WINDOW: Duration = Duration::from_secs(10);
+/// How often a stats message goes out on the WebSocket feed.
+const WS_STATS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Live counters shared between the capture loop and the async worker.
+#[derive(Default)]
+pub struct PipelineStats {
+    pub frames_captured:    AtomicU64,
+    pub frames_processed:   AtomicU64,
+    pub motion_events:      AtomicU64,
+    pub detections_saved:   AtomicU64,
+    /// Detections dropped by `enabled_classes` / `class_thresholds`
+    pub detections_filtered: AtomicU64,
+    pub llm_calls:          AtomicU64,
+    /// Detections covered by a (multi-image) LLM scene request
+    pub llm_batched:        AtomicU64,
+    /// Detections not sent because of `llm.max_requests_per_minute`
+    pub llm_skipped:        AtomicU64,
+    pub channel_drops:      AtomicU64,
+    pub webhooks_delivered: AtomicU64,
+    pub webhooks_failed:    AtomicU64,
+    /// Unix ms of the last frame read from the stream (0 = none yet)
+    pub last_frame_at:      AtomicU64,
+    frame_times:            Mutex<VecDeque<Instant>>,
+    /// Set once the detector is up
+    detector:               OnceLock<Arc<Mutex<DetectorInfo>>>,
+}
+
+/// Point-in-time copy of [`PipelineStats`] for serialization.
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineStatsSnapshot {
+    pub frames_captured:    u64,
+    pub frames_processed:   u64,
+    pub motion_events:      u64,
+    pub detections_saved:   u64,
+    pub detections_filtered: u64,
+    pub llm_calls:          u64,
+    pub llm_batched:        u64,
+    pub llm_skipped:        u64,
+    pub channel_drops:      u64,
+    pub webhooks_delivered: u64,
+    pub webhooks_failed:    u64,
+    pub last_frame_at:      Option<u64>,
+    pub frames_per_second:  f64,
+    pub detector:           Option<DetectorInfo>,
+}
+
+impl PipelineStats {
+    /// Record a frame read from the stream (processed or skipped).
+    pub fn record_frame(&self) {
+        self.frames_captured.fetch_add(1, Ordering::Relaxed);
+        self.last_frame_at.store(now_ms(), Ordering::Relaxed);
+
+        let now = Instant::now();
+        let mut times = self.frame_times.lock().unwrap_or_else(|e| e.into_inner());
+        times.push_back(now);
+        while times.front().map_or(false, |t| now.duration_since(*t) > FPS_WINDOW) {
+            times.pop_front();
+        }
+    }
+
+    /// Frames per second over the last [`FPS_WINDOW`]. Drops to 0 on a stalled stream.
+    pub fn frames_per_second(&self) -> f64 {
+        let now = Instant::now();
+        let times = self.frame_times.lock().unwrap_or_else(|e| e.into_inner());
+        let recent = times.iter().filter(|t| now.duration_since(**t) <= FPS_WINDOW).count();
+        recent as f64 / FPS_WINDOW.as_secs_f64()
+    }
+
+    pub fn snapshot(&self) -> PipelineStatsSnapshot {
+        let last = self.last_frame_at.load(Ordering::Relaxed);
+        PipelineStatsSnapshot {
+            frames_captured:    self.frames_captured.load(Ordering::Relaxed),
+            frames_processed:   self.frames_processed.load(Ordering::Relaxed),
+            motion_events:      self.motion_events.load(Ordering::Relaxed),
+            detections_saved:   self.detections_saved.load(Ordering::Relaxed),
+            detections_filtered: self.detections_filtered.load(Ordering::Relaxed),
+            llm_calls:          self.llm_calls.load(Ordering::Relaxed),
+            llm_batched:        self.llm_batched.load(Ordering::Relaxed),
+            llm_skipped:        self.llm_skipped.load(Ordering::Relaxed),
+            channel_drops:      self.channel_drops.load(Ordering::Relaxed),
+            webhooks_delivered: self.webhooks_delivered.load(Ordering::Relaxed),
+            webhooks_failed:    self.webhooks_failed.load(Ordering::Relaxed),
+            last_frame_at:      if last == 0 { None } else { Some(last) },
+            frames_per_second:  self.frames_per_second(),
+            detector:           self.detector.get().map(|i| i.lock().unwrap_or_else(|e| e.into_inner()).clone()),
+        }
+    }
+}
+
+// ─── LLM rate limit ─────────────────────────────────────────────────────────
+
+/// Start times of LLM requests in the last minute, shared by all pipelines
+/// so several cameras cannot multiply the budget.
+static LLM_REQUESTS: Mutex<VecDeque<Instant>> = Mutex::new(VecDeque::new());
+
+/// Record a request in `window` if fewer than `max_per_minute` were made in
+/// the minute before `now`. `0` means unlimited.
+fn try_acquire_llm_slot(window: &mut VecDeque<Instant>, max_per_minute: u32, now: Instant) -> bool {
+    while window.front().is_some_and(|t| now.duration_since(*t) >= Duration::from_secs(60)) {
+        window.pop_front();
+    }
+    if max_per_minute > 0 && window.len() >= max_per_minute as usize {
+        return false;
+    }
+    window.push_back(now);
+    true
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+// ─── Stall watchdog ─────────────────────────────────────────────────────────
+
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(30);
+/// No stall is reported this long after start (stream open, first frames)
+const WATCHDOG_GRACE_MS: u64 = 60_000;
+/// Forced reconnects without a frame before the pipeline counts as degraded
+const MAX_FAILED_RECOVERIES: u32 = 3;
+
+/// Shared between the capture loop and the watchdog task.
+#[derive(Default)]
+struct Watchdog {
+    /// Set by the watchdog; the capture loop reconnects after its current read
+    reconnect_requested: AtomicBool,
+    /// The capture loop is inside `stream.reconnect()`
+    reconnecting: AtomicBool,
+    /// Unix ms of the last completed reconnect (0 = none)
+    reconnected_at: AtomicU64,
+    degraded: AtomicBool,
+}
+
+/// No frame, reconnect or start within `stall_ms` of `now` (all Unix ms),
+/// never during the grace period after `started_at`.
+fn is_stalled(now: u64, started_at: u64, last_frame_at: u64, reconnected_at: u64, grace_ms: u64, stall_ms: u64) -> bool {
+    if now < started_at.saturating_add(grace_ms) {
+        return false;
+    }
+    let last_sign_of_life = started_at.max(last_frame_at).max(reconnected_at);
+    now.saturating_sub(last_sign_of_life) >= stall_ms
+}
+
+/// Watchdog progress between ticks.
+#[derive(Default)]
+struct Recovery {
+    /// Forced reconnects (or requests never picked up) that brought no frame
+    failed: u32,
+    /// Unix ms of the reconnect the watchdog last requested
+    requested_at: Option<u64>,
+}
+
+/// What one watchdog tick did.
+#[derive(Debug, PartialEq)]
+enum TickAction {
+    /// Frames flow, or a reconnect is under way
+    Idle,
+    /// A reconnect was requested; `degraded` when this tick marked the
+    /// pipeline degraded
+    Reconnect { degraded: bool },
+    /// The previous request was not picked up within one interval — the
+    /// capture loop is stuck in a read; counts as a failed recovery
+    Blocked { degraded: bool },
+}
+
+impl Watchdog {
+    /// One check, every `WATCHDOG_INTERVAL` (times in Unix ms).
+    fn tick(&self, rec: &mut Recovery, now: u64, started_at: u64, last_frame_at: u64, stall_ms: u64) -> TickAction {
+        if rec.requested_at.is_some_and(|t| last_frame_at > t) {
+            self.degraded.store(false, Ordering::Relaxed);
+            *rec = Recovery::default();
+        }
+        if self.reconnecting.load(Ordering::Relaxed) {
+            return TickAction::Idle;
+        }
+        if self.reconnect_requested.load(Ordering::Relaxed) {
+            rec.failed += 1;
+            return TickAction::Blocked { degraded: self.mark_degraded(rec) };
+        }
+        let reconnected_at = self.reconnected_at.load(Ordering::Relaxed);
+        if !is_stalled(now, started_at, last_frame_at, reconnected_at, WATCHDOG_GRACE_MS, stall_ms) {
+            return TickAction::Idle;
+        }
+        // The previous forced reconnect brought no frame
+        if rec.requested_at.is_some() {
+            rec.failed += 1;
+        }
+        self.reconnect_requested.store(true, Ordering::Relaxed);
+        rec.requested_at = Some(now);
+        TickAction::Reconnect { degraded: self.mark_degraded(rec) }
+    }
+
+    /// True only for the tick that crosses `MAX_FAILED_RECOVERIES`.
+    fn mark_degraded(&self, rec: &Recovery) -> bool {
+        rec.failed >= MAX_FAILED_RECOVERIES && !self.degraded.swap(true, Ordering::Relaxed)
+    }
+}
+
+// ─── Annotated snapshots ────────────────────────────────────────────────────
+
+/// Boxes from an older YOLO run are not drawn on the current frame.
+const SNAPSHOT_BOX_MAX_AGE: Duration = Duration::from_secs(3);
+const SNAPSHOT_JPEG_QUALITY: i32 = 85;
+
+/// Latest processed frame and the most recent detections, written by the
+/// capture loop (the frame is moved in, not copied).
+#[derive(Default)]
+struct LatestFrame {
+    frame: Option<Mat>,
+    captured_at: Option<Instant>,
+    detections: Vec<Detection>,
+    detections_at: Option<Instant>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotBox {
+    pub label: String,
+    pub confidence: f32,
+    /// Pixel box in the snapshot (x1, y1, x2, y2)
+    pub bbox: (i32, i32, i32, i32),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AnnotatedSnapshot {
+    pub camera_id: String,
+    pub jpeg_base64: String,
+    pub width: i32,
+    pub height: i32,
+    /// Age of the frame when the snapshot was taken
+    pub frame_age_ms: u64,
+    pub boxes: Vec<SnapshotBox>,
+}
+
+/// Read access to a pipeline's latest frame, usable without holding the
+/// pipeline registry lock.
+#[derive(Clone)]
+pub struct FrameTap {
+    camera_id: String,
+    latest: Arc<Mutex<LatestFrame>>,
+}
+
+impl FrameTap {
+    /// Copy of the latest frame with the recent detection boxes drawn on it.
+    pub fn snapshot(&self) -> Result<AnnotatedSnapshot> {
+        let (mut frame, frame_age, detections) = {
+            let latest = self.latest.lock().unwrap_or_else(|e| e.into_inner());
+            let frame = latest
+                .frame
+                .as_ref()
+                .ok_or_else(|| anyhow!("No frame captured yet for camera {}", self.camera_id))?
+                .try_clone()?;
+            let fresh = latest.detections_at.is_some_and(|t| t.elapsed() <= SNAPSHOT_BOX_MAX_AGE);
+            let detections = if fresh { latest.detections.clone() } else { Vec::new() };
+            (frame, latest.captured_at.map(|t| t.elapsed()).unwrap_or_default(), detections)
+        };
+
+        let boxes = annotate_frame(&mut frame, &detections)?;
+        let mut buf = opencv::core::Vector::<u8>::new();
+        let params = opencv::core::Vector::from_iter([imgcodecs::IMWRITE_JPEG_QUALITY, SNAPSHOT_JPEG_QUALITY]);
+        imgcodecs::imencode(".jpg", &frame, &mut buf, &params)?;
+
+        use base64::Engine as _;
+        Ok(AnnotatedSnapshot {
+            camera_id: self.camera_id.clone(),
+            jpeg_base64: base64::engine::general_purpose::STANDARD.encode(buf.as_slice()),
+            width: frame.cols(),
+            height: frame.rows(),
+            frame_age_ms: frame_age.as_millis() as u64,
+            boxes,
+        })
+    }
+}
+
+/// Draw boxes and `label 87%` captions; returns what was drawn in pixels.
+fn annotate_frame(frame: &mut Mat, detections: &[Detection]) -> Result<Vec<SnapshotBox>> {
+    use opencv::core::{Point, Rect, Scalar};
+
+    let (w, h) = (frame.cols() as f32, frame.rows() as f32);
+    let thickness = ((w.max(h) / 400.0).round() as i32).max(2);
+    let font_scale = (w.max(h) / 1200.0).max(0.5) as f64;
+    let colour = Scalar::new(0.0, 220.0, 0.0, 0.0);
+    let mut boxes = Vec::with_capacity(detections.len());
+
+    for d in detections {
+        let (x1, y1, x2, y2) = d.bbox_norm;
+        let px = |v: f32, max: f32| (v.clamp(0.0, 1.0) * max) as i32;
+        let bbox = (px(x1, w), px(y1, h), px(x2, w), px(y2, h));
+        let label = d.class.as_str().to_string();
+        imgproc::rectangle(
+            frame,
+            Rect::new(bbox.0, bbox.1, (bbox.2 - bbox.0).max(1), (bbox.3 - bbox.1).max(1)),
+            colour, thickness, imgproc::LINE_8, 0,
+        )?;
+        imgproc::put_text(
+            frame,
+            &format!("{} {:.0}%", label, d.confidence * 100.0),
+            Point::new(bbox.0, (bbox.1 - thickness * 2).max(12)),
+            imgproc::FONT_HERSHEY_SIMPLEX, font_scale, colour, thickness, imgproc::LINE_AA, false,
+        )?;
+        boxes.push(SnapshotBox { label, confidence: d.confidence, bbox });
+    }
+    Ok(boxes)
 }
 
 // ─── Pipeline handle returned to Tauri commands ─────────────────────────────
@@ -31,23 +370,86 @@ pub struct PipelineHandle {
     pub camera_id: String,
     pub rtsp_url: String,
     pub started_at: u64,
+    pub stats: Arc<PipelineStats>,
+    zones: Arc<RwLock<Vec<Zone>>>,
+    latest: Arc<Mutex<LatestFrame>>,
     stop_tx: watch::Sender<bool>,
+    config_tx: watch::Sender<Arc<VisionConfig>>,
+    failure: Arc<std::sync::Mutex<Option<String>>>,
+    watchdog: Arc<Watchdog>,
+    /// Set when the capture loop has returned, however it exited
+    exited: Arc<AtomicBool>,
+}
+
+/// Marks the capture loop as exited when dropped, on every return path.
+struct ExitFlag(Arc<AtomicBool>);
+
+impl Drop for ExitFlag {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
 }
 
 impl PipelineHandle {
     pub fn stop(&self) {
         let _ = self.stop_tx.send(true);
     }
+
+    /// Whether the capture loop has returned (stopped or failed).
+    pub fn has_exited(&self) -> bool {
+        self.exited.load(Ordering::Relaxed)
+    }
+
+    /// Why the capture loop gave up (stream could not be opened or
+    /// reconnected, detector init failed); `None` while it runs.
+    pub fn failure(&self) -> Option<String> {
+        self.failure.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Still running, but reconnects forced by the watchdog brought no frames.
+    pub fn degraded(&self) -> bool {
+        self.watchdog.degraded.load(Ordering::Relaxed)
+    }
+
+    /// Replace the zones; picked up on the next frame. Caller validates.
+    pub fn set_zones(&self, zones: Vec<Zone>) {
+        *self.zones.write().unwrap_or_else(|e| e.into_inner()) = zones;
+    }
+
+    /// Apply the live parameters that changed from `old` to `new` (two loads
+    /// of broxeen.toml); the loops pick them up on their next iteration.
+    /// Everything else keeps the value the pipeline was started with.
+    pub fn reconfigure(&self, old: &VisionConfig, new: &VisionConfig) {
+        self.config_tx.send_modify(|running| {
+            let mut next = VisionConfig::clone(running);
+            crate::vision_reload::merge_live(&mut next, old, new);
+            *running = Arc::new(next);
+        });
+    }
+
+    pub fn frame_tap(&self) -> FrameTap {
+        FrameTap { camera_id: self.camera_id.clone(), latest: Arc::clone(&self.latest) }
+    }
 }
 
 // ─── Pipeline ───────────────────────────────────────────────────────────────
 
+type FatalExitHook = Box<dyn FnOnce(String) + Send>;
+
 pub struct Pipeline {
     cfg: VisionConfig,
+    on_fatal_exit: Option<FatalExitHook>,
 }
 
 impl Pipeline {
-    pub fn new(cfg: VisionConfig) -> Self { Self { cfg } }
+    pub fn new(cfg: VisionConfig) -> Self { Self { cfg, on_fatal_exit: None } }
+
+    /// Called (with the reason) when the capture loop exits on an
+    /// unrecoverable error, not when stopped.
+    pub fn on_fatal_exit(mut self, hook: impl FnOnce(String) + Send + 'static) -> Self {
+        self.on_fatal_exit = Some(Box::new(hook));
+        self
+    }
 
     pub fn start(
         self,
@@ -55,10 +457,12 @@ impl Pipeline {
     ) -> Result<PipelineHandle> {
         let cfg = Arc::new(self.cfg);
         let (stop_tx, stop_rx) = watch::channel(false);
+        let (config_tx, config_rx) = watch::channel(Arc::clone(&cfg));
+        let stats = Arc::new(PipelineStats::default());
+        let zones = Arc::new(RwLock::new(cfg.zones.clone()));
 
-        let db = Arc::new(std::sync::Mutex::new(
-            VisionDatabase::open(&cfg.database.path)?,
-        ));
+        let db = VisionDatabase::shared(&cfg.database.path)?;
+        db.set_timezone(crate::local_time::parse(&cfg.database.timezone).map_err(anyhow::Error::msg)?)?;
         let llm = Arc::new(LlmClient::from_config(&cfg.llm));
 
         // Async channel: completed tracks → LLM/scene worker
@@ -69,33 +473,75 @@ impl Pipeline {
 
         // ── Async worker: per-minute LLM batching (Track B) ─────────────
         let worker_db = Arc::clone(&db);
-        let worker_llm = Arc::clone(&llm);
-        let worker_cfg = cfg.clone();
+        let mut worker_llm = Arc::clone(&llm);
+        let mut worker_cfg = cfg.clone();
+        let mut worker_config_rx = config_rx.clone();
         let worker_app = app_handle.clone();
+        let worker_stats = Arc::clone(&stats);
+        let worker_zones = Arc::clone(&zones);
+        let webhook = WebhookSink::spawn(&cfg.notifications, Arc::clone(&stats)).map(Arc::new);
+        let clips = ClipRecorder::new(&cfg.clips, &camera_id, &rtsp_url);
+        let clip_retention_days = cfg.clips.retention_days;
 
         tokio::spawn(async move {
+            // Resolve {camera_location} once per pipeline run (cached on disk)
+            let camera_location = match (
+                &worker_cfg.camera.location,
+                worker_cfg.camera.latitude,
+                worker_cfg.camera.longitude,
+            ) {
+                (Some(label), _, _) => label.clone(),
+                (None, Some(lat), Some(lon)) => crate::geocoding::location_label(lat, lon).await,
+                _ => String::new(),
+            };
+            let mut last_clip_prune: Option<Instant> = None;
+            let speed_calibration = vision_movement::SpeedCalibration::from_camera(&worker_cfg.camera);
+            let mut last_ws_stats = Instant::now();
+
             let mut buf = MinuteBuffer::new(
                 worker_cfg.scene.flush_interval_secs,
                 worker_cfg.scene.ring_capacity,
                 worker_cfg.scene.min_crops_for_llm,
+                worker_cfg.llm.batch_max_items,
             );
+            // Events a crashed / stopped run buffered but never flushed
+            match worker_db.load_scene_spill(&worker_cfg.camera.camera_id) {
+                Ok(spilled) if !spilled.is_empty() => {
+                    info!("Recovered {} unflushed scene events from the last run", spilled.len());
+                    buf.restore(spilled);
+                }
+                Ok(_) => {}
+                Err(e) => warn!("DB load_scene_spill: {}", e),
+            }
 
             loop {
-                // Drain all pending completed tracks
+                // ── Parameters reloaded from broxeen.toml ─────────────────
+                if worker_config_rx.has_changed().unwrap_or(false) {
+                    worker_cfg = worker_config_rx.borrow_and_update().clone();
+                    buf.set_flush_interval(worker_cfg.scene.flush_interval_secs);
+                    worker_llm = Arc::new(LlmClient::from_config(&worker_cfg.llm));
+                }
+
+                // Drain all pending completed tracks (after a recovered batch went out)
                 loop {
+                    if buf.is_recovering() {
+                        break;
+                    }
                     match track_rx.try_recv() {
                         Ok(msg) => {
-                            let summary = vision_movement::analyse_movement(&msg.track);
+                            let mut summary = vision_movement::analyse_movement(&msg.track, speed_calibration.as_ref());
+                            name_zones(&mut summary, &msg.track, &worker_zones.read().unwrap_or_else(|e| e.into_inner()));
                             let mv_tag = vision_movement::movement_tag(&summary, &msg.track.class);
 
                             // ── Track A: save to DB immediately ──────────
-                            let thumbnail = msg.track.crops.first()
-                                .map(|c| c.jpeg_bytes.clone())
+                            let thumbnail = msg.track.thumbnail.clone()
+                                .or_else(|| msg.track.crops.first().map(|c| c.jpeg_bytes.clone()))
                                 .unwrap_or_default();
+                            let thumbnail_format = ThumbnailFormat::detect(&thumbnail)
+                                .map(|f| f.as_str());
 
-                            {
-                                let db = worker_db.lock().unwrap();
-                                if let Err(e) = db.insert_detection(
+                            let detection_id = {
+                                match worker_db.insert_detection(
                                     &msg.camera_id,
                                     &msg.track.id.to_string(),
                                     &msg.track.class,
@@ -103,49 +549,100 @@ impl Pipeline {
                                     Some(&mv_tag),
                                     Some(&summary.direction),
                                     Some(summary.speed_label),
+                                    summary.speed_mps,
                                     Some(&summary.entry_zone),
                                     Some(&summary.exit_zone),
+                                    msg.track.center(),
                                     summary.duration_secs,
                                     &thumbnail,
+                                    thumbnail_format,
+                                    msg.lighting.as_str(),
                                 ) {
-                                    warn!("DB insert_detection: {}", e);
-                                } else {
-                                    info!(
-                                        "✓ Local: {} [{:.0}%] {} cam={}",
-                                        msg.track.class,
-                                        msg.track.confidence * 100.0,
-                                        summary.description,
-                                        msg.camera_id,
-                                    );
-
-                                    // Emit detection event to frontend
-                                    if let Some(ref app) = worker_app {
-                                        use tauri::Emitter;
-                                        let _ = app.emit(
-                                            "broxeen:vision_detection",
-                                            serde_json::json!({
-                                                "camera_id": msg.camera_id,
-                                                "track_id": msg.track.id.to_string(),
-                                                "label": msg.track.class,
-                                                "confidence": msg.track.confidence,
-                                                "movement": mv_tag,
-                                                "direction": summary.direction,
-                                                "duration_s": summary.duration_secs,
-                                            }),
+                                    Err(e) => {
+                                        warn!("DB insert_detection: {}", e);
+                                        None
+                                    }
+                                    Ok(detection_id) => {
+                                        worker_stats.detections_saved.fetch_add(1, Ordering::Relaxed);
+                                        info!(
+                                            "✓ Local: {} [{:.0}%] {} cam={}",
+                                            msg.track.class,
+                                            msg.track.confidence * 100.0,
+                                            summary.description,
+                                            msg.camera_id,
                                         );
+
+                                        let payload = serde_json::json!({
+                                            "camera_id": msg.camera_id,
+                                            "track_id": msg.track.id.to_string(),
+                                            "label": msg.track.class,
+                                            "confidence": msg.track.confidence,
+                                            "movement": mv_tag,
+                                            "direction": summary.direction,
+                                            "speed": summary.speed_label,
+                                            "speed_mps": summary.speed_mps,
+                                            "duration_s": summary.duration_secs,
+                                            "lighting": msg.lighting.as_str(),
+                                        });
+
+                                        if let Some(ref hook) = webhook {
+                                            hook.notify_detection(&msg.track.class, msg.track.confidence, payload.clone());
+                                        }
+                                        if let Some(ws) = crate::vision_ws::current() {
+                                            ws.publish("detection", payload.clone());
+                                        }
+
+                                        // Emit detection event to frontend
+                                        if let Some(ref app) = worker_app {
+                                            use tauri::Emitter;
+                                            let _ = app.emit("broxeen:vision_detection", payload);
+                                            crate::sounds::notify_detection(msg.track.confidence);
+                                        }
+
+                                        if let Some(ref clips) = clips {
+                                            clips.maybe_record(
+                                                ClipTrigger {
+                                                    detection_id,
+                                                    track_id: &msg.track.id.to_string(),
+                                                    label: &msg.track.class,
+                                                    confidence: msg.track.confidence,
+                                                },
+                                                Arc::clone(&worker_db),
+                                                worker_app.clone(),
+                                                webhook.clone(),
+                                            );
+                                        }
+                                        Some(detection_id)
                                     }
                                 }
-                            }
+                            };
 
                             // ── Buffer for LLM batch ─────────────────────
-                            buf.push(ObjectEvent {
+                            let mut event = ObjectEvent {
                                 track_id:    msg.track.id,
+                                detection_id,
                                 class:       msg.track.class.clone(),
                                 confidence:  msg.track.confidence,
                                 movement:    summary,
                                 crops:       msg.track.crops.clone(),
                                 finished_at: chrono::Utc::now(),
-                            });
+                                spill_id:    None,
+                            };
+                            if worker_cfg.scene.spill_max_mb > 0 {
+                                match worker_db.spill_scene_event(
+                                    &worker_cfg.camera.camera_id,
+                                    &event,
+                                    worker_cfg.scene.spill_max_mb * 1024 * 1024,
+                                ) {
+                                    Ok(id) => event.spill_id = Some(id),
+                                    Err(e) => warn!("DB spill_scene_event: {}", e),
+                                }
+                            }
+                            if let Some(evicted) = buf.push(event).and_then(|e| e.spill_id) {
+                                if let Err(e) = worker_db.delete_scene_spill(&[evicted]) {
+                                    warn!("DB delete_scene_spill: {}", e);
+                                }
+                            }
                         }
                         Err(tokio::sync::mpsc::error::TryRecvError::Empty)        => break,
                         Err(tokio::sync::mpsc::error::TryRecvError::Disconnected) => return,
@@ -161,41 +658,110 @@ impl Pipeline {
                             worker_cfg.scene.max_crops_per_batch,
                         );
 
-                        if !crops.is_empty() {
+                        let detection_ids: Vec<i64> = batch.events.iter()
+                            .filter_map(|e| e.detection_id)
+                            .collect();
+                        let spill_ids: Vec<i64> = batch.events.iter()
+                            .filter_map(|e| e.spill_id)
+                            .collect();
+                        let allowed = !crops.is_empty() && try_acquire_llm_slot(
+                            &mut LLM_REQUESTS.lock().unwrap_or_else(|e| e.into_inner()),
+                            worker_cfg.llm.max_requests_per_minute,
+                            Instant::now(),
+                        );
+
+                        if !crops.is_empty() && !allowed {
+                            worker_stats.llm_skipped.fetch_add(batch.events.len() as u64, Ordering::Relaxed);
+                            warn!(
+                                "LLM rate limit ({}/min) reached — {} detection(s) marked llm_skipped",
+                                worker_cfg.llm.max_requests_per_minute, batch.events.len(),
+                            );
+                            if let Err(e) = worker_db.mark_llm_status(&detection_ids, "llm_skipped", None) {
+                                warn!("DB mark_llm_status: {}", e);
+                            }
+                        }
+
+                        if allowed {
+                            worker_stats.llm_calls.fetch_add(1, Ordering::Relaxed);
+                            worker_stats.llm_batched.fetch_add(batch.events.len() as u64, Ordering::Relaxed);
                             match worker_llm.describe_scene(
                                 &crops, &timeline, &worker_cfg.camera.camera_id,
+                                &camera_location, &worker_cfg.scene.narrative_template,
                             ).await {
                                 Ok(result) => {
-                                    info!("📖 LLM [{}]: {}", result.provider, result.narrative);
-                                    let db = worker_db.lock().unwrap();
-                                    if let Err(e) = db.insert_llm_event(
+                                    let provider = if batch.recovered {
+                                        format!("{} (recovered)", result.provider)
+                                    } else {
+                                        result.provider.clone()
+                                    };
+                                    info!("📖 LLM [{}]: {}", provider, result.narrative);
+                                    match worker_db.insert_llm_event(
                                         &worker_cfg.camera.camera_id,
                                         batch.period_start,
                                         batch.period_end,
                                         &result.narrative,
-                                        &result.provider,
+                                        &provider,
                                         crops.len() as u32,
                                         &timeline,
                                     ) {
-                                        warn!("DB insert_llm_event: {}", e);
+                                        Ok(event_id) => {
+                                            if let Err(e) = worker_db.mark_llm_status(&detection_ids, "described", Some(event_id)) {
+                                                warn!("DB mark_llm_status: {}", e);
+                                            }
+                                        }
+                                        Err(e) => warn!("DB insert_llm_event: {}", e),
+                                    }
+
+                                    let payload = serde_json::json!({
+                                        "camera_id": worker_cfg.camera.camera_id,
+                                        "camera_location": camera_location,
+                                        "narrative": result.narrative,
+                                        "provider": provider,
+                                        "crops_sent": crops.len(),
+                                        "detection_ids": detection_ids,
+                                    });
+
+                                    if let Some(ref hook) = webhook {
+                                        hook.notify_narrative(payload.clone());
+                                    }
+                                    if let Some(ws) = crate::vision_ws::current() {
+                                        ws.publish("narrative", payload.clone());
                                     }
 
                                     if let Some(ref app) = worker_app {
                                         use tauri::Emitter;
-                                        let _ = app.emit(
-                                            "broxeen:vision_llm_result",
-                                            serde_json::json!({
-                                                "camera_id": worker_cfg.camera.camera_id,
-                                                "narrative": result.narrative,
-                                                "provider": result.provider,
-                                                "crops_sent": crops.len(),
-                                            }),
-                                        );
+                                        let _ = app.emit("broxeen:vision_llm_result", payload);
                                     }
                                 }
                                 Err(e) => warn!("LLM scene error: {} — detections still saved locally", e),
                             }
                         }
+
+                        // The batch is handled; its spill is no longer needed
+                        if let Err(e) = worker_db.delete_scene_spill(&spill_ids) {
+                            warn!("DB delete_scene_spill: {}", e);
+                        }
+                    }
+                }
+
+                // ── Stats for WebSocket clients ───────────────────────────
+                if let Some(ws) = crate::vision_ws::current() {
+                    if last_ws_stats.elapsed() >= WS_STATS_INTERVAL {
+                        last_ws_stats = Instant::now();
+                        ws.publish("stats", serde_json::json!({
+                            "camera_id": worker_cfg.camera.camera_id,
+                            "stats": worker_stats.snapshot(),
+                        }));
+                    }
+                }
+
+                // ── Clip retention (hourly; 0 days = keep forever) ────────
+                if clips.is_some() && clip_retention_days > 0 && last_clip_prune.map_or(true, |t| t.elapsed() > Duration::from_secs(3600)) {
+                    last_clip_prune = Some(Instant::now());
+                    match worker_db.prune_clips(clip_retention_days) {
+                        Ok(0) => {}
+                        Ok(n) => info!("Pruned {} clip(s) older than {} days", n, clip_retention_days),
+                        Err(e) => warn!("Clip prune: {}", e),
                     }
                 }
 
@@ -206,28 +772,58 @@ impl Pipeline {
         // ── Blocking capture + detection loop ─────────────────────────────
         let cap_cfg = cfg.clone();
         let mut stop_rx_cap = stop_rx.clone();
+        let mut config_rx_cap = config_rx;
+        let cap_stats = Arc::clone(&stats);
+        let cap_zones = Arc::clone(&zones);
+        let latest = Arc::new(Mutex::new(LatestFrame::default()));
+        let cap_latest = Arc::clone(&latest);
+        let failure = Arc::new(std::sync::Mutex::new(None));
+        let cap_failure = Arc::clone(&failure);
+        let mut on_fatal_exit = self.on_fatal_exit;
+        let watchdog = Arc::new(Watchdog::default());
+        let cap_watchdog = Arc::clone(&watchdog);
+        let exited = Arc::new(AtomicBool::new(false));
+        let cap_exited = ExitFlag(Arc::clone(&exited));
 
         tokio::task::spawn_blocking(move || {
+            let _exited = cap_exited;
             let cam = &cap_cfg.camera;
             let det_cfg = &cap_cfg.detector;
+            // Reloadable parameters (detector thresholds, MOG2 sensitivity)
+            let mut live = Arc::clone(&cap_cfg);
+            let mut fail = |reason: String| {
+                warn!("{} — capture loop exits for {}", reason, cam.camera_id);
+                *cap_failure.lock().unwrap_or_else(|e| e.into_inner()) = Some(reason.clone());
+                if let Some(hook) = on_fatal_exit.take() {
+                    hook(reason);
+                }
+            };
 
             let mut stream = match CaptureStream::open(
                 &cam.url, &cam.camera_id, cap_cfg.pipeline.process_every_n_frames,
             ) {
                 Ok(s) => s,
-                Err(e) => { warn!("Failed to open camera stream: {}", e); return; }
+                Err(e) => { fail(format!("Failed to open camera stream: {}", e)); return; }
+            };
+            let reconnect = |stream: &mut CaptureStream| {
+                cap_watchdog.reconnecting.store(true, Ordering::Relaxed);
+                let result = stream.reconnect();
+                cap_watchdog.reconnected_at.store(now_ms(), Ordering::Relaxed);
+                cap_watchdog.reconnecting.store(false, Ordering::Relaxed);
+                result
             };
 
-            let detector = match Detector::new(
+            let mut detector = match Detector::new(
                 &det_cfg.model_path,
                 det_cfg.input_size,
-                det_cfg.confidence_threshold,
+                det_cfg.min_threshold(),
                 det_cfg.nms_threshold,
-                det_cfg.use_openvino,
+                det_cfg.effective_device(),
             ) {
                 Ok(d) => d,
-                Err(e) => { warn!("Detector init failed: {}", e); return; }
+                Err(e) => { fail(format!("Detector init failed: {}", e)); return; }
             };
+            let _ = cap_stats.detector.set(detector.shared_info());
 
             let mut tracker = Tracker::new(
                 cap_cfg.tracker.iou_match_threshold,
@@ -235,6 +831,11 @@ impl Pipeline {
                 cap_cfg.tracker.min_hits,
                 cap_cfg.tracker.crop_max_px,
                 cap_cfg.tracker.crops_per_track,
+                ThumbnailSpec {
+                    format:  cap_cfg.database.thumbnail_format,
+                    quality: cap_cfg.database.thumbnail_quality,
+                    max_px:  cap_cfg.database.thumbnail_max_px,
+                },
             );
 
             // Simple activity gate: use MOG2 at low res to decide if YOLO should run
@@ -245,10 +846,12 @@ impl Pipeline {
                 200000.0,
                 400,
             ).ok();
+            let pl = &cap_cfg.pipeline;
+            let mut lighting = LightingMonitor::new(pl.night_luminance, pl.luminance_hysteresis, pl.force_night);
 
             info!(
-                "▶ Pipeline v0.3: cam={} openvino={} flush={}s",
-                cam.camera_id, det_cfg.use_openvino, cap_cfg.scene.flush_interval_secs,
+                "▶ Pipeline v0.3: cam={} detector={} flush={}s",
+                cam.camera_id, detector.info().execution_provider, cap_cfg.scene.flush_interval_secs,
             );
 
             loop {
@@ -257,24 +860,77 @@ impl Pipeline {
                     break;
                 }
 
+                if cap_watchdog.reconnect_requested.swap(false, Ordering::Relaxed) {
+                    if let Err(re) = reconnect(&mut stream) {
+                        fail(format!("Reconnect failed: {}", re));
+                        break;
+                    }
+                }
+
+                if config_rx_cap.has_changed().unwrap_or(false) {
+                    live = config_rx_cap.borrow_and_update().clone();
+                    detector.set_conf_threshold(live.detector.min_threshold());
+                    let (var, area) = sensitivity(&live.pipeline, lighting.current());
+                    if let Some(m) = activity_detector.as_mut() {
+                        if let Err(e) = m.set_sensitivity(var, area) {
+                            warn!("Motion sensitivity update failed: {}", e);
+                        }
+                    }
+                    info!(
+                        "Config reloaded cam={} min_confidence={:.2} var_threshold={} min_area={}",
+                        cam.camera_id, live.detector.min_threshold(), var, area,
+                    );
+                }
+
                 let frame = match stream.next_frame() {
-                    Ok(Some(f)) => f,
-                    Ok(None) => continue,
+                    Ok(Some(f)) => {
+                        cap_stats.record_frame();
+                        cap_stats.frames_processed.fetch_add(1, Ordering::Relaxed);
+                        f
+                    }
+                    Ok(None) => {
+                        cap_stats.record_frame();
+                        continue;
+                    }
                     Err(e) => {
                         warn!("Capture: {} — reconnecting", e);
-                        match stream.reconnect() {
+                        cap_watchdog.reconnect_requested.store(false, Ordering::Relaxed);
+                        match reconnect(&mut stream) {
                             Ok(_) => continue,
-                            Err(re) => { warn!("Reconnect failed: {}", re); break; }
+                            Err(re) => { fail(format!("Reconnect failed: {}", re)); break; }
                         }
                     }
                 };
 
-                // Activity gate: skip YOLO if no motion detected
+                // Day/night: switch MOG2 sensitivity when luminance crosses the boundary
+                let luminance = crate::vision_motion::mean_luminance(&frame).unwrap_or(255.0);
+                if let Some(now) = lighting.update(luminance) {
+                    let (var, area) = sensitivity(&live.pipeline, now);
+                    info!(
+                        "Lighting → {} (luminance {:.0}) cam={} var_threshold={} min_area={}",
+                        now.as_str(), luminance, cam.camera_id, var, area,
+                    );
+                    if let Some(m) = activity_detector.as_mut() {
+                        if let Err(e) = m.set_sensitivity(var, area) {
+                            warn!("Motion sensitivity update failed: {}", e);
+                        }
+                    }
+                }
+
+                let zones = cap_zones.read().unwrap_or_else(|e| e.into_inner());
+
+                // Activity gate: skip YOLO if no motion outside excluded zones.
+                // Motion bboxes are in the 640px-wide frame used by MotionDetector.
+                let motion_h = frame.rows() as f32 * 640.0 / frame.cols().max(1) as f32;
                 let active = activity_detector.as_mut()
-                    .map(|m| m.process_frame(&frame).map(|objs| !objs.is_empty()).unwrap_or(true))
+                    .map(|m| m.process_frame(&frame).map(|objs| objs.iter().any(|o| {
+                        let (x1, y1, x2, y2) = o.bbox;
+                        vision_zones::allows(&zones, (x1 + x2) as f32 / 1280.0, (y1 + y2) as f32 / 2.0 / motion_h)
+                    })).unwrap_or(true))
                     .unwrap_or(true);
 
-                let detections = if active {
+                let mut detections = if active {
+                    cap_stats.motion_events.fetch_add(1, Ordering::Relaxed);
                     match detector.detect_frame(&frame) {
                         Ok(d) => d,
                         Err(e) => { warn!("Detector: {}", e); vec![] }
@@ -282,30 +938,255 @@ impl Pipeline {
                 } else {
                     vec![]
                 };
+                let before = detections.len();
+                detections.retain(|d| live.detector.accepts(d.class.as_str(), d.confidence));
+                let filtered = (before - detections.len()) as u64;
+                if filtered > 0 {
+                    cap_stats.detections_filtered.fetch_add(filtered, Ordering::Relaxed);
+                }
+                detections.retain(|d| {
+                    let (x1, y1, x2, y2) = d.bbox_norm;
+                    vision_zones::allows(&zones, (x1 + x2) / 2.0, (y1 + y2) / 2.0)
+                });
+                drop(zones);
 
                 let completed = tracker.update(&detections, &frame);
 
+                {
+                    let mut slot = cap_latest.lock().unwrap_or_else(|e| e.into_inner());
+                    let now = Instant::now();
+                    if active {
+                        slot.detections = detections;
+                        slot.detections_at = Some(now);
+                    }
+                    slot.frame = Some(frame);
+                    slot.captured_at = Some(now);
+                }
+
                 for t in completed {
-                    let _ = track_tx.try_send(TrackMsg {
+                    if track_tx.try_send(TrackMsg {
                         track: t,
                         camera_id: cam.camera_id.clone(),
-                    });
+                        lighting: lighting.current(),
+                    }).is_err() {
+                        cap_stats.channel_drops.fetch_add(1, Ordering::Relaxed);
+                    }
                 }
             }
 
             info!("Capture loop exited for camera {}", cam.camera_id);
         });
 
-        let started_at = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
+        let started_at = now_ms();
+
+        // ── Watchdog: reconnect a stream that stopped delivering frames ──
+        let wd = Arc::clone(&watchdog);
+        let wd_stats = Arc::clone(&stats);
+        let wd_failure = Arc::clone(&failure);
+        let mut wd_stop_rx = stop_rx;
+        let wd_camera_id = camera_id.clone();
+        let stall_ms = cfg.pipeline.stall_timeout_secs.saturating_mul(1000);
+        tokio::spawn(async move {
+            let mut recovery = Recovery::default();
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(WATCHDOG_INTERVAL) => {}
+                    changed = wd_stop_rx.changed() => if changed.is_err() { break },
+                }
+                if *wd_stop_rx.borrow() || wd_failure.lock().unwrap_or_else(|e| e.into_inner()).is_some() {
+                    break;
+                }
+
+                let was_degraded = wd.degraded.load(Ordering::Relaxed);
+                let last_frame_at = wd_stats.last_frame_at.load(Ordering::Relaxed);
+                let now = now_ms();
+                let action = wd.tick(&mut recovery, now, started_at, last_frame_at, stall_ms);
+                if was_degraded && !wd.degraded.load(Ordering::Relaxed) {
+                    info!("Camera {} delivers frames again — no longer degraded", wd_camera_id);
+                }
+                let degraded = match action {
+                    TickAction::Idle => continue,
+                    TickAction::Reconnect { degraded } => {
+                        let reconnected_at = wd.reconnected_at.load(Ordering::Relaxed);
+                        warn!(
+                            "Watchdog: no frame from {} for {}s — forcing reconnect",
+                            wd_camera_id, now.saturating_sub(started_at.max(last_frame_at).max(reconnected_at)) / 1000,
+                        );
+                        degraded
+                    }
+                    TickAction::Blocked { degraded } => {
+                        warn!(
+                            "Watchdog: capture of {} has not picked up the reconnect request for {}s",
+                            wd_camera_id, now.saturating_sub(recovery.requested_at.unwrap_or(now)) / 1000,
+                        );
+                        degraded
+                    }
+                };
+                if degraded {
+                    let reason = format!(
+                        "No frames from camera {} after {} reconnects",
+                        wd_camera_id, recovery.failed
+                    );
+                    warn!("{} — pipeline degraded", reason);
+                    if let Some(ref app) = app_handle {
+                        use tauri::Emitter;
+                        let _ = app.emit("broxeen:vision_pipeline_error", serde_json::json!({
+                            "camera_id": wd_camera_id,
+                            "reason": reason,
+                            "failed_recoveries": recovery.failed,
+                            "last_frame_at": (last_frame_at > 0).then_some(last_frame_at),
+                        }));
+                    }
+                }
+            }
+        });
 
         Ok(PipelineHandle {
             camera_id,
             rtsp_url,
             started_at,
+            stats,
+            zones,
+            latest,
             stop_tx,
+            config_tx,
+            failure,
+            watchdog,
+            exited,
         })
     }
 }
+
+/// MOG2 `(var_threshold, min_area)` for the current lighting.
+fn sensitivity(pl: &PipelineConfig, lighting: Lighting) -> (f64, f64) {
+    match lighting {
+        Lighting::Night => (pl.night_var_threshold, pl.night_min_contour_area),
+        Lighting::Day => (pl.bg_var_threshold, pl.min_activity_area),
+    }
+}
+
+/// Replace grid entry/exit zones with configured zone names where they match.
+fn name_zones(summary: &mut vision_movement::MovementSummary, track: &crate::vision_tracker::CompletedTrack, zones: &[Zone]) {
+    let centre = |b: &(f32, f32, f32, f32)| ((b.0 + b.2) / 2.0, (b.1 + b.3) / 2.0);
+    if let Some((x, y)) = track.positions.first().map(centre) {
+        if let Some(name) = vision_zones::zone_name_at(zones, x, y) {
+            summary.entry_zone = name.to_string();
+        }
+    }
+    if let Some((x, y)) = track.positions.last().map(centre) {
+        if let Some(name) = vision_zones::zone_name_at(zones, x, y) {
+            summary.exit_zone = name.to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_llm_rate_window() {
+        let mut window = VecDeque::new();
+        let t0 = Instant::now();
+        assert!(try_acquire_llm_slot(&mut window, 2, t0));
+        assert!(try_acquire_llm_slot(&mut window, 2, t0 + Duration::from_secs(10)));
+        assert!(!try_acquire_llm_slot(&mut window, 2, t0 + Duration::from_secs(30)));
+        // The first request leaves the window after a minute
+        assert!(try_acquire_llm_slot(&mut window, 2, t0 + Duration::from_secs(60)));
+        assert!(!try_acquire_llm_slot(&mut window, 2, t0 + Duration::from_secs(65)));
+        // 0 = unlimited
+        assert!((0..100).all(|_| try_acquire_llm_slot(&mut window, 0, t0 + Duration::from_secs(65))));
+    }
+
+    #[test]
+    fn test_watchdog_stall_after_grace_period() {
+        let (start, grace, stall) = (1_000_000, 60_000, 120_000);
+        // No frame yet, but still connecting
+        assert!(!is_stalled(start + 59_000, start, 0, 0, grace, 30_000));
+        assert!(is_stalled(start + 60_000, start, 0, 0, grace, 30_000));
+        // Measured from the last frame or reconnect, whichever is later
+        assert!(!is_stalled(start + 200_000, start, start + 100_000, 0, grace, stall));
+        assert!(is_stalled(start + 220_000, start, start + 100_000, 0, grace, stall));
+        assert!(!is_stalled(start + 220_000, start, start + 100_000, start + 150_000, grace, stall));
+        assert!(!is_stalled(start + 119_000, start, 0, 0, grace, stall));
+    }
+
+    #[test]
+    fn test_watchdog_counts_blocked_capture_as_failed_recovery() {
+        let wd = Arc::new(Watchdog::default());
+        let last_frame_at = Arc::new(AtomicU64::new(0));
+        let clock = Arc::new(AtomicU64::new(0));
+        // Stub capture loop stuck in a read until a frame is sent
+        let (frame_tx, frame_rx) = std::sync::mpsc::channel::<()>();
+        let capture = {
+            let (wd, last_frame_at, clock) = (Arc::clone(&wd), Arc::clone(&last_frame_at), Arc::clone(&clock));
+            std::thread::spawn(move || {
+                while frame_rx.recv().is_ok() {
+                    last_frame_at.store(clock.load(Ordering::SeqCst), Ordering::SeqCst);
+                    if wd.reconnect_requested.swap(false, Ordering::SeqCst) {
+                        wd.reconnected_at.store(clock.load(Ordering::SeqCst), Ordering::SeqCst);
+                    }
+                }
+            })
+        };
+
+        let (start, stall) = (1_000_000, 60_000);
+        let mut rec = Recovery::default();
+        let mut tick = |at: u64| {
+            clock.store(start + at, Ordering::SeqCst);
+            wd.tick(&mut rec, start + at, start, last_frame_at.load(Ordering::SeqCst), stall)
+        };
+        assert_eq!(tick(30_000), TickAction::Idle);
+        assert_eq!(tick(60_000), TickAction::Reconnect { degraded: false });
+        assert_eq!(tick(90_000), TickAction::Blocked { degraded: false });
+        assert_eq!(tick(120_000), TickAction::Blocked { degraded: false });
+        assert_eq!(tick(150_000), TickAction::Blocked { degraded: true });
+        assert_eq!(tick(180_000), TickAction::Blocked { degraded: false });
+        assert!(wd.degraded.load(Ordering::SeqCst));
+
+        // The read returns a frame, then the loop reconnects as asked
+        clock.store(start + 190_000, Ordering::SeqCst);
+        frame_tx.send(()).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while wd.reconnect_requested.load(Ordering::SeqCst) {
+            assert!(Instant::now() < deadline, "stub capture did not wake up");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(tick(210_000), TickAction::Idle);
+        assert!(!wd.degraded.load(Ordering::SeqCst));
+
+        drop(frame_tx);
+        capture.join().unwrap();
+    }
+
+    #[test]
+    fn test_snapshot_draws_recent_boxes() {
+        use crate::vision_detector::ObjectClass;
+        use opencv::core::{Scalar, CV_8UC3};
+
+        let tap = FrameTap { camera_id: "front".into(), latest: Arc::new(Mutex::new(LatestFrame::default())) };
+        assert!(tap.snapshot().is_err());
+
+        let frame = Mat::new_rows_cols_with_default(360, 640, CV_8UC3, Scalar::all(40.0)).unwrap();
+        {
+            let mut slot = tap.latest.lock().unwrap();
+            slot.frame = Some(frame);
+            slot.captured_at = Some(Instant::now());
+            slot.detections = vec![Detection { class: ObjectClass::Person, confidence: 0.87, bbox_norm: (0.25, 0.5, 0.5, 1.0) }];
+            slot.detections_at = Some(Instant::now());
+        }
+        let snap = tap.snapshot().unwrap();
+        assert_eq!((snap.width, snap.height), (640, 360));
+        assert_eq!(snap.boxes.len(), 1);
+        assert_eq!(snap.boxes[0].label, "person");
+        assert_eq!(snap.boxes[0].bbox, (160, 180, 320, 360));
+        assert!(!snap.jpeg_base64.is_empty());
+        // The stored frame itself stays clean
+        let stored = tap.latest.lock().unwrap().frame.as_ref().unwrap().at_2d::<opencv::core::Vec3b>(180, 160).unwrap().0;
+        assert_eq!(stored, [40, 40, 40]);
+
+        // Stale boxes are not drawn
+        tap.latest.lock().unwrap().detections_at = Instant::now().checked_sub(Duration::from_secs(10));
+        assert!(tap.snapshot().unwrap().boxes.is_empty());
+    }
+}
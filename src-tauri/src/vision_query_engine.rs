@@ -4,11 +4,18 @@
 //! into a SQL SELECT on the monitoring_history view.
 //!
 //! Falls back to local LLM if OpenRouter unavailable.
+//!
+//! `broxeen --vision-query` (vision builds) asks it from a terminal REPL
+//! with history, `\`-commands and multi-line questions — see [`repl`].
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
-use tracing::info;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use rustyline::error::ReadlineError;
+use tracing::{info, warn};
 
+use crate::local_time::LocalClock;
 use crate::vision_db::{VisionDatabase, SCHEMA};
 use crate::vision_llm::LlmClient;
 
@@ -84,6 +91,24 @@ impl QueryResult {
         out.push_str(&format!("  {} row(s)\n", self.rows.len()));
         out
     }
+
+    /// RFC 4180 CSV: header row, then data rows; fields quoted when needed.
+    pub fn to_csv(&self) -> String {
+        fn field(v: &str) -> String {
+            if v.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", v.replace('"', "\"\""))
+            } else {
+                v.to_string()
+            }
+        }
+        let mut out = String::new();
+        for row in std::iter::once(&self.columns).chain(self.rows.iter()) {
+            let line: Vec<String> = row.iter().map(|v| field(v)).collect();
+            out.push_str(&line.join(","));
+            out.push_str("\r\n");
+        }
+        out
+    }
 }
 
 // ─── Engine ──────────────────────────────────────────────────────────────────
@@ -102,7 +127,8 @@ impl<'a> QueryEngine<'a> {
     pub async fn ask(&self, question: &str) -> Result<QueryResult> {
         info!("Text-to-SQL: {}", question);
 
-        let sql = self.client.text_to_sql(question, SCHEMA).await?;
+        let clock = LocalClock::now(self.db.timezone());
+        let sql = self.client.text_to_sql(question, SCHEMA, &clock).await?;
         info!("Generated SQL: {}", sql);
 
         let (columns, rows) = self.db.execute_query(&sql)?;
@@ -114,4 +140,261 @@ impl<'a> QueryEngine<'a> {
             rows,
         })
     }
+
+    /// `ask` that gives up when `cancel` resolves first (e.g. Ctrl-C in the
+    /// REPL), dropping the in-flight LLM request instead of the whole session.
+    pub async fn ask_cancellable<F>(&self, question: &str, cancel: F) -> Result<QueryResult>
+    where
+        F: Future<Output = ()>,
+    {
+        tokio::select! {
+            result = self.ask(question) => result,
+            _ = cancel => Err(anyhow!("Zapytanie anulowane")),
+        }
+    }
+}
+
+// ─── REPL session ────────────────────────────────────────────────────────────
+//
+// Line handling for an interactive front-end: `\`-prefixed meta commands run
+// immediately, anything else accumulates until a blank line and is then asked
+// as one question. `repl` below reads the lines and keeps the history.
+
+#[derive(Debug, PartialEq)]
+pub enum MetaCommand {
+    /// `\sql` — toggle printing the generated SQL
+    ToggleSql,
+    /// `\camera <id>` scopes further questions, bare `\camera` clears it
+    Camera(Option<String>),
+    /// `\schema` — print the schema given to the LLM
+    Schema,
+    /// `\export last <file>.csv`
+    ExportLast(PathBuf),
+}
+
+pub fn parse_meta(line: &str) -> Option<Result<MetaCommand, String>> {
+    let rest = line.trim().strip_prefix('\\')?;
+    let mut parts = rest.split_whitespace();
+    let cmd = parts.next().unwrap_or("");
+    let args: Vec<&str> = parts.collect();
+    Some(match (cmd, args.as_slice()) {
+        ("sql", []) => Ok(MetaCommand::ToggleSql),
+        ("schema", []) => Ok(MetaCommand::Schema),
+        ("camera", []) => Ok(MetaCommand::Camera(None)),
+        ("camera", [id]) => Ok(MetaCommand::Camera(Some(id.to_string()))),
+        ("export", ["last", file]) => Ok(MetaCommand::ExportLast(PathBuf::from(file))),
+        ("export", _) => Err("Użycie: \\export last <plik.csv>".to_string()),
+        _ => Err(format!("Nieznane polecenie: \\{}", rest.trim())),
+    })
+}
+
+/// What the front-end should do after a line.
+#[derive(Debug, PartialEq)]
+pub enum ReplAction {
+    /// Line buffered (or nothing to do) — read the next one
+    Continue,
+    /// Print this text
+    Print(String),
+    /// Ask the engine this (camera-scoped) question
+    Ask(String),
+}
+
+#[derive(Default)]
+pub struct ReplSession {
+    pub show_sql: bool,
+    pub camera:   Option<String>,
+    pub last:     Option<QueryResult>,
+    buffer:       Vec<String>,
+}
+
+impl ReplSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn prompt(&self) -> String {
+        match (&self.camera, self.buffer.is_empty()) {
+            (_, false) => "   ... ".to_string(),
+            (Some(cam), true) => format!("vision[{}]> ", cam),
+            (None, true) => "vision> ".to_string(),
+        }
+    }
+
+    pub fn handle_line(&mut self, line: &str) -> ReplAction {
+        if self.buffer.is_empty() {
+            if let Some(meta) = parse_meta(line) {
+                return match meta {
+                    Ok(cmd) => self.run_meta(cmd),
+                    Err(e) => ReplAction::Print(e),
+                };
+            }
+        }
+        if !line.trim().is_empty() {
+            self.buffer.push(line.trim_end().to_string());
+            return ReplAction::Continue;
+        }
+        if self.buffer.is_empty() {
+            return ReplAction::Continue;
+        }
+        let question = self.buffer.drain(..).collect::<Vec<_>>().join("\n");
+        ReplAction::Ask(scope_question(question.trim(), self.camera.as_deref()))
+    }
+
+    /// Drop a half-typed multi-line question (Ctrl-C at the prompt).
+    pub fn discard_input(&mut self) {
+        self.buffer.clear();
+    }
+
+    /// Render a result according to the `\sql` toggle and remember it for `\export`.
+    pub fn finish(&mut self, result: QueryResult) -> String {
+        let mut out = result.format_table();
+        if !self.show_sql {
+            if let Some(pos) = out.find("\n\n") {
+                out.replace_range(..pos + 1, "");
+            }
+        }
+        self.last = Some(result);
+        out
+    }
+
+    fn run_meta(&mut self, cmd: MetaCommand) -> ReplAction {
+        match cmd {
+            MetaCommand::ToggleSql => {
+                self.show_sql = !self.show_sql;
+                ReplAction::Print(format!("SQL: {}", if self.show_sql { "on" } else { "off" }))
+            }
+            MetaCommand::Camera(id) => {
+                let msg = match &id {
+                    Some(id) => format!("Pytania dotyczą kamery {}", id),
+                    None => "Pytania dotyczą wszystkich kamer".to_string(),
+                };
+                self.camera = id;
+                ReplAction::Print(msg)
+            }
+            MetaCommand::Schema => ReplAction::Print(SCHEMA.trim().to_string()),
+            MetaCommand::ExportLast(path) => ReplAction::Print(match self.export_last(&path) {
+                Ok(n) => format!("Zapisano {} wierszy do {}", n, path.display()),
+                Err(e) => e,
+            }),
+        }
+    }
+
+    fn export_last(&self, path: &Path) -> Result<usize, String> {
+        let last = self.last.as_ref().ok_or("Brak wyników do eksportu")?;
+        std::fs::write(path, last.to_csv())
+            .map_err(|e| format!("Nie można zapisać {}: {}", path.display(), e))?;
+        Ok(last.rows.len())
+    }
+}
+
+/// Append the `\camera` scope so the generated SQL filters on `camera_id`.
+pub fn scope_question(question: &str, camera: Option<&str>) -> String {
+    match camera {
+        Some(id) => format!("{} (tylko kamera camera_id = '{}')", question, id.replace('\'', "''")),
+        None => question.to_string(),
+    }
+}
+
+// ─── Interactive CLI ─────────────────────────────────────────────────────────
+
+const HISTORY_FILE: &str = "vision_query_history.txt";
+
+/// `<data_local_dir>/broxeen/vision_query_history.txt`
+fn history_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("broxeen")
+        .join(HISTORY_FILE)
+}
+
+/// Line-edited REPL over a [`ReplSession`], history kept across sessions.
+/// Ctrl-C while a question runs cancels its LLM call; at the prompt it drops
+/// the half-typed question. Ctrl-D ends the session.
+pub async fn repl(engine: &QueryEngine<'_>) -> Result<()> {
+    let mut editor = rustyline::DefaultEditor::new()?;
+    let history = history_path();
+    if let Some(dir) = history.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let _ = editor.load_history(&history);
+
+    println!("Pytania kończy pusta linia. Polecenia: \\sql, \\camera <id>, \\schema, \\export last <plik.csv>");
+    let mut session = ReplSession::new();
+    loop {
+        let line = match editor.readline(&session.prompt()) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => {
+                session.discard_input();
+                continue;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        if !line.trim().is_empty() {
+            let _ = editor.add_history_entry(line.as_str());
+        }
+        match session.handle_line(&line) {
+            ReplAction::Continue => {}
+            ReplAction::Print(text) => println!("{}", text),
+            ReplAction::Ask(question) => {
+                let cancel = async {
+                    let _ = tokio::signal::ctrl_c().await;
+                };
+                match engine.ask_cancellable(&question, cancel).await {
+                    Ok(result) => println!("{}", session.finish(result)),
+                    Err(e) => eprintln!("Błąd: {}", e),
+                }
+            }
+        }
+    }
+    if let Err(e) = editor.save_history(&history) {
+        warn!("Query history not saved to {}: {}", history.display(), e);
+    }
+    Ok(())
+}
+
+/// `broxeen --vision-query`: [`repl`] on the database and LLM from broxeen.toml.
+pub fn run_cli() -> Result<()> {
+    let cfg = crate::vision_config::load_config()?;
+    let db = VisionDatabase::open(&cfg.database.path)?;
+    let client = LlmClient::from_config(&cfg.llm);
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+    runtime.block_on(repl(&QueryEngine::new(&db, &client)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repl_multiline_meta_and_csv() {
+        let mut repl = ReplSession::new();
+        assert_eq!(repl.handle_line("\\camera front-door"), ReplAction::Print("Pytania dotyczą kamery front-door".into()));
+        assert_eq!(repl.prompt(), "vision[front-door]> ");
+        assert_eq!(repl.handle_line("Ile osób"), ReplAction::Continue);
+        // Meta commands are plain text inside a multi-line question
+        assert_eq!(repl.handle_line("\\sql"), ReplAction::Continue);
+        assert_eq!(
+            repl.handle_line(""),
+            ReplAction::Ask("Ile osób\n\\sql (tylko kamera camera_id = 'front-door')".into())
+        );
+        assert_eq!(repl.handle_line("   "), ReplAction::Continue);
+        assert!(matches!(repl.handle_line("\\export"), ReplAction::Print(e) if e.starts_with("Użycie")));
+        assert!(matches!(repl.handle_line("\\thumb 3"), ReplAction::Print(e) if e.starts_with("Nieznane")));
+
+        let result = QueryResult {
+            question: "q".into(),
+            sql: "SELECT 1".into(),
+            columns: vec!["label".into(), "note".into()],
+            rows: vec![vec!["person".into(), "say \"hi\", twice".into()]],
+        };
+        assert_eq!(result.to_csv(), "label,note\r\nperson,\"say \"\"hi\"\", twice\"\r\n");
+        assert!(!repl.finish(result).contains("SELECT 1"));
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("last.csv");
+        let cmd = format!("\\export last {}", path.display());
+        assert!(matches!(repl.handle_line(&cmd), ReplAction::Print(m) if m.starts_with("Zapisano 1")));
+        assert!(std::fs::read_to_string(&path).unwrap().starts_with("label,note"));
+    }
 }
@@ -0,0 +1,271 @@
+//! vision_reload.rs — Hot reload of broxeen.toml into running pipelines.
+//!
+//! The config file is watched (and re-read on SIGHUP). Each reload is diffed
+//! against the previous load: whitelisted parameters — detector thresholds,
+//! MOG2 sensitivity, scene flush interval, LLM model / rate limit — are pushed
+//! to every running native pipeline over its config watch channel, so the
+//! background model and open tracks survive. Changes to anything the
+//! pipeline only reads at start (camera URL, model path, DB path…) just log
+//! that a restart is needed.
+
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::vision_config::{self, VisionConfig};
+
+/// Watched config file (working directory, as in `load_config`)
+pub const CONFIG_FILE: &str = "broxeen.toml";
+/// Editors write a file in several steps; coalesce them into one reload
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+lazy_static::lazy_static! {
+    /// Previous successful load; reloads are diffed against it
+    static ref LAST_LOADED: Mutex<Option<VisionConfig>> = Mutex::new(None);
+}
+
+/// One diffed config parameter; `apply` is set for live parameters.
+struct Field {
+    key: &'static str,
+    changed: fn(&VisionConfig, &VisionConfig) -> bool,
+    apply: Option<fn(&mut VisionConfig, &VisionConfig)>,
+}
+
+macro_rules! field {
+    (live $($path:ident).+) => {
+        Field {
+            key: stringify!($($path).+),
+            changed: |old, new| old.$($path).+ != new.$($path).+,
+            apply: Some(|running, new| running.$($path).+.clone_from(&new.$($path).+)),
+        }
+    };
+    (restart $($path:ident).+) => {
+        Field {
+            key: stringify!($($path).+),
+            changed: |old, new| old.$($path).+ != new.$($path).+,
+            apply: None,
+        }
+    };
+}
+
+const FIELDS: &[Field] = &[
+    field!(live detector.confidence_threshold),
+    field!(live detector.class_thresholds),
+    field!(live detector.enabled_classes),
+    field!(live pipeline.bg_var_threshold),
+    field!(live pipeline.min_activity_area),
+    field!(live pipeline.night_var_threshold),
+    field!(live pipeline.night_min_contour_area),
+    field!(live scene.flush_interval_secs),
+    field!(live scene.max_crops_per_batch),
+    field!(live scene.narrative_template),
+    field!(live scene.spill_max_mb),
+    field!(live llm.openrouter_api_key),
+    field!(live llm.openrouter_model),
+    field!(live llm.local_base_url),
+    field!(live llm.local_model),
+    field!(live llm.max_tokens),
+    field!(live llm.max_requests_per_minute),
+    field!(restart camera.url),
+    field!(restart camera.camera_id),
+    field!(restart camera.pixels_per_meter),
+    field!(restart detector.model_path),
+    field!(restart detector.input_size),
+    field!(restart detector.nms_threshold),
+    field!(restart detector.use_openvino),
+    field!(restart detector.device),
+    field!(restart detector.intra_threads),
+    field!(restart pipeline.process_every_n_frames),
+    field!(restart pipeline.bg_history),
+    field!(restart pipeline.night_luminance),
+    field!(restart pipeline.luminance_hysteresis),
+    field!(restart pipeline.force_night),
+    field!(restart pipeline.stall_timeout_secs),
+    field!(restart tracker.iou_match_threshold),
+    field!(restart tracker.max_age_frames),
+    field!(restart tracker.min_hits),
+    field!(restart scene.min_crops_for_llm),
+    field!(restart scene.ring_capacity),
+    field!(restart database.path),
+    field!(restart database.timezone),
+    field!(restart websocket.listen),
+    field!(restart notifications.webhook_url),
+];
+
+/// Parameters changed between two loads of the config.
+#[derive(Debug, Default, PartialEq)]
+pub struct ConfigDiff {
+    /// Applied to running pipelines
+    pub live: Vec<&'static str>,
+    /// Take effect on the next pipeline start
+    pub restart: Vec<&'static str>,
+}
+
+pub fn diff(old: &VisionConfig, new: &VisionConfig) -> ConfigDiff {
+    let mut out = ConfigDiff::default();
+    for field in FIELDS.iter().filter(|f| (f.changed)(old, new)) {
+        match field.apply {
+            Some(_) => out.live.push(field.key),
+            None => out.restart.push(field.key),
+        }
+    }
+    out
+}
+
+/// Copy the live parameters that changed from `old` to `new` into `running`.
+/// Parameters unchanged in the file keep their running value, which may
+/// come from the start request rather than the file.
+pub fn merge_live(running: &mut VisionConfig, old: &VisionConfig, new: &VisionConfig) {
+    for field in FIELDS.iter().filter(|f| (f.changed)(old, new)) {
+        if let Some(apply) = field.apply {
+            apply(running, new);
+        }
+    }
+}
+
+/// Re-read broxeen.toml and push live changes to the running pipelines.
+pub fn reload() {
+    let new = match vision_config::load_config() {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            warn!("{} reload failed: {} — keeping current parameters", CONFIG_FILE, e);
+            return;
+        }
+    };
+    let mut last = LAST_LOADED.lock().unwrap_or_else(|e| e.into_inner());
+    let old = last.take().unwrap_or_else(vision_config::default_config);
+    let changes = diff(&old, &new);
+
+    if !changes.restart.is_empty() {
+        warn!(
+            "{} changed {} — restart the pipeline to apply",
+            CONFIG_FILE,
+            changes.restart.join(", ")
+        );
+    }
+    if changes.live.is_empty() {
+        info!("{} reloaded — no live parameters changed", CONFIG_FILE);
+    } else {
+        let pipelines = crate::motion_detection::reconfigure_native_pipelines(&old, &new);
+        info!(
+            "{} reloaded — applied {} to {} running pipeline(s)",
+            CONFIG_FILE,
+            changes.live.join(", "),
+            pipelines
+        );
+    }
+    *last = Some(new);
+}
+
+/// Watch broxeen.toml (and SIGHUP on Unix) and reload on change.
+pub fn start() {
+    *LAST_LOADED.lock().unwrap_or_else(|e| e.into_inner()) = vision_config::load_config().ok();
+
+    let (tx, rx) = mpsc::channel::<()>();
+    let watcher = watch_file(tx.clone());
+    #[cfg(unix)]
+    watch_sighup(tx);
+
+    std::thread::spawn(move || {
+        // The watcher stops when dropped
+        let _watcher = watcher;
+        while rx.recv().is_ok() {
+            std::thread::sleep(DEBOUNCE);
+            while rx.try_recv().is_ok() {}
+            reload();
+        }
+    });
+}
+
+/// Watch the config file's directory: editors often replace the file by
+/// renaming a temporary one, which a watch on the file itself would miss.
+fn watch_file(tx: mpsc::Sender<()>) -> Option<notify::RecommendedWatcher> {
+    use notify::{EventKind, RecursiveMode, Watcher};
+
+    let path = std::env::current_dir().ok()?.join(CONFIG_FILE);
+    let dir = path.parent()?.to_path_buf();
+    let handler = move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else { return };
+        let ours = event.paths.iter().any(|p| p.file_name() == path.file_name());
+        if ours && !matches!(event.kind, EventKind::Access(_)) {
+            let _ = tx.send(());
+        }
+    };
+    let mut watcher = match notify::recommended_watcher(handler) {
+        Ok(w) => w,
+        Err(e) => {
+            warn!("{} watcher unavailable: {} — reload with SIGHUP", CONFIG_FILE, e);
+            return None;
+        }
+    };
+    if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+        warn!("Cannot watch {}: {} — reload with SIGHUP", dir.display(), e);
+        return None;
+    }
+    info!("Watching {} for live config changes", dir.join(CONFIG_FILE).display());
+    Some(watcher)
+}
+
+#[cfg(unix)]
+fn watch_sighup(tx: mpsc::Sender<()>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    tauri::async_runtime::spawn(async move {
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(s) => s,
+            Err(e) => {
+                warn!("SIGHUP handler unavailable: {}", e);
+                return;
+            }
+        };
+        while hangup.recv().await.is_some() {
+            info!("SIGHUP — reloading {}", CONFIG_FILE);
+            if tx.send(()).is_err() {
+                return;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_splits_live_and_restart_parameters() {
+        let old = vision_config::default_config();
+        let mut new = old.clone();
+        new.pipeline.min_activity_area += 200.0;
+        new.detector.class_thresholds.insert("person".into(), 0.6);
+        new.llm.openrouter_model = "google/gemini-2.5-flash".into();
+        new.camera.url = "rtsp://10.0.0.5/stream".into();
+        new.detector.model_path = "models/yolov8n.onnx".into();
+
+        let changes = diff(&old, &new);
+        assert_eq!(
+            changes.live,
+            vec!["detector.class_thresholds", "pipeline.min_activity_area", "llm.openrouter_model"]
+        );
+        assert_eq!(changes.restart, vec!["camera.url", "detector.model_path"]);
+        assert_eq!(diff(&new, &new), ConfigDiff::default());
+    }
+
+    #[test]
+    fn test_merge_live_only_touches_changed_live_parameters() {
+        let old = vision_config::default_config();
+        let mut new = old.clone();
+        new.scene.flush_interval_secs = 15;
+        new.camera.url = "rtsp://10.0.0.5/stream".into();
+
+        // Running values set by the start request, not by the file
+        let mut running = old.clone();
+        running.camera.url = "rtsp://cam/live".into();
+        running.detector.confidence_threshold = 0.8;
+
+        merge_live(&mut running, &old, &new);
+        assert_eq!(running.scene.flush_interval_secs, 15);
+        assert_eq!(running.camera.url, "rtsp://cam/live");
+        assert_eq!(running.detector.confidence_threshold, 0.8);
+    }
+}
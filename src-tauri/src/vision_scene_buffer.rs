@@ -3,6 +3,11 @@
 //!
 //! Sends minimum 3 crops (or all crops if fewer were detected).
 //! If no objects detected → skip LLM call entirely.
+//!
+//! Buffered events are also spilled to the vision DB (`scene_buffer`), so a
+//! crash or restart before the flush loses nothing: the next pipeline start
+//! [`restore`](MinuteBuffer::restore)s them and flushes them first as a
+//! recovered batch.
 
 use chrono::{DateTime, Utc};
 use std::collections::VecDeque;
@@ -17,11 +22,15 @@ use crate::vision_movement::MovementSummary;
 #[derive(Debug, Clone)]
 pub struct ObjectEvent {
     pub track_id:    uuid::Uuid,
+    /// Row in `detections`, when the insert succeeded
+    pub detection_id: Option<i64>,
     pub class:       String,
     pub confidence:  f32,
     pub movement:    MovementSummary,
     pub crops:       Vec<CropSnapshot>,   // ≤3 crops from tracker
     pub finished_at: DateTime<Utc>,
+    /// Row in `scene_buffer` while the event waits for the LLM flush
+    pub spill_id:    Option<i64>,
 }
 
 // ─── LLM batch payload ────────────────────────────────────────────────────────
@@ -30,6 +39,8 @@ pub struct MinuteBatch {
     pub events:       Vec<ObjectEvent>,
     pub period_start: DateTime<Utc>,
     pub period_end:   DateTime<Utc>,
+    /// Events restored from the spill of an earlier run
+    pub recovered:    bool,
 }
 
 impl MinuteBatch {
@@ -112,10 +123,14 @@ pub struct MinuteBuffer {
     ring_capacity:   usize,
     /// Minimum crops to bother sending to LLM
     min_crops:       usize,
+    /// Flush before the interval once this many events wait
+    max_events:      usize,
+    /// Holds events restored from an earlier run until they are drained
+    recovering:      bool,
 }
 
 impl MinuteBuffer {
-    pub fn new(flush_secs: u64, ring_capacity: usize, min_crops: usize) -> Self {
+    pub fn new(flush_secs: u64, ring_capacity: usize, min_crops: usize, max_events: usize) -> Self {
         Self {
             events:         VecDeque::new(),
             flush_interval: std::time::Duration::from_secs(flush_secs),
@@ -123,38 +138,74 @@ impl MinuteBuffer {
             period_start:   Utc::now(),
             ring_capacity,
             min_crops,
+            max_events:     max_events.max(1),
+            recovering:     false,
         }
     }
 
-    /// Add a completed track event.
-    pub fn push(&mut self, event: ObjectEvent) {
-        if self.events.len() >= self.ring_capacity {
-            self.events.pop_front();
-        }
+    /// Add a completed track event. Returns the oldest event when the ring
+    /// was full, so its spill can be dropped too.
+    pub fn push(&mut self, event: ObjectEvent) -> Option<ObjectEvent> {
+        let evicted = if self.events.len() >= self.ring_capacity {
+            self.events.pop_front()
+        } else {
+            None
+        };
         self.events.push_back(event);
         debug!("MinuteBuffer: {} events", self.events.len());
+        evicted
+    }
+
+    /// Take back events spilled by a run that ended before its flush. They
+    /// form a batch of their own, flushed on the next `should_flush`.
+    pub fn restore(&mut self, events: Vec<ObjectEvent>) {
+        if events.is_empty() { return; }
+        if let Some(first) = events.iter().map(|e| e.finished_at).min() {
+            self.period_start = first;
+        }
+        info!("MinuteBuffer: restored {} unflushed events", events.len());
+        self.events = events.into();
+        self.recovering = true;
+    }
+
+    /// Restored events are still waiting; new events should wait for them.
+    pub fn is_recovering(&self) -> bool {
+        self.recovering
     }
 
     /// Whether it's time to flush to LLM.
     pub fn should_flush(&self) -> bool {
         if self.events.is_empty() { return false; }
+        if self.recovering { return true; }
         // Has enough crops to be worth sending?
         let total_crops: usize = self.events.iter().map(|e| e.crops.len()).sum();
         if total_crops < self.min_crops { return false; }
-        self.last_flush.elapsed() >= self.flush_interval
+        self.events.len() >= self.max_events || self.last_flush.elapsed() >= self.flush_interval
     }
 
     /// Drain events and build batch. Resets timer.
     pub fn drain(&mut self) -> Option<MinuteBatch> {
         if self.events.is_empty() { return None; }
         let events: Vec<_> = self.events.drain(..).collect();
+        let recovered = std::mem::take(&mut self.recovering);
         let period_start = self.period_start;
-        let period_end   = Utc::now();
-        self.period_start = period_end;
+        let now = Utc::now();
+        // A recovered period ended with the earlier run, not now
+        let period_end = match events.iter().map(|e| e.finished_at).max() {
+            Some(last) if recovered => last,
+            _ => now,
+        };
+        self.period_start = now;
         self.last_flush   = Instant::now();
 
-        info!("Flushing {} events to LLM batch", events.len());
-        Some(MinuteBatch { events, period_start, period_end })
+        info!("Flushing {} events to LLM batch{}", events.len(), if recovered { " (recovered)" } else { "" });
+        Some(MinuteBatch { events, period_start, period_end, recovered })
+    }
+
+    /// Change the flush interval at runtime (config reload); the running
+    /// period keeps its start.
+    pub fn set_flush_interval(&mut self, flush_secs: u64) {
+        self.flush_interval = std::time::Duration::from_secs(flush_secs);
     }
 
     /// Force flush regardless of timer (used on shutdown).
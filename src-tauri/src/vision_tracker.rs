@@ -9,6 +9,7 @@ use opencv::{core::Mat, imgcodecs, imgproc, prelude::*};
 use tracing::debug;
 use uuid::Uuid;
 
+use crate::image_meta::ThumbnailFormat;
 use crate::vision_detector::Detection;
 
 // ─── Types ───────────────────────────────────────────────────────────────────
@@ -27,10 +28,29 @@ pub struct CompletedTrack {
     pub class:      String,
     pub confidence: f32,
     pub crops:      Vec<CropSnapshot>,
+    /// First crop encoded per `ThumbnailSpec`, stored with the detection
+    pub thumbnail:  Option<Vec<u8>>,
     pub positions:  Vec<(f32, f32, f32, f32)>,   // (x1,y1,x2,y2) history
     pub first_seen: DateTime<Utc>,
     pub last_seen:  DateTime<Utc>,
     pub hit_count:  u32,
+    /// (width, height) of the frames the positions are normalised to
+    pub frame_size: (u32, u32),
+}
+
+impl CompletedTrack {
+    /// Mean bbox centre over the track, normalised to the frame (heatmaps).
+    pub fn center(&self) -> Option<(f32, f32)> {
+        if self.positions.is_empty() {
+            return None;
+        }
+        let n = self.positions.len() as f32;
+        let (sx, sy) = self
+            .positions
+            .iter()
+            .fold((0.0, 0.0), |(sx, sy), p| (sx + (p.0 + p.2) / 2.0, sy + (p.1 + p.3) / 2.0));
+        Some((sx / n, sy / n))
+    }
 }
 
 /// Internal active track state.
@@ -42,12 +62,21 @@ struct ActiveTrack {
     age:        u32,       // frames since last match
     hits:       u32,       // total matched frames
     crops:      Vec<CropSnapshot>,
+    thumbnail:  Option<Vec<u8>>,
     positions:  Vec<(f32, f32, f32, f32)>,
     first_seen: DateTime<Utc>,
     last_seen:  DateTime<Utc>,
     max_crops:  usize,
 }
 
+/// How the stored detection thumbnail is encoded (`[database]` config).
+#[derive(Debug, Clone, Copy)]
+pub struct ThumbnailSpec {
+    pub format:  ThumbnailFormat,
+    pub quality: u8,
+    pub max_px:  u32,
+}
+
 // ─── Tracker ─────────────────────────────────────────────────────────────────
 
 pub struct Tracker {
@@ -57,6 +86,7 @@ pub struct Tracker {
     min_hits:      u32,
     crop_max_px:   u32,
     crops_per_track: usize,
+    thumbnail:     ThumbnailSpec,
 }
 
 impl Tracker {
@@ -66,6 +96,7 @@ impl Tracker {
         min_hits: u32,
         crop_max_px: u32,
         crops_per_track: usize,
+        thumbnail: ThumbnailSpec,
     ) -> Self {
         Self {
             tracks: Vec::new(),
@@ -74,6 +105,7 @@ impl Tracker {
             min_hits,
             crop_max_px,
             crops_per_track,
+            thumbnail,
         }
     }
 
@@ -85,6 +117,7 @@ impl Tracker {
         frame: &Mat,
     ) -> Vec<CompletedTrack> {
         let now = Utc::now();
+        let frame_size = (frame.cols().max(0) as u32, frame.rows().max(0) as u32);
         let mut completed = Vec::new();
 
         // ── 1. Match detections to existing tracks (greedy IoU) ──────────
@@ -131,6 +164,9 @@ impl Tracker {
                     });
                 }
             }
+            if track.thumbnail.is_none() {
+                track.thumbnail = extract_thumbnail(frame, det.bbox_norm, &self.thumbnail);
+            }
         }
 
         // ── 2. Create new tracks for unmatched detections ────────────────
@@ -151,6 +187,7 @@ impl Tracker {
                 age: 0,
                 hits: 1,
                 crops,
+                thumbnail: extract_thumbnail(frame, det.bbox_norm, &self.thumbnail),
                 positions: vec![det.bbox_norm],
                 first_seen: now,
                 last_seen: now,
@@ -172,10 +209,12 @@ impl Tracker {
                         class: track.class,
                         confidence: track.confidence,
                         crops: track.crops,
+                        thumbnail: track.thumbnail,
                         positions: track.positions,
                         first_seen: track.first_seen,
                         last_seen: track.last_seen,
                         hit_count: track.hits,
+                        frame_size,
                     });
                 }
                 debug!("Track {} retired (hits={})", track.id, track.hits);
@@ -207,6 +246,24 @@ fn compute_iou(a: (f32, f32, f32, f32), b: (f32, f32, f32, f32)) -> f32 {
 
 /// Extract a JPEG-encoded crop from a frame given normalised bbox [0..1].
 fn extract_crop(frame: &Mat, bbox_norm: (f32, f32, f32, f32), max_px: u32) -> Option<Vec<u8>> {
+    let crop = crop_region(frame, bbox_norm, max_px)?;
+    let bytes = encode_image(&crop, ThumbnailFormat::Jpeg, 75)?;
+    if bytes.len() < 256 { return None; } // skip tiny/blank crops
+
+    Some(bytes)
+}
+
+/// Crop for the detections table, resized and encoded per `spec`.
+fn extract_thumbnail(frame: &Mat, bbox_norm: (f32, f32, f32, f32), spec: &ThumbnailSpec) -> Option<Vec<u8>> {
+    let crop = crop_region(frame, bbox_norm, spec.max_px)?;
+    let bytes = encode_image(&crop, spec.format, spec.quality)?;
+    if bytes.len() < 128 { return None; }
+
+    Some(bytes)
+}
+
+/// Cut the bbox out of `frame`, downscaled so the longest edge is ≤ `max_px`.
+fn crop_region(frame: &Mat, bbox_norm: (f32, f32, f32, f32), max_px: u32) -> Option<Mat> {
     let fw = frame.cols() as f32;
     let fh = frame.rows() as f32;
 
@@ -227,32 +284,76 @@ fn extract_crop(frame: &Mat, bbox_norm: (f32, f32, f32, f32), max_px: u32) -> Op
 
     // Resize if too large
     let longest = crop.cols().max(crop.rows());
-    let final_crop = if longest > max_px as i32 {
+    if longest > max_px as i32 {
         let scale = max_px as f64 / longest as f64;
-        let new_w = (crop.cols() as f64 * scale) as i32;
-        let new_h = (crop.rows() as f64 * scale) as i32;
+        let new_w = ((crop.cols() as f64 * scale) as i32).max(1);
+        let new_h = ((crop.rows() as f64 * scale) as i32).max(1);
         let mut resized = Mat::default();
         imgproc::resize(
             &crop, &mut resized,
             opencv::core::Size::new(new_w, new_h),
             0.0, 0.0, imgproc::INTER_AREA,
         ).ok()?;
-        resized
+        Some(resized)
     } else {
         let mut out = Mat::default();
         crop.copy_to(&mut out).ok()?;
-        out
-    };
+        Some(out)
+    }
+}
 
-    // Encode to JPEG
+fn encode_image(img: &Mat, format: ThumbnailFormat, quality: u8) -> Option<Vec<u8>> {
+    let quality = quality.clamp(1, 100) as i32;
+    let (ext, param) = match format {
+        ThumbnailFormat::Jpeg => (".jpg", imgcodecs::IMWRITE_JPEG_QUALITY),
+        ThumbnailFormat::Webp => (".webp", imgcodecs::IMWRITE_WEBP_QUALITY),
+    };
     let mut buf = opencv::core::Vector::<u8>::new();
-    let params = opencv::core::Vector::from_iter([
-        imgcodecs::IMWRITE_JPEG_QUALITY, 75,
-    ]);
-    imgcodecs::imencode(".jpg", &final_crop, &mut buf, &params).ok()?;
+    let params = opencv::core::Vector::from_iter([param, quality]);
+    imgcodecs::imencode(ext, img, &mut buf, &params).ok()?;
+    Some(buf.to_vec())
+}
 
-    let bytes = buf.to_vec();
-    if bytes.len() < 256 { return None; } // skip tiny/blank crops
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opencv::core::{Point, Scalar, CV_8UC3};
+
+    /// Street-like fixture: sky gradient, road, a few solid objects.
+    fn fixture_frame() -> Mat {
+        let mut frame = Mat::new_rows_cols_with_default(360, 640, CV_8UC3, Scalar::all(0.0)).unwrap();
+        for y in 0..360 {
+            let shade = 255.0 - y as f64 * 0.5;
+            imgproc::line(
+                &mut frame, Point::new(0, y), Point::new(639, y),
+                Scalar::new(shade, shade * 0.8, 120.0, 0.0), 1, imgproc::LINE_8, 0,
+            ).unwrap();
+        }
+        let road = opencv::core::Rect::new(0, 240, 640, 120);
+        imgproc::rectangle(&mut frame, road, Scalar::new(70.0, 70.0, 70.0, 0.0), -1, imgproc::LINE_8, 0).unwrap();
+        let car = opencv::core::Rect::new(220, 200, 180, 80);
+        imgproc::rectangle(&mut frame, car, Scalar::new(30.0, 30.0, 200.0, 0.0), -1, imgproc::LINE_8, 0).unwrap();
+        for x in [250, 370] {
+            imgproc::circle(&mut frame, Point::new(x, 280), 18, Scalar::all(10.0), -1, imgproc::LINE_AA, 0).unwrap();
+        }
+        frame
+    }
 
-    Some(bytes)
+    #[test]
+    fn test_webp_thumbnail_smaller_than_jpeg() {
+        let frame = fixture_frame();
+        let bbox = (0.25, 0.45, 0.75, 0.9);
+        let spec = |format| ThumbnailSpec { format, quality: 75, max_px: 400 };
+
+        let jpeg = extract_thumbnail(&frame, bbox, &spec(ThumbnailFormat::Jpeg)).unwrap();
+        let webp = extract_thumbnail(&frame, bbox, &spec(ThumbnailFormat::Webp)).unwrap();
+        assert_eq!(ThumbnailFormat::detect(&jpeg), Some(ThumbnailFormat::Jpeg));
+        assert_eq!(ThumbnailFormat::detect(&webp), Some(ThumbnailFormat::Webp));
+        assert!(webp.len() < jpeg.len(), "webp {} B vs jpeg {} B", webp.len(), jpeg.len());
+
+        // max_px bounds the stored size
+        let small = extract_thumbnail(&frame, bbox, &ThumbnailSpec { max_px: 64, ..spec(ThumbnailFormat::Jpeg) }).unwrap();
+        let decoded = imgcodecs::imdecode(&opencv::core::Vector::from_slice(&small), imgcodecs::IMREAD_COLOR).unwrap();
+        assert_eq!(decoded.cols().max(decoded.rows()), 64);
+    }
 }
@@ -0,0 +1,142 @@
+//! Webhook sink for vision events (headless notifications).
+//!
+//! Each saved detection and each LLM narrative is queued (bounded) and POSTed
+//! as JSON by a background task, so a slow receiver never blocks the
+//! detection worker. Failed posts are retried 3× via `http_client::send_with_retry`
+//! (exponential backoff, `Retry-After` honoured).
+//!
+//! With `webhook_secret` set, the raw body is signed with HMAC-SHA256 and sent
+//! as `X-Broxeen-Signature: sha256=<hex>`.
+
+use serde_json::Value;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use crate::http_client::{self, send_with_retry, RetryPolicy};
+use crate::vision_config::NotificationsConfig;
+use crate::vision_pipeline::PipelineStats;
+
+const QUEUE_CAPACITY: usize = 100;
+const MAX_ATTEMPTS: u32 = 3;
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+struct WebhookEvent {
+    kind: &'static str,
+    payload: Value,
+}
+
+pub struct WebhookSink {
+    tx: mpsc::Sender<WebhookEvent>,
+    min_confidence: f32,
+    labels: Vec<String>,
+    stats: Arc<PipelineStats>,
+}
+
+impl WebhookSink {
+    /// Spawn the delivery task. Returns `None` when no webhook is configured.
+    pub fn spawn(cfg: &NotificationsConfig, stats: Arc<PipelineStats>) -> Option<Self> {
+        let url = cfg.webhook_url.as_ref().filter(|u| !u.trim().is_empty())?.clone();
+        let (tx, mut rx) = mpsc::channel::<WebhookEvent>(QUEUE_CAPACITY);
+
+        let http = http_client::client(Duration::from_secs(10)).ok()?;
+        let headers = cfg.webhook_headers.clone();
+        let secret = cfg.webhook_secret.clone().filter(|s| !s.is_empty());
+        let worker_stats = Arc::clone(&stats);
+
+        // Receivers dedupe on X-Broxeen-Event + body, so POST retries are opted in
+        let policy = RetryPolicy::default()
+            .max_retries(MAX_ATTEMPTS - 1)
+            .base_backoff(INITIAL_BACKOFF)
+            .allow_non_idempotent();
+
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                let body = event.payload.to_string();
+                let mut req = http
+                    .post(&url)
+                    .header("Content-Type", "application/json")
+                    .header("X-Broxeen-Event", event.kind);
+                for (k, v) in &headers {
+                    req = req.header(k.as_str(), v.as_str());
+                }
+                if let Some(ref secret) = secret {
+                    req = req.header("X-Broxeen-Signature", sign(secret, &body));
+                }
+
+                match send_with_retry(req.body(body), &policy).await {
+                    Ok((resp, meta)) if resp.status().is_success() => {
+                        worker_stats.webhooks_delivered.fetch_add(1, Ordering::Relaxed);
+                        debug!("Webhook {} delivered (retries: {})", event.kind, meta.retries);
+                    }
+                    Ok((resp, meta)) => {
+                        worker_stats.webhooks_failed.fetch_add(1, Ordering::Relaxed);
+                        warn!("Webhook {} HTTP {}{}", event.kind, resp.status(), meta.describe());
+                    }
+                    Err(e) => {
+                        worker_stats.webhooks_failed.fetch_add(1, Ordering::Relaxed);
+                        warn!("Webhook {} error: {}", event.kind, e);
+                    }
+                }
+            }
+        });
+
+        Some(Self {
+            tx,
+            min_confidence: cfg.min_confidence,
+            labels: cfg.labels.iter().map(|l| l.to_lowercase()).collect(),
+            stats,
+        })
+    }
+
+    /// Queue a detection event if it passes the confidence / label filters.
+    pub fn notify_detection(&self, label: &str, confidence: f32, payload: Value) {
+        if confidence < self.min_confidence {
+            return;
+        }
+        if !self.labels.is_empty() && !self.labels.iter().any(|l| l == &label.to_lowercase()) {
+            return;
+        }
+        self.enqueue("detection", payload);
+    }
+
+    /// Queue an LLM scene narrative.
+    pub fn notify_narrative(&self, payload: Value) {
+        self.enqueue("narrative", payload);
+    }
+
+    /// Queue a recorded-clip notification.
+    pub fn notify_clip(&self, payload: Value) {
+        self.enqueue("clip", payload);
+    }
+
+    fn enqueue(&self, kind: &'static str, payload: Value) {
+        if self.tx.try_send(WebhookEvent { kind, payload }).is_err() {
+            self.stats.webhooks_failed.fetch_add(1, Ordering::Relaxed);
+            warn!("Webhook queue full — dropping {} event", kind);
+        }
+    }
+}
+
+/// `sha256=<hex hmac>` of the request body.
+fn sign(secret: &str, body: &str) -> String {
+    let mac = hmac_sha256::HMAC::mac(body.as_bytes(), secret.as_bytes());
+    let hex: String = mac.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_matches_rfc4231_vector() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", "what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
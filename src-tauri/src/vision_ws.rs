@@ -0,0 +1,435 @@
+//! Live WebSocket feed of vision events for consumers outside the Tauri app.
+//!
+//! With the `vision_ws_listen` setting (e.g. "0.0.0.0:8931") a minimal RFC
+//! 6455 server broadcasts every saved detection, LLM narrative and a
+//! periodic stats snapshot of all pipelines as JSON text frames:
+//! `{"type":"detection","data":{...}}`. Clients connect to
+//! `ws://<host>:8931/?token=<token>` (token only when `vision_ws_token` is set).
+//!
+//! Each client reads from a bounded broadcast channel. A client that falls
+//! `CLIENT_BACKLOG` messages behind, or stalls a write, is disconnected
+//! instead of being buffered for. The server pings every 20 s and drops
+//! clients that stay silent for a minute.
+//!
+//! There is one server for the app. Changing either setting restarts it,
+//! clearing `vision_ws_listen` stops it, and `stop` closes it on app exit.
+
+use base64::Engine;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc, watch};
+use tracing::{debug, info, warn};
+
+const CLIENT_BACKLOG: usize = 256;
+const PING_INTERVAL: Duration = Duration::from_secs(20);
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(60);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_HANDSHAKE_BYTES: usize = 8192;
+/// Clients only send pongs and close frames — anything bigger is abuse.
+const MAX_CLIENT_FRAME: u64 = 4096;
+const WS_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const OP_TEXT: u8 = 0x1;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+/// Close code 1008 (policy violation), sent to clients dropped for lagging.
+const CLOSE_TOO_SLOW: [u8; 2] = [0x03, 0xF0];
+/// Close code 1001 (going away), sent when the server stops.
+const CLOSE_GOING_AWAY: [u8; 2] = [0x03, 0xE9];
+
+static SERVER: Mutex<Option<RunningServer>> = Mutex::new(None);
+
+struct RunningServer {
+    listen: String,
+    token: String,
+    broadcaster: Arc<WsBroadcaster>,
+    /// `true` stops the accept loop and closes every client
+    stop: watch::Sender<bool>,
+}
+
+pub struct WsBroadcaster {
+    tx: broadcast::Sender<Arc<str>>,
+}
+
+/// The running feed, `None` when it is off.
+pub fn current() -> Option<Arc<WsBroadcaster>> {
+    SERVER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(|s| Arc::clone(&s.broadcaster))
+}
+
+/// Start, restart or stop the server to match `listen` / `token`.
+pub fn apply(listen: &str, token: &str) {
+    let listen = listen.trim();
+    let mut server = SERVER.lock().unwrap_or_else(|e| e.into_inner());
+    if server.as_ref().is_some_and(|s| s.listen == listen && s.token == token) {
+        return;
+    }
+    if let Some(old) = server.take() {
+        info!("WebSocket feed on {} stopped", old.listen);
+        let _ = old.stop.send(true);
+    }
+    if listen.is_empty() {
+        return;
+    }
+
+    let (tx, _) = broadcast::channel(CLIENT_BACKLOG);
+    let (stop, stop_rx) = watch::channel(false);
+    tauri::async_runtime::spawn(accept_loop(
+        listen.to_string(),
+        (!token.is_empty()).then(|| Arc::from(token)),
+        tx.clone(),
+        stop_rx,
+    ));
+    *server = Some(RunningServer {
+        listen: listen.to_string(),
+        token: token.to_string(),
+        broadcaster: Arc::new(WsBroadcaster { tx }),
+        stop,
+    });
+}
+
+/// Close the server and its clients (app exit).
+pub fn stop() {
+    apply("", "");
+}
+
+/// Apply the `vision_ws_*` settings now and on every change.
+pub fn watch_ws_settings() {
+    let settings = crate::settings::load_settings();
+    apply(&settings.vision_ws_listen, &settings.vision_ws_token);
+    crate::settings::watch_settings("vision_ws", |change| {
+        apply(&change.settings.vision_ws_listen, &change.settings.vision_ws_token);
+    });
+}
+
+async fn accept_loop(
+    listen: String,
+    token: Option<Arc<str>>,
+    tx: broadcast::Sender<Arc<str>>,
+    mut stop: watch::Receiver<bool>,
+) {
+    let listener = match TcpListener::bind(&listen).await {
+        Ok(l) => l,
+        Err(e) => {
+            warn!("WebSocket feed: cannot listen on {}: {}", listen, e);
+            return;
+        }
+    };
+    info!("WebSocket feed listening on ws://{}", listen);
+    loop {
+        tokio::select! {
+            _ = stop.changed() => break,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, peer)) => {
+                    tokio::spawn(serve_client(stream, peer, token.clone(), tx.subscribe(), stop.clone()));
+                }
+                Err(e) => {
+                    warn!("WebSocket accept: {}", e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            },
+        }
+    }
+}
+
+impl WsBroadcaster {
+    /// Broadcast `{"type": kind, "data": data}` to every connected client.
+    pub fn publish(&self, kind: &str, data: Value) {
+        if self.tx.receiver_count() == 0 {
+            return;
+        }
+        let msg = serde_json::json!({ "type": kind, "data": data }).to_string();
+        let _ = self.tx.send(Arc::from(msg));
+    }
+}
+
+// ─── Handshake ──────────────────────────────────────────────────────────────
+
+#[derive(Debug, PartialEq)]
+enum HandshakeError {
+    BadRequest,
+    Unauthorized,
+}
+
+impl HandshakeError {
+    fn response(&self) -> &'static str {
+        match self {
+            Self::BadRequest => "HTTP/1.1 400 Bad Request\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+            Self::Unauthorized => "HTTP/1.1 401 Unauthorized\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+        }
+    }
+}
+
+/// `Sec-WebSocket-Accept` for a client key (RFC 6455 §4.2.2).
+fn accept_key(key: &str) -> String {
+    let digest = ring::digest::digest(
+        &ring::digest::SHA1_FOR_LEGACY_USE_ONLY,
+        format!("{}{}", key, WS_GUID).as_bytes(),
+    );
+    base64::engine::general_purpose::STANDARD.encode(digest.as_ref())
+}
+
+/// Constant-time comparison, so the token cannot be guessed byte by byte.
+fn tokens_match(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Validate an HTTP upgrade request and build the `101` response.
+fn handshake_response(request: &str, token: Option<&str>) -> Result<String, HandshakeError> {
+    let mut lines = request.split("\r\n");
+    let mut request_line = lines.next().unwrap_or("").split_whitespace();
+    if request_line.next() != Some("GET") {
+        return Err(HandshakeError::BadRequest);
+    }
+    let target = request_line.next().ok_or(HandshakeError::BadRequest)?;
+
+    let headers: HashMap<String, &str> = lines
+        .filter_map(|l| l.split_once(':'))
+        .map(|(k, v)| (k.trim().to_ascii_lowercase(), v.trim()))
+        .collect();
+    let upgrade = headers.get("upgrade").is_some_and(|v| v.eq_ignore_ascii_case("websocket"));
+    let key = headers.get("sec-websocket-key").filter(|k| !k.is_empty());
+    let (true, Some(key)) = (upgrade, key) else {
+        return Err(HandshakeError::BadRequest);
+    };
+
+    if let Some(expected) = token {
+        let given = target
+            .split_once('?')
+            .and_then(|(_, query)| {
+                url::form_urlencoded::parse(query.as_bytes())
+                    .find(|(k, _)| k == "token")
+                    .map(|(_, v)| v.into_owned())
+            })
+            .unwrap_or_default();
+        if !tokens_match(given.as_bytes(), expected.as_bytes()) {
+            return Err(HandshakeError::Unauthorized);
+        }
+    }
+
+    Ok(format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    ))
+}
+
+async fn read_handshake(stream: &mut TcpStream) -> std::io::Result<String> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        if buf.len() > MAX_HANDSHAKE_BYTES {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "handshake too large"));
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+// ─── Framing ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, PartialEq)]
+struct Frame {
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+/// Single unmasked, unfragmented server frame.
+fn encode_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(payload.len() + 10);
+    out.push(0x80 | opcode);
+    match payload.len() {
+        n if n < 126 => out.push(n as u8),
+        n if n <= u16::MAX as usize => {
+            out.push(126);
+            out.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        n => {
+            out.push(127);
+            out.extend_from_slice(&(n as u64).to_be_bytes());
+        }
+    }
+    out.extend_from_slice(payload);
+    out
+}
+
+/// Read one (masked) client frame.
+async fn read_frame<R: AsyncRead + Unpin>(r: &mut R) -> std::io::Result<Frame> {
+    let mut head = [0u8; 2];
+    r.read_exact(&mut head).await?;
+    let opcode = head[0] & 0x0F;
+    let masked = head[1] & 0x80 != 0;
+    let len = match head[1] & 0x7F {
+        126 => r.read_u16().await? as u64,
+        127 => r.read_u64().await?,
+        n => n as u64,
+    };
+    if !masked || len > MAX_CLIENT_FRAME {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid client frame"));
+    }
+    let mut mask = [0u8; 4];
+    r.read_exact(&mut mask).await?;
+    let mut payload = vec![0u8; len as usize];
+    r.read_exact(&mut payload).await?;
+    for (i, b) in payload.iter_mut().enumerate() {
+        *b ^= mask[i % 4];
+    }
+    Ok(Frame { opcode, payload })
+}
+
+async fn send<W: AsyncWrite + Unpin>(w: &mut W, opcode: u8, payload: &[u8]) -> std::io::Result<()> {
+    let frame = encode_frame(opcode, payload);
+    match tokio::time::timeout(WRITE_TIMEOUT, w.write_all(&frame)).await {
+        Ok(res) => res,
+        Err(_) => Err(std::io::ErrorKind::TimedOut.into()),
+    }
+}
+
+// ─── Client loop ────────────────────────────────────────────────────────────
+
+async fn serve_client(
+    mut stream: TcpStream,
+    peer: SocketAddr,
+    token: Option<Arc<str>>,
+    mut rx: broadcast::Receiver<Arc<str>>,
+    mut stop: watch::Receiver<bool>,
+) {
+    let request = match tokio::time::timeout(HANDSHAKE_TIMEOUT, read_handshake(&mut stream)).await {
+        Ok(Ok(r)) => r,
+        _ => {
+            debug!("WebSocket {}: no valid handshake", peer);
+            return;
+        }
+    };
+    match handshake_response(&request, token.as_deref()) {
+        Ok(resp) => {
+            if stream.write_all(resp.as_bytes()).await.is_err() {
+                return;
+            }
+        }
+        Err(e) => {
+            warn!("WebSocket {} rejected: {:?}", peer, e);
+            let _ = stream.write_all(e.response().as_bytes()).await;
+            return;
+        }
+    }
+    info!("WebSocket client connected: {}", peer);
+
+    let (mut rd, mut wr) = stream.into_split();
+    let (frame_tx, mut frame_rx) = mpsc::channel::<Frame>(8);
+    let reader = tokio::spawn(async move {
+        while let Ok(frame) = read_frame(&mut rd).await {
+            if frame_tx.send(frame).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+    ping.tick().await;
+    let mut last_seen = Instant::now();
+
+    let reason = loop {
+        tokio::select! {
+            _ = stop.changed() => {
+                let _ = send(&mut wr, OP_CLOSE, &CLOSE_GOING_AWAY).await;
+                break "server stopped".to_string();
+            }
+            msg = rx.recv() => match msg {
+                Ok(text) => {
+                    if let Err(e) = send(&mut wr, OP_TEXT, text.as_bytes()).await {
+                        break format!("write failed: {}", e);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    let _ = send(&mut wr, OP_CLOSE, &CLOSE_TOO_SLOW).await;
+                    break format!("too slow, {} message(s) behind", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => break "server closed".to_string(),
+            },
+            frame = frame_rx.recv() => match frame {
+                Some(Frame { opcode: OP_PING, payload }) => {
+                    last_seen = Instant::now();
+                    if send(&mut wr, OP_PONG, &payload).await.is_err() {
+                        break "write failed".to_string();
+                    }
+                }
+                Some(Frame { opcode: OP_CLOSE, .. }) => {
+                    let _ = send(&mut wr, OP_CLOSE, &[]).await;
+                    break "closed by client".to_string();
+                }
+                Some(_) => last_seen = Instant::now(),
+                None => break "connection lost".to_string(),
+            },
+            _ = ping.tick() => {
+                if last_seen.elapsed() > CLIENT_TIMEOUT {
+                    break "no pong".to_string();
+                }
+                if send(&mut wr, OP_PING, &[]).await.is_err() {
+                    break "write failed".to_string();
+                }
+            }
+        }
+    };
+    reader.abort();
+    info!("WebSocket client {} disconnected: {}", peer, reason);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handshake_checks_upgrade_and_token() {
+        // RFC 6455 §1.3 example
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+
+        let req = |target: &str| {
+            format!(
+                "GET {} HTTP/1.1\r\nHost: nuc:8931\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+                 Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n",
+                target
+            )
+        };
+        let ok = handshake_response(&req("/?token=s%3Dcret"), Some("s=cret")).unwrap();
+        assert!(ok.starts_with("HTTP/1.1 101"));
+        assert!(ok.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+
+        assert_eq!(handshake_response(&req("/?token=nope"), Some("s=cret")), Err(HandshakeError::Unauthorized));
+        assert_eq!(handshake_response(&req("/"), Some("s=cret")), Err(HandshakeError::Unauthorized));
+        assert!(handshake_response(&req("/"), None).is_ok());
+        assert_eq!(
+            handshake_response("GET / HTTP/1.1\r\nHost: nuc\r\n\r\n", None),
+            Err(HandshakeError::BadRequest)
+        );
+    }
+
+    #[tokio::test]
+    async fn frames_roundtrip() {
+        assert_eq!(encode_frame(OP_TEXT, b"hi"), vec![0x81, 2, b'h', b'i']);
+        assert_eq!(&encode_frame(OP_TEXT, &[0; 200])[..4], &[0x81, 126, 0, 200]);
+        assert_eq!(&encode_frame(OP_TEXT, &[0; 70_000])[..10], &[0x81, 127, 0, 0, 0, 0, 0, 1, 0x11, 0x70]);
+
+        // Masked "Hello" pong from RFC 6455 §5.7
+        let client = [0x8A, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58];
+        let frame = read_frame(&mut &client[..]).await.unwrap();
+        assert_eq!(frame, Frame { opcode: OP_PONG, payload: b"Hello".to_vec() });
+
+        // Unmasked client frames are rejected
+        assert!(read_frame(&mut &encode_frame(OP_PONG, b"x")[..]).await.is_err());
+    }
+}
@@ -0,0 +1,130 @@
+//! Polygon zones for the vision pipeline.
+//!
+//! Coordinates are normalised to the frame (0..1, origin top-left), so zones
+//! survive resolution changes. `exclude` zones drop motion / detections whose
+//! bbox centroid falls inside; when any `include` zone exists, only centroids
+//! inside an include zone are kept. Zone names are recorded as entry/exit zones.
+//!
+//! Pure geometry — compiled without the `vision` feature so the Tauri command
+//! can validate payloads in every build.
+#![cfg_attr(not(feature = "vision"), allow(dead_code))]
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ZoneMode {
+    #[default]
+    Include,
+    Exclude,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Zone {
+    pub name: String,
+    /// Normalised (x, y) vertices, at least 3
+    pub polygon: Vec<(f32, f32)>,
+    #[serde(default)]
+    pub mode: ZoneMode,
+}
+
+impl Zone {
+    /// Even-odd ray casting; points on the edge may land either way.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        let pts = &self.polygon;
+        let mut inside = false;
+        let mut j = pts.len().wrapping_sub(1);
+        for (i, &(xi, yi)) in pts.iter().enumerate() {
+            let (xj, yj) = pts[j];
+            if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+                inside = !inside;
+            }
+            j = i;
+        }
+        inside
+    }
+}
+
+/// Reject zones the pipeline could not use. Messages name the offending zone.
+pub fn validate_zones(zones: &[Zone]) -> Result<(), String> {
+    for (i, zone) in zones.iter().enumerate() {
+        let label = if zone.name.trim().is_empty() { format!("#{}", i + 1) } else { zone.name.clone() };
+        if zone.name.trim().is_empty() {
+            return Err(format!("Strefa {}: brak nazwy", label));
+        }
+        if zone.polygon.len() < 3 {
+            return Err(format!("Strefa {}: wielokąt musi mieć co najmniej 3 punkty", label));
+        }
+        if let Some((x, y)) = zone
+            .polygon
+            .iter()
+            .find(|(x, y)| !(0.0..=1.0).contains(x) || !(0.0..=1.0).contains(y))
+        {
+            return Err(format!("Strefa {}: punkt ({}, {}) poza zakresem [0, 1]", label, x, y));
+        }
+    }
+    Ok(())
+}
+
+/// Whether a centroid passes the zone filter.
+pub fn allows(zones: &[Zone], x: f32, y: f32) -> bool {
+    if zones.iter().any(|z| z.mode == ZoneMode::Exclude && z.contains(x, y)) {
+        return false;
+    }
+    let mut includes = zones.iter().filter(|z| z.mode == ZoneMode::Include).peekable();
+    includes.peek().is_none() || includes.any(|z| z.contains(x, y))
+}
+
+/// Name of the first include zone containing the centroid.
+pub fn zone_name_at(zones: &[Zone], x: f32, y: f32) -> Option<&str> {
+    zones
+        .iter()
+        .find(|z| z.mode == ZoneMode::Include && z.contains(x, y))
+        .map(|z| z.name.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zone(name: &str, mode: ZoneMode, polygon: &[(f32, f32)]) -> Zone {
+        Zone { name: name.into(), polygon: polygon.to_vec(), mode }
+    }
+
+    #[test]
+    fn test_filtering_and_names() {
+        // Street across the bottom third, driveway on the left
+        let street = zone("ulica", ZoneMode::Exclude, &[(0.0, 0.7), (1.0, 0.7), (1.0, 1.0), (0.0, 1.0)]);
+        let driveway = zone("podjazd", ZoneMode::Include, &[(0.0, 0.0), (0.5, 0.0), (0.4, 0.8), (0.0, 0.8)]);
+
+        let zones = vec![street.clone()];
+        assert!(!allows(&zones, 0.5, 0.9));
+        assert!(allows(&zones, 0.5, 0.3));
+        assert_eq!(zone_name_at(&zones, 0.5, 0.3), None);
+
+        let zones = vec![street, driveway];
+        assert!(allows(&zones, 0.2, 0.4));
+        assert!(!allows(&zones, 0.8, 0.4), "outside every include zone");
+        assert!(!allows(&zones, 0.2, 0.75), "exclude wins over include");
+        assert_eq!(zone_name_at(&zones, 0.2, 0.4), Some("podjazd"));
+    }
+
+    #[test]
+    fn test_validate_zones() {
+        let ok = zone("a", ZoneMode::Include, &[(0.0, 0.0), (1.0, 0.0), (0.5, 1.0)]);
+        assert!(validate_zones(std::slice::from_ref(&ok)).is_ok());
+        assert!(validate_zones(&[zone("b", ZoneMode::Exclude, &[(0.0, 0.0), (1.0, 1.0)])])
+            .unwrap_err()
+            .contains("3 punkty"));
+        assert!(validate_zones(&[zone("c", ZoneMode::Include, &[(0.0, 0.0), (1.2, 0.0), (0.5, 1.0)])])
+            .unwrap_err()
+            .contains("[0, 1]"));
+        assert!(validate_zones(&[zone(" ", ZoneMode::Include, &ok.polygon)]).is_err());
+
+        let parsed: Zone = serde_json::from_value(serde_json::json!({
+            "name": "ulica", "polygon": [[0, 0.7], [1, 0.7], [1, 1]], "mode": "exclude"
+        }))
+        .unwrap();
+        assert_eq!(parsed.mode, ZoneMode::Exclude);
+    }
+}
@@ -1,12 +1,154 @@
 //! wake_word.rs — Wake word detection for hands-free activation.
 //! Lightweight local detection without LLM - uses VAD + phonetic matching.
-//! Trigger phrase: "heyken"
+//! Trigger phrase: `wake_word_phrase` from settings (default "heyken"),
+//! spelled out as broad sound classes (vowel, nasal, fricative…) whose
+//! energy / zero-crossing template is aligned against the microphone with
+//! DTW; `wake_word_sensitivity` (0.0–1.0) lowers both the voice-activity RMS
+//! gate and the match threshold as it rises.
 
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::traits::{DeviceTrait, StreamTrait};
 use std::sync::{Arc, Mutex};
 use std::collections::VecDeque;
 use tauri::Emitter;
 
+pub const DEFAULT_PHRASE: &str = "heyken";
+pub const DEFAULT_SENSITIVITY: f32 = 0.5;
+
+/// Phrase + sensitivity, resolved from settings (optionally overridden).
+#[derive(Debug, Clone, PartialEq)]
+pub struct WakeWordConfig {
+    pub phrase: String,
+    pub sensitivity: f32,
+}
+
+impl Default for WakeWordConfig {
+    fn default() -> Self {
+        Self { phrase: DEFAULT_PHRASE.to_string(), sensitivity: DEFAULT_SENSITIVITY }
+    }
+}
+
+impl WakeWordConfig {
+    pub fn from_settings(settings: &crate::settings::AudioSettings) -> Self {
+        Self::default().with_overrides(
+            Some(settings.wake_word_phrase.clone()),
+            Some(settings.wake_word_sensitivity),
+        )
+    }
+
+    pub fn with_overrides(mut self, phrase: Option<String>, sensitivity: Option<f32>) -> Self {
+        if let Some(phrase) = phrase.map(|p| p.trim().to_lowercase()).filter(|p| !p.is_empty()) {
+            self.phrase = phrase;
+        }
+        if let Some(sensitivity) = sensitivity.filter(|s| s.is_finite()) {
+            self.sensitivity = sensitivity.clamp(0.0, 1.0);
+        }
+        self
+    }
+
+    /// Voice-activity gate: 0.025 (strict) … 0.005 (sensitive); 0.015 at 0.5.
+    pub fn rms_threshold(&self) -> f32 {
+        0.025 - 0.02 * self.sensitivity
+    }
+
+    /// Match score needed to trigger: 0.85 (strict) … 0.55 (sensitive); 0.7 at 0.5.
+    pub fn trigger_threshold(&self) -> f32 {
+        0.85 - 0.3 * self.sensitivity
+    }
+
+    pub fn phonetic_form(&self) -> Vec<Phone> {
+        phonetic_form(&self.phrase)
+    }
+}
+
+/// Broad sound classes that energy and zero-crossing rate can tell apart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phone {
+    Vowel,
+    Nasal,
+    Liquid,
+    Fricative,
+    Stop,
+}
+
+impl Phone {
+    /// Expected (energy relative to the loudest frame, scaled zero-crossing
+    /// rate) and typical length in 10 ms frames.
+    fn profile(self) -> (f32, f32, usize) {
+        match self {
+            Phone::Vowel => (1.0, 0.05, 12),
+            Phone::Nasal => (0.5, 0.05, 7),
+            Phone::Liquid => (0.65, 0.05, 6),
+            Phone::Fricative => (0.3, 0.9, 9),
+            Phone::Stop => (0.05, 0.2, 5),
+        }
+    }
+}
+
+/// Spell a phrase as sound classes (Polish digraphs included), merging
+/// neighbours of the same class: "heyken" → fricative, vowel, stop, vowel, nasal.
+fn phonetic_form(phrase: &str) -> Vec<Phone> {
+    let chars: Vec<char> = phrase.to_lowercase().chars().collect();
+    let mut form: Vec<Phone> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let pair: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+        let (phone, len) = match pair.as_str() {
+            "sz" | "cz" | "rz" | "ch" | "dz" | "dź" | "dż" => (Some(Phone::Fricative), 2),
+            _ => {
+                let phone = match chars[i] {
+                    'a' | 'e' | 'i' | 'o' | 'u' | 'y' | 'ó' | 'ą' | 'ę' => Some(Phone::Vowel),
+                    'm' | 'n' | 'ń' => Some(Phone::Nasal),
+                    'l' | 'ł' | 'r' | 'w' | 'j' => Some(Phone::Liquid),
+                    'f' | 'v' | 's' | 'ś' | 'z' | 'ź' | 'ż' | 'h' | 'c' | 'ć' | 'x' => Some(Phone::Fricative),
+                    'p' | 'b' | 't' | 'd' | 'k' | 'g' | 'q' => Some(Phone::Stop),
+                    _ => None,
+                };
+                (phone, 1)
+            }
+        };
+        if let Some(phone) = phone {
+            if form.last() != Some(&phone) {
+                form.push(phone);
+            }
+        }
+        i += len;
+    }
+    form
+}
+
+/// Expected per-frame features of the phrase.
+fn phrase_template(form: &[Phone]) -> Vec<(f32, f32)> {
+    form.iter()
+        .flat_map(|phone| {
+            let (energy, zcr, frames) = phone.profile();
+            std::iter::repeat_n((energy, zcr), frames)
+        })
+        .collect()
+}
+
+/// Longest stretch of audio (in frames) a phrase may take: 2.5× its template.
+fn max_phrase_frames(template_len: usize) -> usize {
+    template_len * 5 / 2
+}
+
+const HOP_SECS: f32 = 0.01;
+
+/// RMS and zero-crossing rate of 20 ms windows every 10 ms.
+fn frame_features(audio: &[f32], sample_rate: u32) -> Vec<(f32, f32)> {
+    let hop = ((sample_rate as f32 * HOP_SECS) as usize).max(1);
+    let win = hop * 2;
+    let mut out = Vec::with_capacity(audio.len() / hop);
+    let mut start = 0;
+    while start + win <= audio.len() {
+        let window = &audio[start..start + win];
+        let rms = (window.iter().map(|s| s * s).sum::<f32>() / win as f32).sqrt();
+        let crossings = window.windows(2).filter(|p| (p[0] >= 0.0) != (p[1] >= 0.0)).count();
+        out.push((rms, crossings as f32 / win as f32));
+        start += hop;
+    }
+    out
+}
+
 /// State for wake word detection
 pub struct WakeWordState {
     pub is_listening: bool,
@@ -15,19 +157,28 @@ pub struct WakeWordState {
     pub audio_buffer: VecDeque<f32>,
     pub rms_threshold: f32,
     pub sample_rate: u32,
+    pub config: WakeWordConfig,
 }
 
 impl WakeWordState {
     pub fn new() -> Self {
+        let config = WakeWordConfig::default();
         Self {
             is_listening: false,
             triggered: false,
             trigger_time: None,
             audio_buffer: VecDeque::with_capacity(16000 * 3), // 3 seconds at 16kHz
-            rms_threshold: 0.015, // Same as silence detection
+            rms_threshold: config.rms_threshold(), // Same as silence detection at default sensitivity
             sample_rate: 16000,
+            config,
         }
     }
+
+    /// Apply a new phrase / sensitivity (takes effect on the next audio callback).
+    pub fn set_config(&mut self, config: WakeWordConfig) {
+        self.rms_threshold = config.rms_threshold();
+        self.config = config;
+    }
     
     pub fn reset(&mut self) {
         self.triggered = false;
@@ -38,116 +189,143 @@ impl WakeWordState {
 
 pub type SharedWakeWordState = Arc<Mutex<WakeWordState>>;
 
-/// Simple phonetic pattern matching for "heyken"
-/// Returns confidence score 0.0-1.0
-fn match_wake_word(audio: &[f32], sample_rate: u32) -> f32 {
-    let min_samples = (sample_rate as f32 * 0.5) as usize; // Need at least 0.5s
-    if audio.len() < min_samples {
+/// Align the phrase template with the end of `audio` (trailing silence
+/// ignored): the phrase may start at any frame but must end at the last
+/// voiced one. Returns confidence score 0.0-1.0
+fn match_wake_word(audio: &[f32], sample_rate: u32, form: &[Phone]) -> f32 {
+    let template = phrase_template(form);
+    if template.is_empty() {
         return 0.0;
     }
-    
-    // Calculate RMS energy profile over time windows
-    let window_size = (sample_rate as f32 * 0.1) as usize; // 100ms windows
-    let num_windows = audio.len() / window_size;
-    
-    if num_windows < 2 {
+    let max_frames = max_phrase_frames(template.len());
+
+    let mut frames = frame_features(audio, sample_rate);
+    let peak = frames.iter().rev().take(max_frames * 2).map(|f| f.0).fold(0.0f32, f32::max);
+    if peak <= 0.0 {
         return 0.0;
     }
-    
-    // Extract energy profile
-    let mut energy_profile = Vec::with_capacity(num_windows);
-    for i in 0..num_windows {
-        let start = i * window_size;
-        let end = ((i + 1) * window_size).min(audio.len());
-        let window = &audio[start..end];
-        
-        let rms = (window.iter().map(|s| s * s).sum::<f32>() / window.len() as f32).sqrt();
-        energy_profile.push(rms);
+    while frames.last().is_some_and(|f| f.0 < peak * 0.1) {
+        frames.pop();
     }
-    
-    // Pattern for "heyken": 2 syllables = 2 energy peaks
-    // Expected pattern: low-high-low-high-low (2 syllables with gap)
-    if energy_profile.len() < 4 {
+    let frames = &frames[frames.len().saturating_sub(max_frames)..];
+    if frames.len() < template.len() / 2 {
         return 0.0;
     }
-    
-    // Find peaks in energy profile
-    let threshold = energy_profile.iter().sum::<f32>() / energy_profile.len() as f32 * 0.6;
-    let mut peaks = 0;
-    let mut in_peak = false;
-    
-    for &energy in &energy_profile {
-        if energy > threshold && !in_peak {
-            peaks += 1;
-            in_peak = true;
-        } else if energy < threshold * 0.7 {
-            in_peak = false;
+    let observed: Vec<(f32, f32)> = frames
+        .iter()
+        .map(|&(rms, zcr)| (rms / peak, (zcr * 2.0).min(1.0)))
+        .collect();
+
+    // Slope-limited DTW: each template frame takes one audio frame, the
+    // phrase may be spoken up to 2× faster or slower, and every template
+    // frame is paid for exactly once, so long easy stretches cannot dilute
+    // the phones that do not fit.
+    let cell = |i: usize, j: usize| {
+        let (t, o) = (template[i], observed[j]);
+        (t.0 - o.0).abs() + 0.5 * (t.1 - o.1).abs()
+    };
+    let (n, m) = (template.len(), observed.len());
+    let mut cost = vec![vec![f32::INFINITY; m]; n];
+    for (j, c) in cost[0].iter_mut().enumerate() {
+        *c = cell(0, j);
+    }
+    for i in 1..n {
+        for j in 1..m {
+            let mut best = cost[i - 1][j - 1];
+            if j >= 2 {
+                best = best.min(cost[i - 1][j - 2]);
+            }
+            if i >= 2 {
+                best = best.min(cost[i - 2][j - 1] + cell(i - 1, j));
+            }
+            cost[i][j] = best + cell(i, j);
         }
     }
-    
-    // "heyken" should have ~2 syllables = ~2 peaks
-    if peaks >= 2 && peaks <= 3 {
-        // Additional check: total duration should be 0.5-1.5s
-        let duration_sec = audio.len() as f32 / sample_rate as f32;
-        if duration_sec >= 0.5 && duration_sec <= 1.5 {
-            return 0.7 + (0.3 * (1.0 - (peaks as f32 - 2.0).abs() / 2.0));
+    (1.0 - (cost[n - 1][m - 1] / n as f32) / 0.5).clamp(0.0, 1.0)
+}
+
+/// Best score over a recording (sliding 100 ms), plus its peak RMS — used by `wake_word_test`.
+pub fn score_recording(audio: &[f32], sample_rate: u32, config: &WakeWordConfig) -> (f32, f32) {
+    let step = (sample_rate as usize / 10).max(1);
+    let form = config.phonetic_form();
+    let max_frames = max_phrase_frames(phrase_template(&form).len());
+    let max_samples = (max_frames as f32 * HOP_SECS * sample_rate as f32) as usize;
+    let mut best = 0.0f32;
+    let mut best_rms = 0.0f32;
+    let mut end = step;
+    while end <= audio.len() {
+        let window = &audio[end.saturating_sub(max_samples)..end];
+        let rms = (window.iter().map(|s| s * s).sum::<f32>() / window.len().max(1) as f32).sqrt();
+        best_rms = best_rms.max(rms);
+        if rms > config.rms_threshold() {
+            best = best.max(match_wake_word(&audio[..end], sample_rate, &form));
         }
+        end += step;
     }
-    
-    0.0
+    (best, best_rms)
 }
 
 /// Start continuous wake word listening
 pub fn start_wake_word_listening(
     state: &SharedWakeWordState,
     app_handle: tauri::AppHandle,
+    config: WakeWordConfig,
 ) -> Result<cpal::Stream, String> {
     let host = cpal::default_host();
-    let device = host
-        .default_input_device()
-        .ok_or("No input device (microphone) found")?;
+    // Same microphone as recordings (mic_device_id)
+    let (device, _) = crate::audio_capture::select_input_device(&host, &crate::settings::load_settings().mic_device_id)?;
 
     let device_name = device.name().unwrap_or_else(|_| "unknown".into());
     println!("[wake-word] Using input device: {device_name}");
 
     // Get default input config
-    let config = device
+    let input_config = device
         .default_input_config()
         .map_err(|e| format!("Cannot get input config: {e}"))?;
     
-    let sample_rate = config.sample_rate().0;
-    let channels = config.channels() as usize;
+    let sample_rate = input_config.sample_rate().0;
+    let channels = input_config.channels() as usize;
     
     // Update state
     {
         let mut s = state.lock().unwrap();
         s.is_listening = true;
         s.sample_rate = sample_rate;
+        s.set_config(config);
         s.reset();
+        println!(
+            "[wake-word] Phrase: \"{}\" ({:?}), sensitivity: {:.2}",
+            s.config.phrase, s.config.phonetic_form(), s.config.sensitivity
+        );
     }
 
     let state_clone = Arc::clone(state);
     let err_fn = |err| eprintln!("[wake-word] Recording error: {err}");
+    let voice_rms = state.lock().unwrap().rms_threshold;
+    let mut meter = crate::audio_level::LevelMeter::new("wake_word", sample_rate, voice_rms);
 
     let stream = device
         .build_input_stream(
-            &config.into(),
+            &input_config.into(),
             move |data: &[f32], _: &cpal::InputCallbackInfo| {
                 let mut s = state_clone.lock().unwrap();
                 if !s.is_listening {
                     return;
                 }
-                
+
+                // Convert to mono; the level meter keeps running after a trigger
+                let mono: Vec<f32> = data
+                    .chunks(channels)
+                    .map(|chunk| chunk.iter().sum::<f32>() / channels as f32)
+                    .collect();
+                meter.push_and_emit(&app_handle, &mono);
+
                 // Already triggered, don't process more until reset
                 if s.triggered {
                     return;
                 }
-                
-                // Convert to mono and add to buffer
-                for chunk in data.chunks(channels) {
-                    let mono: f32 = chunk.iter().sum::<f32>() / channels as f32;
-                    s.audio_buffer.push_back(mono);
-                }
+
+                s.audio_buffer.extend(mono);
                 
                 // Keep buffer at max 3 seconds
                 let max_samples = s.sample_rate as usize * 3;
@@ -162,21 +340,22 @@ pub fn start_wake_word_listening(
                 if rms_value > s.rms_threshold {
                     // Have enough audio and voice detected, try wake word detection
                     let audio_vec: Vec<f32> = s.audio_buffer.iter().copied().collect();
-                    let confidence = match_wake_word(&audio_vec, s.sample_rate);
+                    let confidence = match_wake_word(&audio_vec, s.sample_rate, &s.config.phonetic_form());
                     
                     if confidence > 0.5 {
                         println!("[wake-word] Voice activity detected - confidence: {:.2}, rms: {:.4}, buffer_size: {}", 
                             confidence, rms_value, audio_vec.len());
                     }
                     
-                    if confidence > 0.7 {
-                        println!("[wake-word] ✓ HEYKEN DETECTED! Confidence: {:.2}, RMS: {:.4}", confidence, rms_value);
+                    if confidence > s.config.trigger_threshold() {
+                        println!("[wake-word] ✓ \"{}\" DETECTED! Confidence: {:.2}, RMS: {:.4}", s.config.phrase, confidence, rms_value);
                         s.triggered = true;
                         s.trigger_time = Some(std::time::Instant::now());
                         
                         // Emit Tauri event
                         println!("[wake-word] Emitting wake-word-detected event to frontend");
                         let _ = app_handle.emit("wake-word-detected", serde_json::json!({
+                            "phrase": s.config.phrase,
                             "confidence": confidence,
                             "timestamp": std::time::SystemTime::now()
                                 .duration_since(std::time::UNIX_EPOCH)
@@ -184,6 +363,8 @@ pub fn start_wake_word_listening(
                                 .as_secs(),
                         }));
                         
+                        crate::sounds::play_notification(crate::sounds::NotificationKind::WakeWord);
+
                         // Clear buffer to avoid re-triggering
                         s.audio_buffer.clear();
                         println!("[wake-word] Buffer cleared, waiting for reset");
@@ -201,6 +382,40 @@ pub fn start_wake_word_listening(
     Ok(stream)
 }
 
+/// Record `secs` seconds from the configured microphone as mono f32 samples.
+/// Blocking — run on a blocking thread.
+pub fn record_mono_blocking(secs: f32) -> Result<(Vec<f32>, u32), String> {
+    let host = cpal::default_host();
+    let (device, _) = crate::audio_capture::select_input_device(&host, &crate::settings::load_settings().mic_device_id)?;
+    let config = device
+        .default_input_config()
+        .map_err(|e| format!("Cannot get input config: {e}"))?;
+    let sample_rate = config.sample_rate().0;
+    let channels = config.channels() as usize;
+
+    let samples: Arc<Mutex<Vec<f32>>> = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&samples);
+    let stream = device
+        .build_input_stream(
+            &config.into(),
+            move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                let mut out = sink.lock().unwrap();
+                for chunk in data.chunks(channels) {
+                    out.push(chunk.iter().sum::<f32>() / channels as f32);
+                }
+            },
+            |err| eprintln!("[wake-word] Test recording error: {err}"),
+            None,
+        )
+        .map_err(|e| format!("Cannot build test stream: {e}"))?;
+    stream.play().map_err(|e| format!("Cannot start test recording: {e}"))?;
+    std::thread::sleep(std::time::Duration::from_secs_f32(secs));
+    drop(stream);
+
+    let samples = std::mem::take(&mut *samples.lock().unwrap());
+    Ok((samples, sample_rate))
+}
+
 /// Stop wake word listening
 pub fn stop_wake_word_listening(state: &SharedWakeWordState) {
     let mut s = state.lock().unwrap();
@@ -220,13 +435,82 @@ pub fn check_wake_word_triggered(state: &SharedWakeWordState) -> bool {
     }
 }
 
-/// Get current RMS level for UI visualization
+/// Get current RMS level for UI visualization (last window of audio, as in
+/// `broxeen:audio_level` — prefer listening to that event)
 #[tauri::command]
 pub fn wake_word_get_level(state: tauri::State<SharedWakeWordState>) -> f32 {
     let s = state.lock().unwrap();
-    if s.audio_buffer.is_empty() {
-        return 0.0;
+    let window = crate::audio_level::window_len(s.sample_rate);
+    let skip = s.audio_buffer.len().saturating_sub(window);
+    crate::audio_level::measure(s.audio_buffer.iter().skip(skip).copied()).rms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phonetic_form() {
+        use Phone::*;
+        assert_eq!(phonetic_form("heyken"), vec![Fricative, Vowel, Stop, Vowel, Nasal]);
+        assert_eq!(phonetic_form("komputer"), vec![Stop, Vowel, Nasal, Stop, Vowel, Stop, Vowel, Liquid]);
+        assert_eq!(phonetic_form("szczur"), vec![Fricative, Vowel, Liquid]);
+        assert_eq!(phonetic_form("hej broxeen")[..4], [Fricative, Vowel, Liquid, Stop]);
+    }
+
+    #[test]
+    fn test_sensitivity_thresholds() {
+        let default = WakeWordConfig::default();
+        assert!((default.rms_threshold() - 0.015).abs() < 1e-6);
+        assert!((default.trigger_threshold() - 0.7).abs() < 1e-6);
+
+        let sensitive = WakeWordConfig::default().with_overrides(None, Some(2.0));
+        assert_eq!(sensitive.sensitivity, 1.0);
+        assert!(sensitive.trigger_threshold() < default.trigger_threshold());
+        assert!(sensitive.rms_threshold() < default.rms_threshold());
+    }
+
+    /// Sine "voiced" segments and noise "fricatives" shaped like `parts`.
+    fn synth(sr: u32, parts: &[(&str, f32)]) -> Vec<f32> {
+        let mut seed = 12345u32;
+        let mut audio = Vec::new();
+        for &(kind, secs) in parts {
+            let n = (sr as f32 * secs) as usize;
+            audio.extend((0..n).map(|i| {
+                let t = i as f32 / sr as f32;
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                let noise = (seed >> 16) as f32 / 32768.0 - 1.0;
+                match kind {
+                    "vowel" => 0.3 * (2.0 * std::f32::consts::PI * 180.0 * t).sin(),
+                    "nasal" => 0.15 * (2.0 * std::f32::consts::PI * 150.0 * t).sin(),
+                    "fricative" => 0.08 * noise,
+                    _ => 0.0,
+                }
+            }));
+        }
+        audio
+    }
+
+    #[test]
+    fn test_phrase_shaped_audio_matches_and_steady_tone_does_not() {
+        let sr = 16_000u32;
+        let config = WakeWordConfig::default();
+        // h-e-(k)-e-n, then trailing silence
+        let heyken = synth(sr, &[
+            ("silence", 0.3), ("fricative", 0.1), ("vowel", 0.15), ("silence", 0.05),
+            ("vowel", 0.15), ("nasal", 0.08), ("silence", 0.3),
+        ]);
+        let (score, rms) = score_recording(&heyken, sr, &config);
+        assert!(score > config.trigger_threshold(), "score={score}");
+        assert!(rms > 0.0);
+
+        let tone = synth(sr, &[("silence", 0.3), ("vowel", 0.8), ("silence", 0.3)]);
+        let (score, _) = score_recording(&tone, sr, &config);
+        assert!(score < config.trigger_threshold(), "tone score={score}");
+
+        // Same audio against a phrase of a different shape
+        let other = WakeWordConfig::default().with_overrides(Some("komputer".into()), None);
+        let (score, _) = score_recording(&heyken, sr, &other);
+        assert!(score < other.trigger_threshold(), "mismatch score={score}");
     }
-    let rms = s.audio_buffer.iter().map(|s| s * s).sum::<f32>() / s.audio_buffer.len() as f32;
-    rms.sqrt()
 }
@@ -27,6 +27,7 @@ import {
 } from "./domain/audioSettings";
 import { logger, logAsyncDecorator, logSyncDecorator } from "./lib/logger";
 import { isTauriRuntime } from "./lib/runtime";
+import { invokeCommand } from "./lib/backendError";
 import { bootstrapApp, type AppContext } from "./core/bootstrap";
 import { PluginProvider } from "./contexts/pluginContext";
 import { ChatPersistenceBridge } from "./components/ChatPersistenceBridge";
@@ -102,7 +103,7 @@ export default function App() {
       async () => {
         const context = await bootstrapApp({
           isTauri: runtimeIsTauri,
-          tauriInvoke: runtimeIsTauri ? invoke : undefined,
+          tauriInvoke: runtimeIsTauri ? invokeCommand : undefined,
         });
         setAppCtx(context);
         startupLogger.info("Plugin system initialized successfully");
@@ -259,26 +260,36 @@ export default function App() {
       return;
     }
 
-    // In Tauri, use backend for mic level instead of Web Audio API
+    // In Tauri, the backend pushes mic levels (~10/s) while the wake word
+    // listener or a recording is running
     if (isTauriRuntime()) {
       let cancelled = false;
-      const interval = setInterval(async () => {
-        if (cancelled) return;
-        
-        try {
-          const level = await invoke<number>('stt_get_mic_level');
-          setMicLevel(level);
-          setMicLevelActive(level > 0.02);
-        } catch (error) {
-          // Backend not recording, set level to 0
-          setMicLevel(0);
-          setMicLevelActive(false);
-        }
-      }, 100); // Update every 100ms for smooth animation
+      let unlisten: (() => void) | null = null;
+      let idleTimer: ReturnType<typeof setTimeout> | null = null;
+
+      import("@tauri-apps/api/event")
+        .then(({ listen }) =>
+          listen<{ level: number; voice_active: boolean }>("broxeen:audio_level", (event) => {
+            setMicLevel(event.payload.level);
+            setMicLevelActive(event.payload.voice_active);
+            // No events once the microphone stops: drop the meter to zero
+            if (idleTimer) clearTimeout(idleTimer);
+            idleTimer = setTimeout(() => {
+              setMicLevel(0);
+              setMicLevelActive(false);
+            }, 500);
+          }),
+        )
+        .then((fn) => {
+          if (cancelled) fn();
+          else unlisten = fn;
+        })
+        .catch(() => undefined);
 
       return () => {
         cancelled = true;
-        clearInterval(interval);
+        unlisten?.();
+        if (idleTimer) clearTimeout(idleTimer);
         cleanup();
       };
     }
@@ -1,5 +1,5 @@
 import { useEffect, useMemo, useRef, useState } from "react";
-import { invoke } from "@tauri-apps/api/core";
+import { invokeCommand as invoke } from "../lib/backendError";
 
 const FRAME_CACHE = new Map<string, { base64: string; mimeType: string; ts: number }>();
 
@@ -232,6 +232,7 @@ export default function MicSettingsModal({
               >
                 <option value="openrouter">OpenRouter Whisper (chmura)</option>
                 <option value="webspeech">Web Speech API (przeglądarka)</option>
+                <option value="whisper-local">Whisper lokalnie (offline)</option>
               </select>
             </label>
 
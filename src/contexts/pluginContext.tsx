@@ -17,8 +17,8 @@ import type {
 } from "../core/types";
 import type { DataSourcePlugin, PluginQuery, PluginResult as NewPluginResult } from "../core/plugin.types";
 import type { IntentDetection } from "../core/types";
-import { invoke } from "@tauri-apps/api/core";
 import { isTauriRuntime } from "../lib/runtime";
+import { invokeCommand } from "../lib/backendError";
 import { configStore } from "../config/configStore";
 
 // ─── Context Value ──────────────────────────────────────────
@@ -95,7 +95,7 @@ export function PluginProvider({ context, children }: PluginProviderProps) {
         const runtimeIsTauri = isTauriRuntime();
         const pluginContext = {
           isTauri: runtimeIsTauri,
-          tauriInvoke: runtimeIsTauri ? invoke : undefined,
+          tauriInvoke: runtimeIsTauri ? invokeCommand : undefined,
           scope, // Pass scope to plugin context
           databaseManager: context.databaseManager,
         };
@@ -243,8 +243,12 @@ export function useStt(options: UseSttOptions = {}): UseSttReturn {
       "speech:stt:ui",
       "startRecordingTauriBackend",
       async () => {
-        await invoke("stt_start", { mode });
+        const started = await invoke<string | null>("stt_start", { mode });
         stopInFlightRef.current = false;
+        // The configured microphone was missing and the default one is used
+        if (typeof started === "string" && started.includes("warning:")) {
+          sttLogger.warn(started);
+        }
         sttLogger.info("Native Tauri STT recording started");
       },
     );
@@ -0,0 +1,34 @@
+import { describe, expect, it, vi } from "vitest";
+import { invoke } from "@tauri-apps/api/core";
+import { BackendError, invokeCommand, isBackendError, toBackendError } from "./backendError";
+
+describe("backendError", () => {
+  it("turns a coded backend rejection into a BackendError", () => {
+    const err = toBackendError({
+      code: "not_found",
+      message: "Plik nie istnieje: /tmp/x",
+      details: { path: "/tmp/x" },
+    });
+    expect(err).toBeInstanceOf(BackendError);
+    expect(isBackendError(err, "not_found")).toBe(true);
+    expect(isBackendError(err, "timeout")).toBe(false);
+    expect(String(err)).toBe("Plik nie istnieje: /tmp/x");
+    expect((err as BackendError).details).toEqual({ path: "/tmp/x" });
+  });
+
+  it("leaves other rejections unchanged", () => {
+    expect(toBackendError("boom")).toBe("boom");
+    const plain = new Error("x");
+    expect(toBackendError(plain)).toBe(plain);
+    expect(toBackendError({ error: "x" })).toEqual({ error: "x" });
+  });
+
+  it("invokeCommand rethrows coded failures", async () => {
+    vi.mocked(invoke).mockRejectedValueOnce({ code: "busy", message: "browse: busy, try again" });
+    await expect(invokeCommand("browse", { url: "https://example.com" })).rejects.toMatchObject({
+      name: "BackendError",
+      code: "busy",
+      message: "browse: busy, try again",
+    });
+  });
+});
@@ -0,0 +1,59 @@
+import { invoke, type InvokeArgs } from "@tauri-apps/api/core";
+
+/** `ErrorCode` of the backend (src-tauri/src/error.rs). */
+export type BackendErrorCode =
+  | "not_found"
+  | "permission_denied"
+  | "timeout"
+  | "unreachable"
+  | "not_configured"
+  | "external_tool_missing"
+  | "invalid_input"
+  | "busy"
+  | "unsupported"
+  | "internal";
+
+/**
+ * Failure of a Tauri command that returns `BroxeenError`. `message` is the
+ * backend's human-readable text, so `String(e)` and `e.message` read as before.
+ */
+export class BackendError extends Error {
+  readonly code: BackendErrorCode;
+  readonly details?: unknown;
+
+  constructor(code: BackendErrorCode, message: string, details?: unknown) {
+    super(message);
+    this.name = "BackendError";
+    this.code = code;
+    this.details = details;
+  }
+
+  toString(): string {
+    return this.message;
+  }
+}
+
+/** `{ code, message, details? }` as rejected by `invoke`; anything else is returned unchanged. */
+export function toBackendError(error: unknown): unknown {
+  if (error instanceof BackendError || typeof error !== "object" || error === null) {
+    return error;
+  }
+  const { code, message, details } = error as Record<string, unknown>;
+  if (typeof code !== "string" || typeof message !== "string") {
+    return error;
+  }
+  return new BackendError(code as BackendErrorCode, message, details);
+}
+
+export function isBackendError(error: unknown, code?: BackendErrorCode): error is BackendError {
+  return error instanceof BackendError && (code === undefined || error.code === code);
+}
+
+/** `invoke` that rejects with a `BackendError` for coded command failures. */
+export async function invokeCommand<T>(cmd: string, args?: InvokeArgs): Promise<T> {
+  try {
+    return await invoke<T>(cmd, args);
+  } catch (error) {
+    throw toBackendError(error);
+  }
+}
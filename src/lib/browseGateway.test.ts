@@ -1,6 +1,6 @@
 import { describe, it, expect, vi, beforeEach, afterEach } from "vitest";
 import { invoke } from "@tauri-apps/api/core";
-import { executeBrowseCommand } from "./browseGateway";
+import { executeBrowseCommand, fetchBrowseContentPage, nextContentOffset } from "./browseGateway";
 
 describe("browseGateway", () => {
   beforeEach(() => {
@@ -31,6 +31,31 @@ describe("browseGateway", () => {
     });
   });
 
+  it("pages through long content with browse_content_page", async () => {
+    const mockInvoke = vi.mocked(invoke);
+    mockInvoke.mockResolvedValueOnce({
+      url: "https://example.com/long",
+      title: "Long",
+      content: "Drugi fragment.",
+      content_offset: 20000,
+      content_total_chars: 20030,
+      original_content_length: 20030,
+      content_chunk_chars: 15,
+    });
+
+    const page = await fetchBrowseContentPage("https://example.com/long", 20000);
+
+    expect(mockInvoke).toHaveBeenCalledWith("browse_content_page", {
+      url: "https://example.com/long",
+      offset: 20000,
+      length: undefined,
+    });
+    expect(page.content).toBe("Drugi fragment.");
+    expect(nextContentOffset(page)).toBe(20015);
+    expect(nextContentOffset({ ...page, content_total_chars: 20015 })).toBeNull();
+    expect(nextContentOffset({ url: "u", title: "t", content: "Całość" })).toBeNull();
+  });
+
   it("normalizes raw HTML payload returned by tauri command", async () => {
     const mockInvoke = vi.mocked(invoke);
     mockInvoke.mockResolvedValueOnce({
@@ -1,6 +1,6 @@
-import { invoke } from "@tauri-apps/api/core";
 import { logger, logAsyncDecorator } from "./logger";
 import { isTauriRuntime } from "./runtime";
+import { invokeCommand } from "./backendError";
 import {
   isProbablyTransientHttpStatus,
   retry,
@@ -826,6 +826,15 @@ export interface BrowseResult {
   github_url?: string;
   youtube_url?: string;
   instagram_url?: string;
+  /** ISO 639-1 code of the page content before any translation */
+  detected_language?: string;
+  /** Characters of the page content before any translation (backend chars = code points) */
+  original_content_length?: number;
+  /** Characters of `content` before any translation; the next chunk starts after them */
+  content_chunk_chars?: number;
+  /** Characters of the whole page; `content` is the chunk from `content_offset` */
+  content_total_chars?: number;
+  content_offset?: number;
 }
 
 interface AllOriginsResponse {
@@ -1710,7 +1719,7 @@ export async function executeBrowseCommand(
       });
 
       if (runtimeIsTauri) {
-        const result = await invoke<BrowseResult>("browse", { url });
+        const result = await invokeCommand<BrowseResult>("browse", { url });
         const rawTitle = typeof result.title === "string" ? result.title : "";
         const rawContent =
           typeof result.content === "string" ? result.content : "";
@@ -1739,3 +1748,21 @@ export async function executeBrowseCommand(
 
   return runExecuteBrowseCommand();
 }
+
+/** Offset of the chunk after `result`, or null when it was the last one. */
+export function nextContentOffset(result: BrowseResult): number | null {
+  const total = result.content_total_chars ?? 0;
+  const length = result.content_chunk_chars || Array.from(result.content).length;
+  const next = (result.content_offset ?? 0) + length;
+  return next < total ? next : null;
+}
+
+/** Further chunk of a page already opened with `browse` (Tauri only). */
+export async function fetchBrowseContentPage(
+  url: string,
+  offset: number,
+  length?: number,
+): Promise<BrowseResult> {
+  const result = await invokeCommand<BrowseResult>("browse_content_page", { url, offset, length });
+  return normalizeBrowseResult(result, "tauri", url);
+}
@@ -32,6 +32,13 @@ export interface LlmResponse {
   text: string;
   model: string;
   usage?: { prompt_tokens: number; completion_tokens: number };
+  /** Effective parameters echoed by the Tauri backend */
+  params?: {
+    model: string;
+    temperature: number;
+    max_tokens: number;
+    system_prompt: string | null;
+  } | null;
 }
 
 // ── Config ──────────────────────────────────────────
@@ -171,6 +178,55 @@ async function chatViaTauri(
   return runChatViaTauri();
 }
 
+/**
+ * Stream a chat completion: `onToken` receives each delta as it arrives
+ * (`broxeen:llm_token`). Cancel with `cancelChat(requestId)`; the promise
+ * then resolves with the partial text. Outside Tauri the whole answer is
+ * delivered as one token.
+ */
+export async function chatStream(
+  messages: LlmMessage[],
+  onToken: (delta: string) => void,
+  requestId: string,
+  configOverride?: Partial<LlmConfig>,
+): Promise<LlmResponse> {
+  if (!isTauriRuntime()) {
+    const result = await chat(messages, configOverride);
+    onToken(result.text);
+    return result;
+  }
+
+  const cfg = { ...getConfig(), ...configOverride };
+  const { invoke } = await import("@tauri-apps/api/core");
+  const { listen } = await import("@tauri-apps/api/event");
+  const unlisten = await listen<{ request_id: string; delta: string }>(
+    "broxeen:llm_token",
+    (event) => {
+      if (event.payload.request_id === requestId) onToken(event.payload.delta);
+    },
+  );
+  try {
+    llmClientLogger.debug("Invoking Tauri command llm_chat_stream", { requestId });
+    return await invoke<LlmResponse>("llm_chat_stream", {
+      messages: JSON.stringify(messages),
+      apiKey: cfg.apiKey,
+      model: cfg.model,
+      maxTokens: cfg.maxTokens,
+      temperature: cfg.temperature,
+      requestId,
+    });
+  } finally {
+    unlisten();
+  }
+}
+
+/** Abort a running `chatStream`; false when nothing was streaming. */
+export async function cancelChat(requestId: string): Promise<boolean> {
+  if (!isTauriRuntime()) return false;
+  const { invoke } = await import("@tauri-apps/api/core");
+  return invoke<boolean>("llm_chat_cancel", { requestId });
+}
+
 // ── Convenience wrappers ────────────────────────────
 
 const CONTENT_TRIM = 6000;
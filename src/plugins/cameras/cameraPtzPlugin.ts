@@ -12,8 +12,20 @@ export interface PtzCommand {
   direction: PtzDirection;
   speed?: number;
   presetId?: number;
+  /** Camera IP from the input — controlled directly over ONVIF */
+  ip?: string;
 }
 
+/** ContinuousMove velocity (-1..1) per direction at 100% speed */
+const ONVIF_VELOCITY: Partial<Record<PtzDirection, { pan?: number; tilt?: number; zoom?: number }>> = {
+  'left': { pan: -1 },
+  'right': { pan: 1 },
+  'up': { tilt: 1 },
+  'down': { tilt: -1 },
+  'zoom-in': { zoom: 1 },
+  'zoom-out': { zoom: -1 },
+};
+
 export class CameraPtzPlugin implements Plugin {
   readonly id = 'camera-ptz';
   readonly name = 'Camera PTZ Control';
@@ -57,12 +69,16 @@ export class CameraPtzPlugin implements Plugin {
 
     if (context.isTauri && context.tauriInvoke) {
       try {
-        await context.tauriInvoke('camera_ptz_move', {
-          cameraId: cmd.cameraId,
-          direction: cmd.direction,
-          speed: cmd.speed ?? 50,
-          presetId: cmd.presetId,
-        });
+        if (cmd.ip) {
+          await this.invokeOnvif(cmd, cmd.ip, context.tauriInvoke);
+        } else {
+          await context.tauriInvoke('camera_ptz_move', {
+            cameraId: cmd.cameraId,
+            direction: cmd.direction,
+            speed: cmd.speed ?? 50,
+            presetId: cmd.presetId,
+          });
+        }
 
         const dirLabel = this.directionLabel(cmd.direction);
         return {
@@ -120,15 +136,39 @@ export class CameraPtzPlugin implements Plugin {
     if (!direction) return null;
 
     const cameraId = this.extractCameraId(lower);
+    const ip = lower.match(/\b(\d{1,3}(?:\.\d{1,3}){3})\b/)?.[1];
 
     return {
-      cameraId: cameraId || 'cam-default',
+      cameraId: ip || cameraId || 'cam-default',
       direction,
       speed: 50,
       presetId,
+      ip,
     };
   }
 
+  /** ONVIF PTZ: ContinuousMove for directions, GotoPreset for presets / home */
+  private async invokeOnvif(
+    cmd: PtzCommand,
+    ip: string,
+    invoke: NonNullable<PluginContext['tauriInvoke']>,
+  ): Promise<void> {
+    if (cmd.direction === 'preset' || cmd.direction === 'home') {
+      const preset = cmd.direction === 'home' ? 'home' : String(cmd.presetId ?? 1);
+      await invoke('onvif_ptz_goto_preset', { ip, preset });
+      return;
+    }
+    const scale = (cmd.speed ?? 50) / 100;
+    const velocity = ONVIF_VELOCITY[cmd.direction] ?? {};
+    await invoke('onvif_ptz_move', {
+      ip,
+      pan: (velocity.pan ?? 0) * scale,
+      tilt: (velocity.tilt ?? 0) * scale,
+      zoom: (velocity.zoom ?? 0) * scale,
+      durationMs: 500,
+    });
+  }
+
   private extractCameraId(input: string): string | null {
     if (/wejści|front|wejsc/i.test(input)) return 'cam-front';
     if (/ogr[oó]d|garden/i.test(input)) return 'cam-garden';
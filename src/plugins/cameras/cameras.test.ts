@@ -128,6 +128,16 @@ describe('CameraPtzPlugin', () => {
     expect(result.status).toBe('success');
   });
 
+  it('uses ONVIF PTZ when the camera IP is given', async () => {
+    const ctx = { ...tauriCtx, tauriInvoke: vi.fn().mockResolvedValue(undefined) };
+    await plugin.execute('obróć kamerę 192.168.1.64 w lewo', ctx);
+    expect(ctx.tauriInvoke).toHaveBeenCalledWith('onvif_ptz_move', expect.objectContaining({
+      ip: '192.168.1.64', pan: -0.5, tilt: 0,
+    }));
+    await plugin.execute('preset 2 kamera 192.168.1.64', ctx);
+    expect(ctx.tauriInvoke).toHaveBeenCalledWith('onvif_ptz_goto_preset', { ip: '192.168.1.64', preset: '2' });
+  });
+
   it('handles Tauri invoke failure', async () => {
     const ctx = { ...tauriCtx, tauriInvoke: vi.fn().mockRejectedValue(new Error('PTZ failed')) };
     const result = await plugin.execute('kamera w prawo', ctx);
@@ -357,6 +357,48 @@ describe('NetworkScanPlugin execute', () => {
     }));
   });
 
+  it('shows the fingerprinted device type with its confidence', async () => {
+    const plugin = new NetworkScanPlugin() as any;
+    const tauriInvoke = vi.fn(async (cmd: string) => {
+      if (cmd === 'scan_network') {
+        return {
+          devices: [{
+            ip: '192.168.188.30',
+            mac: null,
+            hostname: 'drukarka',
+            vendor: null,
+            open_ports: [80, 631, 9100],
+            response_time: 3,
+            last_seen: new Date().toISOString(),
+            device_type: 'printer',
+            device_type_confidence: 0.95,
+          }],
+          scan_duration: 100,
+          scan_method: 'tcp-connect-parallel',
+          subnet: '192.168.188',
+        } as any;
+      }
+      return [];
+    });
+
+    plugin.determineScanStrategy = vi.fn(async () => ({
+      type: 'full',
+      subnet: '192.168.188',
+      triggeredBy: 'manual',
+    }));
+    plugin.persistDevices = vi.fn(async () => undefined);
+    plugin.trackScanResults = vi.fn(async () => ({
+      devicesFound: 1,
+      devicesUpdated: 0,
+      newDevices: 1,
+      scanDuration: 1,
+      efficiency: '1 devices/s',
+    }));
+
+    const result = await plugin.execute('skanuj sieć 192.168.188', { isTauri: true, tauriInvoke } as any);
+    expect(result.content[0].data).toContain('**192.168.188.30** *(printer, 95%)*');
+  });
+
   it('adds per-camera action buttons (configPrompt) for camera scan results', async () => {
     const plugin = new NetworkScanPlugin();
 
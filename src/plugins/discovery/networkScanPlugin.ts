@@ -452,14 +452,21 @@ export class NetworkScanPlugin implements Plugin {
 
           console.log(`[NetworkScanPlugin] Starting ${effectiveScanType} scan via Tauri...`);
           
-          const result = await context.tauriInvoke('scan_network', {
-            args: {
-              subnet: userSpecifiedSubnet || scanStrategy.subnet,
-              timeout: 5000,
-              incremental: effectiveScanType === 'incremental',
-              target_ranges: effectiveScanType === 'incremental' ? (scanStrategy.targetRanges || []) : [],
-            },
-          }) as NetworkScanResult;
+          const stopProgress = await this.watchScanProgress(scanId, scanLabel);
+          let result: NetworkScanResult;
+          try {
+            result = await context.tauriInvoke('scan_network', {
+              args: {
+                subnet: userSpecifiedSubnet || scanStrategy.subnet,
+                timeout: 5000,
+                incremental: effectiveScanType === 'incremental',
+                target_ranges: effectiveScanType === 'incremental' ? (scanStrategy.targetRanges || []) : [],
+                scan_id: scanId,
+              },
+            }) as NetworkScanResult;
+          } finally {
+            stopProgress();
+          }
 
           // Cache the result
           this.scanCache.set(cacheKey, result);
@@ -1132,7 +1139,7 @@ export class NetworkScanPlugin implements Plugin {
     }
 
     devices.forEach((device, index) => {
-      content += `${index + 1}. **${device.ip}**\n`;
+      content += `${index + 1}. **${device.ip}**${device.alias ? ` — ${device.alias}` : ''}\n`;
       if (device.hostname) content += `   Hostname: ${device.hostname}\n`;
       if (device.mac) content += `   MAC: \`${device.mac}\`\n`;
       if (device.vendor) content += `   Producent: ${device.vendor}\n`;
@@ -1203,8 +1210,15 @@ export class NetworkScanPlugin implements Plugin {
 
       devicesToShow.forEach((device, index) => {
         content += `${index + 1}. **${device.ip}**`;
-        if (device.device_type) content += ` *(${device.device_type})*`;
+        if (device.alias) content += ` — ${device.alias}`;
+        if (device.device_type) {
+          const confidence = device.device_type_confidence;
+          content += confidence != null && confidence < 1
+            ? ` *(${device.device_type}, ${Math.round(confidence * 100)}%)*`
+            : ` *(${device.device_type})*`;
+        }
         content += '\n';
+        if (device.notes) content += `   Notatka: ${device.notes}\n`;
         if (device.hostname) content += `   Hostname: ${device.hostname}\n`;
         if (device.mac) content += `   MAC: \`${device.mac}\`\n`;
         if (device.vendor) content += `   Producent: ${device.vendor}\n`;
@@ -1445,6 +1459,27 @@ export class NetworkScanPlugin implements Plugin {
     }
   }
 
+  /** Show this scan's `broxeen:scan_progress` events in the process registry; returns the unlisten. */
+  private async watchScanProgress(scanId: string, label: string): Promise<() => void> {
+    try {
+      const { listen } = await import('@tauri-apps/api/event');
+      return await listen<ScanProgressEvent>('broxeen:scan_progress', (ev) => {
+        const p = ev.payload;
+        if (p.scan_id !== scanId) return;
+        processRegistry.upsertRunning({
+          id: scanId,
+          type: 'scan',
+          label,
+          pluginId: this.id,
+          details: `${p.hosts_scanned}/${p.total} hostów, ${p.devices_found} urządzeń, ~${Math.ceil(p.eta_ms / 1000)} s`,
+        });
+      });
+    } catch (err) {
+      console.warn('[NetworkScanPlugin] Scan progress unavailable:', err);
+      return () => {};
+    }
+  }
+
   /** Track scan results and save to history */
   private async trackScanResults(
     scanId: string,
@@ -1576,6 +1611,10 @@ interface NetworkDevice {
   response_time: number;
   last_seen: string;
   device_type?: string;
+  /** 0–1 certainty of `device_type` (backend device fingerprint catalog) */
+  device_type_confidence?: number;
+  alias?: string;
+  notes?: string;
 }
 
 interface NetworkScanResult {
@@ -1583,4 +1622,15 @@ interface NetworkScanResult {
   scan_duration: number;
   scan_method: string;
   subnet: string;
+  scan_id?: string;
+}
+
+/** Payload of `broxeen:scan_progress`, emitted after every batch of hosts. */
+interface ScanProgressEvent {
+  scan_id: string;
+  subnet: string;
+  hosts_scanned: number;
+  total: number;
+  devices_found: number;
+  eta_ms: number;
 }
@@ -39,6 +39,27 @@ export interface DockerInfo {
   server_version: string;
 }
 
+export interface DockerStackService {
+  name: string;
+  containers: string[];
+  running: number;
+  depends_on: string[];
+}
+
+export interface DockerStack {
+  project: string;
+  services: DockerStackService[];
+  state: 'running' | 'partial' | 'stopped';
+  running: number;
+  total: number;
+}
+
+export interface DockerStackReport {
+  project: string;
+  outcomes: { service: string; container: string; action: string; ok: boolean; message: string }[];
+  failed: number;
+}
+
 export class DockerPlugin implements Plugin {
   readonly id = 'docker';
   readonly name = 'Docker';
@@ -62,7 +83,8 @@ export class DockerPlugin implements Plugin {
       'docker', 'kontener', 'kontenery', 'kontenera', 'kontenerów',
       'obraz', 'obrazy', 'dockerowy', 'dockerem',
       'uruchom kontener', 'zatrzymaj kontener', 'restart kontener',
-      'docker logs', 'docker ps', 'docker images'
+      'docker logs', 'docker ps', 'docker images',
+      'stack', 'stos', 'compose'
     ];
     return dockerKeywords.some(keyword => 
       input.toLowerCase().includes(keyword.toLowerCase())
@@ -91,6 +113,11 @@ export class DockerPlugin implements Plugin {
         };
       }
 
+      // Compose stacks: "restart the frigate stack", "zatrzymaj stos frigate"
+      if (/\b(stack|stos|stosy|compose)\b/.test(intentLower)) {
+        return await this.handleStacks(input, intentLower);
+      }
+
       // Handle different Docker commands
       if (intentLower.includes('info') || intentLower.includes('status')) {
         const info = await this.context.tauriInvoke?.('docker_info') as DockerInfo;
@@ -469,6 +496,49 @@ export class DockerPlugin implements Plugin {
     }
   }
 
+  private async handleStacks(input: string, intentLower: string): Promise<PluginResult> {
+    const stacks = await this.context.tauriInvoke?.('docker_list_stacks') as DockerStack[];
+    const project = stacks.find(s => new RegExp(`\\b${s.project.replace(/[.*+?^${}()|[\]\\]/g, '\\$&')}\\b`, 'i').test(input))?.project;
+    const action = intentLower.includes('restart') ? 'docker_stack_restart'
+      : (intentLower.includes('zatrzymaj') || intentLower.includes('stop')) ? 'docker_stack_stop'
+      : null;
+
+    let data: string;
+    let status: PluginResult['status'] = 'success';
+    if (action && !project) {
+      status = 'error';
+      data = `Nie rozpoznano stosu. Dostępne: ${stacks.map(s => s.project).join(', ') || 'brak'}`;
+    } else if (action && project) {
+      const report = await this.context.tauriInvoke?.(action, { project }) as DockerStackReport;
+      const failures = report.outcomes.filter(o => !o.ok);
+      data = `Stos ${project}: ${report.outcomes.length - failures.length}/${report.outcomes.length} operacji OK`;
+      if (failures.length > 0) {
+        status = 'partial';
+        data += '\n' + failures.map(f => `❌ ${f.service} (${f.container}, ${f.action}): ${f.message}`).join('\n');
+      }
+    } else {
+      const shown = project ? stacks.filter(s => s.project === project) : stacks;
+      data = shown.length === 0
+        ? 'Brak stosów docker compose.'
+        : shown.map(s => {
+            const icon = s.state === 'running' ? '🟢' : s.state === 'partial' ? '🟡' : '🔴';
+            const services = s.services.map(svc => `  • ${svc.name} (${svc.running}/${svc.containers.length})`).join('\n');
+            return `${icon} ${s.project} — ${s.running}/${s.total} uruchomionych\n${services}`;
+          }).join('\n\n');
+    }
+
+    return {
+      pluginId: this.id,
+      status,
+      content: [{ type: 'text', data }],
+      metadata: {
+        duration_ms: 0,
+        cached: false,
+        truncated: false
+      }
+    };
+  }
+
   private extractContainerId(intent: string): string | null {
     // Try to extract container name or ID from the intent
     const patterns = [
@@ -4,12 +4,13 @@
  * Supports SMTP sending with attachments, IMAP inbox polling with summaries,
  * and configurable 10-minute auto-polling with chat-based parameter changes.
  *
- * Intents: "email:send", "email:inbox", "email:config", "email:poll"
+ * Intents: "email:send", "email:inbox", "email:config", "email:poll", "email:attachment"
  * Scope: local
  */
 
 import type { Plugin, PluginContext, PluginResult } from '../../core/types';
 import { configStore } from '../../config/configStore';
+import { invokeCommand } from '../../lib/backendError';
 
 export interface EmailConfig {
   smtp_host: string;
@@ -41,11 +42,25 @@ export interface InboxSummary {
   poll_time: string;
 }
 
+export interface EmailAttachment {
+  index: number;
+  filename: string;
+  content_type: string;
+  size: number;
+}
+
+export interface SavedAttachment {
+  path: string;
+  filename: string;
+  content_type: string;
+  size: number;
+}
+
 export class EmailPlugin implements Plugin {
   readonly id = 'email';
   readonly name = 'Email';
   readonly version = '1.0.0';
-  readonly supportedIntents = ['email:send', 'email:inbox', 'email:config', 'email:poll'];
+  readonly supportedIntents = ['email:send', 'email:inbox', 'email:config', 'email:poll', 'email:attachment'];
 
   private pollIntervalId: ReturnType<typeof setInterval> | null = null;
   private pollIntervalMs: number = 10 * 60 * 1000; // 10 minutes default
@@ -62,6 +77,9 @@ export class EmailPlugin implements Plugin {
       /wyślij/i, /wyslij/i, /send/i, /prześlij/i, /przeslij/i,
       /mail.*plik/i, /plik.*mail/i,
     ]],
+    ['attachment', [
+      /załącznik/i, /zalacznik/i, /attachment/i,
+    ]],
     ['inbox', [
       /skrzynk/i, /inbox/i, /poczta/i, /poczt/i, /wiadomoś/i, /wiadomos/i,
       /sprawdź\s*email/i, /sprawdz\s*email/i, /odczytaj\s*email/i,
@@ -94,6 +112,7 @@ export class EmailPlugin implements Plugin {
         case 'config': return this.handleConfig(input, start);
         case 'send':   return await this.handleSend(input, context, start);
         case 'inbox':  return await this.handleInbox(context, start);
+        case 'attachment': return await this.handleAttachment(input, context, start);
         default:       return await this.handleInbox(context, start);
       }
     } catch (err) {
@@ -251,6 +270,76 @@ export class EmailPlugin implements Plugin {
     };
   }
 
+  /**
+   * "załączniki z ostatniego maila" lists them; "pobierz załącznik (nr 2)
+   * z ostatniego maila od księgowej" saves one to Downloads.
+   */
+  private async handleAttachment(
+    input: string,
+    context: PluginContext,
+    start: number,
+  ): Promise<PluginResult> {
+    if (!context.isTauri || !context.tauriInvoke) {
+      return this.browserFallback(start);
+    }
+    const emailConfig = this.getEmailConfig();
+    const summary = (await context.tauriInvoke('email_poll_inbox', {
+      maxMessages: 10,
+      config: emailConfig,
+    })) as InboxSummary;
+
+    // "od księgowej" → sender containing "księgow" (drop the Polish case ending)
+    const sender = input.toLowerCase().match(/\bod\s+([^\s,.]+)/)?.[1];
+    const senderStem = sender && sender.length > 5 ? sender.slice(0, -2) : sender;
+    const message = summary.recent_messages.find(
+      (m) => !senderStem || m.from.toLowerCase().includes(senderStem),
+    );
+    if (!message) {
+      return this.errorResult(
+        senderStem ? `Brak wiadomości od "${sender}" wśród ostatnich 10.` : 'Skrzynka pusta — brak wiadomości.',
+        start,
+      );
+    }
+
+    const label = `**${message.subject}** (od: ${message.from})`;
+    if (/pobierz|zapisz|ściągnij|sciagnij|download|save/i.test(input)) {
+      const nr = input.match(/(?:nr|numer|#)\s*(\d+)/i);
+      const saved = (await context.tauriInvoke('email_download_attachment', {
+        messageId: message.id,
+        attachmentIndex: nr ? Math.max(parseInt(nr[1], 10) - 1, 0) : 0,
+        config: emailConfig,
+      })) as SavedAttachment;
+      return {
+        pluginId: this.id,
+        status: 'success',
+        content: [{
+          type: 'text',
+          data: `📎 Zapisano **${saved.filename}** (${formatSize(saved.size)}) z wiadomości ${label}\n\n📁 ${saved.path}`,
+        }],
+        metadata: { duration_ms: Date.now() - start, cached: false, truncated: false },
+      };
+    }
+
+    const attachments = (await context.tauriInvoke('email_get_attachments', {
+      messageId: message.id,
+      config: emailConfig,
+    })) as EmailAttachment[];
+    const text = attachments.length === 0
+      ? `Wiadomość ${label} nie ma załączników.`
+      : [
+          `📎 Załączniki wiadomości ${label}:`,
+          ...attachments.map((a) => `${a.index + 1}. ${a.filename || '(bez nazwy)'} — ${a.content_type}, ${formatSize(a.size)}`),
+          '',
+          '💡 "pobierz załącznik nr 1 z ostatniego maila" — zapisz w Pobranych',
+        ].join('\n');
+    return {
+      pluginId: this.id,
+      status: 'success',
+      content: [{ type: 'text', data: text }],
+      metadata: { duration_ms: Date.now() - start, cached: false, truncated: false },
+    };
+  }
+
   private handlePollConfig(input: string, start: number): PluginResult {
     const minuteMatch = input.match(/(\d+)\s*(minut|min)/i);
     const secondMatch = input.match(/(\d+)\s*(sekund|sec)/i);
@@ -310,11 +399,10 @@ export class EmailPlugin implements Plugin {
 
     this.pollIntervalId = setInterval(async () => {
       try {
-        const { invoke } = await import('@tauri-apps/api/core');
         const config = this.getEmailConfig();
         if (!config.imap_host || !config.smtp_user) return;
 
-        const summary = (await invoke('email_poll_inbox', {
+        const summary = (await invokeCommand('email_poll_inbox', {
           maxMessages: 5,
           config,
         })) as InboxSummary;
@@ -417,3 +505,9 @@ export class EmailPlugin implements Plugin {
     console.log('EmailPlugin disposed');
   }
 }
+
+function formatSize(bytes: number): string {
+  if (bytes >= 1024 * 1024) return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+  if (bytes >= 1024) return `${Math.round(bytes / 1024)} KB`;
+  return `${bytes} B`;
+}
@@ -21,6 +21,14 @@ export interface FileSearchResult {
   is_dir: boolean;
   preview: string | null;
   mime_type: string;
+  /** Base64 JPEG, only with `includeThumbnails` */
+  thumbnail_base64?: string;
+  image_meta?: {
+    width: number;
+    height: number;
+    taken_at?: string;
+    gps?: { latitude: number; longitude: number };
+  };
 }
 
 export interface FileSearchResponse {
@@ -39,6 +47,8 @@ export interface FileContentResponse {
   size_bytes: number;
   mime_type: string;
   truncated: boolean;
+  /** Byte offset of the returned window */
+  offset?: number;
 }
 
 export class FileSearchPlugin implements Plugin {
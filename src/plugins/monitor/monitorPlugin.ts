@@ -16,6 +16,7 @@
 import type { Plugin, PluginContext, PluginResult } from '../../core/types';
 import { processRegistry } from '../../core/processRegistry';
 import { configStore } from '../../config/configStore';
+import { invokeCommand } from '../../lib/backendError';
 import type { ConfigPromptData, ConfigAction } from '../../components/ChatConfigPrompt';
 import { DeviceRepository } from '../../persistence/deviceRepository';
 import { ConfiguredDeviceRepository } from '../../persistence/configuredDeviceRepository';
@@ -1900,8 +1901,7 @@ export class MonitorPlugin implements Plugin {
     // In Tauri, use the backend resize_image command
     if (typeof window === 'undefined' || (window as Window & { __TAURI__?: unknown }).__TAURI__) {
       try {
-        const { invoke } = await import('@tauri-apps/api/core');
-        const resizedBase64 = await invoke('resize_image', {
+        const resizedBase64 = await invokeCommand('resize_image', {
           base64,
           maxWidth,
         }) as string;
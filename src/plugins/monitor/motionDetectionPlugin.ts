@@ -24,6 +24,11 @@ interface PipelineStatus {
   rtsp_url: string;
   started_at: number;
   running: boolean;
+  /** Native pipeline: stream stalled and watchdog reconnects did not help */
+  degraded?: boolean;
+  /** Python pipeline: automatic restarts after crashes */
+  restart_count?: number;
+  last_exit_reason?: string | null;
 }
 
 interface DetectionStats {
@@ -281,7 +286,14 @@ export class MotionDetectionPlugin implements Plugin {
         const uptimeStr = uptime > 3600
           ? `${Math.floor(uptime / 3600)}h ${Math.floor((uptime % 3600) / 60)}m`
           : uptime > 60 ? `${Math.floor(uptime / 60)}m ${uptime % 60}s` : `${uptime}s`;
-        return `**${p.camera_id}** 🟢  RTSP: \`${p.rtsp_url}\`  Uptime: ${uptimeStr}`;
+        if (!p.running) {
+          const reason = p.last_exit_reason ? ` — ${p.last_exit_reason}` : "";
+          const restart = p.restart_count ? ` 🔄 restart ${p.restart_count}` : "";
+          return `**${p.camera_id}** 🔴${restart}${reason}`;
+        }
+        const state = p.degraded ? "🟠 brak klatek" : "🟢";
+        const restarts = p.restart_count ? `  Restarty: ${p.restart_count}` : "";
+        return `**${p.camera_id}** ${state}  RTSP: \`${p.rtsp_url}\`  Uptime: ${uptimeStr}${restarts}`;
       });
 
       const configPrompt: ConfigPromptData = {
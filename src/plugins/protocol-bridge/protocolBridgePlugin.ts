@@ -28,6 +28,7 @@
 
 import type { Plugin, PluginContext, PluginResult } from '../../core/types';
 import { logger } from '../../lib/logger';
+import { invokeCommand } from '../../lib/backendError';
 
 const browseLogger = logger.scope('bridge:rss');
 
@@ -1550,12 +1551,11 @@ export class ProtocolBridgePlugin implements Plugin {
       
       if (context.isTauri) {
         // Use Tauri RSS parser for better XML handling
-        const { invoke } = await import('@tauri-apps/api/core');
-        const rawContent = await invoke('browse', { url });
+        const rawContent = await invokeCommand('browse', { url });
         
         // Try to parse as RSS feed first
         try {
-          const formattedContent = await invoke('parse_rss_feed_command', { 
+          const formattedContent = await invokeCommand('parse_rss_feed_command', { 
             url, 
             content: (rawContent as any).content, 
             maxItems: 10 
@@ -1638,12 +1638,11 @@ export class ProtocolBridgePlugin implements Plugin {
       
       if (context.isTauri) {
         // Use Tauri RSS parser for better XML handling
-        const { invoke } = await import('@tauri-apps/api/core');
-        const rawContent = await invoke<{ content: string }>('browse', { url });
+        const rawContent = await invokeCommand<{ content: string }>('browse', { url });
         
         // Try to parse as Atom feed first
         try {
-          const formattedContent = await invoke<string>('parse_rss_feed_command', { 
+          const formattedContent = await invokeCommand<string>('parse_rss_feed_command', { 
             url, 
             content: rawContent.content, 
             maxItems: 10 
@@ -88,6 +88,32 @@ describe('DiskInfoPlugin', () => {
       expect((result.metadata as any).configPrompt).toBeDefined();
     });
 
+    it('marks removable drives with their label', async () => {
+      const mockInvoke = vi.fn().mockResolvedValue({
+        hostname: 'test-host',
+        partitions: [
+          {
+            device: '/dev/sdb1',
+            mount_point: '/media/jan/KINGSTON',
+            fs_type: 'vfat',
+            total_bytes: 16_000_000_000,
+            used_bytes: 1_000_000_000,
+            available_bytes: 15_000_000_000,
+            use_percent: 6.0,
+            is_removable: true,
+            label: 'KINGSTON',
+          },
+        ],
+        total_bytes: 16_000_000_000,
+        used_bytes: 1_000_000_000,
+        available_bytes: 15_000_000_000,
+        use_percent: 6.0,
+      });
+
+      const result = await plugin.execute('pokaż dysk', { isTauri: true, tauriInvoke: mockInvoke });
+      expect(result.content[0].data).toContain('🔌 **/media/jan/KINGSTON** (KINGSTON)');
+    });
+
     it('calls get_disk_usage for specific path', async () => {
       const mockInvoke = vi.fn().mockResolvedValue({
         device: '/dev/sda1',
@@ -213,7 +213,7 @@ export class DiskInfoPlugin implements Plugin {
       for (const p of info.partitions) {
         const progressBar = this.buildProgressBar(p.use_percent);
         lines.push(
-          `| **${p.mount_point}** | ${this.formatBytes(p.total_bytes)} | ${progressBar} | ${this.formatBytes(p.available_bytes)} |`,
+          `| ${this.formatLocation(p)} | ${this.formatBytes(p.total_bytes)} | ${progressBar} | ${this.formatBytes(p.available_bytes)} |`,
         );
       }
     }
@@ -232,6 +232,11 @@ export class DiskInfoPlugin implements Plugin {
     return lines.join('\n');
   }
 
+  private formatLocation(p: DiskPartition): string {
+    const label = p.label ? ` (${p.label})` : '';
+    return p.is_removable ? `🔌 **${p.mount_point}**${label}` : `**${p.mount_point}**${label}`;
+  }
+
   private formatPartition(p: DiskPartition): string {
     const lines: string[] = [];
     lines.push(`💾 **${p.device}** zamontowany w **${p.mount_point}**\n`);
@@ -298,6 +303,8 @@ interface DiskPartition {
   used_bytes: number;
   available_bytes: number;
   use_percent: number;
+  is_removable?: boolean;
+  label?: string | null;
 }
 
 interface DiskInfo {
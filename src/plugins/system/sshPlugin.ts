@@ -371,6 +371,9 @@ export class SshPlugin implements Plugin {
       }
       lines.push('```');
     }
+    if (result.truncated) {
+      lines.push(`⚠️ Wyjście przekroczyło limit — polecenie przerwane (odebrano stdout: ${result.stdout_bytes ?? '?'} B, stderr: ${result.stderr_bytes ?? '?'} B)`);
+    }
 
     if (result.stderr.trim() && result.exit_code !== 0) {
       lines.push('\n**Błędy:**');
@@ -526,6 +529,12 @@ interface SshExecResult {
   stderr: string;
   exit_code: number;
   duration_ms: number;
+  /** Output beyond the backend cap (`ssh_output_max_kb`) was dropped */
+  truncated?: boolean;
+  stdout_bytes?: number;
+  stderr_bytes?: number;
+  stdout_base64?: string;
+  stderr_base64?: string;
 }
 
 interface SshTestResult {
@@ -536,9 +545,17 @@ interface SshTestResult {
   ssh_version: string | null;
   duration_ms: number;
   error: string | null;
+  error_kind: SshTestError | null;
 }
 
+type SshTestError =
+  | { kind: 'Unreachable' }
+  | { kind: 'AuthFailed'; message: string }
+  | { kind: 'HostKeyMismatch'; expected_fingerprint: string | null; actual_fingerprint: string | null }
+  | { kind: 'Other'; message: string };
+
 interface KnownHost {
   host: string;
   key_type: string;
+  fingerprint: string | null;
 }